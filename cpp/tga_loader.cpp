@@ -3,6 +3,8 @@
 
 #include <cstdint>
 #include <cstddef>
+#include <cstdio>
+#include <vector>
 
 extern "C" {
 
@@ -25,6 +27,42 @@ int tga_load_rgba(const char* path, TgaImage* out) {
   return 0;
 }
 
+// 将 RGBA 像素保存为未压缩的 32 位 TGA（左上角为原点）
+// 0 成功；-1 参数无效；-3 无法创建文件；-4 写入失败
+int tga_save_rgba(const char* path, int32_t width, int32_t height, const uint8_t* data, size_t len) {
+  if (!path || !data || width <= 0 || height <= 0 || width > 0xFFFF || height > 0xFFFF) return -1;
+  if (len != (size_t)width * (size_t)height * 4) return -1;
+
+  uint8_t header[18] = {0};
+  header[2] = 2;                                  // 未压缩真彩色
+  header[12] = (uint8_t)(width & 0xFF);
+  header[13] = (uint8_t)((width >> 8) & 0xFF);
+  header[14] = (uint8_t)(height & 0xFF);
+  header[15] = (uint8_t)((height >> 8) & 0xFF);
+  header[16] = 32;                                // 每像素位数
+  header[17] = 0x28;                              // 8 位 Alpha + 左上角原点
+
+  // TGA 按 BGRA 顺序存储
+  std::vector<uint8_t> pixels(len);
+  for (size_t i = 0; i < len; i += 4) {
+    pixels[i] = data[i + 2];
+    pixels[i + 1] = data[i + 1];
+    pixels[i + 2] = data[i];
+    pixels[i + 3] = data[i + 3];
+  }
+
+  FILE* file = std::fopen(path, "wb");
+  if (!file) return -3;
+  bool ok = std::fwrite(header, 1, sizeof(header), file) == sizeof(header)
+         && std::fwrite(pixels.data(), 1, len, file) == len;
+  ok = std::fclose(file) == 0 && ok;
+  if (!ok) {
+    std::remove(path);
+    return -4;
+  }
+  return 0;
+}
+
 void tga_free(TgaImage* img) {
   if (img && img->data) {
     stbi_image_free(img->data);
@@ -0,0 +1,45 @@
+//! 文件名和路径处理的模糊测试
+//!
+//! 在 `src-tauri` 目录下运行：`cargo +nightly fuzz run file_names`
+
+#![no_main]
+
+#[allow(dead_code)]
+#[path = "../../src/file_manager/paths.rs"]
+mod paths;
+
+use libfuzzer_sys::fuzz_target;
+use paths::{
+    extended_length_path, is_reserved_name, sanitize_file_name, storage_extension, with_copy_suffix,
+    MAX_EXTENSION_LEN, MAX_FILE_NAME_LEN,
+};
+use std::path::Path;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(name) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    // 清理后的名称是单级、安全且稳定的文件名
+    let sanitized = sanitize_file_name(name);
+    assert!(!sanitized.is_empty() && sanitized.len() <= MAX_FILE_NAME_LEN);
+    assert!(!sanitized.ends_with(['.', ' ']));
+    assert!(!is_reserved_name(&sanitized));
+    assert_eq!(sanitize_file_name(&sanitized), sanitized);
+    let base = Path::new("library");
+    assert_eq!(base.join(&sanitized).parent(), Some(base));
+
+    // 重命名后的名称与原名不同，且仍是合法的文件名
+    let renamed = with_copy_suffix(&sanitized, data.len() + 1);
+    assert_ne!(renamed, sanitized);
+    assert!(renamed.len() <= MAX_FILE_NAME_LEN);
+    assert_eq!(sanitize_file_name(&renamed), renamed);
+
+    if let Some(extension) = storage_extension(name) {
+        assert!(extension.len() <= MAX_EXTENSION_LEN);
+        assert!(extension.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+    }
+
+    let extended = extended_length_path(name);
+    assert_eq!(extended_length_path(&extended), extended);
+});
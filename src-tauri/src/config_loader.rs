@@ -7,11 +7,71 @@ use std::path::{Path, PathBuf};
 use std::fs;
 use tracing::Level;
 use crate::advanced_logging::{AdvancedLogConfig, RotationStrategy};
+use crate::file_manager::network_share::{NetworkShareMode, DEFAULT_CHECK_INTERVAL_SECS};
 
 /// 完整的应用配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     pub logging: LoggingConfig,
+    #[serde(default)]
+    pub library: LibraryConfig,
+    #[serde(default)]
+    pub thumbnails: ThumbnailsConfig,
+    #[serde(default)]
+    pub indexing: IndexingConfig,
+    #[serde(default)]
+    pub storage_io: StorageIoConfig,
+}
+
+/// 后台任务的存储读写限额，0 表示不限制
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageIoConfig {
+    /// 每秒最多读写的 MB 数
+    #[serde(default)]
+    pub max_mb_per_sec: u64,
+    /// 同时进行的写入数
+    #[serde(default)]
+    pub max_concurrent_writes: usize,
+}
+
+/// 索引配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexingConfig {
+    /// 并行计算哈希和读取 EXIF 的线程数上限，0 表示使用全部核心
+    #[serde(default)]
+    pub max_threads: usize,
+}
+
+/// 缩略图配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThumbnailsConfig {
+    /// 是否在 GPU 上缩小大图，需以 gpu-thumbnails 特性编译
+    #[serde(default)]
+    pub gpu_acceleration: bool,
+}
+
+/// 资料库配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryConfig {
+    /// 网络共享模式: "auto", "enabled", "disabled"
+    #[serde(default)]
+    pub network_share: NetworkShareMode,
+    /// 网络共享模式下检查资料库能否访问的间隔（秒）
+    #[serde(default = "default_offline_check_interval")]
+    pub offline_check_interval_seconds: u64,
+}
+
+fn default_offline_check_interval() -> u64 {
+    DEFAULT_CHECK_INTERVAL_SECS
+}
+
+impl Default for LibraryConfig {
+    fn default() -> Self {
+        Self {
+            network_share: NetworkShareMode::default(),
+            offline_check_interval_seconds: DEFAULT_CHECK_INTERVAL_SECS,
+        }
+    }
 }
 
 /// 日志配置
@@ -151,6 +211,10 @@ impl ConfigLoader {
                     colored_output: true,
                 },
             },
+            library: LibraryConfig::default(),
+            thumbnails: ThumbnailsConfig::default(),
+            indexing: IndexingConfig::default(),
+            storage_io: StorageIoConfig::default(),
         }
     }
     
@@ -246,6 +310,11 @@ impl ConfigValidator {
             errors.push("系统监控间隔必须大于0".to_string());
         }
         
+        // 验证离线检查间隔
+        if config.library.offline_check_interval_seconds == 0 {
+            errors.push("离线检查间隔必须大于0".to_string());
+        }
+        
         // 验证系统阈值
         let thresholds = &config.logging.system_monitoring.thresholds;
         if thresholds.cpu_warning >= thresholds.cpu_critical {
@@ -0,0 +1,229 @@
+//! 最近动态模块
+//!
+//! “最近发生了什么”面板使用的动态流，把分散记录的各类动态按时间合并为一个列表：
+//! - 可撤销的批量操作和重复文件清理，来自撤销条目，撤销条目被清理后不再出现
+//! - 监视文件夹导入的文件，来自监视文件夹的导入记录
+//! - 后台任务（目录删除、导出、存储布局迁移、延迟删除）的结束和外部编辑的保存，
+//!   来自进程内的事件日志，只包含本次启动以来且仍在日志中的记录
+//! - 按时间从新到旧排列，游标分页，翻页期间有新动态时不会重复或遗漏
+
+use crate::file_manager::deferred_delete::DeferredDeleteReport;
+use crate::file_manager::directory_delete::{DirectoryDeleteProgress, DirectoryDeleteStatus};
+use crate::file_manager::events::{AppEvent, DeferredDeleteFinished, EventRecord, ExternalEditChanged};
+use crate::file_manager::export::ExportProgress;
+use crate::file_manager::external_edit::ExternalEditSession;
+use crate::file_manager::pagination::{Page, PageCursor};
+use crate::file_manager::storage_layout::LayoutMigrationProgress;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// 动态类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    /// 可撤销的操作
+    Operation,
+    /// 后台任务结束
+    Job,
+    /// 同步导入
+    Sync,
+    /// 外部编辑
+    Edit,
+}
+
+/// 动态筛选条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ActivityFilter {
+    /// 只返回这些类别，为空时返回全部
+    #[serde(default)]
+    pub kinds: Vec<ActivityKind>,
+    /// 只返回此时间及之后的动态
+    #[serde(default)]
+    pub since: Option<DateTime<Local>>,
+}
+
+impl ActivityFilter {
+    pub fn includes(&self, kind: ActivityKind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&kind)
+    }
+
+    fn matches(&self, entry: &ActivityEntry) -> bool {
+        self.includes(entry.detail.kind()) && self.since.is_none_or(|since| entry.occurred_at >= since)
+    }
+}
+
+/// 动态内容
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ActivityDetail {
+    /// 可撤销的操作，`undo_id` 为对应的撤销条目
+    Operation { undo_id: String, operation_type: String },
+    /// 目录删除任务结束，包括取消和失败
+    DirectoryDelete(DirectoryDeleteProgress),
+    /// 导出完成
+    Export(ExportProgress),
+    /// 存储布局迁移完成
+    StorageMigration(LayoutMigrationProgress),
+    /// 延迟删除队列处理完一轮
+    DeferredDelete(DeferredDeleteReport),
+    /// 监视文件夹导入了文件
+    WatchedImport {
+        folder_id: String,
+        folder_path: String,
+        source_path: String,
+        file_id: String,
+        file_name: String,
+    },
+    /// 外部编辑的副本被保存
+    ExternalEdit(ExternalEditSession),
+}
+
+impl ActivityDetail {
+    pub fn kind(&self) -> ActivityKind {
+        match self {
+            Self::Operation { .. } => ActivityKind::Operation,
+            Self::DirectoryDelete(_) | Self::Export(_) | Self::StorageMigration(_) | Self::DeferredDelete(_) => ActivityKind::Job,
+            Self::WatchedImport { .. } => ActivityKind::Sync,
+            Self::ExternalEdit(_) => ActivityKind::Edit,
+        }
+    }
+}
+
+/// 动态流中的一项
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ActivityEntry {
+    /// 带来源前缀的 ID，在动态流中唯一
+    pub id: String,
+    pub occurred_at: DateTime<Local>,
+    pub detail: ActivityDetail,
+}
+
+impl ActivityEntry {
+    /// 分页游标，按 (时间, ID) 从新到旧排序
+    ///
+    /// 时间使用与数据库中相同的 RFC 3339 字符串，数据库和内存中的比较结果一致
+    pub fn cursor(&self) -> PageCursor {
+        PageCursor::new(self.occurred_at.to_rfc3339(), self.id.clone())
+    }
+}
+
+/// 事件日志中的记录转换为动态，不属于动态流的事件和未结束的任务返回 `None`
+pub fn from_event(record: &EventRecord) -> Option<ActivityEntry> {
+    let payload = record.payload.clone();
+    let detail = match record.name.as_str() {
+        name if name == DirectoryDeleteProgress::NAME => {
+            let progress: DirectoryDeleteProgress = serde_json::from_value(payload).ok()?;
+            if progress.status == DirectoryDeleteStatus::Running {
+                return None;
+            }
+            ActivityDetail::DirectoryDelete(progress)
+        }
+        name if name == ExportProgress::NAME => {
+            let progress: ExportProgress = serde_json::from_value(payload).ok()?;
+            if progress.completed < progress.total {
+                return None;
+            }
+            ActivityDetail::Export(progress)
+        }
+        name if name == LayoutMigrationProgress::NAME => {
+            let progress: LayoutMigrationProgress = serde_json::from_value(payload).ok()?;
+            if !progress.finished {
+                return None;
+            }
+            ActivityDetail::StorageMigration(progress)
+        }
+        name if name == DeferredDeleteFinished::NAME => ActivityDetail::DeferredDelete(serde_json::from_value(payload).ok()?),
+        name if name == ExternalEditChanged::NAME => ActivityDetail::ExternalEdit(serde_json::from_value(payload).ok()?),
+        _ => return None,
+    };
+
+    Some(ActivityEntry {
+        id: format!("event:{}", record.seq),
+        occurred_at: record.emitted_at,
+        detail,
+    })
+}
+
+/// 合并各来源的动态，按时间从新到旧取游标之后的一页
+pub fn feed_page(
+    entries: Vec<ActivityEntry>,
+    after: Option<&PageCursor>,
+    limit: usize,
+    filter: &ActivityFilter,
+) -> Page<ActivityEntry> {
+    let mut keyed: Vec<(PageCursor, ActivityEntry)> = entries
+        .into_iter()
+        .filter(|entry| filter.matches(entry))
+        .map(|entry| (entry.cursor(), entry))
+        .filter(|(cursor, _)| after.is_none_or(|after| cursor < after))
+        .collect();
+    keyed.sort_by(|(a, _), (b, _)| b.cmp(a));
+    keyed.truncate(limit + 1);
+
+    Page::from_overfetched(keyed.into_iter().map(|(_, entry)| entry).collect(), limit, ActivityEntry::cursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn operation(id: &str, occurred_at: DateTime<Local>) -> ActivityEntry {
+        ActivityEntry {
+            id: format!("operation:{}", id),
+            occurred_at,
+            detail: ActivityDetail::Operation { undo_id: id.to_string(), operation_type: "batch_operations".to_string() },
+        }
+    }
+
+    #[test]
+    fn test_only_finished_jobs_become_activity() {
+        let record = |name: &str, payload: serde_json::Value| EventRecord {
+            seq: 7,
+            name: name.to_string(),
+            payload,
+            emitted_at: Local::now(),
+        };
+        let progress = |status: DirectoryDeleteStatus| serde_json::to_value(DirectoryDeleteProgress {
+            job_id: "job-1".to_string(),
+            status,
+            ..Default::default()
+        }).unwrap();
+
+        assert!(from_event(&record(DirectoryDeleteProgress::NAME, progress(DirectoryDeleteStatus::Running))).is_none());
+        let entry = from_event(&record(DirectoryDeleteProgress::NAME, progress(DirectoryDeleteStatus::Cancelled))).unwrap();
+        assert_eq!(entry.id, "event:7");
+        assert_eq!(entry.detail.kind(), ActivityKind::Job);
+        assert!(from_event(&record("upload-completed", serde_json::json!({ "files": [] }))).is_none());
+    }
+
+    #[test]
+    fn test_feed_is_newest_first_and_stable() {
+        let now = Local::now();
+        let mut entries: Vec<ActivityEntry> = (0..5)
+            .map(|i| operation(&format!("u{}", i), now - Duration::minutes(i)))
+            .collect();
+
+        let filter = ActivityFilter::default();
+        let first = feed_page(entries.clone(), None, 2, &filter);
+        let ids: Vec<_> = first.items.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, ["operation:u0", "operation:u1"]);
+
+        // 翻页之间出现的新动态排在最前，不影响下一页
+        entries.push(operation("new", now + Duration::minutes(1)));
+        let after = PageCursor::decode(first.next_cursor.as_deref().unwrap()).unwrap();
+        let second = feed_page(entries.clone(), Some(&after), 2, &filter);
+        let ids: Vec<_> = second.items.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, ["operation:u2", "operation:u3"]);
+
+        let recent = ActivityFilter { kinds: vec![ActivityKind::Operation], since: Some(now - Duration::seconds(90)) };
+        assert_eq!(feed_page(entries.clone(), None, 10, &recent).items.len(), 3);
+        let edits = ActivityFilter { kinds: vec![ActivityKind::Edit], since: None };
+        assert!(feed_page(entries, None, 10, &edits).items.is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+//! 统计分析模块
+//!
+//! 为仪表盘提供统计数据：
+//! - 按天或按周汇总新增文件数和字节数
+//! - 没有活动的时间段同样返回，数值为 0，便于直接绘制图表
+//! - 按 MIME 类型、顶层目录和文件年龄分组的存储占用
+//! - 每天记录一次库的总量快照，绘制增长趋势时直接读取，删除的文件也能反映在历史中
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::error::{FileManagerError, Result};
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::BTreeMap;
+
+/// 检查当天快照是否已记录的间隔
+const SNAPSHOT_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// 单次查询允许的最大天数（约十年），避免一次返回过多数据点
+pub const MAX_TIMELINE_DAYS: i64 = 3660;
+
+/// 时间线的汇总粒度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineBucket {
+    #[default]
+    Day,
+    /// 按周汇总，每周从周一开始
+    Week,
+}
+
+impl TimelineBucket {
+    /// 获取日期所在时间段的起始日期
+    pub fn bucket_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => date,
+            Self::Week => date - Duration::days(i64::from(date.weekday().num_days_from_monday())),
+        }
+    }
+
+    /// 时间段的长度
+    fn length(&self) -> Duration {
+        match self {
+            Self::Day => Duration::days(1),
+            Self::Week => Duration::weeks(1),
+        }
+    }
+}
+
+/// 时间线查询范围（含首尾两天）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TimelineRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl TimelineRange {
+    /// 检查范围是否有效
+    pub fn validate(&self) -> Result<()> {
+        if self.start > self.end {
+            return Err(FileManagerError::general_error("Timeline range start must not be after end"));
+        }
+        if (self.end - self.start).num_days() > MAX_TIMELINE_DAYS {
+            return Err(FileManagerError::general_error(format!(
+                "Timeline range cannot exceed {} days", MAX_TIMELINE_DAYS
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 一个时间段内的活动统计
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ActivityPoint {
+    /// 时间段的起始日期
+    pub start: NaiveDate,
+    /// 新增的文件数
+    pub files_added: i64,
+    /// 新增的字节数
+    pub bytes_added: i64,
+}
+
+/// 一组文件的存储占用
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct UsageGroup {
+    /// 分组键：MIME 类型、顶层目录路径或年龄段
+    pub key: String,
+    pub file_count: i64,
+    pub total_size: i64,
+}
+
+/// 存储占用分布
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct StorageBreakdown {
+    pub by_mime_type: Vec<UsageGroup>,
+    /// 按顶层目录分组，根目录下的文件归入 `/`
+    pub by_top_level_directory: Vec<UsageGroup>,
+    /// 按上传时间距今的年龄分组：`last_7_days`、`last_30_days`、`last_year`、`older`
+    pub by_age: Vec<UsageGroup>,
+}
+
+/// 某一天的库统计快照
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct StatsSnapshot {
+    pub date: NaiveDate,
+    pub total_files: i64,
+    pub total_bytes: i64,
+    /// 按 MIME 类型分组的文件数和字节数
+    pub by_mime_type: Vec<UsageGroup>,
+}
+
+impl StatsSnapshot {
+    /// 由按 MIME 类型分组的占用生成快照
+    pub fn from_groups(date: NaiveDate, by_mime_type: Vec<UsageGroup>) -> Self {
+        Self {
+            date,
+            total_files: by_mime_type.iter().map(|group| group.file_count).sum(),
+            total_bytes: by_mime_type.iter().map(|group| group.total_size).sum(),
+            by_mime_type,
+        }
+    }
+}
+
+/// 启动后台快照任务
+///
+/// 每小时检查一次当天是否已记录快照，应用跨天运行或当天首次启动时补记
+pub fn spawn_snapshot_loop(state: FileManagerState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SNAPSHOT_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let Ok(service) = state.lock().await else {
+                continue;
+            };
+            // 只读模式下由写入的进程记录快照
+            if service.is_read_only() {
+                continue;
+            }
+            if let Err(e) = service.run_scheduled_stats_snapshot().await {
+                tracing::warn!("统计快照记录失败: {}", e);
+            }
+        }
+    });
+}
+
+/// 将按天统计的数据汇总为连续的时间线
+///
+/// `daily` 为 `(日期, 文件数, 字节数)`，范围外的日期会被忽略
+pub fn build_timeline(
+    daily: &[(NaiveDate, i64, i64)],
+    range: TimelineRange,
+    bucket: TimelineBucket,
+) -> Vec<ActivityPoint> {
+    let mut points: BTreeMap<NaiveDate, ActivityPoint> = BTreeMap::new();
+
+    let last = bucket.bucket_start(range.end);
+    let mut current = bucket.bucket_start(range.start);
+    while current <= last {
+        points.insert(current, ActivityPoint { start: current, files_added: 0, bytes_added: 0 });
+        current += bucket.length();
+    }
+
+    for (date, files, bytes) in daily {
+        if *date < range.start || *date > range.end {
+            continue;
+        }
+        if let Some(point) = points.get_mut(&bucket.bucket_start(*date)) {
+            point.files_added += files;
+            point.bytes_added += bytes;
+        }
+    }
+
+    points.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_daily_and_weekly_timeline() {
+        let daily = vec![
+            (date("2024-03-04"), 2, 200),
+            (date("2024-03-06"), 1, 50),
+            (date("2024-03-11"), 3, 300),
+            (date("2024-04-01"), 9, 900),
+        ];
+        let range = TimelineRange { start: date("2024-03-04"), end: date("2024-03-12") };
+
+        let days = build_timeline(&daily, range, TimelineBucket::Day);
+        assert_eq!(days.len(), 9);
+        assert_eq!(days[0].files_added, 2);
+        assert_eq!(days[1].files_added, 0);
+        assert_eq!(days[7].bytes_added, 300);
+
+        // 2024-03-04 和 2024-03-11 都是周一
+        let weeks = build_timeline(&daily, range, TimelineBucket::Week);
+        assert_eq!(weeks.len(), 2);
+        assert_eq!((weeks[0].start, weeks[0].files_added, weeks[0].bytes_added), (date("2024-03-04"), 3, 250));
+        assert_eq!((weeks[1].start, weeks[1].files_added), (date("2024-03-11"), 3));
+
+        assert!(TimelineRange { start: date("2024-03-05"), end: date("2024-03-04") }.validate().is_err());
+    }
+}
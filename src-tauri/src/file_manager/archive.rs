@@ -0,0 +1,108 @@
+//! 归档模块
+//!
+//! 把不常用文件的存储文件移到较慢的二级存储位置（如外接硬盘），释放主存储空间：
+//! - 归档位置在设置中配置，必须是存储根目录之外的绝对路径
+//! - 文件记录、标签和缩略图保留在资料库中，列表和搜索照常显示，可按是否归档筛选
+//! - 读取内容、获取 asset 路径或用外部应用打开时自动把存储文件移回主存储
+//! - 备份、导出、完整性检查等只读操作直接读取归档位置中的文件，不移回
+//! - 移动时先复制到目标位置再更新数据库，最后删除源文件，可跨磁盘且中断时不会丢失文件
+//! - 链接文件、打包的文件和存储文件已丢失的文件不能归档
+
+use crate::file_manager::database::{FileInfo, FileStatus};
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::paths::long_path;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::path::{Path, PathBuf};
+
+/// 保存归档设置的状态键
+pub const ARCHIVE_SETTINGS_KEY: &str = "archive";
+
+/// 归档设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ArchiveSettings {
+    /// 归档位置的绝对路径，未配置时不能归档
+    #[serde(default)]
+    pub location: Option<String>,
+}
+
+impl ArchiveSettings {
+    /// 检查归档位置：必须是绝对路径，且不能与存储根目录互相包含
+    pub fn validate(&self, storage_root: &Path) -> Result<()> {
+        let Some(location) = &self.location else {
+            return Ok(());
+        };
+        let location = Path::new(location);
+        if !location.is_absolute() {
+            return Err(FileManagerError::general_error("Archive location must be an absolute path"));
+        }
+        if location.starts_with(storage_root) || storage_root.starts_with(location) {
+            return Err(FileManagerError::general_error("Archive location must be outside the library storage"));
+        }
+        Ok(())
+    }
+}
+
+/// 归档结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ArchiveReport {
+    /// 本次移动的文件数
+    pub moved: usize,
+    /// 已处于目标状态、链接文件或存储文件丢失而跳过的文件 ID
+    pub skipped: Vec<String>,
+    /// 移动失败的文件 ID
+    pub failed: Vec<String>,
+}
+
+/// 文件是否可以归档
+pub fn is_archivable(file: &FileInfo) -> bool {
+    !file.linked && !file.packed && file.archive_path.is_none() && file.status == FileStatus::Ok
+}
+
+/// 文件在归档位置中的路径：按文件 ID 的前两位分目录，保留扩展名
+pub fn archive_blob_path(location: &Path, file: &FileInfo) -> PathBuf {
+    let shard: String = file.id.chars().take(2).collect();
+    let name = match Path::new(&file.name).extension() {
+        Some(extension) => format!("{}.{}", file.id, extension.to_string_lossy()),
+        None => file.id.clone(),
+    };
+    location.join(shard).join(name)
+}
+
+/// 把存储文件复制到目标位置
+///
+/// 先写入同目录下的临时文件再重命名，目标位置不会出现不完整的文件；源文件由调用方在更新数据库后删除
+pub async fn copy_blob(source: &Path, target: &Path) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(long_path(parent)).await?;
+    }
+    let mut temp_name = target.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".partial");
+    let temp_path = target.with_file_name(temp_name);
+
+    if let Err(e) = tokio::fs::copy(long_path(source), long_path(&temp_path)).await {
+        let _ = tokio::fs::remove_file(long_path(&temp_path)).await;
+        return Err(e.into());
+    }
+    tokio::fs::rename(long_path(&temp_path), long_path(target)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_location_validation() {
+        let storage_root = std::env::temp_dir().join("library").join("files");
+        let settings = |location: PathBuf| ArchiveSettings { location: Some(location.display().to_string()) };
+
+        assert!(ArchiveSettings::default().validate(&storage_root).is_ok());
+        assert!(settings(std::env::temp_dir().join("archive")).validate(&storage_root).is_ok());
+        assert!(settings(storage_root.join("archive")).validate(&storage_root).is_err());
+        assert!(settings(std::env::temp_dir().join("library")).validate(&storage_root).is_err());
+        assert!(settings(PathBuf::from("archive")).validate(&storage_root).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! 资料库备份模块
+//!
+//! 将存储文件和数据库快照备份到库外的目录：
+//! - 每次备份在目标目录下新建 `backup-<时间>` 子目录，包含清单 `manifest.json`、
+//!   数据库快照 `library.db` 和 `blobs/` 下按存储路径存放的文件
+//! - 完整备份复制所有存储文件；增量备份以目标目录中最近一次备份为基准，
+//!   只复制内容哈希或修改时间变化的文件，未变化的文件在清单中指向之前的备份
+//! - 清单记录每个文件的大小和 SHA-256，恢复时据此校验完整性
+//! - 快照与当前库使用相同的加密设置，恢复加密库需要原密码
+//! - 恢复前先按清单校验所有文件，发现缺失或损坏时只报告、不修改当前库
+
+use crate::file_manager::error::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::path::{Path, PathBuf};
+
+/// 清单文件名
+pub const BACKUP_MANIFEST_FILE: &str = "manifest.json";
+
+/// 数据库快照文件名
+pub const BACKUP_DATABASE_FILE: &str = "library.db";
+
+/// 存放存储文件的子目录
+pub const BACKUP_BLOBS_DIR: &str = "blobs";
+
+/// 备份目录名前缀
+const BACKUP_DIR_PREFIX: &str = "backup-";
+
+/// 当前清单格式版本
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// 备份范围
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupScope {
+    /// 复制所有存储文件
+    #[default]
+    Full,
+    /// 只复制上次备份后变化的文件
+    Incremental,
+}
+
+/// 清单中的文件记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BackupEntry {
+    pub file_id: String,
+    /// 文件在 `blobs/` 下的相对路径（`/` 分隔）
+    pub blob_path: String,
+    pub file_size: u64,
+    pub content_hash: String,
+    /// 存有该文件的备份；增量备份中未变化的文件指向之前的备份
+    pub backup_id: String,
+}
+
+/// 备份清单
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BackupManifest {
+    pub version: u32,
+    pub backup_id: String,
+    pub scope: BackupScope,
+    pub created_at: DateTime<Local>,
+    /// 增量备份所基于的备份
+    pub base_backup_id: Option<String>,
+    /// 数据库快照的 SHA-256
+    pub database_hash: String,
+    pub encrypted: bool,
+    pub entries: Vec<BackupEntry>,
+}
+
+impl BackupManifest {
+    /// 读取备份目录中的清单
+    pub fn read(backup_dir: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(backup_dir.join(BACKUP_MANIFEST_FILE))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// 写入清单到备份目录
+    pub fn write(&self, backup_dir: &Path) -> Result<()> {
+        std::fs::write(backup_dir.join(BACKUP_MANIFEST_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 获取文件在备份中的位置
+    ///
+    /// 指向之前备份的文件位于同一目标目录下的其他备份目录中
+    pub fn blob_location(backup_dir: &Path, entry: &BackupEntry) -> PathBuf {
+        let root = match backup_dir.file_name() {
+            Some(name) if name.to_string_lossy() == entry.backup_id => backup_dir.to_path_buf(),
+            _ => backup_dir.with_file_name(&entry.backup_id),
+        };
+        resolve_relative(&root.join(BACKUP_BLOBS_DIR), &entry.blob_path)
+    }
+}
+
+/// 备份失败的文件
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BackupFailure {
+    pub file_id: String,
+    pub error: String,
+}
+
+/// 备份结果
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BackupReport {
+    pub backup_dir: String,
+    /// 实际执行的范围；没有可用的基准备份时增量备份按完整备份执行
+    pub scope: BackupScope,
+    pub files_copied: usize,
+    /// 增量备份中沿用之前备份的文件数
+    pub files_reused: usize,
+    pub bytes_copied: u64,
+    pub failed: Vec<BackupFailure>,
+}
+
+/// 恢复方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreMode {
+    /// 用备份替换当前库的全部内容
+    #[default]
+    Full,
+    /// 只添加当前库中没有的目录、文件及其标签，已有记录保持不变
+    Merge,
+}
+
+/// 恢复结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RestoreReport {
+    pub mode: RestoreMode,
+    /// 校验通过的文件数
+    pub verified: usize,
+    /// 备份中找不到的文件 ID
+    pub missing: Vec<String>,
+    /// 内容与清单记录的哈希不一致的文件 ID
+    pub corrupt: Vec<String>,
+    /// 数据库快照的哈希是否与清单一致
+    pub database_ok: bool,
+    /// 是否已写入当前库；校验发现问题时为 `false`，当前库不做任何修改
+    pub applied: bool,
+    pub files_restored: usize,
+    /// 合并恢复时当前库中已存在而跳过的文件数
+    pub files_skipped: usize,
+}
+
+impl RestoreReport {
+    /// 校验是否全部通过
+    pub fn is_intact(&self) -> bool {
+        self.database_ok && self.missing.is_empty() && self.corrupt.is_empty()
+    }
+}
+
+/// 按备份时间生成备份 ID
+pub fn backup_id_for(time: DateTime<Local>) -> String {
+    format!("{}{}", BACKUP_DIR_PREFIX, time.format("%Y%m%d-%H%M%S"))
+}
+
+/// 查找目标目录中最近一次有清单的备份
+pub fn find_latest_backup(destination: &Path) -> Option<(PathBuf, BackupManifest)> {
+    let entries = std::fs::read_dir(destination).ok()?;
+    entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(BACKUP_DIR_PREFIX))
+        .filter_map(|entry| {
+            let path = entry.path();
+            let manifest = BackupManifest::read(&path).ok()?;
+            Some((path, manifest))
+        })
+        .max_by_key(|(_, manifest)| manifest.created_at)
+}
+
+/// 存储文件在备份中的相对路径
+///
+/// 相对存储路径原样保留；旧版本遗留的绝对路径只保留文件名
+pub fn blob_path_for(stored_path: &str, name: &str) -> String {
+    if Path::new(stored_path).is_absolute() {
+        return name.to_string();
+    }
+
+    stored_path
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 将 `/` 分隔的相对路径拼接到根目录下
+pub fn resolve_relative(root: &Path, relative: &str) -> PathBuf {
+    relative
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .fold(root.to_path_buf(), |path, part| path.join(part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_paths() {
+        assert_eq!(blob_path_for("2024/01/02/a.jpg", "a.jpg"), "2024/01/02/a.jpg");
+        assert_eq!(blob_path_for("../../etc/a.jpg", "a.jpg"), "etc/a.jpg");
+
+        let absolute = std::env::temp_dir().join("a.jpg");
+        assert_eq!(blob_path_for(&absolute.to_string_lossy(), "a.jpg"), "a.jpg");
+
+        let entry = BackupEntry {
+            file_id: "f1".to_string(),
+            blob_path: "2024/a.jpg".to_string(),
+            file_size: 1,
+            content_hash: "h".to_string(),
+            backup_id: "backup-1".to_string(),
+        };
+        let root = Path::new("dest");
+        assert_eq!(
+            BackupManifest::blob_location(&root.join("backup-2"), &entry),
+            root.join("backup-1").join(BACKUP_BLOBS_DIR).join("2024").join("a.jpg")
+        );
+        assert_eq!(
+            BackupManifest::blob_location(&root.join("backup-1"), &entry),
+            root.join("backup-1").join(BACKUP_BLOBS_DIR).join("2024").join("a.jpg")
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! 批量操作模块
+//!
+//! 在一个事务中执行拖放等复杂交互产生的一组混合操作：
+//! - 支持移动、重命名、修改标签和删除文件，按顺序执行，后面的操作能看到前面操作的结果
+//! - 任一操作失败时整批不生效
+//! - 每批记录一条撤销条目，可恢复涉及文件的位置、名称、标签和被删除的记录
+//! - 被删除文件的存储文件在撤销条目保留期间不删除，只保留最近的若干条，更早的条目丢弃时才删除
+
+use crate::file_manager::database::FileInfo;
+use crate::file_manager::dry_run::PlannedChange;
+use crate::file_manager::error::{FileManagerError, Result};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// 撤销条目中记录的操作类型
+pub const BATCH_OPERATIONS_OPERATION: &str = "batch_operations";
+
+/// 单批允许的最大操作数
+pub const MAX_BATCH_OPERATIONS: usize = 1000;
+
+/// 保留的批量操作撤销条目数
+pub const MAX_BATCH_UNDO_ENTRIES: usize = 20;
+
+/// 批量操作中的一项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchOperation {
+    /// 移动到指定目录，目标目录中有同名文件时自动添加序号
+    Move { file_id: String, directory_id: String },
+    /// 修改显示的文件名，目录中已有同名文件时整批失败
+    Rename { file_id: String, new_name: String },
+    /// 添加和移除标签
+    Tag {
+        file_id: String,
+        #[serde(default)]
+        add_tags: Vec<String>,
+        #[serde(default)]
+        remove_tags: Vec<String>,
+    },
+    /// 删除文件
+    Delete { file_id: String },
+}
+
+impl BatchOperation {
+    /// 操作涉及的文件 ID
+    pub fn file_id(&self) -> &str {
+        match self {
+            Self::Move { file_id, .. }
+            | Self::Rename { file_id, .. }
+            | Self::Tag { file_id, .. }
+            | Self::Delete { file_id } => file_id,
+        }
+    }
+}
+
+/// 检查批量操作列表：不能为空、不超过上限，且每项都指定了文件
+pub fn validate_operations(operations: &[BatchOperation]) -> Result<()> {
+    if operations.is_empty() {
+        return Err(FileManagerError::general_error("Batch contains no operations"));
+    }
+    if operations.len() > MAX_BATCH_OPERATIONS {
+        return Err(FileManagerError::general_error(format!(
+            "Batch cannot contain more than {} operations", MAX_BATCH_OPERATIONS
+        )));
+    }
+    if operations.iter().any(|operation| operation.file_id().trim().is_empty()) {
+        return Err(FileManagerError::general_error("Batch operation is missing a file ID"));
+    }
+    Ok(())
+}
+
+/// 执行前文件的记录和标签
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BatchFileSnapshot {
+    pub file: FileInfo,
+    pub tags: Vec<String>,
+}
+
+/// 批量操作的撤销信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BatchUndo {
+    /// 涉及的文件在执行前的状态，每个文件只记录一次
+    pub files: Vec<BatchFileSnapshot>,
+    /// 本批删除的文件 ID
+    pub deleted: Vec<String>,
+}
+
+/// 批量操作的执行结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BatchReport {
+    /// 撤销条目 ID，试运行时为空
+    pub undo_id: String,
+    pub moved: usize,
+    pub renamed: usize,
+    pub tagged: usize,
+    pub deleted: usize,
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 试运行时列出的各项修改，移动到原目录、标签没有变化的操作不列出
+    #[serde(default)]
+    pub changes: Vec<PlannedChange>,
+}
+
+impl BatchReport {
+    /// 按操作类型统计
+    pub fn count(undo_id: String, operations: &[BatchOperation]) -> Self {
+        let mut report = Self { undo_id, ..Self::default() };
+        for operation in operations {
+            match operation {
+                BatchOperation::Move { .. } => report.moved += 1,
+                BatchOperation::Rename { .. } => report.renamed += 1,
+                BatchOperation::Tag { .. } => report.tagged += 1,
+                BatchOperation::Delete { .. } => report.deleted += 1,
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation_serialization_and_validation() {
+        let operations: Vec<BatchOperation> = serde_json::from_str(
+            r#"[{"type":"move","file_id":"a","directory_id":"d1"},{"type":"tag","file_id":"a","add_tags":["x"]},{"type":"delete","file_id":"b"}]"#
+        ).unwrap();
+        assert_eq!(operations[1], BatchOperation::Tag {
+            file_id: "a".to_string(),
+            add_tags: vec!["x".to_string()],
+            remove_tags: vec![],
+        });
+        assert!(validate_operations(&operations).is_ok());
+
+        let report = BatchReport::count("u".to_string(), &operations);
+        assert_eq!((report.moved, report.tagged, report.deleted), (1, 1, 1));
+
+        assert!(validate_operations(&[]).is_err());
+        assert!(validate_operations(&[BatchOperation::Delete { file_id: " ".to_string() }]).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+//! 画板文件模块
+//!
+//! 导出的画板保存为单个 `.cboard` 文件，可在其他电脑上双击打开：
+//! - 画板内容由前端定义，后端原样保存；画板中的素材以内容哈希（SHA-256）引用
+//! - 画板的框架和分组保存在清单中，打开后由前端以新画板 ID 整体保存，
+//!   见 [`board_frames`](super::board_frames)
+//! - 文件依次为 8 字节标识、8 字节清单长度（小端）、JSON 清单、按清单顺序排列的素材内容
+//! - 打开时按内容哈希在资料库中查找素材，已有的直接使用，没有的解出后导入收件箱，
+//!   前端用返回的哈希到文件 ID 的对应关系还原画板
+//! - 解出的素材先校验哈希，损坏的素材只记为失败，不影响画板和其他素材
+//! - 安装程序把 `.cboard` 关联到应用；双击打开时文件路径作为启动参数传入，
+//!   macOS 上通过打开文件事件传入，都交给 [`shell_integration`](super::shell_integration) 的启动请求处理
+//! - 画板上的素材也可以收集到资料库中的一个目录，复制或移动后整个目录即可交给别人；
+//!   素材默认取自放置到画板上时记录的使用统计，前端也可直接传入画板当前的文件列表
+
+use crate::file_manager::board_frames::BoardFrame;
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::paths::long_path;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// 画板文件扩展名
+pub const BOARD_EXTENSION: &str = "cboard";
+
+/// 画板文件开头的标识
+const BOARD_MAGIC: &[u8; 8] = b"CBOARD\0\x01";
+
+/// 当前的清单版本
+pub const BOARD_FORMAT_VERSION: u32 = 1;
+
+/// 清单大小上限，避免损坏的文件导致分配过多内存
+const MAX_MANIFEST_SIZE: u64 = 16 * 1024 * 1024;
+
+/// 画板文件中的素材
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BoardAsset {
+    /// 内容的 SHA-256，画板内容以此引用素材
+    pub content_hash: String,
+    /// 导出时的原始文件名，导入时使用
+    pub name: String,
+    pub size: u64,
+}
+
+/// 画板文件的清单
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BoardManifest {
+    pub version: u32,
+    pub name: String,
+    /// 前端定义的画板内容
+    pub board: serde_json::Value,
+    /// 画板的框架和分组，早期导出的文件中没有
+    #[serde(default)]
+    pub frames: Vec<BoardFrame>,
+    pub assets: Vec<BoardAsset>,
+    pub exported_at: DateTime<Local>,
+}
+
+/// 导出画板的请求
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExportBoardRequest {
+    pub name: String,
+    pub board: serde_json::Value,
+    /// 画板 ID，指定时一并导出该画板的框架和分组
+    #[serde(default)]
+    pub board_id: Option<String>,
+    /// 画板上使用的文件，重复内容只保存一份
+    pub file_ids: Vec<String>,
+    /// 画板文件的绝对路径，没有扩展名时补上 `.cboard`
+    pub destination: String,
+}
+
+/// 导出画板的结果
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BoardExportReport {
+    pub path: String,
+    pub assets: Vec<BoardAsset>,
+    /// 画板文件大小（字节）
+    pub size: u64,
+}
+
+/// 素材在资料库中对应的文件
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ResolvedBoardAsset {
+    pub content_hash: String,
+    pub file_id: String,
+    /// 资料库中已有相同内容的文件，没有重新导入
+    pub reused: bool,
+}
+
+/// 打开画板文件的结果
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct OpenedBoard {
+    /// 画板文件路径
+    pub path: String,
+    pub name: String,
+    pub board: serde_json::Value,
+    /// 画板的框架和分组，ID 为导出时的 ID，前端以新画板 ID 调用 `replace_board_frames` 保存
+    pub frames: Vec<BoardFrame>,
+    pub assets: Vec<ResolvedBoardAsset>,
+    /// 无法导入的素材的内容哈希
+    pub failed_assets: Vec<String>,
+}
+
+/// 收集画板素材的方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectMode {
+    /// 在目标目录中创建副本，原文件保留在原目录
+    #[default]
+    Copy,
+    /// 移动到目标目录，可以撤销
+    Move,
+}
+
+/// 收集画板素材的请求
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CollectBoardAssetsRequest {
+    pub board_id: String,
+    pub target_directory_id: String,
+    /// 画板当前的文件；为 `None` 时使用放置到该画板上记录过的文件，已从画板移除的文件也包括在内
+    #[serde(default)]
+    pub file_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub mode: CollectMode,
+}
+
+/// 收集画板素材的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CollectBoardAssetsReport {
+    /// 收集后目标目录中的文件 ID，复制时为副本的 ID
+    pub collected: Vec<String>,
+    /// 已在目标目录中、无需收集的文件 ID
+    pub already_collected: Vec<String>,
+    /// 无法复制的文件 ID
+    pub failed: Vec<String>,
+    /// 移动时的撤销条目 ID，可传给 `undo_batch_operations`
+    pub undo_id: Option<String>,
+}
+
+/// 是否为画板文件，按扩展名判断
+pub fn is_board_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(BOARD_EXTENSION))
+}
+
+/// 导出目标路径：必须是绝对路径，没有扩展名时补上 `.cboard`
+pub fn board_destination(destination: &str) -> Result<PathBuf> {
+    let mut path = PathBuf::from(destination);
+    if !path.is_absolute() {
+        return Err(FileManagerError::general_error("Board destination must be an absolute path"));
+    }
+    if !is_board_file(&path) {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}", BOARD_EXTENSION));
+        path.set_file_name(name);
+    }
+    Ok(path)
+}
+
+/// 写入画板文件，`sources` 与清单中的素材一一对应
+///
+/// 先写入同目录的临时文件再重命名，中断时不会留下不完整的画板文件；
+/// 素材大小与清单不符时返回错误
+pub fn write_board(path: &Path, manifest: &BoardManifest, sources: &[PathBuf]) -> Result<u64> {
+    if sources.len() != manifest.assets.len() {
+        return Err(FileManagerError::general_error("Board asset list does not match its sources"));
+    }
+
+    let temp_path = path.with_extension(format!("{}.tmp", BOARD_EXTENSION));
+    let result = write_board_to(&temp_path, manifest, sources)
+        .and_then(|size| {
+            std::fs::rename(long_path(&temp_path), long_path(path))?;
+            Ok(size)
+        });
+    if result.is_err() {
+        let _ = std::fs::remove_file(long_path(&temp_path));
+    }
+    result
+}
+
+fn write_board_to(path: &Path, manifest: &BoardManifest, sources: &[PathBuf]) -> Result<u64> {
+    let mut writer = BufWriter::new(File::create(long_path(path))?);
+    let manifest_json = serde_json::to_vec(manifest)?;
+    writer.write_all(BOARD_MAGIC)?;
+    writer.write_all(&(manifest_json.len() as u64).to_le_bytes())?;
+    writer.write_all(&manifest_json)?;
+
+    let mut size = (BOARD_MAGIC.len() + 8 + manifest_json.len()) as u64;
+    for (asset, source) in manifest.assets.iter().zip(sources) {
+        let copied = std::io::copy(&mut File::open(long_path(source))?, &mut writer)?;
+        if copied != asset.size {
+            return Err(FileManagerError::general_error(format!(
+                "Board asset {} changed while exporting", asset.name
+            )));
+        }
+        size += copied;
+    }
+
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    Ok(size)
+}
+
+/// 打开的画板文件，清单之后是素材内容
+pub struct BoardReader {
+    reader: BufReader<File>,
+    manifest: BoardManifest,
+    /// 每个素材内容的起始位置
+    offsets: Vec<u64>,
+}
+
+impl BoardReader {
+    /// 打开画板文件并读取清单
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(long_path(path))?);
+        let invalid = || FileManagerError::general_error(format!("Not a valid board file: {}", path.display()));
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic).map_err(|_| invalid())?;
+        if &magic != BOARD_MAGIC {
+            return Err(invalid());
+        }
+        let mut length = [0u8; 8];
+        reader.read_exact(&mut length).map_err(|_| invalid())?;
+        let length = u64::from_le_bytes(length);
+        if length > MAX_MANIFEST_SIZE {
+            return Err(invalid());
+        }
+
+        let mut manifest_json = vec![0u8; length as usize];
+        reader.read_exact(&mut manifest_json).map_err(|_| invalid())?;
+        let manifest: BoardManifest = serde_json::from_slice(&manifest_json).map_err(|_| invalid())?;
+        if manifest.version > BOARD_FORMAT_VERSION {
+            return Err(FileManagerError::general_error(format!(
+                "Board file version {} is newer than supported version {}", manifest.version, BOARD_FORMAT_VERSION
+            )));
+        }
+
+        let mut offset = 16 + length;
+        let offsets = manifest.assets.iter()
+            .map(|asset| {
+                let start = offset;
+                offset += asset.size;
+                start
+            })
+            .collect();
+        let file_size = reader.get_ref().metadata()?.len();
+        if offset > file_size {
+            return Err(invalid());
+        }
+
+        Ok(Self { reader, manifest, offsets })
+    }
+
+    pub fn manifest(&self) -> &BoardManifest {
+        &self.manifest
+    }
+
+    /// 把第 `index` 个素材解出到 `target`，内容哈希与清单不符时删除并返回错误
+    pub fn extract_asset(&mut self, index: usize, target: &Path) -> Result<()> {
+        let asset = &self.manifest.assets[index];
+        self.reader.seek(SeekFrom::Start(self.offsets[index]))?;
+
+        let mut output = File::create(long_path(target))?;
+        let mut hasher = Sha256::new();
+        let mut remaining = asset.size;
+        let mut buffer = [0u8; 64 * 1024];
+        while remaining > 0 {
+            let chunk = remaining.min(buffer.len() as u64) as usize;
+            self.reader.read_exact(&mut buffer[..chunk])?;
+            hasher.update(&buffer[..chunk]);
+            output.write_all(&buffer[..chunk])?;
+            remaining -= chunk as u64;
+        }
+        drop(output);
+
+        if format!("{:x}", hasher.finalize()) != asset.content_hash {
+            let _ = std::fs::remove_file(long_path(target));
+            return Err(FileManagerError::general_error(format!("Board asset {} is corrupted", asset.name)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_board_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("logo.png");
+        std::fs::write(&source, b"logo bytes").unwrap();
+
+        let manifest = BoardManifest {
+            version: BOARD_FORMAT_VERSION,
+            name: "Moodboard".to_string(),
+            board: serde_json::json!({ "items": [{ "asset": "hash" }] }),
+            frames: Vec::new(),
+            assets: vec![BoardAsset {
+                content_hash: format!("{:x}", Sha256::digest(b"logo bytes")),
+                name: "logo.png".to_string(),
+                size: 10,
+            }],
+            exported_at: Local::now(),
+        };
+        let path = board_destination(&temp_dir.path().join("mood").to_string_lossy()).unwrap();
+        assert!(is_board_file(&path));
+        let size = write_board(&path, &manifest, std::slice::from_ref(&source)).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), size);
+
+        let mut reader = BoardReader::open(&path).unwrap();
+        assert_eq!(reader.manifest(), &manifest);
+        let extracted = temp_dir.path().join("extracted");
+        reader.extract_asset(0, &extracted).unwrap();
+        assert_eq!(std::fs::read(&extracted).unwrap(), b"logo bytes");
+
+        // 素材内容被改动后哈希不符
+        let mut data = std::fs::read(&path).unwrap();
+        *data.last_mut().unwrap() ^= 0xff;
+        std::fs::write(&path, &data).unwrap();
+        assert!(BoardReader::open(&path).unwrap().extract_asset(0, &extracted).is_err());
+        assert!(!extracted.exists());
+
+        assert!(BoardReader::open(&source).is_err());
+        assert!(board_destination("relative.cboard").is_err());
+    }
+
+    #[test]
+    fn test_manifest_without_frames() {
+        let manifest: BoardManifest = serde_json::from_value(serde_json::json!({
+            "version": 1,
+            "name": "Old",
+            "board": {},
+            "assets": [],
+            "exported_at": Local::now(),
+        })).unwrap();
+        assert!(manifest.frames.is_empty());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,291 @@
+//! 画板框架模块
+//!
+//! 框架和分组是画板上的命名区域，包含其中的元素并一起移动：
+//! - 画板元素由前端定义，后端只保存框架本身：所属画板、名称、区域和直接包含的元素 ID
+//! - 保存在独立的表中；框架可以放在另一个框架中，一个元素只直接属于一个框架，
+//!   放入新框架时自动从原框架移出
+//! - 移动框架时子框架一起移动，返回需要按同样位移移动的全部元素，由前端更新元素位置
+//! - 删除框架时子框架一并删除，元素保留在画板上
+//! - 导出 `.cboard` 画板文件时写入清单，打开后由前端以新画板 ID 整体保存
+
+use crate::file_manager::error::{FileManagerError, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use ts_rs::TS;
+
+/// 单个框架直接包含的元素上限
+pub const MAX_FRAME_ITEMS: usize = 10_000;
+
+/// 框架类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameKind {
+    /// 显示名称和边框的区域
+    #[default]
+    Frame,
+    /// 不显示边框，只把元素组合在一起
+    Group,
+}
+
+impl FrameKind {
+    /// 获取数据库中存储的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Frame => "frame",
+            Self::Group => "group",
+        }
+    }
+
+    /// 从数据库值解析，未知值按框架处理
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "group" => Self::Group,
+            _ => Self::Frame,
+        }
+    }
+}
+
+/// 画板上的框架
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BoardFrame {
+    pub id: String,
+    pub board_id: String,
+    /// 所在的上级框架，顶层框架为 `None`
+    pub parent_id: Option<String>,
+    pub kind: FrameKind,
+    pub name: String,
+    /// 左上角坐标
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// 直接包含的画板元素 ID，按前端传入的顺序
+    pub item_ids: Vec<String>,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+/// 创建或修改框架的请求，修改时整体替换
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BoardFrameRequest {
+    pub board_id: String,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub kind: FrameKind,
+    pub name: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    #[serde(default)]
+    pub item_ids: Vec<String>,
+}
+
+impl BoardFrameRequest {
+    /// 检查并规范化：名称去除首尾空白且不能为空，坐标必须是有限值且尺寸大于 0，
+    /// 空的上级框架 ID 视为顶层，元素 ID 去重后不超过上限
+    pub fn normalize(mut self) -> Result<Self> {
+        self.board_id = self.board_id.trim().to_string();
+        if self.board_id.is_empty() {
+            return Err(FileManagerError::general_error("Board ID cannot be empty"));
+        }
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return Err(FileManagerError::general_error("Frame name cannot be empty"));
+        }
+        if ![self.x, self.y, self.width, self.height].iter().all(|value| value.is_finite()) {
+            return Err(FileManagerError::general_error("Frame bounds must be finite numbers"));
+        }
+        if self.width <= 0.0 || self.height <= 0.0 {
+            return Err(FileManagerError::general_error("Frame size must be greater than 0"));
+        }
+        self.parent_id = self.parent_id.filter(|parent_id| !parent_id.trim().is_empty());
+
+        let mut seen = HashSet::new();
+        self.item_ids.retain(|item_id| !item_id.is_empty() && seen.insert(item_id.clone()));
+        if self.item_ids.len() > MAX_FRAME_ITEMS {
+            return Err(FileManagerError::general_error(format!(
+                "Frame cannot contain more than {} items", MAX_FRAME_ITEMS
+            )));
+        }
+        Ok(self)
+    }
+}
+
+impl From<BoardFrame> for BoardFrameRequest {
+    fn from(frame: BoardFrame) -> Self {
+        Self {
+            board_id: frame.board_id,
+            parent_id: frame.parent_id,
+            kind: frame.kind,
+            name: frame.name,
+            x: frame.x,
+            y: frame.y,
+            width: frame.width,
+            height: frame.height,
+            item_ids: frame.item_ids,
+        }
+    }
+}
+
+/// 检查并规范化整体保存到 `board_id` 的框架，如从画板文件打开的框架
+pub fn normalize_frames(board_id: &str, frames: Vec<BoardFrame>) -> Result<Vec<BoardFrame>> {
+    frames
+        .into_iter()
+        .map(|frame| {
+            let request = BoardFrameRequest {
+                board_id: board_id.to_string(),
+                ..BoardFrameRequest::from(frame.clone())
+            }.normalize()?;
+            Ok(BoardFrame {
+                board_id: request.board_id,
+                parent_id: request.parent_id,
+                kind: request.kind,
+                name: request.name,
+                x: request.x,
+                y: request.y,
+                width: request.width,
+                height: request.height,
+                item_ids: request.item_ids,
+                ..frame
+            })
+        })
+        .collect()
+}
+
+/// 移动框架的结果
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BoardFrameMove {
+    /// 移动后的框架及其所有子框架
+    pub frames: Vec<BoardFrame>,
+    /// 需要按同样位移移动的画板元素 ID，包括子框架中的元素
+    pub item_ids: Vec<String>,
+    pub dx: f64,
+    pub dy: f64,
+}
+
+/// `frame_id` 及其所有子框架的 ID，上级在前；`frames` 为同一画板的全部框架
+pub fn subtree_ids(frames: &[BoardFrame], frame_id: &str) -> Vec<String> {
+    let mut ids = vec![frame_id.to_string()];
+    let mut index = 0;
+    while index < ids.len() {
+        let parent = ids[index].clone();
+        ids.extend(
+            frames
+                .iter()
+                .filter(|frame| frame.parent_id.as_deref() == Some(parent.as_str()))
+                .map(|frame| frame.id.clone()),
+        );
+        index += 1;
+    }
+    ids
+}
+
+/// 按上级在前的顺序排列整体保存的框架，返回下标
+///
+/// 上级框架不在列表中或存在循环时返回错误
+pub fn parent_first_order(frames: &[BoardFrame]) -> Result<Vec<usize>> {
+    let index_by_id: HashMap<&str, usize> = frames.iter().enumerate().map(|(index, frame)| (frame.id.as_str(), index)).collect();
+    if index_by_id.len() != frames.len() {
+        return Err(FileManagerError::general_error("Frame IDs must be unique"));
+    }
+
+    let mut order = Vec::with_capacity(frames.len());
+    let mut placed = HashSet::new();
+    while order.len() < frames.len() {
+        let before = order.len();
+        for (index, frame) in frames.iter().enumerate() {
+            if placed.contains(&index) {
+                continue;
+            }
+            let ready = match frame.parent_id.as_deref() {
+                None => true,
+                Some(parent_id) => match index_by_id.get(parent_id) {
+                    Some(parent) => placed.contains(parent),
+                    None => {
+                        return Err(FileManagerError::general_error(format!(
+                            "Parent frame not found: {}", parent_id
+                        )));
+                    }
+                },
+            };
+            if ready {
+                placed.insert(index);
+                order.push(index);
+            }
+        }
+        if order.len() == before {
+            return Err(FileManagerError::general_error("Frames cannot contain themselves"));
+        }
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(id: &str, parent_id: Option<&str>) -> BoardFrame {
+        BoardFrame {
+            id: id.to_string(),
+            board_id: "board".to_string(),
+            parent_id: parent_id.map(str::to_string),
+            kind: FrameKind::Frame,
+            name: id.to_string(),
+            x: 0.0,
+            y: 0.0,
+            width: 10.0,
+            height: 10.0,
+            item_ids: Vec::new(),
+            created_at: Local::now(),
+            updated_at: Local::now(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_frame_request() {
+        let request = BoardFrameRequest {
+            board_id: " board ".to_string(),
+            parent_id: Some(" ".to_string()),
+            kind: FrameKind::Group,
+            name: "  Refs ".to_string(),
+            x: -5.0,
+            y: 0.0,
+            width: 100.0,
+            height: 50.0,
+            item_ids: vec!["a".to_string(), "a".to_string(), String::new(), "b".to_string()],
+        }.normalize().unwrap();
+        assert_eq!((request.board_id.as_str(), request.name.as_str()), ("board", "Refs"));
+        assert_eq!(request.parent_id, None);
+        assert_eq!(request.item_ids, vec!["a", "b"]);
+
+        let base = BoardFrameRequest::from(frame("f", None));
+        assert!(BoardFrameRequest { name: " ".to_string(), ..base.clone() }.normalize().is_err());
+        assert!(BoardFrameRequest { width: 0.0, ..base.clone() }.normalize().is_err());
+        assert!(BoardFrameRequest { x: f64::NAN, ..base.clone() }.normalize().is_err());
+        assert!(BoardFrameRequest {
+            item_ids: (0..=MAX_FRAME_ITEMS).map(|index| index.to_string()).collect(),
+            ..base
+        }.normalize().is_err());
+    }
+
+    #[test]
+    fn test_subtree_and_parent_first_order() {
+        let frames = vec![frame("child", Some("root")), frame("leaf", Some("child")), frame("root", None), frame("other", None)];
+        assert_eq!(subtree_ids(&frames, "root"), vec!["root", "child", "leaf"]);
+        assert_eq!(subtree_ids(&frames, "other"), vec!["other"]);
+
+        let order: Vec<&str> = parent_first_order(&frames).unwrap().into_iter().map(|index| frames[index].id.as_str()).collect();
+        assert_eq!(order, vec!["root", "other", "child", "leaf"]);
+
+        assert!(parent_first_order(&[frame("a", Some("missing"))]).is_err());
+        assert!(parent_first_order(&[frame("a", Some("b")), frame("b", Some("a"))]).is_err());
+        assert!(parent_first_order(&[frame("a", None), frame("a", None)]).is_err());
+    }
+}
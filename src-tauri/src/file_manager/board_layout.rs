@@ -0,0 +1,442 @@
+//! 画板自动排列模块
+//!
+//! 按图像元数据在后端计算画板素材的新位置，大量参考图一次整理好：
+//! - 网格：按原顺序排成接近正方形的网格，每张图等比缩放到单元格内
+//! - 按颜色：按平均颜色的色相排列，灰度图按明暗排在彩色图之后
+//! - 按宽高比：从竖图到横图排列，每行等高并缩放到相同宽度
+//! - 按标签分组：每张图归入它在画板上最常见的标签，各组排成网格后自上而下排列，无标签的排在最后
+//! - 宽高比和平均颜色取自已有的缩略图，没有时先生成小尺寸缩略图；非图像文件按正方形排列并在结果中列出
+//! - 画板内容由前端定义，只返回新的位置和尺寸，由前端应用到画板上
+
+use crate::file_manager::error::{FileManagerError, Result};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::HashMap;
+
+/// 默认单元格边长
+pub const DEFAULT_CELL_SIZE: f64 = 256.0;
+
+/// 默认间距
+pub const DEFAULT_SPACING: f64 = 16.0;
+
+/// 标签分组之间的间距，为素材间距的倍数
+const GROUP_SPACING_FACTOR: f64 = 4.0;
+
+/// 饱和度低于此值的颜色视为灰度
+const MIN_SATURATION: f64 = 0.15;
+
+/// 透明度低于此值的像素不参与平均颜色
+const MIN_ALPHA: u8 = 128;
+
+/// 排列方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutStrategy {
+    Grid,
+    Color,
+    AspectRatio,
+    TagClusters,
+}
+
+/// 画板上的一个素材
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LayoutItem {
+    /// 前端的画板元素 ID，同一文件可放置多次
+    pub item_id: String,
+    pub file_id: String,
+}
+
+/// 自动排列请求
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AutoLayoutRequest {
+    pub board_id: String,
+    pub strategy: LayoutStrategy,
+    /// 画板当前的元素；为 `None` 时使用放置到该画板上记录过的文件，元素 ID 即文件 ID
+    #[serde(default)]
+    pub items: Option<Vec<LayoutItem>>,
+    /// 单元格边长，默认 [`DEFAULT_CELL_SIZE`]
+    #[serde(default)]
+    pub cell_size: Option<f64>,
+    /// 素材间距，默认 [`DEFAULT_SPACING`]
+    #[serde(default)]
+    pub spacing: Option<f64>,
+}
+
+impl AutoLayoutRequest {
+    /// 检查单元格边长和间距
+    pub fn validate(&self) -> Result<()> {
+        if !self.cell_size().is_finite() || self.cell_size() <= 0.0 {
+            return Err(FileManagerError::general_error("Cell size must be greater than 0"));
+        }
+        if !self.spacing().is_finite() || self.spacing() < 0.0 {
+            return Err(FileManagerError::general_error("Spacing cannot be negative"));
+        }
+        Ok(())
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size.unwrap_or(DEFAULT_CELL_SIZE)
+    }
+
+    pub fn spacing(&self) -> f64 {
+        self.spacing.unwrap_or(DEFAULT_SPACING)
+    }
+}
+
+/// 一个元素的新位置和尺寸，坐标为左上角，相对于排列区域的左上角
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ItemTransform {
+    pub item_id: String,
+    pub file_id: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// 按标签分组时所在的标签，无标签的组为 `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+/// 自动排列结果
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AutoLayoutResult {
+    pub board_id: String,
+    pub strategy: LayoutStrategy,
+    /// 按排列后的顺序
+    pub transforms: Vec<ItemTransform>,
+    /// 排列区域的总宽度
+    pub width: f64,
+    /// 排列区域的总高度
+    pub height: f64,
+    /// 没有图像元数据、按正方形排列的文件 ID
+    pub missing_metadata: Vec<String>,
+}
+
+/// 从缩略图读取的图像特征
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageFeatures {
+    /// 宽高比（宽 / 高）
+    pub aspect_ratio: f64,
+    /// 不透明像素的平均颜色，完全透明的图像为 `None`
+    pub average_color: Option<[u8; 3]>,
+}
+
+impl ImageFeatures {
+    pub fn from_image(image: &image::DynamicImage) -> Self {
+        let rgba = image.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let aspect_ratio = if width == 0 || height == 0 { 1.0 } else { width as f64 / height as f64 };
+
+        let mut sum = [0u64; 3];
+        let mut count = 0u64;
+        for pixel in rgba.pixels().filter(|pixel| pixel[3] >= MIN_ALPHA) {
+            for (total, value) in sum.iter_mut().zip(pixel.0) {
+                *total += value as u64;
+            }
+            count += 1;
+        }
+        let average_color = (count > 0).then(|| sum.map(|value| (value / count) as u8));
+
+        Self { aspect_ratio, average_color }
+    }
+}
+
+/// 参与排列的一个元素
+#[derive(Debug, Clone)]
+pub struct LayoutInput {
+    pub item: LayoutItem,
+    /// 没有图像元数据时为 `None`
+    pub features: Option<ImageFeatures>,
+    /// 文件标签，只在按标签分组时读取
+    pub tags: Vec<String>,
+}
+
+impl LayoutInput {
+    fn aspect_ratio(&self) -> f64 {
+        self.features.map(|features| features.aspect_ratio).unwrap_or(1.0)
+    }
+}
+
+/// 按排列方式计算新的位置，返回元素位置、总宽度和总高度
+pub fn arrange(
+    strategy: LayoutStrategy,
+    mut inputs: Vec<LayoutInput>,
+    cell_size: f64,
+    spacing: f64,
+) -> (Vec<ItemTransform>, f64, f64) {
+    match strategy {
+        LayoutStrategy::Grid => grid(&inputs, cell_size, spacing, 0.0, None),
+        LayoutStrategy::Color => {
+            inputs.sort_by(|a, b| color_key(a).partial_cmp(&color_key(b)).unwrap_or(std::cmp::Ordering::Equal));
+            grid(&inputs, cell_size, spacing, 0.0, None)
+        }
+        LayoutStrategy::AspectRatio => {
+            inputs.sort_by(|a, b| a.aspect_ratio().total_cmp(&b.aspect_ratio()));
+            justified_rows(&inputs, cell_size, spacing)
+        }
+        LayoutStrategy::TagClusters => tag_clusters(inputs, cell_size, spacing),
+    }
+}
+
+/// 等比缩放到边长为 `cell_size` 的正方形内
+fn fit(aspect_ratio: f64, cell_size: f64) -> (f64, f64) {
+    if aspect_ratio >= 1.0 {
+        (cell_size, cell_size / aspect_ratio)
+    } else {
+        (cell_size * aspect_ratio, cell_size)
+    }
+}
+
+/// 接近正方形的网格的列数
+fn grid_columns(count: usize) -> usize {
+    ((count as f64).sqrt().ceil() as usize).max(1)
+}
+
+/// 从 `top` 开始按顺序排成网格，素材在单元格内居中
+fn grid(
+    inputs: &[LayoutInput],
+    cell_size: f64,
+    spacing: f64,
+    top: f64,
+    group: Option<&str>,
+) -> (Vec<ItemTransform>, f64, f64) {
+    if inputs.is_empty() {
+        return (Vec::new(), 0.0, 0.0);
+    }
+    let columns = grid_columns(inputs.len());
+    let rows = inputs.len().div_ceil(columns);
+    let pitch = cell_size + spacing;
+
+    let transforms = inputs.iter().enumerate().map(|(index, input)| {
+        let (width, height) = fit(input.aspect_ratio(), cell_size);
+        ItemTransform {
+            item_id: input.item.item_id.clone(),
+            file_id: input.item.file_id.clone(),
+            x: (index % columns) as f64 * pitch + (cell_size - width) / 2.0,
+            y: top + (index / columns) as f64 * pitch + (cell_size - height) / 2.0,
+            width,
+            height,
+            group: group.map(str::to_string),
+        }
+    }).collect();
+
+    let width = columns as f64 * pitch - spacing;
+    let height = rows as f64 * pitch - spacing;
+    (transforms, width, height)
+}
+
+/// 按顺序排成等高的行，每行缩放到与同数量网格相同的宽度；最后一行不足时保持单元格高度
+fn justified_rows(inputs: &[LayoutInput], cell_size: f64, spacing: f64) -> (Vec<ItemTransform>, f64, f64) {
+    if inputs.is_empty() {
+        return (Vec::new(), 0.0, 0.0);
+    }
+    let row_width = grid_columns(inputs.len()) as f64 * (cell_size + spacing) - spacing;
+
+    let mut transforms = Vec::with_capacity(inputs.len());
+    let mut width: f64 = 0.0;
+    let mut y = 0.0;
+    let mut start = 0;
+    while start < inputs.len() {
+        // 加入素材直到本行在单元格高度下达到目标宽度
+        let mut end = start;
+        let mut natural_width = 0.0;
+        while end < inputs.len() {
+            natural_width += inputs[end].aspect_ratio() * cell_size;
+            end += 1;
+            if natural_width + (end - start - 1) as f64 * spacing >= row_width {
+                break;
+            }
+        }
+
+        let gaps = (end - start - 1) as f64 * spacing;
+        let full = natural_width + gaps >= row_width;
+        let scale = if full { (row_width - gaps) / natural_width } else { 1.0 };
+        let height = cell_size * scale;
+
+        let mut x = 0.0;
+        for input in &inputs[start..end] {
+            let item_width = input.aspect_ratio() * height;
+            transforms.push(ItemTransform {
+                item_id: input.item.item_id.clone(),
+                file_id: input.item.file_id.clone(),
+                x,
+                y,
+                width: item_width,
+                height,
+                group: None,
+            });
+            x += item_width + spacing;
+        }
+
+        width = width.max(x - spacing);
+        y += height + spacing;
+        start = end;
+    }
+
+    (transforms, width, y - spacing)
+}
+
+/// 排序用的颜色键：彩色按色相和亮度，其后灰度从亮到暗，最后是没有颜色的
+fn color_key(input: &LayoutInput) -> (u8, f64, f64) {
+    let Some([r, g, b]) = input.features.and_then(|features| features.average_color) else {
+        return (2, 0.0, 0.0);
+    };
+    let (hue, saturation, lightness) = rgb_to_hsl(r, g, b);
+    if saturation < MIN_SATURATION {
+        (1, -lightness, 0.0)
+    } else {
+        (0, hue, lightness)
+    }
+}
+
+/// RGB 转换为 HSL，色相为 0 到 360 度，饱和度和亮度为 0 到 1
+fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (f64, f64, f64) {
+    let (r, g, b) = (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+    if delta == 0.0 {
+        return (0.0, 0.0, lightness);
+    }
+
+    let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+    let hue = if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    (hue, saturation, lightness)
+}
+
+/// 按标签分组，每组排成网格后自上而下排列
+///
+/// 每个素材归入它的标签中在画板上出现次数最多的一个（次数相同时按名称），
+/// 组按素材数从多到少排列，无标签的组在最后
+fn tag_clusters(inputs: Vec<LayoutInput>, cell_size: f64, spacing: f64) -> (Vec<ItemTransform>, f64, f64) {
+    let mut frequency: HashMap<&str, usize> = HashMap::new();
+    for tag in inputs.iter().flat_map(|input| &input.tags) {
+        *frequency.entry(tag.as_str()).or_default() += 1;
+    }
+
+    let mut groups: Vec<(Option<String>, Vec<LayoutInput>)> = Vec::new();
+    let assigned: Vec<Option<String>> = inputs.iter().map(|input| {
+        input.tags.iter()
+            .max_by(|a, b| frequency[a.as_str()].cmp(&frequency[b.as_str()]).then_with(|| b.cmp(a)))
+            .cloned()
+    }).collect();
+    for (input, tag) in inputs.into_iter().zip(assigned) {
+        match groups.iter_mut().find(|(group, _)| *group == tag) {
+            Some((_, members)) => members.push(input),
+            None => groups.push((tag, vec![input])),
+        }
+    }
+    groups.sort_by(|(a_tag, a), (b_tag, b)| {
+        a_tag.is_none().cmp(&b_tag.is_none())
+            .then_with(|| b.len().cmp(&a.len()))
+            .then_with(|| a_tag.cmp(b_tag))
+    });
+
+    let group_spacing = spacing * GROUP_SPACING_FACTOR;
+    let mut transforms = Vec::new();
+    let mut width: f64 = 0.0;
+    let mut top = 0.0;
+    for (tag, members) in &groups {
+        let (group_transforms, group_width, group_height) = grid(members, cell_size, spacing, top, tag.as_deref());
+        transforms.extend(group_transforms);
+        width = width.max(group_width);
+        top += group_height + group_spacing;
+    }
+
+    let height = if groups.is_empty() { 0.0 } else { top - group_spacing };
+    (transforms, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(id: &str, aspect_ratio: f64, color: Option<[u8; 3]>, tags: &[&str]) -> LayoutInput {
+        LayoutInput {
+            item: LayoutItem { item_id: id.to_string(), file_id: id.to_string() },
+            features: Some(ImageFeatures { aspect_ratio, average_color: color }),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    fn order(transforms: &[ItemTransform]) -> Vec<&str> {
+        transforms.iter().map(|transform| transform.item_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_grid_and_color_layout() {
+        let inputs = vec![
+            input("gray", 1.0, Some([128, 128, 128]), &[]),
+            input("blue", 2.0, Some([20, 40, 220]), &[]),
+            input("red", 0.5, Some([220, 30, 30]), &[]),
+            input("white", 1.0, Some([250, 250, 250]), &[]),
+            input("green", 1.0, Some([30, 200, 40]), &[]),
+        ];
+
+        // 5 个素材排成 3 列 2 行，横图在单元格内垂直居中
+        let (transforms, width, height) = arrange(LayoutStrategy::Grid, inputs.clone(), 100.0, 10.0);
+        assert_eq!(order(&transforms), vec!["gray", "blue", "red", "white", "green"]);
+        assert_eq!((width, height), (320.0, 210.0));
+        assert_eq!((transforms[1].x, transforms[1].y, transforms[1].width, transforms[1].height), (110.0, 25.0, 100.0, 50.0));
+        assert_eq!((transforms[3].x, transforms[3].y), (0.0, 110.0));
+
+        let (transforms, _, _) = arrange(LayoutStrategy::Color, inputs, 100.0, 10.0);
+        assert_eq!(order(&transforms), vec!["red", "green", "blue", "white", "gray"]);
+    }
+
+    #[test]
+    fn test_aspect_ratio_rows_are_justified() {
+        let inputs = vec![
+            input("wide", 2.0, None, &[]),
+            input("square", 1.0, None, &[]),
+            input("tall", 0.5, None, &[]),
+            input("panorama", 3.0, None, &[]),
+        ];
+        let (transforms, width, height) = arrange(LayoutStrategy::AspectRatio, inputs, 100.0, 10.0);
+        assert_eq!(order(&transforms), vec!["tall", "square", "wide", "panorama"]);
+        assert!((width - 210.0).abs() < 1e-9);
+
+        // 前三张缩小到同一高度撑满一行，全景图单独一行
+        let first_row: Vec<_> = transforms.iter().filter(|transform| transform.y == 0.0).collect();
+        assert_eq!(first_row.len(), 3);
+        assert!(first_row.iter().all(|transform| transform.height == first_row[0].height));
+        assert!((first_row[2].x + first_row[2].width - 210.0).abs() < 1e-9);
+        let panorama = &transforms[3];
+        assert!((panorama.width - 210.0).abs() < 1e-9 && (panorama.height - 70.0).abs() < 1e-9);
+        assert!((height - (first_row[0].height + 10.0 + 70.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tag_clusters_group_by_most_common_tag() {
+        let inputs = vec![
+            input("a", 1.0, None, &["sky"]),
+            input("b", 1.0, None, &[]),
+            input("c", 1.0, None, &["sky", "tree"]),
+            input("d", 1.0, None, &["tree"]),
+            input("e", 1.0, None, &["sky"]),
+        ];
+        let (transforms, _, height) = arrange(LayoutStrategy::TagClusters, inputs, 100.0, 10.0);
+
+        let groups: Vec<_> = transforms.iter().map(|transform| (transform.item_id.as_str(), transform.group.as_deref())).collect();
+        assert_eq!(groups, vec![
+            ("a", Some("sky")), ("c", Some("sky")), ("e", Some("sky")),
+            ("d", Some("tree")),
+            ("b", None),
+        ]);
+        // 各组自上而下排列，组间距为素材间距的 4 倍
+        assert_eq!(transforms[3].y, 210.0 + 40.0);
+        assert_eq!(height, 210.0 + 40.0 + 100.0 + 40.0 + 100.0);
+    }
+}
@@ -0,0 +1,231 @@
+//! 目录快照模块
+//!
+//! 定期保存目录数据库的轻量快照，批量操作出错时可以把整个目录回滚到之前的状态：
+//! - 快照只包含数据库，不包含存储文件，保存在应用数据目录的 `catalog-snapshots/` 下，
+//!   文件名为 `catalog-<时间>.db`，快照 ID 为不含扩展名的文件名
+//! - 后台任务按设置的间隔保存快照，只保留最近的若干个；也可以随时手动保存
+//! - 回滚需要确认：未确认时只返回快照和当前库的文件数，不修改资料库；
+//!   确认后先为当前状态保存一个快照，回滚本身也可以撤回
+//! - 回滚替换目录中的所有表，应用状态（设置等）和预写日志保留当前内容；
+//!   快照之后删除的文件已没有存储文件，在结果中列出，快照之后上传的存储文件保留在磁盘上
+//! - 快照与当前库使用相同的密码，修改密码后之前的快照无法回滚
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::error::{FileManagerError, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 保存快照设置的状态键
+pub const CATALOG_SNAPSHOT_SETTINGS_KEY: &str = "catalog_snapshot_settings";
+
+/// 存放快照的目录，位于应用数据目录下
+pub const CATALOG_SNAPSHOTS_DIR: &str = "catalog-snapshots";
+
+/// 快照文件名前缀
+const SNAPSHOT_PREFIX: &str = "catalog-";
+
+/// 快照文件扩展名
+const SNAPSHOT_EXTENSION: &str = "db";
+
+/// 后台任务检查是否需要保存快照的间隔
+pub const SNAPSHOT_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// 保存间隔（小时）的允许范围
+pub const MIN_SNAPSHOT_INTERVAL_HOURS: u32 = 1;
+pub const MAX_SNAPSHOT_INTERVAL_HOURS: u32 = 7 * 24;
+
+/// 保留数量的上限
+pub const MAX_RETAINED_SNAPSHOTS: usize = 100;
+
+/// 目录快照设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CatalogSnapshotSettings {
+    pub enabled: bool,
+    /// 两次定时快照之间的间隔（小时）
+    pub interval_hours: u32,
+    /// 保留的快照数，超出时删除最旧的
+    pub retain: usize,
+}
+
+impl Default for CatalogSnapshotSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_hours: 6,
+            retain: 10,
+        }
+    }
+}
+
+impl CatalogSnapshotSettings {
+    /// 检查间隔和保留数量的范围
+    pub fn validate(&self) -> Result<()> {
+        if !(MIN_SNAPSHOT_INTERVAL_HOURS..=MAX_SNAPSHOT_INTERVAL_HOURS).contains(&self.interval_hours) {
+            return Err(FileManagerError::general_error(format!(
+                "Snapshot interval must be between {} and {} hours",
+                MIN_SNAPSHOT_INTERVAL_HOURS, MAX_SNAPSHOT_INTERVAL_HOURS
+            )));
+        }
+        if !(1..=MAX_RETAINED_SNAPSHOTS).contains(&self.retain) {
+            return Err(FileManagerError::general_error(format!(
+                "Retained snapshot count must be between 1 and {}", MAX_RETAINED_SNAPSHOTS
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 已保存的目录快照
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CatalogSnapshot {
+    pub id: String,
+    pub created_at: DateTime<Local>,
+    /// 快照文件大小（字节）
+    pub size: u64,
+}
+
+/// 回滚的结果；未确认时 `applied` 为 `false`，只包含预览信息
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CatalogRollbackReport {
+    pub snapshot: CatalogSnapshot,
+    pub applied: bool,
+    /// 回滚前当前库中的文件数
+    pub current_files: usize,
+    /// 快照中的文件数
+    pub snapshot_files: usize,
+    /// 回滚前为当前状态保存的快照，可用于撤回回滚
+    #[serde(default)]
+    pub safety_snapshot: Option<CatalogSnapshot>,
+    /// 回滚后缺少存储文件的文件 ID（快照之后已删除），这些文件已标记为损坏
+    #[serde(default)]
+    pub missing_files: Vec<String>,
+}
+
+/// 快照目录
+pub fn snapshots_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join(CATALOG_SNAPSHOTS_DIR)
+}
+
+/// 快照 ID 对应的文件路径；ID 不是快照文件名时返回错误，避免访问快照目录以外的文件
+pub fn snapshot_path(dir: &Path, snapshot_id: &str) -> Result<PathBuf> {
+    let valid = snapshot_id.starts_with(SNAPSHOT_PREFIX)
+        && snapshot_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if !valid {
+        return Err(FileManagerError::general_error(format!("Invalid catalog snapshot ID: {}", snapshot_id)));
+    }
+    Ok(dir.join(format!("{}.{}", snapshot_id, SNAPSHOT_EXTENSION)))
+}
+
+/// 为新快照选择未被占用的 ID，同一秒内多次保存时追加序号
+pub fn new_snapshot_id(dir: &Path, time: DateTime<Local>) -> String {
+    let base = format!("{}{}", SNAPSHOT_PREFIX, time.format("%Y%m%d-%H%M%S"));
+    let mut snapshot_id = base.clone();
+    let mut suffix = 1;
+    while dir.join(format!("{}.{}", snapshot_id, SNAPSHOT_EXTENSION)).exists() {
+        snapshot_id = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+    snapshot_id
+}
+
+/// 列出快照目录中的快照，最新的在前；写入中的临时文件不列出
+pub fn list_snapshots(dir: &Path) -> std::io::Result<Vec<CatalogSnapshot>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some(SNAPSHOT_EXTENSION) {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if !id.starts_with(SNAPSHOT_PREFIX) {
+            continue;
+        }
+        let metadata = std::fs::metadata(&path)?;
+        snapshots.push(CatalogSnapshot {
+            id: id.to_string(),
+            created_at: metadata.modified()?.into(),
+            size: metadata.len(),
+        });
+    }
+
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+    Ok(snapshots)
+}
+
+/// 删除超出保留数量的旧快照，返回删除的快照 ID
+pub fn prune_snapshots(dir: &Path, retain: usize) -> std::io::Result<Vec<String>> {
+    let mut removed = Vec::new();
+    for snapshot in list_snapshots(dir)?.into_iter().skip(retain) {
+        std::fs::remove_file(dir.join(format!("{}.{}", snapshot.id, SNAPSHOT_EXTENSION)))?;
+        removed.push(snapshot.id);
+    }
+    Ok(removed)
+}
+
+/// 启动后台快照任务，按设置的间隔保存目录快照
+pub fn spawn_snapshot_loop(state: FileManagerState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SNAPSHOT_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let Ok(service) = state.lock().await else {
+                continue;
+            };
+            // 只读模式下由写入的进程保存快照
+            if service.is_read_only() {
+                continue;
+            }
+            match service.run_scheduled_catalog_snapshot().await {
+                Ok(Some(snapshot)) => tracing::info!("已保存目录快照: {} ({} 字节)", snapshot.id, snapshot.size),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("目录快照保存失败: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_snapshot_ids_and_pruning() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let time = Local::now();
+
+        let first = new_snapshot_id(dir, time);
+        std::fs::write(snapshot_path(dir, &first).unwrap(), b"first").unwrap();
+        let second = new_snapshot_id(dir, time);
+        assert_eq!(second, format!("{}-1", first));
+        std::fs::write(snapshot_path(dir, &second).unwrap(), b"second").unwrap();
+        std::fs::write(dir.join(format!("{}.db.tmp", first)), b"partial").unwrap();
+
+        let snapshots = list_snapshots(dir).unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots.iter().map(|snapshot| snapshot.size).sum::<u64>(), 11);
+
+        assert_eq!(prune_snapshots(dir, 1).unwrap().len(), 1);
+        assert_eq!(list_snapshots(dir).unwrap().len(), 1);
+        assert!(list_snapshots(&dir.join("missing")).unwrap().is_empty());
+
+        assert!(snapshot_path(dir, "../library").is_err());
+        assert!(snapshot_path(dir, "catalog-x/../../library").is_err());
+    }
+}
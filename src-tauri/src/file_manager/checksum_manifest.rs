@@ -0,0 +1,168 @@
+//! 校验清单模块
+//!
+//! 交付文件时附带校验清单，客户收到后可以用常见工具核对文件是否完整：
+//! - `sha256sum`：每行为 SHA-256 和文件名，可用 `sha256sum -c` 校验
+//! - `sfv`：每行为文件名和 CRC32，兼容只支持 SFV 的校验工具；CRC32 只能发现传输损坏，不能防篡改
+//! - `json`：包含文件名、大小和 SHA-256，便于程序处理
+//! - 为选中的文件生成时，文件名与平铺导出时的原始文件名一致，重名的文件按导出规则追加序号；
+//!   校验值按存储文件的实际内容计算，不使用数据库中记录的哈希
+//! - 导出时可以同时在目标目录写入清单，文件名为导出后的相对路径，加水印等处理后的文件按导出结果计算
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+
+/// 校验清单格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestFormat {
+    Sfv,
+    Sha256sum,
+    Json,
+}
+
+impl ManifestFormat {
+    /// 写入导出目录时使用的清单文件名
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Sfv => "checksums.sfv",
+            Self::Sha256sum => "SHA256SUMS",
+            Self::Json => "checksums.json",
+        }
+    }
+}
+
+/// 清单中的文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ManifestEntry {
+    /// 相对于交付目录的路径，以 `/` 分隔
+    pub name: String,
+    pub size: u64,
+    /// SFV 格式为大写的 CRC32，其他格式为小写的 SHA-256
+    pub checksum: String,
+}
+
+/// 生成的校验清单
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ChecksumManifest {
+    pub format: ManifestFormat,
+    /// 建议的清单文件名
+    pub file_name: String,
+    pub content: String,
+    pub entries: Vec<ManifestEntry>,
+}
+
+/// JSON 清单的内容
+#[derive(Serialize)]
+struct JsonManifest<'a> {
+    algorithm: &'static str,
+    generated_at: DateTime<Local>,
+    files: Vec<JsonManifestFile<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonManifestFile<'a> {
+    name: &'a str,
+    size: u64,
+    sha256: &'a str,
+}
+
+/// 按清单格式计算文件的校验值和大小
+///
+/// 需要读取整个文件，应在阻塞线程中调用
+pub fn checksum_file(path: &Path, format: ManifestFormat) -> std::io::Result<(String, u64)> {
+    match format {
+        ManifestFormat::Sfv => {
+            let mut hasher = crc32fast::Hasher::new();
+            let size = read_chunks(path, |chunk| hasher.update(chunk))?;
+            Ok((format!("{:08X}", hasher.finalize()), size))
+        }
+        ManifestFormat::Sha256sum | ManifestFormat::Json => {
+            let mut hasher = Sha256::new();
+            let size = read_chunks(path, |chunk| hasher.update(chunk))?;
+            Ok((format!("{:x}", hasher.finalize()), size))
+        }
+    }
+}
+
+/// 分块读取文件，返回读取的字节数
+fn read_chunks(path: &Path, mut update: impl FnMut(&[u8])) -> std::io::Result<u64> {
+    let mut file = std::fs::File::open(path)?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(size);
+        }
+        update(&buffer[..read]);
+        size += read as u64;
+    }
+}
+
+/// 按格式生成清单内容
+pub fn render_manifest(format: ManifestFormat, entries: Vec<ManifestEntry>, generated_at: DateTime<Local>) -> ChecksumManifest {
+    let content = match format {
+        ManifestFormat::Sfv => {
+            let mut content = format!("; Generated by Collaboard on {}\n", generated_at.format("%Y-%m-%d %H:%M:%S"));
+            for entry in &entries {
+                content.push_str(&format!("{} {}\n", entry.name, entry.checksum));
+            }
+            content
+        }
+        // 星号表示按二进制模式校验，Windows 上的 sha256sum 不会转换换行符
+        ManifestFormat::Sha256sum => entries.iter()
+            .map(|entry| format!("{} *{}\n", entry.checksum, entry.name))
+            .collect(),
+        ManifestFormat::Json => {
+            let manifest = JsonManifest {
+                algorithm: "sha256",
+                generated_at,
+                files: entries.iter()
+                    .map(|entry| JsonManifestFile { name: &entry.name, size: entry.size, sha256: &entry.checksum })
+                    .collect(),
+            };
+            serde_json::to_string_pretty(&manifest).expect("manifest serialization cannot fail") + "\n"
+        }
+    };
+
+    ChecksumManifest { format, file_name: format.file_name().to_string(), content, entries }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_render_manifest_formats() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hello.txt");
+        std::fs::write(&path, b"hello").unwrap();
+
+        let (crc, size) = checksum_file(&path, ManifestFormat::Sfv).unwrap();
+        assert_eq!((crc.as_str(), size), ("3610A686", 5));
+        let (sha256, _) = checksum_file(&path, ManifestFormat::Sha256sum).unwrap();
+        assert_eq!(sha256, "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824");
+
+        let entry = |checksum: &str| vec![ManifestEntry { name: "dir/hello.txt".to_string(), size, checksum: checksum.to_string() }];
+        let sfv = render_manifest(ManifestFormat::Sfv, entry(&crc), Local::now());
+        assert!(sfv.content.starts_with("; Generated by Collaboard"));
+        assert!(sfv.content.ends_with("dir/hello.txt 3610A686\n"));
+
+        let sums = render_manifest(ManifestFormat::Sha256sum, entry(&sha256), Local::now());
+        assert_eq!(sums.content, format!("{} *dir/hello.txt\n", sha256));
+        assert_eq!(sums.file_name, "SHA256SUMS");
+
+        let json = render_manifest(ManifestFormat::Json, entry(&sha256), Local::now());
+        let value: serde_json::Value = serde_json::from_str(&json.content).unwrap();
+        assert_eq!(value["files"][0]["sha256"], sha256);
+        assert_eq!(value["files"][0]["size"], 5);
+    }
+}
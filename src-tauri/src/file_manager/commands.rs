@@ -7,72 +7,546 @@
 //! - 参数验证和错误处理
 
 use crate::file_manager::{
+    activity::{ActivityEntry, ActivityFilter},
+    analytics::{ActivityPoint, StatsSnapshot, StorageBreakdown, TimelineBucket, TimelineRange},
+    archive::{ArchiveReport, ArchiveSettings},
+    backup::{BackupReport, BackupScope, RestoreMode, RestoreReport},
+    batch::{BatchOperation, BatchReport, MAX_BATCH_OPERATIONS},
+    board_bundle::{
+        BoardExportReport, CollectBoardAssetsReport, CollectBoardAssetsRequest, ExportBoardRequest, OpenedBoard,
+    },
+    board_frames::{BoardFrame, BoardFrameMove, BoardFrameRequest},
+    board_layout::{AutoLayoutRequest, AutoLayoutResult},
+    catalog_snapshots::{CatalogRollbackReport, CatalogSnapshot, CatalogSnapshotSettings},
+    checksum_manifest::{ChecksumManifest, ManifestFormat},
+    config::FileTypePolicy,
+    database::DirectoryDefaults,
+    deferred_delete::{DeferredDelete, DeferredDeleteReport},
+    directory_delete::{self, DirectoryDeleteJob},
+    directory_import::{self, DirectoryImportReport, ImportDirectoryRequest},
+    directory_templates::{DirectoryTemplate, TemplateApplication, TemplateFolder},
     error::{FileManagerError, Result},
+    events::{self, DeferredDeleteFinished, EventDescriptor, EventRecord, UploadCompleted, EVENT_CATALOG},
+    i18n::{self, current_locale, localize_error, Locale, ValidationError},
+    ids::IdStrategy,
+    embedded_metadata::{ImageMetadataFields, ImageMetadataResult},
+    export::{ExportReport, ExportRequest},
+    export_presets::ExportPreset,
+    external_edit::{self, ExternalEditSession},
+    duplicates::{DuplicateGroup, DuplicatePlan, ResolveDuplicatesReport},
+    image_compare::ImageComparison,
+    inbox::TriageAction,
+    job_queue::{self, QueuedJob, RecoveredJob},
+    low_memory::LowMemorySettings,
+    integrity::{IntegrityReport, IntegritySettings, DEFAULT_SAMPLE_SIZE},
+    library_summary::{self, LibrarySummary},
+    metadata::GeoBoundingBox,
+    metadata_transfer::{MetadataExportReport, MetadataImportReport},
+    network_share::LibraryOffline,
+    similarity::{DEFAULT_MAX_DISTANCE, HASH_BITS},
+    size_limits::SizeLimits,
+    thumbnail::{
+        self, CacheStats, PrefetchContext, PreviewSettings, ThumbnailCacheSettings, ThumbnailSize, MAX_PREVIEW_DIMENSION,
+        MIN_PREVIEW_DIMENSION, MIN_THUMBNAIL_CACHE_BYTES,
+    },
+    notifications::{self, JobNotification, NotificationSettings},
+    pack_store::{self, PackReport, PackSettings, PackStats},
+    open_with::{normalize_mime_pattern, ExternalApp, OpenWithSettings},
+    pagination::{Page, PageCursor, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE},
+    playlists::{Playlist, PlaylistItem, PlaylistRequest},
+    quarantine::{QuarantineSettings, QuarantinedFile},
+    quick_capture::{self, parse_shortcut, QuickCaptureSettings},
+    quick_find::{QuickFindResult, DEFAULT_QUICK_FIND_LIMIT, MAX_QUICK_FIND_LIMIT},
+    read_only::{self, ReadOnlyReason, WriterLease},
+    response_guard::{self, ListingContinuation, ResponseLimitSettings, MIN_MAX_RESPONSE_BYTES},
+    screenshot::{capture_into_library, CaptureMode},
+    search::{SearchHistoryEntry, SearchSuggestion, DEFAULT_SUGGESTION_LIMIT, MAX_SUGGESTION_LIMIT},
+    shell_integration::{self, ShellIntegrationStatus},
+    sidecar::MAX_RATING,
+    startup::{BackendStatus, StartupReport, StartupTask, StartupTasks},
+    storage_layout::{self, StorageLayout, StorageLayoutSettings},
+    telemetry::{self, parse_endpoint, Feature, TelemetryPreview, TelemetrySettings},
+    trash::{TrashPurgeReport, TrashSettings, TrashedFile},
+    url_import::{download, parse_import_url},
+    usage::{SuggestedFile, SuggestionContext, UsageKind},
+    watched_folders::{self, AddWatchedFolderRequest, WatchedFolder, WatchedImportReport},
+    watermark::Watermark,
+    working_sets::{is_valid_ttl, WorkingSet, MAX_WORKING_SET_TTL_MINUTES},
     service::{
-        FileManagerService, UploadRequest, UploadResponse,
+        FileManagerService, UploadRequest, UploadResponse, ConflictPolicy,
         CreateDirectoryRequest, CreateDirectoryResponse,
-        DirectoryTreeNode, FileListItem,
+        MoveDirectoryRequest, MoveDirectoryResponse, DirectoryItemCounts,
+        DirectoryTreeNode, FileListItem, MissingFileScanReport, ProcessingReport, ReindexScope, SearchScope,
+        BulkTagRequest, BulkTagResponse, ThumbnailResponse, SimilarImage,
     },
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tauri::State;
-use tokio::sync::Mutex;
+use ts_rs::TS;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime, State};
+use tauri_plugin_opener::OpenerExt;
+use tokio::sync::{mpsc, oneshot, Mutex, MutexGuard};
 
 /// 全局文件管理服务状态
-pub type FileManagerState = Arc<Mutex<FileManagerService>>;
+///
+/// 应用启动时先注册空状态，服务在后台初始化完成后才可用；
+/// 在此之前获取服务返回 [`FileManagerError::Initializing`]
+#[derive(Clone, Default)]
+pub struct FileManagerState {
+    inner: Arc<BackendState>,
+}
+
+#[derive(Default)]
+struct BackendState {
+    service: OnceLock<Mutex<FileManagerService>>,
+    startup_report: OnceLock<StartupReport>,
+    /// 启动迁移和恢复任务的进度，初始化期间由初始化任务更新
+    startup_tasks: StartupTasks,
+    failure: OnceLock<String>,
+    /// 资料库已加密、等待密码时，向初始化任务提交密码的通道
+    unlock: std::sync::Mutex<Option<mpsc::UnboundedSender<UnlockRequest>>>,
+    /// 只读模式的原因，与服务中的记录一致；状态查询不需要等待服务锁
+    read_only: std::sync::Mutex<Option<ReadOnlyReason>>,
+    /// 本进程持有的资料库写入锁
+    writer_lock: std::sync::Mutex<Option<WriterLease>>,
+    /// 资料库所在的网络共享无法访问时的离线信息
+    offline: std::sync::Mutex<Option<LibraryOffline>>,
+    /// 应用正在退出，不再交出服务锁
+    shutting_down: AtomicBool,
+}
+
+/// 解锁请求，初始化任务用密码打开数据库后回复结果
+pub struct UnlockRequest {
+    pub password: String,
+    pub reply: oneshot::Sender<Result<()>>,
+}
+
+impl FileManagerState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 服务初始化完成
+    pub fn set_ready(&self, service: FileManagerService, report: StartupReport) {
+        self.set_unlocked();
+        if self.inner.service.set(Mutex::new(service)).is_err() {
+            tracing::warn!("文件管理服务重复初始化，忽略");
+            return;
+        }
+        let _ = self.inner.startup_report.set(report);
+    }
+
+    /// 资料库已加密，等待用户输入密码；返回接收解锁请求的通道
+    pub fn set_locked(&self) -> mpsc::UnboundedReceiver<UnlockRequest> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        *self.inner.unlock.lock().unwrap() = Some(sender);
+        receiver
+    }
+
+    /// 资料库已解锁，继续初始化
+    pub fn set_unlocked(&self) {
+        self.inner.unlock.lock().unwrap().take();
+    }
+
+    /// 提交密码并等待初始化任务打开数据库的结果
+    pub async fn unlock(&self, password: String) -> Result<()> {
+        let not_locked = || FileManagerError::general_error("Library is not locked");
+        let sender = self.inner.unlock.lock().unwrap().clone().ok_or_else(not_locked)?;
+
+        let (reply, response) = oneshot::channel();
+        sender.send(UnlockRequest { password, reply }).map_err(|_| not_locked())?;
+        response.await.map_err(|_| FileManagerError::general_error("Unlock was interrupted"))?
+    }
+
+    /// 服务初始化失败
+    pub fn set_failed(&self, message: String) {
+        let _ = self.inner.failure.set(message);
+    }
+
+    /// 以只读模式启动，不获取写入锁
+    pub fn request_read_only(&self) {
+        *self.inner.read_only.lock().unwrap() = Some(ReadOnlyReason::Requested);
+    }
+
+    /// 获取只读模式的原因，可写时为 `None`
+    pub fn read_only_reason(&self) -> Option<ReadOnlyReason> {
+        self.inner.read_only.lock().unwrap().clone()
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.inner.read_only.lock().unwrap().is_some()
+    }
+
+    /// 启动时获取资料库的写入锁，返回只读原因；已要求只读时不获取
+    ///
+    /// 文件系统不支持文件锁时按可写模式打开
+    pub fn acquire_writer_lock(&self, app_data_dir: &std::path::Path) -> Option<ReadOnlyReason> {
+        if let Some(reason) = self.read_only_reason() {
+            return Some(reason);
+        }
+        match read_only::acquire_writer_lock(app_data_dir, false).map(|lock| lock.into_reason()) {
+            Ok(Ok(lease)) => {
+                *self.inner.writer_lock.lock().unwrap() = Some(lease);
+                None
+            }
+            Ok(Err(reason)) => {
+                tracing::warn!("资料库正被其他进程写入，以只读模式打开: {}", reason);
+                *self.inner.read_only.lock().unwrap() = Some(reason.clone());
+                Some(reason)
+            }
+            Err(e) => {
+                tracing::warn!("资料库写入锁获取失败，按可写模式打开: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 手动进入或退出只读模式，返回切换后的只读原因
+    ///
+    /// 进入时释放写入锁，其他进程随后可以写入；退出时重新获取写入锁，
+    /// 其他进程仍在写入时保持只读并返回 [`FileManagerError::ReadOnly`]
+    pub async fn switch_read_only(&self, service: &mut FileManagerService, enabled: bool) -> Result<Option<ReadOnlyReason>> {
+        if enabled {
+            self.apply_read_only(service, Some(ReadOnlyReason::Requested)).await?;
+            self.inner.writer_lock.lock().unwrap().take();
+            return Ok(self.read_only_reason());
+        }
+        self.leave_read_only(service, false).await
+    }
+
+    /// 接管心跳已停止或文件锁已释放的资料库，原持有者下次心跳时转为只读模式
+    pub async fn take_over(&self, service: &mut FileManagerService) -> Result<Option<ReadOnlyReason>> {
+        self.leave_read_only(service, true).await
+    }
+
+    async fn leave_read_only(&self, service: &mut FileManagerService, take_over: bool) -> Result<Option<ReadOnlyReason>> {
+        if !self.is_read_only() {
+            return Ok(None);
+        }
+
+        match read_only::acquire_writer_lock(&service.config().app_data_dir, take_over)?.into_reason() {
+            Ok(lease) => {
+                *self.inner.writer_lock.lock().unwrap() = Some(lease);
+                self.apply_read_only(service, None).await?;
+                // 只读期间没有开始会话，重新开始后异常退出时下次启动会执行恢复扫描
+                service.begin_session().await?;
+                Ok(None)
+            }
+            Err(reason) => {
+                self.apply_read_only(service, Some(reason.clone())).await?;
+                Err(FileManagerError::ReadOnly(reason))
+            }
+        }
+    }
+
+    /// 更新写入锁的心跳；已被其他进程接管时转为只读模式并返回只读原因
+    pub async fn refresh_writer_lock(&self) -> Result<Option<ReadOnlyReason>> {
+        let owner = {
+            let mut writer_lock = self.inner.writer_lock.lock().unwrap();
+            let Some(lease) = writer_lock.as_mut() else {
+                return Ok(None);
+            };
+            match lease.heartbeat()? {
+                Some(owner) => {
+                    writer_lock.take();
+                    owner
+                }
+                None => return Ok(None),
+            }
+        };
+
+        let reason = ReadOnlyReason::InUse {
+            holder: Some(owner.holder()),
+            heartbeat_at: Some(owner.heartbeat_at),
+            can_take_over: false,
+        };
+        let mut service = self.lock().await?;
+        self.apply_read_only(&mut service, Some(reason.clone())).await?;
+        Ok(Some(reason))
+    }
+
+    async fn apply_read_only(&self, service: &mut FileManagerService, reason: Option<ReadOnlyReason>) -> Result<()> {
+        service.apply_read_only(reason.clone()).await?;
+        *self.inner.read_only.lock().unwrap() = reason;
+        Ok(())
+    }
+
+    /// 资料库离线信息，网络共享恢复访问后为空
+    pub fn offline(&self) -> Option<LibraryOffline> {
+        self.inner.offline.lock().unwrap().clone()
+    }
+
+    pub fn set_offline(&self, offline: Option<LibraryOffline>) {
+        *self.inner.offline.lock().unwrap() = offline;
+    }
+
+    /// 获取服务状态
+    pub fn status(&self) -> BackendStatus {
+        if self.inner.service.get().is_some() {
+            BackendStatus::Ready { read_only: self.read_only_reason(), offline: self.offline() }
+        } else if let Some(message) = self.inner.failure.get() {
+            BackendStatus::Failed { message: message.clone() }
+        } else if self.inner.unlock.lock().unwrap().is_some() {
+            BackendStatus::Locked
+        } else {
+            BackendStatus::Initializing
+        }
+    }
+
+    /// 获取启动耗时报告，初始化完成前为空
+    pub fn startup_report(&self) -> Option<&StartupReport> {
+        self.inner.startup_report.get()
+    }
+
+    /// 启动迁移和恢复任务的进度记录
+    pub fn startup_tasks(&self) -> &StartupTasks {
+        &self.inner.startup_tasks
+    }
+
+    /// 获取服务锁，应用开始退出后返回 [`FileManagerError::ShuttingDown`]
+    pub async fn lock(&self) -> Result<MutexGuard<'_, FileManagerService>> {
+        if self.inner.shutting_down.load(Ordering::SeqCst) {
+            return Err(FileManagerError::ShuttingDown);
+        }
+        match self.inner.service.get() {
+            Some(service) => Ok(service.lock().await),
+            None => match self.status() {
+                BackendStatus::Failed { message } => Err(FileManagerError::InitializationFailed { message }),
+                BackendStatus::Locked => Err(FileManagerError::LibraryLocked),
+                _ => Err(FileManagerError::Initializing),
+            },
+        }
+    }
+
+    /// 获取服务锁并检查资料库可写，网络共享断开时返回 [`FileManagerError::LibraryOffline`]，
+    /// 只读模式下返回 [`FileManagerError::ReadOnly`]
+    ///
+    /// 逐个文件执行的后台任务每一步都通过它获取服务锁，步骤之间释放
+    pub async fn lock_writable(&self) -> Result<MutexGuard<'_, FileManagerService>> {
+        if let Some(offline) = self.offline() {
+            return Err(FileManagerError::LibraryOffline { path: offline.path });
+        }
+        let service = self.lock().await?;
+        service.ensure_writable()?;
+        Ok(service)
+    }
+
+    /// 获取服务锁，应用开始退出后也可获取
+    ///
+    /// 只供后台任务在退出过程中收尾（如移出任务队列），[`Self::shutdown`] 会等待这些任务注销后再关闭服务
+    pub async fn lock_for_cleanup(&self) -> Result<MutexGuard<'_, FileManagerService>> {
+        match self.inner.service.get() {
+            Some(service) => Ok(service.lock().await),
+            None => Err(FileManagerError::Initializing),
+        }
+    }
+
+    /// 应用退出时停止后台任务并关闭服务
+    ///
+    /// 先拒绝新的命令和后台循环获取服务锁，再暂停后台目录删除并等待持有服务锁的任务
+    /// （导出、逐个文件执行的导入和打包维护的当前一步、后台循环的当前一轮）结束，最后标记正常关闭。
+    /// 超过 `timeout` 仍未结束时不标记正常关闭，下次启动执行恢复扫描
+    pub async fn shutdown(&self, timeout: Duration) -> Result<()> {
+        // 服务尚未初始化完成时没有需要关闭的资源
+        let Some(service) = self.inner.service.get() else {
+            return Ok(());
+        };
+        self.inner.shutting_down.store(true, Ordering::SeqCst);
+
+        let drained = tokio::time::timeout(timeout, async {
+            directory_delete::pause_all().await;
+            service.lock().await
+        }).await;
+        match drained {
+            Ok(service) => service.shutdown().await,
+            Err(_) => {
+                tracing::warn!("等待运行中的任务超时（{:?}），本次不标记为正常关闭", timeout);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 应用退出时等待运行中的任务结束的最长时间
+pub const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 获取服务锁，服务尚未就绪时直接返回带错误码的响应
+macro_rules! lock_service {
+    ($state:expr) => {
+        match $state.lock().await {
+            Ok(service) => service,
+            Err(error) => return Ok(CommandResponse::from_error(&error)),
+        }
+    };
+}
+
+/// 获取服务锁并检查资料库可写，只读模式下直接返回 `read_only` 错误码，
+/// 网络共享断开时返回 `library_offline` 错误码
+macro_rules! lock_service_mut {
+    ($state:expr) => {
+        match $state.lock_writable().await {
+            Ok(service) => service,
+            Err(error) => return Ok(CommandResponse::from_error(&error)),
+        }
+    };
+}
+
+/// 批量上传每批最多的文件数
+pub const MAX_BATCH_UPLOAD_FILES: usize = 50;
+
+/// 搜索关键词的最小长度
+pub const MIN_SEARCH_QUERY_LEN: usize = 2;
+
+/// 资料库密码的最小长度
+pub const MIN_LIBRARY_PASSWORD_LEN: usize = 8;
 
 /// 文件上传命令参数
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct UploadFileCommand {
     pub file_data: Vec<u8>,
     pub original_name: String,
     pub directory_id: Option<String>,
+    /// 同名文件冲突策略，默认重命名
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+    /// 按拍摄日期整理到自动创建的日期文件夹
+    #[serde(default)]
+    pub organize_by_capture_date: bool,
+    /// 文件修改时间（毫秒时间戳）
+    #[serde(default)]
+    pub last_modified: Option<i64>,
+}
+
+/// 批量上传中单个文件的结果，`index` 为文件在请求中的位置
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum UploadItemResult {
+    Uploaded {
+        index: usize,
+        original_name: String,
+        response: UploadResponse,
+    },
+    Failed {
+        index: usize,
+        original_name: String,
+        /// 按当前语言生成的错误信息
+        error: String,
+        error_code: String,
+    },
+}
+
+impl UploadItemResult {
+    /// 参数校验失败的文件
+    fn invalid(index: usize, original_name: String, error: ValidationError) -> Self {
+        telemetry::record_error(error.code());
+        Self::Failed {
+            index,
+            original_name,
+            error: error.message(current_locale()),
+            error_code: error.code().to_string(),
+        }
+    }
+
+    /// 上传失败的文件
+    fn failed(index: usize, original_name: String, error: &FileManagerError) -> Self {
+        telemetry::record_error(error.code());
+        Self::Failed {
+            index,
+            original_name,
+            error: localize_error(error, current_locale()),
+            error_code: error.code().to_string(),
+        }
+    }
 }
 
 /// 创建目录命令参数
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct CreateDirectoryCommand {
     pub name: String,
     pub parent_id: Option<String>,
 }
 
 /// 删除文件命令参数
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct DeleteFileCommand {
     pub file_id: String,
+    /// 调用方最后看到的文件 `updated_at`
+    #[serde(default)]
+    pub expected_updated_at: String,
 }
 
 /// 删除目录命令参数
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct DeleteDirectoryCommand {
     pub directory_id: String,
+    /// 目录非空时是否仍然删除
+    #[serde(default)]
+    pub force: bool,
+    /// 调用方最后看到的目录 `updated_at`
+    #[serde(default)]
+    pub expected_updated_at: String,
+    /// 只列出要删除的文件和目录，不删除
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 移动目录命令参数
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MoveDirectoryCommand {
+    pub directory_id: String,
+    pub new_parent_id: Option<String>,
+    /// 调用方最后看到的目录 `updated_at`
+    #[serde(default)]
+    pub expected_updated_at: String,
 }
 
 /// 获取目录文件命令参数
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct GetDirectoryFilesCommand {
     pub directory_id: String,
+    /// 按是否归档筛选，未指定时返回全部文件
+    #[serde(default)]
+    pub archived: Option<bool>,
 }
 
 /// 获取文件信息命令参数
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct GetFileInfoCommand {
     pub file_id: String,
 }
 
 /// 读取文件内容命令参数
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct ReadFileContentCommand {
     pub file_id: String,
 }
 
 /// 命令响应包装器
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct CommandResponse<T> {
     pub success: bool,
     pub data: Option<T>,
+    /// 按当前语言生成的错误信息
     pub error: Option<String>,
+    /// 稳定的错误码，参数校验和服务错误时提供
+    #[serde(default)]
+    pub error_code: Option<String>,
+    /// 列表响应超出大小上限、只返回了第一页时提供，其余部分通过 `get_listing_page` 获取
+    #[serde(default)]
+    pub continuation: Option<ListingContinuation>,
 }
 
 impl<T> CommandResponse<T> {
@@ -82,6 +556,8 @@ impl<T> CommandResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            error_code: None,
+            continuation: None,
         }
     }
 
@@ -91,6 +567,70 @@ impl<T> CommandResponse<T> {
             success: false,
             data: None,
             error: Some(error),
+            error_code: None,
+            continuation: None,
+        }
+    }
+
+    /// 创建带错误码的服务错误响应，错误信息使用当前语言
+    pub fn from_error(error: &FileManagerError) -> Self {
+        telemetry::record_error(error.code());
+        Self {
+            error_code: Some(error.code().to_string()),
+            ..Self::error(localize_error(error, current_locale()))
+        }
+    }
+
+    /// 创建参数校验失败响应，错误信息使用当前语言
+    pub fn invalid(error: ValidationError) -> Self {
+        telemetry::record_error(error.code());
+        Self {
+            error_code: Some(error.code().to_string()),
+            ..Self::error(error.message(current_locale()))
+        }
+    }
+}
+
+impl<T: Serialize> CommandResponse<Vec<T>> {
+    /// 创建列表响应
+    ///
+    /// 序列化后超出响应大小上限时只返回第一页，并附带续传信息和警告
+    pub fn listing(result: Result<Vec<T>>) -> Self {
+        match result {
+            Ok(items) => {
+                let (items, continuation) = response_guard::guard_listing(items);
+                Self { continuation, ..Self::success(items) }
+            }
+            Err(error) => Self::from_error(&error),
+        }
+    }
+}
+
+/// 直接返回原始字节的命令出错时的错误，与 [`CommandResponse`] 一样带有错误码和按当前语言生成的错误信息
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CommandError {
+    /// 稳定的错误码
+    pub code: String,
+    pub message: String,
+}
+
+impl CommandError {
+    /// 服务错误
+    pub fn from_error(error: &FileManagerError) -> Self {
+        telemetry::record_error(error.code());
+        Self {
+            code: error.code().to_string(),
+            message: localize_error(error, current_locale()),
+        }
+    }
+
+    /// 参数校验失败
+    pub fn invalid(error: ValidationError) -> Self {
+        telemetry::record_error(error.code());
+        Self {
+            code: error.code().to_string(),
+            message: error.message(current_locale()),
         }
     }
 }
@@ -100,7 +640,7 @@ impl<T> From<Result<T>> for CommandResponse<T> {
     fn from(result: Result<T>) -> Self {
         match result {
             Ok(data) => CommandResponse::success(data),
-            Err(error) => CommandResponse::error(error.to_string()),
+            Err(error) => CommandResponse::from_error(&error),
         }
     }
 }
@@ -111,6 +651,7 @@ impl<T> From<Result<T>> for CommandResponse<T> {
 #[tauri::command]
 pub async fn upload_file(
     command: UploadFileCommand,
+    app: AppHandle,
     service: State<'_, FileManagerState>,
 ) -> std::result::Result<CommandResponse<UploadResponse>, String> {
     tracing::info!("开始处理文件上传请求: 文件名={}, 文件大小={} bytes, 目录ID={:?}", 
@@ -119,23 +660,28 @@ pub async fn upload_file(
     // 参数验证
     if command.file_data.is_empty() {
         tracing::error!("文件上传失败: 文件数据为空");
-        return Ok(CommandResponse::error("File data cannot be empty".to_string()));
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileData));
     }
 
     if command.original_name.trim().is_empty() {
         tracing::error!("文件上传失败: 文件名为空");
-        return Ok(CommandResponse::error("Original name cannot be empty".to_string()));
+        return Ok(CommandResponse::invalid(ValidationError::EmptyOriginalName));
     }
 
+    telemetry::record_feature(Feature::Upload);
+
     // 获取服务实例
     tracing::debug!("获取文件管理服务实例");
-    let service = service.lock().await;
+    let service = lock_service_mut!(service);
     
     // 构建请求
     let request = UploadRequest {
         file_data: command.file_data,
         original_name: command.original_name.clone(),
         directory_id: command.directory_id.clone(),
+        conflict_policy: command.conflict_policy,
+        organize_by_capture_date: command.organize_by_capture_date,
+        last_modified: command.last_modified,
     };
 
     tracing::debug!("调用文件管理服务上传文件");
@@ -146,6 +692,7 @@ pub async fn upload_file(
         Ok(response) => {
             tracing::info!("文件上传成功: 文件ID={}, 文件名={}, 大小={} bytes", 
                 response.file_id, response.file_name, response.file_size);
+            emit_upload_completed(&app, vec![response.clone()]);
         }
         Err(error) => {
             tracing::error!("文件上传失败: {}", error);
@@ -155,6 +702,14 @@ pub async fn upload_file(
     Ok(CommandResponse::from(result))
 }
 
+/// 发送上传完成事件，因冲突策略跳过的文件不计入
+fn emit_upload_completed(app: &AppHandle, files: Vec<UploadResponse>) {
+    let files: Vec<UploadResponse> = files.into_iter().filter(|file| !file.skipped).collect();
+    if !files.is_empty() {
+        events::emit(app, &UploadCompleted { files });
+    }
+}
+
 /// 创建目录命令
 /// 
 /// 在指定父目录下创建新目录
@@ -165,15 +720,15 @@ pub async fn create_directory(
 ) -> std::result::Result<CommandResponse<CreateDirectoryResponse>, String> {
     // 参数验证
     if command.name.trim().is_empty() {
-        return Ok(CommandResponse::error("Directory name cannot be empty".to_string()));
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDirectoryName));
     }
 
     // 验证目录名不包含非法字符
     if command.name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|']) {
-        return Ok(CommandResponse::error("Directory name contains invalid characters".to_string()));
+        return Ok(CommandResponse::invalid(ValidationError::InvalidDirectoryName));
     }
 
-    let service = service.lock().await;
+    let service = lock_service_mut!(service);
     
     let request = CreateDirectoryRequest {
         name: command.name,
@@ -186,7 +741,7 @@ pub async fn create_directory(
 
 /// 删除文件命令
 /// 
-/// 删除指定的文件
+/// 删除指定的文件；文件在调用方看到之后被修改过时返回 `conflict` 错误码
 #[tauri::command]
 pub async fn delete_file(
     command: DeleteFileCommand,
@@ -194,29 +749,148 @@ pub async fn delete_file(
 ) -> std::result::Result<CommandResponse<()>, String> {
     // 参数验证
     if command.file_id.trim().is_empty() {
-        return Ok(CommandResponse::error("File ID cannot be empty".to_string()));
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    if command.expected_updated_at.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::MissingRevision));
     }
 
-    let service = service.lock().await;
-    let result = service.delete_file(&command.file_id).await;
+    let service = lock_service_mut!(service);
+    let result = match service.check_file_revision(&command.file_id, &command.expected_updated_at).await {
+        Ok(()) => service.delete_file(&command.file_id).await,
+        Err(e) => Err(e),
+    };
     Ok(CommandResponse::from(result))
 }
 
 /// 删除目录命令
 /// 
-/// 递归删除指定目录及其所有内容
+/// 检查目录能否删除后立即返回任务句柄，在后台递归删除目录及其所有内容；
+/// 进度通过 `directory-delete-progress` 事件发送，可用 `cancel_directory_delete` 取消。
+/// 目录在调用方看到之后被修改过时返回 `conflict` 错误码。
+/// `dry_run` 为 `true` 时不创建任务，返回的句柄中列出要删除的文件和目录，只读模式下也可使用
 #[tauri::command]
 pub async fn delete_directory(
     command: DeleteDirectoryCommand,
+    app: AppHandle,
     service: State<'_, FileManagerState>,
-) -> std::result::Result<CommandResponse<()>, String> {
+) -> std::result::Result<CommandResponse<DirectoryDeleteJob>, String> {
+    // 参数验证
+    if command.directory_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDirectoryId));
+    }
+
+    if command.expected_updated_at.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::MissingRevision));
+    }
+
+    if command.dry_run {
+        let service = lock_service!(service);
+        let result = match service.check_directory_revision(&command.directory_id, &command.expected_updated_at).await {
+            Ok(()) => service.preview_directory_delete(&command.directory_id, command.force).await,
+            Err(e) => Err(e),
+        };
+        let result = result.map(|(counts, changes)| DirectoryDeleteJob {
+            job_id: String::new(),
+            directory_id: counts.directory_id,
+            path: counts.path,
+            total: counts.file_count + counts.directory_count + 1,
+            dry_run: true,
+            changes,
+        });
+        return Ok(CommandResponse::from(result));
+    }
+
+    let state = service.inner().clone();
+    let started_at = chrono::Local::now();
+    let (job_id, result) = {
+        let service = lock_service_mut!(service);
+        let job_id = service.generate_id();
+        let result = match service.check_directory_revision(&command.directory_id, &command.expected_updated_at).await {
+            Ok(()) => service.prepare_directory_delete(&command.directory_id, command.force).await,
+            Err(e) => Err(e),
+        };
+        // 登记到任务队列，应用中途退出时下次启动继续删除
+        if let Ok(counts) = &result {
+            let job = QueuedJob::DirectoryDelete {
+                directory_id: counts.directory_id.clone(),
+                path: counts.path.clone(),
+                started_at: Some(started_at),
+            };
+            service.enqueue_job(&job_id, &job).await;
+        }
+        (job_id, result)
+    };
+
+    let result = result.map(|counts| DirectoryDeleteJob {
+        job_id,
+        directory_id: counts.directory_id,
+        path: counts.path,
+        total: counts.file_count + counts.directory_count + 1,
+        dry_run: false,
+        changes: Vec::new(),
+    });
+    if let Ok(job) = &result {
+        tracing::info!("开始删除目录: {}, 共 {} 项", job.path, job.total);
+        directory_delete::spawn(app, state, job.clone(), started_at);
+    }
+    Ok(CommandResponse::from(result))
+}
+
+/// 取消目录删除命令
+///
+/// 任务在当前批次完成后停止，已删除的文件不恢复；返回任务是否仍在运行
+#[tauri::command]
+pub async fn cancel_directory_delete(job_id: String) -> std::result::Result<CommandResponse<bool>, String> {
+    Ok(CommandResponse::success(directory_delete::cancel(&job_id)))
+}
+
+/// 获取目录内容统计命令
+///
+/// 返回目录子树中的文件数和子目录数，用于删除前确认
+#[tauri::command]
+pub async fn get_directory_item_counts(
+    directory_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<DirectoryItemCounts>, String> {
+    if directory_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDirectoryId));
+    }
+
+    let service = lock_service!(service);
+    let result = service.get_directory_item_counts(&directory_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 移动目录命令
+/// 
+/// 将目录及其子目录移动到新的父目录下；目录在调用方看到之后被修改过时返回 `conflict` 错误码
+#[tauri::command]
+pub async fn move_directory(
+    command: MoveDirectoryCommand,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<MoveDirectoryResponse>, String> {
     // 参数验证
     if command.directory_id.trim().is_empty() {
-        return Ok(CommandResponse::error("Directory ID cannot be empty".to_string()));
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDirectoryId));
+    }
+
+    if command.expected_updated_at.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::MissingRevision));
+    }
+
+    let service = lock_service_mut!(service);
+    if let Err(e) = service.check_directory_revision(&command.directory_id, &command.expected_updated_at).await {
+        return Ok(CommandResponse::from_error(&e));
     }
+    
+    let request = MoveDirectoryRequest {
+        directory_id: command.directory_id,
+        new_parent_id: command.new_parent_id,
+    };
 
-    let service = service.lock().await;
-    let result = service.delete_directory(&command.directory_id).await;
+    let result = service.move_directory(request).await;
     Ok(CommandResponse::from(result))
 }
 
@@ -227,14 +901,14 @@ pub async fn delete_directory(
 pub async fn get_directory_tree(
     service: State<'_, FileManagerState>,
 ) -> std::result::Result<CommandResponse<Vec<DirectoryTreeNode>>, String> {
-    let service = service.lock().await;
+    let service = lock_service!(service);
     let result = service.get_directory_tree().await;
     Ok(CommandResponse::from(result))
 }
 
 /// 获取目录中的文件列表命令
 /// 
-/// 返回指定目录中的所有文件
+/// 返回指定目录中的所有文件，超出响应大小上限时分页返回
 #[tauri::command]
 pub async fn get_directory_files(
     command: GetDirectoryFilesCommand,
@@ -242,12 +916,15 @@ pub async fn get_directory_files(
 ) -> std::result::Result<CommandResponse<Vec<FileListItem>>, String> {
     // 参数验证
     if command.directory_id.trim().is_empty() {
-        return Ok(CommandResponse::error("Directory ID cannot be empty".to_string()));
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDirectoryId));
     }
 
-    let service = service.lock().await;
-    let result = service.get_files_in_directory(&command.directory_id).await;
-    Ok(CommandResponse::from(result))
+    let service = lock_service!(service);
+    let result = service.get_files_in_directory(&command.directory_id).await.map(|files| match command.archived {
+        Some(archived) => files.into_iter().filter(|file| file.archived == archived).collect(),
+        None => files,
+    });
+    Ok(CommandResponse::listing(result))
 }
 
 /// 获取文件信息命令
@@ -260,45 +937,50 @@ pub async fn get_file_info(
 ) -> std::result::Result<CommandResponse<Option<FileListItem>>, String> {
     // 参数验证
     if command.file_id.trim().is_empty() {
-        return Ok(CommandResponse::error("File ID cannot be empty".to_string()));
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
     }
 
-    let service = service.lock().await;
+    let service = lock_service!(service);
     let result = service.get_file_info(&command.file_id).await;
     Ok(CommandResponse::from(result))
 }
 
 /// 批量上传文件命令
 /// 
-/// 支持一次上传多个文件
+/// 支持一次上传多个文件；单个文件失败不影响其他文件，按请求顺序返回每个文件的结果，
+/// 前端据此标出失败的文件并重试
 #[tauri::command]
 pub async fn upload_multiple_files(
     files: Vec<UploadFileCommand>,
+    app: AppHandle,
     service: State<'_, FileManagerState>,
-) -> std::result::Result<CommandResponse<Vec<UploadResponse>>, String> {
+) -> std::result::Result<CommandResponse<Vec<UploadItemResult>>, String> {
     // 参数验证
     if files.is_empty() {
-        return Ok(CommandResponse::error("No files to upload".to_string()));
+        return Ok(CommandResponse::invalid(ValidationError::NoFilesToUpload));
     }
 
-    if files.len() > 50 {
-        return Ok(CommandResponse::error("Too many files, maximum 50 files per batch".to_string()));
+    if files.len() > MAX_BATCH_UPLOAD_FILES {
+        return Ok(CommandResponse::invalid(ValidationError::TooManyFiles { max: MAX_BATCH_UPLOAD_FILES }));
     }
 
-    let service = service.lock().await;
-    let mut results = Vec::new();
-    let mut errors = Vec::new();
+    telemetry::record_feature(Feature::BatchUpload);
+    let service = lock_service_mut!(service);
+    let mut results = Vec::with_capacity(files.len());
+    let mut uploaded = Vec::new();
 
     // 逐个上传文件
     for (index, file_command) in files.into_iter().enumerate() {
+        let original_name = file_command.original_name.clone();
+
         // 验证单个文件
         if file_command.file_data.is_empty() {
-            errors.push(format!("File {} has empty data", index));
+            results.push(UploadItemResult::invalid(index, original_name, ValidationError::EmptyFileData));
             continue;
         }
 
         if file_command.original_name.trim().is_empty() {
-            errors.push(format!("File {} has empty name", index));
+            results.push(UploadItemResult::invalid(index, original_name, ValidationError::EmptyOriginalName));
             continue;
         }
 
@@ -306,81 +988,83 @@ pub async fn upload_multiple_files(
             file_data: file_command.file_data,
             original_name: file_command.original_name,
             directory_id: file_command.directory_id,
+            conflict_policy: file_command.conflict_policy,
+            organize_by_capture_date: file_command.organize_by_capture_date,
+            last_modified: file_command.last_modified,
         };
 
         match service.upload_file(request).await {
-            Ok(response) => results.push(response),
-            Err(error) => errors.push(format!("File {}: {}", index, error)),
+            Ok(response) => {
+                uploaded.push(response.clone());
+                results.push(UploadItemResult::Uploaded { index, original_name, response });
+            }
+            Err(error) => {
+                tracing::warn!("批量上传中的文件上传失败: {}, {}", original_name, error);
+                results.push(UploadItemResult::failed(index, original_name, &error));
+            }
         }
     }
 
-    if !errors.is_empty() {
-        return Ok(CommandResponse::error(format!(
-            "Some files failed to upload: {}",
-            errors.join(", ")
-        )));
-    }
+    // 部分文件失败时已上传的文件仍然保留
+    emit_upload_completed(&app, uploaded);
 
     Ok(CommandResponse::success(results))
 }
 
+/// 将搜索命令的范围参数转换为 [`SearchScope`]
+fn search_scope(
+    scope_directory_id: Option<String>,
+    recursive: Option<bool>,
+) -> std::result::Result<Option<SearchScope>, ValidationError> {
+    match scope_directory_id {
+        Some(directory_id) if directory_id.trim().is_empty() => Err(ValidationError::EmptyDirectoryId),
+        Some(directory_id) => Ok(Some(SearchScope { directory_id, recursive: recursive.unwrap_or(false) })),
+        None => Ok(None),
+    }
+}
+
 /// 搜索文件命令
 /// 
-/// 根据文件名搜索文件
+/// 根据文件名搜索文件；指定 `scope_directory_id` 时只搜索该目录，
+/// `recursive` 为真时同时搜索所有子目录
 #[tauri::command]
 pub async fn search_files(
     query: String,
-    directory_id: Option<String>,
+    scope_directory_id: Option<String>,
+    recursive: Option<bool>,
     service: State<'_, FileManagerState>,
 ) -> std::result::Result<CommandResponse<Vec<FileListItem>>, String> {
     // 参数验证
     if query.trim().is_empty() {
-        return Ok(CommandResponse::error("Search query cannot be empty".to_string()));
+        return Ok(CommandResponse::invalid(ValidationError::EmptySearchQuery));
     }
 
-    if query.len() < 2 {
-        return Ok(CommandResponse::error("Search query must be at least 2 characters".to_string()));
+    if query.len() < MIN_SEARCH_QUERY_LEN {
+        return Ok(CommandResponse::invalid(ValidationError::SearchQueryTooShort { min: MIN_SEARCH_QUERY_LEN }));
     }
 
-    let service = service.lock().await;
-    
-    // 简单实现：获取所有文件然后过滤
-    // 在实际应用中，应该在数据库层面实现搜索
-    let all_files = if let Some(dir_id) = directory_id {
-        service.get_files_in_directory(&dir_id).await
-    } else {
-        // 获取所有目录的文件（这里需要改进）
-        let tree = service.get_directory_tree().await?;
-        let mut all_files = Vec::new();
-        for node in tree {
-            if let Ok(files) = service.get_files_in_directory(&node.id).await {
-                all_files.extend(files);
-            }
-        }
-        Ok(all_files)
+    let scope = match search_scope(scope_directory_id, recursive) {
+        Ok(scope) => scope,
+        Err(error) => return Ok(CommandResponse::invalid(error)),
     };
 
-    match all_files {
-        Ok(files) => {
-            let query_lower = query.to_lowercase();
-            let filtered_files: Vec<FileListItem> = files
-                .into_iter()
-                .filter(|file| {
-                    file.name.to_lowercase().contains(&query_lower) ||
-                    file.original_name.to_lowercase().contains(&query_lower)
-                })
-                .collect();
-            
-            Ok(CommandResponse::success(filtered_files))
-        }
-        Err(error) => Ok(CommandResponse::error(error.to_string())),
+    telemetry::record_feature(Feature::Search);
+    let service = lock_service!(service);
+
+    // 搜索历史只用于输入建议，记录失败不影响搜索
+    if let Err(e) = service.record_search(&query).await {
+        tracing::warn!("记录搜索历史失败: {}", e);
     }
+    
+    let result = service.search_files(&query, scope.as_ref()).await;
+    Ok(CommandResponse::listing(result))
 }
 
 /// 获取存储统计信息命令
 /// 
 /// 返回存储空间使用情况
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct StorageStats {
     pub total_files: usize,
     pub total_directories: usize,
@@ -393,7 +1077,7 @@ pub struct StorageStats {
 pub async fn get_storage_stats(
     service: State<'_, FileManagerState>,
 ) -> std::result::Result<CommandResponse<StorageStats>, String> {
-    let service = service.lock().await;
+    let service = lock_service!(service);
     
     // 获取目录树统计
     let directories = service.get_directory_tree().await
@@ -444,10 +1128,10 @@ pub async fn validate_file_type(
     service: State<'_, FileManagerState>,
 ) -> std::result::Result<CommandResponse<bool>, String> {
     if filename.trim().is_empty() {
-        return Ok(CommandResponse::error("Filename cannot be empty".to_string()));
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileName));
     }
 
-    let service = service.lock().await;
+    let service = lock_service!(service);
     
     // 这里需要访问配置，但我们的服务结构需要调整
     // 暂时返回一个简单的验证
@@ -469,52 +1153,3749 @@ pub async fn validate_file_type(
 
 /// 读取文件内容命令
 /// 
-/// 读取指定文件的二进制内容，用于预览等功能
+/// 读取指定文件的二进制内容，用于预览等功能。
+/// 内容以原始字节返回，不经过 JSON 序列化；出错时返回带错误码的 [`CommandError`]，
+/// 文件超出内联读取上限时错误码为 `file_size_exceeded`，前端应改用 `get_file_asset_path`
 #[tauri::command]
 pub async fn read_file_content(
     command: ReadFileContentCommand,
     service: State<'_, FileManagerState>,
-) -> std::result::Result<CommandResponse<Vec<u8>>, String> {
+) -> std::result::Result<tauri::ipc::Response, CommandError> {
     tracing::info!("读取文件内容: file_id={}", command.file_id);
     
     // 参数验证
     if command.file_id.trim().is_empty() {
-        return Ok(CommandResponse::error("File ID cannot be empty".to_string()));
+        return Err(CommandError::invalid(ValidationError::EmptyFileId));
     }
     
-    let service = service.lock().await;
-    let result = service.read_file_content(&command.file_id).await;
-    
-    match &result {
+    let service = service.lock().await.map_err(|e| CommandError::from_error(&e))?;
+    match service.read_file_content(&command.file_id).await {
         Ok(content) => {
             tracing::info!("文件内容读取成功: file_id={}, size={} bytes", command.file_id, content.len());
+            Ok(tauri::ipc::Response::new(content))
         }
         Err(e) => {
             tracing::error!("文件内容读取失败: file_id={}, error={:?}", command.file_id, e);
+            Err(CommandError::from_error(&e))
         }
     }
-    
-    Ok(result.into())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// 获取文件存储路径命令
+///
+/// 返回的绝对路径可通过前端的 `convertFileSrc` 转换为 asset 协议地址，用于加载大文件；
+/// 链接文件等存储目录以外的路径逐个加入 asset 协议的允许范围；运行时为泛型参数，以便在模拟运行时中测试
+#[tauri::command]
+pub async fn get_file_asset_path<R: Runtime>(
+    app: AppHandle<R>,
+    file_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<String>, String> {
+    // 参数验证
+    if file_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
 
-    #[test]
-    fn test_command_response_success() {
-        let response = CommandResponse::success("test data".to_string());
-        assert!(response.success);
-        assert_eq!(response.data, Some("test data".to_string()));
-        assert!(response.error.is_none());
+    let service = lock_service!(service);
+    let result = service.get_file_asset_path(&file_id).await.and_then(|path| {
+        let scope = app.asset_protocol_scope();
+        if !scope.is_allowed(&path) {
+            scope.allow_file(&path)
+                .map_err(|e| FileManagerError::general_error(format!("Failed to allow file in asset scope: {}", e)))?;
+        }
+        Ok(path.to_string_lossy().into_owned())
+    });
+    Ok(CommandResponse::from(result))
+}
+
+/// 重新关联文件命令参数
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RelinkFileCommand {
+    pub file_id: String,
+    /// 找到的文件所在的本地路径
+    pub new_path: String,
+}
+
+/// 扫描缺失文件命令
+///
+/// 将存储文件已丢失的文件记录标记为损坏
+#[tauri::command]
+pub async fn scan_missing_files(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<MissingFileScanReport>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.scan_missing_files().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 按拍摄位置搜索文件命令
+///
+/// 返回 EXIF 拍摄位置位于经纬度范围内的文件，供地图视图使用
+#[tauri::command]
+pub async fn search_by_location(
+    bbox: GeoBoundingBox,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<FileListItem>>, String> {
+    // 参数验证
+    if !bbox.is_valid() {
+        return Ok(CommandResponse::invalid(ValidationError::InvalidBoundingBox));
     }
 
-    #[test]
-    fn test_command_response_error() {
-        let response: CommandResponse<String> = CommandResponse::error("test error".to_string());
-        assert!(!response.success);
-        assert!(response.data.is_none());
-        assert_eq!(response.error, Some("test error".to_string()));
+    let service = lock_service!(service);
+    let result = service.search_by_location(&bbox).await;
+    Ok(CommandResponse::listing(result))
+}
+
+/// 获取损坏文件列表命令
+#[tauri::command]
+pub async fn get_broken_files(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<FileListItem>>, String> {
+    let service = lock_service!(service);
+    let result = service.get_broken_files().await;
+    Ok(CommandResponse::listing(result))
+}
+
+/// 重新关联文件命令
+///
+/// 使用指定路径下的文件替换损坏文件的存储内容；链接文件改为指向新的原始文件位置
+#[tauri::command]
+pub async fn relink_file(
+    command: RelinkFileCommand,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<FileListItem>, String> {
+    // 参数验证
+    if command.file_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    if command.new_path.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyNewPath));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.relink_file(&command.file_id, std::path::Path::new(&command.new_path)).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 清除损坏文件命令
+///
+/// 删除所有标记为损坏的文件记录，返回清除的数量
+#[tauri::command]
+pub async fn purge_broken_files(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<usize>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.purge_broken_files().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 批量更新标签命令
+///
+/// 在一个事务中为多个文件添加和移除标签
+#[tauri::command]
+pub async fn update_tags_bulk(
+    request: BulkTagRequest,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<BulkTagResponse>, String> {
+    // 参数验证
+    if request.file_ids.is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileIds));
+    }
+
+    if request.add_tags.is_empty() && request.remove_tags.is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::NoTagChanges));
+    }
+
+    telemetry::record_feature(Feature::BulkTag);
+    let service = lock_service_mut!(service);
+    let result = service.update_tags_bulk(request).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取文件标签命令
+#[tauri::command]
+pub async fn get_file_tags(
+    file_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<String>>, String> {
+    if file_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    let service = lock_service!(service);
+    let result = service.get_file_tags(&file_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取缩略图命令
+///
+/// 返回指定尺寸档位的缩略图，`pending` 为真时表示更合适的尺寸正在后台生成；
+/// 传入浏览位置 `context` 时，同时在后台预取前后相邻文件的同尺寸缩略图
+#[tauri::command]
+pub async fn get_thumbnail(
+    file_id: String,
+    size: ThumbnailSize,
+    context: Option<PrefetchContext>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ThumbnailResponse>, String> {
+    if file_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    let service = lock_service!(service);
+    let result = service.get_thumbnail(&file_id, size).await;
+
+    // 预取失败不影响本次请求
+    if let Some(context) = context {
+        if let Err(e) = service.prefetch_thumbnails(&context, size).await {
+            tracing::warn!("预取缩略图失败: {}", e);
+        }
+    }
+    Ok(CommandResponse::from(result))
+}
+
+/// 比较图像命令
+///
+/// 返回两张图像的差异热力图、像素差异数和 SSIM
+#[tauri::command]
+pub async fn compare_images(
+    file_id_a: String,
+    file_id_b: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ImageComparison>, String> {
+    if file_id_a.trim().is_empty() || file_id_b.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    telemetry::record_feature(Feature::CompareImages);
+    let service = lock_service!(service);
+    let result = service.compare_images(&file_id_a, &file_id_b).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 查找相似图像命令
+///
+/// 按感知哈希的汉明距离查找相似图像，`max_distance` 默认为 10（0 ~ 64）
+#[tauri::command]
+pub async fn find_similar_images(
+    file_id: String,
+    max_distance: Option<u32>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<SimilarImage>>, String> {
+    // 参数验证
+    if file_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    let max_distance = max_distance.unwrap_or(DEFAULT_MAX_DISTANCE);
+    if max_distance > HASH_BITS {
+        return Ok(CommandResponse::invalid(ValidationError::MaxDistanceTooLarge { max: HASH_BITS }));
+    }
+
+    telemetry::record_feature(Feature::SimilarImages);
+    let service = lock_service!(service);
+    let result = service.find_similar_images(&file_id, max_distance).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 从 URL 导入文件命令
+///
+/// 下载远程文件后按普通上传流程保存，并记录来源地址；运行时为泛型参数，以便在模拟运行时中测试
+#[tauri::command]
+pub async fn import_from_url<R: Runtime>(
+    url: String,
+    directory_id: Option<String>,
+    tags: Option<Vec<String>>,
+    app: AppHandle<R>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<UploadResponse>, String> {
+    // 参数验证
+    let url = match parse_import_url(&url) {
+        Ok(url) => url,
+        Err(e) => return Ok(CommandResponse::from_error(&e)),
+    };
+
+    telemetry::record_feature(Feature::UrlImport);
+    let (max_size, supported_types) = {
+        let service = lock_service_mut!(service);
+        (service.config().max_download_size(), service.config().supported_file_types.clone())
+    };
+
+    // 下载期间不持有服务锁，避免阻塞其他命令
+    tracing::info!("开始从 URL 下载文件: {}", url);
+    let url_text = url.to_string();
+    let downloaded = download(url, max_size, &supported_types).await;
+
+    let service = lock_service_mut!(service);
+    let result = match downloaded {
+        Ok(downloaded) => service.import_downloaded_file(downloaded, directory_id, tags.unwrap_or_default()).await,
+        Err(e) => Err(e),
+    };
+
+    let notification = match &result {
+        Ok(response) => JobNotification::url_import_finished(&response.original_name),
+        Err(e) => {
+            tracing::error!("URL 导入失败: {}, {}", url_text, e);
+            JobNotification::url_import_failed(&url_text, e)
+        }
+    };
+    notifications::notify(&app, &service, notification).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 添加监视文件夹命令
+#[tauri::command]
+pub async fn add_watched_folder(
+    request: AddWatchedFolderRequest,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<WatchedFolder>, String> {
+    // 参数验证
+    if request.path.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFolderPath));
+    }
+
+    telemetry::record_feature(Feature::WatchedFolder);
+    let service = lock_service_mut!(service);
+    let result = service.add_watched_folder(request).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 移除监视文件夹命令
+#[tauri::command]
+pub async fn remove_watched_folder(
+    folder_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if folder_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFolderId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.remove_watched_folder(&folder_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取监视文件夹列表命令
+#[tauri::command]
+pub async fn get_watched_folders(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<WatchedFolder>>, String> {
+    let service = lock_service!(service);
+    let result = service.get_watched_folders().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 立即扫描监视文件夹命令
+///
+/// 不等待下一次定时扫描，立即导入所有监视文件夹中的新文件；每个文件单独获取服务锁
+#[tauri::command]
+pub async fn scan_watched_folders(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<WatchedImportReport>, String> {
+    let result = watched_folders::import_all(&service).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 导出文件命令
+///
+/// 将选中的文件复制到外部目录，每处理完一个文件发送一次 `export-progress` 事件
+#[tauri::command]
+pub async fn export_files(
+    request: ExportRequest,
+    app: AppHandle,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ExportReport>, String> {
+    // 参数验证
+    if request.file_ids.is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileIds));
+    }
+
+    if request.destination_dir.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDestination));
+    }
+
+    telemetry::record_feature(Feature::Export);
+    let service = lock_service!(service);
+    let result = service.export_files(request, |progress| {
+        events::emit(&app, &progress);
+    }).await;
+
+    let notification = match &result {
+        Ok(report) => JobNotification::export_finished(report),
+        Err(e) => JobNotification::export_failed(e),
+    };
+    notifications::notify(&app, &service, notification).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取库增长时间线命令
+///
+/// 返回指定日期范围内按天或按周统计的新增文件数和字节数
+#[tauri::command]
+pub async fn get_activity_timeline(
+    range: TimelineRange,
+    bucket: Option<TimelineBucket>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<ActivityPoint>>, String> {
+    let service = lock_service!(service);
+    let result = service.get_activity_timeline(range, bucket.unwrap_or_default()).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取存储占用分布命令
+///
+/// 按 MIME 类型、顶层目录和文件年龄分组统计，供磁盘占用树图使用
+#[tauri::command]
+pub async fn get_storage_breakdown(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<StorageBreakdown>, String> {
+    let service = lock_service!(service);
+    let result = service.get_storage_breakdown().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取完整性抽检设置命令
+#[tauri::command]
+pub async fn get_integrity_settings(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<IntegritySettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_integrity_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存完整性抽检设置命令
+#[tauri::command]
+pub async fn set_integrity_settings(
+    settings: IntegritySettings,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if settings.sample_size == 0 {
+        return Ok(CommandResponse::invalid(ValidationError::InvalidSampleSize));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.set_integrity_settings(&settings).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 立即执行完整性抽检命令
+#[tauri::command]
+pub async fn run_integrity_check(
+    sample_size: Option<usize>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<IntegrityReport>, String> {
+    telemetry::record_feature(Feature::IntegrityCheck);
+    let service = lock_service_mut!(service);
+    let result = service.run_integrity_sample(sample_size.unwrap_or(DEFAULT_SAMPLE_SIZE)).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 查找重复文件命令
+#[tauri::command]
+pub async fn find_duplicates(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<DuplicateGroup>>, String> {
+    telemetry::record_feature(Feature::FindDuplicates);
+    let service = lock_service!(service);
+    let result = service.find_duplicates().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 执行重复文件清理计划命令
+///
+/// `dry_run` 为 `true` 时只重新检查计划并列出将要删除或链接的副本和合并的标签，只读模式下也可使用
+#[tauri::command]
+pub async fn resolve_duplicates(
+    plan: DuplicatePlan,
+    dry_run: Option<bool>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ResolveDuplicatesReport>, String> {
+    if dry_run.unwrap_or(false) {
+        let service = lock_service!(service);
+        let result = service.preview_resolve_duplicates(plan).await;
+        return Ok(CommandResponse::from(result));
+    }
+
+    telemetry::record_feature(Feature::ResolveDuplicates);
+    let service = lock_service_mut!(service);
+    let result = service.resolve_duplicates(plan).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 撤销重复文件清理命令
+#[tauri::command]
+pub async fn undo_resolve_duplicates(
+    undo_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if undo_id.is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyUndoId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.undo_resolve_duplicates(&undo_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 截图命令
+///
+/// 截取屏幕或活动窗口，保存为目标目录中的新文件，文件名按截图时间生成
+#[tauri::command]
+pub async fn capture_screenshot(
+    mode: CaptureMode,
+    directory_id: Option<String>,
+    tags: Option<Vec<String>>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<UploadResponse>, String> {
+    telemetry::record_feature(Feature::Screenshot);
+    let result = capture_into_library(&service, mode, directory_id, tags.unwrap_or_default()).await;
+    if let Err(e) = &result {
+        tracing::error!("截图失败: {}", e);
+    }
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取快速截图设置命令
+#[tauri::command]
+pub async fn get_quick_capture_settings(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<QuickCaptureSettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_quick_capture_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存快速截图设置命令
+///
+/// 保存后立即按新设置重新注册全局快捷键
+#[tauri::command]
+pub async fn set_quick_capture_settings(
+    settings: QuickCaptureSettings,
+    app: AppHandle,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if let Err(e) = parse_shortcut(&settings.shortcut) {
+        return Ok(CommandResponse::from_error(&e));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = match service.set_quick_capture_settings(&settings).await {
+        Ok(()) => quick_capture::apply_settings(&app, &settings),
+        Err(e) => Err(e),
+    };
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取通知设置命令
+#[tauri::command]
+pub async fn get_notification_settings(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<NotificationSettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_notification_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存通知设置命令
+#[tauri::command]
+pub async fn set_notification_settings(
+    settings: NotificationSettings,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.set_notification_settings(&settings).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 整理文件命令：移动、修改标签或删除
+#[tauri::command]
+pub async fn triage_file(
+    file_id: String,
+    action: TriageAction,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Option<FileListItem>>, String> {
+    telemetry::record_feature(Feature::Triage);
+    let service = lock_service_mut!(service);
+    let result = service.triage_file(&file_id, action).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取收件箱待整理文件数命令
+#[tauri::command]
+pub async fn get_inbox_count(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<i64>, String> {
+    let service = lock_service!(service);
+    let result = service.get_inbox_count().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取错误信息语言命令
+#[tauri::command]
+pub async fn get_locale(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Locale>, String> {
+    let service = lock_service!(service);
+    let result = service.get_locale().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 设置错误信息语言命令
+///
+/// 保存后立即生效，之后的命令错误使用新语言
+#[tauri::command]
+pub async fn set_locale(
+    locale: Locale,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.set_locale(locale).await;
+    if result.is_ok() {
+        i18n::set_current_locale(locale);
+    }
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取启动耗时报告命令
+///
+/// 供错过 `backend://ready` 事件的窗口查询
+#[tauri::command]
+pub async fn get_startup_report(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<StartupReport>, String> {
+    match service.startup_report() {
+        Some(report) => Ok(CommandResponse::success(report.clone())),
+        None => Ok(CommandResponse::from_error(&FileManagerError::Initializing)),
+    }
+}
+
+/// 获取恢复的任务命令
+///
+/// 返回本次启动时处理的、上次运行被中断的导入、导出、目录删除和打包维护任务，
+/// 恢复在启动完成后于后台进行，尚未处理完的任务不在结果中
+#[tauri::command]
+pub async fn get_recovered_jobs() -> std::result::Result<CommandResponse<Vec<RecoveredJob>>, String> {
+    Ok(CommandResponse::success(job_queue::recovered_jobs()))
+}
+
+/// 获取资料库概要命令
+///
+/// 供窗口标题、托盘提示等使用，计数来自一次聚合查询；变化时后台发送 `library-summary` 事件
+#[tauri::command]
+pub async fn get_library_summary(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<LibrarySummary>, String> {
+    let result = library_summary::load(&service).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 设置资料库名称命令
+///
+/// 名称为空时恢复默认名称，返回生效的名称
+#[tauri::command]
+pub async fn set_library_name(
+    name: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<String>, String> {
+    let name = match library_summary::normalize_library_name(&name) {
+        Ok(name) => name,
+        Err(error) => return Ok(CommandResponse::from_error(&error)),
+    };
+    let service = lock_service_mut!(service);
+    let result = match service.set_library_name(name.as_deref()).await {
+        Ok(()) => service.get_library_name().await,
+        Err(e) => Err(e),
+    };
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取启动任务状态命令
+///
+/// 不需要等待初始化，启动画面中可轮询迁移和恢复任务的进度，补充 `backend://startup-task` 事件
+#[tauri::command]
+pub async fn get_startup_tasks_status(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<StartupTask>>, String> {
+    Ok(CommandResponse::success(service.startup_tasks().snapshot()))
+}
+
+/// 获取后端服务状态命令
+///
+/// 不需要等待初始化，前端可在启动画面中轮询
+#[tauri::command]
+pub async fn get_backend_status(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<BackendStatus>, String> {
+    Ok(CommandResponse::success(service.status()))
+}
+
+/// 获取使用统计设置命令
+#[tauri::command]
+pub async fn get_telemetry_settings(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<TelemetrySettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_telemetry_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存使用统计设置命令
+///
+/// 保存后立即生效，关闭时清空尚未发送的计数
+#[tauri::command]
+pub async fn set_telemetry_settings(
+    settings: TelemetrySettings,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if let Some(endpoint) = &settings.endpoint {
+        if let Err(e) = parse_endpoint(endpoint) {
+            return Ok(CommandResponse::from_error(&e));
+        }
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.set_telemetry_settings(&settings).await;
+    if result.is_ok() {
+        telemetry::set_enabled(settings.enabled);
+    }
+    Ok(CommandResponse::from(result))
+}
+
+/// 预览使用统计命令
+///
+/// 返回下次将要发送的完整内容
+#[tauri::command]
+pub async fn get_telemetry_preview(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<TelemetryPreview>, String> {
+    let service = lock_service!(service);
+    let result = service.get_telemetry_settings().await.map(|settings| TelemetryPreview {
+        settings,
+        batch: telemetry::snapshot(),
+    });
+    Ok(CommandResponse::from(result))
+}
+
+/// 查询资料库是否已设置密码命令
+#[tauri::command]
+pub async fn is_library_encrypted(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<bool>, String> {
+    let service = lock_service!(service);
+    Ok(CommandResponse::success(service.is_library_encrypted()))
+}
+
+/// 设置资料库密码命令
+///
+/// 加密目录数据库，之后每次启动都需要输入密码
+#[tauri::command]
+pub async fn set_library_password(
+    password: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if password.chars().count() < MIN_LIBRARY_PASSWORD_LEN {
+        return Ok(CommandResponse::invalid(ValidationError::PasswordTooShort { min: MIN_LIBRARY_PASSWORD_LEN }));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.set_library_password(&password).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 修改资料库密码命令
+#[tauri::command]
+pub async fn change_library_password(
+    old_password: String,
+    new_password: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if new_password.chars().count() < MIN_LIBRARY_PASSWORD_LEN {
+        return Ok(CommandResponse::invalid(ValidationError::PasswordTooShort { min: MIN_LIBRARY_PASSWORD_LEN }));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.change_library_password(&old_password, &new_password).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 解锁资料库命令
+///
+/// 启动时资料库已加密的情况下提交密码，密码正确后继续初始化
+#[tauri::command]
+pub async fn unlock_library(
+    password: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    let result = service.unlock(password).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 设置文件评分和备注命令
+///
+/// `rating` 为 0-5，`rating` 或 `notes` 为空时清除
+#[tauri::command]
+pub async fn set_file_annotations(
+    file_id: String,
+    rating: Option<u8>,
+    notes: Option<String>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<FileListItem>, String> {
+    if file_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    if rating.is_some_and(|rating| rating > MAX_RATING) {
+        return Ok(CommandResponse::invalid(ValidationError::InvalidRating { max: MAX_RATING }));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.set_file_annotations(&file_id, rating, notes).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 读取 XMP / JSON 附属文件，将其中的标签、评分和备注应用到文件
+#[tauri::command]
+pub async fn import_sidecar(
+    file_id: String,
+    path: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<FileListItem>, String> {
+    if file_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    if path.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptySidecarPath));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.import_sidecar(&file_id, std::path::Path::new(&path)).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 导出元数据命令
+///
+/// 把标签、评分、备注和标题写入 `destination` 处的 JSON 文件；范围参数与搜索命令相同，未指定时导出所有文件
+#[tauri::command]
+pub async fn export_metadata(
+    destination: String,
+    scope_directory_id: Option<String>,
+    recursive: Option<bool>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<MetadataExportReport>, String> {
+    if destination.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyMetadataPath));
+    }
+
+    let scope = match search_scope(scope_directory_id, recursive) {
+        Ok(scope) => scope,
+        Err(error) => return Ok(CommandResponse::invalid(error)),
+    };
+
+    let service = lock_service!(service);
+    let result = service.export_metadata(scope.as_ref(), std::path::Path::new(&destination)).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 导入元数据命令
+///
+/// `match_by_hash` 为真时按内容哈希匹配库中文件，否则按目录路径和原始文件名匹配
+#[tauri::command]
+pub async fn import_metadata(
+    path: String,
+    match_by_hash: bool,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<MetadataImportReport>, String> {
+    if path.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyMetadataPath));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.import_metadata(std::path::Path::new(&path), match_by_hash).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取文件类型策略命令
+#[tauri::command]
+pub async fn get_file_type_policy(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<FileTypePolicy>, String> {
+    let service = lock_service!(service);
+    let result = service.get_file_type_policy().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存文件类型策略命令
+///
+/// 保存后立即生效，返回规范化后的策略
+#[tauri::command]
+pub async fn set_file_type_policy(
+    policy: FileTypePolicy,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<FileTypePolicy>, String> {
+    let mut service = lock_service_mut!(service);
+    let result = service.set_file_type_policy(policy).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取 ID 生成策略命令
+#[tauri::command]
+pub async fn get_id_strategy(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<IdStrategy>, String> {
+    let service = lock_service!(service);
+    let result = service.get_id_strategy().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存 ID 生成策略命令
+///
+/// 只影响之后创建的记录，已有记录的 ID 不变
+#[tauri::command]
+pub async fn set_id_strategy(
+    strategy: IdStrategy,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<IdStrategy>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.set_id_strategy(strategy).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取按文件类别的大小上限命令
+#[tauri::command]
+pub async fn get_size_limits(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<SizeLimits>, String> {
+    let service = lock_service!(service);
+    let result = service.get_size_limits().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存按文件类别的大小上限命令
+///
+/// 保存后上传、导入和替换文件内容立即使用新的上限
+#[tauri::command]
+pub async fn set_size_limits(
+    limits: SizeLimits,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<SizeLimits>, String> {
+    let mut service = lock_service_mut!(service);
+    let result = service.set_size_limits(limits).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取隔离区设置命令
+#[tauri::command]
+pub async fn get_quarantine_settings(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<QuarantineSettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_quarantine_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存隔离区设置命令
+#[tauri::command]
+pub async fn set_quarantine_settings(
+    settings: QuarantineSettings,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.set_quarantine_settings(&settings).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取隔离区文件列表命令
+#[tauri::command]
+pub async fn get_quarantined_files(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<QuarantinedFile>>, String> {
+    let service = lock_service!(service);
+    let result = service.get_quarantined_files().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 批准隔离区文件命令
+///
+/// 跳过大小和类型检查导入到库中，`directory_id` 为空时使用上传时的目标目录
+#[tauri::command]
+pub async fn approve_quarantined_file(
+    id: String,
+    directory_id: Option<String>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<UploadResponse>, String> {
+    if id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyQuarantineId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.approve_quarantined_file(&id, directory_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 丢弃隔离区文件命令
+#[tauri::command]
+pub async fn discard_quarantined_file(
+    id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    if id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyQuarantineId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.discard_quarantined_file(&id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取回收站设置命令
+#[tauri::command]
+pub async fn get_trash_settings(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<TrashSettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_trash_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存回收站设置命令
+#[tauri::command]
+pub async fn set_trash_settings(
+    settings: TrashSettings,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.set_trash_settings(&settings).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取回收站文件列表命令
+#[tauri::command]
+pub async fn get_trashed_files(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<TrashedFile>>, String> {
+    let service = lock_service!(service);
+    let result = service.get_trashed_files().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 从回收站恢复文件命令
+///
+/// `directory_id` 为空时恢复到删除前所在的目录
+#[tauri::command]
+pub async fn restore_trashed_file(
+    id: String,
+    directory_id: Option<String>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<UploadResponse>, String> {
+    if id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyTrashId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.restore_trashed_file(&id, directory_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 永久删除回收站文件命令
+#[tauri::command]
+pub async fn purge_trashed_file(
+    id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    if id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyTrashId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.purge_trashed_file(&id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 立即按保留策略清理回收站命令
+#[tauri::command]
+pub async fn apply_trash_retention(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<TrashPurgeReport>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.apply_trash_retention().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取目录默认标签命令
+#[tauri::command]
+pub async fn get_directory_defaults(
+    directory_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<DirectoryDefaults>, String> {
+    if directory_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDirectoryId));
+    }
+
+    let service = lock_service!(service);
+    let result = service.get_directory_defaults(&directory_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 设置目录默认标签命令
+///
+/// 之后上传到该目录的文件自动添加这些标签，`inherit` 为真时同时应用到所有子目录；
+/// 标签为空且不继承时清除设置
+#[tauri::command]
+pub async fn set_directory_defaults(
+    directory_id: String,
+    tags: Vec<String>,
+    inherit: bool,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<DirectoryDefaults>, String> {
+    if directory_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDirectoryId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.set_directory_defaults(&directory_id, &tags, inherit).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取搜索建议命令
+///
+/// 供输入即搜索的搜索框使用，按搜索历史、标签名、文件名中的词的顺序返回以 `prefix` 开头的建议；
+/// 前缀为空时返回最近的搜索和常用标签
+#[tauri::command]
+pub async fn get_search_suggestions(
+    prefix: String,
+    limit: Option<usize>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<SearchSuggestion>>, String> {
+    let limit = limit.unwrap_or(DEFAULT_SUGGESTION_LIMIT).clamp(1, MAX_SUGGESTION_LIMIT);
+
+    let service = lock_service!(service);
+    let result = service.get_search_suggestions(&prefix, limit).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取搜索历史命令
+#[tauri::command]
+pub async fn get_search_history(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<SearchHistoryEntry>>, String> {
+    let service = lock_service!(service);
+    let result = service.get_search_history().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 清空搜索历史命令
+#[tauri::command]
+pub async fn clear_search_history(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.clear_search_history().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 备份资料库命令
+///
+/// 在 `destination` 下新建备份目录，写入数据库快照、存储文件和清单；
+/// 增量备份以该目录中最近一次备份为基准，完成后按设置发送系统通知
+#[tauri::command]
+pub async fn backup_library(
+    scope: BackupScope,
+    destination: String,
+    app: AppHandle,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<BackupReport>, String> {
+    if destination.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDestination));
+    }
+
+    telemetry::record_feature(Feature::Backup);
+    let service = lock_service!(service);
+    let result = service.backup_library(scope, &destination).await;
+
+    let notification = match &result {
+        Ok(report) => JobNotification::backup_finished(report),
+        Err(e) => JobNotification::backup_failed(e),
+    };
+    notifications::notify(&app, &service, notification).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 从备份恢复资料库命令
+///
+/// 先按清单校验备份，有缺失或损坏的文件时返回 `applied: false` 的报告，当前库不做修改
+#[tauri::command]
+pub async fn restore_library(
+    backup_path: String,
+    mode: RestoreMode,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<RestoreReport>, String> {
+    if backup_path.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyBackupPath));
+    }
+
+    telemetry::record_feature(Feature::Restore);
+    let service = lock_service_mut!(service);
+    let result = service.restore_library(&backup_path, mode).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取存储布局设置命令
+#[tauri::command]
+pub async fn get_storage_layout(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<StorageLayoutSettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_storage_layout_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 切换存储布局命令
+///
+/// 新上传的文件立即使用新布局；已有文件在后台分批迁移，
+/// 进度通过 `storage-migration-progress` 事件发送
+#[tauri::command]
+pub async fn set_storage_layout(
+    layout: StorageLayout,
+    app: AppHandle,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<StorageLayoutSettings>, String> {
+    let state = service.inner().clone();
+    let result = {
+        let mut service = lock_service_mut!(service);
+        service.set_storage_layout(layout).await
+    };
+
+    if matches!(result, Ok(settings) if settings.migration_pending) {
+        storage_layout::spawn_migration(app, state);
+    }
+    Ok(CommandResponse::from(result))
+}
+
+/// 链接库外文件命令
+///
+/// 只记录原始文件的位置，不复制到存储区
+#[tauri::command]
+pub async fn link_file(
+    path: String,
+    directory_id: Option<String>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<UploadResponse>, String> {
+    if path.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyLinkPath));
+    }
+
+    telemetry::record_feature(Feature::LinkFile);
+    let service = lock_service_mut!(service);
+    let result = service.link_file(std::path::Path::new(&path), directory_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 检查链接文件命令
+///
+/// 原始文件已不存在的链接文件标记为损坏，重新出现时恢复为正常
+#[tauri::command]
+pub async fn check_linked_files(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<MissingFileScanReport>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.check_linked_files().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 将链接文件转换为托管文件命令
+///
+/// 把原始文件复制到存储区，库外的原始文件保持不变
+#[tauri::command]
+pub async fn convert_to_managed(
+    file_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<FileListItem>, String> {
+    if file_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.convert_to_managed(&file_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取分页列表命令
+///
+/// 按偏移量获取被截断的列表响应的下一页，`continuation` 为空时表示已是最后一页
+#[tauri::command]
+pub async fn get_listing_page(
+    listing_id: String,
+    offset: usize,
+) -> std::result::Result<CommandResponse<Vec<serde_json::Value>>, String> {
+    if listing_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyListingId));
+    }
+
+    match response_guard::listing_page(&listing_id, offset) {
+        Ok((items, continuation)) => Ok(CommandResponse { continuation, ..CommandResponse::success(items) }),
+        Err(error) => Ok(CommandResponse::from_error(&error)),
+    }
+}
+
+/// 获取响应大小上限设置命令
+#[tauri::command]
+pub async fn get_response_limit(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ResponseLimitSettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_response_limit_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存响应大小上限设置命令
+///
+/// 保存后立即生效，之后的列表响应按新上限分页
+#[tauri::command]
+pub async fn set_response_limit(
+    settings: ResponseLimitSettings,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    if settings.max_response_bytes < MIN_MAX_RESPONSE_BYTES {
+        return Ok(CommandResponse::invalid(ValidationError::ResponseLimitTooSmall { min: MIN_MAX_RESPONSE_BYTES }));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.set_response_limit_settings(&settings).await;
+    if result.is_ok() {
+        response_guard::set_max_response_bytes(settings.max_response_bytes);
+    }
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取事件目录命令
+///
+/// 返回后端会发送的所有事件名、来源和说明
+#[tauri::command]
+pub async fn get_event_catalog() -> std::result::Result<CommandResponse<Vec<EventDescriptor>>, String> {
+    Ok(CommandResponse::success(EVENT_CATALOG.to_vec()))
+}
+
+/// 获取最近事件命令
+///
+/// 返回序号大于 `after_seq` 的事件，供错过事件的窗口补收；不指定时返回日志中的全部事件
+#[tauri::command]
+pub async fn get_recent_events(
+    after_seq: Option<u64>,
+) -> std::result::Result<CommandResponse<Vec<EventRecord>>, String> {
+    Ok(CommandResponse::success(events::events_after(after_seq.unwrap_or(0))))
+}
+
+/// 解析分页参数，返回游标和每页条目数
+fn parse_page_params(cursor: Option<&str>, limit: Option<usize>) -> std::result::Result<(Option<PageCursor>, usize), ValidationError> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(ValidationError::InvalidPageSize { max: MAX_PAGE_SIZE });
+    }
+
+    match cursor.filter(|cursor| !cursor.trim().is_empty()) {
+        Some(cursor) => PageCursor::decode(cursor)
+            .map(|cursor| (Some(cursor), limit))
+            .ok_or(ValidationError::InvalidCursor),
+        None => Ok((None, limit)),
+    }
+}
+
+/// 分页获取目录中的文件命令
+///
+/// 按原始文件名排序，`cursor` 为上一页返回的 `next_cursor`，为空时从第一页开始
+#[tauri::command]
+pub async fn get_directory_files_page(
+    directory_id: String,
+    cursor: Option<String>,
+    limit: Option<usize>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Page<FileListItem>>, String> {
+    if directory_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDirectoryId));
+    }
+
+    let (after, limit) = match parse_page_params(cursor.as_deref(), limit) {
+        Ok(params) => params,
+        Err(error) => return Ok(CommandResponse::invalid(error)),
+    };
+
+    let service = lock_service!(service);
+    let result = service.get_files_in_directory_page(&directory_id, after.as_ref(), limit).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 分页搜索文件命令
+///
+/// 与 `search_files` 匹配规则和搜索范围相同，结果按原始文件名排序分页返回
+#[tauri::command]
+pub async fn search_files_page(
+    query: String,
+    scope_directory_id: Option<String>,
+    recursive: Option<bool>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Page<FileListItem>>, String> {
+    if query.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptySearchQuery));
+    }
+
+    if query.len() < MIN_SEARCH_QUERY_LEN {
+        return Ok(CommandResponse::invalid(ValidationError::SearchQueryTooShort { min: MIN_SEARCH_QUERY_LEN }));
+    }
+
+    let scope = match search_scope(scope_directory_id, recursive) {
+        Ok(scope) => scope,
+        Err(error) => return Ok(CommandResponse::invalid(error)),
+    };
+
+    let (after, limit) = match parse_page_params(cursor.as_deref(), limit) {
+        Ok(params) => params,
+        Err(error) => return Ok(CommandResponse::invalid(error)),
+    };
+
+    let service = lock_service!(service);
+
+    // 只在第一页记录搜索历史和使用统计，翻页不算新的搜索
+    if after.is_none() {
+        telemetry::record_feature(Feature::Search);
+        if let Err(e) = service.record_search(&query).await {
+            tracing::warn!("记录搜索历史失败: {}", e);
+        }
+    }
+
+    let result = service.search_files_page(&query, scope.as_ref(), after.as_ref(), limit).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取最近动态命令
+///
+/// 合并可撤销的操作、后台任务的结束、监视文件夹导入和外部编辑，按时间从新到旧分页；
+/// `cursor` 为上一页返回的 `next_cursor`，为空时从最新的动态开始
+#[tauri::command]
+pub async fn get_activity_feed(
+    cursor: Option<String>,
+    limit: Option<usize>,
+    filter: Option<ActivityFilter>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Page<ActivityEntry>>, String> {
+    let (after, limit) = match parse_page_params(cursor.as_deref(), limit) {
+        Ok(params) => params,
+        Err(error) => return Ok(CommandResponse::invalid(error)),
+    };
+
+    let service = lock_service!(service);
+    let result = service.get_activity_feed(after.as_ref(), limit, &filter.unwrap_or_default()).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取待处理文件列表命令
+///
+/// 返回尚未处理或处理失败的文件，失败的文件附带错误信息
+#[tauri::command]
+pub async fn get_unprocessed_files(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<FileListItem>>, String> {
+    let service = lock_service!(service);
+    let result = service.get_unprocessed_files().await;
+    Ok(CommandResponse::listing(result))
+}
+
+/// 重新处理文件命令
+///
+/// 为尚未处理或处理失败的文件重新生成内容哈希和拍摄位置
+#[tauri::command]
+pub async fn process_pending_files(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ProcessingReport>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.process_pending_files().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 重新索引文件命令
+///
+/// 清除指定范围内文件的缩略图和派生数据后重新处理
+#[tauri::command]
+pub async fn reindex_files(
+    scope: ReindexScope,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ProcessingReport>, String> {
+    match &scope {
+        ReindexScope::Files { file_ids } if file_ids.is_empty() => {
+            return Ok(CommandResponse::invalid(ValidationError::EmptyFileIds));
+        }
+        ReindexScope::Directory { directory_id } if directory_id.trim().is_empty() => {
+            return Ok(CommandResponse::invalid(ValidationError::EmptyDirectoryId));
+        }
+        _ => {}
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.reindex_files(&scope).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取统计快照历史命令
+///
+/// 返回日期范围内每天记录的文件总数、总字节数和按类型的分组，供增长趋势图使用
+#[tauri::command]
+pub async fn get_stats_history(
+    range: TimelineRange,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<StatsSnapshot>>, String> {
+    let service = lock_service!(service);
+    let result = service.get_stats_history(range).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 批量操作命令
+///
+/// 在一个事务中按顺序执行移动、重命名、标签修改和删除，任一操作失败时整批不生效。
+/// `revisions` 为文件 ID 到调用方最后看到的 `updated_at`，移动、重命名和删除的文件必须提供；
+/// 任一文件在调用方看到之后被修改过时整批不执行，返回 `conflict` 错误码。
+/// `dry_run` 为 `true` 时在事务中执行后回滚，返回的结果中列出各项修改
+#[tauri::command]
+pub async fn batch_operations(
+    operations: Vec<BatchOperation>,
+    revisions: Option<HashMap<String, String>>,
+    dry_run: Option<bool>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<BatchReport>, String> {
+    // 参数验证
+    if operations.is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyBatch));
+    }
+
+    if operations.len() > MAX_BATCH_OPERATIONS {
+        return Ok(CommandResponse::invalid(ValidationError::TooManyOperations { max: MAX_BATCH_OPERATIONS }));
+    }
+
+    if operations.iter().any(|operation| operation.file_id().trim().is_empty()) {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    let revisions = revisions.unwrap_or_default();
+    let checked: BTreeMap<&str, &str> = match operations.iter()
+        .filter(|operation| !matches!(operation, BatchOperation::Tag { .. }))
+        .map(|operation| {
+            revisions.get(operation.file_id())
+                .filter(|revision| !revision.trim().is_empty())
+                .map(|revision| (operation.file_id(), revision.as_str()))
+        })
+        .collect::<Option<_>>()
+    {
+        Some(checked) => checked,
+        None => return Ok(CommandResponse::invalid(ValidationError::MissingRevision)),
+    };
+
+    let dry_run = dry_run.unwrap_or(false);
+    if !dry_run {
+        telemetry::record_feature(Feature::BatchOperations);
+    }
+    let service = lock_service_mut!(service);
+    for (file_id, revision) in checked {
+        if let Err(e) = service.check_file_revision(file_id, revision).await {
+            return Ok(CommandResponse::from_error(&e));
+        }
+    }
+    let result = if dry_run {
+        service.preview_batch_operations(operations).await
+    } else {
+        service.batch_operations(operations).await
+    };
+    Ok(CommandResponse::from(result))
+}
+
+/// 撤销批量操作命令
+#[tauri::command]
+pub async fn undo_batch_operations(
+    undo_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if undo_id.is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyUndoId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.undo_batch_operations(&undo_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取外部应用设置命令
+#[tauri::command]
+pub async fn get_open_with(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<OpenWithSettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_open_with_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 设置外部应用命令
+///
+/// 为 MIME 类型（如 `image/png` 或 `image/*`）设置按优先顺序排列的应用，列表为空时移除；返回保存后的设置
+#[tauri::command]
+pub async fn set_open_with(
+    mime_type: String,
+    apps: Vec<ExternalApp>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<OpenWithSettings>, String> {
+    // 参数验证
+    if normalize_mime_pattern(&mime_type).is_err() {
+        return Ok(CommandResponse::invalid(ValidationError::InvalidMimeType));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.set_open_with(&mime_type, apps).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取可打开文件的外部应用命令
+///
+/// 供文件右键菜单的“打开方式”使用，首选应用排在最前
+#[tauri::command]
+pub async fn get_apps_for_file(
+    file_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<ExternalApp>>, String> {
+    // 参数验证
+    if file_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    let service = lock_service!(service);
+    let result = service.get_apps_for_file(&file_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 用外部应用打开文件命令
+///
+/// 未指定应用时使用该文件类型的首选应用，没有配置时由系统默认程序打开；运行时为泛型参数，以便在模拟运行时中测试
+#[tauri::command]
+pub async fn open_file_with<R: Runtime>(
+    app: AppHandle<R>,
+    file_id: String,
+    app_id: Option<String>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if file_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    let resolved = {
+        let service = lock_service!(service);
+        service.resolve_open_with(&file_id, app_id.as_deref()).await
+    };
+    let (path, program) = match resolved {
+        Ok(resolved) => resolved,
+        Err(error) => return Ok(CommandResponse::from_error(&error)),
+    };
+
+    match app.opener().open_path(path.to_string_lossy(), program.as_deref()) {
+        Ok(()) => Ok(CommandResponse::success(())),
+        Err(e) => {
+            tracing::warn!("外部应用打开文件失败: {}, {}", file_id, e);
+            Ok(CommandResponse::error(format!("Failed to open file: {}", e)))
+        }
+    }
+}
+
+/// 在外部应用中编辑文件命令
+///
+/// 把文件复制到临时位置后用外部应用打开，副本被保存时发送 `external-edit-changed` 事件，
+/// 前端确认后调用 `apply_external_edit` 导入为新版本，编辑完成后调用 `end_external_edit`；
+/// 运行时为泛型参数，以便在模拟运行时中测试
+#[tauri::command]
+pub async fn edit_externally<R: Runtime>(
+    app: AppHandle<R>,
+    file_id: String,
+    app_id: Option<String>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ExternalEditSession>, String> {
+    // 参数验证
+    if file_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    let service = lock_service!(service);
+    let (session, program) = match service.start_external_edit(&file_id, app_id.as_deref()).await {
+        Ok(started) => started,
+        Err(error) => return Ok(CommandResponse::from_error(&error)),
+    };
+
+    match app.opener().open_path(session.temp_path.clone(), program.as_deref()) {
+        Ok(()) => Ok(CommandResponse::success(session)),
+        Err(e) => {
+            tracing::warn!("外部应用打开编辑副本失败: {}, {}", file_id, e);
+            if let Err(end_error) = service.end_external_edit(&session.id).await {
+                tracing::warn!("编辑会话结束失败: {}, {}", session.id, end_error);
+            }
+            Ok(CommandResponse::error(format!("Failed to open file: {}", e)))
+        }
+    }
+}
+
+/// 获取进行中的外部编辑会话命令
+#[tauri::command]
+pub async fn get_external_edits() -> std::result::Result<CommandResponse<Vec<ExternalEditSession>>, String> {
+    Ok(CommandResponse::success(external_edit::list_sessions()))
+}
+
+/// 导入外部编辑结果命令
+///
+/// 以编辑副本的当前内容替换原文件并递增版本号
+#[tauri::command]
+pub async fn apply_external_edit(
+    session_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<UploadResponse>, String> {
+    // 参数验证
+    if session_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptySessionId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.apply_external_edit(&session_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 结束外部编辑命令，删除编辑副本，未导入的修改被丢弃
+#[tauri::command]
+pub async fn end_external_edit(
+    session_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if session_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptySessionId));
+    }
+
+    let service = lock_service!(service);
+    let result = service.end_external_edit(&session_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取缩略图生成设置命令
+#[tauri::command]
+pub async fn get_preview_settings(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<PreviewSettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_preview_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存缩略图生成设置命令
+///
+/// 之后生成的缩略图立即使用新设置，已有的缩略图在后台按新设置重新生成
+#[tauri::command]
+pub async fn set_preview_settings(
+    settings: PreviewSettings,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<PreviewSettings>, String> {
+    // 参数验证
+    if !(1..=100).contains(&settings.quality) {
+        return Ok(CommandResponse::invalid(ValidationError::InvalidPreviewQuality));
+    }
+    if !(MIN_PREVIEW_DIMENSION..=MAX_PREVIEW_DIMENSION).contains(&settings.max_dimension) {
+        return Ok(CommandResponse::invalid(ValidationError::InvalidPreviewDimension {
+            min: MIN_PREVIEW_DIMENSION,
+            max: MAX_PREVIEW_DIMENSION,
+        }));
+    }
+
+    let state = service.inner().clone();
+    let result = {
+        let mut service = lock_service_mut!(service);
+        service.set_preview_settings(settings).await
+    };
+
+    if result.is_ok() {
+        thumbnail::spawn_preview_regeneration(state);
+    }
+    Ok(CommandResponse::from(result))
+}
+
+/// 写入图像元数据命令
+///
+/// 更新文件的标题、描述和关键词，并在 JPEG / PNG 文件中以 XMP 内嵌，导出的文件随之携带元数据
+#[tauri::command]
+pub async fn write_image_metadata(
+    file_id: String,
+    fields: ImageMetadataFields,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ImageMetadataResult>, String> {
+    // 参数验证
+    if file_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+    if fields.is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::NoMetadataFields));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.write_image_metadata(&file_id, fields).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 创建工作集命令
+///
+/// 工作集用于跨目录收集文件后一起处理，超过有效期未修改时自动清除
+#[tauri::command]
+pub async fn create_working_set(
+    name: Option<String>,
+    ttl_minutes: Option<u32>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<WorkingSet>, String> {
+    // 参数验证
+    if ttl_minutes.is_some_and(|ttl_minutes| !is_valid_ttl(ttl_minutes)) {
+        return Ok(CommandResponse::invalid(ValidationError::InvalidWorkingSetTtl {
+            max: MAX_WORKING_SET_TTL_MINUTES,
+        }));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.create_working_set(name, ttl_minutes).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取工作集列表命令
+#[tauri::command]
+pub async fn list_working_sets(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<WorkingSet>>, String> {
+    let service = lock_service!(service);
+    let result = service.list_working_sets().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 添加工作集成员命令
+///
+/// 已在工作集中的文件保持原来的顺序
+#[tauri::command]
+pub async fn add_to_working_set(
+    working_set_id: String,
+    file_ids: Vec<String>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<WorkingSet>, String> {
+    // 参数验证
+    if working_set_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyWorkingSetId));
+    }
+    if file_ids.is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileIds));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.update_working_set(&working_set_id, &file_ids, &[]).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 移除工作集成员命令
+#[tauri::command]
+pub async fn remove_from_working_set(
+    working_set_id: String,
+    file_ids: Vec<String>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<WorkingSet>, String> {
+    // 参数验证
+    if working_set_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyWorkingSetId));
+    }
+    if file_ids.is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileIds));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.update_working_set(&working_set_id, &[], &file_ids).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取工作集文件命令
+///
+/// 已删除的文件不列出
+#[tauri::command]
+pub async fn get_working_set_files(
+    working_set_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<FileListItem>>, String> {
+    // 参数验证
+    if working_set_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyWorkingSetId));
+    }
+
+    let service = lock_service!(service);
+    let result = service.get_working_set_files(&working_set_id).await;
+    Ok(CommandResponse::listing(result))
+}
+
+/// 删除工作集命令
+#[tauri::command]
+pub async fn delete_working_set(
+    working_set_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if working_set_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyWorkingSetId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.delete_working_set(&working_set_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 快速切换命令
+///
+/// 供命令面板使用，按 `prefix` 模糊匹配目录、标签、工作集和文件并混合排序
+#[tauri::command]
+pub async fn quick_find(
+    prefix: String,
+    limit: Option<usize>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<QuickFindResult>>, String> {
+    // 参数验证
+    if prefix.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptySearchQuery));
+    }
+    let limit = limit.unwrap_or(DEFAULT_QUICK_FIND_LIMIT).clamp(1, MAX_QUICK_FIND_LIMIT);
+
+    let service = lock_service!(service);
+    let result = service.quick_find(&prefix, limit).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取归档设置命令
+#[tauri::command]
+pub async fn get_archive_settings(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ArchiveSettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_archive_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 设置归档位置命令
+///
+/// 归档位置必须是存储根目录之外的绝对路径，为空时清除设置
+#[tauri::command]
+pub async fn set_archive_settings(
+    settings: ArchiveSettings,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ArchiveSettings>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.set_archive_settings(settings).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 归档文件命令
+///
+/// 将存储文件移到归档位置，文件记录、标签和缩略图保留；访问内容时自动移回
+#[tauri::command]
+pub async fn archive_files(
+    file_ids: Vec<String>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ArchiveReport>, String> {
+    // 参数验证
+    if file_ids.is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileIds));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.archive_files(&file_ids).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 移回归档文件命令
+///
+/// 将已归档文件的存储文件移回主存储，未归档的文件跳过
+#[tauri::command]
+pub async fn restore_archived_files(
+    file_ids: Vec<String>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ArchiveReport>, String> {
+    // 参数验证
+    if file_ids.is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileIds));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.restore_archived_files(&file_ids).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取打包存储设置命令
+#[tauri::command]
+pub async fn get_pack_settings(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<PackSettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_pack_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 设置打包存储命令
+///
+/// 开启后后台任务把不超过大小上限的已索引文件合并到打包文件；关闭后已打包的文件保持打包
+#[tauri::command]
+pub async fn set_pack_settings(
+    settings: PackSettings,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<PackSettings>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.set_pack_settings(settings).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取打包存储使用情况命令
+#[tauri::command]
+pub async fn get_pack_stats(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<PackStats>, String> {
+    let service = lock_service!(service);
+    let result = service.get_pack_stats().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 立即执行打包和压缩命令
+///
+/// 每个文件和打包文件单独获取服务锁
+#[tauri::command]
+pub async fn run_pack_maintenance(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<PackReport>, String> {
+    let result = pack_store::run_maintenance(&service).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 创建目录模板命令
+///
+/// `folders` 为嵌套的文件夹结构，同一层级的文件夹不能重名
+#[tauri::command]
+pub async fn create_directory_template(
+    name: String,
+    folders: Vec<TemplateFolder>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<DirectoryTemplate>, String> {
+    // 参数验证
+    if name.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyTemplateName));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.create_directory_template(&name, folders).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取目录模板列表命令
+#[tauri::command]
+pub async fn list_directory_templates(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<DirectoryTemplate>>, String> {
+    let service = lock_service!(service);
+    let result = service.list_directory_templates().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 修改目录模板命令
+///
+/// 整体替换模板的名称和文件夹结构，已按模板创建的目录不受影响
+#[tauri::command]
+pub async fn update_directory_template(
+    template_id: String,
+    name: String,
+    folders: Vec<TemplateFolder>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<DirectoryTemplate>, String> {
+    // 参数验证
+    if template_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyTemplateId));
+    }
+    if name.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyTemplateName));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.update_directory_template(&template_id, &name, folders).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 删除目录模板命令
+#[tauri::command]
+pub async fn delete_directory_template(
+    template_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if template_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyTemplateId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.delete_directory_template(&template_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 按模板创建目录命令
+///
+/// 在父目录下创建名为 `name` 的目录，再按模板创建其中的子目录；失败时不留下不完整的目录
+#[tauri::command]
+pub async fn create_directory_from_template(
+    template_id: String,
+    parent_id: Option<String>,
+    name: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<TemplateApplication>, String> {
+    // 参数验证
+    if template_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyTemplateId));
+    }
+    if name.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDirectoryName));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.create_directory_from_template(&template_id, parent_id, &name).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 记录文件使用命令
+///
+/// 前端在查看器中打开文件或把文件放置到画板上时调用，放置时传入画板 ID；用外部应用打开时后端自动记录
+#[tauri::command]
+pub async fn record_file_usage(
+    file_id: String,
+    kind: UsageKind,
+    board_id: Option<String>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if file_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.record_file_usage(&file_id, kind, board_id.as_deref()).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取推荐素材命令
+///
+/// 按当前画板和目录推荐可能用到的文件，用于“推荐素材”面板
+#[tauri::command]
+pub async fn get_suggestions(
+    context: SuggestionContext,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<SuggestedFile>>, String> {
+    let service = lock_service!(service);
+    let result = service.get_suggestions(context).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取资源管理器集成状态命令
+///
+/// 返回当前平台是否支持右键菜单，以及“Send to Collaboard”菜单项是否已登记
+#[tauri::command]
+pub async fn get_shell_integration() -> std::result::Result<CommandResponse<ShellIntegrationStatus>, String> {
+    Ok(CommandResponse::success(shell_integration::status()))
+}
+
+/// 开启或关闭右键菜单命令
+///
+/// 在当前用户的注册表中登记或删除“Send to Collaboard”菜单项，仅支持 Windows
+#[tauri::command]
+pub async fn set_context_menu_enabled(
+    enabled: bool,
+) -> std::result::Result<CommandResponse<ShellIntegrationStatus>, String> {
+    let result = tokio::task::spawn_blocking(move || shell_integration::set_context_menu_enabled(enabled))
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|_| shell_integration::status());
+    Ok(CommandResponse::from(result))
+}
+
+/// 导出画板命令
+///
+/// 把前端的画板内容和画板上使用的文件保存为单个 `.cboard` 文件
+#[tauri::command]
+pub async fn export_board(
+    request: ExportBoardRequest,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<BoardExportReport>, String> {
+    // 参数验证
+    if request.name.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyBoardName));
+    }
+
+    if request.destination.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDestination));
+    }
+
+    let service = lock_service!(service);
+    let result = service.export_board(request).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 打开画板文件命令
+///
+/// 读取 `.cboard` 文件，素材按内容哈希对应到资料库中的文件，资料库中没有的导入收件箱
+#[tauri::command]
+pub async fn open_board_file(
+    path: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<OpenedBoard>, String> {
+    // 参数验证
+    if path.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyBoardPath));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.open_board(std::path::Path::new(&path)).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 切换只读模式命令
+///
+/// 退出只读模式时重新获取写入锁，其他进程仍在写入时返回 `read_only` 错误码
+#[tauri::command]
+pub async fn set_read_only(
+    enabled: bool,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Option<ReadOnlyReason>>, String> {
+    let state = service.inner().clone();
+    let mut service = lock_service!(service);
+    let result = state.switch_read_only(&mut service, enabled).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 接管资料库命令
+///
+/// 原持有者的心跳已停止或文件锁已释放时可以接管，原持有者下次心跳时转为只读模式
+#[tauri::command]
+pub async fn take_over_library(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Option<ReadOnlyReason>>, String> {
+    let state = service.inner().clone();
+    let mut service = lock_service!(service);
+    let result = state.take_over(&mut service).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取缩略图缓存占用命令
+#[tauri::command]
+pub async fn get_cache_stats(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<CacheStats>, String> {
+    let service = lock_service!(service);
+    let result = service.get_cache_stats().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 清空缩略图缓存命令
+///
+/// 返回释放的字节数，之后查看文件时重新生成缩略图
+#[tauri::command]
+pub async fn clear_thumbnail_cache(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<u64>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.clear_thumbnail_cache().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取缩略图缓存设置命令
+#[tauri::command]
+pub async fn get_thumbnail_cache_settings(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ThumbnailCacheSettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_thumbnail_cache_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存缩略图缓存设置命令
+///
+/// 新上限低于当前占用时立即淘汰最久未用的缩略图，返回淘汰后的占用
+#[tauri::command]
+pub async fn set_thumbnail_cache_settings(
+    settings: ThumbnailCacheSettings,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<CacheStats>, String> {
+    // 参数验证
+    if settings.max_bytes < MIN_THUMBNAIL_CACHE_BYTES {
+        return Ok(CommandResponse::invalid(ValidationError::ThumbnailCacheTooSmall { min: MIN_THUMBNAIL_CACHE_BYTES }));
+    }
+
+    let mut service = lock_service_mut!(service);
+    let result = service.set_thumbnail_cache_settings(settings).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取低内存模式设置命令
+#[tauri::command]
+pub async fn get_low_memory_settings(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<LowMemorySettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_low_memory_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存低内存模式设置命令
+///
+/// 立即生效：开启时限制缓存、停止预取、预览改为流式加载并减少并行线程，返回淘汰后的缩略图缓存占用
+#[tauri::command]
+pub async fn set_low_memory_settings(
+    settings: LowMemorySettings,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<CacheStats>, String> {
+    let mut service = lock_service_mut!(service);
+    let result = service.set_low_memory_settings(settings).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取导出预设命令
+#[tauri::command]
+pub async fn get_export_presets(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<ExportPreset>>, String> {
+    let service = lock_service!(service);
+    let result = service.get_export_presets().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存导出预设命令
+///
+/// 整体替换已有的预设，ID 为空的预设分配新 ID
+#[tauri::command]
+pub async fn set_export_presets(
+    presets: Vec<ExportPreset>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<ExportPreset>>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.set_export_presets(presets).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 按预设导出图片命令
+///
+/// 按预设缩放、转换格式后导出到外部目录，可选叠加水印，每处理完一个文件发送一次 `export-progress` 事件；
+/// 运行时为泛型参数，以便在模拟运行时中测试
+#[tauri::command]
+pub async fn export_with_preset<R: Runtime>(
+    file_ids: Vec<String>,
+    preset_id: String,
+    destination: String,
+    watermark: Option<Watermark>,
+    app: AppHandle<R>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ExportReport>, String> {
+    // 参数验证
+    if file_ids.is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileIds));
+    }
+
+    if preset_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyPresetId));
+    }
+
+    if destination.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDestination));
+    }
+
+    telemetry::record_feature(Feature::Export);
+    let service = lock_service!(service);
+    let result = service.export_with_preset(&file_ids, &preset_id, &destination, watermark.as_ref(), |progress| {
+        events::emit(&app, &progress);
+    }).await;
+
+    let notification = match &result {
+        Ok(report) => JobNotification::export_finished(report),
+        Err(e) => JobNotification::export_failed(e),
+    };
+    notifications::notify(&app, &service, notification).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 生成校验清单命令
+///
+/// 为选中的文件生成 SHA-256 或 CRC32 校验清单，由前端保存或随交付文件一起发送
+#[tauri::command]
+pub async fn generate_manifest(
+    file_ids: Vec<String>,
+    format: ManifestFormat,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<ChecksumManifest>, String> {
+    // 参数验证
+    if file_ids.is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFileIds));
+    }
+
+    let service = lock_service!(service);
+    let result = service.generate_manifest(&file_ids, format).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 导入文件夹命令
+///
+/// 把本地文件夹中的文件导入资料库；`dry_run` 为 `true` 时只返回导入计划，只读模式下也可使用。
+/// 逐个文件获取服务锁，导入期间其他命令仍可执行
+#[tauri::command]
+pub async fn import_directory(
+    request: ImportDirectoryRequest,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<DirectoryImportReport>, String> {
+    // 参数验证
+    if request.path.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFolderPath));
+    }
+
+    let result = directory_import::import_directory(&service, request).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 收集画板素材命令
+///
+/// 把画板上的素材复制或移动到资料库中的一个目录，便于整体交接
+#[tauri::command]
+pub async fn collect_board_assets(
+    request: CollectBoardAssetsRequest,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<CollectBoardAssetsReport>, String> {
+    // 参数验证
+    if request.board_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyBoardId));
+    }
+
+    if request.target_directory_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyDirectoryId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.collect_board_assets(request).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 自动排列画板命令
+///
+/// 按网格、颜色、宽高比或标签分组计算画板素材的新位置，由前端应用到画板上
+#[tauri::command]
+pub async fn auto_layout_board(
+    request: AutoLayoutRequest,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<AutoLayoutResult>, String> {
+    // 参数验证
+    if request.board_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyBoardId));
+    }
+
+    let service = lock_service!(service);
+    let result = service.auto_layout_board(request).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 创建画板框架命令
+///
+/// 框架和分组是画板上的命名区域，包含其中的元素并一起移动
+#[tauri::command]
+pub async fn create_board_frame(
+    request: BoardFrameRequest,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<BoardFrame>, String> {
+    // 参数验证
+    if request.board_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyBoardId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.create_board_frame(request).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 修改画板框架命令，整体替换名称、区域、上级框架和包含的元素
+#[tauri::command]
+pub async fn update_board_frame(
+    frame_id: String,
+    request: BoardFrameRequest,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<BoardFrame>, String> {
+    // 参数验证
+    if frame_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFrameId));
+    }
+
+    if request.board_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyBoardId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.update_board_frame(&frame_id, request).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取画板框架命令
+#[tauri::command]
+pub async fn list_board_frames(
+    board_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<BoardFrame>>, String> {
+    // 参数验证
+    if board_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyBoardId));
+    }
+
+    let service = lock_service!(service);
+    let result = service.list_board_frames(&board_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 移动画板框架命令
+///
+/// 子框架一起移动，返回需要由前端按同样位移移动的元素
+#[tauri::command]
+pub async fn move_board_frame(
+    frame_id: String,
+    dx: f64,
+    dy: f64,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<BoardFrameMove>, String> {
+    // 参数验证
+    if frame_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFrameId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.move_board_frame(&frame_id, dx, dy).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 删除画板框架命令，子框架一并删除，其中的元素保留在画板上
+#[tauri::command]
+pub async fn delete_board_frame(
+    frame_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<String>>, String> {
+    // 参数验证
+    if frame_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyFrameId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.delete_board_frame(&frame_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 整体替换画板框架命令
+///
+/// 用于保存从画板文件打开的框架，框架使用新 ID
+#[tauri::command]
+pub async fn replace_board_frames(
+    board_id: String,
+    frames: Vec<BoardFrame>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<BoardFrame>>, String> {
+    // 参数验证
+    if board_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyBoardId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.replace_board_frames(&board_id, frames).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 创建播放列表命令
+///
+/// 播放列表是有顺序的文件列表，用于全屏评审模式；关联画板时表示画板的讲解顺序
+#[tauri::command]
+pub async fn create_playlist(
+    request: PlaylistRequest,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Playlist>, String> {
+    // 参数验证
+    if request.name.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyPlaylistName));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.create_playlist(request).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 修改播放列表命令，整体替换名称、关联画板和文件顺序
+#[tauri::command]
+pub async fn update_playlist(
+    playlist_id: String,
+    request: PlaylistRequest,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Playlist>, String> {
+    // 参数验证
+    if playlist_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyPlaylistId));
+    }
+
+    if request.name.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyPlaylistName));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.update_playlist(&playlist_id, request).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取播放列表命令，指定画板时只返回该画板的讲解顺序
+#[tauri::command]
+pub async fn list_playlists(
+    board_id: Option<String>,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<Playlist>>, String> {
+    let service = lock_service!(service);
+    let result = service.list_playlists(board_id.as_deref()).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取播放列表中的文件命令
+#[tauri::command]
+pub async fn get_playlist_files(
+    playlist_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<FileListItem>>, String> {
+    // 参数验证
+    if playlist_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyPlaylistId));
+    }
+
+    let service = lock_service!(service);
+    let result = service.get_playlist_files(&playlist_id).await;
+    Ok(CommandResponse::listing(result))
+}
+
+/// 获取放映条目命令
+///
+/// 返回播放列表中第 `index` 个仍存在的文件（从 0 开始）和供 asset 协议加载的存储路径
+#[tauri::command]
+pub async fn get_playlist_item(
+    playlist_id: String,
+    index: usize,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<PlaylistItem>, String> {
+    // 参数验证
+    if playlist_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyPlaylistId));
+    }
+
+    let service = lock_service!(service);
+    let result = service.get_playlist_item(&playlist_id, index).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 删除播放列表命令，其中的文件不受影响
+#[tauri::command]
+pub async fn delete_playlist(
+    playlist_id: String,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    // 参数验证
+    if playlist_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptyPlaylistId));
+    }
+
+    let service = lock_service_mut!(service);
+    let result = service.delete_playlist(&playlist_id).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取目录快照列表命令，最新的在前
+#[tauri::command]
+pub async fn list_catalog_snapshots(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<CatalogSnapshot>>, String> {
+    let service = lock_service!(service);
+    let result = service.list_catalog_snapshots().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 立即保存目录快照命令，超出保留数量的旧快照会被删除
+#[tauri::command]
+pub async fn create_catalog_snapshot(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<CatalogSnapshot>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.create_catalog_snapshot().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取目录快照设置命令
+#[tauri::command]
+pub async fn get_catalog_snapshot_settings(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<CatalogSnapshotSettings>, String> {
+    let service = lock_service!(service);
+    let result = service.get_catalog_snapshot_settings().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 保存目录快照设置命令
+#[tauri::command]
+pub async fn set_catalog_snapshot_settings(
+    settings: CatalogSnapshotSettings,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<()>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.set_catalog_snapshot_settings(&settings).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 回滚目录到快照命令
+///
+/// `confirm` 为 `false` 时只返回快照和当前库的文件数供用户确认；
+/// 确认后先为当前状态保存快照，再替换目录内容
+#[tauri::command]
+pub async fn rollback_catalog(
+    snapshot_id: String,
+    confirm: bool,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<CatalogRollbackReport>, String> {
+    // 参数验证
+    if snapshot_id.trim().is_empty() {
+        return Ok(CommandResponse::invalid(ValidationError::EmptySnapshotId));
+    }
+
+    let service = if confirm {
+        lock_service_mut!(service)
+    } else {
+        lock_service!(service)
+    };
+    let result = service.rollback_catalog(&snapshot_id, confirm).await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 获取延迟删除队列命令
+///
+/// 列出被其他进程占用、等待后台任务删除的存储文件
+#[tauri::command]
+pub async fn list_deferred_deletes(
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<Vec<DeferredDelete>>, String> {
+    let service = lock_service!(service);
+    let result = service.list_deferred_deletes().await;
+    Ok(CommandResponse::from(result))
+}
+
+/// 立即重试延迟删除命令，不等待各条目的下次尝试时间
+#[tauri::command]
+pub async fn retry_deferred_deletes(
+    app: AppHandle,
+    service: State<'_, FileManagerState>,
+) -> std::result::Result<CommandResponse<DeferredDeleteReport>, String> {
+    let service = lock_service_mut!(service);
+    let result = service.process_deferred_deletes(true).await;
+    if let Ok(report) = &result {
+        if report.has_outcome() {
+            events::emit(&app, &DeferredDeleteFinished(report.clone()));
+        }
+    }
+    Ok(CommandResponse::from(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_manager::read_only::WriterLock;
+    use crate::file_manager::testing::{
+        invoke_command, invoke_command_error, mock_webview, png_fixture, ready_state, text_fixture, TestLibrary,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_command_response_success() {
+        let response = CommandResponse::success("test data".to_string());
+        assert!(response.success);
+        assert_eq!(response.data, Some("test data".to_string()));
+        assert!(response.error.is_none());
+    }
+
+    #[test]
+    fn test_command_response_error() {
+        let response: CommandResponse<String> = CommandResponse::error("test error".to_string());
+        assert!(!response.success);
+        assert!(response.data.is_none());
+        assert_eq!(response.error, Some("test error".to_string()));
+    }
+
+    #[test]
+    fn test_upload_item_result_reports_code() {
+        let result = UploadItemResult::invalid(2, "empty.png".to_string(), ValidationError::EmptyFileData);
+        let value = serde_json::to_value(&result).unwrap();
+        assert_eq!(value["status"], "failed");
+        assert_eq!(value["index"], 2);
+        assert_eq!(value["original_name"], "empty.png");
+        assert_eq!(value["error_code"], ValidationError::EmptyFileData.code());
+    }
+
+    #[tokio::test]
+    async fn test_state_not_ready() {
+        let state = FileManagerState::new();
+        assert_eq!(state.status(), BackendStatus::Initializing);
+        assert_eq!(state.lock().await.err().unwrap().code(), "initializing");
+        assert!(state.startup_report().is_none());
+
+        state.set_failed("disk offline".to_string());
+        assert_eq!(state.status(), BackendStatus::Failed { message: "disk offline".to_string() });
+        assert_eq!(state.lock().await.err().unwrap().code(), "initialization_failed");
+    }
+
+    #[tokio::test]
+    async fn test_state_shutdown_waits_for_running_work() {
+        let (service, _temp_dir) = TestLibrary::builder().build().await.into_parts();
+        let state = FileManagerState::new();
+        state.set_ready(service, StartupReport::default());
+        let service = state.inner.service.get().unwrap();
+        assert!(!service.lock().await.begin_session().await.unwrap());
+
+        // 持有服务锁的任务超时仍未结束时不标记正常关闭
+        let running = service.lock().await;
+        state.shutdown(Duration::from_millis(50)).await.unwrap();
+        assert_eq!(state.lock().await.err().unwrap().code(), "shutting_down");
+        assert!(!running.begin_session().await.unwrap());
+
+        // 任务结束后才标记正常关闭
+        let shutdown = tokio::spawn({
+            let state = state.clone();
+            async move { state.shutdown(Duration::from_secs(5)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!shutdown.is_finished());
+        drop(running);
+        shutdown.await.unwrap().unwrap();
+        assert!(service.lock().await.begin_session().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_state_unlock_round_trip() {
+        let state = FileManagerState::new();
+        assert!(state.unlock("secret".to_string()).await.is_err());
+
+        let mut requests = state.set_locked();
+        assert_eq!(state.status(), BackendStatus::Locked);
+        assert_eq!(state.lock().await.err().unwrap().code(), "library_locked");
+
+        let responder = tokio::spawn(async move {
+            let request = requests.recv().await.unwrap();
+            let result = if request.password == "secret" { Ok(()) } else { Err(FileManagerError::IncorrectPassword) };
+            request.reply.send(result).unwrap();
+            requests.recv().await.unwrap().reply.send(Ok(())).unwrap();
+        });
+
+        assert_eq!(state.unlock("wrong".to_string()).await.unwrap_err().code(), "incorrect_password");
+        state.unlock("secret".to_string()).await.unwrap();
+        responder.await.unwrap();
+
+        state.set_unlocked();
+        assert_eq!(state.status(), BackendStatus::Initializing);
+    }
+
+    #[tokio::test]
+    async fn test_state_switch_read_only() {
+        let (mut service, temp_dir) = TestLibrary::builder().build().await.into_parts();
+        let state = FileManagerState::new();
+        assert_eq!(state.acquire_writer_lock(temp_dir.path()), None);
+        assert!(matches!(read_only::acquire_writer_lock(temp_dir.path(), false).unwrap(), WriterLock::InUse { .. }));
+
+        assert_eq!(state.switch_read_only(&mut service, true).await.unwrap(), Some(ReadOnlyReason::Requested));
+        assert_eq!(service.ensure_writable().unwrap_err().code(), "read_only");
+
+        // 释放写入锁后其他进程开始写入，无法退出只读模式
+        let WriterLock::Acquired(other_writer) = read_only::acquire_writer_lock(temp_dir.path(), false).unwrap() else {
+            panic!("writer lock should be released in read-only mode");
+        };
+        let error = state.switch_read_only(&mut service, false).await.unwrap_err();
+        assert!(matches!(error, FileManagerError::ReadOnly(ReadOnlyReason::InUse { holder: Some(_), .. })));
+        assert!(state.is_read_only());
+
+        drop(other_writer);
+        assert_eq!(state.switch_read_only(&mut service, false).await.unwrap(), None);
+        service.ensure_writable().unwrap();
+    }
+
+    /// 顶层参数按 Tauri 的默认规则使用驼峰命名，`dryRun` 必须只做试运行
+    #[test]
+    fn test_dry_run_with_frontend_payload() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let (kept, copy) = tauri::async_runtime::block_on(async {
+            (library.add_file("a.txt", &text_fixture(1)).await, library.add_file("b.txt", &text_fixture(1)).await)
+        });
+        let (service, _temp_dir) = library.into_parts();
+        let state = FileManagerState::new();
+        state.set_ready(service, StartupReport::default());
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![resolve_duplicates, batch_operations]);
+
+        // 与 FileManagerService.resolveDuplicates(plan, true) 发送的参数相同
+        let response = invoke_command(&webview, "resolve_duplicates", json!({
+            "plan": { "action": "delete", "groups": [{ "keep_file_id": kept, "duplicate_file_ids": [copy] }] },
+            "dryRun": true,
+        }));
+        assert_eq!(response["success"], true);
+        assert_eq!(response["data"]["dry_run"], true);
+
+        // 与 FileManagerService.batchOperations(operations, {}, true) 发送的参数相同
+        let response = invoke_command(&webview, "batch_operations", json!({
+            "operations": [{ "type": "tag", "file_id": kept, "add_tags": ["draft"] }],
+            "revisions": {},
+            "dryRun": true,
+        }));
+        assert_eq!(response["success"], true);
+        assert_eq!(response["data"]["dry_run"], true);
+
+        tauri::async_runtime::block_on(async {
+            let service = state.lock().await.unwrap();
+            assert!(service.get_file_info(&copy).await.unwrap().is_some());
+            assert!(service.get_file_tags(&kept).await.unwrap().is_empty());
+        });
+    }
+
+    /// 与 FileManagerService.triageFile 发送的参数相同
+    #[test]
+    fn test_triage_file_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![triage_file]);
+
+        let response = invoke_command(&webview, "triage_file", json!({
+            "fileId": file_id,
+            "action": { "type": "tag", "add_tags": ["sorted"] },
+        }));
+        assert_eq!(response["success"], true);
+
+        tauri::async_runtime::block_on(async {
+            let service = state.lock().await.unwrap();
+            assert_eq!(service.get_file_tags(&file_id).await.unwrap(), vec!["sorted"]);
+        });
+    }
+
+    /// 与 FileManagerService.moveDirectory 和 getDirectoryItemCounts 发送的参数相同
+    #[test]
+    fn test_move_directory_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let source = tauri::async_runtime::block_on(library.add_directory(None, "source"));
+        let target = tauri::async_runtime::block_on(library.add_directory(None, "target"));
+        tauri::async_runtime::block_on(library.add_file_in(Some(&source), "a.txt", &text_fixture(1)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![move_directory, get_directory_item_counts]);
+
+        let counts = invoke_command(&webview, "get_directory_item_counts", json!({ "directoryId": source }));
+        assert_eq!(counts["data"]["file_count"], 1);
+
+        let updated_at = tauri::async_runtime::block_on(async {
+            let service = state.lock().await.unwrap();
+            service.get_directory_tree().await.unwrap()
+                .into_iter()
+                .find(|node| node.id == source)
+                .unwrap()
+                .updated_at
+        });
+        let moved = invoke_command(&webview, "move_directory", json!({
+            "command": { "directory_id": source, "new_parent_id": target, "expected_updated_at": updated_at },
+        }));
+        assert_eq!(moved["success"], true);
+        assert_eq!(moved["data"]["parent_id"], json!(target));
+    }
+
+    /// 与 FileManagerService 中损坏文件相关的封装发送的参数相同
+    #[test]
+    fn test_missing_file_commands_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let relinked = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        let purged = tauri::async_runtime::block_on(library.add_file("b.txt", &text_fixture(2)));
+        let found = library.path().join("found.txt");
+        std::fs::write(&found, text_fixture(3)).unwrap();
+        let (state, _temp_dir) = ready_state(library);
+        tauri::async_runtime::block_on(async {
+            let service = state.lock().await.unwrap();
+            for file_id in [&relinked, &purged] {
+                std::fs::remove_file(service.get_file_asset_path(file_id).await.unwrap()).unwrap();
+            }
+        });
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![
+            scan_missing_files,
+            get_broken_files,
+            relink_file,
+            purge_broken_files,
+        ]);
+
+        let report = invoke_command(&webview, "scan_missing_files", json!({}));
+        assert_eq!(report["data"]["broken"], 2);
+        let broken = invoke_command(&webview, "get_broken_files", json!({}));
+        assert_eq!(broken["data"].as_array().unwrap().len(), 2);
+
+        let response = invoke_command(&webview, "relink_file", json!({
+            "command": { "file_id": relinked, "new_path": found },
+        }));
+        assert_eq!(response["success"], true);
+
+        let response = invoke_command(&webview, "purge_broken_files", json!({}));
+        assert_eq!(response["data"], 1);
+    }
+
+    /// 与 FileManagerService.updateTagsBulk 和 getFileTags 发送的参数相同
+    #[test]
+    fn test_tag_commands_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        tauri::async_runtime::block_on(library.tag(&file_id, &["old"]));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![update_tags_bulk, get_file_tags]);
+
+        let response = invoke_command(&webview, "update_tags_bulk", json!({
+            "request": { "file_ids": [file_id], "add_tags": ["new"], "remove_tags": ["old"] },
+        }));
+        assert_eq!(response["data"]["changed"], 2);
+
+        let tags = invoke_command(&webview, "get_file_tags", json!({ "fileId": file_id }));
+        assert_eq!(tags["data"], json!(["new"]));
+    }
+
+    /// 与 FileManagerService.getThumbnail 发送的参数相同，传入的浏览位置用于预取
+    #[test]
+    fn test_get_thumbnail_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let directory_id = tauri::async_runtime::block_on(library.add_directory(None, "images"));
+        let file_id = tauri::async_runtime::block_on(library.add_file_in(Some(&directory_id), "a.png", &png_fixture(64, 64, 1)));
+        tauri::async_runtime::block_on(library.add_file_in(Some(&directory_id), "b.png", &png_fixture(64, 64, 2)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![get_thumbnail]);
+
+        let response = invoke_command(&webview, "get_thumbnail", json!({
+            "fileId": file_id,
+            "size": "small",
+            "context": { "directory_id": directory_id, "index": 0, "count": 1 },
+        }));
+        assert_eq!(response["data"]["size"], "small");
+
+        // 相邻的 b.png 在后台预取
+        tauri::async_runtime::block_on(async {
+            let service = state.lock().await.unwrap();
+            for _ in 0..500 {
+                if service.get_cache_stats().await.unwrap().file_count == 2 {
+                    return;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            panic!("neighbouring thumbnail was not prefetched");
+        });
+    }
+
+    /// 与 FileManagerService.compareImages 发送的参数相同
+    #[test]
+    fn test_compare_images_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let a = tauri::async_runtime::block_on(library.add_file("a.png", &png_fixture(20, 20, 1)));
+        let b = tauri::async_runtime::block_on(library.add_file("b.png", &png_fixture(10, 10, 2)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![compare_images]);
+
+        let response = invoke_command(&webview, "compare_images", json!({ "fileIdA": a, "fileIdB": b }));
+        assert_eq!(response["data"]["width"], 20);
+        assert_eq!(response["data"]["resized"], true);
+    }
+
+    /// 与 FileManagerService.findSimilarImages 发送的参数相同
+    #[test]
+    fn test_find_similar_images_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.png", &png_fixture(32, 32, 1)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![find_similar_images]);
+
+        let response = invoke_command(&webview, "find_similar_images", json!({ "fileId": file_id, "maxDistance": 10 }));
+        assert_eq!(response["success"], true);
+
+        let response = invoke_command(&webview, "find_similar_images", json!({ "fileId": file_id, "maxDistance": HASH_BITS + 1 }));
+        assert_eq!(response["error_code"], ValidationError::MaxDistanceTooLarge { max: HASH_BITS }.code());
+    }
+
+    /// 与 FileManagerService.importFromUrl 发送的参数相同
+    #[test]
+    fn test_import_from_url_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let directory_id = tauri::async_runtime::block_on(library.add_directory(None, "inbox"));
+        tauri::async_runtime::block_on(library.set_notification_settings(&NotificationSettings { enabled: false })).unwrap();
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![import_from_url]);
+
+        let base = tauri::async_runtime::block_on(crate::file_manager::url_import::tests::serve_once(
+            "Content-Type: text/plain\r\n",
+            b"remote notes".to_vec(),
+        ));
+        let response = invoke_command(&webview, "import_from_url", json!({
+            "url": format!("{}/notes.txt", base),
+            "directoryId": directory_id,
+            "tags": ["Inbox"],
+        }));
+        assert_eq!(response["data"]["directory_id"], json!(directory_id));
+    }
+
+    /// 与 FileManagerService.removeWatchedFolder 发送的参数相同
+    #[test]
+    fn test_remove_watched_folder_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let watched = library.path().join("watched");
+        std::fs::create_dir(&watched).unwrap();
+        let folder = tauri::async_runtime::block_on(library.add_watched_folder(AddWatchedFolderRequest {
+            path: watched.to_string_lossy().into_owned(),
+            target_directory_id: None,
+            default_tags: Vec::new(),
+            import_mode: Default::default(),
+        })).unwrap();
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![remove_watched_folder, get_watched_folders]);
+
+        let response = invoke_command(&webview, "remove_watched_folder", json!({ "folderId": folder.id }));
+        assert_eq!(response["success"], true);
+        let folders = invoke_command(&webview, "get_watched_folders", json!({}));
+        assert_eq!(folders["data"], json!([]));
+    }
+
+    /// 与 FileManagerService.runIntegrityCheck 发送的参数相同
+    #[test]
+    fn test_run_integrity_check_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        for seed in 0..3 {
+            tauri::async_runtime::block_on(library.add_file(&format!("{}.txt", seed), &text_fixture(seed)));
+        }
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![run_integrity_check]);
+
+        let response = invoke_command(&webview, "run_integrity_check", json!({ "sampleSize": 1 }));
+        assert_eq!(response["data"]["checked"], 1);
+    }
+
+    /// 与 FileManagerService.undoResolveDuplicates 发送的参数相同
+    #[test]
+    fn test_undo_resolve_duplicates_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let kept = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        let copy = tauri::async_runtime::block_on(library.add_file("b.txt", &text_fixture(1)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![resolve_duplicates, undo_resolve_duplicates]);
+
+        let report = invoke_command(&webview, "resolve_duplicates", json!({
+            "plan": { "action": "delete", "groups": [{ "keep_file_id": kept, "duplicate_file_ids": [copy] }] },
+            "dryRun": false,
+        }));
+        let response = invoke_command(&webview, "undo_resolve_duplicates", json!({ "undoId": report["data"]["undo_id"] }));
+        assert_eq!(response["success"], true);
+
+        tauri::async_runtime::block_on(async {
+            let service = state.lock().await.unwrap();
+            assert!(service.get_file_info(&copy).await.unwrap().is_some());
+        });
+    }
+
+    /// 与 FileManagerService.getFileAssetUrl 发送的参数相同
+    #[test]
+    fn test_get_file_asset_path_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.png", &png_fixture(8, 8, 1)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![get_file_asset_path]);
+
+        let response = invoke_command(&webview, "get_file_asset_path", json!({ "fileId": file_id }));
+        let path = response["data"].as_str().unwrap();
+        assert!(std::path::Path::new(path).is_file());
+    }
+
+    /// 与 FileManagerService.captureScreenshot 发送的参数相同；服务未就绪时在截图前返回，
+    /// 测试环境中不会真正截图
+    #[test]
+    fn test_capture_screenshot_args() {
+        let state = FileManagerState::new();
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![capture_screenshot]);
+
+        let response = invoke_command(&webview, "capture_screenshot", json!({
+            "mode": "active_window",
+            "directoryId": "inbox",
+            "tags": ["screenshots"],
+        }));
+        assert_eq!(response["error_code"], "initializing");
+    }
+
+    /// 与 FileManagerService.changeLibraryPassword 发送的参数相同；测试资料库使用内存数据库，
+    /// 参数读取正确时由服务返回资料库未加密的错误
+    #[test]
+    fn test_change_library_password_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![change_library_password]);
+
+        let response = invoke_command(&webview, "change_library_password", json!({ "oldPassword": "old secret", "newPassword": "x" }));
+        assert_eq!(response["error_code"], ValidationError::PasswordTooShort { min: MIN_LIBRARY_PASSWORD_LEN }.code());
+
+        let response = invoke_command(&webview, "change_library_password", json!({ "oldPassword": "old secret", "newPassword": "new secret" }));
+        assert_eq!(response["error_code"], "general");
+        assert!(response["error"].as_str().unwrap().contains("not encrypted"));
+    }
+
+    /// 与 FileManagerService.setFileAnnotations 和 importSidecar 发送的参数相同
+    #[test]
+    fn test_annotation_commands_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        let sidecar = library.path().join("a.txt.json");
+        std::fs::write(&sidecar, br#"{ "tags": ["travel"], "notes": "imported" }"#).unwrap();
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![set_file_annotations, import_sidecar]);
+
+        let response = invoke_command(&webview, "set_file_annotations", json!({ "fileId": file_id, "rating": 4, "notes": "cover" }));
+        assert_eq!((&response["data"]["rating"], &response["data"]["notes"]), (&json!(4), &json!("cover")));
+
+        let response = invoke_command(&webview, "import_sidecar", json!({ "fileId": file_id, "path": sidecar }));
+        assert_eq!(response["data"]["notes"], "imported");
+    }
+
+    /// 与 FileManagerService.approveQuarantinedFile 发送的参数相同
+    #[test]
+    fn test_approve_quarantined_file_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let directory_id = tauri::async_runtime::block_on(library.add_directory(None, "approved"));
+        let id = tauri::async_runtime::block_on(async {
+            library.set_quarantine_settings(&QuarantineSettings { enabled: true }).await.unwrap();
+            let request = UploadRequest {
+                file_data: b"executable content".to_vec(),
+                original_name: "tool.exe".to_string(),
+                ..Default::default()
+            };
+            match library.upload_file(request).await.unwrap_err() {
+                FileManagerError::Quarantined { id, .. } => id,
+                error => panic!("upload should be quarantined: {}", error),
+            }
+        });
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![approve_quarantined_file]);
+
+        let response = invoke_command(&webview, "approve_quarantined_file", json!({ "id": id, "directoryId": directory_id }));
+        assert_eq!(response["data"]["directory_id"], json!(directory_id));
+    }
+
+    /// 与 FileManagerService.getDirectoryDefaults 和 setDirectoryDefaults 发送的参数相同
+    #[test]
+    fn test_directory_defaults_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let directory_id = tauri::async_runtime::block_on(library.add_directory(None, "travel"));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![get_directory_defaults, set_directory_defaults]);
+
+        let response = invoke_command(&webview, "set_directory_defaults", json!({
+            "directoryId": directory_id,
+            "tags": ["trip"],
+            "inherit": true,
+        }));
+        assert_eq!(response["success"], true);
+
+        let defaults = invoke_command(&webview, "get_directory_defaults", json!({ "directoryId": directory_id }));
+        assert_eq!(defaults["data"], json!({ "directory_id": directory_id, "tags": ["trip"], "inherit": true }));
+    }
+
+    /// 与 FileManagerService.restoreLibrary 发送的参数相同
+    #[test]
+    fn test_restore_library_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let backup_root = library.path().join("backups");
+        std::fs::create_dir(&backup_root).unwrap();
+        let backup = tauri::async_runtime::block_on(async {
+            library.add_file("a.txt", &text_fixture(1)).await;
+            library.backup_library(BackupScope::Full, &backup_root.to_string_lossy()).await.unwrap()
+        });
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![restore_library]);
+
+        let response = invoke_command(&webview, "restore_library", json!({ "backupPath": backup.backup_dir, "mode": "merge" }));
+        assert_eq!(response["data"]["mode"], "merge");
+        assert_eq!(response["data"]["applied"], true);
+    }
+
+    /// 与 FileManagerService.linkFile 和 convertToManaged 发送的参数相同
+    #[test]
+    fn test_linked_file_commands_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let directory_id = tauri::async_runtime::block_on(library.add_directory(None, "archive"));
+        let original = library.path().join("outside.txt");
+        std::fs::write(&original, text_fixture(1)).unwrap();
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![link_file, convert_to_managed]);
+
+        let linked = invoke_command(&webview, "link_file", json!({ "path": original, "directoryId": directory_id }));
+        assert_eq!(linked["data"]["directory_id"], json!(directory_id));
+
+        let response = invoke_command(&webview, "convert_to_managed", json!({ "fileId": linked["data"]["file_id"] }));
+        assert_eq!(response["data"]["linked"], false);
+    }
+
+    /// 与 FileManagerService.getListingPage 发送的参数相同
+    #[test]
+    fn test_get_listing_page_args() {
+        let half = response_guard::DEFAULT_MAX_RESPONSE_BYTES * 3 / 4;
+        let (_, continuation) = response_guard::guard_listing(vec!["a".repeat(half), "b".repeat(half)]);
+        let continuation = continuation.unwrap();
+        let state = FileManagerState::new();
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![get_listing_page]);
+
+        let response = invoke_command(&webview, "get_listing_page", json!({
+            "listingId": continuation.listing_id,
+            "offset": continuation.next_offset,
+        }));
+        assert_eq!(response["data"][0].as_str().unwrap().len(), half);
+        assert!(response["continuation"].is_null());
+    }
+
+    /// 与 FileManagerService.getRecentEvents 发送的参数相同
+    #[test]
+    fn test_get_recent_events_args() {
+        let state = FileManagerState::new();
+        let (app, webview) = mock_webview(&state, tauri::generate_handler![get_recent_events]);
+        events::emit(app.handle(), &events::BackendLocked);
+
+        let all = invoke_command(&webview, "get_recent_events", json!({}));
+        let last = all["data"].as_array().unwrap().last().unwrap()["seq"].as_u64().unwrap();
+
+        // 其他测试同时发送的事件序号更大，只检查不再返回已收到的事件
+        let response = invoke_command(&webview, "get_recent_events", json!({ "afterSeq": last }));
+        assert!(response["data"].as_array().unwrap().iter().all(|record| record["seq"].as_u64().unwrap() > last));
+    }
+
+    /// 与 FileManagerService.getDirectoryFilesPage 发送的参数相同
+    #[test]
+    fn test_get_directory_files_page_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let directory_id = tauri::async_runtime::block_on(library.add_directory(None, "paged"));
+        for seed in 0..3 {
+            tauri::async_runtime::block_on(library.add_file_in(Some(&directory_id), &format!("{}.txt", seed), &text_fixture(seed)));
+        }
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![get_directory_files_page]);
+
+        let first = invoke_command(&webview, "get_directory_files_page", json!({ "directoryId": directory_id, "cursor": null, "limit": 2 }));
+        assert_eq!(first["data"]["items"].as_array().unwrap().len(), 2);
+
+        let second = invoke_command(&webview, "get_directory_files_page", json!({
+            "directoryId": directory_id,
+            "cursor": first["data"]["next_cursor"],
+            "limit": 2,
+        }));
+        assert_eq!(second["data"]["items"][0]["original_name"], "2.txt");
+    }
+
+    /// 与 FileManagerService.undoBatchOperations 发送的参数相同
+    #[test]
+    fn test_undo_batch_operations_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![batch_operations, undo_batch_operations]);
+
+        let report = invoke_command(&webview, "batch_operations", json!({
+            "operations": [{ "type": "tag", "file_id": file_id, "add_tags": ["draft"] }],
+            "revisions": {},
+            "dryRun": false,
+        }));
+        let response = invoke_command(&webview, "undo_batch_operations", json!({ "undoId": report["data"]["undo_id"] }));
+        assert_eq!(response["success"], true);
+
+        tauri::async_runtime::block_on(async {
+            let service = state.lock().await.unwrap();
+            assert!(service.get_file_tags(&file_id).await.unwrap().is_empty());
+        });
+    }
+
+    /// 与 FileManagerService 中打开方式相关的封装发送的参数相同；指定未配置的应用时在打开前返回错误，
+    /// 测试中不会启动外部程序
+    #[test]
+    fn test_open_with_commands_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![set_open_with, get_apps_for_file, open_file_with]);
+        let editor = json!({ "id": "editor", "name": "Editor", "program": "editor" });
+
+        let response = invoke_command(&webview, "set_open_with", json!({ "mimeType": "text/*", "apps": [editor] }));
+        assert_eq!(response["success"], true);
+
+        let apps = invoke_command(&webview, "get_apps_for_file", json!({ "fileId": file_id }));
+        assert_eq!(apps["data"], json!([editor]));
+
+        let response = invoke_command(&webview, "open_file_with", json!({ "fileId": file_id, "appId": "missing" }));
+        assert!(response["error"].as_str().unwrap().contains("missing"));
+    }
+
+    /// 与 FileManagerService 中外部编辑相关的封装发送的参数相同；编辑会话直接通过服务创建，
+    /// 测试中不会启动外部程序
+    #[test]
+    fn test_external_edit_commands_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        let (session, _) = tauri::async_runtime::block_on(library.start_external_edit(&file_id, None)).unwrap();
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(
+            &state,
+            tauri::generate_handler![edit_externally, apply_external_edit, end_external_edit],
+        );
+
+        let response = invoke_command(&webview, "edit_externally", json!({ "fileId": file_id, "appId": "missing" }));
+        assert!(response["error"].as_str().unwrap().contains("missing"));
+
+        let applied = invoke_command(&webview, "apply_external_edit", json!({ "sessionId": session.id }));
+        assert_eq!(applied["data"]["version"], 2);
+
+        let response = invoke_command(&webview, "end_external_edit", json!({ "sessionId": session.id }));
+        assert_eq!(response["success"], true);
+    }
+
+    /// 写入图像元数据命令的参数与 FileManagerService 中的封装发送的参数相同
+    #[test]
+    fn test_write_image_metadata_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.png", &png_fixture(8, 8, 1)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![write_image_metadata]);
+
+        let result = invoke_command(
+            &webview,
+            "write_image_metadata",
+            json!({ "fileId": file_id, "fields": { "title": "Sunset" } }),
+        );
+        assert_eq!(result["data"]["embedded"], true);
+    }
+
+    /// 工作集命令的参数与 FileManagerService 中的封装发送的参数相同
+    #[test]
+    fn test_working_set_commands_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let first = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        let second = tauri::async_runtime::block_on(library.add_file("b.txt", &text_fixture(2)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(
+            &state,
+            tauri::generate_handler![
+                create_working_set,
+                add_to_working_set,
+                remove_from_working_set,
+                get_working_set_files,
+                delete_working_set
+            ],
+        );
+
+        let created = invoke_command(&webview, "create_working_set", json!({ "name": "Picks", "ttlMinutes": 30 }));
+        assert_eq!(created["data"]["ttl_minutes"], 30);
+        let working_set_id = created["data"]["id"].as_str().unwrap().to_string();
+
+        let added = invoke_command(
+            &webview,
+            "add_to_working_set",
+            json!({ "workingSetId": working_set_id, "fileIds": [first, second] }),
+        );
+        assert_eq!(added["data"]["file_count"], 2);
+
+        let removed = invoke_command(
+            &webview,
+            "remove_from_working_set",
+            json!({ "workingSetId": working_set_id, "fileIds": [first] }),
+        );
+        assert_eq!(removed["data"]["file_count"], 1);
+
+        let files = invoke_command(&webview, "get_working_set_files", json!({ "workingSetId": working_set_id }));
+        assert_eq!(files["data"][0]["id"], second);
+
+        let response = invoke_command(&webview, "delete_working_set", json!({ "workingSetId": working_set_id }));
+        assert_eq!(response["success"], true);
+    }
+
+    /// 归档命令的参数与 FileManagerService 中的封装发送的参数相同
+    #[test]
+    fn test_archive_commands_args() {
+        let archive_dir = tempfile::TempDir::new().unwrap();
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        let settings = ArchiveSettings { location: Some(archive_dir.path().to_string_lossy().to_string()) };
+        tauri::async_runtime::block_on(library.set_archive_settings(settings)).unwrap();
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![archive_files, restore_archived_files]);
+
+        let archived = invoke_command(&webview, "archive_files", json!({ "fileIds": [file_id] }));
+        assert_eq!(archived["data"]["moved"], 1);
+
+        let restored = invoke_command(&webview, "restore_archived_files", json!({ "fileIds": [file_id] }));
+        assert_eq!(restored["data"]["moved"], 1);
+    }
+
+    /// 目录模板命令的参数与 FileManagerService 中的封装发送的参数相同
+    #[test]
+    fn test_directory_template_commands_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let parent_id = tauri::async_runtime::block_on(library.add_directory(None, "Projects"));
+        let template = tauri::async_runtime::block_on(library.create_directory_template("Shoot", Vec::new())).unwrap();
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(
+            &state,
+            tauri::generate_handler![
+                update_directory_template,
+                delete_directory_template,
+                create_directory_from_template
+            ],
+        );
+
+        let updated = invoke_command(
+            &webview,
+            "update_directory_template",
+            json!({ "templateId": template.id, "name": "Shoot", "folders": [{ "name": "Raw", "children": [] }] }),
+        );
+        assert_eq!(updated["data"]["folders"][0]["name"], "Raw");
+
+        let applied = invoke_command(
+            &webview,
+            "create_directory_from_template",
+            json!({ "templateId": template.id, "parentId": parent_id, "name": "Wedding" }),
+        );
+        assert_eq!(applied["data"]["folders_created"], 1);
+        assert!(applied["data"]["path"].as_str().unwrap().contains("Projects"));
+
+        let response = invoke_command(&webview, "delete_directory_template", json!({ "templateId": template.id }));
+        assert_eq!(response["success"], true);
+    }
+
+    /// 记录文件使用命令的参数与 FileManagerService 中的封装发送的参数相同；画板 ID 通过推荐理由确认已记录
+    #[test]
+    fn test_record_file_usage_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![record_file_usage, get_suggestions]);
+
+        let response = invoke_command(
+            &webview,
+            "record_file_usage",
+            json!({ "fileId": file_id, "kind": "placement", "boardId": "board-1" }),
+        );
+        assert_eq!(response["success"], true);
+
+        let suggestions = invoke_command(&webview, "get_suggestions", json!({ "context": { "board_id": "board-1" } }));
+        assert_eq!(suggestions["data"][0]["file"]["id"], file_id);
+        assert_eq!(suggestions["data"][0]["reasons"][0], "used_on_board");
+    }
+
+    /// 按预设导出命令的参数与 FileManagerService 中的封装发送的参数相同；空白的水印文字被拒绝，说明水印参数已读取
+    #[test]
+    fn test_export_with_preset_args() {
+        let destination = tempfile::TempDir::new().unwrap();
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.png", &png_fixture(8, 8, 1)));
+        tauri::async_runtime::block_on(library.set_notification_settings(&NotificationSettings { enabled: false })).unwrap();
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![export_with_preset]);
+        let destination = destination.path().to_string_lossy().to_string();
+
+        let response = invoke_command(
+            &webview,
+            "export_with_preset",
+            json!({
+                "fileIds": [file_id],
+                "presetId": "web",
+                "destination": destination,
+                "watermark": { "content": { "kind": "text", "text": " " } }
+            }),
+        );
+        assert!(response["error"].as_str().unwrap().contains("Watermark text"));
+
+        let report = invoke_command(
+            &webview,
+            "export_with_preset",
+            json!({ "fileIds": [file_id], "presetId": "web", "destination": destination, "watermark": null }),
+        );
+        assert_eq!(report["data"]["exported"].as_array().unwrap().len(), 1);
+    }
+
+    /// 生成校验清单命令的参数与 FileManagerService 中的封装发送的参数相同
+    #[test]
+    fn test_generate_manifest_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![generate_manifest]);
+
+        let manifest = invoke_command(&webview, "generate_manifest", json!({ "fileIds": [file_id], "format": "sfv" }));
+        assert_eq!(manifest["data"]["file_name"], "checksums.sfv");
+        assert_eq!(manifest["data"]["entries"].as_array().unwrap().len(), 1);
+    }
+
+    /// 播放列表命令的参数与 FileManagerService 中的封装发送的参数相同
+    #[test]
+    fn test_playlist_commands_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let first = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        let second = tauri::async_runtime::block_on(library.add_file("b.txt", &text_fixture(2)));
+        let playlist = tauri::async_runtime::block_on(library.create_playlist(PlaylistRequest {
+            name: "Review".to_string(),
+            board_id: Some("board-1".to_string()),
+            file_ids: vec![first.clone()],
+        })).unwrap();
+        tauri::async_runtime::block_on(library.create_playlist(PlaylistRequest {
+            name: "Other".to_string(),
+            board_id: None,
+            file_ids: Vec::new(),
+        })).unwrap();
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(
+            &state,
+            tauri::generate_handler![
+                update_playlist,
+                list_playlists,
+                get_playlist_files,
+                get_playlist_item,
+                delete_playlist
+            ],
+        );
+
+        let updated = invoke_command(
+            &webview,
+            "update_playlist",
+            json!({ "playlistId": playlist.id, "request": { "name": "Review", "board_id": "board-1", "file_ids": [first, second] } }),
+        );
+        assert_eq!(updated["data"]["item_count"], 2);
+
+        let playlists = invoke_command(&webview, "list_playlists", json!({ "boardId": "board-1" }));
+        assert_eq!(playlists["data"].as_array().unwrap().len(), 1);
+
+        let files = invoke_command(&webview, "get_playlist_files", json!({ "playlistId": playlist.id }));
+        assert_eq!(files["data"][0]["id"], first);
+
+        let item = invoke_command(&webview, "get_playlist_item", json!({ "playlistId": playlist.id, "index": 1 }));
+        assert_eq!(item["data"]["file"]["id"], second);
+
+        let response = invoke_command(&webview, "delete_playlist", json!({ "playlistId": playlist.id }));
+        assert_eq!(response["success"], true);
+    }
+
+    /// 回滚目录命令的参数与 FileManagerService 中的封装发送的参数相同
+    #[test]
+    fn test_rollback_catalog_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        let snapshot = tauri::async_runtime::block_on(library.create_catalog_snapshot()).unwrap();
+        tauri::async_runtime::block_on(library.add_file("b.txt", &text_fixture(2)));
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![rollback_catalog]);
+
+        let preview = invoke_command(&webview, "rollback_catalog", json!({ "snapshotId": snapshot.id, "confirm": false }));
+        assert_eq!(preview["data"]["applied"], false);
+        assert_eq!(preview["data"]["current_files"], 2);
+        assert_eq!(preview["data"]["snapshot_files"], 1);
+
+        let report = invoke_command(&webview, "rollback_catalog", json!({ "snapshotId": snapshot.id, "confirm": true }));
+        assert_eq!(report["data"]["applied"], true);
+    }
+
+    /// 导入元数据命令的参数与 FileManagerService 中的封装发送的参数相同
+    #[test]
+    fn test_import_metadata_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.txt", &text_fixture(1)));
+        tauri::async_runtime::block_on(library.tag(&file_id, &["sky"]));
+        let path = library.path().join("metadata.json");
+        tauri::async_runtime::block_on(library.export_metadata(None, &path)).unwrap();
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![import_metadata]);
+
+        let report = invoke_command(
+            &webview,
+            "import_metadata",
+            json!({ "path": path.to_string_lossy(), "matchByHash": true }),
+        );
+        assert_eq!(report["data"]["total"], 1);
+        assert_eq!(report["data"]["matched"], 1);
+    }
+
+    /// 画板框架命令的参数与 FileManagerService 中的封装发送的参数相同
+    #[test]
+    fn test_board_frame_commands_with_frontend_payload() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let (service, _temp_dir) = library.into_parts();
+        let state = FileManagerState::new();
+        state.set_ready(service, StartupReport::default());
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![
+            create_board_frame,
+            update_board_frame,
+            list_board_frames,
+            move_board_frame,
+            delete_board_frame,
+            replace_board_frames,
+        ]);
+        let request = json!({ "board_id": "board", "name": "Refs", "x": 0, "y": 0, "width": 10, "height": 10, "item_ids": ["a"] });
+
+        let created = invoke_command(&webview, "create_board_frame", json!({ "request": request }));
+        assert_eq!(created["success"], true);
+        let frame_id = created["data"]["id"].as_str().unwrap().to_string();
+
+        let updated = invoke_command(&webview, "update_board_frame", json!({ "frameId": frame_id, "request": request }));
+        assert_eq!(updated["data"]["kind"], "frame");
+
+        let moved = invoke_command(&webview, "move_board_frame", json!({ "frameId": frame_id, "dx": 5, "dy": 5 }));
+        assert_eq!(moved["data"]["item_ids"], json!(["a"]));
+
+        let listed = invoke_command(&webview, "list_board_frames", json!({ "boardId": "board" }));
+        assert_eq!(listed["data"][0]["x"], 5.0);
+
+        let replaced = invoke_command(&webview, "replace_board_frames", json!({ "boardId": "copy", "frames": listed["data"] }));
+        assert_eq!(replaced["data"][0]["board_id"], "copy");
+
+        let deleted = invoke_command(&webview, "delete_board_frame", json!({ "frameId": frame_id }));
+        assert_eq!(deleted["data"], json!([frame_id]));
+
+        let response = invoke_command(&webview, "list_board_frames", json!({ "boardId": " " }));
+        assert_eq!(response["error_code"], ValidationError::EmptyBoardId.code());
+    }
+
+    /// 读取文件内容出错时与其他命令一样返回错误码和本地化的错误信息
+    #[test]
+    fn test_read_file_content_error_code() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let (service, _temp_dir) = library.into_parts();
+        let state = FileManagerState::new();
+        state.set_ready(service, StartupReport::default());
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![read_file_content]);
+
+        let error = invoke_command_error(&webview, "read_file_content", json!({ "command": { "file_id": " " } }));
+        assert_eq!(error["code"], ValidationError::EmptyFileId.code());
+        assert_eq!(error["message"], ValidationError::EmptyFileId.message(current_locale()));
+
+        let error = invoke_command_error(&webview, "read_file_content", json!({ "command": { "file_id": "missing" } }));
+        assert_eq!(error["code"], "file_not_found");
+        assert!(error["message"].as_str().unwrap().contains("missing"));
+    }
+
+    #[test]
+    fn test_command_response_error_code() {
+        let response: CommandResponse<()> = CommandResponse::invalid(ValidationError::EmptyFileId);
+        assert_eq!(response.error_code.as_deref(), Some("empty_file_id"));
+
+        let response: CommandResponse<()> = CommandResponse::from(Err(FileManagerError::FileNotFound {
+            path: "abc".to_string(),
+        }));
+        assert_eq!(response.error_code.as_deref(), Some("file_not_found"));
+        assert!(response.error.unwrap().contains("abc"));
     }
 
     #[test]
@@ -523,6 +4904,9 @@ mod tests {
             file_data: vec![],
             original_name: "".to_string(),
             directory_id: None,
+            conflict_policy: ConflictPolicy::default(),
+            organize_by_capture_date: false,
+            last_modified: None,
         };
         
         assert!(command.file_data.is_empty());
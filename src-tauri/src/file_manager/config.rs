@@ -3,14 +3,68 @@
 //! 负责管理文件管理系统的配置信息，包括：
 //! - 数据库文件路径
 //! - 文件存储路径
-//! - 系统限制参数
+//! - 系统限制参数（含按文件类别的大小上限）
+//! - 文件类型限制策略
+//! - 缩略图生成设置
 //! - 应用数据目录初始化
 
 use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::size_limits::SizeLimits;
+use crate::file_manager::storage_layout::StorageLayout;
+use crate::file_manager::thumbnail::PreviewSettings;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 use chrono::Datelike;
 
+/// 保存文件类型策略的状态键
+pub const FILE_TYPE_POLICY_KEY: &str = "file_type_policy";
+
+/// “允许除可执行文件外的所有类型”模式下禁止的扩展名
+pub const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "exe", "msi", "com", "scr", "pif", "bat", "cmd", "ps1", "vbs", "vbe", "js", "jse", "wsf", "wsh",
+    "hta", "cpl", "msc", "lnk", "reg", "dll", "sys", "jar", "app", "command", "sh", "apk",
+];
+
+/// 文件类型限制方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum FileTypePolicyMode {
+    /// 只允许支持列表中的类型
+    #[default]
+    Allowlist,
+    /// 允许除黑名单外的所有类型
+    Blocklist,
+    /// 允许除可执行文件外的所有类型
+    AllowAllExceptExecutables,
+}
+
+/// 文件类型策略
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct FileTypePolicy {
+    pub mode: FileTypePolicyMode,
+    /// 黑名单模式下禁止的扩展名（小写、不含点）
+    #[serde(default)]
+    pub blocked_types: Vec<String>,
+}
+
+impl FileTypePolicy {
+    /// 规范化黑名单：去掉前导点、转为小写并去重
+    pub fn normalized(mut self) -> Self {
+        let mut blocked_types: Vec<String> = self.blocked_types.iter()
+            .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect();
+        blocked_types.sort();
+        blocked_types.dedup();
+        self.blocked_types = blocked_types;
+        self
+    }
+}
+
 /// 文件管理系统配置
 #[derive(Debug, Clone)]
 pub struct FileManagerConfig {
@@ -24,6 +78,14 @@ pub struct FileManagerConfig {
     pub max_file_size: u64,
     /// 支持的文件类型
     pub supported_file_types: Vec<String>,
+    /// 文件类型限制策略，允许名单模式下使用 `supported_file_types`
+    pub file_type_policy: FileTypePolicy,
+    /// 新上传文件使用的存储布局
+    pub storage_layout: StorageLayout,
+    /// 缩略图生成设置
+    pub preview_settings: PreviewSettings,
+    /// 按文件类别的大小上限，覆盖 `max_file_size`
+    pub size_limits: SizeLimits,
 }
 
 impl FileManagerConfig {
@@ -60,13 +122,17 @@ impl FileManagerConfig {
             storage_path,
             max_file_size: 100 * 1024 * 1024, // 100MB
             supported_file_types: Self::default_supported_types(),
+            file_type_policy: FileTypePolicy::default(),
+            storage_layout: StorageLayout::default(),
+            preview_settings: PreviewSettings::default(),
+            size_limits: SizeLimits::default(),
         })
     }
 
     /// 获取应用数据目录
     /// 
     /// 在 Windows 上通常是 %APPDATA%/Collaboard
-    fn get_app_data_dir() -> Result<PathBuf> {
+    pub fn get_app_data_dir() -> Result<PathBuf> {
         // 尝试使用环境变量获取应用数据目录
         if let Ok(app_data) = std::env::var("APPDATA") {
             return Ok(Path::new(&app_data).join("Collaboard"));
@@ -98,6 +164,9 @@ impl FileManagerConfig {
             "gif".to_string(),
             "bmp".to_string(),
             "webp".to_string(),
+            "heic".to_string(),
+            "heif".to_string(),
+            "avif".to_string(),
             "svg".to_string(),
             "tiff".to_string(),
             "tga".to_string(),
@@ -114,14 +183,44 @@ impl FileManagerConfig {
         ]
     }
 
-    /// 检查文件类型是否支持
+    /// 按文件类型策略检查文件类型是否支持
+    ///
+    /// 没有扩展名的文件只在允许名单模式下被拒绝
     pub fn is_file_type_supported(&self, file_path: &Path) -> bool {
-        if let Some(extension) = file_path.extension() {
-            if let Some(ext_str) = extension.to_str() {
-                return self.supported_file_types.contains(&ext_str.to_lowercase());
+        let extension = file_path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+
+        match self.file_type_policy.mode {
+            FileTypePolicyMode::Allowlist => {
+                extension.is_some_and(|ext| self.supported_file_types.contains(&ext))
+            }
+            FileTypePolicyMode::Blocklist => {
+                !extension.is_some_and(|ext| self.file_type_policy.blocked_types.contains(&ext))
+            }
+            FileTypePolicyMode::AllowAllExceptExecutables => {
+                !extension.is_some_and(|ext| EXECUTABLE_EXTENSIONS.contains(&ext.as_str()))
             }
         }
-        false
+    }
+
+    /// 获取缩略图缓存目录
+    pub fn thumbnail_dir(&self) -> PathBuf {
+        self.app_data_dir.join("thumbnails")
+    }
+
+    /// 获取隔离区目录
+    pub fn quarantine_dir(&self) -> PathBuf {
+        self.app_data_dir.join("quarantine")
+    }
+
+    /// 获取回收站目录
+    pub fn trash_dir(&self) -> PathBuf {
+        self.app_data_dir.join("trash")
+    }
+
+    /// 获取打开画板文件时解出素材的临时目录
+    pub fn board_import_dir(&self) -> PathBuf {
+        self.app_data_dir.join("board-import")
     }
 
     /// 检查文件大小是否在限制范围内
@@ -129,16 +228,30 @@ impl FileManagerConfig {
         size <= self.max_file_size
     }
 
+    /// 按文件类别的上限检查文件大小，类别没有单独设置时使用 `max_file_size`
+    pub fn check_file_size(&self, file_path: &Path, size: u64) -> Result<()> {
+        self.size_limits.check(file_path, size, self.max_file_size)
+    }
+
+    /// 下载时还不知道文件类别，按所有类别中最大的上限接收数据
+    pub fn max_download_size(&self) -> u64 {
+        self.size_limits.largest(self.max_file_size)
+    }
+
     /// 获取相对于存储根目录的子目录路径
     /// 
     /// 按日期组织文件：YYYY/MM/DD
     pub fn get_storage_subdir(&self) -> PathBuf {
-        let now = chrono::Local::now();
-        self.storage_path.join(format!(
+        self.storage_path.join(self.storage_subdir_for_date(chrono::Local::now().date_naive()))
+    }
+
+    /// 获取指定日期对应的存储子目录（相对于存储根目录）
+    pub fn storage_subdir_for_date(&self, date: chrono::NaiveDate) -> PathBuf {
+        PathBuf::from(format!(
             "{:04}/{:02}/{:02}",
-            now.year(),
-            now.month(),
-            now.day()
+            date.year(),
+            date.month(),
+            date.day()
         ))
     }
 
@@ -183,6 +296,10 @@ mod tests {
             storage_path: PathBuf::new(),
             max_file_size: 1024,
             supported_file_types: vec!["jpg".to_string(), "png".to_string()],
+            file_type_policy: FileTypePolicy::default(),
+            storage_layout: StorageLayout::default(),
+            preview_settings: PreviewSettings::default(),
+            size_limits: SizeLimits::default(),
         };
 
         assert!(config.is_file_type_supported(Path::new("test.jpg")));
@@ -190,6 +307,32 @@ mod tests {
         assert!(!config.is_file_type_supported(Path::new("test.exe")));
     }
 
+    #[test]
+    fn test_file_type_policy_modes() {
+        let mut config = FileManagerConfig {
+            app_data_dir: PathBuf::new(),
+            database_path: PathBuf::new(),
+            storage_path: PathBuf::new(),
+            max_file_size: 1024,
+            supported_file_types: vec!["jpg".to_string()],
+            file_type_policy: FileTypePolicy {
+                mode: FileTypePolicyMode::Blocklist,
+                blocked_types: vec![".PSD".to_string(), "psd".to_string()],
+            }.normalized(),
+            storage_layout: StorageLayout::default(),
+            preview_settings: PreviewSettings::default(),
+            size_limits: SizeLimits::default(),
+        };
+        assert_eq!(config.file_type_policy.blocked_types, ["psd"]);
+        assert!(config.is_file_type_supported(Path::new("scan.dng")));
+        assert!(config.is_file_type_supported(Path::new("README")));
+        assert!(!config.is_file_type_supported(Path::new("layers.Psd")));
+
+        config.file_type_policy.mode = FileTypePolicyMode::AllowAllExceptExecutables;
+        assert!(config.is_file_type_supported(Path::new("layers.psd")));
+        assert!(!config.is_file_type_supported(Path::new("setup.EXE")));
+    }
+
     #[test]
     fn test_file_size_validation() {
         let config = FileManagerConfig {
@@ -198,6 +341,10 @@ mod tests {
             storage_path: PathBuf::new(),
             max_file_size: 1024,
             supported_file_types: vec![],
+            file_type_policy: FileTypePolicy::default(),
+            storage_layout: StorageLayout::default(),
+            preview_settings: PreviewSettings::default(),
+            size_limits: SizeLimits::default(),
         };
 
         assert!(config.is_file_size_valid(512));
@@ -213,6 +360,10 @@ mod tests {
             storage_path: PathBuf::new(),
             max_file_size: 1024,
             supported_file_types: vec![],
+            file_type_policy: FileTypePolicy::default(),
+            storage_layout: StorageLayout::default(),
+            preview_settings: PreviewSettings::default(),
+            size_limits: SizeLimits::default(),
         };
 
         let filename1 = config.generate_unique_filename("test.jpg");
@@ -5,17 +5,50 @@
 //! - 目录和文件的 CRUD 操作
 //! - 事务管理和错误处理
 //! - 数据库连接池管理
+//! - 使用 SQLCipher 按密码加密数据库文件
+//! - 由触发器维护的目录闭包表，子树查询为单次索引查询
 
+use crate::file_manager::activity::{ActivityDetail, ActivityEntry};
+use crate::file_manager::analytics::{StatsSnapshot, StorageBreakdown, UsageGroup};
+use crate::file_manager::backup::RestoreMode;
+use crate::file_manager::batch::{BatchOperation, BatchUndo, BATCH_OPERATIONS_OPERATION};
+use crate::file_manager::board_frames::{parent_first_order, BoardFrame, BoardFrameRequest, FrameKind};
+use crate::file_manager::deferred_delete::DeferredDelete;
+use crate::file_manager::directory_templates::{DirectoryTemplate, TemplateFolder};
+use crate::file_manager::dry_run::{display_path, PlannedChange};
+use crate::file_manager::duplicates::{DuplicateAction, DuplicateUndo, RESOLVE_DUPLICATES_OPERATION};
 use crate::file_manager::error::{FileManagerError, Result};
-use chrono::{DateTime, Local};
-use rusqlite::{params, Connection, Row};
+#[cfg(test)]
+use crate::file_manager::faults::FaultInjector;
+use crate::file_manager::ids::IdStrategy;
+use crate::file_manager::job_queue::{JobCheckpoint, PersistedJob, QueuedJob};
+use crate::file_manager::journal::{JournalEntry, JournalOperation};
+use crate::file_manager::metadata::{GeoBoundingBox, GeoLocation};
+use crate::file_manager::pack_store::{PackEntry, PackInfo, MAX_PACK_SIZE};
+use crate::file_manager::network_share;
+use crate::file_manager::pagination::PageCursor;
+use crate::file_manager::paths::{search_key, with_copy_suffix};
+use crate::file_manager::playlists::Playlist;
+use crate::file_manager::quarantine::QuarantinedFile;
+use crate::file_manager::trash::TrashedFile;
+use crate::file_manager::query_log;
+use crate::file_manager::retry;
+use crate::file_manager::search::{SearchHistoryEntry, MAX_SEARCH_HISTORY};
+use crate::file_manager::usage::{FileUsageStats, UsageKind};
+use crate::file_manager::watched_folders::{ImportMode, WatchedFolder};
+use crate::file_manager::working_sets::{expiry_from, WorkingSet, MAX_WORKING_SET_MEMBERS};
+use chrono::{DateTime, Local, NaiveDate};
+use rusqlite::{params, Connection, ErrorCode, Row};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use ts_rs::TS;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use std::sync::{Arc, Mutex};
-use uuid::Uuid;
 
 /// 目录信息结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct DirectoryInfo {
     pub id: String,
     pub name: String,
@@ -25,8 +58,20 @@ pub struct DirectoryInfo {
     pub updated_at: DateTime<Local>,
 }
 
+/// 目录的默认标签
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DirectoryDefaults {
+    pub directory_id: String,
+    /// 上传到该目录的文件自动添加的标签
+    pub tags: Vec<String>,
+    /// 是否同时应用到所有子目录
+    pub inherit: bool,
+}
+
 /// 文件信息结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct FileInfo {
     pub id: String,
     pub name: String,
@@ -35,13 +80,172 @@ pub struct FileInfo {
     pub file_path: String,
     pub file_size: i64,
     pub mime_type: String,
+    pub version: i64,
+    pub status: FileStatus,
+    /// EXIF 中记录的拍摄位置
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// 图像的感知哈希，尚未计算时为 `None`
+    pub perceptual_hash: Option<u64>,
+    /// 从 URL 导入的文件的来源地址
+    pub source_url: Option<String>,
+    /// 存储文件的 SHA-256，上传时记录
+    pub content_hash: Option<String>,
+    /// 评分（0-5），未评分时为 `None`
+    pub rating: Option<u8>,
+    /// 备注
+    pub notes: Option<String>,
+    /// 标题，写回图像元数据时使用
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 是否为链接文件：`file_path` 是库外原始文件的绝对路径，文件不归资料库管理
+    pub linked: bool,
+    /// 已归档文件在归档位置中的绝对路径，`None` 表示存储文件在主存储中
+    #[serde(default)]
+    pub archive_path: Option<String>,
+    /// 内容是否保存在打包文件中，按 `content_hash` 查找
+    #[serde(default)]
+    pub packed: bool,
+    /// 索引处理状态：内容哈希和拍摄位置等派生数据是否已生成
+    #[serde(default)]
+    pub processing_status: ProcessingStatus,
+    /// 处理失败时的错误信息
+    #[serde(default)]
+    pub processing_error: Option<String>,
     pub created_at: DateTime<Local>,
     pub updated_at: DateTime<Local>,
 }
 
+/// 文件状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    /// 存储文件正常
+    #[default]
+    Ok,
+    /// 存储文件已丢失
+    Broken,
+}
+
+impl FileStatus {
+    /// 获取数据库中存储的状态值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Broken => "broken",
+        }
+    }
+
+    /// 从数据库中的状态值解析，未知值视为正常
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "broken" => Self::Broken,
+            _ => Self::Ok,
+        }
+    }
+}
+
+/// 文件的索引处理状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessingStatus {
+    /// 新上传或内容已替换，尚未处理
+    #[default]
+    Pending,
+    /// 派生数据已生成
+    Indexed,
+    /// 处理失败，错误信息见 `processing_error`
+    Failed,
+}
+
+impl ProcessingStatus {
+    /// 获取数据库中存储的状态值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Indexed => "indexed",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// 从数据库中的状态值解析，未知值视为待处理
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "indexed" => Self::Indexed,
+            "failed" => Self::Failed,
+            _ => Self::Pending,
+        }
+    }
+}
+
+/// 查询工作集的语句，成员数只统计仍存在的文件
+const WORKING_SET_QUERY: &str = r#"
+    SELECT ws.id, ws.name,
+        (SELECT COUNT(*) FROM working_set_members m JOIN files f ON f.id = m.file_id WHERE m.working_set_id = ws.id),
+        ws.ttl_minutes, ws.expires_at, ws.created_at, ws.updated_at
+    FROM working_sets ws"#;
+
+/// 查询播放列表的语句，条目数只统计仍存在的文件
+const PLAYLIST_QUERY: &str = r#"
+    SELECT p.id, p.name, p.board_id,
+        (SELECT COUNT(*) FROM playlist_items i JOIN files f ON f.id = i.file_id WHERE i.playlist_id = p.id),
+        p.created_at, p.updated_at
+    FROM playlists p"#;
+
+/// 文件表查询列
+const FILE_COLUMNS: &str =
+    "id, name, original_name, directory_id, file_path, file_size, mime_type, version, status, latitude, longitude, perceptual_hash, source_url, content_hash, rating, notes, title, linked, archive_path, packed, processing_status, processing_error, created_at, updated_at";
+
+/// 内存数据库的路径标记
+const IN_MEMORY_PATH: &str = ":memory:";
+
+/// 目录自身及其所有子目录的 ID（`?1` 为目录路径），按路径索引和闭包表主键查询
+const SUBTREE_DIRECTORY_IDS: &str =
+    "SELECT c.descendant_id FROM directory_closure c JOIN directories d ON d.id = c.ancestor_id WHERE d.path = ?1";
+
+/// 以文件 ID 关联文件的表，删除文件记录时在同一事务中一起删除
+///
+/// 未启用外键约束，表定义中的 `ON DELETE CASCADE` 不会生效；监视文件夹的导入记录保留，
+/// 删除的文件不会被再次自动导入
+const FILE_DEPENDENT_TABLES: &[&str] = &["file_tags", "working_set_members", "playlist_items", "file_usage"];
+
+/// 删除文件记录及其在 [`FILE_DEPENDENT_TABLES`] 中的关联记录
+fn delete_file_rows(conn: &Connection, file_id: &str) -> rusqlite::Result<()> {
+    for table in FILE_DEPENDENT_TABLES {
+        conn.execute(&format!("DELETE FROM {} WHERE file_id = ?1", table), params![file_id])?;
+    }
+    conn.execute("DELETE FROM files WHERE id = ?1", params![file_id])?;
+    Ok(())
+}
+
+/// 按父目录关系重建目录闭包表
+///
+/// 闭包表与目录表不一致（旧版本数据库、从快照恢复）时调用；深度上限防止损坏数据中的循环引用导致死循环
+const REBUILD_DIRECTORY_CLOSURE: &str = r#"
+    DELETE FROM directory_closure;
+    WITH RECURSIVE tree (ancestor_id, descendant_id, depth) AS (
+        SELECT id, id, 0 FROM directories
+        UNION ALL
+        SELECT t.ancestor_id, d.id, t.depth + 1
+        FROM tree t JOIN directories d ON d.parent_id = t.descendant_id
+        WHERE t.depth < 1024
+    )
+    INSERT OR IGNORE INTO directory_closure (ancestor_id, descendant_id, depth)
+        SELECT ancestor_id, descendant_id, depth FROM tree;
+"#;
+
 /// 数据库服务
 pub struct DatabaseService {
     connection: Arc<Mutex<Connection>>,
+    path: PathBuf,
+    /// 数据库文件是否已加密
+    encrypted: AtomicBool,
+    /// 新记录使用的 ID 生成策略，保存为 `IdStrategy as u8`
+    id_strategy: AtomicU8,
+    #[cfg(test)]
+    faults: FaultInjector,
 }
 
 impl DatabaseService {
@@ -49,17 +253,373 @@ impl DatabaseService {
     /// 
     /// 如果数据库文件不存在，会自动创建并初始化表结构
     pub async fn new(db_path: &Path) -> Result<Self> {
-        let connection = Connection::open(db_path)
-            .map_err(FileManagerError::Database)?;
+        Self::open(db_path, None).await
+    }
+
+    /// 打开数据库，`passphrase` 为加密数据库的密码
+    ///
+    /// 数据库已加密但未提供密码时返回 [`FileManagerError::LibraryLocked`]，
+    /// 密码错误时返回 [`FileManagerError::IncorrectPassword`]
+    pub async fn open(db_path: &Path, passphrase: Option<&str>) -> Result<Self> {
+        let connection = Self::open_connection(db_path, passphrase)?;
         
         let service = Self { 
-            connection: Arc::new(Mutex::new(connection))
+            connection: Arc::new(Mutex::new(connection)),
+            path: db_path.to_path_buf(),
+            encrypted: AtomicBool::new(passphrase.is_some()),
+            id_strategy: AtomicU8::new(IdStrategy::default() as u8),
+            #[cfg(test)]
+            faults: FaultInjector::default(),
         };
         service.initialize_tables().await?;
         
         Ok(service)
     }
 
+    /// 创建内存中的数据库，供测试使用
+    ///
+    /// 数据不写入磁盘，连接关闭后丢失；不支持加密
+    #[cfg(test)]
+    pub async fn open_in_memory() -> Result<Self> {
+        let connection = Connection::open_in_memory()
+            .map_err(FileManagerError::Database)?;
+        retry::install(&connection).map_err(FileManagerError::Database)?;
+        query_log::install(&connection);
+
+        let service = Self {
+            connection: Arc::new(Mutex::new(connection)),
+            path: PathBuf::from(IN_MEMORY_PATH),
+            encrypted: AtomicBool::new(false),
+            id_strategy: AtomicU8::new(IdStrategy::default() as u8),
+            faults: FaultInjector::default(),
+        };
+        service.initialize_tables().await?;
+
+        Ok(service)
+    }
+
+    /// 故障注入器，与文件系统服务的注入器相互独立
+    #[cfg(test)]
+    pub fn faults(&self) -> &FaultInjector {
+        &self.faults
+    }
+
+    /// 新记录使用的 ID 生成策略
+    pub fn id_strategy(&self) -> IdStrategy {
+        IdStrategy::from_u8(self.id_strategy.load(Ordering::Relaxed))
+    }
+
+    /// 切换 ID 生成策略，只影响之后创建的记录
+    pub fn set_id_strategy(&self, strategy: IdStrategy) {
+        self.id_strategy.store(strategy as u8, Ordering::Relaxed);
+    }
+
+    /// 按当前策略生成新记录的 ID
+    pub fn generate_id(&self) -> String {
+        self.id_strategy().generate()
+    }
+
+    /// 是否为内存中的数据库
+    pub fn is_in_memory(&self) -> bool {
+        self.path == Path::new(IN_MEMORY_PATH)
+    }
+
+    /// 打开连接并读取一次表结构，确认密码正确
+    fn open_connection(db_path: &Path, passphrase: Option<&str>) -> Result<Connection> {
+        let connection = Connection::open(db_path)
+            .map_err(FileManagerError::Database)?;
+        retry::install(&connection).map_err(FileManagerError::Database)?;
+        if let Some(passphrase) = passphrase {
+            connection.pragma_update(None, "key", passphrase)
+                .map_err(FileManagerError::Database)?;
+        }
+
+        match connection.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(())) {
+            Ok(()) => {
+                network_share::configure_connection(&connection).map_err(FileManagerError::Database)?;
+                query_log::install(&connection);
+                Ok(connection)
+            }
+            Err(rusqlite::Error::SqliteFailure(error, _)) if error.code == ErrorCode::NotADatabase => {
+                match passphrase {
+                    Some(_) => Err(FileManagerError::IncorrectPassword),
+                    None => Err(FileManagerError::LibraryLocked),
+                }
+            }
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    /// 数据库文件是否已加密
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted.load(Ordering::Relaxed)
+    }
+
+    /// 加密未加密的数据库
+    ///
+    /// 先导出一份加密副本，关闭连接后替换原文件，再用密码重新打开
+    pub async fn encrypt(&self, passphrase: &str) -> Result<()> {
+        if self.is_encrypted() {
+            return Err(FileManagerError::general_error("Library is already encrypted"));
+        }
+        if self.is_in_memory() {
+            return Err(FileManagerError::general_error("In-memory database cannot be encrypted"));
+        }
+
+        let file_name = self.path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let encrypted_path = self.path.with_file_name(format!(".{}.encrypting", file_name));
+        if encrypted_path.exists() {
+            std::fs::remove_file(&encrypted_path)?;
+        }
+
+        let mut conn = self.connection.lock().unwrap();
+        conn.execute(
+            "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+            params![encrypted_path.to_string_lossy(), passphrase],
+        ).map_err(FileManagerError::Database)?;
+        let exported = conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()));
+        conn.execute("DETACH DATABASE encrypted", []).map_err(FileManagerError::Database)?;
+        if let Err(e) = exported {
+            let _ = std::fs::remove_file(&encrypted_path);
+            return Err(FileManagerError::Database(e));
+        }
+
+        // 替换文件前必须关闭原连接（Windows 不允许替换已打开的文件）
+        let previous = std::mem::replace(
+            &mut *conn,
+            Connection::open_in_memory().map_err(FileManagerError::Database)?,
+        );
+        previous.close().map_err(|(_, e)| FileManagerError::Database(e))?;
+
+        if let Err(e) = std::fs::rename(&encrypted_path, &self.path) {
+            *conn = Self::open_connection(&self.path, None)?;
+            let _ = std::fs::remove_file(&encrypted_path);
+            return Err(FileManagerError::FileSystem(e));
+        }
+
+        *conn = Self::open_connection(&self.path, Some(passphrase))?;
+        self.encrypted.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// 修改加密数据库的密码，旧密码不正确时返回 [`FileManagerError::IncorrectPassword`]
+    pub async fn change_passphrase(&self, old_passphrase: &str, new_passphrase: &str) -> Result<()> {
+        if !self.is_encrypted() {
+            return Err(FileManagerError::general_error("Library is not encrypted"));
+        }
+
+        // 用旧密码单独打开一次，确认密码正确
+        Self::open_connection(&self.path, Some(old_passphrase))?;
+
+        let conn = self.connection.lock().unwrap();
+        conn.pragma_update(None, "rekey", new_passphrase)
+            .map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 将数据库快照写入 `target`（不能已存在）
+    ///
+    /// 使用 `VACUUM INTO`，快照与当前库使用相同的密码
+    pub async fn snapshot_to(&self, target: &Path) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute("VACUUM INTO ?1", params![target.to_string_lossy()])
+            .map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 从备份的数据库快照恢复，返回写入的文件 ID
+    ///
+    /// 完整恢复清空所有表后写入快照中的内容；合并恢复只添加当前库中没有的目录、文件及其标签，
+    /// 路径已存在的目录沿用当前库中的目录。所有修改在一个事务中完成
+    pub async fn restore_snapshot(&self, snapshot: &Path, encrypted: bool, mode: RestoreMode) -> Result<Vec<String>> {
+        if encrypted && !self.is_encrypted() {
+            return Err(FileManagerError::general_error(
+                "Encrypted backups can only be restored into a library encrypted with the same password",
+            ));
+        }
+
+        self.with_snapshot_attached(snapshot, encrypted, |conn| match mode {
+            RestoreMode::Full => Self::replace_from_snapshot(conn, &[]),
+            RestoreMode::Merge => Self::merge_from_snapshot(conn),
+        })
+    }
+
+    /// 用目录快照替换当前库的内容，返回快照中的文件 ID
+    ///
+    /// 应用状态、预写日志、任务队列、延迟删除队列和回收站保留当前内容，已结束的任务不会重新出现，
+    /// 待删除的存储文件和回收站中的文件也不会被遗忘；打包文件索引同样保留，与磁盘上的打包文件一致，
+    /// 恢复的文件记录的打包标记由调用方按索引校正；快照与当前库使用相同的密码
+    pub async fn rollback_to_snapshot(&self, snapshot: &Path) -> Result<Vec<String>> {
+        self.with_snapshot_attached(snapshot, self.is_encrypted(), |conn| {
+            Self::replace_from_snapshot(
+                conn,
+                &[
+                    "app_state", "operation_journal", "job_queue", "job_outputs", "deferred_deletes", "trashed_files",
+                    "packs", "pack_entries",
+                ],
+            )
+        })
+    }
+
+    /// 统计目录快照中的文件数
+    pub async fn count_snapshot_files(&self, snapshot: &Path) -> Result<usize> {
+        self.with_snapshot_attached(snapshot, self.is_encrypted(), |conn| {
+            conn.query_row("SELECT COUNT(*) FROM backup.files", [], |row| row.get::<_, i64>(0))
+                .map(|count| count as usize)
+        })
+    }
+
+    /// 以 `backup` 为名附加快照数据库后执行 `f`，完成后分离
+    fn with_snapshot_attached<T>(
+        &self,
+        snapshot: &Path,
+        encrypted: bool,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+    ) -> Result<T> {
+        let mut conn = self.connection.lock().unwrap();
+        // 未加密的快照需要显式指定空密码，否则会沿用当前库的密码
+        let attach = if encrypted { "ATTACH DATABASE ?1 AS backup" } else { "ATTACH DATABASE ?1 AS backup KEY ''" };
+        conn.execute(attach, params![snapshot.to_string_lossy()])
+            .map_err(FileManagerError::Database)?;
+
+        let result = f(&mut conn);
+        conn.execute("DETACH DATABASE backup", []).map_err(FileManagerError::Database)?;
+
+        result.map_err(|e| match e {
+            rusqlite::Error::SqliteFailure(error, _) if error.code == ErrorCode::NotADatabase => {
+                FileManagerError::IncorrectPassword
+            }
+            e => FileManagerError::Database(e),
+        })
+    }
+
+    /// 用快照替换所有表的内容，只复制两边都有的列（兼容旧版本的快照）；`keep` 中的表不修改
+    fn replace_from_snapshot(conn: &mut Connection, keep: &[&str]) -> rusqlite::Result<Vec<String>> {
+        let tx = conn.transaction()?;
+        let backup_tables = Self::table_names(&tx, "backup")?;
+
+        for table in Self::table_names(&tx, "main")? {
+            if keep.contains(&table.as_str()) {
+                continue;
+            }
+            tx.execute(&format!("DELETE FROM main.\"{}\"", table), [])?;
+            if !backup_tables.contains(&table) {
+                continue;
+            }
+
+            let backup_columns = Self::column_names(&tx, "backup", &table)?;
+            let columns = Self::column_names(&tx, "main", &table)?
+                .into_iter()
+                .filter(|column| backup_columns.contains(column))
+                .map(|column| format!("\"{}\"", column))
+                .collect::<Vec<_>>()
+                .join(", ");
+            tx.execute(
+                &format!("INSERT INTO main.\"{0}\" ({1}) SELECT {1} FROM backup.\"{0}\"", table, columns),
+                [],
+            )?;
+        }
+
+        // 逐表复制时触发器按插入顺序维护的闭包表不完整，按恢复后的目录重建
+        tx.execute_batch(REBUILD_DIRECTORY_CLOSURE)?;
+
+        let file_ids = {
+            let mut stmt = tx.prepare("SELECT id FROM main.files")?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<Vec<String>>>()?
+        };
+        tx.commit()?;
+        Ok(file_ids)
+    }
+
+    /// 添加快照中当前库没有的目录、文件和标签
+    ///
+    /// 存储路径已被当前库中其他文件占用的文件跳过
+    fn merge_from_snapshot(conn: &mut Connection) -> rusqlite::Result<Vec<String>> {
+        let tx = conn.transaction()?;
+
+        // 备份中的目录 -> 当前库中的目录：优先按路径对应，其次按 ID（目录已改名）
+        tx.execute_batch(
+            r#"
+            CREATE TEMP TABLE restore_directory_map (backup_id TEXT PRIMARY KEY, main_id TEXT NOT NULL);
+            INSERT INTO restore_directory_map
+                SELECT b.id, m.id FROM backup.directories b JOIN main.directories m ON m.path = b.path;
+            INSERT OR IGNORE INTO restore_directory_map
+                SELECT b.id, b.id FROM backup.directories b WHERE b.id IN (SELECT id FROM main.directories);
+
+            INSERT INTO main.directories (id, name, parent_id, path, created_at, updated_at)
+                SELECT b.id, b.name,
+                       COALESCE((SELECT main_id FROM restore_directory_map WHERE backup_id = b.parent_id), b.parent_id),
+                       b.path, b.created_at, b.updated_at
+                FROM backup.directories b
+                WHERE b.id NOT IN (SELECT backup_id FROM restore_directory_map);
+
+            CREATE TEMP TABLE restore_new_files (id TEXT PRIMARY KEY);
+            INSERT INTO restore_new_files
+                SELECT id FROM backup.files
+                WHERE id NOT IN (SELECT id FROM main.files)
+                  AND file_path NOT IN (SELECT file_path FROM main.files);
+            "#,
+        )?;
+
+        let backup_columns = Self::column_names(&tx, "backup", "files")?;
+        let columns = Self::column_names(&tx, "main", "files")?
+            .into_iter()
+            .filter(|column| backup_columns.contains(column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        tx.execute(
+            &format!(
+                "INSERT INTO main.files ({0}) SELECT {0} FROM backup.files WHERE id IN (SELECT id FROM restore_new_files)",
+                columns,
+            ),
+            [],
+        )?;
+
+        tx.execute_batch(
+            r#"
+            UPDATE main.files
+                SET directory_id = (SELECT main_id FROM restore_directory_map WHERE backup_id = main.files.directory_id)
+                WHERE id IN (SELECT id FROM restore_new_files)
+                  AND directory_id IN (SELECT backup_id FROM restore_directory_map);
+
+            INSERT OR IGNORE INTO main.file_tags (file_id, tag, created_at)
+                SELECT file_id, tag, created_at FROM backup.file_tags
+                WHERE file_id IN (SELECT id FROM restore_new_files);
+            "#,
+        )?;
+
+        let file_ids = {
+            let mut stmt = tx.prepare("SELECT id FROM restore_new_files")?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<Vec<String>>>()?
+        };
+        tx.execute_batch("DROP TABLE temp.restore_directory_map; DROP TABLE temp.restore_new_files;")?;
+        // 快照中的目录不一定按父目录在前的顺序插入
+        tx.execute_batch(REBUILD_DIRECTORY_CLOSURE)?;
+        tx.commit()?;
+        Ok(file_ids)
+    }
+
+    /// 获取指定数据库中的表名
+    fn table_names(conn: &Connection, schema: &str) -> rusqlite::Result<Vec<String>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT name FROM {}.sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+            schema,
+        ))?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// 获取指定数据库中表的列名
+    fn column_names(conn: &Connection, schema: &str, table: &str) -> rusqlite::Result<Vec<String>> {
+        let mut stmt = conn.prepare(&format!("PRAGMA {}.table_info(\"{}\")", schema, table))?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>("name"))?;
+        rows.collect()
+    }
+
     /// 初始化数据库表结构
     async fn initialize_tables(&self) -> Result<()> {
         let conn = self.connection.lock().unwrap();
@@ -99,6 +659,28 @@ impl DatabaseService {
             [],
         ).map_err(FileManagerError::Database)?;
 
+        // 旧版本数据库升级：补充后续版本新增的列
+        Self::ensure_column(&conn, "files", "version", "INTEGER NOT NULL DEFAULT 1")?;
+        Self::ensure_column(&conn, "files", "status", "TEXT NOT NULL DEFAULT 'ok'")?;
+        Self::ensure_column(&conn, "files", "latitude", "REAL")?;
+        Self::ensure_column(&conn, "files", "longitude", "REAL")?;
+        Self::ensure_column(&conn, "files", "perceptual_hash", "INTEGER")?;
+        Self::ensure_column(&conn, "files", "source_url", "TEXT")?;
+        Self::ensure_column(&conn, "files", "content_hash", "TEXT")?;
+        Self::ensure_column(&conn, "files", "verified_at", "TEXT")?;
+        Self::ensure_column(&conn, "files", "rating", "INTEGER")?;
+        Self::ensure_column(&conn, "files", "notes", "TEXT")?;
+        Self::ensure_column(&conn, "files", "linked", "INTEGER NOT NULL DEFAULT 0")?;
+        Self::ensure_column(&conn, "files", "archive_path", "TEXT")?;
+        Self::ensure_column(&conn, "files", "packed", "INTEGER NOT NULL DEFAULT 0")?;
+        if Self::ensure_column(&conn, "files", "processing_status", "TEXT NOT NULL DEFAULT 'pending'")? {
+            // 已有内容哈希的旧文件在上传时已处理过，其余的留待重新处理
+            conn.execute("UPDATE files SET processing_status = 'indexed' WHERE content_hash IS NOT NULL", [])
+                .map_err(FileManagerError::Database)?;
+        }
+        Self::ensure_column(&conn, "files", "processing_error", "TEXT")?;
+        Self::ensure_column(&conn, "files", "title", "TEXT")?;
+
         // 创建索引以提高查询性能
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_directories_parent_id ON directories (parent_id)",
@@ -110,221 +692,3738 @@ impl DatabaseService {
             [],
         ).map_err(FileManagerError::Database)?;
 
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_files_location ON files (latitude, longitude) WHERE latitude IS NOT NULL",
+            [],
+        ).map_err(FileManagerError::Database)?;
+
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_directories_path ON directories (path)",
             [],
         ).map_err(FileManagerError::Database)?;
 
-        Ok(())
-    }
+        // 等待索引的文件通常很少，部分索引使资料库概要中的计数不必扫描整个文件表
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_files_processing_pending ON files (processing_status) WHERE processing_status = 'pending'",
+            [],
+        ).map_err(FileManagerError::Database)?;
 
-    /// 创建目录
-    pub async fn create_directory(
-        &self,
-        name: &str,
-        parent_id: Option<&str>,
-        path: &str,
-    ) -> Result<DirectoryInfo> {
-        let id = Uuid::new_v4().to_string();
-        let now = Local::now();
-        
-        let conn = self.connection.lock().unwrap();
+        // 创建文件标签表
         conn.execute(
             r#"
-            INSERT INTO directories (id, name, parent_id, path, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            CREATE TABLE IF NOT EXISTS file_tags (
+                file_id TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (file_id, tag),
+                FOREIGN KEY (file_id) REFERENCES files (id) ON DELETE CASCADE
+            )
             "#,
-            params![
-                id,
-                name,
-                parent_id,
-                path,
-                now.to_rfc3339(),
-                now.to_rfc3339()
-            ],
+            [],
         ).map_err(FileManagerError::Database)?;
 
-        Ok(DirectoryInfo {
-            id,
-            name: name.to_string(),
-            parent_id: parent_id.map(|s| s.to_string()),
-            path: path.to_string(),
-            created_at: now,
-            updated_at: now,
-        })
-    }
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_file_tags_tag ON file_tags (tag)",
+            [],
+        ).map_err(FileManagerError::Database)?;
 
-    /// 获取目录信息
-    pub async fn get_directory(&self, id: &str) -> Result<Option<DirectoryInfo>> {
-        let conn = self.connection.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, parent_id, path, created_at, updated_at FROM directories WHERE id = ?1"
+        // 创建监视文件夹表
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS watched_folders (
+                id TEXT PRIMARY KEY,
+                path TEXT NOT NULL UNIQUE,
+                target_directory_id TEXT,
+                default_tags TEXT NOT NULL DEFAULT '[]',
+                import_mode TEXT NOT NULL DEFAULT 'copy',
+                created_at TEXT NOT NULL
+            )
+            "#,
+            [],
         ).map_err(FileManagerError::Database)?;
 
-        let result = stmt.query_row(params![id], |row| {
-            Ok(self.row_to_directory_info(row)?)
-        });
+        // 创建监视文件夹已导入文件表（复制模式下避免重复导入）
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS watched_folder_imports (
+                folder_id TEXT NOT NULL,
+                source_path TEXT NOT NULL,
+                file_size INTEGER NOT NULL,
+                modified_at INTEGER NOT NULL,
+                file_id TEXT NOT NULL,
+                PRIMARY KEY (folder_id, source_path),
+                FOREIGN KEY (folder_id) REFERENCES watched_folders (id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
 
-        match result {
-            Ok(dir) => Ok(Some(dir)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(FileManagerError::Database(e)),
-        }
-    }
+        // 创建目录默认标签表
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS directory_defaults (
+                directory_id TEXT PRIMARY KEY,
+                tags TEXT NOT NULL DEFAULT '[]',
+                inherit INTEGER NOT NULL DEFAULT 0,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (directory_id) REFERENCES directories (id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
 
-    /// 获取子目录列表
-    pub async fn get_child_directories(&self, parent_id: Option<&str>) -> Result<Vec<DirectoryInfo>> {
-        let conn = self.connection.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, parent_id, path, created_at, updated_at FROM directories WHERE parent_id IS ?1 ORDER BY name"
+        // 创建隔离区文件表
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS quarantined_files (
+                id TEXT PRIMARY KEY,
+                original_name TEXT NOT NULL,
+                directory_id TEXT,
+                file_size INTEGER NOT NULL,
+                reason TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            [],
         ).map_err(FileManagerError::Database)?;
 
-        let rows = stmt.query_map(params![parent_id], |row| {
-            Ok(self.row_to_directory_info(row)?)
+        // 创建回收站文件表
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS trashed_files (
+                id TEXT PRIMARY KEY,
+                file_id TEXT NOT NULL,
+                original_name TEXT NOT NULL,
+                directory_id TEXT NOT NULL,
+                file_size INTEGER NOT NULL,
+                mime_type TEXT NOT NULL,
+                tags TEXT NOT NULL DEFAULT '[]',
+                deleted_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建延迟删除队列表（被其他进程占用、暂时无法删除的存储文件）
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS deferred_deletes (
+                file_path TEXT PRIMARY KEY,
+                file_id TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                last_error TEXT NOT NULL,
+                queued_at TEXT NOT NULL,
+                next_attempt_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建目录大小缓存表（递归大小，文件或目录变化时由触发器清空）
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS directory_size_cache (
+                directory_id TEXT PRIMARY KEY,
+                total_size INTEGER NOT NULL,
+                total_files INTEGER NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        conn.execute_batch(
+            r#"
+            CREATE TRIGGER IF NOT EXISTS trg_files_insert_invalidate_size AFTER INSERT ON files
+            BEGIN DELETE FROM directory_size_cache; END;
+            CREATE TRIGGER IF NOT EXISTS trg_files_delete_invalidate_size AFTER DELETE ON files
+            BEGIN DELETE FROM directory_size_cache; END;
+            CREATE TRIGGER IF NOT EXISTS trg_files_update_invalidate_size AFTER UPDATE OF file_size, directory_id ON files
+            BEGIN DELETE FROM directory_size_cache; END;
+            CREATE TRIGGER IF NOT EXISTS trg_directories_insert_invalidate_size AFTER INSERT ON directories
+            BEGIN DELETE FROM directory_size_cache; END;
+            CREATE TRIGGER IF NOT EXISTS trg_directories_delete_invalidate_size AFTER DELETE ON directories
+            BEGIN DELETE FROM directory_size_cache; END;
+            CREATE TRIGGER IF NOT EXISTS trg_directories_update_invalidate_size AFTER UPDATE OF path ON directories
+            BEGIN DELETE FROM directory_size_cache; END;
+            "#,
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建目录闭包表（每个目录与其自身及所有祖先目录各一行，由触发器在创建、移动、删除目录时维护）
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS directory_closure (
+                ancestor_id TEXT NOT NULL,
+                descendant_id TEXT NOT NULL,
+                depth INTEGER NOT NULL,
+                PRIMARY KEY (ancestor_id, descendant_id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_directory_closure_descendant ON directory_closure (descendant_id);
+
+            CREATE TRIGGER IF NOT EXISTS trg_directories_insert_closure AFTER INSERT ON directories
+            BEGIN
+                INSERT OR IGNORE INTO directory_closure (ancestor_id, descendant_id, depth)
+                    SELECT ancestor_id, NEW.id, depth + 1 FROM directory_closure WHERE descendant_id = NEW.parent_id
+                    UNION ALL SELECT NEW.id, NEW.id, 0;
+            END;
+            CREATE TRIGGER IF NOT EXISTS trg_directories_delete_closure AFTER DELETE ON directories
+            BEGIN
+                DELETE FROM directory_closure WHERE descendant_id = OLD.id OR ancestor_id = OLD.id;
+            END;
+            CREATE TRIGGER IF NOT EXISTS trg_directories_move_closure AFTER UPDATE OF parent_id ON directories
+            WHEN OLD.parent_id IS NOT NEW.parent_id
+            BEGIN
+                DELETE FROM directory_closure
+                WHERE descendant_id IN (SELECT descendant_id FROM directory_closure WHERE ancestor_id = NEW.id)
+                  AND ancestor_id NOT IN (SELECT descendant_id FROM directory_closure WHERE ancestor_id = NEW.id);
+                INSERT OR IGNORE INTO directory_closure (ancestor_id, descendant_id, depth)
+                    SELECT p.ancestor_id, c.descendant_id, p.depth + c.depth + 1
+                    FROM directory_closure p, directory_closure c
+                    WHERE p.descendant_id = NEW.parent_id AND c.ancestor_id = NEW.id;
+            END;
+            "#,
+        ).map_err(FileManagerError::Database)?;
+
+        // 旧版本数据库没有闭包表，或闭包表缺少部分目录时重建
+        let (closure_count, directory_count): (i64, i64) = conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM directory_closure WHERE depth = 0), (SELECT COUNT(*) FROM directories)",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).map_err(FileManagerError::Database)?;
+        if closure_count != directory_count {
+            tracing::info!("重建目录闭包表: {} 个目录", directory_count);
+            conn.execute_batch(REBUILD_DIRECTORY_CLOSURE).map_err(FileManagerError::Database)?;
+        }
+
+        // 创建操作日志表（多步操作的预写日志）
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS operation_journal (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                operation_type TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+        // 执行失败的操作标记为 failed，保留以便排查，启动时不重放
+        Self::ensure_column(&conn, "operation_journal", "status", "TEXT NOT NULL DEFAULT 'pending'")?;
+        Self::ensure_column(&conn, "operation_journal", "error", "TEXT")?;
+
+        // 创建撤销条目表（可撤销的批量操作，记录恢复所需的数据）
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS undo_entries (
+                id TEXT PRIMARY KEY,
+                operation_type TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建搜索历史表，按规范化后的关键词去重
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS search_history (
+                query_key TEXT PRIMARY KEY,
+                query TEXT NOT NULL,
+                use_count INTEGER NOT NULL DEFAULT 1,
+                last_used_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建统计快照表，每天一行，按 MIME 类型的分组以 JSON 保存
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS stats_snapshots (
+                date TEXT PRIMARY KEY,
+                total_files INTEGER NOT NULL,
+                total_bytes INTEGER NOT NULL,
+                by_mime_type TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建工作集表，过期时间为 Unix 时间戳（秒），便于比较
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS working_sets (
+                id TEXT PRIMARY KEY,
+                name TEXT,
+                ttl_minutes INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS working_set_members (
+                working_set_id TEXT NOT NULL,
+                file_id TEXT NOT NULL,
+                added_at TEXT NOT NULL,
+                PRIMARY KEY (working_set_id, file_id),
+                FOREIGN KEY (working_set_id) REFERENCES working_sets (id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建播放列表表，条目按 position 排序，同一文件可以出现多次
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS playlists (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                board_id TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS playlist_items (
+                playlist_id TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                file_id TEXT NOT NULL,
+                PRIMARY KEY (playlist_id, position),
+                FOREIGN KEY (playlist_id) REFERENCES playlists (id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建画板框架表，框架直接包含的元素按 position 排序，一个元素只属于一个框架
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS board_frames (
+                id TEXT PRIMARY KEY,
+                board_id TEXT NOT NULL,
+                parent_id TEXT,
+                kind TEXT NOT NULL,
+                name TEXT NOT NULL,
+                x REAL NOT NULL,
+                y REAL NOT NULL,
+                width REAL NOT NULL,
+                height REAL NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_board_frames_board ON board_frames (board_id)",
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS board_frame_items (
+                board_id TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                frame_id TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                PRIMARY KEY (board_id, item_id),
+                FOREIGN KEY (frame_id) REFERENCES board_frames (id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建打包文件表和打包内容索引表
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS packs (
+                id TEXT PRIMARY KEY,
+                size INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS pack_entries (
+                content_hash TEXT PRIMARY KEY,
+                pack_id TEXT NOT NULL,
+                offset INTEGER NOT NULL,
+                length INTEGER NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建目录模板表，文件夹结构以 JSON 保存
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS directory_templates (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                folders TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建文件使用统计表，未记录画板的使用 board_id 为空字符串
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS file_usage (
+                file_id TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                board_id TEXT NOT NULL DEFAULT '',
+                count INTEGER NOT NULL DEFAULT 0,
+                last_used_at TEXT NOT NULL,
+                PRIMARY KEY (file_id, kind, board_id)
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建标签使用统计表，文件删除后保留
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS tag_usage (
+                tag TEXT PRIMARY KEY,
+                count INTEGER NOT NULL DEFAULT 0,
+                last_used_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建任务队列表（可恢复的批量任务，结束时删除，启动时仍存在的即为被中断的任务）
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS job_queue (
+                id TEXT PRIMARY KEY,
+                job TEXT NOT NULL,
+                checkpoint TEXT NOT NULL DEFAULT '{}',
+                state TEXT NOT NULL DEFAULT 'running',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建任务输出表（导出已写入的目标文件，恢复时计入导出结果）
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS job_outputs (
+                job_id TEXT NOT NULL,
+                path TEXT NOT NULL,
+                PRIMARY KEY (job_id, path)
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        // 创建应用状态表（键值对，用于记录运行状态标记）
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS app_state (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 确保表中存在指定列，不存在时追加
+    ///
+    /// 用于在不破坏已有数据的前提下升级旧版本创建的数据库
+    fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<bool> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))
+            .map_err(FileManagerError::Database)?;
+        let exists = stmt.query_map([], |row| row.get::<_, String>("name"))
+            .map_err(FileManagerError::Database)?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+
+        if !exists {
+            conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            ).map_err(FileManagerError::Database)?;
+        }
+
+        Ok(!exists)
+    }
+
+    /// 读取应用状态值
+    pub async fn get_state(&self, key: &str) -> Result<Option<String>> {
+        let conn = self.connection.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT value FROM app_state WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        );
+
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    /// 写入应用状态值
+    pub async fn set_state(&self, key: &str, value: &str) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO app_state (key, value, updated_at) VALUES (?1, ?2, ?3)
+            ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+            "#,
+            params![key, value, Local::now().to_rfc3339()],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 设置连接是否只允许查询，开启后任何写入都返回错误
+    pub async fn set_query_only(&self, query_only: bool) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.pragma_update(None, "query_only", query_only)
+            .map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 将挂起的写入刷新到数据库文件
+    ///
+    /// 在 WAL 模式下执行检查点，非 WAL 模式下该操作无副作用
+    pub async fn flush(&self) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+            .map_err(FileManagerError::Database)?;
+        conn.execute_batch("PRAGMA optimize")
+            .map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 创建目录
+    pub async fn create_directory(
+        &self,
+        name: &str,
+        parent_id: Option<&str>,
+        path: &str,
+    ) -> Result<DirectoryInfo> {
+        let id = self.generate_id();
+        let now = Local::now();
+        
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO directories (id, name, parent_id, path, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![
+                id,
+                name,
+                parent_id,
+                path,
+                now.to_rfc3339(),
+                now.to_rfc3339()
+            ],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(DirectoryInfo {
+            id,
+            name: name.to_string(),
+            parent_id: parent_id.map(|s| s.to_string()),
+            path: path.to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// 获取目录信息
+    pub async fn get_directory(&self, id: &str) -> Result<Option<DirectoryInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, parent_id, path, created_at, updated_at FROM directories WHERE id = ?1"
+        ).map_err(FileManagerError::Database)?;
+
+        let result = stmt.query_row(params![id], |row| {
+            Ok(self.row_to_directory_info(row)?)
+        });
+
+        match result {
+            Ok(dir) => Ok(Some(dir)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    /// 保存目录的默认标签，标签为空且不继承时删除设置
+    pub async fn set_directory_defaults(&self, defaults: &DirectoryDefaults) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        if defaults.tags.is_empty() && !defaults.inherit {
+            conn.execute("DELETE FROM directory_defaults WHERE directory_id = ?1", params![defaults.directory_id])
+                .map_err(FileManagerError::Database)?;
+            return Ok(());
+        }
+
+        conn.execute(
+            r#"
+            INSERT INTO directory_defaults (directory_id, tags, inherit, updated_at) VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(directory_id) DO UPDATE SET tags = ?2, inherit = ?3, updated_at = ?4
+            "#,
+            params![
+                defaults.directory_id,
+                serde_json::to_string(&defaults.tags)?,
+                defaults.inherit,
+                Local::now().to_rfc3339()
+            ],
+        ).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 获取目录的默认标签设置，未设置时返回空设置
+    pub async fn get_directory_defaults(&self, directory_id: &str) -> Result<DirectoryDefaults> {
+        let conn = self.connection.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT tags, inherit FROM directory_defaults WHERE directory_id = ?1",
+            params![directory_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)),
+        );
+
+        match result {
+            Ok((tags, inherit)) => Ok(DirectoryDefaults {
+                directory_id: directory_id.to_string(),
+                tags: serde_json::from_str(&tags)?,
+                inherit,
+            }),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(DirectoryDefaults {
+                directory_id: directory_id.to_string(),
+                ..Default::default()
+            }),
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    /// 计算上传到目录时应添加的默认标签
+    ///
+    /// 包括目录自身的默认标签和设置了继承的上级目录的默认标签，近的在前
+    pub async fn get_effective_default_tags(&self, directory: &DirectoryInfo) -> Result<Vec<String>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT dd.tags FROM directory_defaults dd
+            JOIN directories d ON d.id = dd.directory_id
+            WHERE d.id = ?1 OR (dd.inherit = 1 AND substr(?2, 1, length(d.path) + 1) = d.path || '/')
+            ORDER BY length(d.path) DESC
+            "#
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![directory.id, directory.path], |row| row.get::<_, String>(0))
+            .map_err(FileManagerError::Database)?;
+
+        let mut tags: Vec<String> = Vec::new();
+        for row in rows {
+            let directory_tags: Vec<String> = serde_json::from_str(&row.map_err(FileManagerError::Database)?)?;
+            for tag in directory_tags {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        }
+        Ok(tags)
+    }
+
+    /// 获取子目录列表
+    pub async fn get_child_directories(&self, parent_id: Option<&str>) -> Result<Vec<DirectoryInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, parent_id, path, created_at, updated_at FROM directories WHERE parent_id IS ?1 ORDER BY name"
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![parent_id], |row| {
+            Ok(self.row_to_directory_info(row)?)
+        }).map_err(FileManagerError::Database)?;
+
+        let mut directories = Vec::new();
+        for row in rows {
+            directories.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(directories)
+    }
+
+    /// 删除目录（级联删除子目录和文件）
+    ///
+    /// 未启用外键约束，因此按目录闭包表在一个事务中显式删除整个子树的记录
+    pub async fn delete_directory(&self, id: &str) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+
+        let path: Option<String> = match tx.query_row(
+            "SELECT path FROM directories WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        ) {
+            Ok(path) => Some(path),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(FileManagerError::Database(e)),
+        };
+
+        if let Some(path) = path {
+            for table in FILE_DEPENDENT_TABLES {
+                tx.execute(
+                    &format!(
+                        "DELETE FROM {} WHERE file_id IN (SELECT id FROM files WHERE directory_id IN ({}))",
+                        table, SUBTREE_DIRECTORY_IDS,
+                    ),
+                    params![path],
+                ).map_err(FileManagerError::Database)?;
+            }
+
+            tx.execute(
+                &format!("DELETE FROM files WHERE directory_id IN ({})", SUBTREE_DIRECTORY_IDS),
+                params![path],
+            ).map_err(FileManagerError::Database)?;
+
+            tx.execute(
+                &format!("DELETE FROM directory_defaults WHERE directory_id IN ({})", SUBTREE_DIRECTORY_IDS),
+                params![path],
+            ).map_err(FileManagerError::Database)?;
+
+            tx.execute(
+                &format!("DELETE FROM directories WHERE id IN ({})", SUBTREE_DIRECTORY_IDS),
+                params![path],
+            ).map_err(FileManagerError::Database)?;
+        }
+
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 统计目录子树中的文件数和子目录数（不含目录自身）
+    pub async fn count_directory_contents(&self, path: &str) -> Result<(i64, i64)> {
+        let conn = self.connection.lock().unwrap();
+
+        let file_count: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM files WHERE directory_id IN ({})", SUBTREE_DIRECTORY_IDS),
+            params![path],
+            |row| row.get(0),
+        ).map_err(FileManagerError::Database)?;
+
+        let directory_count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM directory_closure c JOIN directories d ON d.id = c.ancestor_id WHERE d.path = ?1 AND c.depth > 0",
+            params![path],
+            |row| row.get(0),
+        ).map_err(FileManagerError::Database)?;
+
+        Ok((file_count, directory_count))
+    }
+
+    /// 获取目录子树中的所有文件
+    pub async fn get_files_in_subtree(&self, path: &str) -> Result<Vec<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            &format!(
+                "SELECT {} FROM files WHERE directory_id IN ({}) ORDER BY name",
+                FILE_COLUMNS, SUBTREE_DIRECTORY_IDS,
+            )
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![path], |row| self.row_to_file_info(row)).map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(files)
+    }
+
+    /// 获取目录子树中的一批文件，供分批删除使用
+    ///
+    /// 按目录 ID 经闭包表选取，目录被移动或改名后仍是原来的子树；只选取 `updated_before` 之前
+    /// 最后修改的文件，删除期间上传或移入的文件不会被选中
+    pub async fn get_subtree_file_batch(
+        &self,
+        directory_id: &str,
+        updated_before: &DateTime<Local>,
+        limit: usize,
+    ) -> Result<Vec<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            &format!(
+                r#"
+                SELECT {} FROM files
+                WHERE directory_id IN (SELECT descendant_id FROM directory_closure WHERE ancestor_id = ?1)
+                  AND updated_at <= ?2
+                LIMIT ?3
+                "#,
+                FILE_COLUMNS,
+            )
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![directory_id, updated_before.to_rfc3339(), limit as i64], |row| {
+            self.row_to_file_info(row)
+        })
+            .map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(files)
+    }
+
+    /// 获取目录子树中的所有目录（含目录自身），子目录在前
+    pub async fn get_directories_in_subtree(&self, path: &str) -> Result<Vec<DirectoryInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            &format!(
+                "SELECT id, name, parent_id, path, created_at, updated_at FROM directories WHERE id IN ({}) ORDER BY path DESC",
+                SUBTREE_DIRECTORY_IDS,
+            )
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![path], |row| self.row_to_directory_info(row))
+            .map_err(FileManagerError::Database)?;
+
+        let mut directories = Vec::new();
+        for row in rows {
+            directories.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(directories)
+    }
+
+    /// 移动目录
+    ///
+    /// 在一个事务中更新目录的父目录（触发器随之更新闭包表），并重写目录及其所有子目录的路径前缀
+    pub async fn move_directory(
+        &self,
+        id: &str,
+        new_parent_id: Option<&str>,
+        old_path: &str,
+        new_path: &str,
+    ) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let now = Local::now().to_rfc3339();
+
+        tx.execute(
+            "UPDATE directories SET parent_id = ?1, updated_at = ?2 WHERE id = ?3",
+            params![new_parent_id, now, id],
+        ).map_err(FileManagerError::Database)?;
+
+        tx.execute(
+            r#"
+            UPDATE directories
+            SET path = ?1 || substr(path, length(?2) + 1), updated_at = ?3
+            WHERE id IN (SELECT descendant_id FROM directory_closure WHERE ancestor_id = ?4)
+            "#,
+            params![new_path, old_path, now, id],
+        ).map_err(FileManagerError::Database)?;
+
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 创建文件记录
+    pub async fn create_file(
+        &self,
+        name: &str,
+        original_name: &str,
+        directory_id: &str,
+        file_path: &str,
+        file_size: i64,
+        mime_type: &str,
+    ) -> Result<FileInfo> {
+        #[cfg(test)]
+        self.faults.before_insert()?;
+
+        let id = self.generate_id();
+        let now = Local::now();
+        
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO files (id, name, original_name, directory_id, file_path, file_size, mime_type, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+            params![
+                id,
+                name,
+                original_name,
+                directory_id,
+                file_path,
+                file_size,
+                mime_type,
+                now.to_rfc3339(),
+                now.to_rfc3339()
+            ],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(FileInfo {
+            id,
+            name: name.to_string(),
+            original_name: original_name.to_string(),
+            directory_id: directory_id.to_string(),
+            file_path: file_path.to_string(),
+            file_size,
+            mime_type: mime_type.to_string(),
+            version: 1,
+            status: FileStatus::Ok,
+            latitude: None,
+            longitude: None,
+            perceptual_hash: None,
+            source_url: None,
+            content_hash: None,
+            rating: None,
+            notes: None,
+            title: None,
+            linked: false,
+            archive_path: None,
+            packed: false,
+            processing_status: ProcessingStatus::Pending,
+            processing_error: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// 创建链接文件记录，`file_path` 为库外原始文件的绝对路径
+    pub async fn create_linked_file(
+        &self,
+        name: &str,
+        original_name: &str,
+        directory_id: &str,
+        file_path: &str,
+        file_size: i64,
+        mime_type: &str,
+    ) -> Result<FileInfo> {
+        let id = self.generate_id();
+        let now = Local::now().to_rfc3339();
+
+        {
+            let conn = self.connection.lock().unwrap();
+            conn.execute(
+                r#"
+                INSERT INTO files (id, name, original_name, directory_id, file_path, file_size, mime_type, linked, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 1, ?8, ?8)
+                "#,
+                params![id, name, original_name, directory_id, file_path, file_size, mime_type, now],
+            ).map_err(FileManagerError::Database)?;
+        }
+
+        self.get_file(&id).await?
+            .ok_or_else(|| FileManagerError::FileNotFound { path: id })
+    }
+
+    /// 获取文件信息
+    pub async fn get_file(&self, id: &str) -> Result<Option<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM files WHERE id = ?1", FILE_COLUMNS)
+        ).map_err(FileManagerError::Database)?;
+
+        let result = stmt.query_row(params![id], |row| {
+            Ok(self.row_to_file_info(row)?)
+        });
+
+        match result {
+            Ok(file) => Ok(Some(file)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    /// 按内容哈希查找存储文件完好的文件，有多个时返回最早导入的
+    pub async fn find_file_by_content_hash(&self, content_hash: &str) -> Result<Option<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            &format!(
+                "SELECT {} FROM files WHERE content_hash = ?1 AND status = 'ok' ORDER BY created_at LIMIT 1",
+                FILE_COLUMNS
+            )
+        ).map_err(FileManagerError::Database)?;
+
+        match stmt.query_row(params![content_hash], |row| self.row_to_file_info(row)) {
+            Ok(file) => Ok(Some(file)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    /// 在目录中按原始文件名查找文件
+    pub async fn find_file_by_original_name(
+        &self,
+        directory_id: &str,
+        original_name: &str,
+    ) -> Result<Option<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM files WHERE directory_id = ?1 AND original_name = ?2 ORDER BY created_at LIMIT 1", FILE_COLUMNS)
+        ).map_err(FileManagerError::Database)?;
+
+        let result = stmt.query_row(params![directory_id, original_name], |row| {
+            Ok(self.row_to_file_info(row)?)
+        });
+
+        match result {
+            Ok(file) => Ok(Some(file)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    /// 替换文件内容
+    ///
+    /// 保留文件 ID 和原始文件名，将记录指向新的存储文件并设置版本号；新内容总是由资料库管理
+    pub async fn replace_file_content(
+        &self,
+        id: &str,
+        name: &str,
+        file_path: &str,
+        file_size: i64,
+        mime_type: &str,
+        version: i64,
+    ) -> Result<FileInfo> {
+        {
+            let conn = self.connection.lock().unwrap();
+            let updated = conn.execute(
+                r#"
+                UPDATE files
+                SET name = ?1, file_path = ?2, file_size = ?3, mime_type = ?4, version = ?5, updated_at = ?6,
+                    status = 'ok', perceptual_hash = NULL, content_hash = NULL, verified_at = NULL, linked = 0, archive_path = NULL, packed = 0,
+                    processing_status = 'pending', processing_error = NULL
+                WHERE id = ?7
+                "#,
+                params![name, file_path, file_size, mime_type, version, Local::now().to_rfc3339(), id],
+            ).map_err(FileManagerError::Database)?;
+
+            if updated == 0 {
+                return Err(FileManagerError::FileNotFound { path: id.to_string() });
+            }
+        }
+
+        self.get_file(id).await?
+            .ok_or_else(|| FileManagerError::FileNotFound { path: id.to_string() })
+    }
+
+    /// 更新文件的存储路径
+    pub async fn update_file_path(&self, id: &str, file_path: &str) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE files SET file_path = ?1 WHERE id = ?2",
+            params![file_path, id],
+        ).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 设置文件是否为链接文件
+    pub async fn set_file_linked(&self, id: &str, linked: bool) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE files SET linked = ?1 WHERE id = ?2",
+            params![linked, id],
+        ).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 设置文件的归档位置，`None` 表示存储文件已移回主存储
+    pub async fn set_file_archive_path(&self, id: &str, archive_path: Option<&str>) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE files SET archive_path = ?1, updated_at = ?2 WHERE id = ?3",
+            params![archive_path, Local::now().to_rfc3339(), id],
+        ).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 将文件恢复为主存储中的普通存储文件，清除归档和打包标记
+    pub async fn set_file_loose(&self, id: &str) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE files SET archive_path = NULL, packed = 0 WHERE id = ?1",
+            params![id],
+        ).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 获取可以打包的文件：已完成索引、不超过 `max_size` 字节的主存储文件
+    pub async fn get_pack_candidates(&self, max_size: u64, limit: usize) -> Result<Vec<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {} FROM files
+            WHERE packed = 0 AND linked = 0 AND archive_path IS NULL AND status = 'ok'
+                AND processing_status = 'indexed' AND content_hash IS NOT NULL AND file_size <= ?1
+            LIMIT ?2
+            "#,
+            FILE_COLUMNS
+        )).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![max_size as i64, limit as i64], |row| self.row_to_file_info(row))
+            .map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(files)
+    }
+
+    /// 按内容哈希查找打包内容
+    pub async fn get_pack_entry(&self, content_hash: &str) -> Result<Option<PackEntry>> {
+        let conn = self.connection.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT content_hash, pack_id, offset, length FROM pack_entries WHERE content_hash = ?1",
+            params![content_hash],
+            Self::row_to_pack_entry,
+        );
+
+        match result {
+            Ok(entry) => Ok(Some(entry)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    /// 获取可以继续追加内容的打包文件
+    pub async fn get_writable_pack(&self) -> Result<Option<PackInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT id, size FROM packs WHERE size < ?1 ORDER BY created_at DESC LIMIT 1",
+            params![MAX_PACK_SIZE as i64],
+            |row| Ok(PackInfo { id: row.get(0)?, size: row.get::<_, i64>(1)? as u64 }),
+        );
+
+        match result {
+            Ok(pack) => Ok(Some(pack)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    /// 登记新的打包文件
+    pub async fn create_pack(&self, id: &str) -> Result<PackInfo> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "INSERT INTO packs (id, size, created_at) VALUES (?1, 0, ?2)",
+            params![id, Local::now().to_rfc3339()],
+        ).map_err(FileManagerError::Database)?;
+        Ok(PackInfo { id: id.to_string(), size: 0 })
+    }
+
+    /// 记录文件已打包
+    ///
+    /// `new_entry` 为本次追加的内容时同时写入索引并更新打包文件大小；内容已在打包文件中时为 `None`
+    pub async fn mark_file_packed(&self, file_id: &str, new_entry: Option<&PackEntry>) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        if let Some(entry) = new_entry {
+            Self::insert_pack_entry(&tx, entry)?;
+        }
+        tx.execute("UPDATE files SET packed = 1 WHERE id = ?1", params![file_id])
+            .map_err(FileManagerError::Database)?;
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    fn insert_pack_entry(conn: &Connection, entry: &PackEntry) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO pack_entries (content_hash, pack_id, offset, length) VALUES (?1, ?2, ?3, ?4)",
+            params![entry.content_hash, entry.pack_id, entry.offset as i64, entry.length as i64],
+        ).map_err(FileManagerError::Database)?;
+        conn.execute(
+            "UPDATE packs SET size = MAX(size, ?1) WHERE id = ?2",
+            params![(entry.offset + entry.length) as i64, entry.pack_id],
+        ).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 删除不再被任何打包文件记录引用的打包内容索引，返回删除的条数
+    pub async fn delete_dead_pack_entries(&self) -> Result<usize> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            DELETE FROM pack_entries
+            WHERE NOT EXISTS (
+                SELECT 1 FROM files WHERE files.packed = 1 AND files.content_hash = pack_entries.content_hash
+            )
+            "#,
+            [],
+        ).map_err(FileManagerError::Database)
+    }
+
+    /// 获取每个打包文件及其中有效内容的字节数
+    pub async fn get_pack_usage(&self) -> Result<Vec<(PackInfo, u64)>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT packs.id, packs.size, COALESCE(SUM(CASE WHEN EXISTS (
+                SELECT 1 FROM files WHERE files.packed = 1 AND files.content_hash = pack_entries.content_hash
+            ) THEN pack_entries.length ELSE 0 END), 0)
+            FROM packs LEFT JOIN pack_entries ON pack_entries.pack_id = packs.id
+            GROUP BY packs.id
+            ORDER BY packs.created_at
+            "#
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok((PackInfo { id: row.get(0)?, size: row.get::<_, i64>(1)? as u64 }, row.get::<_, i64>(2)? as u64))
+        }).map_err(FileManagerError::Database)?;
+
+        let mut packs = Vec::new();
+        for row in rows {
+            packs.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(packs)
+    }
+
+    /// 获取打包文件中的全部内容索引，按偏移排序
+    pub async fn get_pack_entries(&self, pack_id: &str) -> Result<Vec<PackEntry>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT content_hash, pack_id, offset, length FROM pack_entries WHERE pack_id = ?1 ORDER BY offset"
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![pack_id], Self::row_to_pack_entry)
+            .map_err(FileManagerError::Database)?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(entries)
+    }
+
+    /// 用重写后的打包文件替换旧打包文件：登记新打包文件、更新内容索引并删除旧记录
+    pub async fn replace_pack(&self, old_pack_id: &str, new_pack_id: Option<&str>, entries: &[PackEntry]) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        if let Some(new_pack_id) = new_pack_id {
+            tx.execute(
+                "INSERT INTO packs (id, size, created_at) VALUES (?1, 0, ?2)",
+                params![new_pack_id, Local::now().to_rfc3339()],
+            ).map_err(FileManagerError::Database)?;
+        }
+        for entry in entries {
+            Self::insert_pack_entry(&tx, entry)?;
+        }
+        tx.execute("DELETE FROM pack_entries WHERE pack_id = ?1", params![old_pack_id])
+            .map_err(FileManagerError::Database)?;
+        tx.execute("DELETE FROM packs WHERE id = ?1", params![old_pack_id])
+            .map_err(FileManagerError::Database)?;
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 统计打包的文件数
+    pub async fn count_packed_files(&self) -> Result<usize> {
+        let conn = self.connection.lock().unwrap();
+        conn.query_row("SELECT COUNT(*) FROM files WHERE packed = 1", [], |row| row.get::<_, i64>(0))
+            .map(|count| count as usize)
+            .map_err(FileManagerError::Database)
+    }
+
+    fn row_to_pack_entry(row: &Row) -> rusqlite::Result<PackEntry> {
+        Ok(PackEntry {
+            content_hash: row.get(0)?,
+            pack_id: row.get(1)?,
+            offset: row.get::<_, i64>(2)? as u64,
+            length: row.get::<_, i64>(3)? as u64,
+        })
+    }
+
+    /// 将链接文件指向新的原始文件位置，并恢复为正常状态
+    ///
+    /// 原始文件可能已被修改，清除依赖内容的哈希，由调用方重新记录
+    pub async fn relink_external_file(&self, id: &str, file_path: &str, file_size: i64) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            UPDATE files
+            SET file_path = ?1, file_size = ?2, updated_at = ?3,
+                status = 'ok', perceptual_hash = NULL, content_hash = NULL, verified_at = NULL,
+                processing_status = 'pending', processing_error = NULL
+            WHERE id = ?4 AND linked = 1
+            "#,
+            params![file_path, file_size, Local::now().to_rfc3339(), id],
+        ).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 批量更新文件的存储路径，所有修改在一个事务中完成
+    pub async fn update_file_paths(&self, paths: &[(String, String)]) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        for (id, file_path) in paths {
+            tx.execute(
+                "UPDATE files SET file_path = ?1 WHERE id = ?2",
+                params![file_path, id],
+            ).map_err(FileManagerError::Database)?;
+        }
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 更新文件状态
+    pub async fn set_file_status(&self, id: &str, status: FileStatus) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE files SET status = ?1 WHERE id = ?2",
+            params![status.as_str(), id],
+        ).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 将文件移动到其他目录，同时更新原始文件名
+    pub async fn move_file(&self, id: &str, directory_id: &str, original_name: &str) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE files SET directory_id = ?1, original_name = ?2, updated_at = ?3 WHERE id = ?4",
+            params![directory_id, original_name, Local::now().to_rfc3339(), id],
+        ).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 设置文件的评分和备注，`None` 表示清除
+    pub async fn set_file_annotations(&self, id: &str, rating: Option<u8>, notes: Option<&str>) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE files SET rating = ?1, notes = ?2, updated_at = ?3 WHERE id = ?4",
+            params![rating, notes, Local::now().to_rfc3339(), id],
+        ).map_err(FileManagerError::Database)?;
+        if updated == 0 {
+            return Err(FileManagerError::FileNotFound { path: id.to_string() });
+        }
+        Ok(())
+    }
+
+    /// 设置文件的标题，`None` 表示清除
+    pub async fn set_file_title(&self, id: &str, title: Option<&str>) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE files SET title = ?1, updated_at = ?2 WHERE id = ?3",
+            params![title, Local::now().to_rfc3339(), id],
+        ).map_err(FileManagerError::Database)?;
+        if updated == 0 {
+            return Err(FileManagerError::FileNotFound { path: id.to_string() });
+        }
+        Ok(())
+    }
+
+    /// 统计资料库概要中的计数：(文件总数, 等待索引的文件数, 延迟删除队列长度)
+    pub async fn get_library_counts(&self) -> Result<(i64, i64, i64)> {
+        let conn = self.connection.lock().unwrap();
+        conn.query_row(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM files),
+                (SELECT COUNT(*) FROM files WHERE processing_status = 'pending'),
+                (SELECT COUNT(*) FROM deferred_deletes)
+            "#,
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).map_err(FileManagerError::Database)
+    }
+
+    /// 统计目录中直接包含的文件数（不含子目录）
+    pub async fn count_files_in_directory(&self, directory_id: &str) -> Result<i64> {
+        let conn = self.connection.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*) FROM files WHERE directory_id = ?1",
+            params![directory_id],
+            |row| row.get(0),
+        ).map_err(FileManagerError::Database)
+    }
+
+    /// 设置文件的拍摄位置，`None` 表示清除
+    pub async fn set_file_location(&self, id: &str, location: Option<GeoLocation>) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE files SET latitude = ?1, longitude = ?2 WHERE id = ?3",
+            params![
+                location.map(|location| location.latitude),
+                location.map(|location| location.longitude),
+                id
+            ],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 按创建日期统计新增文件数和字节数
+    ///
+    /// 创建时间以本地时区的 RFC 3339 格式存储，前 10 个字符即为本地日期
+    pub async fn get_daily_additions(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(NaiveDate, i64, i64)>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT substr(created_at, 1, 10) AS day, COUNT(*), COALESCE(SUM(file_size), 0)
+            FROM files
+            WHERE substr(created_at, 1, 10) BETWEEN ?1 AND ?2
+            GROUP BY day
+            ORDER BY day
+            "#
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(
+            params![start.format("%Y-%m-%d").to_string(), end.format("%Y-%m-%d").to_string()],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)),
+        ).map_err(FileManagerError::Database)?;
+
+        let mut daily = Vec::new();
+        for row in rows {
+            let (day, files, bytes) = row.map_err(FileManagerError::Database)?;
+            if let Ok(date) = NaiveDate::parse_from_str(&day, "%Y-%m-%d") {
+                daily.push((date, files, bytes));
+            }
+        }
+
+        Ok(daily)
+    }
+
+    /// 统计存储占用分布
+    ///
+    /// 分别按 MIME 类型、顶层目录和文件年龄分组，结果按占用从大到小排列
+    pub async fn get_storage_breakdown(&self) -> Result<StorageBreakdown> {
+        let conn = self.connection.lock().unwrap();
+
+        let group = |sql: &str| -> Result<Vec<UsageGroup>> {
+            let mut stmt = conn.prepare(sql).map_err(FileManagerError::Database)?;
+            let rows = stmt.query_map([], |row| {
+                Ok(UsageGroup {
+                    key: row.get(0)?,
+                    file_count: row.get(1)?,
+                    total_size: row.get(2)?,
+                })
+            }).map_err(FileManagerError::Database)?;
+
+            let mut groups = Vec::new();
+            for row in rows {
+                groups.push(row.map_err(FileManagerError::Database)?);
+            }
+            Ok(groups)
+        };
+
+        let by_mime_type = group(
+            r#"
+            SELECT mime_type, COUNT(*), COALESCE(SUM(file_size), 0) AS total
+            FROM files
+            GROUP BY mime_type
+            ORDER BY total DESC, mime_type
+            "#
+        )?;
+
+        // 目录路径形如 `/a/b`，取第一段作为顶层目录
+        let by_top_level_directory = group(
+            r#"
+            SELECT
+                CASE WHEN instr(substr(d.path, 2), '/') = 0 THEN d.path
+                     ELSE substr(d.path, 1, instr(substr(d.path, 2), '/'))
+                END AS top_level,
+                COUNT(*), COALESCE(SUM(f.file_size), 0) AS total
+            FROM files f
+            JOIN directories d ON d.id = f.directory_id
+            GROUP BY top_level
+            ORDER BY total DESC, top_level
+            "#
+        )?;
+
+        let by_age = group(
+            r#"
+            SELECT
+                CASE WHEN age < 7 THEN 'last_7_days'
+                     WHEN age < 30 THEN 'last_30_days'
+                     WHEN age < 365 THEN 'last_year'
+                     ELSE 'older'
+                END AS bucket,
+                COUNT(*), COALESCE(SUM(file_size), 0) AS total
+            FROM (SELECT file_size, julianday('now') - julianday(created_at) AS age FROM files)
+            GROUP BY bucket
+            ORDER BY total DESC, bucket
+            "#
+        )?;
+
+        Ok(StorageBreakdown {
+            by_mime_type,
+            by_top_level_directory,
+            by_age,
+        })
+    }
+
+    /// 保存统计快照，同一天已有快照时覆盖
+    pub async fn save_stats_snapshot(&self, snapshot: &StatsSnapshot) -> Result<()> {
+        let by_mime_type = serde_json::to_string(&snapshot.by_mime_type)?;
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT OR REPLACE INTO stats_snapshots (date, total_files, total_bytes, by_mime_type, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+            params![
+                snapshot.date.format("%Y-%m-%d").to_string(),
+                snapshot.total_files,
+                snapshot.total_bytes,
+                by_mime_type,
+                Local::now().to_rfc3339(),
+            ],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 检查指定日期是否已有统计快照
+    pub async fn has_stats_snapshot(&self, date: NaiveDate) -> Result<bool> {
+        let conn = self.connection.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM stats_snapshots WHERE date = ?1",
+            params![date.format("%Y-%m-%d").to_string()],
+            |row| row.get(0),
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(count > 0)
+    }
+
+    /// 获取日期范围内（含首尾两天）的统计快照，按日期排列
+    pub async fn get_stats_snapshots(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<StatsSnapshot>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT date, total_files, total_bytes, by_mime_type
+            FROM stats_snapshots
+            WHERE date BETWEEN ?1 AND ?2
+            ORDER BY date
+            "#
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(
+            params![start.format("%Y-%m-%d").to_string(), end.format("%Y-%m-%d").to_string()],
+            |row| Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+            )),
+        ).map_err(FileManagerError::Database)?;
+
+        let mut snapshots = Vec::new();
+        for row in rows {
+            let (date, total_files, total_bytes, by_mime_type) = row.map_err(FileManagerError::Database)?;
+            let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") else {
+                continue;
+            };
+            snapshots.push(StatsSnapshot {
+                date,
+                total_files,
+                total_bytes,
+                by_mime_type: serde_json::from_str(&by_mime_type)?,
+            });
+        }
+
+        Ok(snapshots)
+    }
+
+    /// 创建工作集
+    pub async fn create_working_set(&self, name: Option<&str>, ttl_minutes: u32) -> Result<WorkingSet> {
+        let id = self.generate_id();
+        let now = Local::now();
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO working_sets (id, name, ttl_minutes, expires_at, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+            "#,
+            params![id, name, ttl_minutes, expiry_from(now, ttl_minutes).timestamp(), now.to_rfc3339()],
+        ).map_err(FileManagerError::Database)?;
+
+        Self::query_working_set(&conn, &id)?
+            .ok_or_else(|| FileManagerError::general_error(format!("Working set not found: {}", id)))
+    }
+
+    /// 获取工作集，不存在或已过期时返回 `None`
+    pub async fn get_working_set(&self, id: &str) -> Result<Option<WorkingSet>> {
+        let conn = self.connection.lock().unwrap();
+        Self::query_working_set(&conn, id)
+    }
+
+    /// 获取所有未过期的工作集，按创建时间排列
+    pub async fn get_working_sets(&self) -> Result<Vec<WorkingSet>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE ws.expires_at > ?1 ORDER BY ws.created_at", WORKING_SET_QUERY
+        )).map_err(FileManagerError::Database)?;
+
+        let working_sets = stmt.query_map(params![Local::now().timestamp()], Self::row_to_working_set)
+            .map_err(FileManagerError::Database)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(FileManagerError::Database)?;
+        Ok(working_sets)
+    }
+
+    /// 修改工作集成员并重新计算过期时间
+    ///
+    /// 在一个事务中完成，添加的文件有任一不存在或成员数超出上限时整体回滚；返回实际变更的成员数
+    pub async fn update_working_set_members(
+        &self,
+        id: &str,
+        add_file_ids: &[String],
+        remove_file_ids: &[String],
+    ) -> Result<usize> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let now = Local::now();
+
+        let ttl_minutes: u32 = match tx.query_row(
+            "SELECT ttl_minutes FROM working_sets WHERE id = ?1 AND expires_at > ?2",
+            params![id, now.timestamp()],
+            |row| row.get(0),
+        ) {
+            Ok(ttl_minutes) => ttl_minutes,
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                return Err(FileManagerError::general_error(format!("Working set not found: {}", id)));
+            }
+            Err(e) => return Err(FileManagerError::Database(e)),
+        };
+
+        let mut changed = 0;
+        for file_id in add_file_ids {
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM files WHERE id = ?1)",
+                params![file_id],
+                |row| row.get(0),
+            ).map_err(FileManagerError::Database)?;
+            if !exists {
+                return Err(FileManagerError::FileNotFound { path: file_id.clone() });
+            }
+            changed += tx.execute(
+                "INSERT OR IGNORE INTO working_set_members (working_set_id, file_id, added_at) VALUES (?1, ?2, ?3)",
+                params![id, file_id, now.to_rfc3339()],
+            ).map_err(FileManagerError::Database)?;
+        }
+        for file_id in remove_file_ids {
+            changed += tx.execute(
+                "DELETE FROM working_set_members WHERE working_set_id = ?1 AND file_id = ?2",
+                params![id, file_id],
+            ).map_err(FileManagerError::Database)?;
+        }
+
+        let members: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM working_set_members WHERE working_set_id = ?1",
+            params![id],
+            |row| row.get(0),
+        ).map_err(FileManagerError::Database)?;
+        if members as usize > MAX_WORKING_SET_MEMBERS {
+            return Err(FileManagerError::general_error(format!(
+                "Working set cannot contain more than {} files", MAX_WORKING_SET_MEMBERS
+            )));
+        }
+
+        tx.execute(
+            "UPDATE working_sets SET expires_at = ?1, updated_at = ?2 WHERE id = ?3",
+            params![expiry_from(now, ttl_minutes).timestamp(), now.to_rfc3339(), id],
+        ).map_err(FileManagerError::Database)?;
+        tx.commit().map_err(FileManagerError::Database)?;
+
+        Ok(changed)
+    }
+
+    /// 获取工作集中仍存在的文件，按加入顺序排列
+    pub async fn get_working_set_files(&self, id: &str) -> Result<Vec<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {}
+            FROM files
+            JOIN working_set_members ON working_set_members.file_id = files.id
+            WHERE working_set_members.working_set_id = ?1
+            ORDER BY working_set_members.added_at, files.original_name
+            "#,
+            FILE_COLUMNS
+        )).map_err(FileManagerError::Database)?;
+
+        let files = stmt.query_map(params![id], |row| self.row_to_file_info(row))
+            .map_err(FileManagerError::Database)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(FileManagerError::Database)?;
+        Ok(files)
+    }
+
+    /// 删除工作集及其成员，返回是否存在
+    pub async fn delete_working_set(&self, id: &str) -> Result<bool> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        tx.execute("DELETE FROM working_set_members WHERE working_set_id = ?1", params![id])
+            .map_err(FileManagerError::Database)?;
+        let deleted = tx.execute("DELETE FROM working_sets WHERE id = ?1", params![id])
+            .map_err(FileManagerError::Database)?;
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(deleted > 0)
+    }
+
+    /// 删除在 `now` 之前过期的工作集，返回删除的数量
+    pub async fn purge_expired_working_sets(&self, now: DateTime<Local>) -> Result<usize> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        tx.execute(
+            "DELETE FROM working_set_members WHERE working_set_id IN (SELECT id FROM working_sets WHERE expires_at <= ?1)",
+            params![now.timestamp()],
+        ).map_err(FileManagerError::Database)?;
+        let purged = tx.execute("DELETE FROM working_sets WHERE expires_at <= ?1", params![now.timestamp()])
+            .map_err(FileManagerError::Database)?;
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(purged)
+    }
+
+    fn query_working_set(conn: &Connection, id: &str) -> Result<Option<WorkingSet>> {
+        match conn.query_row(
+            &format!("{} WHERE ws.id = ?1 AND ws.expires_at > ?2", WORKING_SET_QUERY),
+            params![id, Local::now().timestamp()],
+            Self::row_to_working_set,
+        ) {
+            Ok(working_set) => Ok(Some(working_set)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    fn row_to_working_set(row: &Row) -> rusqlite::Result<WorkingSet> {
+        let timestamp = |index: usize, value: String| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|time| time.with_timezone(&Local))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+        };
+        let expires_at = DateTime::from_timestamp(row.get(4)?, 0)
+            .map(|time| time.with_timezone(&Local))
+            .ok_or(rusqlite::Error::IntegralValueOutOfRange(4, 0))?;
+
+        Ok(WorkingSet {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            file_count: row.get::<_, i64>(2)? as usize,
+            ttl_minutes: row.get(3)?,
+            expires_at,
+            created_at: timestamp(5, row.get(5)?)?,
+            updated_at: timestamp(6, row.get(6)?)?,
+        })
+    }
+
+    /// 创建或整体替换播放列表；`id` 为 `None` 时新建
+    ///
+    /// 在一个事务中完成，任一文件不存在时整体回滚；修改不存在的播放列表时返回 `None`
+    pub async fn save_playlist(
+        &self,
+        id: Option<&str>,
+        name: &str,
+        board_id: Option<&str>,
+        file_ids: &[String],
+    ) -> Result<Option<Playlist>> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let now = Local::now().to_rfc3339();
+
+        let id = match id {
+            Some(id) => {
+                let updated = tx.execute(
+                    "UPDATE playlists SET name = ?1, board_id = ?2, updated_at = ?3 WHERE id = ?4",
+                    params![name, board_id, now, id],
+                ).map_err(FileManagerError::Database)?;
+                if updated == 0 {
+                    return Ok(None);
+                }
+                tx.execute("DELETE FROM playlist_items WHERE playlist_id = ?1", params![id])
+                    .map_err(FileManagerError::Database)?;
+                id.to_string()
+            }
+            None => {
+                let id = self.generate_id();
+                tx.execute(
+                    "INSERT INTO playlists (id, name, board_id, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+                    params![id, name, board_id, now],
+                ).map_err(FileManagerError::Database)?;
+                id
+            }
+        };
+
+        for (position, file_id) in file_ids.iter().enumerate() {
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM files WHERE id = ?1)",
+                params![file_id],
+                |row| row.get(0),
+            ).map_err(FileManagerError::Database)?;
+            if !exists {
+                return Err(FileManagerError::FileNotFound { path: file_id.clone() });
+            }
+            tx.execute(
+                "INSERT INTO playlist_items (playlist_id, position, file_id) VALUES (?1, ?2, ?3)",
+                params![id, position as i64, file_id],
+            ).map_err(FileManagerError::Database)?;
+        }
+
+        let playlist = Self::query_playlist(&tx, &id)?;
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(playlist)
+    }
+
+    /// 获取播放列表
+    pub async fn get_playlist(&self, id: &str) -> Result<Option<Playlist>> {
+        let conn = self.connection.lock().unwrap();
+        Self::query_playlist(&conn, id)
+    }
+
+    /// 获取播放列表，指定画板时只返回关联到该画板的，按创建时间排列
+    pub async fn get_playlists(&self, board_id: Option<&str>) -> Result<Vec<Playlist>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE ?1 IS NULL OR p.board_id = ?1 ORDER BY p.created_at", PLAYLIST_QUERY
+        )).map_err(FileManagerError::Database)?;
+
+        let playlists = stmt.query_map(params![board_id], Self::row_to_playlist)
+            .map_err(FileManagerError::Database)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(FileManagerError::Database)?;
+        Ok(playlists)
+    }
+
+    /// 获取播放列表中仍存在的文件，按放映顺序排列；`offset` 和 `limit` 按仍存在的文件计算
+    pub async fn get_playlist_files(&self, id: &str, offset: usize, limit: usize) -> Result<Vec<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {}
+            FROM files
+            JOIN playlist_items ON playlist_items.file_id = files.id
+            WHERE playlist_items.playlist_id = ?1
+            ORDER BY playlist_items.position
+            LIMIT ?2 OFFSET ?3
+            "#,
+            FILE_COLUMNS
+        )).map_err(FileManagerError::Database)?;
+
+        let files = stmt.query_map(params![id, limit as i64, offset as i64], |row| self.row_to_file_info(row))
+            .map_err(FileManagerError::Database)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(FileManagerError::Database)?;
+        Ok(files)
+    }
+
+    /// 删除播放列表，返回是否存在
+    pub async fn delete_playlist(&self, id: &str) -> Result<bool> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        tx.execute("DELETE FROM playlist_items WHERE playlist_id = ?1", params![id])
+            .map_err(FileManagerError::Database)?;
+        let deleted = tx.execute("DELETE FROM playlists WHERE id = ?1", params![id])
+            .map_err(FileManagerError::Database)?;
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(deleted > 0)
+    }
+
+    fn query_playlist(conn: &Connection, id: &str) -> Result<Option<Playlist>> {
+        match conn.query_row(&format!("{} WHERE p.id = ?1", PLAYLIST_QUERY), params![id], Self::row_to_playlist) {
+            Ok(playlist) => Ok(Some(playlist)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    fn row_to_playlist(row: &Row) -> rusqlite::Result<Playlist> {
+        let timestamp = |index: usize, value: String| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|time| time.with_timezone(&Local))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+        };
+
+        Ok(Playlist {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            board_id: row.get(2)?,
+            item_count: row.get::<_, i64>(3)? as usize,
+            created_at: timestamp(4, row.get(4)?)?,
+            updated_at: timestamp(5, row.get(5)?)?,
+        })
+    }
+
+    /// 创建或整体替换画板框架；`id` 为 `None` 时新建
+    ///
+    /// 在一个事务中完成，放入框架的元素从同一画板的其他框架中移出；
+    /// 修改不存在或不属于该画板的框架时返回 `None`
+    pub async fn save_board_frame(&self, id: Option<&str>, request: &BoardFrameRequest) -> Result<Option<BoardFrame>> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let now = Local::now().to_rfc3339();
+
+        let id = match id {
+            Some(id) => {
+                let updated = tx.execute(
+                    r#"
+                    UPDATE board_frames
+                    SET parent_id = ?1, kind = ?2, name = ?3, x = ?4, y = ?5, width = ?6, height = ?7, updated_at = ?8
+                    WHERE id = ?9 AND board_id = ?10
+                    "#,
+                    params![
+                        request.parent_id,
+                        request.kind.as_str(),
+                        request.name,
+                        request.x,
+                        request.y,
+                        request.width,
+                        request.height,
+                        now,
+                        id,
+                        request.board_id,
+                    ],
+                ).map_err(FileManagerError::Database)?;
+                if updated == 0 {
+                    return Ok(None);
+                }
+                id.to_string()
+            }
+            None => {
+                let id = self.generate_id();
+                Self::insert_board_frame(&tx, &id, request, &now).map_err(FileManagerError::Database)?;
+                id
+            }
+        };
+        Self::set_board_frame_items(&tx, &request.board_id, &id, &request.item_ids)
+            .map_err(FileManagerError::Database)?;
+
+        let frame = Self::query_board_frames(&tx, "id = ?1", &id)
+            .map_err(FileManagerError::Database)?
+            .pop();
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(frame)
+    }
+
+    /// 获取画板框架
+    pub async fn get_board_frame(&self, id: &str) -> Result<Option<BoardFrame>> {
+        let conn = self.connection.lock().unwrap();
+        Self::query_board_frames(&conn, "id = ?1", id)
+            .map(|mut frames| frames.pop())
+            .map_err(FileManagerError::Database)
+    }
+
+    /// 获取画板的所有框架，按创建顺序排列
+    pub async fn get_board_frames(&self, board_id: &str) -> Result<Vec<BoardFrame>> {
+        let conn = self.connection.lock().unwrap();
+        Self::query_board_frames(&conn, "board_id = ?1", board_id).map_err(FileManagerError::Database)
+    }
+
+    /// 平移框架，框架中的元素由前端移动
+    pub async fn offset_board_frames(&self, ids: &[String], dx: f64, dy: f64) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let now = Local::now().to_rfc3339();
+        for id in ids {
+            tx.execute(
+                "UPDATE board_frames SET x = x + ?1, y = y + ?2, updated_at = ?3 WHERE id = ?4",
+                params![dx, dy, now, id],
+            ).map_err(FileManagerError::Database)?;
+        }
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 删除框架，返回删除的数量；框架中的元素不受影响
+    pub async fn delete_board_frames(&self, ids: &[String]) -> Result<usize> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let mut deleted = 0;
+        for id in ids {
+            tx.execute("DELETE FROM board_frame_items WHERE frame_id = ?1", params![id])
+                .map_err(FileManagerError::Database)?;
+            deleted += tx.execute("DELETE FROM board_frames WHERE id = ?1", params![id])
+                .map_err(FileManagerError::Database)?;
+        }
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(deleted)
+    }
+
+    /// 用 `frames` 整体替换画板的框架，框架使用新 ID，上级框架随之对应
+    ///
+    /// 在一个事务中完成；`frames` 中的上级框架必须在同一列表中且不能形成循环
+    pub async fn replace_board_frames(&self, board_id: &str, frames: &[BoardFrame]) -> Result<Vec<BoardFrame>> {
+        let order = parent_first_order(frames)?;
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let now = Local::now().to_rfc3339();
+
+        tx.execute("DELETE FROM board_frame_items WHERE board_id = ?1", params![board_id])
+            .map_err(FileManagerError::Database)?;
+        tx.execute("DELETE FROM board_frames WHERE board_id = ?1", params![board_id])
+            .map_err(FileManagerError::Database)?;
+
+        let mut new_ids: HashMap<&str, String> = HashMap::new();
+        for index in order {
+            let frame = &frames[index];
+            let id = self.generate_id();
+            let request = BoardFrameRequest {
+                board_id: board_id.to_string(),
+                parent_id: frame.parent_id.as_deref().and_then(|parent_id| new_ids.get(parent_id).cloned()),
+                ..BoardFrameRequest::from(frame.clone())
+            };
+            Self::insert_board_frame(&tx, &id, &request, &now).map_err(FileManagerError::Database)?;
+            Self::set_board_frame_items(&tx, board_id, &id, &request.item_ids).map_err(FileManagerError::Database)?;
+            new_ids.insert(&frame.id, id);
+        }
+
+        let frames = Self::query_board_frames(&tx, "board_id = ?1", board_id).map_err(FileManagerError::Database)?;
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(frames)
+    }
+
+    fn insert_board_frame(conn: &Connection, id: &str, request: &BoardFrameRequest, now: &str) -> rusqlite::Result<()> {
+        conn.execute(
+            r#"
+            INSERT INTO board_frames (id, board_id, parent_id, kind, name, x, y, width, height, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)
+            "#,
+            params![
+                id,
+                request.board_id,
+                request.parent_id,
+                request.kind.as_str(),
+                request.name,
+                request.x,
+                request.y,
+                request.width,
+                request.height,
+                now,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 替换框架直接包含的元素，元素原来所在的框架不再包含它
+    fn set_board_frame_items(conn: &Connection, board_id: &str, frame_id: &str, item_ids: &[String]) -> rusqlite::Result<()> {
+        conn.execute("DELETE FROM board_frame_items WHERE frame_id = ?1", params![frame_id])?;
+        for (position, item_id) in item_ids.iter().enumerate() {
+            conn.execute(
+                "INSERT OR REPLACE INTO board_frame_items (board_id, item_id, frame_id, position) VALUES (?1, ?2, ?3, ?4)",
+                params![board_id, item_id, frame_id, position as i64],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 按条件（`?1` 为参数）查询框架及其直接包含的元素
+    fn query_board_frames(conn: &Connection, condition: &str, value: &str) -> rusqlite::Result<Vec<BoardFrame>> {
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT id, board_id, parent_id, kind, name, x, y, width, height, created_at, updated_at
+            FROM board_frames
+            WHERE {}
+            ORDER BY created_at, rowid
+            "#,
+            condition
+        ))?;
+        let mut frames = stmt.query_map(params![value], Self::row_to_board_frame)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut items = conn.prepare("SELECT item_id FROM board_frame_items WHERE frame_id = ?1 ORDER BY position")?;
+        for frame in &mut frames {
+            frame.item_ids = items.query_map(params![frame.id], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+        }
+        Ok(frames)
+    }
+
+    fn row_to_board_frame(row: &Row) -> rusqlite::Result<BoardFrame> {
+        let timestamp = |index: usize, value: String| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|time| time.with_timezone(&Local))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+        };
+
+        Ok(BoardFrame {
+            id: row.get(0)?,
+            board_id: row.get(1)?,
+            parent_id: row.get(2)?,
+            kind: FrameKind::from_db(&row.get::<_, String>(3)?),
+            name: row.get(4)?,
+            x: row.get(5)?,
+            y: row.get(6)?,
+            width: row.get(7)?,
+            height: row.get(8)?,
+            item_ids: Vec::new(),
+            created_at: timestamp(9, row.get(9)?)?,
+            updated_at: timestamp(10, row.get(10)?)?,
+        })
+    }
+
+    /// 创建目录模板
+    pub async fn create_directory_template(&self, name: &str, folders: &[TemplateFolder]) -> Result<DirectoryTemplate> {
+        let id = self.generate_id();
+        let now = Local::now().to_rfc3339();
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO directory_templates (id, name, folders, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?4)
+            "#,
+            params![id, name, serde_json::to_string(folders)?, now],
+        ).map_err(FileManagerError::Database)?;
+
+        Self::query_directory_template(&conn, &id)?
+            .ok_or_else(|| FileManagerError::general_error(format!("Directory template not found: {}", id)))
+    }
+
+    /// 获取目录模板
+    pub async fn get_directory_template(&self, id: &str) -> Result<Option<DirectoryTemplate>> {
+        let conn = self.connection.lock().unwrap();
+        Self::query_directory_template(&conn, id)
+    }
+
+    /// 获取所有目录模板，按名称排列
+    pub async fn get_directory_templates(&self) -> Result<Vec<DirectoryTemplate>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, folders, created_at, updated_at FROM directory_templates ORDER BY name COLLATE NOCASE, created_at"
+        ).map_err(FileManagerError::Database)?;
+
+        let templates = stmt.query_map([], Self::row_to_directory_template)
+            .map_err(FileManagerError::Database)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(FileManagerError::Database)?;
+        Ok(templates)
+    }
+
+    /// 修改目录模板的名称和文件夹结构，返回修改后的模板，不存在时返回 `None`
+    pub async fn update_directory_template(
+        &self,
+        id: &str,
+        name: &str,
+        folders: &[TemplateFolder],
+    ) -> Result<Option<DirectoryTemplate>> {
+        let conn = self.connection.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE directory_templates SET name = ?1, folders = ?2, updated_at = ?3 WHERE id = ?4",
+            params![name, serde_json::to_string(folders)?, Local::now().to_rfc3339(), id],
+        ).map_err(FileManagerError::Database)?;
+        if updated == 0 {
+            return Ok(None);
+        }
+        Self::query_directory_template(&conn, id)
+    }
+
+    /// 删除目录模板，返回是否存在
+    pub async fn delete_directory_template(&self, id: &str) -> Result<bool> {
+        let conn = self.connection.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM directory_templates WHERE id = ?1", params![id])
+            .map_err(FileManagerError::Database)?;
+        Ok(deleted > 0)
+    }
+
+    fn query_directory_template(conn: &Connection, id: &str) -> Result<Option<DirectoryTemplate>> {
+        match conn.query_row(
+            "SELECT id, name, folders, created_at, updated_at FROM directory_templates WHERE id = ?1",
+            params![id],
+            Self::row_to_directory_template,
+        ) {
+            Ok(template) => Ok(Some(template)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    fn row_to_directory_template(row: &Row) -> rusqlite::Result<DirectoryTemplate> {
+        let timestamp = |index: usize, value: String| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|time| time.with_timezone(&Local))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+        };
+        let folders: String = row.get(2)?;
+        let folders = serde_json::from_str(&folders)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?;
+
+        Ok(DirectoryTemplate {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            folders,
+            created_at: timestamp(3, row.get(3)?)?,
+            updated_at: timestamp(4, row.get(4)?)?,
+        })
+    }
+
+    /// 记录一次文件使用，同时累加文件所带标签的使用次数
+    pub async fn record_file_usage(&self, file_id: &str, kind: UsageKind, board_id: Option<&str>) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let now = Local::now().to_rfc3339();
+
+        let exists: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM files WHERE id = ?1)",
+            params![file_id],
+            |row| row.get(0),
+        ).map_err(FileManagerError::Database)?;
+        if !exists {
+            return Err(FileManagerError::FileNotFound { path: file_id.to_string() });
+        }
+
+        tx.execute(
+            r#"
+            INSERT INTO file_usage (file_id, kind, board_id, count, last_used_at)
+            VALUES (?1, ?2, ?3, 1, ?4)
+            ON CONFLICT(file_id, kind, board_id) DO UPDATE SET
+                count = count + 1,
+                last_used_at = excluded.last_used_at
+            "#,
+            params![file_id, kind.as_str(), board_id.unwrap_or(""), now],
+        ).map_err(FileManagerError::Database)?;
+
+        tx.execute(
+            r#"
+            INSERT INTO tag_usage (tag, count, last_used_at)
+            SELECT tag, 1, ?2 FROM file_tags WHERE file_id = ?1
+            ON CONFLICT(tag) DO UPDATE SET
+                count = count + 1,
+                last_used_at = excluded.last_used_at
+            "#,
+            params![file_id, now],
+        ).map_err(FileManagerError::Database)?;
+
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 获取放置到画板上的仍存在的文件，按首次放置的先后排列
+    pub async fn get_board_file_ids(&self, board_id: &str) -> Result<Vec<String>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT u.file_id
+            FROM file_usage u
+            JOIN files f ON f.id = u.file_id
+            WHERE u.kind = 'placement' AND u.board_id = ?1
+            ORDER BY u.rowid
+            "#
+        ).map_err(FileManagerError::Database)?;
+
+        let file_ids = stmt.query_map(params![board_id], |row| row.get(0))
+            .map_err(FileManagerError::Database)?
+            .collect::<std::result::Result<Vec<String>, _>>()
+            .map_err(FileManagerError::Database)?;
+        Ok(file_ids)
+    }
+
+    /// 获取仍存在的文件的使用统计
+    ///
+    /// `board_uses` 为在 `board_id` 上的放置次数；按画板放置次数、总次数和最近使用时间排列，最多 `limit` 条
+    pub async fn get_file_usage_stats(&self, board_id: Option<&str>, limit: usize) -> Result<Vec<FileUsageStats>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT
+                u.file_id,
+                SUM(u.count) AS total_uses,
+                SUM(CASE WHEN u.kind = 'placement' AND u.board_id = ?1 THEN u.count ELSE 0 END) AS board_uses,
+                MAX(u.last_used_at) AS last_used_at
+            FROM file_usage u
+            JOIN files f ON f.id = u.file_id
+            GROUP BY u.file_id
+            ORDER BY board_uses DESC, total_uses DESC, last_used_at DESC
+            LIMIT ?2
+            "#
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![board_id, limit as i64], |row| Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, String>(3)?,
+        ))).map_err(FileManagerError::Database)?;
+
+        let mut stats = Vec::new();
+        for row in rows {
+            let (file_id, total_uses, board_uses, last_used_at) = row.map_err(FileManagerError::Database)?;
+            stats.push(FileUsageStats {
+                file_id,
+                total_uses: total_uses.max(0) as u64,
+                board_uses: board_uses.max(0) as u64,
+                last_used_at: DateTime::parse_from_rfc3339(&last_used_at)
+                    .ok()
+                    .map(|time| time.with_timezone(&Local)),
+            });
+        }
+
+        Ok(stats)
+    }
+
+    /// 获取所有标签的使用次数
+    pub async fn get_tag_usage(&self) -> Result<HashMap<String, u64>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT tag, count FROM tag_usage")
+            .map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+            .map_err(FileManagerError::Database)?;
+
+        let mut usage = HashMap::new();
+        for row in rows {
+            let (tag, count) = row.map_err(FileManagerError::Database)?;
+            usage.insert(tag, count.max(0) as u64);
+        }
+
+        Ok(usage)
+    }
+
+    /// 获取带有指定标签的文件 ID，最多 `limit` 个
+    pub async fn get_file_ids_with_tag(&self, tag: &str, limit: usize) -> Result<Vec<String>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT file_id FROM file_tags WHERE tag = ?1 ORDER BY file_id LIMIT ?2"
+        ).map_err(FileManagerError::Database)?;
+
+        let file_ids = stmt.query_map(params![tag, limit as i64], |row| row.get(0))
+            .map_err(FileManagerError::Database)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(FileManagerError::Database)?;
+        Ok(file_ids)
+    }
+
+    /// 添加监视文件夹
+    pub async fn create_watched_folder(
+        &self,
+        path: &str,
+        target_directory_id: Option<&str>,
+        default_tags: &[String],
+        import_mode: ImportMode,
+    ) -> Result<WatchedFolder> {
+        let id = self.generate_id();
+        let now = Local::now().to_rfc3339();
+        let tags_json = serde_json::to_string(default_tags)?;
+
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO watched_folders (id, path, target_directory_id, default_tags, import_mode, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![id, path, target_directory_id, tags_json, import_mode.as_str(), now],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(WatchedFolder {
+            id,
+            path: path.to_string(),
+            target_directory_id: target_directory_id.map(str::to_string),
+            default_tags: default_tags.to_vec(),
+            import_mode,
+            created_at: now,
+        })
+    }
+
+    /// 删除监视文件夹及其导入记录
+    pub async fn delete_watched_folder(&self, id: &str) -> Result<bool> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+
+        tx.execute("DELETE FROM watched_folder_imports WHERE folder_id = ?1", params![id])
+            .map_err(FileManagerError::Database)?;
+        let deleted = tx.execute("DELETE FROM watched_folders WHERE id = ?1", params![id])
+            .map_err(FileManagerError::Database)?;
+
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(deleted > 0)
+    }
+
+    /// 获取所有监视文件夹
+    pub async fn get_watched_folders(&self) -> Result<Vec<WatchedFolder>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, path, target_directory_id, default_tags, import_mode, created_at FROM watched_folders ORDER BY path"
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| {
+            let tags_json: String = row.get("default_tags")?;
+            Ok(WatchedFolder {
+                id: row.get("id")?,
+                path: row.get("path")?,
+                target_directory_id: row.get("target_directory_id")?,
+                default_tags: serde_json::from_str(&tags_json).unwrap_or_default(),
+                import_mode: ImportMode::from_db(&row.get::<_, String>("import_mode")?),
+                created_at: row.get("created_at")?,
+            })
+        }).map_err(FileManagerError::Database)?;
+
+        let mut folders = Vec::new();
+        for row in rows {
+            folders.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(folders)
+    }
+
+    /// 检查监视文件夹中的文件是否已按当前大小和修改时间导入过
+    pub async fn is_watched_file_imported(
+        &self,
+        folder_id: &str,
+        source_path: &str,
+        file_size: i64,
+        modified_at: i64,
+    ) -> Result<bool> {
+        let conn = self.connection.lock().unwrap();
+        conn.query_row(
+            r#"
+            SELECT COUNT(*) FROM watched_folder_imports
+            WHERE folder_id = ?1 AND source_path = ?2 AND file_size = ?3 AND modified_at = ?4
+            "#,
+            params![folder_id, source_path, file_size, modified_at],
+            |row| row.get::<_, i64>(0),
+        )
+        .map(|count| count > 0)
+        .map_err(FileManagerError::Database)
+    }
+
+    /// 添加隔离区文件记录
+    pub async fn insert_quarantined_file(&self, file: &QuarantinedFile) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO quarantined_files (id, original_name, directory_id, file_size, reason, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![
+                file.id,
+                file.original_name,
+                file.directory_id,
+                file.file_size,
+                serde_json::to_string(&file.reason)?,
+                file.created_at
+            ],
+        ).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 获取隔离区文件，`id` 为 `None` 时返回全部（最新的在前）
+    pub async fn get_quarantined_files(&self, id: Option<&str>) -> Result<Vec<QuarantinedFile>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, original_name, directory_id, file_size, reason, created_at FROM quarantined_files
+            WHERE ?1 IS NULL OR id = ?1
+            ORDER BY created_at DESC
+            "#
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![id], |row| {
+            let reason: String = row.get("reason")?;
+            Ok(QuarantinedFile {
+                id: row.get("id")?,
+                original_name: row.get("original_name")?,
+                directory_id: row.get("directory_id")?,
+                file_size: row.get("file_size")?,
+                reason: serde_json::from_str(&reason).map_err(|e| rusqlite::Error::FromSqlConversionFailure(
+                    4, rusqlite::types::Type::Text, Box::new(e)
+                ))?,
+                created_at: row.get("created_at")?,
+            })
+        }).map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+        Ok(files)
+    }
+
+    /// 删除隔离区文件记录，返回记录是否存在
+    pub async fn delete_quarantined_file(&self, id: &str) -> Result<bool> {
+        let conn = self.connection.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM quarantined_files WHERE id = ?1", params![id])
+            .map_err(FileManagerError::Database)?;
+        Ok(deleted > 0)
+    }
+
+    /// 添加回收站文件记录
+    pub async fn insert_trashed_file(&self, file: &TrashedFile) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO trashed_files (id, file_id, original_name, directory_id, file_size, mime_type, tags, deleted_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+            params![
+                file.id,
+                file.file_id,
+                file.original_name,
+                file.directory_id,
+                file.file_size,
+                file.mime_type,
+                serde_json::to_string(&file.tags)?,
+                file.deleted_at
+            ],
+        ).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 获取回收站文件，`id` 为 `None` 时返回全部（最新删除的在前）
+    pub async fn get_trashed_files(&self, id: Option<&str>) -> Result<Vec<TrashedFile>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, file_id, original_name, directory_id, file_size, mime_type, tags, deleted_at FROM trashed_files
+            WHERE ?1 IS NULL OR id = ?1
+            ORDER BY deleted_at DESC
+            "#
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![id], |row| {
+            let tags: String = row.get("tags")?;
+            Ok(TrashedFile {
+                id: row.get("id")?,
+                file_id: row.get("file_id")?,
+                original_name: row.get("original_name")?,
+                directory_id: row.get("directory_id")?,
+                file_size: row.get("file_size")?,
+                mime_type: row.get("mime_type")?,
+                tags: serde_json::from_str(&tags).map_err(|e| rusqlite::Error::FromSqlConversionFailure(
+                    6, rusqlite::types::Type::Text, Box::new(e)
+                ))?,
+                deleted_at: row.get("deleted_at")?,
+            })
+        }).map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+        Ok(files)
+    }
+
+    /// 删除回收站文件记录，返回记录是否存在
+    pub async fn delete_trashed_file(&self, id: &str) -> Result<bool> {
+        let conn = self.connection.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM trashed_files WHERE id = ?1", params![id])
+            .map_err(FileManagerError::Database)?;
+        Ok(deleted > 0)
+    }
+
+    /// 把存储文件加入延迟删除队列，已在队列中时不修改
+    pub async fn queue_deferred_delete(&self, entry: &DeferredDelete) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO deferred_deletes (file_path, file_id, attempts, last_error, queued_at, next_attempt_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT(file_path) DO NOTHING
+            "#,
+            params![
+                entry.file_path,
+                entry.file_id,
+                entry.attempts,
+                entry.last_error,
+                entry.queued_at,
+                entry.next_attempt_at
+            ],
+        ).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 获取延迟删除队列，最早加入的在前
+    pub async fn get_deferred_deletes(&self) -> Result<Vec<DeferredDelete>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT file_path, file_id, attempts, last_error, queued_at, next_attempt_at FROM deferred_deletes
+            ORDER BY queued_at, file_path
+            "#
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(DeferredDelete {
+                file_path: row.get("file_path")?,
+                file_id: row.get("file_id")?,
+                attempts: row.get("attempts")?,
+                last_error: row.get("last_error")?,
+                queued_at: row.get("queued_at")?,
+                next_attempt_at: row.get("next_attempt_at")?,
+            })
+        }).map_err(FileManagerError::Database)?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row.map_err(FileManagerError::Database)?);
+        }
+        Ok(entries)
+    }
+
+    /// 更新延迟删除条目的失败次数、错误和下次尝试时间
+    pub async fn update_deferred_delete(&self, entry: &DeferredDelete) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE deferred_deletes SET attempts = ?2, last_error = ?3, next_attempt_at = ?4 WHERE file_path = ?1",
+            params![entry.file_path, entry.attempts, entry.last_error, entry.next_attempt_at],
+        ).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 移出延迟删除队列
+    pub async fn remove_deferred_delete(&self, file_path: &str) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute("DELETE FROM deferred_deletes WHERE file_path = ?1", params![file_path])
+            .map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 存储路径是否被文件记录使用（包括归档路径）
+    pub async fn is_storage_path_in_use(&self, file_path: &str) -> Result<bool> {
+        let conn = self.connection.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*) FROM files WHERE file_path = ?1 OR archive_path = ?1",
+            params![file_path],
+            |row| row.get::<_, i64>(0),
+        )
+        .map(|count| count > 0)
+        .map_err(FileManagerError::Database)
+    }
+
+    /// 记录监视文件夹中已导入的文件
+    pub async fn record_watched_import(
+        &self,
+        folder_id: &str,
+        source_path: &str,
+        file_size: i64,
+        modified_at: i64,
+        file_id: &str,
+    ) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO watched_folder_imports (folder_id, source_path, file_size, modified_at, file_id)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(folder_id, source_path) DO UPDATE SET
+                file_size = excluded.file_size, modified_at = excluded.modified_at, file_id = excluded.file_id
+            "#,
+            params![folder_id, source_path, file_size, modified_at, file_id],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 记录存储文件的内容哈希，同时视为一次校验
+    pub async fn set_content_hash(&self, id: &str, content_hash: &str) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE files SET content_hash = ?1, verified_at = ?2 WHERE id = ?3",
+            params![content_hash, Local::now().to_rfc3339(), id],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 更新文件的索引处理状态，`error` 只在处理失败时记录
+    pub async fn set_processing_status(&self, id: &str, status: ProcessingStatus, error: Option<&str>) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE files SET processing_status = ?1, processing_error = ?2 WHERE id = ?3",
+            params![status.as_str(), error, id],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 清除文件的派生数据（拍摄位置、感知哈希、内容哈希），并标记为待处理
+    pub async fn clear_derived_data(&self, ids: &[String]) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        for id in ids {
+            tx.execute(
+                r#"
+                UPDATE files
+                SET latitude = NULL, longitude = NULL, perceptual_hash = NULL, content_hash = NULL, verified_at = NULL,
+                    processing_status = 'pending', processing_error = NULL
+                WHERE id = ?1
+                "#,
+                params![id],
+            ).map_err(FileManagerError::Database)?;
+        }
+        tx.commit().map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 获取尚未处理或处理失败的文件
+    pub async fn get_unprocessed_files(&self) -> Result<Vec<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM files WHERE processing_status != 'indexed' ORDER BY created_at",
+            FILE_COLUMNS
+        )).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| self.row_to_file_info(row))
+            .map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(files)
+    }
+
+    /// 记录文件已完成校验
+    pub async fn mark_verified(&self, id: &str) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE files SET verified_at = ?1 WHERE id = ?2",
+            params![Local::now().to_rfc3339(), id],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 获取最久未校验的文件，从未校验的文件排在最前
+    ///
+    /// 链接文件的原始文件可能被用户正常修改，不参与校验
+    pub async fn get_files_for_verification(&self, limit: usize) -> Result<Vec<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM files WHERE status = 'ok' AND linked = 0 ORDER BY verified_at IS NOT NULL, verified_at LIMIT ?1",
+            FILE_COLUMNS
+        )).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![limit as i64], |row| self.row_to_file_info(row))
+            .map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(files)
+    }
+
+    /// 获取内容哈希与其他文件相同的文件，按哈希和上传时间排序
+    ///
+    /// 链接文件不参与，清理重复文件不会删除或替换库外的原始文件
+    pub async fn get_duplicate_files(&self) -> Result<Vec<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {} FROM files
+            WHERE status = 'ok' AND linked = 0 AND content_hash IN (
+                SELECT content_hash FROM files
+                WHERE status = 'ok' AND linked = 0 AND content_hash IS NOT NULL
+                GROUP BY content_hash HAVING COUNT(*) > 1
+            )
+            ORDER BY content_hash, created_at, id
+            "#,
+            FILE_COLUMNS
+        )).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| self.row_to_file_info(row))
+            .map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(files)
+    }
+
+    /// 在一个事务中提交重复文件清理结果并记录撤销条目
+    ///
+    /// 删除模式下将副本的标签合并到保留的文件，并删除副本记录
+    pub async fn commit_duplicate_resolution(&self, undo_id: &str, undo: &DuplicateUndo) -> Result<()> {
+        let payload = serde_json::to_string(undo)?;
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let now = Local::now().to_rfc3339();
+
+        if undo.action == DuplicateAction::Delete {
+            for group in &undo.groups {
+                for copy in &group.copies {
+                    for tag in &copy.tags {
+                        tx.execute(
+                            "INSERT OR IGNORE INTO file_tags (file_id, tag, created_at) VALUES (?1, ?2, ?3)",
+                            params![group.keep_file_id, tag, now],
+                        ).map_err(FileManagerError::Database)?;
+                    }
+                    delete_file_rows(&tx, &copy.file.id).map_err(FileManagerError::Database)?;
+                }
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO undo_entries (id, operation_type, payload, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![undo_id, RESOLVE_DUPLICATES_OPERATION, payload, now],
+        ).map_err(FileManagerError::Database)?;
+
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 获取撤销条目，返回操作类型和记录的数据
+    pub async fn get_undo_entry(&self, id: &str) -> Result<Option<(String, String)>> {
+        let conn = self.connection.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT operation_type, payload FROM undo_entries WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        );
+
+        match result {
+            Ok(entry) => Ok(Some(entry)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    /// 在一个事务中恢复重复文件清理前的记录和标签，并移除撤销条目
+    pub async fn restore_duplicate_resolution(&self, undo_id: &str, undo: &DuplicateUndo) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let now = Local::now().to_rfc3339();
+
+        if undo.action == DuplicateAction::Delete {
+            for group in &undo.groups {
+                tx.execute("DELETE FROM file_tags WHERE file_id = ?1", params![group.keep_file_id])
+                    .map_err(FileManagerError::Database)?;
+                for tag in &group.kept_tags {
+                    tx.execute(
+                        "INSERT INTO file_tags (file_id, tag, created_at) VALUES (?1, ?2, ?3)",
+                        params![group.keep_file_id, tag, now],
+                    ).map_err(FileManagerError::Database)?;
+                }
+
+                for copy in &group.copies {
+                    Self::insert_file_row(&tx, &copy.file)?;
+
+                    for tag in &copy.tags {
+                        tx.execute(
+                            "INSERT INTO file_tags (file_id, tag, created_at) VALUES (?1, ?2, ?3)",
+                            params![copy.file.id, tag, now],
+                        ).map_err(FileManagerError::Database)?;
+                    }
+                }
+            }
+        }
+
+        tx.execute("DELETE FROM undo_entries WHERE id = ?1", params![undo_id])
+            .map_err(FileManagerError::Database)?;
+
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 写入完整的文件记录，用于恢复已删除的记录
+    fn insert_file_row(conn: &Connection, file: &FileInfo) -> Result<()> {
+        conn.execute(
+            &format!("INSERT INTO files ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)", FILE_COLUMNS),
+            params![
+                file.id,
+                file.name,
+                file.original_name,
+                file.directory_id,
+                file.file_path,
+                file.file_size,
+                file.mime_type,
+                file.version,
+                file.status.as_str(),
+                file.latitude,
+                file.longitude,
+                file.perceptual_hash.map(|hash| hash as i64),
+                file.source_url,
+                file.content_hash,
+                file.rating,
+                file.notes,
+                file.title,
+                file.linked,
+                file.archive_path,
+                file.packed,
+                file.processing_status.as_str(),
+                file.processing_error,
+                file.created_at.to_rfc3339(),
+                file.updated_at.to_rfc3339()
+            ],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 在一个事务中按顺序执行批量操作并记录撤销条目
+    ///
+    /// 任一操作失败时事务回滚，所有操作都不生效；标签和文件名应已规范化
+    pub async fn apply_batch_operations(&self, undo_id: &str, operations: &[BatchOperation], undo: &BatchUndo) -> Result<()> {
+        let payload = serde_json::to_string(undo)?;
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let now = Local::now().to_rfc3339();
+
+        run_batch_operations(&tx, operations, &now)?;
+
+        tx.execute(
+            "INSERT INTO undo_entries (id, operation_type, payload, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![undo_id, BATCH_OPERATIONS_OPERATION, payload, now],
+        ).map_err(FileManagerError::Database)?;
+
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 在事务中执行批量操作后回滚，返回实际执行时的各项修改
+    pub async fn preview_batch_operations(&self, operations: &[BatchOperation]) -> Result<Vec<PlannedChange>> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let changes = run_batch_operations(&tx, operations, &Local::now().to_rfc3339())?;
+        tx.rollback().map_err(FileManagerError::Database)?;
+        Ok(changes)
+    }
+
+    /// 在一个事务中恢复批量操作前的文件记录和标签，并移除撤销条目
+    ///
+    /// 之后又被删除的文件无法恢复，此时不做任何修改
+    pub async fn restore_batch_operations(&self, undo_id: &str, undo: &BatchUndo) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let now = Local::now().to_rfc3339();
+
+        for snapshot in &undo.files {
+            let file = &snapshot.file;
+            if undo.deleted.contains(&file.id) {
+                Self::insert_file_row(&tx, file)?;
+            } else {
+                let updated = tx.execute(
+                    "UPDATE files SET directory_id = ?1, original_name = ?2, updated_at = ?3 WHERE id = ?4",
+                    params![file.directory_id, file.original_name, now, file.id],
+                ).map_err(FileManagerError::Database)?;
+                if updated == 0 {
+                    return Err(FileManagerError::FileNotFound { path: file.id.clone() });
+                }
+            }
+
+            tx.execute("DELETE FROM file_tags WHERE file_id = ?1", params![file.id])
+                .map_err(FileManagerError::Database)?;
+            for tag in &snapshot.tags {
+                tx.execute(
+                    "INSERT INTO file_tags (file_id, tag, created_at) VALUES (?1, ?2, ?3)",
+                    params![file.id, tag, now],
+                ).map_err(FileManagerError::Database)?;
+            }
+        }
+
+        tx.execute("DELETE FROM undo_entries WHERE id = ?1", params![undo_id])
+            .map_err(FileManagerError::Database)?;
+
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 删除指定类型中最近 `keep` 条之外的撤销条目，返回被删除条目记录的数据
+    pub async fn prune_undo_entries(&self, operation_type: &str, keep: usize) -> Result<Vec<String>> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+
+        let expired = {
+            let mut stmt = tx.prepare(
+                "SELECT id, payload FROM undo_entries WHERE operation_type = ?1 ORDER BY created_at DESC LIMIT -1 OFFSET ?2"
+            ).map_err(FileManagerError::Database)?;
+            let rows = stmt.query_map(params![operation_type, keep as i64], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            }).map_err(FileManagerError::Database)?;
+
+            let mut expired = Vec::new();
+            for row in rows {
+                expired.push(row.map_err(FileManagerError::Database)?);
+            }
+            expired
+        };
+
+        for (id, _) in &expired {
+            tx.execute("DELETE FROM undo_entries WHERE id = ?1", params![id])
+                .map_err(FileManagerError::Database)?;
+        }
+
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(expired.into_iter().map(|(_, payload)| payload).collect())
+    }
+
+    /// 按时间从新到旧获取撤销条目对应的动态，从游标之后开始，`since` 之前的不返回
+    pub async fn get_operation_activity(
+        &self,
+        since: Option<&str>,
+        after: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<Vec<ActivityEntry>> {
+        let (after_time, after_id) = after.map_or(("", ""), |cursor| (cursor.sort_key.as_str(), cursor.id.as_str()));
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, operation_type, created_at FROM (
+                SELECT id, operation_type, created_at, 'operation:' || id AS entry_id FROM undo_entries
+            )
+            WHERE created_at >= ?1 AND (?2 = '' OR (created_at, entry_id) < (?2, ?3))
+            ORDER BY created_at DESC, entry_id DESC
+            LIMIT ?4
+            "#
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![since.unwrap_or(""), after_time, after_id, limit as i64], |row| Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+        ))).map_err(FileManagerError::Database)?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let (undo_id, operation_type, created_at) = row.map_err(FileManagerError::Database)?;
+            let Ok(occurred_at) = DateTime::parse_from_rfc3339(&created_at) else { continue };
+            entries.push(ActivityEntry {
+                id: format!("operation:{}", undo_id),
+                occurred_at: occurred_at.with_timezone(&Local),
+                detail: ActivityDetail::Operation { undo_id, operation_type },
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// 按导入时间从新到旧获取监视文件夹导入的动态，从游标之后开始，`since` 之前的不返回
+    ///
+    /// 导入时间即文件记录的创建时间，导入的文件已被删除时不返回
+    pub async fn get_watched_import_activity(
+        &self,
+        since: Option<&str>,
+        after: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<Vec<ActivityEntry>> {
+        let (after_time, after_id) = after.map_or(("", ""), |cursor| (cursor.sort_key.as_str(), cursor.id.as_str()));
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT folder_id, folder_path, source_path, file_id, file_name, created_at FROM (
+                SELECT i.folder_id, w.path AS folder_path, i.source_path, i.file_id,
+                       f.original_name AS file_name, f.created_at,
+                       'sync:' || i.folder_id || ':' || i.source_path AS entry_id
+                FROM watched_folder_imports i
+                JOIN watched_folders w ON w.id = i.folder_id
+                JOIN files f ON f.id = i.file_id
+            )
+            WHERE created_at >= ?1 AND (?2 = '' OR (created_at, entry_id) < (?2, ?3))
+            ORDER BY created_at DESC, entry_id DESC
+            LIMIT ?4
+            "#
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![since.unwrap_or(""), after_time, after_id, limit as i64], |row| Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, String>(5)?,
+        ))).map_err(FileManagerError::Database)?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let (folder_id, folder_path, source_path, file_id, file_name, created_at) = row.map_err(FileManagerError::Database)?;
+            let Ok(occurred_at) = DateTime::parse_from_rfc3339(&created_at) else { continue };
+            entries.push(ActivityEntry {
+                id: format!("sync:{}:{}", folder_id, source_path),
+                occurred_at: occurred_at.with_timezone(&Local),
+                detail: ActivityDetail::WatchedImport { folder_id, folder_path, source_path, file_id, file_name },
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// 记录文件的来源地址
+    pub async fn set_source_url(&self, id: &str, source_url: &str) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE files SET source_url = ?1 WHERE id = ?2",
+            params![source_url, id],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 保存文件的感知哈希
+    pub async fn set_perceptual_hash(&self, id: &str, hash: u64) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE files SET perceptual_hash = ?1 WHERE id = ?2",
+            params![hash as i64, id],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 获取所有已计算的感知哈希
+    pub async fn get_perceptual_hashes(&self) -> Result<Vec<(String, u64)>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, perceptual_hash FROM files WHERE perceptual_hash IS NOT NULL AND status = 'ok'"
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u64)))
+            .map_err(FileManagerError::Database)?;
+
+        let mut hashes = Vec::new();
+        for row in rows {
+            hashes.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(hashes)
+    }
+
+    /// 获取尚未计算感知哈希的图像文件
+    pub async fn get_unhashed_images(&self) -> Result<Vec<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM files WHERE perceptual_hash IS NULL AND mime_type LIKE 'image/%' AND status = 'ok'",
+            FILE_COLUMNS
+        )).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| self.row_to_file_info(row))
+            .map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(files)
+    }
+
+    /// 查询拍摄位置位于指定范围内的文件
+    pub async fn search_by_location(&self, bbox: &GeoBoundingBox) -> Result<Vec<FileInfo>> {
+        let longitude_condition = if bbox.crosses_antimeridian() {
+            "(longitude >= ?3 OR longitude <= ?4)"
+        } else {
+            "longitude BETWEEN ?3 AND ?4"
+        };
+
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM files WHERE latitude BETWEEN ?1 AND ?2 AND {} ORDER BY created_at",
+            FILE_COLUMNS, longitude_condition
+        )).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(
+            params![bbox.south, bbox.north, bbox.west, bbox.east],
+            |row| self.row_to_file_info(row),
+        ).map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(files)
+    }
+
+    /// 获取指定状态的所有文件
+    pub async fn get_files_by_status(&self, status: FileStatus) -> Result<Vec<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM files WHERE status = ?1 ORDER BY name", FILE_COLUMNS)
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![status.as_str()], |row| self.row_to_file_info(row))
+            .map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(files)
+    }
+
+    /// 获取所有链接文件
+    pub async fn get_linked_files(&self) -> Result<Vec<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM files WHERE linked = 1 ORDER BY name", FILE_COLUMNS)
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| self.row_to_file_info(row))
+            .map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(files)
+    }
+
+    /// 获取所有文件
+    pub async fn get_all_files(&self) -> Result<Vec<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM files ORDER BY name", FILE_COLUMNS)
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| self.row_to_file_info(row))
+            .map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(files)
+    }
+
+    /// 根据存储文件名获取文件信息
+    pub async fn get_file_by_name(&self, name: &str) -> Result<Option<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM files WHERE name = ?1", FILE_COLUMNS)
+        ).map_err(FileManagerError::Database)?;
+
+        let result = stmt.query_row(params![name], |row| {
+            Ok(self.row_to_file_info(row)?)
+        });
+
+        match result {
+            Ok(file) => Ok(Some(file)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(FileManagerError::Database(e)),
+        }
+    }
+
+    /// 获取目录下的所有文件
+    pub async fn get_files_in_directory(&self, directory_id: &str) -> Result<Vec<FileInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM files WHERE directory_id = ?1 ORDER BY name", FILE_COLUMNS)
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![directory_id], |row| {
+            Ok(self.row_to_file_info(row)?)
+        }).map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(files)
+    }
+
+    /// 按原始文件名分页获取目录中的文件，返回游标之后的至多 `limit` 项
+    pub async fn get_files_in_directory_after(
+        &self,
+        directory_id: &str,
+        after: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<Vec<FileInfo>> {
+        let (after_name, after_id) = after.map_or(("", ""), |cursor| (cursor.sort_key.as_str(), cursor.id.as_str()));
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {} FROM files
+            WHERE directory_id = ?1 AND (original_name, id) > (?2, ?3)
+            ORDER BY original_name, id
+            LIMIT ?4
+            "#,
+            FILE_COLUMNS
+        )).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![directory_id, after_name, after_id, limit as i64], |row| {
+            self.row_to_file_info(row)
+        }).map_err(FileManagerError::Database)?;
+
+        let mut files = Vec::new();
+        for row in rows {
+            files.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(files)
+    }
+
+    /// 删除文件记录及其标签、工作集成员、播放列表条目和使用统计
+    pub async fn delete_file(&self, id: &str) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        delete_file_rows(&tx, id).map_err(FileManagerError::Database)?;
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 批量添加和移除文件标签
+    ///
+    /// 所有修改在一个事务中完成，任一文件不存在时整体回滚；返回实际变更的标签关联数
+    pub async fn update_tags_bulk(
+        &self,
+        file_ids: &[String],
+        add_tags: &[String],
+        remove_tags: &[String],
+    ) -> Result<usize> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        let now = Local::now().to_rfc3339();
+        let mut changed = 0;
+
+        {
+            let mut exists_stmt = tx.prepare("SELECT COUNT(*) FROM files WHERE id = ?1")
+                .map_err(FileManagerError::Database)?;
+            let mut insert_stmt = tx.prepare(
+                "INSERT OR IGNORE INTO file_tags (file_id, tag, created_at) VALUES (?1, ?2, ?3)"
+            ).map_err(FileManagerError::Database)?;
+            let mut delete_stmt = tx.prepare(
+                "DELETE FROM file_tags WHERE file_id = ?1 AND tag = ?2"
+            ).map_err(FileManagerError::Database)?;
+
+            for file_id in file_ids {
+                let count: i64 = exists_stmt.query_row(params![file_id], |row| row.get(0))
+                    .map_err(FileManagerError::Database)?;
+                if count == 0 {
+                    return Err(FileManagerError::FileNotFound { path: file_id.clone() });
+                }
+
+                for tag in add_tags {
+                    changed += insert_stmt.execute(params![file_id, tag, now])
+                        .map_err(FileManagerError::Database)?;
+                }
+                for tag in remove_tags {
+                    changed += delete_stmt.execute(params![file_id, tag])
+                        .map_err(FileManagerError::Database)?;
+                }
+            }
+        }
+
+        tx.commit().map_err(FileManagerError::Database)?;
+        Ok(changed)
+    }
+
+    /// 获取文件的所有标签
+    pub async fn get_file_tags(&self, file_id: &str) -> Result<Vec<String>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT tag FROM file_tags WHERE file_id = ?1 ORDER BY tag"
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map(params![file_id], |row| row.get(0))
+            .map_err(FileManagerError::Database)?;
+
+        let mut tags = Vec::new();
+        for row in rows {
+            tags.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(tags)
+    }
+
+    /// 获取所有文件的标签，按文件 ID 分组
+    pub async fn get_all_file_tags(&self) -> Result<HashMap<String, Vec<String>>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT file_id, tag FROM file_tags ORDER BY file_id, tag"
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(FileManagerError::Database)?;
+
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        for row in rows {
+            let (file_id, tag) = row.map_err(FileManagerError::Database)?;
+            tags.entry(file_id).or_default().push(tag);
+        }
+
+        Ok(tags)
+    }
+
+    /// 获取所有标签及使用该标签的文件数
+    pub async fn get_tag_counts(&self) -> Result<Vec<(String, i64)>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT tag, COUNT(*) FROM file_tags GROUP BY tag ORDER BY tag"
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(FileManagerError::Database)?;
+
+        let mut tags = Vec::new();
+        for row in rows {
+            tags.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(tags)
+    }
+
+    /// 获取所有文件的原始文件名
+    pub async fn get_original_names(&self) -> Result<Vec<String>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT original_name FROM files")
+            .map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| row.get(0))
+            .map_err(FileManagerError::Database)?;
+
+        let mut names = Vec::new();
+        for row in rows {
+            names.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(names)
+    }
+
+    /// 获取所有文件的 ID、显示名称和所在目录，供快速切换使用
+    pub async fn get_file_names(&self) -> Result<Vec<(String, String, String)>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id, original_name, directory_id FROM files")
+            .map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(FileManagerError::Database)?;
+
+        let mut names = Vec::new();
+        for row in rows {
+            names.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(names)
+    }
+
+    /// 记录一次搜索
+    ///
+    /// 相同关键词（忽略大小写和编码差异）只保留一条，更新为最近一次的写法；
+    /// 超出 [`MAX_SEARCH_HISTORY`] 的最早记录被删除
+    pub async fn record_search(&self, query: &str) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO search_history (query_key, query, use_count, last_used_at) VALUES (?1, ?2, 1, ?3)
+            ON CONFLICT(query_key) DO UPDATE SET
+                query = excluded.query,
+                use_count = use_count + 1,
+                last_used_at = excluded.last_used_at
+            "#,
+            params![search_key(query), query, Local::now().to_rfc3339()],
+        ).map_err(FileManagerError::Database)?;
+
+        conn.execute(
+            r#"
+            DELETE FROM search_history WHERE query_key NOT IN (
+                SELECT query_key FROM search_history ORDER BY last_used_at DESC LIMIT ?1
+            )
+            "#,
+            params![MAX_SEARCH_HISTORY as i64],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 获取搜索历史，最近使用的在前
+    pub async fn get_search_history(&self) -> Result<Vec<SearchHistoryEntry>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT query, use_count, last_used_at FROM search_history ORDER BY last_used_at DESC"
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(SearchHistoryEntry {
+                query: row.get(0)?,
+                use_count: row.get(1)?,
+                last_used_at: row.get(2)?,
+            })
+        }).map_err(FileManagerError::Database)?;
+
+        let mut history = Vec::new();
+        for row in rows {
+            history.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(history)
+    }
+
+    /// 清空搜索历史
+    pub async fn clear_search_history(&self) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute("DELETE FROM search_history", [])
+            .map_err(FileManagerError::Database)?;
+        Ok(())
+    }
+
+    /// 获取完整的目录树
+    pub async fn get_directory_tree(&self) -> Result<Vec<DirectoryInfo>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, parent_id, path, created_at, updated_at FROM directories ORDER BY path"
+        ).map_err(FileManagerError::Database)?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(self.row_to_directory_info(row)?)
+        }).map_err(FileManagerError::Database)?;
+
+        let mut directories = Vec::new();
+        for row in rows {
+            directories.push(row.map_err(FileManagerError::Database)?);
+        }
+
+        Ok(directories)
+    }
+
+    /// 获取所有目录的递归大小（字节数和文件数，包含所有子目录）
+    ///
+    /// 优先读取缓存；缓存被触发器清空后，用一次闭包表聚合查询重新计算全部目录并写回缓存
+    pub async fn get_directory_sizes(&self) -> Result<HashMap<String, (i64, i64)>> {
+        let mut conn = self.connection.lock().unwrap();
+
+        let mut sizes = HashMap::new();
+        {
+            let mut stmt = conn.prepare(
+                "SELECT directory_id, total_size, total_files FROM directory_size_cache"
+            ).map_err(FileManagerError::Database)?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, (row.get(1)?, row.get(2)?)))
+            }).map_err(FileManagerError::Database)?;
+            for row in rows {
+                let (id, size) = row.map_err(FileManagerError::Database)?;
+                sizes.insert(id, size);
+            }
+        }
+
+        let directory_count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM directories", [], |row| row.get(0),
+        ).map_err(FileManagerError::Database)?;
+        if sizes.len() as i64 == directory_count {
+            return Ok(sizes);
+        }
+
+        // 缓存失效，按闭包表把每个文件计入其所在目录及所有祖先目录
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        sizes.clear();
+        {
+            let mut stmt = tx.prepare(
+                r#"
+                SELECT c.ancestor_id, COALESCE(SUM(f.file_size), 0), COUNT(f.id)
+                FROM directory_closure c LEFT JOIN files f ON f.directory_id = c.descendant_id
+                GROUP BY c.ancestor_id
+                "#
+            ).map_err(FileManagerError::Database)?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, (row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)))
+            }).map_err(FileManagerError::Database)?;
+
+            for row in rows {
+                let (id, size) = row.map_err(FileManagerError::Database)?;
+                sizes.insert(id, size);
+            }
+        }
+
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO directory_size_cache (directory_id, total_size, total_files) VALUES (?1, ?2, ?3)"
+            ).map_err(FileManagerError::Database)?;
+            for (id, (size, count)) in &sizes {
+                stmt.execute(params![id, size, count]).map_err(FileManagerError::Database)?;
+            }
+        }
+        tx.commit().map_err(FileManagerError::Database)?;
+
+        Ok(sizes)
+    }
+
+    /// 检查路径是否已存在
+    pub async fn path_exists(&self, path: &str) -> Result<bool> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT COUNT(*) FROM directories WHERE path = ?1"
+        ).map_err(FileManagerError::Database)?;
+
+        let count: i64 = stmt.query_row(params![path], |row| {
+            row.get(0)
         }).map_err(FileManagerError::Database)?;
 
-        let mut directories = Vec::new();
-        for row in rows {
-            directories.push(row.map_err(FileManagerError::Database)?);
-        }
+        Ok(count > 0)
+    }
 
-        Ok(directories)
+    /// 在操作日志中记录即将执行的操作
+    pub async fn journal_begin(&self, operation: &JournalOperation) -> Result<i64> {
+        let payload = serde_json::to_string(operation)?;
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "INSERT INTO operation_journal (operation_type, payload, created_at) VALUES (?1, ?2, ?3)",
+            params![operation.kind(), payload, Local::now().to_rfc3339()],
+        ).map_err(FileManagerError::Database)?;
+
+        Ok(conn.last_insert_rowid())
     }
 
-    /// 删除目录（级联删除子目录和文件）
-    pub async fn delete_directory(&self, id: &str) -> Result<()> {
+    /// 标记操作完成并从日志中移除
+    pub async fn journal_complete(&self, id: i64) -> Result<()> {
         let conn = self.connection.lock().unwrap();
         conn.execute(
-            "DELETE FROM directories WHERE id = ?1",
+            "DELETE FROM operation_journal WHERE id = ?1",
             params![id],
         ).map_err(FileManagerError::Database)?;
 
         Ok(())
     }
 
-    /// 创建文件记录
-    pub async fn create_file(
-        &self,
-        name: &str,
-        original_name: &str,
-        directory_id: &str,
-        file_path: &str,
-        file_size: i64,
-        mime_type: &str,
-    ) -> Result<FileInfo> {
-        let id = Uuid::new_v4().to_string();
-        let now = Local::now();
-        
+    /// 标记操作执行失败，启动恢复时不再重放
+    pub async fn journal_fail(&self, id: i64, error: &str) -> Result<()> {
         let conn = self.connection.lock().unwrap();
         conn.execute(
-            r#"
-            INSERT INTO files (id, name, original_name, directory_id, file_path, file_size, mime_type, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
-            "#,
-            params![
-                id,
-                name,
-                original_name,
-                directory_id,
-                file_path,
-                file_size,
-                mime_type,
-                now.to_rfc3339(),
-                now.to_rfc3339()
-            ],
+            "UPDATE operation_journal SET status = 'failed', error = ?2 WHERE id = ?1",
+            params![id, error],
         ).map_err(FileManagerError::Database)?;
 
-        Ok(FileInfo {
-            id,
-            name: name.to_string(),
-            original_name: original_name.to_string(),
-            directory_id: directory_id.to_string(),
-            file_path: file_path.to_string(),
-            file_size,
-            mime_type: mime_type.to_string(),
-            created_at: now,
-            updated_at: now,
-        })
+        Ok(())
     }
 
-    /// 获取文件信息
-    pub async fn get_file(&self, id: &str) -> Result<Option<FileInfo>> {
+    /// 获取所有未完成的操作（按记录顺序），不包括已标记失败的操作
+    pub async fn get_pending_journal_entries(&self) -> Result<Vec<JournalEntry>> {
         let conn = self.connection.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, name, original_name, directory_id, file_path, file_size, mime_type, created_at, updated_at FROM files WHERE id = ?1"
+            "SELECT id, payload, created_at FROM operation_journal WHERE status = 'pending' ORDER BY id"
         ).map_err(FileManagerError::Database)?;
 
-        let result = stmt.query_row(params![id], |row| {
-            Ok(self.row_to_file_info(row)?)
-        });
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        }).map_err(FileManagerError::Database)?;
 
-        match result {
-            Ok(file) => Ok(Some(file)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(FileManagerError::Database(e)),
+        let mut entries = Vec::new();
+        for row in rows {
+            let (id, payload, created_at) = row.map_err(FileManagerError::Database)?;
+            entries.push(JournalEntry {
+                id,
+                operation: serde_json::from_str(&payload)?,
+                created_at,
+            });
         }
+
+        Ok(entries)
     }
 
-    /// 获取目录下的所有文件
-    pub async fn get_files_in_directory(&self, directory_id: &str) -> Result<Vec<FileInfo>> {
+    /// 登记任务，已存在时替换参数、清空检查点并重新标记为运行中
+    pub async fn save_job(&self, id: &str, job: &QueuedJob) -> Result<()> {
         let conn = self.connection.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, original_name, directory_id, file_path, file_size, mime_type, created_at, updated_at FROM files WHERE directory_id = ?1 ORDER BY name"
+        let now = Local::now().to_rfc3339();
+        conn.execute(
+            r#"
+            INSERT INTO job_queue (id, job, checkpoint, state, created_at, updated_at)
+            VALUES (?1, ?2, '{}', 'running', ?3, ?3)
+            ON CONFLICT(id) DO UPDATE SET
+                job = excluded.job, checkpoint = '{}', state = 'running', updated_at = excluded.updated_at
+            "#,
+            params![id, serde_json::to_string(job)?, now],
         ).map_err(FileManagerError::Database)?;
 
-        let rows = stmt.query_map(params![directory_id], |row| {
-            Ok(self.row_to_file_info(row)?)
-        }).map_err(FileManagerError::Database)?;
+        Ok(())
+    }
 
-        let mut files = Vec::new();
-        for row in rows {
-            files.push(row.map_err(FileManagerError::Database)?);
-        }
+    /// 记录任务检查点
+    pub async fn set_job_checkpoint(&self, id: &str, checkpoint: &JobCheckpoint) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE job_queue SET checkpoint = ?1, updated_at = ?2 WHERE id = ?3",
+            params![serde_json::to_string(checkpoint)?, Local::now().to_rfc3339(), id],
+        ).map_err(FileManagerError::Database)?;
 
-        Ok(files)
+        Ok(())
     }
 
-    /// 删除文件记录
-    pub async fn delete_file(&self, id: &str) -> Result<()> {
+    /// 记录任务已写入的输出文件
+    pub async fn add_job_output(&self, id: &str, path: &str) -> Result<()> {
         let conn = self.connection.lock().unwrap();
         conn.execute(
-            "DELETE FROM files WHERE id = ?1",
-            params![id],
+            "INSERT OR IGNORE INTO job_outputs (job_id, path) VALUES (?1, ?2)",
+            params![id, path],
         ).map_err(FileManagerError::Database)?;
+
         Ok(())
     }
 
-    /// 获取完整的目录树
-    pub async fn get_directory_tree(&self) -> Result<Vec<DirectoryInfo>> {
+    /// 获取任务已写入的输出文件，按路径排列
+    pub async fn get_job_outputs(&self, id: &str) -> Result<Vec<String>> {
         let conn = self.connection.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, parent_id, path, created_at, updated_at FROM directories ORDER BY path"
-        ).map_err(FileManagerError::Database)?;
+        let mut stmt = conn.prepare("SELECT path FROM job_outputs WHERE job_id = ?1 ORDER BY path")
+            .map_err(FileManagerError::Database)?;
 
-        let rows = stmt.query_map([], |row| {
-            Ok(self.row_to_directory_info(row)?)
-        }).map_err(FileManagerError::Database)?;
+        let rows = stmt.query_map(params![id], |row| row.get::<_, String>(0))
+            .map_err(FileManagerError::Database)?;
 
-        let mut directories = Vec::new();
+        let mut paths = Vec::new();
         for row in rows {
-            directories.push(row.map_err(FileManagerError::Database)?);
+            paths.push(row.map_err(FileManagerError::Database)?);
         }
 
-        Ok(directories)
+        Ok(paths)
     }
 
-    /// 检查路径是否已存在
-    pub async fn path_exists(&self, path: &str) -> Result<bool> {
+    /// 在一个事务中移除任务及其输出记录
+    pub async fn delete_job(&self, id: &str) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(FileManagerError::Database)?;
+        tx.execute("DELETE FROM job_outputs WHERE job_id = ?1", params![id])
+            .map_err(FileManagerError::Database)?;
+        tx.execute("DELETE FROM job_queue WHERE id = ?1", params![id])
+            .map_err(FileManagerError::Database)?;
+        tx.commit().map_err(FileManagerError::Database)?;
+
+        Ok(())
+    }
+
+    /// 把队列中运行中的任务标记为被中断，返回标记的任务数
+    ///
+    /// 启动时在接受命令之前调用，此时运行中的任务都属于上次运行
+    pub async fn mark_interrupted_jobs(&self) -> Result<usize> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute("UPDATE job_queue SET state = 'interrupted' WHERE state = 'running'", [])
+            .map_err(FileManagerError::Database)
+    }
+
+    /// 获取被中断的任务，按登记顺序排列
+    pub async fn get_interrupted_jobs(&self) -> Result<Vec<PersistedJob>> {
         let conn = self.connection.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT COUNT(*) FROM directories WHERE path = ?1"
+            "SELECT id, job, checkpoint, updated_at FROM job_queue WHERE state = 'interrupted' ORDER BY created_at, id"
         ).map_err(FileManagerError::Database)?;
 
-        let count: i64 = stmt.query_row(params![path], |row| {
-            row.get(0)
-        }).map_err(FileManagerError::Database)?;
+        let rows = stmt.query_map([], |row| Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+        ))).map_err(FileManagerError::Database)?;
 
-        Ok(count > 0)
+        let mut jobs = Vec::new();
+        for row in rows {
+            let (id, job, checkpoint, updated_at) = row.map_err(FileManagerError::Database)?;
+            jobs.push(PersistedJob {
+                id,
+                job: serde_json::from_str(&job)?,
+                checkpoint: serde_json::from_str(&checkpoint)?,
+                updated_at: DateTime::parse_from_rfc3339(&updated_at)
+                    .map(|time| time.with_timezone(&Local))
+                    .unwrap_or_else(|_| Local::now()),
+            });
+        }
+
+        Ok(jobs)
     }
 
     /// 将数据库行转换为目录信息
@@ -379,22 +4478,199 @@ impl DatabaseService {
             file_path: row.get("file_path")?,
             file_size: row.get("file_size")?,
             mime_type: row.get("mime_type")?,
+            version: row.get("version")?,
+            status: FileStatus::from_db(&row.get::<_, String>("status")?),
+            latitude: row.get("latitude")?,
+            longitude: row.get("longitude")?,
+            // SQLite 只有有符号整数，哈希按位存储为 i64
+            perceptual_hash: row.get::<_, Option<i64>>("perceptual_hash")?.map(|hash| hash as u64),
+            source_url: row.get("source_url")?,
+            content_hash: row.get("content_hash")?,
+            rating: row.get("rating")?,
+            notes: row.get("notes")?,
+            title: row.get("title")?,
+            linked: row.get("linked")?,
+            archive_path: row.get("archive_path")?,
+            packed: row.get("packed")?,
+            processing_status: ProcessingStatus::from_db(&row.get::<_, String>("processing_status")?),
+            processing_error: row.get("processing_error")?,
             created_at,
             updated_at,
         })
     }
 }
 
+
+/// 在事务中按顺序执行批量操作，返回各项修改
+fn run_batch_operations(tx: &rusqlite::Transaction, operations: &[BatchOperation], now: &str) -> Result<Vec<PlannedChange>> {
+    let name_taken = |directory_id: &str, name: &str, file_id: &str| -> Result<bool> {
+        let count: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM files WHERE directory_id = ?1 AND original_name = ?2 AND id != ?3",
+            params![directory_id, name, file_id],
+            |row| row.get(0),
+        ).map_err(FileManagerError::Database)?;
+        Ok(count > 0)
+    };
+
+    let mut changes = Vec::with_capacity(operations.len());
+    for operation in operations {
+        // 前面的操作可能已删除或移动了文件，每次都读取事务内的最新状态
+        let file_id = operation.file_id();
+        let current = tx.query_row(
+            "SELECT f.directory_id, f.original_name, d.path FROM files f JOIN directories d ON d.id = f.directory_id WHERE f.id = ?1",
+            params![file_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+        );
+        let (directory_id, original_name, directory_path) = match current {
+            Ok(current) => current,
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                return Err(FileManagerError::FileNotFound { path: file_id.to_string() });
+            }
+            Err(e) => return Err(FileManagerError::Database(e)),
+        };
+        let path = display_path(&directory_path, &original_name);
+
+        match operation {
+            BatchOperation::Move { directory_id: target, .. } => {
+                if *target == directory_id {
+                    continue;
+                }
+                let target_path: String = match tx.query_row(
+                    "SELECT path FROM directories WHERE id = ?1",
+                    params![target],
+                    |row| row.get(0),
+                ) {
+                    Ok(target_path) => target_path,
+                    Err(rusqlite::Error::QueryReturnedNoRows) => {
+                        return Err(FileManagerError::DirectoryNotFound { path: target.clone() });
+                    }
+                    Err(e) => return Err(FileManagerError::Database(e)),
+                };
+
+                let mut name = original_name.clone();
+                let mut index = 1;
+                while name_taken(target, &name, file_id)? {
+                    name = with_copy_suffix(&original_name, index);
+                    index += 1;
+                }
+                tx.execute(
+                    "UPDATE files SET directory_id = ?1, original_name = ?2, updated_at = ?3 WHERE id = ?4",
+                    params![target, name, now, file_id],
+                ).map_err(FileManagerError::Database)?;
+                changes.push(PlannedChange::MoveFile {
+                    file_id: file_id.to_string(),
+                    path,
+                    new_path: display_path(&target_path, &name),
+                });
+            }
+            BatchOperation::Rename { new_name, .. } => {
+                if name_taken(&directory_id, new_name, file_id)? {
+                    return Err(FileManagerError::general_error(format!(
+                        "A file named {} already exists in the directory", new_name
+                    )));
+                }
+                tx.execute(
+                    "UPDATE files SET original_name = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![new_name, now, file_id],
+                ).map_err(FileManagerError::Database)?;
+                changes.push(PlannedChange::RenameFile {
+                    file_id: file_id.to_string(),
+                    path,
+                    new_path: display_path(&directory_path, new_name),
+                });
+            }
+            BatchOperation::Tag { add_tags, remove_tags, .. } => {
+                let mut added = Vec::new();
+                for tag in add_tags {
+                    let inserted = tx.execute(
+                        "INSERT OR IGNORE INTO file_tags (file_id, tag, created_at) VALUES (?1, ?2, ?3)",
+                        params![file_id, tag, now],
+                    ).map_err(FileManagerError::Database)?;
+                    if inserted > 0 {
+                        added.push(tag.clone());
+                    }
+                }
+                let mut removed = Vec::new();
+                for tag in remove_tags {
+                    let deleted = tx.execute(
+                        "DELETE FROM file_tags WHERE file_id = ?1 AND tag = ?2",
+                        params![file_id, tag],
+                    ).map_err(FileManagerError::Database)?;
+                    if deleted > 0 {
+                        removed.push(tag.clone());
+                    }
+                }
+                if !added.is_empty() || !removed.is_empty() {
+                    changes.push(PlannedChange::TagFile { file_id: file_id.to_string(), path, added, removed });
+                }
+            }
+            BatchOperation::Delete { .. } => {
+                delete_file_rows(tx, file_id).map_err(FileManagerError::Database)?;
+                changes.push(PlannedChange::DeleteFile { file_id: file_id.to_string(), path });
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tempfile::NamedTempFile;
+    use tempfile::{NamedTempFile, TempDir};
 
     async fn create_test_db() -> DatabaseService {
         let temp_file = NamedTempFile::new().unwrap();
         DatabaseService::new(temp_file.path()).await.unwrap()
     }
 
+    /// 数据库位于临时目录的 `test.db`，用于需要重新打开数据库或在旁边写入文件的测试
+    async fn create_test_db_in_dir() -> (DatabaseService, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = DatabaseService::new(&temp_dir.path().join("test.db")).await.unwrap();
+        (db, temp_dir)
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_and_change_passphrase() {
+        let (db, temp_dir) = create_test_db_in_dir().await;
+        let path = temp_dir.path().join("test.db");
+        let dir = db.create_directory("photos", None, "/photos").await.unwrap();
+
+        db.encrypt("first secret").await.unwrap();
+        assert!(db.is_encrypted());
+        assert!(db.get_directory(&dir.id).await.unwrap().is_some());
+        assert!(db.encrypt("again").await.is_err());
+
+        assert!(matches!(DatabaseService::open(&path, None).await, Err(FileManagerError::LibraryLocked)));
+        assert!(matches!(DatabaseService::open(&path, Some("wrong")).await, Err(FileManagerError::IncorrectPassword)));
+
+        assert!(matches!(
+            db.change_passphrase("wrong", "second secret").await,
+            Err(FileManagerError::IncorrectPassword)
+        ));
+        db.change_passphrase("first secret", "second secret").await.unwrap();
+        drop(db);
+
+        let reopened = DatabaseService::open(&path, Some("second secret")).await.unwrap();
+        assert!(reopened.get_directory(&dir.id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_keeps_encryption() {
+        let (db, temp_dir) = create_test_db_in_dir().await;
+        let dir = db.create_directory("photos", None, "/photos").await.unwrap();
+        db.encrypt("secret").await.unwrap();
+
+        let snapshot = temp_dir.path().join("snapshot.db");
+        db.snapshot_to(&snapshot).await.unwrap();
+        assert!(db.snapshot_to(&snapshot).await.is_err());
+
+        assert!(matches!(DatabaseService::open(&snapshot, None).await, Err(FileManagerError::LibraryLocked)));
+        let restored = DatabaseService::open(&snapshot, Some("secret")).await.unwrap();
+        assert!(restored.get_directory(&dir.id).await.unwrap().is_some());
+    }
+
     #[tokio::test]
     async fn test_create_and_get_directory() {
         let db = create_test_db().await;
@@ -432,6 +4708,314 @@ mod tests {
         assert_eq!(retrieved.name, file.name);
     }
 
+    #[tokio::test]
+    async fn test_app_state() {
+        let db = create_test_db().await;
+
+        assert!(db.get_state("clean_shutdown").await.unwrap().is_none());
+
+        db.set_state("clean_shutdown", "false").await.unwrap();
+        db.set_state("clean_shutdown", "true").await.unwrap();
+        assert_eq!(db.get_state("clean_shutdown").await.unwrap().as_deref(), Some("true"));
+
+        db.flush().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_move_directory_rewrites_subtree() {
+        let db = create_test_db().await;
+
+        let a = db.create_directory("a", None, "/a").await.unwrap();
+        let b = db.create_directory("b", Some(&a.id), "/a/b").await.unwrap();
+        let c = db.create_directory("c", Some(&b.id), "/a/b/c").await.unwrap();
+        let ab = db.create_directory("ab", None, "/ab").await.unwrap();
+
+        db.move_directory(&b.id, None, "/a/b", "/b").await.unwrap();
+
+        let b = db.get_directory(&b.id).await.unwrap().unwrap();
+        assert_eq!(b.path, "/b");
+        assert!(b.parent_id.is_none());
+        assert_eq!(db.get_directory(&c.id).await.unwrap().unwrap().path, "/b/c");
+        // 仅前缀相同的兄弟目录不受影响
+        assert_eq!(db.get_directory(&ab.id).await.unwrap().unwrap().path, "/ab");
+    }
+
+    #[tokio::test]
+    async fn test_directory_closure_follows_moves_and_rebuilds() {
+        let (db, temp_dir) = create_test_db_in_dir().await;
+
+        let a = db.create_directory("a", None, "/a").await.unwrap();
+        let b = db.create_directory("b", Some(&a.id), "/a/b").await.unwrap();
+        let c = db.create_directory("c", Some(&b.id), "/a/b/c").await.unwrap();
+        let x = db.create_directory("x", None, "/x").await.unwrap();
+        db.create_file("1.bin", "1.bin", &c.id, "1.bin", 5, "application/octet-stream").await.unwrap();
+
+        let ancestors = |db: &DatabaseService, id: &str| {
+            let conn = db.connection.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "SELECT ancestor_id, depth FROM directory_closure WHERE descendant_id = ?1 ORDER BY depth"
+            ).unwrap();
+            stmt.query_map(params![id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+                .unwrap()
+                .collect::<rusqlite::Result<Vec<_>>>()
+                .unwrap()
+        };
+        assert_eq!(ancestors(&db, &c.id), [(c.id.clone(), 0), (b.id.clone(), 1), (a.id.clone(), 2)]);
+
+        db.move_directory(&b.id, Some(&x.id), "/a/b", "/x/b").await.unwrap();
+        assert_eq!(ancestors(&db, &c.id), [(c.id.clone(), 0), (b.id.clone(), 1), (x.id.clone(), 2)]);
+        assert_eq!(db.count_directory_contents("/x").await.unwrap(), (1, 2));
+        assert_eq!(db.count_directory_contents("/a").await.unwrap(), (0, 0));
+        let sizes = db.get_directory_sizes().await.unwrap();
+        assert_eq!(sizes[&x.id], (5, 1));
+        assert_eq!(sizes[&a.id], (0, 0));
+
+        // 旧版本数据库没有闭包表，重新打开时按父目录关系重建
+        db.connection.lock().unwrap().execute("DELETE FROM directory_closure", []).unwrap();
+        drop(db);
+        let db = DatabaseService::new(&temp_dir.path().join("test.db")).await.unwrap();
+        assert_eq!(ancestors(&db, &c.id), [(c.id.clone(), 0), (b.id.clone(), 1), (x.id.clone(), 2)]);
+        assert_eq!(db.get_files_in_subtree("/x").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_delete_directory_removes_subtree() {
+        let db = create_test_db().await;
+
+        let a = db.create_directory("a", None, "/a").await.unwrap();
+        let b = db.create_directory("b", Some(&a.id), "/a/b").await.unwrap();
+        let ab = db.create_directory("ab", None, "/ab").await.unwrap();
+        db.create_file("f1.txt", "f1.txt", &a.id, "/s/f1.txt", 1, "text/plain").await.unwrap();
+        db.create_file("f2.txt", "f2.txt", &b.id, "/s/f2.txt", 1, "text/plain").await.unwrap();
+        db.create_file("f3.txt", "f3.txt", &ab.id, "/s/f3.txt", 1, "text/plain").await.unwrap();
+
+        assert_eq!(db.count_directory_contents("/a").await.unwrap(), (2, 1));
+        assert_eq!(db.get_files_in_subtree("/a").await.unwrap().len(), 2);
+
+        db.delete_directory(&a.id).await.unwrap();
+
+        assert!(db.get_directory(&b.id).await.unwrap().is_none());
+        assert!(db.get_directory(&ab.id).await.unwrap().is_some());
+        assert_eq!(db.get_files_in_subtree("/ab").await.unwrap().len(), 1);
+        assert_eq!(db.count_directory_contents("/a").await.unwrap(), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_delete_file_removes_dependent_rows() {
+        let db = create_test_db().await;
+
+        let dir = db.create_directory("test", None, "/test").await.unwrap();
+        let file = db.create_file("a.jpg", "a.jpg", &dir.id, "a.jpg", 1, "image/jpeg").await.unwrap();
+        let file_ids = vec![file.id.clone()];
+        db.update_tags_bulk(&file_ids, &["red".to_string()], &[]).await.unwrap();
+        let working_set = db.create_working_set(None, 60).await.unwrap();
+        db.update_working_set_members(&working_set.id, &file_ids, &[]).await.unwrap();
+        db.save_playlist(None, "review", None, &file_ids).await.unwrap();
+        db.record_file_usage(&file.id, UsageKind::Placement, Some("board")).await.unwrap();
+
+        db.delete_file(&file.id).await.unwrap();
+
+        let conn = db.connection.lock().unwrap();
+        for table in FILE_DEPENDENT_TABLES {
+            let count: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM {} WHERE file_id = ?1", table),
+                params![file.id],
+                |row| row.get(0),
+            ).unwrap();
+            assert_eq!(count, 0, "{} still references the deleted file", table);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_tags_bulk() {
+        let db = create_test_db().await;
+
+        let dir = db.create_directory("test", None, "/test").await.unwrap();
+        let a = db.create_file("a.jpg", "a.jpg", &dir.id, "a.jpg", 1, "image/jpeg").await.unwrap();
+        let b = db.create_file("b.jpg", "b.jpg", &dir.id, "b.jpg", 1, "image/jpeg").await.unwrap();
+        let ids = vec![a.id.clone(), b.id.clone()];
+
+        let changed = db.update_tags_bulk(&ids, &["red".to_string(), "sky".to_string()], &[]).await.unwrap();
+        assert_eq!(changed, 4);
+
+        let changed = db.update_tags_bulk(&ids, &["red".to_string()], &["sky".to_string()]).await.unwrap();
+        assert_eq!(changed, 2);
+        assert_eq!(db.get_file_tags(&a.id).await.unwrap(), vec!["red".to_string()]);
+
+        // 任一文件不存在时整体回滚
+        let ids = vec![a.id.clone(), "missing".to_string()];
+        assert!(db.update_tags_bulk(&ids, &["blue".to_string()], &[]).await.is_err());
+        assert_eq!(db.get_file_tags(&a.id).await.unwrap(), vec!["red".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_directory_sizes_are_cached_and_invalidated() {
+        let db = create_test_db().await;
+
+        let a = db.create_directory("a", None, "/a").await.unwrap();
+        let b = db.create_directory("b", Some(&a.id), "/a/b").await.unwrap();
+        let ab = db.create_directory("ab", None, "/ab").await.unwrap();
+        db.create_file("1.bin", "1.bin", &a.id, "1.bin", 10, "application/octet-stream").await.unwrap();
+        db.create_file("2.bin", "2.bin", &b.id, "2.bin", 32, "application/octet-stream").await.unwrap();
+
+        let sizes = db.get_directory_sizes().await.unwrap();
+        assert_eq!(sizes[&a.id], (42, 2));
+        assert_eq!(sizes[&b.id], (32, 1));
+        assert_eq!(sizes[&ab.id], (0, 0));
+
+        // 写入文件后缓存失效并重新计算
+        let file = db.create_file("3.bin", "3.bin", &b.id, "3.bin", 8, "application/octet-stream").await.unwrap();
+        assert_eq!(db.get_directory_sizes().await.unwrap()[&a.id], (50, 3));
+
+        db.delete_file(&file.id).await.unwrap();
+        assert_eq!(db.get_directory_sizes().await.unwrap()[&b.id], (32, 1));
+    }
+
+    #[tokio::test]
+    async fn test_journal_lifecycle() {
+        let db = create_test_db().await;
+
+        let operation = JournalOperation::DeleteFile {
+            file_id: "file-1".to_string(),
+            file_path: "/tmp/file-1".to_string(),
+            linked: false,
+        };
+        let id = db.journal_begin(&operation).await.unwrap();
+
+        let pending = db.get_pending_journal_entries().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].operation, operation);
+
+        db.journal_complete(id).await.unwrap();
+        assert!(db.get_pending_journal_entries().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replace_file_content() {
+        let db = create_test_db().await;
+
+        let dir = db.create_directory("test", None, "/test").await.unwrap();
+        let file = db.create_file("v1.jpg", "photo.jpg", &dir.id, "/path/v1.jpg", 10, "image/jpeg").await.unwrap();
+        assert_eq!(file.version, 1);
+
+        let found = db.find_file_by_original_name(&dir.id, "photo.jpg").await.unwrap().unwrap();
+        assert_eq!(found.id, file.id);
+
+        let replaced = db.replace_file_content(&file.id, "v2.jpg", "/path/v2.jpg", 20, "image/jpeg", 2).await.unwrap();
+        assert_eq!(replaced.id, file.id);
+        assert_eq!(replaced.original_name, "photo.jpg");
+        assert_eq!(replaced.file_path, "/path/v2.jpg");
+        assert_eq!(replaced.version, 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_by_location() {
+        let db = create_test_db().await;
+
+        let dir = db.create_directory("test", None, "/test").await.unwrap();
+        let sydney = db.create_file("a.jpg", "a.jpg", &dir.id, "/path/a.jpg", 1, "image/jpeg").await.unwrap();
+        let fiji = db.create_file("b.jpg", "b.jpg", &dir.id, "/path/b.jpg", 1, "image/jpeg").await.unwrap();
+        db.create_file("c.jpg", "c.jpg", &dir.id, "/path/c.jpg", 1, "image/jpeg").await.unwrap();
+
+        db.set_file_location(&sydney.id, Some(GeoLocation { latitude: -33.86, longitude: 151.21 })).await.unwrap();
+        db.set_file_location(&fiji.id, Some(GeoLocation { latitude: -17.8, longitude: -178.0 })).await.unwrap();
+
+        let bbox = GeoBoundingBox { south: -40.0, west: 140.0, north: -30.0, east: 160.0 };
+        let found = db.search_by_location(&bbox).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].latitude, Some(-33.86));
+
+        // 跨越 180° 经线的范围
+        let bbox = GeoBoundingBox { south: -40.0, west: 150.0, north: 0.0, east: -170.0 };
+        assert_eq!(db.search_by_location(&bbox).await.unwrap().len(), 2);
+
+        db.set_file_location(&sydney.id, None).await.unwrap();
+        assert_eq!(db.search_by_location(&bbox).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_daily_additions() {
+        let db = create_test_db().await;
+
+        let dir = db.create_directory("test", None, "/test").await.unwrap();
+        for (name, size, created_at) in [
+            ("a.jpg", 10, "2024-03-04T09:00:00+08:00"),
+            ("b.jpg", 20, "2024-03-04T23:30:00+08:00"),
+            ("c.jpg", 40, "2024-03-06T08:00:00+08:00"),
+            ("d.jpg", 80, "2024-05-01T08:00:00+08:00"),
+        ] {
+            let file = db.create_file(name, name, &dir.id, &format!("/path/{}", name), size, "image/jpeg").await.unwrap();
+            db.connection.lock().unwrap()
+                .execute("UPDATE files SET created_at = ?1 WHERE id = ?2", params![created_at, file.id])
+                .unwrap();
+        }
+
+        let start = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+        let daily = db.get_daily_additions(start, end).await.unwrap();
+        assert_eq!(daily, vec![
+            (NaiveDate::from_ymd_opt(2024, 3, 4).unwrap(), 2, 30),
+            (NaiveDate::from_ymd_opt(2024, 3, 6).unwrap(), 1, 40),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_storage_breakdown() {
+        let db = create_test_db().await;
+
+        let root = db.create_directory("root", None, "/").await.unwrap();
+        let photos = db.create_directory("photos", Some(&root.id), "/photos").await.unwrap();
+        let trips = db.create_directory("trips", Some(&photos.id), "/photos/trips").await.unwrap();
+
+        db.create_file("a.jpg", "a.jpg", &photos.id, "/path/a.jpg", 100, "image/jpeg").await.unwrap();
+        db.create_file("b.jpg", "b.jpg", &trips.id, "/path/b.jpg", 200, "image/jpeg").await.unwrap();
+        let old = db.create_file("c.txt", "c.txt", &root.id, "/path/c.txt", 5, "text/plain").await.unwrap();
+        db.connection.lock().unwrap()
+            .execute("UPDATE files SET created_at = '2001-01-01T00:00:00+00:00' WHERE id = ?1", params![old.id])
+            .unwrap();
+
+        let breakdown = db.get_storage_breakdown().await.unwrap();
+        assert_eq!(breakdown.by_mime_type[0], UsageGroup { key: "image/jpeg".to_string(), file_count: 2, total_size: 300 });
+        assert_eq!(breakdown.by_top_level_directory, vec![
+            UsageGroup { key: "/photos".to_string(), file_count: 2, total_size: 300 },
+            UsageGroup { key: "/".to_string(), file_count: 1, total_size: 5 },
+        ]);
+        assert_eq!(breakdown.by_age, vec![
+            UsageGroup { key: "last_7_days".to_string(), file_count: 2, total_size: 300 },
+            UsageGroup { key: "older".to_string(), file_count: 1, total_size: 5 },
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_job_queue_survives_restart() {
+        let db = create_test_db().await;
+        let job = QueuedJob::DirectoryDelete {
+            directory_id: "dir-1".to_string(),
+            path: "/photos".to_string(),
+            started_at: None,
+        };
+
+        db.save_job("job-1", &job).await.unwrap();
+        db.set_job_checkpoint("job-1", &JobCheckpoint::writing(2, "/export/a.jpg")).await.unwrap();
+        db.add_job_output("job-1", "/export/b.jpg").await.unwrap();
+        assert!(db.get_interrupted_jobs().await.unwrap().is_empty());
+
+        // 下次启动时运行中的任务标记为被中断，之后新登记的任务不受影响
+        assert_eq!(db.mark_interrupted_jobs().await.unwrap(), 1);
+        db.save_job("job-2", &QueuedJob::PackMaintenance).await.unwrap();
+        let jobs = db.get_interrupted_jobs().await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id, "job-1");
+        assert_eq!(jobs[0].checkpoint, JobCheckpoint::writing(2, "/export/a.jpg"));
+        assert_eq!(db.get_job_outputs("job-1").await.unwrap(), ["/export/b.jpg"]);
+
+        // 恢复时重新登记会清空检查点，输出记录保留到任务移除
+        db.save_job("job-1", &job).await.unwrap();
+        assert!(db.get_interrupted_jobs().await.unwrap().is_empty());
+        db.delete_job("job-1").await.unwrap();
+        assert!(db.get_job_outputs("job-1").await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_directory_tree() {
         let db = create_test_db().await;
@@ -0,0 +1,121 @@
+//! 延迟删除模块
+//!
+//! Windows 上文件被杀毒软件、预览程序或同步工具打开时无法删除，短时间重试后仍可能失败：
+//! - 删除文件或目录时，存储文件被占用的，数据库记录照常删除，存储文件加入延迟删除队列，
+//!   删除操作本身视为成功
+//! - 队列保存在数据库中，重启后继续处理；后台任务每 30 秒处理一次到期的条目，
+//!   失败后的等待时间从 30 秒开始翻倍，最长 1 小时，累计失败 20 次后放弃
+//! - 删除成功或放弃时发送 `deferred-delete` 事件；放弃的存储文件保留在磁盘上，可手动清理
+//! - 存储路径重新被文件记录使用（如从备份恢复）时不再删除，直接移出队列
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::events::{self, DeferredDeleteFinished};
+use crate::file_manager::retry::is_transient_io_error;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// 后台任务处理队列的间隔
+pub const DEFERRED_DELETE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 累计失败达到该次数后放弃
+pub const MAX_DELETE_ATTEMPTS: u32 = 20;
+
+/// 第一次失败后的等待时间（秒）
+const INITIAL_RETRY_SECS: i64 = 30;
+
+/// 单次等待的上限（秒）
+const MAX_RETRY_SECS: i64 = 60 * 60;
+
+/// 等待删除的存储文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DeferredDelete {
+    /// 存储路径，与文件记录中的 `file_path` 或 `archive_path` 相同
+    pub file_path: String,
+    /// 原文件 ID，文件记录已删除
+    pub file_id: String,
+    /// 已失败的次数
+    pub attempts: u32,
+    pub last_error: String,
+    pub queued_at: DateTime<Local>,
+    pub next_attempt_at: DateTime<Local>,
+}
+
+/// 处理队列的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DeferredDeleteReport {
+    /// 已删除的存储文件，包括已不存在的
+    pub deleted: Vec<DeferredDelete>,
+    /// 存储路径重新被使用、不再删除的条目
+    pub kept: Vec<DeferredDelete>,
+    /// 失败次数达到上限而放弃的条目，`last_error` 为最后一次的错误
+    pub abandoned: Vec<DeferredDelete>,
+    /// 仍在队列中的条目数
+    pub pending: usize,
+}
+
+impl DeferredDeleteReport {
+    /// 是否有需要通知前端的结果
+    pub fn has_outcome(&self) -> bool {
+        !self.deleted.is_empty() || !self.abandoned.is_empty()
+    }
+}
+
+/// 是否为文件被其他进程占用导致的删除失败
+///
+/// Windows 上删除被打开的文件可能返回共享冲突，也可能返回拒绝访问
+pub fn is_file_locked(error: &std::io::Error) -> bool {
+    is_transient_io_error(error) || (cfg!(windows) && error.kind() == std::io::ErrorKind::PermissionDenied)
+}
+
+/// 第 `attempts` 次失败后的等待时间
+pub fn retry_delay(attempts: u32) -> chrono::Duration {
+    let secs = INITIAL_RETRY_SECS.saturating_mul(1 << attempts.saturating_sub(1).min(16));
+    chrono::Duration::seconds(secs.min(MAX_RETRY_SECS))
+}
+
+/// 启动后台延迟删除任务，有文件删除成功或放弃时通知前端
+pub fn spawn_deferred_delete_loop(app: AppHandle, state: FileManagerState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(DEFERRED_DELETE_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let Ok(service) = state.lock().await else {
+                continue;
+            };
+            // 只读模式下由写入的进程删除
+            if service.is_read_only() {
+                continue;
+            }
+            match service.process_deferred_deletes(false).await {
+                Ok(report) if report.has_outcome() => {
+                    tracing::info!("延迟删除: {} 个已删除, {} 个放弃, {} 个等待中",
+                        report.deleted.len(), report.abandoned.len(), report.pending);
+                    events::emit(&app, &DeferredDeleteFinished(report));
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("延迟删除处理失败: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(1), chrono::Duration::seconds(30));
+        assert_eq!(retry_delay(3), chrono::Duration::seconds(120));
+        assert_eq!(retry_delay(MAX_DELETE_ATTEMPTS), chrono::Duration::seconds(MAX_RETRY_SECS));
+
+        assert!(is_file_locked(&std::io::Error::from(std::io::ErrorKind::ResourceBusy)));
+        assert!(!is_file_locked(&std::io::Error::from(std::io::ErrorKind::NotFound)));
+    }
+}
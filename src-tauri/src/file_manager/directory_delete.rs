@@ -0,0 +1,245 @@
+//! 目录删除任务模块
+//!
+//! 删除包含大量文件的目录可能需要几分钟，改为后台任务执行：
+//! - 命令先检查目录能否删除并统计要删除的项数，然后立即返回任务句柄
+//! - 后台任务分批删除子树中的文件，每批单独获取服务锁，删除期间其他命令仍可执行
+//! - 每批按目录 ID 经闭包表选取文件，只删除任务开始前已在子树中的文件；目录在删除期间被移动或改名，
+//!   或有新上传、移入的文件时任务以失败结束，目录和这些文件保留
+//! - 每批完成后发送进度事件，报告已删除的项数和总数
+//! - 可按任务 ID 取消；已删除的文件不恢复，目录和剩余的文件保留
+//! - 文件全部删除后再删除目录自身和子目录记录
+//! - 任务登记在任务队列中，应用中途退出时下次启动继续删除，见 [`job_queue`](super::job_queue)
+//! - 应用正常退出时任务在当前批次完成后暂停，保留在任务队列中，下次启动继续删除
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::dry_run::PlannedChange;
+use crate::file_manager::error::FileManagerError;
+use crate::file_manager::events;
+use crate::file_manager::job_queue::JobCheckpoint;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// 每批删除的文件数
+pub const DELETE_BATCH_SIZE: usize = 200;
+
+/// 运行中的删除任务及其控制标记
+static RUNNING_JOBS: Mutex<BTreeMap<String, Arc<JobControl>>> = Mutex::new(BTreeMap::new());
+
+/// 运行中任务的控制标记，任务在当前批次完成后检查
+#[derive(Default)]
+struct JobControl {
+    /// 用户取消：任务结束并移出任务队列
+    cancelled: AtomicBool,
+    /// 应用退出：任务暂停并保留在任务队列中
+    paused: AtomicBool,
+}
+
+/// 删除任务句柄，命令立即返回
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DirectoryDeleteJob {
+    /// 试运行时不创建任务，为空
+    pub job_id: String,
+    pub directory_id: String,
+    pub path: String,
+    /// 要删除的项数：子树中的文件、子目录和目录自身
+    pub total: i64,
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 试运行时列出要删除的文件和目录：先文件，再由深到浅的目录，最后是目录自身
+    #[serde(default)]
+    pub changes: Vec<PlannedChange>,
+}
+
+/// 删除任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum DirectoryDeleteStatus {
+    #[default]
+    Running,
+    Finished,
+    Cancelled,
+    Failed,
+}
+
+/// 删除进度事件负载
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DirectoryDeleteProgress {
+    pub job_id: String,
+    pub directory_id: String,
+    pub removed: i64,
+    pub total: i64,
+    pub status: DirectoryDeleteStatus,
+    /// 任务失败时的错误信息
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 登记任务，返回控制标记
+fn register(job_id: &str) -> Arc<JobControl> {
+    let control = Arc::new(JobControl::default());
+    RUNNING_JOBS.lock().unwrap().insert(job_id.to_string(), control.clone());
+    control
+}
+
+fn unregister(job_id: &str) {
+    RUNNING_JOBS.lock().unwrap().remove(job_id);
+}
+
+/// 请求取消删除任务，任务在当前批次完成后停止；任务不存在或已结束时返回 `false`
+pub fn cancel(job_id: &str) -> bool {
+    match RUNNING_JOBS.lock().unwrap().get(job_id) {
+        Some(control) => {
+            control.cancelled.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 运行中的删除任务数
+pub fn running_jobs() -> usize {
+    RUNNING_JOBS.lock().unwrap().len()
+}
+
+/// 应用退出时暂停所有删除任务，等待当前批次完成
+///
+/// 暂停的任务不移出任务队列，下次启动时从剩余的文件继续删除
+pub async fn pause_all() {
+    for control in RUNNING_JOBS.lock().unwrap().values() {
+        control.paused.store(true, Ordering::SeqCst);
+    }
+    while running_jobs() > 0 {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// 在后台执行删除任务，只删除 `started_at` 之前最后修改的文件
+///
+/// 每批单独获取服务锁；进入只读模式或某批失败时任务以失败结束，目录和剩余的文件保留
+pub fn spawn(app: AppHandle, state: FileManagerState, job: DirectoryDeleteJob, started_at: DateTime<Local>) {
+    let control = register(&job.job_id);
+
+    tauri::async_runtime::spawn(async move {
+        let mut progress = DirectoryDeleteProgress {
+            job_id: job.job_id.clone(),
+            directory_id: job.directory_id.clone(),
+            total: job.total,
+            ..Default::default()
+        };
+
+        loop {
+            if control.paused.load(Ordering::SeqCst) {
+                pause(&job, &progress);
+                return;
+            }
+            if control.cancelled.load(Ordering::SeqCst) {
+                tracing::info!("目录删除已取消: {}, 已删除 {}/{}", job.path, progress.removed, progress.total);
+                progress.status = DirectoryDeleteStatus::Cancelled;
+                break;
+            }
+
+            let batch = {
+                let service = match state.lock().await {
+                    Ok(service) => service,
+                    // 检查暂停标记后应用才开始退出
+                    Err(FileManagerError::ShuttingDown) => {
+                        pause(&job, &progress);
+                        return;
+                    }
+                    Err(e) => {
+                        progress.status = DirectoryDeleteStatus::Failed;
+                        progress.error = Some(e.to_string());
+                        break;
+                    }
+                };
+                let batch = match service.ensure_writable() {
+                    Ok(()) => {
+                        service.delete_directory_batch(&job.directory_id, &job.path, &started_at, DELETE_BATCH_SIZE).await
+                    }
+                    Err(e) => Err(e),
+                };
+                if let Ok(removed) = &batch {
+                    let processed = progress.removed as usize + removed;
+                    service.checkpoint_job(&job.job_id, &JobCheckpoint::processed(processed)).await;
+                }
+                batch
+            };
+
+            match batch {
+                Ok(0) => {
+                    let finished = match state.lock().await {
+                        Ok(service) => service.finish_directory_delete_job(&job.directory_id, &job.path).await,
+                        Err(FileManagerError::ShuttingDown) => {
+                            pause(&job, &progress);
+                            return;
+                        }
+                        Err(e) => Err(e),
+                    };
+                    match finished {
+                        Ok(()) => {
+                            progress.removed = progress.total;
+                            progress.status = DirectoryDeleteStatus::Finished;
+                        }
+                        Err(e) => {
+                            progress.status = DirectoryDeleteStatus::Failed;
+                            progress.error = Some(e.to_string());
+                        }
+                    }
+                    break;
+                }
+                Ok(removed) => {
+                    progress.removed += removed as i64;
+                    events::emit(&app, &progress);
+                }
+                Err(e) => {
+                    progress.status = DirectoryDeleteStatus::Failed;
+                    progress.error = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+
+        if let Some(error) = &progress.error {
+            tracing::error!("目录删除失败: {}, {}", job.path, error);
+        }
+        // 应用正在退出时也要移出任务队列，否则已结束或已取消的任务下次启动会再次执行；
+        // 退出流程会等待任务注销后才关闭服务
+        if let Ok(service) = state.lock_for_cleanup().await {
+            service.finish_job(&job.job_id).await;
+        }
+        unregister(&job.job_id);
+        events::emit(&app, &progress);
+    });
+}
+
+/// 暂停任务，保留任务队列中的记录
+fn pause(job: &DirectoryDeleteJob, progress: &DirectoryDeleteProgress) {
+    tracing::info!("应用退出，目录删除暂停: {}, 已删除 {}/{}", job.path, progress.removed, progress.total);
+    unregister(&job.job_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_running_job() {
+        let control = register("job-1");
+        assert!(!cancel("job-2"));
+        assert!(cancel("job-1"));
+        assert!(control.cancelled.load(Ordering::SeqCst));
+        assert!(!control.paused.load(Ordering::SeqCst));
+
+        unregister("job-1");
+        assert!(!cancel("job-1"));
+    }
+}
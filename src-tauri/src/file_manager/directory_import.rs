@@ -0,0 +1,260 @@
+//! 文件夹导入模块
+//!
+//! 把本地文件夹中的文件一次性导入资料库的指定目录（默认收件箱）：
+//! - 可选包含子文件夹，所有文件平铺导入到目标目录；隐藏文件、临时文件和附属元数据文件不导入
+//! - 导入前按内容哈希（SHA-256）比对，资料库中已有的文件和文件夹内重复的文件只导入一次
+//! - 试运行只扫描和计算哈希，不修改资料库，返回将要导入的文件、已存在的文件、
+//!   不符合导入规则的文件和预计占用的空间，确认后再以相同参数正式导入
+//! - 源文件保留不动；正式导入时单个文件失败不影响其他文件
+//! - 扫描、比对和逐个导入时分别获取服务锁，计算哈希和按后台 I/O 限额等待时不持有服务锁，
+//!   导入期间其他命令仍可执行；导入登记在任务队列中，应用中途退出时下次启动重新执行
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::integrity::hash_file;
+use crate::file_manager::io_throttle;
+use crate::file_manager::job_queue::{JobCheckpoint, QueuedJob};
+use crate::file_manager::watched_folders::is_ignored_file;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::path::{Path, PathBuf};
+
+/// 导入文件夹的请求
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ImportDirectoryRequest {
+    /// 源文件夹的绝对路径
+    pub path: String,
+    /// 目标目录，`None` 表示收件箱
+    #[serde(default)]
+    pub directory_id: Option<String>,
+    /// 是否包含子文件夹
+    #[serde(default)]
+    pub recursive: bool,
+    /// 只返回导入计划，不修改资料库
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 将要导入的文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PlannedImport {
+    pub path: String,
+    pub size: u64,
+}
+
+/// 内容已存在、不会导入的文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExistingImport {
+    pub path: String,
+    pub size: u64,
+    /// 资料库中内容相同的文件 ID
+    #[serde(default)]
+    pub file_id: Option<String>,
+    /// 文件夹中内容相同、会被导入的文件路径
+    #[serde(default)]
+    pub duplicate_of: Option<String>,
+}
+
+/// 不符合导入规则或无法读取的文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RejectedImport {
+    pub path: String,
+    pub reason: String,
+}
+
+/// 导入文件夹的结果；试运行时 `imported` 和 `failed` 为空
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DirectoryImportReport {
+    pub dry_run: bool,
+    pub to_import: Vec<PlannedImport>,
+    pub existing: Vec<ExistingImport>,
+    pub rejected: Vec<RejectedImport>,
+    /// 将要导入的文件总大小，即预计新占用的空间（字节）
+    pub bytes_to_import: u64,
+    /// 已存在而不导入的文件总大小（字节）
+    pub bytes_existing: u64,
+    /// 导入的文件 ID
+    pub imported: Vec<String>,
+    /// 导入失败的文件
+    pub failed: Vec<RejectedImport>,
+}
+
+/// 扫描源文件夹的结果
+#[derive(Debug, Clone, Default)]
+pub struct ScannedDirectory {
+    /// 规范化后的源文件夹
+    pub root: PathBuf,
+    /// 符合导入规则的文件及其大小，按路径排序
+    pub files: Vec<(PathBuf, u64)>,
+    /// 不符合导入规则或无法读取的文件
+    pub rejected: Vec<RejectedImport>,
+}
+
+/// 列出文件夹中要导入的文件，按路径排序
+///
+/// 需要遍历文件夹，应在阻塞线程中调用
+pub fn scan_import_directory(root: &Path, recursive: bool) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(folder) = pending.pop() {
+        for entry in std::fs::read_dir(&folder)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                if recursive && !hidden {
+                    pending.push(path);
+                }
+            } else if file_type.is_file() && !is_ignored_file(&path) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// 把本地文件夹中的文件导入指定目录，未指定时导入收件箱
+///
+/// 先按内容哈希与资料库和文件夹中的其他文件比对，内容已存在的文件不导入；
+/// 试运行时只返回导入计划，不创建收件箱、不修改资料库，只读模式下也可使用
+pub async fn import_directory(state: &FileManagerState, request: ImportDirectoryRequest) -> Result<DirectoryImportReport> {
+    let job_id = state.lock().await?.generate_id();
+    run(state, &job_id, request).await
+}
+
+/// 重新执行被中断的文件夹导入，中断前已导入的文件按内容哈希识别为已存在
+pub async fn resume_directory_import(
+    state: &FileManagerState,
+    job_id: &str,
+    request: ImportDirectoryRequest,
+) -> Result<DirectoryImportReport> {
+    run(state, job_id, request).await
+}
+
+/// 执行文件夹导入，实际导入期间登记在任务队列中
+///
+/// 每个文件单独获取服务锁；资料库变为只读或应用开始退出时停止，任务保留在队列中
+async fn run(state: &FileManagerState, job_id: &str, request: ImportDirectoryRequest) -> Result<DirectoryImportReport> {
+    let scanned = if request.dry_run {
+        state.lock().await?.scan_directory_import(&request).await?
+    } else {
+        state.lock_writable().await?.scan_directory_import(&request).await?
+    };
+
+    let mut report = DirectoryImportReport { dry_run: request.dry_run, rejected: scanned.rejected, ..Default::default() };
+    let mut hashed = Vec::with_capacity(scanned.files.len());
+    for (path, size) in scanned.files {
+        let hash_path = path.clone();
+        match tokio::task::spawn_blocking(move || hash_file(&hash_path)).await {
+            Ok(Ok(content_hash)) => hashed.push((path, size, content_hash)),
+            Ok(Err(e)) => {
+                report.rejected.push(RejectedImport { path: path.to_string_lossy().into_owned(), reason: e.to_string() });
+                continue;
+            }
+            Err(e) => return Err(FileManagerError::general_error(format!("Hash task failed: {}", e))),
+        }
+        io_throttle::throttle(size).await;
+    }
+
+    let planned = state.lock().await?.plan_directory_import(hashed, &mut report).await?;
+    if request.dry_run {
+        tracing::info!("文件夹导入试运行: {:?}, {} 个待导入 ({} 字节), {} 个已存在, {} 个不符合规则",
+            scanned.root, report.to_import.len(), report.bytes_to_import, report.existing.len(), report.rejected.len());
+        return Ok(report);
+    }
+
+    let directory_id = {
+        let service = state.lock_writable().await?;
+        let directory_id = match &request.directory_id {
+            Some(directory_id) => directory_id.clone(),
+            None => service.ensure_inbox_directory().await?,
+        };
+        service.enqueue_job(job_id, &QueuedJob::DirectoryImport { request: request.clone() }).await;
+        directory_id
+    };
+
+    for (index, (path, size, content_hash)) in planned.into_iter().enumerate() {
+        let imported = {
+            let _permit = io_throttle::write_permit().await;
+            let service = state.lock_writable().await?;
+            let imported = service.import_directory_file(&path, &directory_id, &content_hash).await;
+            service.checkpoint_job(job_id, &JobCheckpoint::processed(index + 1)).await;
+            imported
+        };
+        match imported {
+            Ok(file_id) => report.imported.push(file_id),
+            Err(e) => {
+                tracing::warn!("文件夹导入失败: {:?}, {}", path, e);
+                report.failed.push(RejectedImport { path: path.to_string_lossy().into_owned(), reason: e.to_string() });
+            }
+        }
+        io_throttle::throttle(size).await;
+    }
+    state.lock().await?.finish_job(job_id).await;
+
+    tracing::info!("文件夹导入完成: {:?}, {} 个导入, {} 个已存在, {} 个失败",
+        scanned.root, report.imported.len(), report.existing.len(), report.failed.len());
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_manager::testing::{initialize_for_tests, ready_state};
+    use std::time::{Duration, Instant};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scan_import_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        for name in ["a.jpg", "a.jpg.xmp", ".hidden.jpg", "b.part", "sub/c.jpg", ".git/config"] {
+            std::fs::write(root.join(name), b"data").unwrap();
+        }
+
+        assert_eq!(scan_import_directory(root, false).unwrap(), vec![root.join("a.jpg")]);
+        assert_eq!(scan_import_directory(root, true).unwrap(), vec![root.join("a.jpg"), root.join("sub").join("c.jpg")]);
+        assert!(scan_import_directory(&root.join("missing"), false).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_throttled_import_releases_service_lock() {
+        let (state, _temp_dir) = ready_state(initialize_for_tests().await);
+        let source_dir = TempDir::new().unwrap();
+        for seed in 0..3u8 {
+            std::fs::write(source_dir.path().join(format!("{}.txt", seed)), vec![b'a' + seed; 128 * 1024]).unwrap();
+        }
+        let request = ImportDirectoryRequest {
+            path: source_dir.path().to_string_lossy().into_owned(),
+            directory_id: None,
+            recursive: false,
+            dry_run: false,
+        };
+
+        // 按 1 MB/s 计算哈希和导入共需约 750 毫秒，期间命令获取服务锁不必等待导入结束
+        let started = Instant::now();
+        let import = io_throttle::scoped(1, 1, import_directory(&state, request));
+        let command = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let waiting = Instant::now();
+            let _service = state.lock().await.unwrap();
+            waiting.elapsed()
+        };
+        let (report, waited) = tokio::join!(import, command);
+
+        assert_eq!(report.unwrap().imported.len(), 3);
+        assert!(started.elapsed() >= Duration::from_millis(600), "{:?}", started.elapsed());
+        assert!(waited < Duration::from_millis(200), "{:?}", waited);
+    }
+}
@@ -0,0 +1,129 @@
+//! 目录模板模块
+//!
+//! 模板定义一组嵌套的文件夹结构，如“项目”模板包含 `refs`、`sketches`、`finals`、`exports`：
+//! - 模板保存在数据库中，可创建、修改和删除
+//! - 从模板创建目录时，在父目录下创建指定名称的目录，再按模板逐级创建子目录
+//! - 文件夹名称按目录名的规则校验，同一层级不能重名（忽略大小写）
+//! - 创建过程中任一子目录失败时删除已创建的整个目录，不留下不完整的结构
+
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::paths::{normalize_name, sanitize_file_name};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::HashSet;
+
+/// 单个模板的文件夹数上限
+pub const MAX_TEMPLATE_FOLDERS: usize = 200;
+
+/// 模板的嵌套层数上限
+pub const MAX_TEMPLATE_DEPTH: usize = 8;
+
+/// 模板中的文件夹
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TemplateFolder {
+    pub name: String,
+    #[serde(default)]
+    pub children: Vec<TemplateFolder>,
+}
+
+/// 目录模板
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DirectoryTemplate {
+    pub id: String,
+    pub name: String,
+    pub folders: Vec<TemplateFolder>,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+/// 从模板创建目录的结果
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TemplateApplication {
+    /// 新建的顶层目录 ID
+    pub directory_id: String,
+    pub path: String,
+    /// 按模板创建的子目录数
+    pub folders_created: usize,
+}
+
+/// 校验模板名称，返回去除首尾空白后的名称
+pub fn validate_template_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(FileManagerError::general_error("Template name cannot be empty"));
+    }
+    Ok(name.to_string())
+}
+
+/// 校验并规范化文件夹结构：名称去除首尾空白并转为 NFC，检查名称、同级重名、层数和总数
+pub fn validate_folders(folders: Vec<TemplateFolder>) -> Result<Vec<TemplateFolder>> {
+    let mut count = 0;
+    let folders = validate_level(folders, 1, &mut count)?;
+    if count > MAX_TEMPLATE_FOLDERS {
+        return Err(FileManagerError::general_error(format!(
+            "Template cannot have more than {} folders", MAX_TEMPLATE_FOLDERS
+        )));
+    }
+    Ok(folders)
+}
+
+fn validate_level(folders: Vec<TemplateFolder>, depth: usize, count: &mut usize) -> Result<Vec<TemplateFolder>> {
+    if !folders.is_empty() && depth > MAX_TEMPLATE_DEPTH {
+        return Err(FileManagerError::general_error(format!(
+            "Template cannot be nested more than {} levels", MAX_TEMPLATE_DEPTH
+        )));
+    }
+
+    let mut seen = HashSet::new();
+    let mut validated = Vec::with_capacity(folders.len());
+    for folder in folders {
+        let name = normalize_name(folder.name.trim());
+        if name.is_empty() || sanitize_file_name(&name) != name {
+            return Err(FileManagerError::general_error(format!("Invalid folder name: {}", folder.name)));
+        }
+        if !seen.insert(name.to_lowercase()) {
+            return Err(FileManagerError::general_error(format!("Duplicate folder name: {}", name)));
+        }
+
+        *count += 1;
+        let children = validate_level(folder.children, depth + 1, count)?;
+        validated.push(TemplateFolder { name, children });
+    }
+    Ok(validated)
+}
+
+/// 文件夹总数
+pub fn count_folders(folders: &[TemplateFolder]) -> usize {
+    folders.iter().map(|folder| 1 + count_folders(&folder.children)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder(name: &str, children: Vec<TemplateFolder>) -> TemplateFolder {
+        TemplateFolder { name: name.to_string(), children }
+    }
+
+    #[test]
+    fn test_validate_folders() {
+        let folders = validate_folders(vec![
+            folder(" refs ", vec![folder("Cafe\u{301}", vec![])]),
+            folder("finals", vec![]),
+        ]).unwrap();
+        assert_eq!(folders[0].name, "refs");
+        assert_eq!(folders[0].children[0].name, "Caf\u{e9}");
+        assert_eq!(count_folders(&folders), 3);
+
+        assert!(validate_folders(vec![folder("a/b", vec![])]).is_err());
+        assert!(validate_folders(vec![folder("CON", vec![])]).is_err());
+        assert!(validate_folders(vec![folder("refs", vec![]), folder("REFS", vec![])]).is_err());
+
+        let deep = (0..=MAX_TEMPLATE_DEPTH).fold(Vec::new(), |children, level| vec![folder(&level.to_string(), children)]);
+        assert!(validate_folders(deep).is_err());
+    }
+}
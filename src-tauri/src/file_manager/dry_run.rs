@@ -0,0 +1,54 @@
+//! 试运行模块
+//!
+//! 删除目录、批量操作和重复文件清理等破坏性命令接受 `dry_run` 参数，供确认对话框展示实际后果：
+//! - 试运行与实际执行使用相同的检查，实际执行会失败的请求试运行同样返回错误
+//! - 返回的报告与实际执行相同，`dry_run` 为 `true`，`changes` 逐项列出将要进行的修改，不创建撤销条目或后台任务
+//! - 批量操作在事务中实际执行后回滚，移动时自动添加的序号、标签的实际增减都与执行时一致
+//! - 文件夹导入的试运行结果即导入计划，见 [`directory_import`](super::directory_import)
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// 试运行报告中的一项修改，路径为资料库中的显示路径
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PlannedChange {
+    /// 删除文件记录及其存储文件
+    DeleteFile { file_id: String, path: String },
+    /// 删除目录
+    DeleteDirectory { directory_id: String, path: String },
+    /// 移动文件，目标目录中有同名文件时 `new_path` 中的文件名已添加序号
+    MoveFile { file_id: String, path: String, new_path: String },
+    /// 重命名文件
+    RenameFile { file_id: String, path: String, new_path: String },
+    /// 修改标签，只列出实际增减的标签
+    TagFile { file_id: String, path: String, added: Vec<String>, removed: Vec<String> },
+    /// 存储文件替换为指向 `target_file_id` 存储文件的硬链接
+    LinkFile { file_id: String, path: String, target_file_id: String },
+}
+
+/// 目录中文件的显示路径
+pub fn display_path(directory_path: &str, name: &str) -> String {
+    format!("{}/{}", directory_path.trim_end_matches('/'), name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_serialization() {
+        assert_eq!(display_path("/", "a.jpg"), "/a.jpg");
+        assert_eq!(display_path("/photos/", "a.jpg"), "/photos/a.jpg");
+
+        let change = PlannedChange::MoveFile {
+            file_id: "f1".to_string(),
+            path: "/a.jpg".to_string(),
+            new_path: "/photos/a (1).jpg".to_string(),
+        };
+        let json = serde_json::to_string(&change).unwrap();
+        assert!(json.starts_with(r#"{"type":"move_file""#));
+        assert_eq!(serde_json::from_str::<PlannedChange>(&json).unwrap(), change);
+    }
+}
@@ -0,0 +1,153 @@
+//! 重复文件清理模块
+//!
+//! 按内容哈希找出重复文件，并执行用户确认过的清理计划：
+//! - 每组保留一个文件，其余副本删除或替换为指向保留文件的硬链接
+//! - 删除副本时，副本上的标签合并到保留的文件
+//! - 执行前重新计算所有文件的哈希，内容不一致时整个计划不执行
+//! - 每次执行记录一条撤销条目，可恢复删除的记录和标签
+
+use crate::file_manager::database::FileInfo;
+use crate::file_manager::dry_run::PlannedChange;
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::service::FileListItem;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::HashSet;
+
+/// 撤销条目中记录的操作类型
+pub const RESOLVE_DUPLICATES_OPERATION: &str = "resolve_duplicates";
+
+/// 重复副本的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateAction {
+    /// 删除副本的记录和存储文件，标签合并到保留的文件
+    #[default]
+    Delete,
+    /// 保留副本的记录，存储文件替换为指向保留文件的硬链接
+    Hardlink,
+}
+
+/// 内容相同的一组文件，按上传时间从早到晚排列
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DuplicateGroup {
+    pub content_hash: String,
+    pub files: Vec<FileListItem>,
+}
+
+/// 一组重复文件的处理方式
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DuplicateResolution {
+    pub keep_file_id: String,
+    pub duplicate_file_ids: Vec<String>,
+}
+
+/// 用户确认的清理计划
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DuplicatePlan {
+    #[serde(default)]
+    pub action: DuplicateAction,
+    pub groups: Vec<DuplicateResolution>,
+}
+
+impl DuplicatePlan {
+    /// 检查计划是否有效：每组至少一个副本，且同一文件在计划中只出现一次
+    pub fn validate(&self) -> Result<()> {
+        if self.groups.is_empty() {
+            return Err(FileManagerError::general_error("Duplicate plan is empty"));
+        }
+
+        let mut seen = HashSet::new();
+        for group in &self.groups {
+            if group.duplicate_file_ids.is_empty() {
+                return Err(FileManagerError::general_error(format!(
+                    "No duplicates listed for file: {}", group.keep_file_id
+                )));
+            }
+
+            for file_id in std::iter::once(&group.keep_file_id).chain(&group.duplicate_file_ids) {
+                if !seen.insert(file_id.as_str()) {
+                    return Err(FileManagerError::general_error(format!(
+                        "File appears more than once in duplicate plan: {}", file_id
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 清理计划的执行结果
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ResolveDuplicatesReport {
+    /// 撤销条目 ID，试运行时为空
+    pub undo_id: String,
+    /// 删除的副本数
+    pub removed: usize,
+    /// 替换为硬链接的副本数
+    pub linked: usize,
+    /// 释放的字节数
+    pub bytes_reclaimed: i64,
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 试运行时列出的各项修改：删除或链接的副本，以及并入保留文件的标签
+    #[serde(default)]
+    pub changes: Vec<PlannedChange>,
+}
+
+/// 被处理的副本及其标签
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DuplicateCopy {
+    pub file: FileInfo,
+    pub tags: Vec<String>,
+}
+
+/// 撤销条目中记录的一组处理结果
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DuplicateUndoGroup {
+    pub keep_file_id: String,
+    /// 执行前的内容哈希，撤销时用于确认保留的文件没有被替换
+    pub content_hash: String,
+    /// 合并前保留文件的标签
+    pub kept_tags: Vec<String>,
+    pub copies: Vec<DuplicateCopy>,
+}
+
+/// 清理计划的撤销信息
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DuplicateUndo {
+    pub action: DuplicateAction,
+    pub groups: Vec<DuplicateUndoGroup>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolution(keep: &str, duplicates: &[&str]) -> DuplicateResolution {
+        DuplicateResolution {
+            keep_file_id: keep.to_string(),
+            duplicate_file_ids: duplicates.iter().map(|id| id.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_plan_validation() {
+        let plan = |groups| DuplicatePlan { action: DuplicateAction::Delete, groups };
+
+        assert!(plan(vec![resolution("a", &["b"]), resolution("c", &["d", "e"])]).validate().is_ok());
+        assert!(plan(vec![]).validate().is_err());
+        assert!(plan(vec![resolution("a", &[])]).validate().is_err());
+        assert!(plan(vec![resolution("a", &["a"])]).validate().is_err());
+        assert!(plan(vec![resolution("a", &["b"]), resolution("c", &["b"])]).validate().is_err());
+    }
+}
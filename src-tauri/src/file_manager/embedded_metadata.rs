@@ -0,0 +1,236 @@
+//! 图像内嵌元数据模块
+//!
+//! 把标题、描述和关键词以 XMP 数据包写入图像文件本身，导出或复制后元数据随文件携带：
+//! - JPEG：写入 APP1 段（标识为 `http://ns.adobe.com/xap/1.0/`），放在开头的 APP0 / EXIF 段之后
+//! - PNG：写入关键字为 `XML:com.adobe.xmp` 的未压缩 iTXt 块，放在 IHDR 之后
+//! - 已有的 XMP 被替换，图像数据和 EXIF 等其他内容保持不变
+//! - 其他格式不支持内嵌，元数据只保存在数据库中
+
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::service::FileListItem;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// JPEG XMP 段的标识
+const JPEG_XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// PNG XMP 块的关键字
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// 写入图像元数据的字段，`None` 表示保持不变，空字符串表示清除
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ImageMetadataFields {
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 描述，对应文件的备注
+    #[serde(default)]
+    pub description: Option<String>,
+    /// 关键词，替换文件的全部标签
+    #[serde(default)]
+    pub keywords: Option<Vec<String>>,
+}
+
+impl ImageMetadataFields {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.description.is_none() && self.keywords.is_none()
+    }
+}
+
+/// 写入图像元数据的结果
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ImageMetadataResult {
+    pub file: FileListItem,
+    /// 元数据是否已写入文件本身；格式不支持或为链接文件时只更新数据库
+    pub embedded: bool,
+}
+
+/// 把 XMP 数据包写入图像数据，格式不支持时返回 `None`
+pub fn embed_xmp(data: &[u8], xmp: &str) -> Result<Option<Vec<u8>>> {
+    if data.starts_with(&[0xFF, 0xD8]) {
+        embed_jpeg(data, xmp).map(Some)
+    } else if data.starts_with(PNG_SIGNATURE) {
+        embed_png(data, xmp).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+fn malformed(format: &str) -> FileManagerError {
+    FileManagerError::general_error(format!("Malformed {} data", format))
+}
+
+/// 替换 JPEG 中的 XMP 段
+///
+/// 只遍历扫描数据之前的标记段，扫描数据原样保留
+fn embed_jpeg(data: &[u8], xmp: &str) -> Result<Vec<u8>> {
+    let segment_len = 2 + JPEG_XMP_HEADER.len() + xmp.len();
+    if segment_len > u16::MAX as usize {
+        return Err(FileManagerError::general_error("XMP packet is too large to embed in a JPEG"));
+    }
+
+    let mut kept: Vec<&[u8]> = Vec::new();
+    let mut insert_at = 0;
+    let mut leading = true;
+    let mut pos = 2;
+    loop {
+        // 标记前可以有填充的 0xFF
+        while data.get(pos) == Some(&0xFF) && data.get(pos + 1) == Some(&0xFF) {
+            pos += 1;
+        }
+        let marker = match data.get(pos..pos + 2) {
+            Some([0xFF, marker]) => *marker,
+            _ => return Err(malformed("JPEG")),
+        };
+        // 扫描开始（SOS）或图像结束之后的数据原样复制
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        let len = data.get(pos + 2..pos + 4)
+            .map(|len| u16::from_be_bytes([len[0], len[1]]) as usize)
+            .filter(|len| *len >= 2 && pos + 2 + len <= data.len())
+            .ok_or_else(|| malformed("JPEG"))?;
+        let segment = &data[pos..pos + 2 + len];
+        let is_xmp = marker == 0xE1 && segment[4..].starts_with(JPEG_XMP_HEADER);
+
+        if !is_xmp {
+            kept.push(segment);
+            // 新的 XMP 段放在开头连续的 APP0（JFIF）和 APP1（EXIF）段之后
+            if leading && (marker == 0xE0 || marker == 0xE1) {
+                insert_at = kept.len();
+            } else {
+                leading = false;
+            }
+        }
+        pos += 2 + len;
+    }
+
+    let mut xmp_segment = Vec::with_capacity(segment_len + 2);
+    xmp_segment.extend_from_slice(&[0xFF, 0xE1]);
+    xmp_segment.extend_from_slice(&(segment_len as u16).to_be_bytes());
+    xmp_segment.extend_from_slice(JPEG_XMP_HEADER);
+    xmp_segment.extend_from_slice(xmp.as_bytes());
+
+    let mut output = Vec::with_capacity(data.len() + xmp_segment.len());
+    output.extend_from_slice(&data[..2]);
+    for segment in &kept[..insert_at] {
+        output.extend_from_slice(segment);
+    }
+    output.extend_from_slice(&xmp_segment);
+    for segment in &kept[insert_at..] {
+        output.extend_from_slice(segment);
+    }
+    output.extend_from_slice(&data[pos..]);
+    Ok(output)
+}
+
+/// 替换 PNG 中的 XMP 块
+fn embed_png(data: &[u8], xmp: &str) -> Result<Vec<u8>> {
+    let mut chunk_data = Vec::with_capacity(PNG_XMP_KEYWORD.len() + 5 + xmp.len());
+    chunk_data.extend_from_slice(PNG_XMP_KEYWORD);
+    // 关键字结束符、不压缩、压缩方法、空的语言标签和翻译关键字
+    chunk_data.extend_from_slice(&[0, 0, 0, 0, 0]);
+    chunk_data.extend_from_slice(xmp.as_bytes());
+
+    let mut output = Vec::with_capacity(data.len() + chunk_data.len() + 12);
+    output.extend_from_slice(PNG_SIGNATURE);
+
+    let mut pos = PNG_SIGNATURE.len();
+    while pos < data.len() {
+        let len = data.get(pos..pos + 4)
+            .map(|len| u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize)
+            .filter(|len| pos + 12 + len <= data.len())
+            .ok_or_else(|| malformed("PNG"))?;
+        let chunk_type = &data[pos + 4..pos + 8];
+        let body = &data[pos + 8..pos + 8 + len];
+        let chunk = &data[pos..pos + 12 + len];
+        pos += 12 + len;
+
+        let is_xmp = chunk_type == b"iTXt"
+            && body.starts_with(PNG_XMP_KEYWORD)
+            && body.get(PNG_XMP_KEYWORD.len()) == Some(&0);
+        if is_xmp {
+            continue;
+        }
+        output.extend_from_slice(chunk);
+        if chunk_type == b"IHDR" {
+            write_png_chunk(&mut output, b"iTXt", &chunk_data);
+        }
+    }
+    Ok(output)
+}
+
+fn write_png_chunk(output: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    output.extend_from_slice(chunk_type);
+    output.extend_from_slice(data);
+    let mut crc = crc32_update(0xFFFF_FFFF, chunk_type);
+    crc = crc32_update(crc, data);
+    output.extend_from_slice(&(crc ^ 0xFFFF_FFFF).to_be_bytes());
+}
+
+/// PNG 块使用的 CRC-32（多项式 0xEDB88320）
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_manager::sidecar::{parse_xmp, to_xmp, SidecarMetadata};
+
+    /// 读取内嵌的 XMP 数据包
+    fn read_xmp(data: &[u8]) -> Option<String> {
+        let header = if data.starts_with(PNG_SIGNATURE) {
+            [PNG_XMP_KEYWORD, &[0, 0, 0, 0, 0][..]].concat()
+        } else {
+            JPEG_XMP_HEADER.to_vec()
+        };
+        let start = data.windows(header.len()).position(|window| window == header)? + header.len();
+        let end = data[start..].windows(17).position(|window| window == b"<?xpacket end=\"w\"")? + start;
+        String::from_utf8(data[start..end].to_vec()).ok()
+    }
+
+    fn encode(format: image::ImageFormat) -> Vec<u8> {
+        let mut data = std::io::Cursor::new(Vec::new());
+        image::RgbImage::new(8, 8).write_to(&mut data, format).unwrap();
+        data.into_inner()
+    }
+
+    #[test]
+    fn test_embedded_xmp_replaces_previous_packet() {
+        let first = to_xmp(&SidecarMetadata { title: Some("first".to_string()), ..Default::default() });
+        let metadata = SidecarMetadata {
+            tags: vec!["sea".to_string()],
+            title: Some("Sunset".to_string()),
+            notes: Some("from the pier".to_string()),
+            ..Default::default()
+        };
+
+        for format in [image::ImageFormat::Jpeg, image::ImageFormat::Png] {
+            let original = encode(format);
+            let once = embed_xmp(&original, &first).unwrap().unwrap();
+            let twice = embed_xmp(&once, &to_xmp(&metadata)).unwrap().unwrap();
+
+            let xmp = read_xmp(&twice).unwrap();
+            assert_eq!(parse_xmp(&format!("{}<?xpacket end=\"w\"?>", xmp)).unwrap(), metadata);
+            assert_eq!(twice.windows(5).filter(|window| window == b"first").count(), 0);
+
+            // 图像内容不受影响
+            let decoded = image::load_from_memory(&twice).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (8, 8));
+        }
+
+        assert_eq!(embed_xmp(b"plain text", &first).unwrap(), None);
+        assert!(embed_xmp(&[0xFF, 0xD8, 0x00], &first).is_err());
+    }
+}
@@ -3,6 +3,8 @@
 //! 定义了文件管理系统中可能出现的所有错误类型，
 //! 并提供统一的错误处理机制。
 
+use crate::file_manager::read_only::ReadOnlyReason;
+use crate::file_manager::size_limits::FileCategory;
 use thiserror::Error;
 
 /// 文件管理系统错误类型
@@ -24,13 +26,21 @@ pub enum FileManagerError {
     #[error("Directory not found: {path}")]
     DirectoryNotFound { path: String },
 
+    /// 目录非空错误（未指定强制删除时）
+    #[error("Directory not empty: {path} ({file_count} files, {directory_count} subdirectories)")]
+    DirectoryNotEmpty {
+        path: String,
+        file_count: i64,
+        directory_count: i64,
+    },
+
     /// 文件类型不支持错误
     #[error("Unsupported file type: {file_type}")]
     UnsupportedFileType { file_type: String },
 
-    /// 文件大小超限错误
-    #[error("File size exceeds limit: {size} bytes (max: {max_size} bytes)")]
-    FileSizeExceeded { size: u64, max_size: u64 },
+    /// 文件大小超限错误，`category` 为单独设置了上限的文件类别
+    #[error("File size exceeds {}limit: {size} bytes (max: {max_size} bytes)", .category.map(|category| format!("{} ", category.name())).unwrap_or_default())]
+    FileSizeExceeded { size: u64, max_size: u64, category: Option<FileCategory> },
 
     /// 权限不足错误
     #[error("Permission denied: {operation}")]
@@ -48,6 +58,42 @@ pub enum FileManagerError {
     #[error("UUID parse error: {0}")]
     UuidParse(#[from] uuid::Error),
 
+    /// 目录数据库已加密，需要输入密码
+    #[error("Library is locked: password required")]
+    LibraryLocked,
+
+    /// 目录数据库密码错误
+    #[error("Incorrect library password")]
+    IncorrectPassword,
+
+    /// 上传被拒绝，文件已放入隔离区等待审核
+    #[error("File quarantined ({reason}): {id}")]
+    Quarantined { id: String, reason: String },
+
+    /// 服务仍在后台初始化
+    #[error("Service is initializing")]
+    Initializing,
+
+    /// 应用正在退出，不再接受新的请求
+    #[error("Service is shutting down")]
+    ShuttingDown,
+
+    /// 服务初始化失败
+    #[error("Service initialization failed: {message}")]
+    InitializationFailed { message: String },
+
+    /// 资料库处于只读模式
+    #[error("Library is read-only: {0}")]
+    ReadOnly(ReadOnlyReason),
+
+    /// 资料库所在的网络共享无法访问
+    #[error("Library is offline: {path} is unreachable")]
+    LibraryOffline { path: String },
+
+    /// 修改基于的版本已过期：`expected` 为调用方持有的 `updated_at`，`actual` 为当前记录的
+    #[error("Conflict: {id} was modified (expected updated_at {expected}, current {actual})")]
+    Conflict { id: String, expected: String, actual: String },
+
     /// 通用错误
     #[error("General error: {message}")]
     General { message: String },
@@ -71,6 +117,33 @@ impl FileManagerError {
         }
     }
 
+    /// 稳定的错误码，供前端区分错误类型
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Database(_) => "database",
+            Self::FileSystem(_) => "file_system",
+            Self::FileNotFound { .. } => "file_not_found",
+            Self::DirectoryNotFound { .. } => "directory_not_found",
+            Self::DirectoryNotEmpty { .. } => "directory_not_empty",
+            Self::UnsupportedFileType { .. } => "unsupported_file_type",
+            Self::FileSizeExceeded { .. } => "file_size_exceeded",
+            Self::PermissionDenied { .. } => "permission_denied",
+            Self::Configuration { .. } => "configuration",
+            Self::Serialization(_) => "serialization",
+            Self::UuidParse(_) => "uuid_parse",
+            Self::LibraryLocked => "library_locked",
+            Self::IncorrectPassword => "incorrect_password",
+            Self::Quarantined { .. } => "quarantined",
+            Self::Initializing => "initializing",
+            Self::ShuttingDown => "shutting_down",
+            Self::InitializationFailed { .. } => "initialization_failed",
+            Self::ReadOnly(_) => "read_only",
+            Self::LibraryOffline { .. } => "library_offline",
+            Self::Conflict { .. } => "conflict",
+            Self::General { .. } => "general",
+        }
+    }
+
     /// 检查是否为数据库错误
     pub fn is_database_error(&self) -> bool {
         matches!(self, Self::Database(_))
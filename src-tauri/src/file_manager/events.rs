@@ -0,0 +1,305 @@
+//! 事件总线模块
+//!
+//! 后端发往前端的所有事件都经过这里，前端只需面对一套订阅接口：
+//! - 每种事件是一个实现 [`AppEvent`] 的强类型负载，事件名与负载类型一一对应
+//! - [`EVENT_CATALOG`] 列出所有事件及其说明，前端可通过 `get_event_catalog` 查询
+//! - 发送前先写入进程内的事件日志并分配递增序号，
+//!   窗口错过事件（如启动时尚未订阅）时可通过 `get_recent_events` 补收
+//! - 发送失败只记录警告，不影响发送方
+
+use crate::file_manager::board_bundle::OpenedBoard;
+use crate::file_manager::deferred_delete::DeferredDeleteReport;
+use crate::file_manager::directory_delete::DirectoryDeleteProgress;
+use crate::file_manager::export::ExportProgress;
+use crate::file_manager::external_edit::ExternalEditSession;
+use crate::file_manager::integrity::IntegrityReport;
+use crate::file_manager::library_summary::LibrarySummary;
+use crate::file_manager::network_share::LibraryOffline;
+use crate::file_manager::quick_capture::QuickCaptureOutcome;
+use crate::file_manager::read_only::ReadOnlyReason;
+use crate::file_manager::service::UploadResponse;
+use crate::file_manager::shell_integration::SendToReport;
+use crate::file_manager::startup::{StartupReport, StartupTask};
+use crate::file_manager::storage_layout::LayoutMigrationProgress;
+use crate::file_manager::watched_folders::WatchedImportReport;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Runtime};
+
+/// 事件日志保留的条数
+pub const EVENT_LOG_CAPACITY: usize = 256;
+
+/// 最近发送的事件，按序号递增
+static EVENT_LOG: Mutex<EventLog> = Mutex::new(EventLog { next_seq: 1, records: VecDeque::new() });
+
+/// 发往前端的事件
+pub trait AppEvent: Serialize {
+    /// 事件名，必须列在 [`EVENT_CATALOG`] 中
+    const NAME: &'static str;
+}
+
+/// 事件说明
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+pub struct EventDescriptor {
+    pub name: &'static str,
+    /// 事件来源
+    pub source: &'static str,
+    pub description: &'static str,
+}
+
+/// 所有事件
+pub const EVENT_CATALOG: &[EventDescriptor] = &[
+    EventDescriptor { name: BackendReady::NAME, source: "startup", description: "Backend finished starting; payload is the startup phase timings" },
+    EventDescriptor { name: BackendLocked::NAME, source: "startup", description: "Library is encrypted and waiting for unlock_library" },
+    EventDescriptor { name: BackendFailed::NAME, source: "startup", description: "Backend failed to start; payload is the error message" },
+    EventDescriptor { name: StartupTask::NAME, source: "startup", description: "A startup migration or recovery task started, made progress or finished" },
+    EventDescriptor { name: UploadCompleted::NAME, source: "uploads", description: "Files were uploaded into the library" },
+    EventDescriptor { name: ExportProgress::NAME, source: "jobs", description: "One more file of a running export was processed" },
+    EventDescriptor { name: DirectoryDeleteProgress::NAME, source: "jobs", description: "A batch of a background directory deletion finished, or the deletion ended" },
+    EventDescriptor { name: LayoutMigrationProgress::NAME, source: "jobs", description: "A batch of the storage layout migration finished" },
+    EventDescriptor { name: WatchedImport::NAME, source: "watchers", description: "A watched folder scan imported or failed to import files" },
+    EventDescriptor { name: SendToImport::NAME, source: "shell", description: "Files sent from the Explorer context menu or a deep link were imported into the Inbox" },
+    EventDescriptor { name: BoardOpened::NAME, source: "shell", description: "A .cboard file opened from the OS was loaded and its assets resolved" },
+    EventDescriptor { name: QuickCaptureOutcome::NAME, source: "watchers", description: "A global shortcut screenshot was saved or failed" },
+    EventDescriptor { name: ExternalEditChanged::NAME, source: "watchers", description: "A file being edited in an external app was saved and can be imported" },
+    EventDescriptor { name: IntegrityAlert::NAME, source: "monitoring", description: "Integrity sampling found files whose content no longer matches" },
+    EventDescriptor { name: DeferredDeleteFinished::NAME, source: "jobs", description: "Storage files whose deletion was deferred because they were locked were deleted or given up on" },
+    EventDescriptor { name: LibraryTakenOver::NAME, source: "monitoring", description: "Another instance took over the library; this instance is now read-only" },
+    EventDescriptor { name: LibraryOffline::NAME, source: "monitoring", description: "The network share holding the library became unreachable; changes are rejected until it is back" },
+    EventDescriptor { name: LibraryOnline::NAME, source: "monitoring", description: "The network share holding the library is reachable again" },
+    EventDescriptor { name: LibrarySummary::NAME, source: "monitoring", description: "The library name, item count, pending jobs or sync status changed" },
+];
+
+/// 后端启动完成，负载为各阶段耗时
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(transparent)]
+pub struct BackendReady(pub StartupReport);
+
+impl AppEvent for BackendReady {
+    const NAME: &'static str = "backend://ready";
+}
+
+/// 资料库已加密，等待输入密码
+#[derive(Debug, Clone, Copy, Serialize, TS)]
+#[ts(export)]
+pub struct BackendLocked;
+
+impl AppEvent for BackendLocked {
+    const NAME: &'static str = "backend://locked";
+}
+
+/// 后端初始化失败，负载为错误信息
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(transparent)]
+pub struct BackendFailed(pub String);
+
+impl AppEvent for BackendFailed {
+    const NAME: &'static str = "backend://failed";
+}
+
+impl AppEvent for StartupTask {
+    const NAME: &'static str = "backend://startup-task";
+}
+
+/// 文件上传完成
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct UploadCompleted {
+    pub files: Vec<UploadResponse>,
+}
+
+impl AppEvent for UploadCompleted {
+    const NAME: &'static str = "upload-completed";
+}
+
+impl AppEvent for ExportProgress {
+    const NAME: &'static str = "export-progress";
+}
+
+impl AppEvent for DirectoryDeleteProgress {
+    const NAME: &'static str = "directory-delete-progress";
+}
+
+impl AppEvent for LayoutMigrationProgress {
+    const NAME: &'static str = "storage-migration-progress";
+}
+
+/// 监视文件夹扫描导入了文件或有文件导入失败
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(transparent)]
+pub struct WatchedImport(pub WatchedImportReport);
+
+impl AppEvent for WatchedImport {
+    const NAME: &'static str = "watched-import";
+}
+
+/// 从资源管理器右键菜单或深度链接发送的文件已导入收件箱
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(transparent)]
+pub struct SendToImport(pub SendToReport);
+
+impl AppEvent for SendToImport {
+    const NAME: &'static str = "send-to-import";
+}
+
+/// 从系统打开的画板文件已读取，素材已对应到资料库中的文件
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(transparent)]
+pub struct BoardOpened(pub OpenedBoard);
+
+impl AppEvent for BoardOpened {
+    const NAME: &'static str = "board-opened";
+}
+
+/// 资料库已被其他进程接管，本进程转为只读模式
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(transparent)]
+pub struct LibraryTakenOver(pub ReadOnlyReason);
+
+impl AppEvent for LibraryTakenOver {
+    const NAME: &'static str = "library-taken-over";
+}
+
+impl AppEvent for QuickCaptureOutcome {
+    const NAME: &'static str = "quick-capture";
+}
+
+/// 外部编辑的副本被保存，可确认导入为新版本
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(transparent)]
+pub struct ExternalEditChanged(pub ExternalEditSession);
+
+impl AppEvent for ExternalEditChanged {
+    const NAME: &'static str = "external-edit-changed";
+}
+
+/// 完整性抽检发现内容不一致的文件
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(transparent)]
+pub struct IntegrityAlert(pub IntegrityReport);
+
+impl AppEvent for IntegrityAlert {
+    const NAME: &'static str = "integrity-alert";
+}
+
+/// 被占用而延迟删除的存储文件已删除或已放弃
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(transparent)]
+pub struct DeferredDeleteFinished(pub DeferredDeleteReport);
+
+impl AppEvent for DeferredDeleteFinished {
+    const NAME: &'static str = "deferred-delete";
+}
+
+impl AppEvent for LibraryOffline {
+    const NAME: &'static str = "library-offline";
+}
+
+impl AppEvent for LibrarySummary {
+    const NAME: &'static str = "library-summary";
+}
+
+/// 资料库所在的网络共享恢复访问
+#[derive(Debug, Clone, Copy, Serialize, TS)]
+#[ts(export)]
+pub struct LibraryOnline;
+
+impl AppEvent for LibraryOnline {
+    const NAME: &'static str = "library-online";
+}
+
+/// 事件日志中的记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EventRecord {
+    pub seq: u64,
+    pub name: String,
+    pub payload: serde_json::Value,
+    pub emitted_at: DateTime<Local>,
+}
+
+struct EventLog {
+    next_seq: u64,
+    records: VecDeque<EventRecord>,
+}
+
+/// 发送事件
+///
+/// 先写入事件日志再发送，发送失败只记录警告
+pub fn emit<R: Runtime, E: AppEvent>(app: &AppHandle<R>, event: &E) {
+    record(event);
+    if let Err(e) = app.emit(E::NAME, event) {
+        tracing::warn!("事件发送失败: {}, {}", E::NAME, e);
+    }
+}
+
+/// 获取序号大于 `after_seq` 的事件，按序号排列
+pub fn events_after(after_seq: u64) -> Vec<EventRecord> {
+    EVENT_LOG.lock().unwrap()
+        .records
+        .iter()
+        .filter(|record| record.seq > after_seq)
+        .cloned()
+        .collect()
+}
+
+/// 写入事件日志，返回分配的序号
+fn record<E: AppEvent>(event: &E) -> u64 {
+    let payload = serde_json::to_value(event).unwrap_or(serde_json::Value::Null);
+    let mut log = EVENT_LOG.lock().unwrap();
+    let seq = log.next_seq;
+    log.next_seq += 1;
+    if log.records.len() >= EVENT_LOG_CAPACITY {
+        log.records.pop_front();
+    }
+    log.records.push_back(EventRecord {
+        seq,
+        name: E::NAME.to_string(),
+        payload,
+        emitted_at: Local::now(),
+    });
+    seq
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_event_catalog_names_are_unique() {
+        let names: HashSet<_> = EVENT_CATALOG.iter().map(|event| event.name).collect();
+        assert_eq!(names.len(), EVENT_CATALOG.len());
+        assert!(names.contains(IntegrityAlert::NAME));
+    }
+
+    #[test]
+    fn test_recorded_events_can_be_replayed() {
+        let first = record(&BackendFailed("disk full".to_string()));
+        let second = record(&BackendLocked);
+        assert!(second > first);
+
+        let replayed: Vec<_> = events_after(first - 1)
+            .into_iter()
+            .filter(|record| record.seq == first || record.seq == second)
+            .collect();
+        assert_eq!(replayed.len(), 2);
+        assert_eq!(replayed[0].name, "backend://failed");
+        assert_eq!(replayed[0].payload, serde_json::json!("disk full"));
+        assert_eq!(replayed[1].payload, serde_json::Value::Null);
+        assert!(events_after(second).iter().all(|record| record.seq > second));
+    }
+}
@@ -0,0 +1,185 @@
+//! 文件导出模块
+//!
+//! 将选中的文件复制到库外的目录，用于整理交付文件：
+//! - 使用原始文件名或存储文件名
+//! - 平铺到目标目录或保留库中的目录结构
+//! - 目标文件已存在时按策略重命名、覆盖或跳过
+//! - 可选在每个文件旁写入记录标签、评分和备注的附属文件
+//! - 可选给导出的图片叠加水印
+//! - 可选在目标目录写入导出文件的校验清单
+
+use crate::file_manager::checksum_manifest::ManifestFormat;
+use crate::file_manager::paths::{sanitize_file_name, with_copy_suffix};
+use crate::file_manager::sidecar::SidecarFormat;
+use crate::file_manager::watermark::Watermark;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// 导出的目录结构
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportLayout {
+    /// 所有文件直接放在目标目录下
+    #[default]
+    Flatten,
+    /// 按文件在库中的目录路径建立子目录
+    KeepStructure,
+}
+
+/// 目标文件已存在时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportCollisionPolicy {
+    /// 追加序号后缀，如 `photo (1).jpg`
+    #[default]
+    Rename,
+    /// 覆盖已有文件
+    Overwrite,
+    /// 保留已有文件，不导出
+    Skip,
+}
+
+/// 导出请求
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExportRequest {
+    pub file_ids: Vec<String>,
+    pub destination_dir: String,
+    /// 使用上传时的原始文件名，否则使用存储文件名
+    #[serde(default = "default_use_original_names")]
+    pub use_original_names: bool,
+    #[serde(default)]
+    pub layout: ExportLayout,
+    #[serde(default)]
+    pub collision_policy: ExportCollisionPolicy,
+    /// 附属元数据文件格式，`None` 表示不写入
+    #[serde(default)]
+    pub sidecar: Option<SidecarFormat>,
+    /// 叠加在导出图片上的水印，`None` 表示原样复制
+    #[serde(default)]
+    pub watermark: Option<Watermark>,
+    /// 导出完成后写入目标目录的校验清单格式，`None` 表示不写入
+    #[serde(default)]
+    pub manifest: Option<ManifestFormat>,
+}
+
+fn default_use_original_names() -> bool {
+    true
+}
+
+/// 导出进度
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExportProgress {
+    /// 已处理的文件数（含跳过和失败）
+    pub completed: usize,
+    pub total: usize,
+    pub bytes_copied: u64,
+    /// 刚处理完的文件 ID
+    pub file_id: String,
+}
+
+/// 导出失败的文件
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExportFailure {
+    pub file_id: String,
+    pub error: String,
+}
+
+/// 导出结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExportReport {
+    /// 导出的文件路径
+    pub exported: Vec<String>,
+    /// 因目标已存在而跳过的文件 ID
+    pub skipped: Vec<String>,
+    pub failed: Vec<ExportFailure>,
+    pub bytes_copied: u64,
+    /// 写入的校验清单路径
+    #[serde(default)]
+    pub manifest_path: Option<String>,
+}
+
+/// 将库中的目录路径转换为导出目标下的相对路径
+///
+/// 每一段都经过文件名清理，保证在所有平台上都能创建
+pub fn relative_export_dir(directory_path: &str) -> PathBuf {
+    directory_path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(sanitize_file_name)
+        .collect()
+}
+
+/// 确定导出目标路径，返回 `None` 表示跳过
+///
+/// `claimed` 记录本次导出已使用的路径；与本次导出的其他文件同名时总是重命名，不会互相覆盖
+pub fn resolve_export_target(
+    directory: &Path,
+    file_name: &str,
+    policy: ExportCollisionPolicy,
+    claimed: &mut HashSet<PathBuf>,
+) -> Option<PathBuf> {
+    let target = directory.join(file_name);
+    let exists_on_disk = target.exists();
+
+    let resolved = if claimed.contains(&target) {
+        next_free_name(directory, file_name, claimed)
+    } else if !exists_on_disk {
+        target
+    } else {
+        match policy {
+            ExportCollisionPolicy::Overwrite => target,
+            ExportCollisionPolicy::Skip => return None,
+            ExportCollisionPolicy::Rename => next_free_name(directory, file_name, claimed),
+        }
+    };
+
+    claimed.insert(resolved.clone());
+    Some(resolved)
+}
+
+/// 查找第一个既不存在于磁盘、也未被本次导出占用的带序号文件名
+fn next_free_name(directory: &Path, file_name: &str, claimed: &HashSet<PathBuf>) -> PathBuf {
+    (1..)
+        .map(|index| directory.join(with_copy_suffix(file_name, index)))
+        .find(|candidate| !candidate.exists() && !claimed.contains(candidate))
+        .expect("unbounded suffix search always finds a free name")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_relative_export_dir() {
+        assert_eq!(relative_export_dir("/"), PathBuf::new());
+        assert_eq!(relative_export_dir("/projects/a:b"), Path::new("projects").join("a_b"));
+    }
+
+    #[test]
+    fn test_resolve_export_target() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("photo.jpg"), b"existing").unwrap();
+        let mut claimed = HashSet::new();
+
+        let skip = resolve_export_target(temp_dir.path(), "photo.jpg", ExportCollisionPolicy::Skip, &mut claimed);
+        assert!(skip.is_none());
+
+        let overwrite = resolve_export_target(temp_dir.path(), "photo.jpg", ExportCollisionPolicy::Overwrite, &mut claimed);
+        assert_eq!(overwrite.unwrap(), temp_dir.path().join("photo.jpg"));
+
+        // 同一次导出中的同名文件不会覆盖刚导出的文件
+        let second = resolve_export_target(temp_dir.path(), "photo.jpg", ExportCollisionPolicy::Overwrite, &mut claimed);
+        assert_eq!(second.unwrap(), temp_dir.path().join("photo (1).jpg"));
+
+        let fresh = resolve_export_target(temp_dir.path(), "new.jpg", ExportCollisionPolicy::Rename, &mut claimed);
+        assert_eq!(fresh.unwrap(), temp_dir.path().join("new.jpg"));
+    }
+}
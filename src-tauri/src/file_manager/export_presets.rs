@@ -0,0 +1,180 @@
+//! 导出预设模块
+//!
+//! 预设记录一组导出参数，如“网页”预设把图片缩到最长边 2048 像素的 JPEG，一次调用即可导出：
+//! - 预设包括最长边、编码格式、JPEG 质量和文件名后缀，保存在设置中
+//! - 默认只缩小，超过最长边的图片按比例缩小；开启放大后较小的图片也按比例放大到最长边
+//! - 导出的文件名为原始文件名加后缀，扩展名按编码格式替换，如 `photo_web.jpg`
+//! - 只能导出可以解码的图像，其他文件记为失败
+//! - 可选在缩放后叠加水印，见 [`watermark`](super::watermark)
+
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::ids::IdStrategy;
+use crate::file_manager::paths::sanitize_file_name;
+use crate::file_manager::thumbnail::{decode_image, encode_image, PreviewFormat};
+use crate::file_manager::watermark::PreparedWatermark;
+use image::imageops::FilterType;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// 保存导出预设的状态键
+pub const EXPORT_PRESETS_KEY: &str = "export_presets";
+
+/// 预设数量上限
+pub const MAX_EXPORT_PRESETS: usize = 50;
+
+/// 最长边的允许范围
+pub const MIN_PRESET_EDGE: u32 = 16;
+pub const MAX_PRESET_EDGE: u32 = 16384;
+
+/// 导出预设
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExportPreset {
+    /// 保存时为空的预设会分配新 ID
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// 导出图片的最长边（像素）
+    pub max_edge: u32,
+    pub format: PreviewFormat,
+    /// JPEG 质量（1-100）
+    pub quality: u8,
+    /// 追加在文件名（不含扩展名）后的后缀，可以为空
+    #[serde(default)]
+    pub suffix: String,
+    /// 比最长边小的图片是否放大
+    #[serde(default)]
+    pub upscale: bool,
+}
+
+/// 尚未保存过预设时使用的默认预设
+pub fn default_export_presets() -> Vec<ExportPreset> {
+    vec![ExportPreset {
+        id: "web".to_string(),
+        name: "Web".to_string(),
+        max_edge: 2048,
+        format: PreviewFormat::Jpeg,
+        quality: 85,
+        suffix: "_web".to_string(),
+        upscale: false,
+    }]
+}
+
+/// 校验并规范化预设列表：名称去除首尾空白，空 ID 按 `id_strategy` 分配新 ID，检查参数范围、后缀和重复
+pub fn validate_presets(presets: Vec<ExportPreset>, id_strategy: IdStrategy) -> Result<Vec<ExportPreset>> {
+    if presets.len() > MAX_EXPORT_PRESETS {
+        return Err(FileManagerError::general_error(format!(
+            "Cannot have more than {} export presets", MAX_EXPORT_PRESETS
+        )));
+    }
+
+    let mut ids = HashSet::new();
+    let mut validated = Vec::with_capacity(presets.len());
+    for mut preset in presets {
+        preset.name = preset.name.trim().to_string();
+        if preset.name.is_empty() {
+            return Err(FileManagerError::general_error("Export preset name cannot be empty"));
+        }
+        if !(MIN_PRESET_EDGE..=MAX_PRESET_EDGE).contains(&preset.max_edge) {
+            return Err(FileManagerError::general_error(format!(
+                "Export preset max edge must be between {} and {}", MIN_PRESET_EDGE, MAX_PRESET_EDGE
+            )));
+        }
+        if !(1..=100).contains(&preset.quality) {
+            return Err(FileManagerError::general_error("Export preset quality must be between 1 and 100"));
+        }
+        if !preset.suffix.is_empty() && sanitize_file_name(&preset.suffix) != preset.suffix {
+            return Err(FileManagerError::general_error(format!("Invalid export preset suffix: {}", preset.suffix)));
+        }
+        if preset.id.trim().is_empty() {
+            preset.id = id_strategy.generate();
+        }
+        if !ids.insert(preset.id.clone()) {
+            return Err(FileManagerError::general_error(format!("Duplicate export preset ID: {}", preset.id)));
+        }
+        validated.push(preset);
+    }
+    Ok(validated)
+}
+
+/// 按预设导出的文件名：原始文件名加后缀，扩展名按编码格式替换
+pub fn preset_file_name(original_name: &str, preset: &ExportPreset) -> String {
+    let stem = Path::new(original_name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    sanitize_file_name(&format!("{}{}.{}", stem, preset.suffix, preset.format.extension()))
+}
+
+/// 按预设转换图片并写入 `target`，返回写入的字节数；`watermark` 在缩放后叠加
+///
+/// 解码和缩放较慢，需在阻塞线程中调用
+pub fn render_preset(
+    source: &Path,
+    target: &Path,
+    preset: &ExportPreset,
+    watermark: Option<&PreparedWatermark>,
+) -> Result<u64> {
+    let image = decode_image(source)?;
+    let longest = image.width().max(image.height());
+    let image = if longest > preset.max_edge || (preset.upscale && longest < preset.max_edge) {
+        image.resize(preset.max_edge, preset.max_edge, FilterType::Lanczos3)
+    } else {
+        image
+    };
+    let image = match watermark {
+        Some(watermark) => watermark.apply(&image),
+        None => image,
+    };
+
+    encode_image(&image, preset.format, preset.quality, None, target)?;
+    Ok(std::fs::metadata(target)?.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn preset(max_edge: u32, upscale: bool) -> ExportPreset {
+        ExportPreset { max_edge, upscale, ..default_export_presets().remove(0) }
+    }
+
+    #[test]
+    fn test_validate_presets() {
+        let presets = validate_presets(vec![
+            ExportPreset { id: String::new(), name: " Print ".to_string(), ..preset(4096, true) },
+            preset(2048, false),
+        ], IdStrategy::Ulid).unwrap();
+        assert_eq!(presets[0].name, "Print");
+        assert_eq!(presets[0].id.len(), 26);
+
+        assert!(validate_presets(vec![preset(2048, false), preset(1024, false)], IdStrategy::Uuid).is_err());
+        assert!(validate_presets(vec![preset(8, false)], IdStrategy::Uuid).is_err());
+        assert!(validate_presets(vec![ExportPreset { quality: 0, ..preset(2048, false) }], IdStrategy::Uuid).is_err());
+        assert!(validate_presets(vec![ExportPreset { suffix: "/web".to_string(), ..preset(2048, false) }], IdStrategy::Uuid).is_err());
+        assert_eq!(preset_file_name("holiday.photo.png", &preset(2048, false)), "holiday.photo_web.jpg");
+    }
+
+    #[test]
+    fn test_render_preset_scales_to_max_edge() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source.png");
+        image::RgbImage::new(600, 300).save(&source).unwrap();
+        let target = temp_dir.path().join("out.jpg");
+
+        let size = render_preset(&source, &target, &preset(200, false), None).unwrap();
+        assert_eq!(std::fs::metadata(&target).unwrap().len(), size);
+        assert_eq!(image::image_dimensions(&target).unwrap(), (200, 100));
+
+        // 默认不放大较小的图片
+        render_preset(&source, &target, &preset(1000, false), None).unwrap();
+        assert_eq!(image::image_dimensions(&target).unwrap(), (600, 300));
+        render_preset(&source, &target, &preset(1000, true), None).unwrap();
+        assert_eq!(image::image_dimensions(&target).unwrap(), (1000, 500));
+
+        assert!(render_preset(&temp_dir.path().join("missing.png"), &target, &preset(200, false), None).is_err());
+    }
+}
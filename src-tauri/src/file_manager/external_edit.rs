@@ -0,0 +1,177 @@
+//! 外部编辑模块
+//!
+//! 在外部应用中编辑库中的文件，保存后确认时作为原文件的新版本导入：
+//! - 开始编辑时把文件复制到应用数据目录下的临时位置，用配置的外部应用打开副本
+//! - 后台定期检查副本的修改时间和大小，发现保存后发送事件，由前端询问是否导入
+//! - 确认导入时以副本内容替换原文件并递增版本号；编辑期间原文件已被其他操作修改时拒绝导入
+//! - 导入后会话保持打开，可继续编辑再次导入，结束会话时删除副本
+//! - 会话只保存在内存中，应用退出后不再跟踪
+
+use crate::file_manager::events::{self, ExternalEditChanged};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::AppHandle;
+
+/// 检查副本是否被保存的间隔
+pub const EDIT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 应用数据目录下存放编辑副本的目录名
+pub const EXTERNAL_EDIT_DIR: &str = "external_edits";
+
+/// 进行中的编辑会话
+static SESSIONS: Mutex<Vec<TrackedEdit>> = Mutex::new(Vec::new());
+
+/// 外部编辑会话
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExternalEditSession {
+    pub id: String,
+    pub file_id: String,
+    /// 编辑副本的路径
+    pub temp_path: String,
+    /// 副本对应的原文件版本，导入时原文件必须仍是该版本
+    pub base_version: i64,
+    /// 副本在上次导入（或开始编辑）后被保存过
+    pub has_changes: bool,
+    pub started_at: DateTime<Local>,
+}
+
+/// 副本的修改时间和大小，任一变化即视为被保存
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    /// 读取文件当前的修改时间和大小，文件不存在时返回 `None`
+    pub fn read(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self { modified: metadata.modified().ok(), len: metadata.len() })
+    }
+}
+
+struct TrackedEdit {
+    session: ExternalEditSession,
+    stamp: Option<FileStamp>,
+}
+
+/// 会话的编辑副本目录
+pub fn session_dir(app_data_dir: &Path, session_id: &str) -> PathBuf {
+    app_data_dir.join(EXTERNAL_EDIT_DIR).join(session_id)
+}
+
+/// 登记会话，`stamp` 为副本刚写入时的状态
+pub fn register(session: ExternalEditSession, stamp: Option<FileStamp>) {
+    SESSIONS.lock().unwrap().push(TrackedEdit { session, stamp });
+}
+
+/// 获取会话
+pub fn get_session(session_id: &str) -> Option<ExternalEditSession> {
+    SESSIONS.lock().unwrap()
+        .iter()
+        .find(|edit| edit.session.id == session_id)
+        .map(|edit| edit.session.clone())
+}
+
+/// 获取所有进行中的会话，按开始时间排列
+pub fn list_sessions() -> Vec<ExternalEditSession> {
+    SESSIONS.lock().unwrap().iter().map(|edit| edit.session.clone()).collect()
+}
+
+/// 记录副本已导入为原文件的 `version` 版本
+pub fn mark_applied(session_id: &str, version: i64, stamp: Option<FileStamp>) -> Option<ExternalEditSession> {
+    let mut sessions = SESSIONS.lock().unwrap();
+    let edit = sessions.iter_mut().find(|edit| edit.session.id == session_id)?;
+    edit.session.base_version = version;
+    edit.session.has_changes = false;
+    edit.stamp = stamp;
+    Some(edit.session.clone())
+}
+
+/// 移除会话
+pub fn remove(session_id: &str) -> Option<ExternalEditSession> {
+    let mut sessions = SESSIONS.lock().unwrap();
+    let index = sessions.iter().position(|edit| edit.session.id == session_id)?;
+    Some(sessions.remove(index).session)
+}
+
+/// 检查所有副本，返回自上次检查后被保存过的会话
+pub fn poll_changes() -> Vec<ExternalEditSession> {
+    let mut sessions = SESSIONS.lock().unwrap();
+    let mut changed = Vec::new();
+    for edit in sessions.iter_mut() {
+        let stamp = FileStamp::read(Path::new(&edit.session.temp_path));
+        // 编辑器保存时可能先删除再写入，副本暂时不存在时等下次检查
+        if stamp.is_some() && stamp != edit.stamp {
+            edit.stamp = stamp;
+            edit.session.has_changes = true;
+            changed.push(edit.session.clone());
+        }
+    }
+    changed
+}
+
+/// 结束会话并删除编辑副本
+pub async fn end_session(app_data_dir: &Path, session_id: &str) -> Option<ExternalEditSession> {
+    let session = remove(session_id)?;
+    let dir = session_dir(app_data_dir, session_id);
+    if let Err(e) = tokio::fs::remove_dir_all(&dir).await {
+        tracing::warn!("编辑副本删除失败: {:?}, {}", dir, e);
+    }
+    Some(session)
+}
+
+/// 定期检查编辑副本，发现保存后发送事件
+pub fn spawn_edit_watch_loop(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(EDIT_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            for session in poll_changes() {
+                tracing::info!("外部编辑副本已保存: {}, {}", session.file_id, session.temp_path);
+                events::emit(&app, &ExternalEditChanged(session));
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_manager::ids::IdStrategy;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_poll_detects_saved_copies() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("photo.jpg");
+        std::fs::write(&path, b"original").unwrap();
+
+        let session = ExternalEditSession {
+            id: IdStrategy::default().generate(),
+            file_id: "file-1".to_string(),
+            temp_path: path.to_string_lossy().to_string(),
+            base_version: 1,
+            has_changes: false,
+            started_at: Local::now(),
+        };
+        register(session.clone(), FileStamp::read(&path));
+        assert!(!poll_changes().iter().any(|changed| changed.id == session.id));
+
+        std::fs::write(&path, b"edited content").unwrap();
+        let changed: Vec<_> = poll_changes().into_iter().filter(|changed| changed.id == session.id).collect();
+        assert_eq!(changed.len(), 1);
+        assert!(changed[0].has_changes);
+        assert!(!poll_changes().iter().any(|changed| changed.id == session.id));
+
+        let applied = mark_applied(&session.id, 2, FileStamp::read(&path)).unwrap();
+        assert_eq!((applied.base_version, applied.has_changes), (2, false));
+        assert_eq!(remove(&session.id), Some(applied));
+        assert_eq!(get_session(&session.id), None);
+    }
+}
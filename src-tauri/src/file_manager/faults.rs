@@ -0,0 +1,89 @@
+//! 故障注入模块（仅测试）
+//!
+//! 在文件系统服务和数据库服务中模拟上传流程各步骤的失败，验证回滚和启动恢复不会留下孤立文件：
+//! - 写入失败：暂存文件只写入前 N 字节后返回错误，模拟磁盘已满或设备断开
+//! - 操作失败：插入文件记录或提升暂存文件返回错误，调用方应回滚已完成的步骤
+//! - 崩溃：在两个步骤之间直接 panic，回滚代码不会执行，模拟进程被强制结束，
+//!   之后由启动恢复流程清理
+//! - 删除被占用：删除存储文件返回文件被占用的错误，模拟杀毒软件正打开着文件
+//!
+//! 每个故障只触发一次；测试在 `tokio::spawn` 中运行上传以捕获模拟崩溃的 panic
+
+use crate::file_manager::error::{FileManagerError, Result};
+use std::sync::{Arc, Mutex};
+
+/// 注入的故障
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// 写入暂存文件时在指定字节数后失败
+    FailWriteAfter(usize),
+    /// 插入文件记录失败
+    FailInsert,
+    /// 暂存文件已写入、插入文件记录前崩溃
+    CrashBeforeInsert,
+    /// 提升暂存文件失败
+    FailPromote,
+    /// 文件记录已写入、提升暂存文件前崩溃
+    CrashBeforePromote,
+    /// 删除存储文件时文件被其他进程占用
+    LockedDelete,
+}
+
+/// 故障注入器，克隆后共享同一组待触发的故障
+#[derive(Debug, Clone, Default)]
+pub struct FaultInjector {
+    armed: Arc<Mutex<Vec<Fault>>>,
+}
+
+impl FaultInjector {
+    /// 安排一个故障，在对应步骤下一次执行时触发
+    pub fn arm(&self, fault: Fault) {
+        self.armed.lock().unwrap().push(fault);
+    }
+
+    /// 取出第一个匹配的故障
+    fn take(&self, matches: impl Fn(&Fault) -> bool) -> Option<Fault> {
+        let mut armed = self.armed.lock().unwrap();
+        let index = armed.iter().position(matches)?;
+        Some(armed.remove(index))
+    }
+
+    /// 本次写入暂存文件允许写入的字节数，未安排写入故障时为 `None`
+    pub fn write_limit(&self) -> Option<usize> {
+        match self.take(|fault| matches!(fault, Fault::FailWriteAfter(_))) {
+            Some(Fault::FailWriteAfter(limit)) => Some(limit),
+            _ => None,
+        }
+    }
+
+    /// 插入文件记录前调用
+    pub fn before_insert(&self) -> Result<()> {
+        self.trigger(Fault::FailInsert, Fault::CrashBeforeInsert)
+    }
+
+    /// 提升暂存文件前调用
+    pub fn before_promote(&self) -> Result<()> {
+        self.trigger(Fault::FailPromote, Fault::CrashBeforePromote)
+    }
+
+    /// 删除存储文件前调用
+    pub fn before_delete(&self) -> Result<()> {
+        match self.take(|fault| *fault == Fault::LockedDelete) {
+            Some(_) => Err(FileManagerError::FileSystem(std::io::ErrorKind::ResourceBusy.into())),
+            None => Ok(()),
+        }
+    }
+
+    fn trigger(&self, fail: Fault, crash: Fault) -> Result<()> {
+        match self.take(|fault| *fault == fail || *fault == crash) {
+            Some(fault) if fault == crash => panic!("injected crash: {:?}", fault),
+            Some(fault) => Err(injected_error(fault)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 注入故障返回的错误
+pub fn injected_error(fault: Fault) -> FileManagerError {
+    FileManagerError::general_error(format!("Injected fault: {:?}", fault))
+}
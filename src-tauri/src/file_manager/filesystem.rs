@@ -6,8 +6,17 @@
 //! - 目录创建和删除
 //! - 文件类型检测和验证
 //! - 大文件处理和进度跟踪
+//! - 文件被杀毒软件等临时占用时自动重试，见 [`retry`](super::retry)
+//! - 测试中可以改用内存存储，见 [`memory_storage`](super::memory_storage)
 
 use crate::file_manager::error::{FileManagerError, Result};
+#[cfg(test)]
+use crate::file_manager::faults::{self, Fault, FaultInjector};
+#[cfg(test)]
+use crate::file_manager::memory_storage::MemoryStorage;
+use crate::file_manager::paths::{long_path, storage_extension};
+use crate::file_manager::retry::retry_io;
+use crate::file_manager::sniffer;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -22,9 +31,28 @@ pub struct UploadInfo {
     pub unique_name: String,
 }
 
+/// 暂存的上传文件
+///
+/// 文件内容已写入暂存区，但尚未提升到最终存储位置
+#[derive(Debug, Clone)]
+pub struct StagedUpload {
+    /// 上传信息（`saved_path` 为提升后的最终路径）
+    pub info: UploadInfo,
+    /// 暂存区中的文件路径
+    pub staged_path: PathBuf,
+}
+
+/// 暂存区目录名（位于存储根目录下，保证与最终位置处于同一文件系统）
+pub const STAGING_DIR_NAME: &str = ".staging";
+
 /// 文件系统服务
 pub struct FileSystemService {
     storage_root: PathBuf,
+    #[cfg(test)]
+    faults: FaultInjector,
+    /// 设置后存储根目录下的读写都在内存中进行
+    #[cfg(test)]
+    memory: Option<MemoryStorage>,
 }
 
 impl FileSystemService {
@@ -32,9 +60,31 @@ impl FileSystemService {
     pub fn new(storage_root: &Path) -> Result<Self> {
         Ok(Self {
             storage_root: storage_root.to_path_buf(),
+            #[cfg(test)]
+            faults: FaultInjector::default(),
+            #[cfg(test)]
+            memory: None,
         })
     }
 
+    /// 创建使用内存存储的文件系统服务实例
+    ///
+    /// 路径计算与磁盘存储相同，但不会创建 `storage_root`；链接到库外的原始文件仍从磁盘读取
+    #[cfg(test)]
+    pub fn in_memory(storage_root: &Path, storage: MemoryStorage) -> Self {
+        Self {
+            storage_root: storage_root.to_path_buf(),
+            faults: FaultInjector::default(),
+            memory: Some(storage),
+        }
+    }
+
+    /// 故障注入器，与数据库服务的注入器相互独立
+    #[cfg(test)]
+    pub fn faults(&self) -> &FaultInjector {
+        &self.faults
+    }
+
     /// 保存上传的文件
     /// 
     /// 将文件数据保存到指定的存储目录，并返回文件信息
@@ -57,7 +107,7 @@ impl FileSystemService {
         
         // 确保目标目录存在
         let full_target_dir = self.storage_root.join(target_dir);
-        fs::create_dir_all(&full_target_dir).await.map_err(|e| {
+        self.create_dir_all(&full_target_dir).await.map_err(|e| {
             FileManagerError::FileSystem(e)
         })?;
 
@@ -65,14 +115,14 @@ impl FileSystemService {
         let file_path = full_target_dir.join(&unique_name);
         
         // 检查文件是否已存在（虽然 UUID 重复的概率极低）
-        if file_path.exists() {
+        if self.exists(&file_path) {
             return Err(FileManagerError::general_error(
                 format!("File already exists: {}", file_path.display())
             ));
         }
 
         // 保存文件
-        fs::write(&file_path, file_data).await.map_err(|e| {
+        retry_io("写入文件", &file_path, || self.write_blob(&file_path, file_data)).await.map_err(|e| {
             FileManagerError::FileSystem(e)
         })?;
 
@@ -104,7 +154,7 @@ impl FileSystemService {
         
         // 确保目标目录存在
         let full_target_dir = self.storage_root.join(target_dir);
-        fs::create_dir_all(&full_target_dir).await.map_err(|e| {
+        self.create_dir_all(&full_target_dir).await.map_err(|e| {
             FileManagerError::FileSystem(e)
         })?;
 
@@ -112,7 +162,7 @@ impl FileSystemService {
         let file_path = full_target_dir.join(&unique_name);
         
         // 创建文件
-        let mut file = fs::File::create(&file_path).await.map_err(|e| {
+        let mut file = self.create_blob(&file_path).await.map_err(|e| {
             FileManagerError::FileSystem(e)
         })?;
 
@@ -165,15 +215,301 @@ impl FileSystemService {
         })
     }
 
+    /// 暂存上传的文件
+    ///
+    /// 将文件数据写入暂存区，最终路径仅计算不创建，
+    /// 需在数据库记录成功后调用 [`promote_staged`](Self::promote_staged) 完成提交
+    pub async fn stage_file(
+        &self,
+        file_data: &[u8],
+        original_name: &str,
+        target_dir: &Path,
+    ) -> Result<StagedUpload> {
+        if file_data.is_empty() {
+            return Err(FileManagerError::general_error("File data is empty"));
+        }
+
+        let mime_type = self.detect_mime_type(original_name, file_data);
+        let unique_name = self.generate_unique_filename(original_name);
+        let staged_path = self.prepare_staging_path(&unique_name).await?;
+
+        // 写入失败时删除不完整的暂存文件
+        if let Err(e) = self.write_staged(&staged_path, file_data).await {
+            let _ = self.discard_staged(&staged_path).await;
+            return Err(e);
+        }
+
+        Ok(StagedUpload {
+            info: UploadInfo {
+                original_name: original_name.to_string(),
+                file_size: file_data.len() as u64,
+                mime_type,
+                saved_path: self.storage_root.join(target_dir).join(&unique_name),
+                unique_name,
+            },
+            staged_path,
+        })
+    }
+
+    /// 暂存大文件（分块处理）
+    ///
+    /// 与 [`stage_file`](Self::stage_file) 相同，但以流式方式写入暂存区
+    pub async fn stage_large_file<F>(
+        &self,
+        mut file_reader: impl AsyncReadExt + Unpin,
+        original_name: &str,
+        target_dir: &Path,
+        expected_size: u64,
+        mut progress_callback: F,
+    ) -> Result<StagedUpload>
+    where
+        F: FnMut(u64, u64),
+    {
+        let unique_name = self.generate_unique_filename(original_name);
+        let staged_path = self.prepare_staging_path(&unique_name).await?;
+
+        // 写入失败时删除不完整的暂存文件
+        let written = self.write_staged_stream(&staged_path, &mut file_reader, expected_size, &mut progress_callback).await;
+        let (total_written, first_chunk) = match written {
+            Ok(written) => written,
+            Err(e) => {
+                let _ = self.discard_staged(&staged_path).await;
+                return Err(e);
+            }
+        };
+
+        let mime_type = self.detect_mime_type(original_name, &first_chunk);
+
+        Ok(StagedUpload {
+            info: UploadInfo {
+                original_name: original_name.to_string(),
+                file_size: total_written,
+                mime_type,
+                saved_path: self.storage_root.join(target_dir).join(&unique_name),
+                unique_name,
+            },
+            staged_path,
+        })
+    }
+
+    /// 写入暂存文件并同步到磁盘
+    async fn write_staged(&self, staged_path: &Path, file_data: &[u8]) -> Result<()> {
+        #[cfg(test)]
+        if let Some(limit) = self.faults.write_limit() {
+            self.write_blob(staged_path, &file_data[..limit.min(file_data.len())]).await?;
+            return Err(faults::injected_error(Fault::FailWriteAfter(limit)));
+        }
+
+        // 提升前必须确保数据已落盘
+        retry_io("写入暂存文件", staged_path, || async {
+            let mut file = self.create_blob(staged_path).await?;
+            file.write_all(file_data).await?;
+            file.sync_all().await
+        }).await.map_err(|e| {
+            FileManagerError::FileSystem(e)
+        })
+    }
+
+    /// 以流式方式写入暂存文件并同步到磁盘，返回写入的字节数和第一个数据块（用于检测文件类型）
+    async fn write_staged_stream<F>(
+        &self,
+        staged_path: &Path,
+        file_reader: &mut (impl AsyncReadExt + Unpin),
+        expected_size: u64,
+        progress_callback: &mut F,
+    ) -> Result<(u64, Vec<u8>)>
+    where
+        F: FnMut(u64, u64),
+    {
+        #[cfg(test)]
+        let write_limit = self.faults.write_limit();
+
+        let mut file = self.create_blob(staged_path).await.map_err(|e| {
+            FileManagerError::FileSystem(e)
+        })?;
+
+        let mut buffer = vec![0u8; 64 * 1024];
+        let mut total_written = 0u64;
+        let mut first_chunk = Vec::new();
+
+        loop {
+            let bytes_read = file_reader.read(&mut buffer).await.map_err(|e| {
+                FileManagerError::FileSystem(e)
+            })?;
+
+            if bytes_read == 0 {
+                break;
+            }
+
+            let chunk = &buffer[..bytes_read];
+            if first_chunk.is_empty() {
+                first_chunk.extend_from_slice(chunk);
+            }
+
+            #[cfg(test)]
+            if let Some(limit) = write_limit {
+                if total_written as usize + chunk.len() > limit {
+                    file.write_all(&chunk[..limit - total_written as usize]).await?;
+                    return Err(faults::injected_error(Fault::FailWriteAfter(limit)));
+                }
+            }
+
+            file.write_all(chunk).await.map_err(|e| {
+                FileManagerError::FileSystem(e)
+            })?;
+
+            total_written += bytes_read as u64;
+            progress_callback(total_written, expected_size);
+        }
+
+        // 提升前必须确保数据已落盘
+        file.sync_all().await.map_err(|e| {
+            FileManagerError::FileSystem(e)
+        })?;
+
+        Ok((total_written, first_chunk))
+    }
+
+    /// 将暂存文件原子地移动到最终位置
+    pub async fn promote_staged(&self, staged_path: &Path, final_path: &Path) -> Result<()> {
+        #[cfg(test)]
+        self.faults.before_promote()?;
+
+        if self.exists(final_path) {
+            return Err(FileManagerError::general_error(
+                format!("File already exists: {}", final_path.display())
+            ));
+        }
+
+        if let Some(parent) = final_path.parent() {
+            self.create_dir_all(parent).await.map_err(|e| {
+                FileManagerError::FileSystem(e)
+            })?;
+        }
+
+        // 暂存区与最终位置位于同一存储根目录下，rename 是原子操作
+        retry_io("移动暂存文件", staged_path, || self.rename(staged_path, final_path)).await.map_err(|e| {
+            FileManagerError::FileSystem(e)
+        })?;
+
+        Ok(())
+    }
+
+    /// 丢弃暂存文件
+    pub async fn discard_staged(&self, staged_path: &Path) -> Result<()> {
+        match retry_io("删除暂存文件", staged_path, || self.remove_blob(staged_path)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(FileManagerError::FileSystem(e)),
+        }
+    }
+
+    /// 列出暂存区中遗留的文件
+    ///
+    /// 正常流程下暂存区应为空，启动时存在的文件说明上次上传被中断
+    pub async fn list_staged_files(&self) -> Result<Vec<PathBuf>> {
+        let staging_dir = self.staging_dir();
+        if !self.exists(&staging_dir) {
+            return Ok(Vec::new());
+        }
+
+        self.list_files(&staging_dir).await.map_err(|e| {
+            FileManagerError::FileSystem(e)
+        })
+    }
+
+    /// 将目录路径解析为存储根目录下的绝对路径
+    ///
+    /// 数据库中的目录路径以 `/` 开头（如 `/photos/2024`），直接 `join` 会替换掉存储根目录，
+    /// 因此这里只保留普通路径组件
+    pub fn resolve_directory_path(&self, dir_path: &Path) -> PathBuf {
+        let relative: PathBuf = dir_path
+            .components()
+            .filter(|component| matches!(component, std::path::Component::Normal(_)))
+            .collect();
+        self.storage_root.join(relative)
+    }
+
+    /// 将存储文件的绝对路径转换为数据库中保存的路径
+    ///
+    /// 存储根目录下的文件保存为以 `/` 分隔的相对路径（如 `2024/01/15/uuid.jpg`），
+    /// 使整个库可以移动到其他位置或其他机器；存储根目录之外的路径原样保存
+    pub fn to_stored_path(&self, path: &Path) -> String {
+        match path.strip_prefix(&self.storage_root) {
+            Ok(relative) => relative
+                .components()
+                .filter_map(|component| match component {
+                    std::path::Component::Normal(part) => part.to_str(),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("/"),
+            Err(_) => path.display().to_string(),
+        }
+    }
+
+    /// 将数据库中保存的路径解析为存储文件的绝对路径
+    ///
+    /// 相对路径基于当前存储根目录解析；绝对路径（旧版本数据）原样返回
+    pub fn resolve_stored_path(&self, stored_path: &str) -> PathBuf {
+        let path = Path::new(stored_path);
+        if path.is_absolute() {
+            return path.to_path_buf();
+        }
+
+        stored_path
+            .split('/')
+            .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+            .fold(self.storage_root.clone(), |full_path, part| full_path.join(part))
+    }
+
+    /// 为旧版本保存的绝对路径找到对应的相对路径
+    ///
+    /// 路径位于存储根目录下时直接转换；否则（库已被移动）按 `YYYY/MM/DD/文件名`
+    /// 的存储布局在当前存储根目录下查找同名文件
+    pub fn relocate_legacy_path(&self, legacy_path: &str) -> Option<String> {
+        let path = Path::new(legacy_path);
+        if !path.is_absolute() {
+            return None;
+        }
+
+        if path.starts_with(&self.storage_root) {
+            return Some(self.to_stored_path(path));
+        }
+
+        let components: Vec<_> = path.components().collect();
+        let tail: PathBuf = components.iter().skip(components.len().saturating_sub(4)).collect();
+        let candidate = self.storage_root.join(&tail);
+        self.is_file(&candidate).then(|| self.to_stored_path(&candidate))
+    }
+
+    /// 获取暂存区目录
+    fn staging_dir(&self) -> PathBuf {
+        self.storage_root.join(STAGING_DIR_NAME)
+    }
+
+    /// 确保暂存区存在并返回暂存文件路径
+    async fn prepare_staging_path(&self, unique_name: &str) -> Result<PathBuf> {
+        let staging_dir = self.staging_dir();
+        self.create_dir_all(&staging_dir).await.map_err(|e| {
+            FileManagerError::FileSystem(e)
+        })?;
+
+        Ok(staging_dir.join(unique_name))
+    }
+
     /// 删除文件
     pub async fn delete_file(&self, file_path: &Path) -> Result<()> {
-        if !file_path.exists() {
+        if !self.exists(file_path) {
             return Err(FileManagerError::FileNotFound {
                 path: file_path.display().to_string(),
             });
         }
 
-        fs::remove_file(file_path).await.map_err(|e| {
+        #[cfg(test)]
+        self.faults.before_delete()?;
+
+        retry_io("删除文件", file_path, || self.remove_blob(file_path)).await.map_err(|e| {
             FileManagerError::FileSystem(e)
         })?;
 
@@ -182,9 +518,9 @@ impl FileSystemService {
 
     /// 创建目录
     pub async fn create_directory(&self, dir_path: &Path) -> Result<()> {
-        let full_path = self.storage_root.join(dir_path);
+        let full_path = self.resolve_directory_path(dir_path);
         
-        fs::create_dir_all(&full_path).await.map_err(|e| {
+        self.create_dir_all(&full_path).await.map_err(|e| {
             FileManagerError::FileSystem(e)
         })?;
 
@@ -192,16 +528,24 @@ impl FileSystemService {
     }
 
     /// 删除目录（递归删除）
+    ///
+    /// 拒绝删除存储根目录本身（如空路径或 `/`）
     pub async fn delete_directory(&self, dir_path: &Path) -> Result<()> {
-        let full_path = self.storage_root.join(dir_path);
-        
-        if !full_path.exists() {
+        let full_path = self.resolve_directory_path(dir_path);
+
+        if full_path == self.storage_root {
+            return Err(FileManagerError::PermissionDenied {
+                operation: format!("delete storage root: {}", full_path.display()),
+            });
+        }
+
+        if !self.exists(&full_path) {
             return Err(FileManagerError::DirectoryNotFound {
                 path: full_path.display().to_string(),
             });
         }
 
-        fs::remove_dir_all(&full_path).await.map_err(|e| {
+        retry_io("删除目录", &full_path, || self.remove_dir_all(&full_path)).await.map_err(|e| {
             FileManagerError::FileSystem(e)
         })?;
 
@@ -210,34 +554,64 @@ impl FileSystemService {
 
     /// 检查文件是否存在
     pub async fn file_exists(&self, file_path: &Path) -> bool {
-        file_path.exists() && file_path.is_file()
+        self.is_file(file_path)
     }
 
     /// 检查目录是否存在
     pub async fn directory_exists(&self, dir_path: &Path) -> bool {
-        let full_path = self.storage_root.join(dir_path);
-        full_path.exists() && full_path.is_dir()
+        let full_path = self.resolve_directory_path(dir_path);
+        self.is_dir(&full_path)
     }
 
-    /// 获取文件大小
-    pub async fn get_file_size(&self, file_path: &Path) -> Result<u64> {
-        let metadata = fs::metadata(file_path).await.map_err(|e| {
+    /// 移动目录
+    ///
+    /// 源目录和目标目录均为相对于存储根目录的目录路径
+    pub async fn move_directory(&self, from: &Path, to: &Path) -> Result<()> {
+        let from_path = self.resolve_directory_path(from);
+        let to_path = self.resolve_directory_path(to);
+
+        if !self.exists(&from_path) {
+            return Err(FileManagerError::DirectoryNotFound {
+                path: from_path.display().to_string(),
+            });
+        }
+
+        if self.exists(&to_path) {
+            return Err(FileManagerError::general_error(
+                format!("Directory already exists: {}", to_path.display())
+            ));
+        }
+
+        if let Some(parent) = to_path.parent() {
+            self.create_dir_all(parent).await.map_err(|e| {
+                FileManagerError::FileSystem(e)
+            })?;
+        }
+
+        retry_io("移动目录", &from_path, || self.rename(&from_path, &to_path)).await.map_err(|e| {
             FileManagerError::FileSystem(e)
         })?;
 
-        Ok(metadata.len())
+        Ok(())
+    }
+
+    /// 获取文件大小
+    pub async fn get_file_size(&self, file_path: &Path) -> Result<u64> {
+        self.blob_len(file_path).await.map_err(|e| {
+            FileManagerError::FileSystem(e)
+        })
     }
 
     /// 移动文件
     pub async fn move_file(&self, from: &Path, to: &Path) -> Result<()> {
         // 确保目标目录存在
         if let Some(parent) = to.parent() {
-            fs::create_dir_all(parent).await.map_err(|e| {
+            self.create_dir_all(parent).await.map_err(|e| {
                 FileManagerError::FileSystem(e)
             })?;
         }
 
-        fs::rename(from, to).await.map_err(|e| {
+        retry_io("移动文件", from, || self.rename(from, to)).await.map_err(|e| {
             FileManagerError::FileSystem(e)
         })?;
 
@@ -248,65 +622,72 @@ impl FileSystemService {
     pub async fn copy_file(&self, from: &Path, to: &Path) -> Result<()> {
         // 确保目标目录存在
         if let Some(parent) = to.parent() {
-            fs::create_dir_all(parent).await.map_err(|e| {
+            self.create_dir_all(parent).await.map_err(|e| {
                 FileManagerError::FileSystem(e)
             })?;
         }
 
-        fs::copy(from, to).await.map_err(|e| {
+        retry_io("复制文件", from, || self.copy_blob(from, to)).await.map_err(|e| {
             FileManagerError::FileSystem(e)
         })?;
 
         Ok(())
     }
 
+    /// 读取库外原始文件的信息，用于创建链接文件
+    ///
+    /// 不复制文件，`saved_path` 即原始文件的规范化绝对路径；MIME 类型无法从扩展名判断时
+    /// 只读取文件开头部分检测。原始文件不属于存储，使用内存存储时同样从磁盘读取
+    pub async fn inspect_linked_file(&self, path: &Path, original_name: &str) -> Result<UploadInfo> {
+        let path = fs::canonicalize(long_path(path)).await?;
+        let metadata = fs::metadata(&path).await?;
+        if !metadata.is_file() {
+            return Err(FileManagerError::FileNotFound { path: path.display().to_string() });
+        }
+
+        let mut head = Vec::with_capacity(512);
+        fs::File::open(&path).await?.take(512).read_to_end(&mut head).await?;
+
+        Ok(UploadInfo {
+            original_name: original_name.to_string(),
+            file_size: metadata.len(),
+            mime_type: self.detect_mime_type(original_name, &head),
+            saved_path: path,
+            unique_name: self.generate_unique_filename(original_name),
+        })
+    }
+
     /// 读取文件内容
     pub async fn read_file(&self, file_path: &Path) -> Result<Vec<u8>> {
-        fs::read(file_path).await.map_err(|e| {
+        retry_io("读取文件", file_path, || self.read_blob(file_path)).await.map_err(|e| {
             FileManagerError::FileSystem(e)
         })
     }
 
     /// 获取目录中的所有文件
     pub async fn list_files_in_directory(&self, dir_path: &Path) -> Result<Vec<PathBuf>> {
-        let full_path = self.storage_root.join(dir_path);
+        let full_path = self.resolve_directory_path(dir_path);
         
-        if !full_path.exists() {
+        if !self.exists(&full_path) {
             return Err(FileManagerError::DirectoryNotFound {
                 path: full_path.display().to_string(),
             });
         }
 
-        let mut entries = fs::read_dir(&full_path).await.map_err(|e| {
-            FileManagerError::FileSystem(e)
-        })?;
-
-        let mut files = Vec::new();
-        while let Some(entry) = entries.next_entry().await.map_err(|e| {
+        self.list_files(&full_path).await.map_err(|e| {
             FileManagerError::FileSystem(e)
-        })? {
-            let path = entry.path();
-            if path.is_file() {
-                files.push(path);
-            }
-        }
-
-        Ok(files)
+        })
     }
 
     /// 生成唯一文件名
+    ///
+    /// 存储文件名只由 UUID 和规范化后的扩展名组成，不受原始文件名长度和字符的影响
     fn generate_unique_filename(&self, original_name: &str) -> String {
-        let path = Path::new(original_name);
-        let extension = path.extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or("");
-        
         let uuid = uuid::Uuid::new_v4();
-        
-        if extension.is_empty() {
-            uuid.to_string()
-        } else {
-            format!("{}.{}", uuid, extension)
+
+        match storage_extension(original_name) {
+            Some(extension) => format!("{}.{}", uuid, extension),
+            None => uuid.to_string(),
         }
     }
 
@@ -323,35 +704,7 @@ impl FileSystemService {
 
     /// 从文件内容检测 MIME 类型
     fn detect_mime_from_content(&self, data: &[u8]) -> String {
-        if data.is_empty() {
-            return "application/octet-stream".to_string();
-        }
-
-        // 检查常见的文件签名
-        match data {
-            // JPEG
-            d if d.len() >= 3 && d[0] == 0xFF && d[1] == 0xD8 && d[2] == 0xFF => {
-                "image/jpeg".to_string()
-            }
-            // PNG
-            d if d.len() >= 8 && d[0..8] == [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A] => {
-                "image/png".to_string()
-            }
-            // GIF
-            d if d.len() >= 6 && (d[0..6] == *b"GIF87a" || d[0..6] == *b"GIF89a") => {
-                "image/gif".to_string()
-            }
-            // PDF
-            d if d.len() >= 4 && d[0..4] == *b"%PDF" => {
-                "application/pdf".to_string()
-            }
-            // ZIP
-            d if d.len() >= 4 && d[0..4] == [0x50, 0x4B, 0x03, 0x04] => {
-                "application/zip".to_string()
-            }
-            // 默认为二进制流
-            _ => "application/octet-stream".to_string(),
-        }
+        sniffer::detect_mime_from_content(data)
     }
 
     /// 验证文件类型是否被支持
@@ -368,8 +721,8 @@ impl FileSystemService {
     pub async fn cleanup_temp_files(&self, temp_dir: &Path) -> Result<()> {
         let full_path = self.storage_root.join(temp_dir);
         
-        if full_path.exists() {
-            fs::remove_dir_all(&full_path).await.map_err(|e| {
+        if self.exists(&full_path) {
+            self.remove_dir_all(&full_path).await.map_err(|e| {
                 FileManagerError::FileSystem(e)
             })?;
         }
@@ -378,11 +731,160 @@ impl FileSystemService {
     }
 }
 
+/// 存储的基本读写操作
+///
+/// 默认直接操作磁盘；测试中设置了内存存储时改为读写内存，上层逻辑（重试、错误转换、故障注入）保持不变
+impl FileSystemService {
+    async fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            return memory.create_dir_all(path);
+        }
+        fs::create_dir_all(long_path(path)).await
+    }
+
+    async fn create_blob(&self, path: &Path) -> std::io::Result<BlobWriter> {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            memory.write(path, &[])?;
+            return Ok(BlobWriter::Memory(memory.clone(), path.to_path_buf()));
+        }
+        Ok(BlobWriter::Disk(fs::File::create(long_path(path)).await?))
+    }
+
+    async fn write_blob(&self, path: &Path, data: &[u8]) -> std::io::Result<()> {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            return memory.write(path, data);
+        }
+        fs::write(long_path(path), data).await
+    }
+
+    async fn read_blob(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            return memory.read(path);
+        }
+        fs::read(long_path(path)).await
+    }
+
+    async fn blob_len(&self, path: &Path) -> std::io::Result<u64> {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            return memory.len(path);
+        }
+        Ok(fs::metadata(long_path(path)).await?.len())
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            return memory.rename(from, to);
+        }
+        fs::rename(long_path(from), long_path(to)).await
+    }
+
+    async fn copy_blob(&self, from: &Path, to: &Path) -> std::io::Result<u64> {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            return memory.copy(from, to);
+        }
+        fs::copy(long_path(from), long_path(to)).await
+    }
+
+    async fn remove_blob(&self, path: &Path) -> std::io::Result<()> {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            return memory.remove_file(path);
+        }
+        fs::remove_file(long_path(path)).await
+    }
+
+    async fn remove_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            return memory.remove_dir_all(path);
+        }
+        fs::remove_dir_all(long_path(path)).await
+    }
+
+    /// 列出目录中直接包含的文件
+    async fn list_files(&self, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            return memory.list_files(dir);
+        }
+
+        let mut entries = fs::read_dir(long_path(dir)).await?;
+        let mut files = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.is_file(path) || self.is_dir(path)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            return memory.is_file(path);
+        }
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            return memory.is_dir(path);
+        }
+        path.is_dir()
+    }
+}
+
+/// 流式写入的存储文件
+enum BlobWriter {
+    Disk(fs::File),
+    #[cfg(test)]
+    Memory(MemoryStorage, PathBuf),
+}
+
+impl BlobWriter {
+    async fn write_all(&mut self, data: &[u8]) -> std::io::Result<()> {
+        match self {
+            Self::Disk(file) => file.write_all(data).await,
+            #[cfg(test)]
+            Self::Memory(memory, path) => memory.append(path, data),
+        }
+    }
+
+    async fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Disk(file) => file.flush().await,
+            #[cfg(test)]
+            Self::Memory(..) => Ok(()),
+        }
+    }
+
+    async fn sync_all(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Disk(file) => file.sync_all().await,
+            #[cfg(test)]
+            Self::Memory(..) => Ok(()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::TempDir;
-    use tokio::io::Cursor;
+    use std::io::Cursor;
 
     async fn create_test_service() -> (FileSystemService, TempDir) {
         let temp_dir = TempDir::new().unwrap();
@@ -430,6 +932,44 @@ mod tests {
         assert!(result.saved_path.exists());
     }
 
+    #[tokio::test]
+    async fn test_stage_and_promote_file() {
+        let (service, _temp_dir) = create_test_service().await;
+
+        let staged = service.stage_file(
+            b"Staged content",
+            "staged.txt",
+            Path::new("uploads")
+        ).await.unwrap();
+
+        assert!(staged.staged_path.exists());
+        assert!(!staged.info.saved_path.exists());
+        assert_eq!(service.list_staged_files().await.unwrap().len(), 1);
+
+        service.promote_staged(&staged.staged_path, &staged.info.saved_path).await.unwrap();
+
+        assert!(!staged.staged_path.exists());
+        assert!(staged.info.saved_path.exists());
+        assert!(service.list_staged_files().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_discard_staged_file() {
+        let (service, _temp_dir) = create_test_service().await;
+
+        let staged = service.stage_file(
+            b"Discarded content",
+            "discard.txt",
+            Path::new("uploads")
+        ).await.unwrap();
+
+        service.discard_staged(&staged.staged_path).await.unwrap();
+        assert!(!staged.staged_path.exists());
+
+        // 重复丢弃不应报错
+        service.discard_staged(&staged.staged_path).await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_create_and_delete_directory() {
         let (service, _temp_dir) = create_test_service().await;
@@ -443,6 +983,54 @@ mod tests {
         assert!(!service.directory_exists(dir_path).await);
     }
 
+    #[tokio::test]
+    async fn test_directory_paths_stay_inside_storage_root() {
+        let (service, temp_dir) = create_test_service().await;
+
+        let resolved = service.resolve_directory_path(Path::new("/photos/2024"));
+        assert_eq!(resolved, temp_dir.path().join("photos").join("2024"));
+
+        let resolved = service.resolve_directory_path(Path::new("/../escape"));
+        assert_eq!(resolved, temp_dir.path().join("escape"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_storage_root_refused() {
+        let (service, temp_dir) = create_test_service().await;
+
+        assert!(service.delete_directory(Path::new("")).await.is_err());
+        assert!(service.delete_directory(Path::new("/")).await.is_err());
+        assert!(temp_dir.path().exists());
+    }
+
+    #[tokio::test]
+    async fn test_stored_paths_are_relative() {
+        let (service, temp_dir) = create_test_service().await;
+
+        let full_path = temp_dir.path().join("2024").join("01").join("15").join("a.jpg");
+        let stored = service.to_stored_path(&full_path);
+        assert_eq!(stored, "2024/01/15/a.jpg");
+        assert_eq!(service.resolve_stored_path(&stored), full_path);
+
+        // 库被移动后，旧的绝对路径按存储布局重新定位
+        std::fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        std::fs::write(&full_path, b"data").unwrap();
+        let moved = Path::new("/old/library/files/2024/01/15/a.jpg");
+        assert_eq!(service.relocate_legacy_path(&moved.display().to_string()).as_deref(), Some("2024/01/15/a.jpg"));
+        assert_eq!(service.relocate_legacy_path("/old/library/files/2024/01/15/missing.jpg"), None);
+    }
+
+    #[tokio::test]
+    async fn test_move_directory() {
+        let (service, _temp_dir) = create_test_service().await;
+
+        service.create_directory(Path::new("/a/b")).await.unwrap();
+        service.move_directory(Path::new("/a/b"), Path::new("/c")).await.unwrap();
+
+        assert!(!service.directory_exists(Path::new("/a/b")).await);
+        assert!(service.directory_exists(Path::new("/c")).await);
+    }
+
     #[tokio::test]
     async fn test_mime_type_detection() {
         let (service, _temp_dir) = create_test_service().await;
@@ -485,4 +1073,34 @@ mod tests {
         service.delete_file(&upload_info.saved_path).await.unwrap();
         assert!(!service.file_exists(&upload_info.saved_path).await);
     }
+
+    #[tokio::test]
+    async fn test_memory_storage_never_touches_disk() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage_root = temp_dir.path().join("files");
+        let storage = MemoryStorage::new();
+        let service = FileSystemService::in_memory(&storage_root, storage.clone());
+
+        let staged = service.stage_file(b"Staged content", "staged.txt", Path::new("2024")).await.unwrap();
+        assert_eq!(service.list_staged_files().await.unwrap(), vec![staged.staged_path.clone()]);
+        service.promote_staged(&staged.staged_path, &staged.info.saved_path).await.unwrap();
+        assert!(service.file_exists(&staged.info.saved_path).await);
+        assert_eq!(service.read_file(&staged.info.saved_path).await.unwrap(), b"Staged content");
+
+        let large = service.stage_large_file(Cursor::new(vec![7u8; 200 * 1024]), "large.bin", Path::new("2024"), 200 * 1024, |_, _| {}).await.unwrap();
+        assert_eq!(service.get_file_size(&large.staged_path).await.unwrap(), 200 * 1024);
+        service.discard_staged(&large.staged_path).await.unwrap();
+
+        service.create_directory(Path::new("/photos/2024")).await.unwrap();
+        service.copy_file(&staged.info.saved_path, &storage_root.join("photos/2024/copy.txt")).await.unwrap();
+        service.move_directory(Path::new("/photos"), Path::new("/archive/photos")).await.unwrap();
+        assert!(!service.directory_exists(Path::new("/photos")).await);
+        assert_eq!(service.list_files_in_directory(Path::new("/archive/photos/2024")).await.unwrap().len(), 1);
+
+        service.delete_directory(Path::new("/archive")).await.unwrap();
+        service.delete_file(&staged.info.saved_path).await.unwrap();
+        assert!(service.delete_file(&staged.info.saved_path).await.is_err());
+        assert_eq!(storage.file_count(), 0);
+        assert!(!storage_root.exists());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,295 @@
+//! GPU 缩略图缩放模块（`gpu-thumbnails` 特性）
+//!
+//! 数万张大尺寸图片首次建立索引时，缩放是生成缩略图的主要耗时：
+//! - 用 wgpu 计算着色器按面积平均缩小图像，效果与 CPU 上缩小时的盒式滤波相近
+//! - 设备在首次使用时初始化一次；没有硬件适配器（软件渲染器不计）时不再尝试
+//! - 图像无需缩小、超出存储缓冲区上限或执行出错时由调用方回退到 CPU
+//! - 颜色按 8 位 sRGB 值直接平均，与 CPU 路径一致；不含透明通道的图像输出时去掉透明通道
+//! - 显存带宽有限的集成显卡上未必更快，可用
+//!   `cargo test --release --features gpu-thumbnails -- --ignored bench_` 在目标机器上对比
+
+use image::{DynamicImage, RgbaImage};
+use std::sync::OnceLock;
+use wgpu::util::DeviceExt;
+
+/// 工作组边长，与着色器中的 `@workgroup_size` 一致
+const WORKGROUP_SIZE: u32 = 8;
+
+const SHADER: &str = r#"
+struct Params {
+    src_width: u32,
+    src_height: u32,
+    dst_width: u32,
+    dst_height: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> src: array<u32>;
+@group(0) @binding(2) var<storage, read_write> dst: array<u32>;
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.dst_width || id.y >= params.dst_height) {
+        return;
+    }
+
+    let x0 = id.x * params.src_width / params.dst_width;
+    let x1 = max(x0 + 1u, (id.x + 1u) * params.src_width / params.dst_width);
+    let y0 = id.y * params.src_height / params.dst_height;
+    let y1 = max(y0 + 1u, (id.y + 1u) * params.src_height / params.dst_height);
+
+    var sum = vec4<f32>(0.0);
+    for (var y = y0; y < y1; y++) {
+        for (var x = x0; x < x1; x++) {
+            sum += unpack4x8unorm(src[y * params.src_width + x]);
+        }
+    }
+    dst[id.y * params.dst_width + id.x] = pack4x8unorm(sum / f32((x1 - x0) * (y1 - y0)));
+}
+"#;
+
+struct GpuResizer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    layout: wgpu::BindGroupLayout,
+    /// 单个存储缓冲区的字节上限
+    max_buffer_bytes: u64,
+}
+
+impl GpuResizer {
+    async fn new() -> std::result::Result<Self, String> {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                force_fallback_adapter: false,
+                compatible_surface: None,
+            })
+            .await
+            .ok_or_else(|| "no GPU adapter found".to_string())?;
+
+        let info = adapter.get_info();
+        if info.device_type == wgpu::DeviceType::Cpu {
+            return Err(format!("{} is a software renderer", info.name));
+        }
+
+        let limits = adapter.limits();
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("thumbnail"),
+                    required_features: wgpu::Features::empty(),
+                    required_limits: limits.clone(),
+                    memory_hints: wgpu::MemoryHints::Performance,
+                },
+                None,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("downscale"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("downscale"),
+            layout: None,
+            module: &shader,
+            entry_point: "main",
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let layout = pipeline.get_bind_group_layout(0);
+
+        tracing::info!("GPU 缩略图加速已启用: {} ({:?})", info.name, info.backend);
+        Ok(Self {
+            device,
+            queue,
+            pipeline,
+            layout,
+            max_buffer_bytes: u64::from(limits.max_storage_buffer_binding_size).min(limits.max_buffer_size),
+        })
+    }
+
+    fn downscale(&self, source: &RgbaImage, width: u32, height: u32) -> std::result::Result<RgbaImage, String> {
+        let params: Vec<u8> = [source.width(), source.height(), width, height]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let params = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("downscale params"),
+            contents: &params,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let src = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("downscale source"),
+            contents: source.as_raw(),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let output_bytes = u64::from(width) * u64::from(height) * 4;
+        let dst = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("downscale output"),
+            size: output_bytes,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("downscale readback"),
+            size: output_bytes,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("downscale"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: src.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: dst.as_entire_binding() },
+            ],
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("downscale") });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("downscale"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(width.div_ceil(WORKGROUP_SIZE), height.div_ceil(WORKGROUP_SIZE), 1);
+        }
+        encoder.copy_buffer_to_buffer(&dst, 0, &staging, 0, output_bytes);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let _ = self.device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+
+        let pixels = slice.get_mapped_range().to_vec();
+        staging.unmap();
+        RgbaImage::from_raw(width, height, pixels).ok_or_else(|| "GPU output has an unexpected size".to_string())
+    }
+}
+
+/// 首次调用时初始化 GPU 设备，不可用时返回 `None`
+fn resizer() -> Option<&'static GpuResizer> {
+    static RESIZER: OnceLock<Option<GpuResizer>> = OnceLock::new();
+    RESIZER
+        .get_or_init(|| match pollster::block_on(GpuResizer::new()) {
+            Ok(resizer) => Some(resizer),
+            Err(e) => {
+                tracing::warn!("GPU 缩略图加速不可用，使用 CPU 生成: {}", e);
+                None
+            }
+        })
+        .as_ref()
+}
+
+/// 保持宽高比缩小到 `max` × `max` 以内的尺寸
+fn fit_dimensions(width: u32, height: u32, max: u32) -> (u32, u32) {
+    let ratio = (f64::from(max) / f64::from(width)).min(f64::from(max) / f64::from(height));
+    let scale = |value: u32| ((f64::from(value) * ratio).round() as u32).max(1);
+    (scale(width), scale(height))
+}
+
+/// 在 GPU 上把图像缩小到 `max` × `max` 以内
+///
+/// 不适用（无需缩小、GPU 不可用、图像超出缓冲区上限）时返回 `None`，执行出错时返回错误，两者都应回退到 CPU
+pub fn downscale(image: &DynamicImage, max: u32) -> Option<std::result::Result<DynamicImage, String>> {
+    let (width, height) = fit_dimensions(image.width(), image.height(), max);
+    if width >= image.width() && height >= image.height() {
+        return None;
+    }
+
+    let resizer = resizer()?;
+    if u64::from(image.width()) * u64::from(image.height()) * 4 > resizer.max_buffer_bytes {
+        return None;
+    }
+
+    let has_alpha = image.color().has_alpha();
+    Some(resizer.downscale(&image.to_rgba8(), width, height).map(|thumbnail| {
+        let thumbnail = DynamicImage::ImageRgba8(thumbnail);
+        if has_alpha {
+            thumbnail
+        } else {
+            DynamicImage::ImageRgb8(thumbnail.to_rgb8())
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(image::RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x * 255 / width) as u8, (y * 255 / height) as u8, 128])
+        }))
+    }
+
+    #[test]
+    fn test_fit_dimensions() {
+        assert_eq!(fit_dimensions(6000, 4000, 512), (512, 341));
+        assert_eq!(fit_dimensions(1000, 4000, 256), (64, 256));
+        assert_eq!(fit_dimensions(10000, 1, 128), (128, 1));
+    }
+
+    #[test]
+    fn test_gpu_downscale_matches_cpu() {
+        let image = gradient(1200, 800);
+        let Some(result) = downscale(&image, 128) else {
+            // 没有 GPU 的环境中跳过
+            return;
+        };
+        let gpu = result.unwrap().to_rgb8();
+        let cpu = image.thumbnail(128, 128).to_rgb8();
+        assert_eq!(gpu.dimensions(), cpu.dimensions());
+
+        let max_difference = gpu.as_raw().iter().zip(cpu.as_raw())
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap();
+        assert!(max_difference <= 8, "max channel difference {}", max_difference);
+
+        assert!(downscale(&gradient(64, 64), 128).is_none());
+    }
+
+    #[test]
+    #[ignore = "基准测试：cargo test --release --features gpu-thumbnails -- --ignored bench_"]
+    fn bench_gpu_and_cpu_downscale() {
+        const ROUNDS: u32 = 10;
+        let image = gradient(6000, 4000);
+
+        let started = Instant::now();
+        for _ in 0..ROUNDS {
+            std::hint::black_box(image.thumbnail(512, 512));
+        }
+        let cpu = started.elapsed() / ROUNDS;
+
+        // 首次调用包含设备初始化，不计入
+        if downscale(&image, 512).is_none() {
+            println!("CPU: {:?}/张，GPU 不可用", cpu);
+            return;
+        }
+        let started = Instant::now();
+        for _ in 0..ROUNDS {
+            std::hint::black_box(downscale(&image, 512).unwrap().unwrap());
+        }
+        let gpu = started.elapsed() / ROUNDS;
+
+        println!("6000x4000 -> 512: CPU {:?}/张, GPU {:?}/张, 加速 {:.1} 倍",
+            cpu, gpu, cpu.as_secs_f64() / gpu.as_secs_f64());
+    }
+}
@@ -0,0 +1,262 @@
+//! HEIC / AVIF 解码模块
+//!
+//! 手机截图和新版软件的导出越来越多地使用 HEIC 和 AVIF，`image` 库默认无法解码这两种格式：
+//! - 按文件内容识别格式，不依赖扩展名
+//! - 以 `heif-native` 特性编译时由 libheif 在进程内解码 HEIC 和 AVIF；以 `avif-native` 特性编译时
+//!   AVIF 由 `image` 的 dav1d 解码器解码。开启后用户不需要安装任何工具，Windows 上的发布构建应开启
+//! - 没有进程内解码器或解码失败时，调用系统中已安装的解码工具转换为 PNG 后再解码：
+//!   macOS 上优先使用系统自带的 `sips`，其他平台依次尝试 libheif 的 `heif-convert`、
+//!   libavif 的 `avifdec`（仅 AVIF）和 ImageMagick 的 `magick`
+//! - 缩略图、比较、相似度和导出共用同一流程；都不可用或都解码失败时返回错误，上传和存储不受影响，只是没有缩略图
+//! - 进程内解码读取文件嵌入的 ICC 配置文件，转换后的 PNG 保留工具写入的 ICC 配置文件
+
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::sniffer::sniffer;
+use image::DynamicImage;
+use std::io::Read;
+use std::path::Path;
+
+/// 识别格式时读取的文件头长度
+const HEADER_LEN: usize = 64;
+
+/// 命令参数中替换为源文件路径的占位符
+const INPUT: &str = "{input}";
+
+/// 命令参数中替换为输出 PNG 路径的占位符
+const OUTPUT: &str = "{output}";
+
+/// 需要外部工具解码的图像格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeifFormat {
+    /// HEVC 编码的 HEIF 图像
+    Heic,
+    Avif,
+}
+
+impl HeifFormat {
+    /// 按 MIME 类型识别，不是 HEIC / AVIF 时返回 `None`
+    pub fn from_mime_type(mime_type: &str) -> Option<Self> {
+        match mime_type {
+            "image/heic" | "image/heif" => Some(Self::Heic),
+            "image/avif" => Some(Self::Avif),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Heic => "HEIC",
+            Self::Avif => "AVIF",
+        }
+    }
+}
+
+/// 外部解码工具
+struct Converter {
+    program: &'static str,
+    args: &'static [&'static str],
+    /// 为 `false` 时只用于 AVIF
+    heic: bool,
+}
+
+/// 按优先顺序排列的解码工具
+const CONVERTERS: &[Converter] = &[
+    #[cfg(target_os = "macos")]
+    Converter { program: "sips", args: &["-s", "format", "png", INPUT, "--out", OUTPUT], heic: true },
+    Converter { program: "heif-convert", args: &[INPUT, OUTPUT], heic: true },
+    Converter { program: "avifdec", args: &[INPUT, OUTPUT], heic: false },
+    Converter { program: "magick", args: &[INPUT, OUTPUT], heic: true },
+];
+
+/// 按文件头识别是否为 HEIC / AVIF
+pub fn detect_format(header: &[u8]) -> Option<HeifFormat> {
+    sniffer().sniff(header).and_then(HeifFormat::from_mime_type)
+}
+
+/// 读取文件头识别是否为 HEIC / AVIF
+pub fn detect_file_format(path: &Path) -> std::io::Result<Option<HeifFormat>> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    std::fs::File::open(path)?.take(HEADER_LEN as u64).read_to_end(&mut header)?;
+    Ok(detect_format(&header))
+}
+
+/// 在进程内解码，返回图像和嵌入的 ICC 配置文件
+///
+/// 未以支持该格式的特性编译时返回 `None`，由调用方改用 [`convert_to_png`]
+#[cfg(feature = "heif-native")]
+pub fn decode_native(source: &Path, format: HeifFormat) -> Option<Result<(DynamicImage, Option<Vec<u8>>)>> {
+    let decoded = decode_with_libheif(source);
+    // libheif 未带 AV1 解码器编译时交给 dav1d
+    #[cfg(feature = "avif-native")]
+    let decoded = match decoded {
+        Err(_) if format == HeifFormat::Avif => decode_with_dav1d(source),
+        decoded => decoded,
+    };
+    #[cfg(not(feature = "avif-native"))]
+    let _ = format;
+    Some(decoded)
+}
+
+/// 在进程内解码，返回图像和嵌入的 ICC 配置文件
+///
+/// 未以支持该格式的特性编译时返回 `None`，由调用方改用 [`convert_to_png`]
+#[cfg(not(feature = "heif-native"))]
+pub fn decode_native(source: &Path, format: HeifFormat) -> Option<Result<(DynamicImage, Option<Vec<u8>>)>> {
+    #[cfg(feature = "avif-native")]
+    if format == HeifFormat::Avif {
+        return Some(decode_with_dav1d(source));
+    }
+    let _ = (source, format);
+    None
+}
+
+/// 用 libheif 解码主图像为 RGBA
+#[cfg(feature = "heif-native")]
+fn decode_with_libheif(source: &Path) -> Result<(DynamicImage, Option<Vec<u8>>)> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let decode_error = |e: libheif_rs::HeifError| FileManagerError::general_error(format!("libheif: {}", e));
+    let path = source.to_str()
+        .ok_or_else(|| FileManagerError::general_error(format!("Invalid image path: {}", source.display())))?;
+    let context = HeifContext::read_from_file(path).map_err(decode_error)?;
+    let handle = context.primary_image_handle().map_err(decode_error)?;
+    let icc_profile = handle.color_profile_raw().map(|profile| profile.data);
+    let decoded = LibHeif::new()
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+        .map_err(decode_error)?;
+
+    let plane = decoded.planes().interleaved
+        .ok_or_else(|| FileManagerError::general_error("libheif returned no interleaved plane"))?;
+    let (width, height) = (plane.width, plane.height);
+    // 每行末尾可能有对齐填充，逐行复制
+    let row_len = width as usize * 4;
+    let mut pixels = Vec::with_capacity(row_len * height as usize);
+    for row in plane.data.chunks(plane.stride).take(height as usize) {
+        pixels.extend_from_slice(&row[..row_len]);
+    }
+    let image = image::RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| FileManagerError::general_error("libheif returned a truncated image"))?;
+    Ok((DynamicImage::ImageRgba8(image), icc_profile))
+}
+
+/// 用 `image` 的 dav1d 解码器解码 AVIF
+#[cfg(feature = "avif-native")]
+fn decode_with_dav1d(source: &Path) -> Result<(DynamicImage, Option<Vec<u8>>)> {
+    use image::ImageDecoder;
+
+    let decode_error = |e: image::ImageError| FileManagerError::general_error(format!("Failed to decode AVIF image: {}", e));
+    let mut reader = image::ImageReader::open(source)?;
+    reader.set_format(image::ImageFormat::Avif);
+    let mut decoder = reader.into_decoder().map_err(decode_error)?;
+    let icc_profile = decoder.icc_profile().unwrap_or(None);
+    let image = DynamicImage::from_decoder(decoder).map_err(decode_error)?;
+    Ok((image, icc_profile))
+}
+
+/// 用外部工具把图像转换为 PNG，返回 PNG 数据
+///
+/// 会等待外部进程结束，需在阻塞线程中调用
+pub fn convert_to_png(source: &Path, format: HeifFormat) -> Result<Vec<u8>> {
+    let output = std::env::temp_dir().join(format!("collaboard-decode-{}.png", uuid::Uuid::new_v4()));
+    let mut failures = Vec::new();
+
+    for converter in CONVERTERS.iter().filter(|converter| converter.heic || format == HeifFormat::Avif) {
+        match run_converter(converter, source, &output) {
+            Ok(()) => {
+                let png = std::fs::read(&output);
+                let _ = std::fs::remove_file(&output);
+                match png {
+                    Ok(png) => return Ok(png),
+                    Err(e) => failures.push(format!("{}: {}", converter.program, e)),
+                }
+            }
+            // 没有安装的工具直接跳过
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                let _ = std::fs::remove_file(&output);
+                failures.push(format!("{}: {}", converter.program, e));
+            }
+        }
+    }
+
+    if failures.is_empty() {
+        Err(FileManagerError::general_error(format!(
+            "No {} decoder is available; build with the heif-native feature or install libheif (heif-convert) or ImageMagick",
+            format.name()
+        )))
+    } else {
+        Err(FileManagerError::general_error(format!(
+            "Failed to decode {} image: {}", format.name(), failures.join("; ")
+        )))
+    }
+}
+
+/// 运行解码工具，工具未安装时返回 `NotFound`，退出码非零时返回包含错误输出的错误
+fn run_converter(converter: &Converter, source: &Path, output: &Path) -> std::io::Result<()> {
+    let mut command = std::process::Command::new(converter.program);
+    for arg in converter.args {
+        match *arg {
+            INPUT => command.arg(source),
+            OUTPUT => command.arg(output),
+            arg => command.arg(arg),
+        };
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let result = command.output()?;
+    if result.status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "exited with {}: {}",
+            result.status,
+            String::from_utf8_lossy(&result.stderr).trim()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detect_heif_formats() {
+        assert_eq!(detect_format(b"\0\0\0\x18ftypheic\0\0\0\0mif1heic"), Some(HeifFormat::Heic));
+        assert_eq!(detect_format(b"\0\0\0\x18ftypavif\0\0\0\0mif1avif"), Some(HeifFormat::Avif));
+        assert_eq!(detect_format(&[0xFF, 0xD8, 0xFF, 0xE0]), None);
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("IMG_0001");
+        std::fs::write(&path, b"\0\0\0\x18ftypheic\0\0\0\0mif1heic").unwrap();
+        assert_eq!(detect_file_format(&path).unwrap(), Some(HeifFormat::Heic));
+        assert!(detect_file_format(&temp_dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_native_decoders_match_features() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("broken.heic");
+        std::fs::write(&path, b"\0\0\0\x18ftypheic\0\0\0\0mif1heic").unwrap();
+
+        // 截断的文件在进程内解码失败，调用方改用外部工具
+        let decoded = decode_native(&path, HeifFormat::Heic);
+        assert_eq!(decoded.is_some(), cfg!(feature = "heif-native"));
+        assert!(decoded.is_none_or(|decoded| decoded.is_err()));
+        let decoded = decode_native(&path, HeifFormat::Avif);
+        assert_eq!(decoded.is_some(), cfg!(any(feature = "heif-native", feature = "avif-native")));
+    }
+
+    #[test]
+    fn test_avif_only_converters_are_skipped_for_heic() {
+        let heic: Vec<_> = CONVERTERS.iter().filter(|converter| converter.heic).map(|converter| converter.program).collect();
+        assert!(heic.contains(&"heif-convert"));
+        assert!(!heic.contains(&"avifdec"));
+        assert!(CONVERTERS.iter().all(|converter| converter.args.contains(&INPUT) && converter.args.contains(&OUTPUT)));
+    }
+}
@@ -0,0 +1,354 @@
+//! 错误信息本地化模块
+//!
+//! 命令返回的错误带有稳定的错误码，前端可以按错误码自行处理，
+//! 错误信息按当前语言设置生成：
+//! - [`FileManagerError`] 的错误码由 [`FileManagerError::code`] 提供
+//! - 命令参数校验失败使用 [`ValidationError`]
+//! - 语言设置保存在数据库中，启动时加载到进程内
+
+use crate::file_manager::error::FileManagerError;
+use crate::file_manager::read_only::ReadOnlyReason;
+use crate::file_manager::size_limits::FileCategory;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 保存语言设置的状态键
+pub const LOCALE_SETTINGS_KEY: &str = "locale";
+
+/// 错误信息语言
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en")]
+    En,
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+}
+
+/// 当前语言，命令返回错误时读取
+static CURRENT_LOCALE: AtomicU8 = AtomicU8::new(Locale::En as u8);
+
+/// 获取当前语言
+pub fn current_locale() -> Locale {
+    match CURRENT_LOCALE.load(Ordering::Relaxed) {
+        value if value == Locale::ZhCn as u8 => Locale::ZhCn,
+        _ => Locale::En,
+    }
+}
+
+/// 切换当前语言
+pub fn set_current_locale(locale: Locale) {
+    CURRENT_LOCALE.store(locale as u8, Ordering::Relaxed);
+}
+
+/// 按语言生成错误信息
+///
+/// 底层错误（数据库、IO 等）的详细信息保持原文，只翻译外层描述
+pub fn localize_error(error: &FileManagerError, locale: Locale) -> String {
+    if locale == Locale::En {
+        return error.to_string();
+    }
+
+    match error {
+        FileManagerError::Database(e) => format!("数据库错误: {}", e),
+        FileManagerError::FileSystem(e) => format!("文件系统错误: {}", e),
+        FileManagerError::FileNotFound { path } => format!("文件不存在: {}", path),
+        FileManagerError::DirectoryNotFound { path } => format!("目录不存在: {}", path),
+        FileManagerError::DirectoryNotEmpty { path, file_count, directory_count } => {
+            format!("目录非空: {}（{} 个文件，{} 个子目录）", path, file_count, directory_count)
+        }
+        FileManagerError::UnsupportedFileType { file_type } => format!("不支持的文件类型: {}", file_type),
+        FileManagerError::FileSizeExceeded { size, max_size, category } => {
+            let category = match category {
+                Some(FileCategory::Image) => "图片",
+                Some(FileCategory::Video) => "视频",
+                Some(FileCategory::Audio) => "音频",
+                Some(FileCategory::Document) => "文档",
+                Some(FileCategory::Archive) => "压缩包",
+                None => "",
+            };
+            format!("文件大小超出{}限制: {} 字节（上限 {} 字节）", category, size, max_size)
+        }
+        FileManagerError::PermissionDenied { operation } => format!("权限不足: {}", operation),
+        FileManagerError::Configuration { message } => format!("配置错误: {}", message),
+        FileManagerError::Serialization(e) => format!("序列化错误: {}", e),
+        FileManagerError::UuidParse(e) => format!("UUID 解析错误: {}", e),
+        FileManagerError::LibraryLocked => "资料库已加密，请输入密码".to_string(),
+        FileManagerError::IncorrectPassword => "资料库密码错误".to_string(),
+        FileManagerError::Quarantined { id, reason } => format!("文件已放入隔离区（{}）: {}", reason, id),
+        FileManagerError::Initializing => "服务正在初始化，请稍候".to_string(),
+        FileManagerError::ShuttingDown => "应用正在退出".to_string(),
+        FileManagerError::InitializationFailed { message } => format!("服务初始化失败: {}", message),
+        FileManagerError::ReadOnly(reason) => match reason {
+            ReadOnlyReason::Requested => "资料库处于只读模式".to_string(),
+            ReadOnlyReason::InUse { holder: Some(holder), .. } => format!("资料库正被 {} 使用，当前为只读模式", holder),
+            ReadOnlyReason::InUse { holder: None, .. } => "资料库正被其他进程使用，当前为只读模式".to_string(),
+        },
+        FileManagerError::LibraryOffline { path } => format!("资料库所在的网络共享无法访问（{}），请检查网络连接", path),
+        FileManagerError::Conflict { id, .. } => format!("{} 已被其他窗口修改，请刷新后重试", id),
+        FileManagerError::General { message } => format!("操作失败: {}", message),
+    }
+}
+
+/// 命令参数校验错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    EmptyFileData,
+    EmptyOriginalName,
+    EmptyDirectoryName,
+    InvalidDirectoryName,
+    EmptyFileId,
+    EmptyFileIds,
+    EmptyDirectoryId,
+    NoFilesToUpload,
+    TooManyFiles { max: usize },
+    EmptySearchQuery,
+    SearchQueryTooShort { min: usize },
+    EmptyFileName,
+    InvalidBoundingBox,
+    EmptyNewPath,
+    NoTagChanges,
+    MaxDistanceTooLarge { max: u32 },
+    EmptyFolderPath,
+    EmptyFolderId,
+    EmptyDestination,
+    InvalidSampleSize,
+    EmptyUndoId,
+    PasswordTooShort { min: usize },
+    InvalidRating { max: u8 },
+    EmptySidecarPath,
+    EmptyQuarantineId,
+    EmptyTrashId,
+    EmptyBackupPath,
+    EmptyLinkPath,
+    EmptyListingId,
+    ResponseLimitTooSmall { min: usize },
+    InvalidCursor,
+    InvalidPageSize { max: usize },
+    EmptyBatch,
+    TooManyOperations { max: usize },
+    InvalidMimeType,
+    EmptySessionId,
+    InvalidPreviewQuality,
+    InvalidPreviewDimension { min: u32, max: u32 },
+    ThumbnailCacheTooSmall { min: u64 },
+    NoMetadataFields,
+    EmptyWorkingSetId,
+    InvalidWorkingSetTtl { max: u32 },
+    EmptyTemplateId,
+    EmptyPresetId,
+    EmptyTemplateName,
+    EmptyBoardName,
+    EmptyBoardPath,
+    EmptyBoardId,
+    EmptyPlaylistId,
+    EmptyFrameId,
+    EmptyPlaylistName,
+    EmptySnapshotId,
+    MissingRevision,
+    EmptyMetadataPath,
+}
+
+impl ValidationError {
+    /// 稳定的错误码
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::EmptyFileData => "empty_file_data",
+            Self::EmptyOriginalName => "empty_original_name",
+            Self::EmptyDirectoryName => "empty_directory_name",
+            Self::InvalidDirectoryName => "invalid_directory_name",
+            Self::EmptyFileId => "empty_file_id",
+            Self::EmptyFileIds => "empty_file_ids",
+            Self::EmptyDirectoryId => "empty_directory_id",
+            Self::NoFilesToUpload => "no_files_to_upload",
+            Self::TooManyFiles { .. } => "too_many_files",
+            Self::EmptySearchQuery => "empty_search_query",
+            Self::SearchQueryTooShort { .. } => "search_query_too_short",
+            Self::EmptyFileName => "empty_file_name",
+            Self::InvalidBoundingBox => "invalid_bounding_box",
+            Self::EmptyNewPath => "empty_new_path",
+            Self::NoTagChanges => "no_tag_changes",
+            Self::MaxDistanceTooLarge { .. } => "max_distance_too_large",
+            Self::EmptyFolderPath => "empty_folder_path",
+            Self::EmptyFolderId => "empty_folder_id",
+            Self::EmptyDestination => "empty_destination",
+            Self::InvalidSampleSize => "invalid_sample_size",
+            Self::EmptyUndoId => "empty_undo_id",
+            Self::PasswordTooShort { .. } => "password_too_short",
+            Self::InvalidRating { .. } => "invalid_rating",
+            Self::EmptySidecarPath => "empty_sidecar_path",
+            Self::EmptyQuarantineId => "empty_quarantine_id",
+            Self::EmptyTrashId => "empty_trash_id",
+            Self::EmptyBackupPath => "empty_backup_path",
+            Self::EmptyLinkPath => "empty_link_path",
+            Self::EmptyListingId => "empty_listing_id",
+            Self::ResponseLimitTooSmall { .. } => "response_limit_too_small",
+            Self::InvalidCursor => "invalid_cursor",
+            Self::InvalidPageSize { .. } => "invalid_page_size",
+            Self::EmptyBatch => "empty_batch",
+            Self::TooManyOperations { .. } => "too_many_operations",
+            Self::InvalidMimeType => "invalid_mime_type",
+            Self::EmptySessionId => "empty_session_id",
+            Self::InvalidPreviewQuality => "invalid_preview_quality",
+            Self::InvalidPreviewDimension { .. } => "invalid_preview_dimension",
+            Self::ThumbnailCacheTooSmall { .. } => "thumbnail_cache_too_small",
+            Self::NoMetadataFields => "no_metadata_fields",
+            Self::EmptyWorkingSetId => "empty_working_set_id",
+            Self::InvalidWorkingSetTtl { .. } => "invalid_working_set_ttl",
+            Self::EmptyTemplateId => "empty_template_id",
+            Self::EmptyPresetId => "empty_preset_id",
+            Self::EmptyTemplateName => "empty_template_name",
+            Self::EmptyBoardName => "empty_board_name",
+            Self::EmptyBoardPath => "empty_board_path",
+            Self::EmptyBoardId => "empty_board_id",
+            Self::EmptyPlaylistId => "empty_playlist_id",
+            Self::EmptyFrameId => "empty_frame_id",
+            Self::EmptyPlaylistName => "empty_playlist_name",
+            Self::EmptySnapshotId => "empty_snapshot_id",
+            Self::MissingRevision => "missing_revision",
+            Self::EmptyMetadataPath => "empty_metadata_path",
+        }
+    }
+
+    /// 按语言生成错误信息
+    pub fn message(&self, locale: Locale) -> String {
+        match locale {
+            Locale::En => match self {
+                Self::EmptyFileData => "File data cannot be empty".to_string(),
+                Self::EmptyOriginalName => "Original name cannot be empty".to_string(),
+                Self::EmptyDirectoryName => "Directory name cannot be empty".to_string(),
+                Self::InvalidDirectoryName => "Directory name contains invalid characters".to_string(),
+                Self::EmptyFileId => "File ID cannot be empty".to_string(),
+                Self::EmptyFileIds => "File IDs cannot be empty".to_string(),
+                Self::EmptyDirectoryId => "Directory ID cannot be empty".to_string(),
+                Self::NoFilesToUpload => "No files to upload".to_string(),
+                Self::TooManyFiles { max } => format!("Too many files, maximum {} files per batch", max),
+                Self::EmptySearchQuery => "Search query cannot be empty".to_string(),
+                Self::SearchQueryTooShort { min } => format!("Search query must be at least {} characters", min),
+                Self::EmptyFileName => "Filename cannot be empty".to_string(),
+                Self::InvalidBoundingBox => "Invalid bounding box".to_string(),
+                Self::EmptyNewPath => "New path cannot be empty".to_string(),
+                Self::NoTagChanges => "No tags to add or remove".to_string(),
+                Self::MaxDistanceTooLarge { max } => format!("Max distance cannot exceed {}", max),
+                Self::EmptyFolderPath => "Folder path cannot be empty".to_string(),
+                Self::EmptyFolderId => "Folder ID cannot be empty".to_string(),
+                Self::EmptyDestination => "Destination directory cannot be empty".to_string(),
+                Self::InvalidSampleSize => "Sample size must be greater than 0".to_string(),
+                Self::EmptyUndoId => "Undo ID cannot be empty".to_string(),
+                Self::PasswordTooShort { min } => format!("Password must be at least {} characters", min),
+                Self::InvalidRating { max } => format!("Rating must be between 0 and {}", max),
+                Self::EmptySidecarPath => "Sidecar path cannot be empty".to_string(),
+                Self::EmptyQuarantineId => "Quarantine ID cannot be empty".to_string(),
+                Self::EmptyTrashId => "Trash ID cannot be empty".to_string(),
+                Self::EmptyBackupPath => "Backup path cannot be empty".to_string(),
+                Self::EmptyLinkPath => "Linked file path cannot be empty".to_string(),
+                Self::EmptyListingId => "Listing ID cannot be empty".to_string(),
+                Self::ResponseLimitTooSmall { min } => format!("Response size limit must be at least {} bytes", min),
+                Self::InvalidCursor => "Invalid or outdated page cursor".to_string(),
+                Self::InvalidPageSize { max } => format!("Page size must be between 1 and {}", max),
+                Self::EmptyBatch => "Batch contains no operations".to_string(),
+                Self::TooManyOperations { max } => format!("Too many operations, maximum {} per batch", max),
+                Self::InvalidMimeType => "MIME type must look like image/png or image/*".to_string(),
+                Self::EmptySessionId => "Edit session ID cannot be empty".to_string(),
+                Self::InvalidPreviewQuality => "Preview quality must be between 1 and 100".to_string(),
+                Self::InvalidPreviewDimension { min, max } => format!("Preview size must be between {} and {} pixels", min, max),
+                Self::ThumbnailCacheTooSmall { min } => format!("Thumbnail cache limit must be at least {} MB", min / (1024 * 1024)),
+                Self::NoMetadataFields => "Specify at least one of title, description or keywords".to_string(),
+                Self::EmptyWorkingSetId => "Working set ID cannot be empty".to_string(),
+                Self::InvalidWorkingSetTtl { max } => format!("Working set TTL must be between 1 and {} minutes", max),
+                Self::EmptyTemplateId => "Template ID cannot be empty".to_string(),
+                Self::EmptyPresetId => "Export preset ID cannot be empty".to_string(),
+                Self::EmptyTemplateName => "Template name cannot be empty".to_string(),
+                Self::EmptyBoardName => "Board name cannot be empty".to_string(),
+                Self::EmptyBoardPath => "Board file path cannot be empty".to_string(),
+                Self::EmptyBoardId => "Board ID cannot be empty".to_string(),
+                Self::EmptyPlaylistId => "Playlist ID cannot be empty".to_string(),
+                Self::EmptyFrameId => "Frame ID cannot be empty".to_string(),
+                Self::EmptyPlaylistName => "Playlist name cannot be empty".to_string(),
+                Self::EmptySnapshotId => "Snapshot ID cannot be empty".to_string(),
+                Self::MissingRevision => "The updated_at of the item being changed is required".to_string(),
+                Self::EmptyMetadataPath => "Metadata file path cannot be empty".to_string(),
+            },
+            Locale::ZhCn => match self {
+                Self::EmptyFileData => "文件数据不能为空".to_string(),
+                Self::EmptyOriginalName => "原始文件名不能为空".to_string(),
+                Self::EmptyDirectoryName => "目录名不能为空".to_string(),
+                Self::InvalidDirectoryName => "目录名包含非法字符".to_string(),
+                Self::EmptyFileId => "文件 ID 不能为空".to_string(),
+                Self::EmptyFileIds => "文件 ID 列表不能为空".to_string(),
+                Self::EmptyDirectoryId => "目录 ID 不能为空".to_string(),
+                Self::NoFilesToUpload => "没有要上传的文件".to_string(),
+                Self::TooManyFiles { max } => format!("文件过多，每批最多 {} 个", max),
+                Self::EmptySearchQuery => "搜索关键词不能为空".to_string(),
+                Self::SearchQueryTooShort { min } => format!("搜索关键词至少需要 {} 个字符", min),
+                Self::EmptyFileName => "文件名不能为空".to_string(),
+                Self::InvalidBoundingBox => "无效的地理范围".to_string(),
+                Self::EmptyNewPath => "新路径不能为空".to_string(),
+                Self::NoTagChanges => "没有要添加或移除的标签".to_string(),
+                Self::MaxDistanceTooLarge { max } => format!("最大距离不能超过 {}", max),
+                Self::EmptyFolderPath => "文件夹路径不能为空".to_string(),
+                Self::EmptyFolderId => "文件夹 ID 不能为空".to_string(),
+                Self::EmptyDestination => "目标目录不能为空".to_string(),
+                Self::InvalidSampleSize => "抽检数量必须大于 0".to_string(),
+                Self::EmptyUndoId => "撤销 ID 不能为空".to_string(),
+                Self::PasswordTooShort { min } => format!("密码至少需要 {} 个字符", min),
+                Self::InvalidRating { max } => format!("评分必须在 0 到 {} 之间", max),
+                Self::EmptySidecarPath => "附属文件路径不能为空".to_string(),
+                Self::EmptyQuarantineId => "隔离记录 ID 不能为空".to_string(),
+                Self::EmptyTrashId => "回收站记录 ID 不能为空".to_string(),
+                Self::EmptyBackupPath => "备份路径不能为空".to_string(),
+                Self::EmptyLinkPath => "链接文件路径不能为空".to_string(),
+                Self::EmptyListingId => "列表 ID 不能为空".to_string(),
+                Self::ResponseLimitTooSmall { min } => format!("响应大小上限不能小于 {} 字节", min),
+                Self::InvalidCursor => "分页游标无效".to_string(),
+                Self::InvalidPageSize { max } => format!("每页条目数必须在 1 到 {} 之间", max),
+                Self::EmptyBatch => "批量操作不能为空".to_string(),
+                Self::TooManyOperations { max } => format!("操作过多，每批最多 {} 项", max),
+                Self::InvalidMimeType => "MIME 类型格式应为 image/png 或 image/*".to_string(),
+                Self::EmptySessionId => "编辑会话 ID 不能为空".to_string(),
+                Self::InvalidPreviewQuality => "预览质量必须在 1 到 100 之间".to_string(),
+                Self::InvalidPreviewDimension { min, max } => format!("预览尺寸必须在 {} 到 {} 像素之间", min, max),
+                Self::ThumbnailCacheTooSmall { min } => format!("缩略图缓存上限不能小于 {} MB", min / (1024 * 1024)),
+                Self::NoMetadataFields => "至少需要指定标题、描述或关键词之一".to_string(),
+                Self::EmptyWorkingSetId => "工作集 ID 不能为空".to_string(),
+                Self::InvalidWorkingSetTtl { max } => format!("工作集有效期必须在 1 到 {} 分钟之间", max),
+                Self::EmptyTemplateId => "模板 ID 不能为空".to_string(),
+                Self::EmptyPresetId => "导出预设 ID 不能为空".to_string(),
+                Self::EmptyTemplateName => "模板名称不能为空".to_string(),
+                Self::EmptyBoardName => "画板名称不能为空".to_string(),
+                Self::EmptyBoardPath => "画板文件路径不能为空".to_string(),
+                Self::EmptyBoardId => "画板 ID 不能为空".to_string(),
+                Self::EmptyPlaylistId => "播放列表 ID 不能为空".to_string(),
+                Self::EmptyFrameId => "框架 ID 不能为空".to_string(),
+                Self::EmptyPlaylistName => "播放列表名称不能为空".to_string(),
+                Self::EmptySnapshotId => "快照 ID 不能为空".to_string(),
+                Self::MissingRevision => "需要提供被修改项的 updated_at".to_string(),
+                Self::EmptyMetadataPath => "元数据文件路径不能为空".to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localize_error() {
+        let error = FileManagerError::FileNotFound { path: "abc".to_string() };
+        assert_eq!(localize_error(&error, Locale::En), "File not found: abc");
+        assert_eq!(localize_error(&error, Locale::ZhCn), "文件不存在: abc");
+
+        let error = ValidationError::TooManyFiles { max: 50 };
+        assert_eq!(error.message(Locale::En), "Too many files, maximum 50 files per batch");
+        assert_eq!(error.message(Locale::ZhCn), "文件过多，每批最多 50 个");
+    }
+
+    #[test]
+    fn test_locale_serialization() {
+        assert_eq!(serde_json::to_string(&Locale::ZhCn).unwrap(), r#""zh-CN""#);
+        assert_eq!(serde_json::from_str::<Locale>(r#""en""#).unwrap(), Locale::En);
+    }
+}
@@ -0,0 +1,109 @@
+//! ID 生成策略模块
+//!
+//! 数据库记录的 ID 由可配置的生成器产生：
+//! - `uuid`：UUID v4（默认），与已有数据的格式一致
+//! - `ulid`：26 个字符的 ULID，按生成时间（毫秒）排序，新记录在索引中相邻，日志中也便于按时间查找
+//! - `nano_id`：21 个字符的 URL 安全随机 ID，适合放在分享链接中
+//! - 切换策略只影响之后创建的记录，已有的 ID 不变，不同格式的 ID 可以共存
+//! - 设置保存在数据库中，启动时加载
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// 保存 ID 生成策略的状态键
+pub const ID_STRATEGY_KEY: &str = "id_strategy";
+
+/// ULID 使用的 Crockford Base32 字母表
+const CROCKFORD_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Nano ID 使用的 URL 安全字母表
+const NANO_ID_ALPHABET: &[u8; 64] = b"_-0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Nano ID 的长度，随机部分约 126 位
+const NANO_ID_LEN: usize = 21;
+
+/// ID 生成策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum IdStrategy {
+    #[default]
+    Uuid,
+    Ulid,
+    NanoId,
+}
+
+impl IdStrategy {
+    /// 按策略生成新 ID
+    pub fn generate(self) -> String {
+        match self {
+            Self::Uuid => uuid::Uuid::new_v4().to_string(),
+            Self::Ulid => ulid(),
+            Self::NanoId => nano_id(),
+        }
+    }
+
+    /// 从 `as u8` 的值还原，未知值视为 UUID
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Ulid,
+            2 => Self::NanoId,
+            _ => Self::Uuid,
+        }
+    }
+}
+
+/// 生成 ULID：48 位毫秒时间戳加 80 位随机数，编码为 26 个字符
+pub fn ulid() -> String {
+    let timestamp = chrono::Utc::now().timestamp_millis().max(0) as u128 & ((1 << 48) - 1);
+    let random = rand::thread_rng().gen::<u128>() & ((1 << 80) - 1);
+    let value = (timestamp << 80) | random;
+
+    // 128 位按 5 位一组编码，第一个字符只包含最高的 3 位
+    (0..26)
+        .map(|index| CROCKFORD_ALPHABET[((value >> (125 - 5 * index)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+/// 生成 21 个字符的 Nano ID
+pub fn nano_id() -> String {
+    let mut rng = rand::thread_rng();
+    (0..NANO_ID_LEN)
+        .map(|_| NANO_ID_ALPHABET[rng.gen_range(0..NANO_ID_ALPHABET.len())] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_id_formats() {
+        assert!(uuid::Uuid::parse_str(&IdStrategy::Uuid.generate()).is_ok());
+
+        let ulid = IdStrategy::Ulid.generate();
+        assert_eq!(ulid.len(), 26);
+        assert!(ulid.bytes().all(|byte| CROCKFORD_ALPHABET.contains(&byte)));
+        // 最高位字符最多为 7，避免超出 128 位
+        assert!(ulid.as_bytes()[0] <= b'7');
+
+        let nano_id = IdStrategy::NanoId.generate();
+        assert_eq!(nano_id.len(), NANO_ID_LEN);
+        assert!(nano_id.bytes().all(|byte| NANO_ID_ALPHABET.contains(&byte)));
+
+        for strategy in [IdStrategy::Uuid, IdStrategy::Ulid, IdStrategy::NanoId] {
+            assert_eq!(IdStrategy::from_u8(strategy as u8), strategy);
+        }
+        assert_eq!(serde_json::to_string(&IdStrategy::NanoId).unwrap(), "\"nano_id\"");
+    }
+
+    #[test]
+    fn test_ulid_sorts_by_time() {
+        let earlier = ulid();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let later = ulid();
+        assert!(earlier < later);
+    }
+}
@@ -0,0 +1,179 @@
+//! 图像比较模块
+//!
+//! 比较两张图像并生成：
+//! - 差异热力图（PNG）
+//! - 像素差异计数
+//! - 结构相似度（SSIM）
+
+use crate::file_manager::error::{FileManagerError, Result};
+use image::{imageops::FilterType, DynamicImage, GrayImage, ImageFormat, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::io::Cursor;
+
+/// 像素差异阈值：任一通道差值超过该值才计为不同像素，用于忽略压缩噪声
+pub const DEFAULT_DIFF_THRESHOLD: u8 = 8;
+
+/// SSIM 计算窗口边长
+const SSIM_WINDOW: u32 = 8;
+
+/// 图像比较结果
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ImageComparison {
+    /// 比较所用的宽度（以第一张图像为准）
+    pub width: u32,
+    pub height: u32,
+    /// 第二张图像尺寸不同，已缩放到第一张图像的尺寸后再比较
+    pub resized: bool,
+    /// 差异超过阈值的像素数
+    pub different_pixels: u64,
+    pub total_pixels: u64,
+    /// 不同像素占比（0.0 ~ 1.0）
+    pub diff_ratio: f64,
+    /// 基于亮度的结构相似度（1.0 表示完全相同）
+    pub ssim: f64,
+    /// PNG 编码的差异热力图，差异越大颜色越亮（黑 -> 红 -> 黄）
+    pub heatmap: Vec<u8>,
+}
+
+/// 比较两张图像
+pub fn compare_images(a: &DynamicImage, b: &DynamicImage, threshold: u8) -> Result<ImageComparison> {
+    let (width, height) = (a.width(), a.height());
+    if width == 0 || height == 0 {
+        return Err(FileManagerError::general_error("Cannot compare empty images"));
+    }
+
+    let resized = (b.width(), b.height()) != (width, height);
+    let b = if resized {
+        b.resize_exact(width, height, FilterType::Triangle)
+    } else {
+        b.clone()
+    };
+
+    let a_rgba = a.to_rgba8();
+    let b_rgba = b.to_rgba8();
+
+    let mut heatmap = RgbaImage::new(width, height);
+    let mut different_pixels = 0u64;
+    for (x, y, pixel_a) in a_rgba.enumerate_pixels() {
+        let pixel_b = b_rgba.get_pixel(x, y);
+        let diff = pixel_a.0.iter()
+            .zip(pixel_b.0.iter())
+            .map(|(ca, cb)| ca.abs_diff(*cb))
+            .max()
+            .unwrap_or(0);
+
+        if diff > threshold {
+            different_pixels += 1;
+        }
+        heatmap.put_pixel(x, y, heat_color(diff));
+    }
+
+    let total_pixels = u64::from(width) * u64::from(height);
+    let ssim = ssim(&a.to_luma8(), &b.to_luma8());
+
+    let mut heatmap_png = Vec::new();
+    heatmap.write_to(&mut Cursor::new(&mut heatmap_png), ImageFormat::Png)
+        .map_err(|e| FileManagerError::general_error(format!("Failed to encode heatmap: {}", e)))?;
+
+    Ok(ImageComparison {
+        width,
+        height,
+        resized,
+        different_pixels,
+        total_pixels,
+        diff_ratio: different_pixels as f64 / total_pixels as f64,
+        ssim,
+        heatmap: heatmap_png,
+    })
+}
+
+/// 将差值映射为热力图颜色
+fn heat_color(diff: u8) -> Rgba<u8> {
+    let intensity = u16::from(diff) * 2;
+    let red = intensity.min(255) as u8;
+    let green = intensity.saturating_sub(255).min(255) as u8;
+    Rgba([red, green, 0, 255])
+}
+
+/// 计算两张同尺寸灰度图的平均 SSIM
+///
+/// 使用不重叠的 8x8 窗口，图像小于窗口时整张图作为一个窗口
+fn ssim(a: &GrayImage, b: &GrayImage) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let (width, height) = a.dimensions();
+    let window_w = SSIM_WINDOW.min(width);
+    let window_h = SSIM_WINDOW.min(height);
+
+    let mut total = 0.0;
+    let mut windows = 0u32;
+    for wy in (0..=height - window_h).step_by(window_h as usize) {
+        for wx in (0..=width - window_w).step_by(window_w as usize) {
+            let n = f64::from(window_w * window_h);
+            let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+
+            for y in wy..wy + window_h {
+                for x in wx..wx + window_w {
+                    let va = f64::from(a.get_pixel(x, y).0[0]);
+                    let vb = f64::from(b.get_pixel(x, y).0[0]);
+                    sum_a += va;
+                    sum_b += vb;
+                    sum_aa += va * va;
+                    sum_bb += vb * vb;
+                    sum_ab += va * vb;
+                }
+            }
+
+            let mean_a = sum_a / n;
+            let mean_b = sum_b / n;
+            let var_a = sum_aa / n - mean_a * mean_a;
+            let var_b = sum_bb / n - mean_b * mean_b;
+            let covariance = sum_ab / n - mean_a * mean_b;
+
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+            windows += 1;
+        }
+    }
+
+    total / f64::from(windows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_images() {
+        let image = DynamicImage::ImageRgb8(image::RgbImage::from_fn(32, 32, |x, y| {
+            image::Rgb([(x * 8) as u8, (y * 8) as u8, 128])
+        }));
+
+        let result = compare_images(&image, &image, DEFAULT_DIFF_THRESHOLD).unwrap();
+        assert_eq!(result.different_pixels, 0);
+        assert!((result.ssim - 1.0).abs() < 1e-9);
+        assert!(!result.resized);
+        assert!(!result.heatmap.is_empty());
+    }
+
+    #[test]
+    fn test_different_images() {
+        let a = DynamicImage::ImageRgb8(image::RgbImage::new(16, 16));
+        let mut b = image::RgbImage::new(16, 16);
+        for x in 0..8 {
+            for y in 0..16 {
+                b.put_pixel(x, y, image::Rgb([255, 255, 255]));
+            }
+        }
+        let b = DynamicImage::ImageRgb8(b);
+
+        let result = compare_images(&a, &b, DEFAULT_DIFF_THRESHOLD).unwrap();
+        assert_eq!(result.different_pixels, 128);
+        assert_eq!(result.total_pixels, 256);
+        assert!((result.diff_ratio - 0.5).abs() < 1e-9);
+        assert!(result.ssim < 0.9);
+    }
+}
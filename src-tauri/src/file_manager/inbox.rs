@@ -0,0 +1,46 @@
+//! 收件箱模块
+//!
+//! 快速截图和未指定目标目录的监视文件夹导入先进入收件箱，由用户逐个整理：
+//! - 移动到其他目录，目标目录中有同名文件时自动重命名
+//! - 添加或移除标签，文件留在收件箱中
+//! - 删除不需要的文件
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// 收件箱目录名，位于根目录下
+pub const INBOX_DIRECTORY_NAME: &str = "Inbox";
+
+/// 整理收件箱文件的操作
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriageAction {
+    /// 移动到指定目录
+    Move { directory_id: String },
+    /// 添加和移除标签
+    Tag {
+        #[serde(default)]
+        add_tags: Vec<String>,
+        #[serde(default)]
+        remove_tags: Vec<String>,
+    },
+    /// 删除文件
+    Delete,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triage_action_serialization() {
+        let action: TriageAction = serde_json::from_str(r#"{"type":"move","directory_id":"d1"}"#).unwrap();
+        assert_eq!(action, TriageAction::Move { directory_id: "d1".to_string() });
+
+        let action: TriageAction = serde_json::from_str(r#"{"type":"tag","add_tags":["todo"]}"#).unwrap();
+        assert_eq!(action, TriageAction::Tag { add_tags: vec!["todo".to_string()], remove_tags: vec![] });
+
+        assert_eq!(serde_json::to_string(&TriageAction::Delete).unwrap(), r#"{"type":"delete"}"#);
+    }
+}
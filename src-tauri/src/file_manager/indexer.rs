@@ -0,0 +1,151 @@
+//! 并行索引模块
+//!
+//! 批量导入和重新索引时，内容哈希、感知哈希和 EXIF 读取都是 CPU 或磁盘密集的同步操作：
+//! - 一批文件在专用的 rayon 线程池中并行计算，异步任务只等待结果，之后依次写入数据库
+//! - 线程数由配置文件 `[indexing] max_threads` 限制，0 表示使用全部核心；线程池在首次使用时创建
+//! - 低内存模式下改用最多 2 个线程的线程池，切换后新开始的批次生效
+//! - 每个文件的各项结果相互独立，某项失败只影响该项
+//! - 上传单个文件时同样经过线程池，但不计算感知哈希，避免解码大图拖慢上传
+
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::integrity::hash_file;
+use crate::file_manager::low_memory::{self, LOW_MEMORY_THREADS};
+use crate::file_manager::metadata::{read_location_from_file, GeoLocation};
+use crate::file_manager::similarity::perceptual_hash;
+use crate::file_manager::thumbnail::decode_image;
+use rayon::prelude::*;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+/// 批量处理时每批的文件数，每批计算完成后写入数据库
+pub const INDEX_BATCH_SIZE: usize = 64;
+
+static MAX_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+static POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+
+/// 低内存模式使用的线程池
+static LOW_MEMORY_POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+
+/// 设置索引线程数上限，0 表示使用全部核心；需在首次索引之前调用
+pub fn set_max_threads(threads: usize) {
+    MAX_THREADS.store(threads, Ordering::Relaxed);
+}
+
+/// 当前使用的线程池，低内存模式下使用线程数更少的线程池
+fn pool() -> &'static rayon::ThreadPool {
+    let threads = match MAX_THREADS.load(Ordering::Relaxed) {
+        0 => std::thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1),
+        threads => threads,
+    };
+    if low_memory::is_enabled() {
+        return LOW_MEMORY_POOL.get_or_init(|| build_pool(threads.min(LOW_MEMORY_THREADS), "indexer-low-memory"));
+    }
+    POOL.get_or_init(|| build_pool(threads, "indexer"))
+}
+
+fn build_pool(threads: usize, name: &'static str) -> rayon::ThreadPool {
+    tracing::info!("索引线程池: {} 个线程", threads);
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(move |index| format!("{}-{}", name, index))
+        .build()
+        .expect("failed to spawn indexing threads")
+}
+
+/// 在索引线程池中并行处理，结果与输入顺序一致
+pub async fn map_parallel<T, R, F>(items: Vec<T>, f: F) -> Result<Vec<R>>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> R + Send + Sync + 'static,
+{
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    pool().spawn(move || {
+        let _ = sender.send(items.into_par_iter().map(f).collect());
+    });
+    receiver.await.map_err(|_| FileManagerError::general_error("Indexing task failed"))
+}
+
+/// 一个文件的索引任务
+#[derive(Debug, Clone)]
+pub struct IndexJob {
+    pub file_id: String,
+    pub path: PathBuf,
+    /// 是否读取 EXIF 中的拍摄位置
+    pub read_location: bool,
+    /// 是否计算感知哈希
+    pub perceptual_hash: bool,
+}
+
+/// 一个文件的索引结果
+#[derive(Debug)]
+pub struct IndexOutput {
+    pub file_id: String,
+    pub content_hash: Result<String>,
+    pub location: Option<GeoLocation>,
+    /// 未要求计算时为 `None`
+    pub perceptual_hash: Option<Result<u64>>,
+}
+
+impl IndexOutput {
+    /// 整批任务失败时每个文件的结果
+    pub fn failed(file_id: String, error: &FileManagerError) -> Self {
+        Self {
+            file_id,
+            content_hash: Err(FileManagerError::general_error(error.to_string())),
+            location: None,
+            perceptual_hash: None,
+        }
+    }
+}
+
+fn compute(job: IndexJob) -> IndexOutput {
+    IndexOutput {
+        content_hash: hash_file(&job.path).map_err(FileManagerError::from),
+        location: if job.read_location { read_location_from_file(&job.path) } else { None },
+        perceptual_hash: job.perceptual_hash.then(|| decode_image(&job.path).map(|image| perceptual_hash(&image))),
+        file_id: job.file_id,
+    }
+}
+
+/// 并行计算一批文件的索引数据，结果与任务顺序一致
+pub async fn compute_batch(jobs: Vec<IndexJob>) -> Result<Vec<IndexOutput>> {
+    map_parallel(jobs, compute).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_compute_batch_keeps_order_and_isolates_failures() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut jobs = Vec::new();
+        for index in 0..10 {
+            let path = temp_dir.path().join(format!("{}.txt", index));
+            std::fs::write(&path, format!("file {}", index)).unwrap();
+            jobs.push(IndexJob { file_id: index.to_string(), path, read_location: false, perceptual_hash: false });
+        }
+        jobs.push(IndexJob {
+            file_id: "broken".to_string(),
+            path: temp_dir.path().join("missing.jpg"),
+            read_location: true,
+            perceptual_hash: true,
+        });
+
+        let outputs = compute_batch(jobs.clone()).await.unwrap();
+        let ids: Vec<_> = outputs.iter().map(|output| output.file_id.as_str()).collect();
+        let expected: Vec<_> = jobs.iter().map(|job| job.file_id.as_str()).collect();
+        assert_eq!(ids, expected);
+
+        assert_eq!(outputs[3].content_hash.as_ref().unwrap(), &hash_file(&jobs[3].path).unwrap());
+        assert!(outputs[3].perceptual_hash.is_none());
+
+        let broken = outputs.last().unwrap();
+        assert!(broken.content_hash.is_err());
+        assert!(broken.location.is_none());
+        assert!(matches!(broken.perceptual_hash, Some(Err(_))));
+    }
+}
@@ -0,0 +1,136 @@
+//! 完整性抽检模块
+//!
+//! 上传时记录存储文件的 SHA-256，之后定期重新计算一部分文件的哈希并与记录比对：
+//! - 默认关闭，需要用户在设置中开启
+//! - 每次按上次校验时间从旧到新轮换抽取固定数量的文件，长期下来覆盖整个库
+//! - 哈希不一致时写入错误日志并发送 `integrity-alert` 事件
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::events::{self, IntegrityAlert};
+use crate::file_manager::notifications::{self, JobNotification};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// 保存抽检设置的状态键
+pub const INTEGRITY_SETTINGS_KEY: &str = "integrity_sampling";
+
+/// 记录上次抽检时间的状态键
+pub const INTEGRITY_LAST_RUN_KEY: &str = "integrity_last_run";
+
+/// 两次定时抽检的最小间隔
+pub const SAMPLING_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 检查是否到期的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// 默认每次抽检的文件数
+pub const DEFAULT_SAMPLE_SIZE: usize = 200;
+
+/// 抽检设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct IntegritySettings {
+    pub enabled: bool,
+    /// 每次抽检的文件数
+    pub sample_size: usize,
+}
+
+impl Default for IntegritySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sample_size: DEFAULT_SAMPLE_SIZE,
+        }
+    }
+}
+
+/// 哈希不一致的文件
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct IntegrityMismatch {
+    pub file_id: String,
+    pub file_path: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// 抽检结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct IntegrityReport {
+    /// 校验的文件数
+    pub checked: usize,
+    /// 之前没有记录哈希、本次补记的文件数
+    pub recorded: usize,
+    /// 存储文件不存在的文件数（由缺失文件扫描处理）
+    pub missing: usize,
+    pub mismatched: Vec<IntegrityMismatch>,
+}
+
+/// 计算文件的 SHA-256（小写十六进制）
+pub fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 启动后台抽检任务
+///
+/// 每小时检查一次是否开启以及距上次抽检是否已满一天
+pub fn spawn_sampling_loop(app: AppHandle, state: FileManagerState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let Ok(service) = state.lock().await else {
+                continue;
+            };
+            // 只读模式下由写入的进程抽检
+            if service.is_read_only() {
+                continue;
+            }
+            match service.run_scheduled_integrity_sample().await {
+                Ok(Some(report)) if !report.mismatched.is_empty() => {
+                    notifications::notify(&app, &service, JobNotification::integrity_mismatch(&report)).await;
+                    events::emit(&app, &IntegrityAlert(report));
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("完整性抽检失败: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hash_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.txt");
+        std::fs::write(&path, b"abc").unwrap();
+
+        assert_eq!(
+            hash_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
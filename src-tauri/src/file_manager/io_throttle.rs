@@ -0,0 +1,131 @@
+//! 后台存储 I/O 限速模块
+//!
+//! 文件夹导入、监视文件夹同步和打包存储维护等后台任务可能持续大量读写，占满磁盘带宽后用户的其他程序会卡顿：
+//! - 配置文件 `[storage_io]` 中设置每秒最多读写的字节数（MB/s）和同时进行的写入数，0 表示不限制
+//! - 所有后台任务共用同一个限额，按已读写的字节数在下一次读写前等待，首个文件不等待
+//! - 后台任务逐个文件（或打包文件）获取服务锁，读写完成、释放服务锁后再按限额等待，
+//!   等待期间其他命令和用户直接上传、导出等前台操作不受影响；前台操作本身不限速
+//! - 写入数达到上限时后续写入排队等待
+
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+struct IoThrottle {
+    /// 每秒字节数上限，`None` 表示不限速
+    bytes_per_sec: Option<u64>,
+    /// 下一次读写可以开始的时间
+    next_free: Mutex<Instant>,
+    writes: Option<Semaphore>,
+}
+
+impl IoThrottle {
+    fn new(max_mb_per_sec: u64, max_concurrent_writes: usize) -> Self {
+        Self {
+            bytes_per_sec: (max_mb_per_sec > 0).then(|| max_mb_per_sec.saturating_mul(BYTES_PER_MB)),
+            next_free: Mutex::new(Instant::now()),
+            writes: (max_concurrent_writes > 0).then(|| Semaphore::new(max_concurrent_writes)),
+        }
+    }
+
+    /// 登记已读写的字节数，返回下一次读写前需要等待的时间
+    fn reserve(&self, bytes: u64) -> Option<Duration> {
+        let bytes_per_sec = self.bytes_per_sec?;
+        let mut next_free = self.next_free.lock().unwrap();
+        let now = Instant::now();
+        let start = (*next_free).max(now);
+        *next_free = start + Duration::from_secs_f64(bytes as f64 / bytes_per_sec as f64);
+        let wait = next_free.saturating_duration_since(now);
+        (!wait.is_zero()).then_some(wait)
+    }
+
+    async fn pace(&self, bytes: u64) {
+        if let Some(wait) = self.reserve(bytes) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    async fn write_permit(&self) -> Option<SemaphorePermit<'_>> {
+        // 信号量不会关闭
+        self.writes.as_ref()?.acquire().await.ok()
+    }
+}
+
+static THROTTLE: OnceLock<IoThrottle> = OnceLock::new();
+
+#[cfg(test)]
+tokio::task_local! {
+    /// 测试中代替全局限额，不影响同一进程中的其他测试
+    static SCOPED: &'static IoThrottle;
+}
+
+fn current() -> Option<&'static IoThrottle> {
+    #[cfg(test)]
+    if let Ok(throttle) = SCOPED.try_with(|throttle| *throttle) {
+        return Some(throttle);
+    }
+    THROTTLE.get()
+}
+
+/// 设置后台 I/O 限额，需在启动后台任务之前调用一次；0 表示不限制
+pub fn configure(max_mb_per_sec: u64, max_concurrent_writes: usize) {
+    if THROTTLE.set(IoThrottle::new(max_mb_per_sec, max_concurrent_writes)).is_err() {
+        tracing::warn!("后台 I/O 限额已设置，忽略重复设置");
+        return;
+    }
+    if max_mb_per_sec > 0 || max_concurrent_writes > 0 {
+        tracing::info!("后台 I/O 限额: {} MB/s, {} 个同时写入（0 为不限制）", max_mb_per_sec, max_concurrent_writes);
+    }
+}
+
+/// 后台任务读写 `bytes` 字节后调用，超出限额时等待；调用前须释放服务锁
+pub async fn throttle(bytes: u64) {
+    if let Some(throttle) = current() {
+        throttle.pace(bytes).await;
+    }
+}
+
+/// 获取后台写入许可，不限制同时写入数时返回 `None`；许可在写入完成后释放
+pub async fn write_permit() -> Option<SemaphorePermit<'static>> {
+    current()?.write_permit().await
+}
+
+/// 在 `future` 中使用给定的限额代替全局限额
+#[cfg(test)]
+pub async fn scoped<F: std::future::Future>(max_mb_per_sec: u64, max_concurrent_writes: usize, future: F) -> F::Output {
+    let throttle: &'static IoThrottle = Box::leak(Box::new(IoThrottle::new(max_mb_per_sec, max_concurrent_writes)));
+    SCOPED.scope(throttle, future).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_throttle_paces_transfers() {
+        // 首次读写不推迟，每次读写后按 1 MB/s 等待
+        let started = Instant::now();
+        scoped(1, 0, async {
+            throttle(BYTES_PER_MB / 4).await;
+            throttle(BYTES_PER_MB / 4).await;
+        }).await;
+        assert!(started.elapsed() >= Duration::from_millis(450), "{:?}", started.elapsed());
+
+        // 作用域外不受测试限额影响
+        assert!(SCOPED.try_with(|_| ()).is_err());
+        assert!(IoThrottle::new(0, 0).reserve(BYTES_PER_MB).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_write_permits_are_limited() {
+        let throttle = IoThrottle::new(0, 2);
+        let first = throttle.write_permit().await.unwrap();
+        let _second = throttle.write_permit().await.unwrap();
+        assert!(tokio::time::timeout(Duration::from_millis(50), throttle.write_permit()).await.is_err());
+        drop(first);
+        assert!(throttle.write_permit().await.is_some());
+        assert!(IoThrottle::new(0, 0).write_permit().await.is_none());
+    }
+}
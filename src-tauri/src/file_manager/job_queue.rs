@@ -0,0 +1,265 @@
+//! 任务队列模块
+//!
+//! 文件夹导入、导出、后台目录删除和打包维护在数据库中登记，应用崩溃后下次启动时恢复或回滚：
+//! - 任务开始时写入队列，执行中定期记录检查点，结束（包括失败和取消）时移除
+//! - 启动时队列中仍有的任务即为被中断的任务，先标记为中断，启动完成后在后台逐个处理
+//! - 文件夹导入重新执行，已导入的文件按内容哈希识别为已存在，不会重复导入
+//! - 导出删除中断时正在写入的目标文件，从检查点继续导出剩余的文件
+//! - 目录删除从剩余的文件继续，目录已不存在时直接移除任务
+//! - 打包维护回滚：删除中断时正在写入、尚未登记的打包文件，剩余工作由下次定期维护完成
+//! - 处理结果可通过 `get_recovered_jobs` 查询
+//! - 任务队列写入失败只记录警告，不影响任务本身
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::directory_delete::{self, DirectoryDeleteJob};
+use crate::file_manager::directory_import::{self, ImportDirectoryRequest};
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::events;
+use crate::file_manager::export::ExportRequest;
+use crate::file_manager::paths::long_path;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+/// 本次启动时处理的被中断任务
+static RECOVERED_JOBS: Mutex<Vec<RecoveredJob>> = Mutex::new(Vec::new());
+
+/// 队列中的任务，记录重新执行所需的参数
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QueuedJob {
+    /// 文件夹导入
+    DirectoryImport { request: ImportDirectoryRequest },
+    /// 导出文件，恢复后 `file_ids` 只包含尚未导出的文件
+    Export { request: ExportRequest },
+    /// 后台目录删除，`started_at` 之后上传或移入的文件不删除（旧版本登记的任务没有记录）
+    DirectoryDelete {
+        directory_id: String,
+        path: String,
+        #[serde(default)]
+        started_at: Option<DateTime<Local>>,
+    },
+    /// 打包维护
+    PackMaintenance,
+}
+
+impl QueuedJob {
+    /// 获取任务类型名称
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::DirectoryImport { .. } => "directory_import",
+            Self::Export { .. } => "export",
+            Self::DirectoryDelete { .. } => "directory_delete",
+            Self::PackMaintenance => "pack_maintenance",
+        }
+    }
+}
+
+/// 任务检查点
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct JobCheckpoint {
+    /// 已处理的条目数
+    #[serde(default)]
+    pub processed: usize,
+    /// 正在写入的文件，中断时可能不完整
+    #[serde(default)]
+    pub writing: Option<String>,
+}
+
+impl JobCheckpoint {
+    pub fn processed(processed: usize) -> Self {
+        Self { processed, writing: None }
+    }
+
+    pub fn writing(processed: usize, path: impl Into<String>) -> Self {
+        Self { processed, writing: Some(path.into()) }
+    }
+}
+
+/// 队列中被中断的任务
+#[derive(Debug, Clone)]
+pub struct PersistedJob {
+    pub id: String,
+    pub job: QueuedJob,
+    pub checkpoint: JobCheckpoint,
+    /// 最后一次记录检查点的时间
+    pub updated_at: DateTime<Local>,
+}
+
+/// 被中断任务的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryAction {
+    /// 从检查点继续执行；目录删除在后台继续，进度照常通过事件发送
+    Resumed,
+    /// 撤销了不完整的部分
+    RolledBack,
+    /// 已无需继续，如要删除的目录已不存在
+    Discarded,
+    /// 恢复失败，任务已从队列移除
+    Failed,
+}
+
+/// 被中断任务的处理结果
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RecoveredJob {
+    pub job_id: String,
+    pub job: QueuedJob,
+    /// 中断前的最后一个检查点
+    pub checkpoint: JobCheckpoint,
+    pub interrupted_at: DateTime<Local>,
+    pub action: RecoveryAction,
+    /// 恢复失败时的错误信息
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 删除中断时可能不完整的文件，文件不存在时视为已删除
+pub async fn remove_partial_file(path: &Path) -> Result<()> {
+    match tokio::fs::remove_file(long_path(path)).await {
+        Ok(()) => {
+            tracing::info!("已删除不完整的文件: {:?}", path);
+            Ok(())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 获取本次启动时处理的被中断任务，按处理顺序排列
+pub fn recovered_jobs() -> Vec<RecoveredJob> {
+    RECOVERED_JOBS.lock().unwrap().clone()
+}
+
+/// 在后台逐个恢复或回滚上次运行时被中断的任务
+///
+/// 只处理启动时标记为中断的任务，启动完成后新开始的任务不受影响；只读模式下不处理
+pub fn spawn_recovery(app: AppHandle, state: FileManagerState) {
+    tauri::async_runtime::spawn(async move {
+        let jobs = match state.lock().await {
+            Ok(service) if !service.is_read_only() => service.get_interrupted_jobs().await,
+            _ => return,
+        };
+        let jobs = match jobs {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                tracing::warn!("读取被中断的任务失败: {}", e);
+                return;
+            }
+        };
+
+        for job in jobs {
+            tracing::info!("恢复被中断的任务: {} {}, 已处理 {}", job.job.kind(), job.id, job.checkpoint.processed);
+            let result = recover(&app, &state, &job).await;
+            let (action, error) = match result {
+                Ok(action) => (action, None),
+                Err(e) => {
+                    tracing::warn!("被中断的任务恢复失败: {} {}, {}", job.job.kind(), job.id, e);
+                    if let Ok(service) = state.lock().await {
+                        service.finish_job(&job.id).await;
+                    }
+                    (RecoveryAction::Failed, Some(e.to_string()))
+                }
+            };
+
+            RECOVERED_JOBS.lock().unwrap().push(RecoveredJob {
+                job_id: job.id,
+                job: job.job,
+                checkpoint: job.checkpoint,
+                interrupted_at: job.updated_at,
+                action,
+                error,
+            });
+        }
+    });
+}
+
+/// 按任务类型恢复或回滚一个任务
+async fn recover(app: &AppHandle, state: &FileManagerState, job: &PersistedJob) -> Result<RecoveryAction> {
+    let service = state.lock().await?;
+    service.ensure_writable()?;
+
+    match &job.job {
+        QueuedJob::DirectoryImport { request } => {
+            // 导入逐个文件获取服务锁
+            drop(service);
+            directory_import::resume_directory_import(state, &job.id, request.clone()).await?;
+            Ok(RecoveryAction::Resumed)
+        }
+        QueuedJob::Export { request } => {
+            service.resume_export(&job.id, request.clone(), &job.checkpoint, |progress| {
+                events::emit(app, &progress);
+            }).await?;
+            Ok(RecoveryAction::Resumed)
+        }
+        QueuedJob::DirectoryDelete { directory_id, path, started_at } => {
+            let counts = match service.prepare_directory_delete(directory_id, true).await {
+                Ok(counts) => counts,
+                Err(FileManagerError::DirectoryNotFound { .. }) => {
+                    service.finish_job(&job.id).await;
+                    return Ok(RecoveryAction::Discarded);
+                }
+                Err(e) => return Err(e),
+            };
+            // 中断期间目录被移动或改名时不再删除新位置上的内容
+            if counts.path != *path {
+                return Err(FileManagerError::general_error(format!(
+                    "Directory was moved during deletion: {} -> {}", path, counts.path
+                )));
+            }
+            drop(service);
+
+            // 删除任务在后台继续，结束时自行移出队列；旧版本登记的任务以最后一次检查点为起始时间
+            directory_delete::spawn(app.clone(), state.clone(), DirectoryDeleteJob {
+                job_id: job.id.clone(),
+                directory_id: counts.directory_id,
+                path: counts.path,
+                total: counts.file_count + counts.directory_count + 1,
+                dry_run: false,
+                changes: Vec::new(),
+            }, started_at.unwrap_or(job.updated_at));
+            Ok(RecoveryAction::Resumed)
+        }
+        QueuedJob::PackMaintenance => {
+            service.roll_back_pack_maintenance(&job.id, &job.checkpoint).await?;
+            Ok(RecoveryAction::RolledBack)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_serialization_roundtrip() {
+        let job = QueuedJob::DirectoryDelete {
+            directory_id: "dir-1".to_string(),
+            path: "/photos".to_string(),
+            started_at: Some(Local::now()),
+        };
+        let json = serde_json::to_string(&job).unwrap();
+        assert!(json.contains("\"type\":\"directory_delete\""));
+        assert_eq!(serde_json::from_str::<QueuedJob>(&json).unwrap().kind(), "directory_delete");
+
+        // 旧版本登记的目录删除任务没有起始时间
+        let json = r#"{"type":"directory_delete","directory_id":"dir-1","path":"/photos"}"#;
+        assert!(matches!(
+            serde_json::from_str::<QueuedJob>(json).unwrap(),
+            QueuedJob::DirectoryDelete { started_at: None, .. }
+        ));
+
+        // 刚登记的任务检查点为空对象
+        assert_eq!(serde_json::from_str::<JobCheckpoint>("{}").unwrap(), JobCheckpoint::default());
+        let checkpoint = JobCheckpoint::writing(3, "/export/a.jpg");
+        let json = serde_json::to_string(&checkpoint).unwrap();
+        assert_eq!(serde_json::from_str::<JobCheckpoint>(&json).unwrap(), checkpoint);
+    }
+}
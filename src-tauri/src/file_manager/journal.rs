@@ -0,0 +1,91 @@
+//! 操作日志模块
+//!
+//! 为跨越数据库和文件系统的多步操作提供预写日志：
+//! - 执行前记录操作意图
+//! - 执行完成后标记完成
+//! - 执行失败时撤销已完成的步骤并把条目标记为失败，启动时不重放
+//! - 启动时重放未完成（进程在执行中途退出）的操作，使其最终完成
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// 日志中记录的操作
+///
+/// 每个操作的重放都必须是幂等的：无论上次在哪一步中断，
+/// 重新执行都会得到与一次成功执行相同的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalOperation {
+    /// 删除文件：删除存储文件，再删除数据库记录
+    DeleteFile {
+        file_id: String,
+        file_path: String,
+        /// 链接文件只删除数据库记录，保留库外的原始文件
+        #[serde(default)]
+        linked: bool,
+    },
+    /// 删除目录：删除存储目录，再删除数据库记录
+    DeleteDirectory {
+        directory_id: String,
+        path: String,
+    },
+    /// 移动目录：重命名存储目录，再更新目录及其子目录的路径
+    MoveDirectory {
+        directory_id: String,
+        new_parent_id: Option<String>,
+        old_path: String,
+        new_path: String,
+    },
+}
+
+impl JournalOperation {
+    /// 获取操作类型名称
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::DeleteFile { .. } => "delete_file",
+            Self::DeleteDirectory { .. } => "delete_directory",
+            Self::MoveDirectory { .. } => "move_directory",
+        }
+    }
+}
+
+/// 未完成的日志条目
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub operation: JournalOperation,
+    pub created_at: String,
+}
+
+/// 日志重放结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct JournalRecoveryReport {
+    /// 重放完成的操作数
+    pub completed: usize,
+    /// 重放失败、保留在日志中的操作数
+    pub failed: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation_serialization_roundtrip() {
+        let operation = JournalOperation::MoveDirectory {
+            directory_id: "dir-1".to_string(),
+            new_parent_id: None,
+            old_path: "/a/b".to_string(),
+            new_path: "/b".to_string(),
+        };
+
+        let json = serde_json::to_string(&operation).unwrap();
+        assert!(json.contains("\"type\":\"move_directory\""));
+
+        let parsed: JournalOperation = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, operation);
+        assert_eq!(parsed.kind(), "move_directory");
+    }
+}
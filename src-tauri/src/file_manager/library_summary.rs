@@ -0,0 +1,213 @@
+//! 资料库概要模块
+//!
+//! 窗口标题、托盘提示和崩溃日志只需要资料库的简要信息，一次调用即可取得：
+//! - 资料库名称、存储路径、文件总数、待处理任务数和同步状态
+//! - 计数来自一次聚合查询，待处理文件数使用部分索引，大型资料库上同样很快
+//! - 待处理任务包括等待索引的文件、延迟删除队列中的文件和运行中的目录删除任务
+//! - 同步状态为可写、只读（其他进程正在写入）或离线（网络共享断开）；离线时不查询数据库，沿用上次的计数
+//! - 后台定期重新计算，变化时发送 `library-summary` 事件并更新主窗口标题
+//! - 最近一次的概要保存在进程内，程序崩溃时写入日志
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::events;
+use crate::file_manager::i18n::{self, Locale};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// 保存资料库名称的状态键
+pub const LIBRARY_NAME_KEY: &str = "library_name";
+
+/// 未设置名称时使用的资料库名称
+pub const DEFAULT_LIBRARY_NAME: &str = "Collaboard";
+
+/// 资料库名称的长度上限
+pub const MAX_LIBRARY_NAME_LEN: usize = 100;
+
+/// 后台重新计算概要的间隔
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 最近一次计算的概要
+static LATEST: Mutex<Option<LibrarySummary>> = Mutex::new(None);
+
+/// 资料库的同步状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncStatus {
+    /// 本进程持有写入锁，修改直接写入资料库
+    #[default]
+    Writable,
+    /// 其他进程正在写入或手动进入只读模式
+    ReadOnly,
+    /// 资料库所在的网络共享无法访问
+    Offline,
+}
+
+/// 资料库概要，`library-summary` 事件负载
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LibrarySummary {
+    pub workspace_name: String,
+    pub storage_path: String,
+    /// 文件总数，不含目录
+    pub total_items: i64,
+    pub pending_jobs: i64,
+    pub sync_status: SyncStatus,
+}
+
+impl LibrarySummary {
+    /// 主窗口标题，非可写状态时附加状态说明
+    pub fn window_title(&self, locale: Locale) -> String {
+        match self.status_label(locale) {
+            Some(status) => format!("{} ({}) - {}", self.workspace_name, status, DEFAULT_LIBRARY_NAME),
+            None => format!("{} - {}", self.workspace_name, DEFAULT_LIBRARY_NAME),
+        }
+    }
+
+    /// 托盘提示等多行说明
+    pub fn tooltip(&self, locale: Locale) -> String {
+        let mut lines = vec![self.workspace_name.clone()];
+        lines.push(match locale {
+            Locale::En => format!("{} items", self.total_items),
+            Locale::ZhCn => format!("{} 个文件", self.total_items),
+        });
+        if self.pending_jobs > 0 {
+            lines.push(match locale {
+                Locale::En => format!("{} pending jobs", self.pending_jobs),
+                Locale::ZhCn => format!("{} 个待处理任务", self.pending_jobs),
+            });
+        }
+        if let Some(status) = self.status_label(locale) {
+            lines.push(status.to_string());
+        }
+        lines.join("\n")
+    }
+
+    fn status_label(&self, locale: Locale) -> Option<&'static str> {
+        match (self.sync_status, locale) {
+            (SyncStatus::Writable, _) => None,
+            (SyncStatus::ReadOnly, Locale::En) => Some("read-only"),
+            (SyncStatus::ReadOnly, Locale::ZhCn) => Some("只读"),
+            (SyncStatus::Offline, Locale::En) => Some("offline"),
+            (SyncStatus::Offline, Locale::ZhCn) => Some("离线"),
+        }
+    }
+}
+
+/// 规范化资料库名称：去除首尾空白，为空时返回 `None` 表示恢复默认名称
+pub fn normalize_library_name(name: &str) -> Result<Option<String>> {
+    let name = name.trim();
+    if name.chars().count() > MAX_LIBRARY_NAME_LEN {
+        return Err(FileManagerError::general_error(format!(
+            "Library name cannot be longer than {} characters", MAX_LIBRARY_NAME_LEN
+        )));
+    }
+    Ok((!name.is_empty()).then(|| name.to_string()))
+}
+
+/// 最近一次计算的概要，尚未计算时为空
+pub fn latest() -> Option<LibrarySummary> {
+    LATEST.lock().unwrap().clone()
+}
+
+/// 计算资料库概要；离线时不访问数据库，沿用上次的计数
+pub async fn load(state: &FileManagerState) -> Result<LibrarySummary> {
+    if state.offline().is_some() {
+        if let Some(summary) = latest() {
+            return Ok(record(LibrarySummary { sync_status: SyncStatus::Offline, ..summary }));
+        }
+    }
+
+    let service = state.lock().await?;
+    let sync_status = if state.offline().is_some() {
+        SyncStatus::Offline
+    } else if service.is_read_only() {
+        SyncStatus::ReadOnly
+    } else {
+        SyncStatus::Writable
+    };
+    Ok(record(service.get_library_summary(sync_status).await?))
+}
+
+fn record(summary: LibrarySummary) -> LibrarySummary {
+    *LATEST.lock().unwrap() = Some(summary.clone());
+    summary
+}
+
+/// 在默认的 panic 处理之前记录最近一次的资料库概要，便于从崩溃日志了解资料库的规模和状态
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // panic 可能发生在持有锁时，不等待锁
+        if let Ok(latest) = LATEST.try_lock() {
+            if let Some(summary) = latest.as_ref() {
+                tracing::error!("程序崩溃，资料库概要: {:?}", summary);
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+/// 定期重新计算概要，变化时通知前端并更新主窗口标题
+pub fn spawn_refresh_loop(app: AppHandle, state: FileManagerState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        let mut last: Option<LibrarySummary> = None;
+        loop {
+            interval.tick().await;
+
+            let summary = match load(&state).await {
+                Ok(summary) => summary,
+                Err(e) => {
+                    tracing::debug!("资料库概要计算失败: {}", e);
+                    continue;
+                }
+            };
+            if last.as_ref() == Some(&summary) {
+                continue;
+            }
+
+            if let Some(window) = app.get_webview_window("main") {
+                if let Err(e) = window.set_title(&summary.window_title(i18n::current_locale())) {
+                    tracing::warn!("更新窗口标题失败: {}", e);
+                }
+            }
+            events::emit(&app, &summary);
+            last = Some(summary);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_text() {
+        let mut summary = LibrarySummary {
+            workspace_name: "Design Team".to_string(),
+            storage_path: "/srv/library".to_string(),
+            total_items: 1200,
+            pending_jobs: 0,
+            sync_status: SyncStatus::Writable,
+        };
+        assert_eq!(summary.window_title(Locale::En), "Design Team - Collaboard");
+        assert_eq!(summary.tooltip(Locale::En), "Design Team\n1200 items");
+
+        summary.pending_jobs = 3;
+        summary.sync_status = SyncStatus::Offline;
+        assert_eq!(summary.window_title(Locale::ZhCn), "Design Team (离线) - Collaboard");
+        assert_eq!(summary.tooltip(Locale::En), "Design Team\n1200 items\n3 pending jobs\noffline");
+    }
+
+    #[test]
+    fn test_normalize_library_name() {
+        assert_eq!(normalize_library_name("  Team  ").unwrap().as_deref(), Some("Team"));
+        assert_eq!(normalize_library_name("   ").unwrap(), None);
+        assert!(normalize_library_name(&"x".repeat(MAX_LIBRARY_NAME_LEN + 1)).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! 低内存模式模块
+//!
+//! 与 DCC 软件等占用大量内存的程序同时运行时，可在设置中切换到低内存配置，运行时切换立即生效：
+//! - 缩略图缓存上限不超过 [`LOW_MEMORY_THUMBNAIL_CACHE_BYTES`]，开启时立即淘汰超出的部分；
+//!   暂存的分页列表最多 [`LOW_MEMORY_CACHED_LISTINGS`] 个
+//! - 不预取相邻文件的缩略图
+//! - 预览强制通过 asset 协议流式加载，`read_file_content` 只读取不超过 [`LOW_MEMORY_INLINE_READ_SIZE`] 的文件
+//! - 索引、哈希计算和画板排列等并行任务改用最多 [`LOW_MEMORY_THREADS`] 个线程的线程池，已开始的任务不受影响
+//! - 关闭后恢复保存的缩略图缓存上限和原有线程池
+//! - 设置保存在数据库中，启动时加载
+
+use crate::file_manager::thumbnail::ThumbnailCacheSettings;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 保存低内存模式设置的状态键
+pub const LOW_MEMORY_SETTINGS_KEY: &str = "low_memory";
+
+/// 低内存模式下的缩略图缓存上限
+pub const LOW_MEMORY_THUMBNAIL_CACHE_BYTES: u64 = 256 * 1024 * 1024;
+
+/// 低内存模式下最多暂存的分页列表数
+pub const LOW_MEMORY_CACHED_LISTINGS: usize = 2;
+
+/// 低内存模式下通过 IPC 直接读取的文件大小上限，更大的文件需通过 asset 协议加载
+pub const LOW_MEMORY_INLINE_READ_SIZE: u64 = 1024 * 1024;
+
+/// 低内存模式下并行任务的线程数
+pub const LOW_MEMORY_THREADS: usize = 2;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// 低内存模式设置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LowMemorySettings {
+    pub enabled: bool,
+}
+
+/// 是否处于低内存模式
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// 开启或关闭低内存模式
+pub fn set_enabled(enabled: bool) {
+    if ENABLED.swap(enabled, Ordering::Relaxed) != enabled {
+        tracing::info!("低内存模式已{}", if enabled { "开启" } else { "关闭" });
+    }
+}
+
+/// 按当前模式限制缩略图缓存上限
+pub fn cap_thumbnail_cache(settings: ThumbnailCacheSettings) -> ThumbnailCacheSettings {
+    capped(settings, is_enabled())
+}
+
+fn capped(settings: ThumbnailCacheSettings, enabled: bool) -> ThumbnailCacheSettings {
+    if !enabled {
+        return settings;
+    }
+    ThumbnailCacheSettings {
+        max_bytes: settings.max_bytes.min(LOW_MEMORY_THUMBNAIL_CACHE_BYTES),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thumbnail_cache_is_capped_only_when_enabled() {
+        let large = ThumbnailCacheSettings { max_bytes: 4 * 1024 * 1024 * 1024 };
+        assert_eq!(capped(large, false), large);
+        assert_eq!(capped(large, true).max_bytes, LOW_MEMORY_THUMBNAIL_CACHE_BYTES);
+
+        // 保存的上限本来就更小时保持不变
+        let small = ThumbnailCacheSettings { max_bytes: 128 * 1024 * 1024 };
+        assert_eq!(capped(small, true), small);
+
+        assert_eq!(serde_json::from_str::<LowMemorySettings>("{\"enabled\":true}").unwrap(), LowMemorySettings { enabled: true });
+    }
+}
@@ -0,0 +1,203 @@
+//! 内存存储模块
+//!
+//! 为 [`FileSystemService`](super::filesystem::FileSystemService) 提供内存中的存储实现，只在测试中编译：
+//! - 文件内容和目录保存在内存中，不写入磁盘
+//! - 路径仍以存储根目录为前缀，数据库中保存的路径与磁盘存储完全一致
+//! - 错误类型与磁盘操作相同（如父目录不存在时返回 `NotFound`），重试和错误处理逻辑不需要区分
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Entries {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    directories: BTreeSet<PathBuf>,
+}
+
+impl Entries {
+    fn check_parent(&self, path: &Path) -> Result<()> {
+        match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() && !self.directories.contains(parent) => {
+                Err(not_found(parent))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 内存存储，克隆后共享同一份内容
+#[derive(Clone, Default)]
+pub struct MemoryStorage {
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// 创建目录及其所有上级目录
+    pub fn create_dir_all(&self, path: &Path) -> Result<()> {
+        let mut entries = self.entries();
+        for ancestor in path.ancestors().filter(|ancestor| !ancestor.as_os_str().is_empty()) {
+            if entries.files.contains_key(ancestor) {
+                return Err(Error::new(ErrorKind::AlreadyExists, format!("not a directory: {}", ancestor.display())));
+            }
+        }
+        for ancestor in path.ancestors().filter(|ancestor| !ancestor.as_os_str().is_empty()) {
+            entries.directories.insert(ancestor.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// 写入文件，已存在时覆盖
+    pub fn write(&self, path: &Path, data: &[u8]) -> Result<()> {
+        let mut entries = self.entries();
+        entries.check_parent(path)?;
+        if entries.directories.contains(path) {
+            return Err(Error::new(ErrorKind::AlreadyExists, format!("is a directory: {}", path.display())));
+        }
+        entries.files.insert(path.to_path_buf(), data.to_vec());
+        Ok(())
+    }
+
+    /// 在文件末尾追加内容
+    pub fn append(&self, path: &Path, data: &[u8]) -> Result<()> {
+        self.entries()
+            .files
+            .get_mut(path)
+            .ok_or_else(|| not_found(path))?
+            .extend_from_slice(data);
+        Ok(())
+    }
+
+    pub fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        self.entries().files.get(path).cloned().ok_or_else(|| not_found(path))
+    }
+
+    pub fn len(&self, path: &Path) -> Result<u64> {
+        self.entries().files.get(path).map(|data| data.len() as u64).ok_or_else(|| not_found(path))
+    }
+
+    pub fn remove_file(&self, path: &Path) -> Result<()> {
+        self.entries().files.remove(path).map(|_| ()).ok_or_else(|| not_found(path))
+    }
+
+    /// 删除目录及其中的所有内容
+    pub fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        let mut entries = self.entries();
+        if !entries.directories.contains(path) {
+            return Err(not_found(path));
+        }
+        entries.files.retain(|file, _| !file.starts_with(path));
+        entries.directories.retain(|directory| !directory.starts_with(path));
+        Ok(())
+    }
+
+    /// 重命名文件或目录，目录中的内容随之移动
+    pub fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let mut entries = self.entries();
+        entries.check_parent(to)?;
+
+        if let Some(data) = entries.files.remove(from) {
+            entries.files.insert(to.to_path_buf(), data);
+            return Ok(());
+        }
+        if !entries.directories.contains(from) {
+            return Err(not_found(from));
+        }
+
+        let moved = |path: &Path| match path.strip_prefix(from).expect("path under renamed directory") {
+            rest if rest.as_os_str().is_empty() => to.to_path_buf(),
+            rest => to.join(rest),
+        };
+        let files: Vec<_> = entries.files.keys().filter(|file| file.starts_with(from)).cloned().collect();
+        for file in files {
+            let data = entries.files.remove(&file).expect("listed file");
+            entries.files.insert(moved(&file), data);
+        }
+        let directories: Vec<_> = entries.directories.iter().filter(|directory| directory.starts_with(from)).cloned().collect();
+        for directory in directories {
+            entries.directories.remove(&directory);
+            entries.directories.insert(moved(&directory));
+        }
+        Ok(())
+    }
+
+    /// 复制文件，返回复制的字节数
+    pub fn copy(&self, from: &Path, to: &Path) -> Result<u64> {
+        let data = self.read(from)?;
+        self.write(to, &data)?;
+        Ok(data.len() as u64)
+    }
+
+    pub fn is_file(&self, path: &Path) -> bool {
+        self.entries().files.contains_key(path)
+    }
+
+    pub fn is_dir(&self, path: &Path) -> bool {
+        self.entries().directories.contains(path)
+    }
+
+    /// 列出目录中直接包含的文件，不包含子目录中的文件
+    pub fn list_files(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let entries = self.entries();
+        if !entries.directories.contains(path) {
+            return Err(not_found(path));
+        }
+        Ok(entries.files.keys().filter(|file| file.parent() == Some(path)).cloned().collect())
+    }
+
+    /// 存储中的文件数量
+    pub fn file_count(&self) -> usize {
+        self.entries().files.len()
+    }
+}
+
+fn not_found(path: &Path) -> Error {
+    Error::new(ErrorKind::NotFound, format!("no such file or directory: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_requires_parent_directory() {
+        let storage = MemoryStorage::new();
+        let path = Path::new("/library/a/file.txt");
+
+        assert_eq!(storage.write(path, b"data").unwrap_err().kind(), ErrorKind::NotFound);
+        storage.create_dir_all(Path::new("/library/a")).unwrap();
+        storage.write(path, b"data").unwrap();
+        storage.append(path, b"!").unwrap();
+
+        assert_eq!(storage.read(path).unwrap(), b"data!");
+        assert_eq!(storage.len(path).unwrap(), 5);
+        assert!(storage.is_dir(Path::new("/library")));
+        assert!(storage.create_dir_all(&path.join("child")).is_err());
+    }
+
+    #[test]
+    fn test_rename_and_remove_directory() {
+        let storage = MemoryStorage::new();
+        storage.create_dir_all(Path::new("/library/a/b")).unwrap();
+        storage.write(Path::new("/library/a/top.txt"), b"top").unwrap();
+        storage.write(Path::new("/library/a/b/nested.txt"), b"nested").unwrap();
+
+        storage.rename(Path::new("/library/a"), Path::new("/library/c")).unwrap();
+        assert!(!storage.is_dir(Path::new("/library/a")));
+        assert_eq!(storage.read(Path::new("/library/c/b/nested.txt")).unwrap(), b"nested");
+        assert_eq!(storage.list_files(Path::new("/library/c")).unwrap(), vec![PathBuf::from("/library/c/top.txt")]);
+
+        storage.remove_dir_all(Path::new("/library/c")).unwrap();
+        assert_eq!(storage.file_count(), 0);
+        assert!(storage.is_dir(Path::new("/library")));
+    }
+}
@@ -0,0 +1,203 @@
+//! 图像元数据模块
+//!
+//! 从上传的文件内容中读取 EXIF 元数据：
+//! - 拍摄时间（用于按拍摄日期整理导入的照片）
+//! - GPS 坐标（用于按地理位置筛选照片）
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::io::{BufRead, BufReader, Cursor, Seek};
+use std::path::Path;
+
+/// 地理位置（WGS84 十进制度数，南纬和西经为负数）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct GeoLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// 经纬度范围
+///
+/// `west` 大于 `east` 时表示范围跨越 180° 经线
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct GeoBoundingBox {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+impl GeoBoundingBox {
+    /// 检查范围是否有效
+    pub fn is_valid(&self) -> bool {
+        (-90.0..=90.0).contains(&self.south)
+            && (-90.0..=90.0).contains(&self.north)
+            && (-180.0..=180.0).contains(&self.west)
+            && (-180.0..=180.0).contains(&self.east)
+            && self.south <= self.north
+    }
+
+    /// 范围是否跨越 180° 经线
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.west > self.east
+    }
+}
+
+/// 读取 EXIF 数据，文件不含 EXIF 或格式不支持时返回 `None`
+fn read_exif<R: BufRead + Seek>(reader: &mut R) -> Option<exif::Exif> {
+    exif::Reader::new()
+        .read_from_container(reader)
+        .ok()
+}
+
+/// 读取 EXIF 中的日期时间字段
+fn read_datetime(exif: &exif::Exif, tag: exif::Tag) -> Option<NaiveDateTime> {
+    let field = exif.get_field(tag, exif::In::PRIMARY)?;
+    let exif::Value::Ascii(ref values) = field.value else {
+        return None;
+    };
+    let datetime = exif::DateTime::from_ascii(values.first()?).ok()?;
+
+    NaiveDate::from_ymd_opt(datetime.year.into(), datetime.month.into(), datetime.day.into())?
+        .and_hms_opt(datetime.hour.into(), datetime.minute.into(), datetime.second.into())
+}
+
+/// 读取照片的拍摄时间
+///
+/// 优先使用 `DateTimeOriginal`，其次是 `DateTimeDigitized` 和 `DateTime`
+pub fn read_capture_date(data: &[u8]) -> Option<NaiveDateTime> {
+    let exif = read_exif(&mut Cursor::new(data))?;
+    [exif::Tag::DateTimeOriginal, exif::Tag::DateTimeDigitized, exif::Tag::DateTime]
+        .into_iter()
+        .find_map(|tag| read_datetime(&exif, tag))
+}
+
+/// 确定用于整理的拍摄日期
+///
+/// 依次使用 EXIF 拍摄时间、文件修改时间（毫秒时间戳，由前端提供），最后是当前时间
+pub fn resolve_capture_date(data: &[u8], last_modified_ms: Option<i64>) -> NaiveDate {
+    if let Some(captured) = read_capture_date(data) {
+        return captured.date();
+    }
+
+    last_modified_ms
+        .and_then(DateTime::from_timestamp_millis)
+        .map(|modified| modified.with_timezone(&Local).date_naive())
+        .unwrap_or_else(|| Local::now().date_naive())
+}
+
+/// 读取 GPS 坐标分量（度、分、秒）并根据方向参考转换为带符号的十进制度数
+fn read_coordinate(exif: &exif::Exif, tag: exif::Tag, ref_tag: exif::Tag, negative_ref: u8) -> Option<f64> {
+    let field = exif.get_field(tag, exif::In::PRIMARY)?;
+    let exif::Value::Rational(ref parts) = field.value else {
+        return None;
+    };
+    let [degrees, minutes, seconds] = parts.get(..3)? else {
+        return None;
+    };
+    let value = degrees.to_f64() + minutes.to_f64() / 60.0 + seconds.to_f64() / 3600.0;
+
+    let reference = exif.get_field(ref_tag, exif::In::PRIMARY)?;
+    let exif::Value::Ascii(ref values) = reference.value else {
+        return None;
+    };
+    let sign = match values.first()?.first()? {
+        direction if direction.eq_ignore_ascii_case(&negative_ref) => -1.0,
+        _ => 1.0,
+    };
+
+    value.is_finite().then_some(sign * value)
+}
+
+/// 从 EXIF 数据中读取 GPS 坐标
+fn location_from_exif(exif: &exif::Exif) -> Option<GeoLocation> {
+    let latitude = read_coordinate(exif, exif::Tag::GPSLatitude, exif::Tag::GPSLatitudeRef, b'S')?;
+    let longitude = read_coordinate(exif, exif::Tag::GPSLongitude, exif::Tag::GPSLongitudeRef, b'W')?;
+
+    ((-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude))
+        .then_some(GeoLocation { latitude, longitude })
+}
+
+/// 读取存储文件的 GPS 坐标
+///
+/// 只读取 EXIF 所在的部分，不会将整个文件载入内存
+pub fn read_location_from_file(path: &Path) -> Option<GeoLocation> {
+    let file = std::fs::File::open(path).ok()?;
+    location_from_exif(&read_exif(&mut BufReader::new(file))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 生成只包含给定字段的 TIFF 格式 EXIF 数据
+    pub(crate) fn exif_with_fields(fields: &[exif::Field]) -> Vec<u8> {
+        let mut writer = exif::experimental::Writer::new();
+        for field in fields {
+            writer.push_field(field);
+        }
+        let mut buffer = Cursor::new(Vec::new());
+        writer.write(&mut buffer, false).unwrap();
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn test_capture_date_from_exif() {
+        let field = exif::Field {
+            tag: exif::Tag::DateTimeOriginal,
+            ifd_num: exif::In::PRIMARY,
+            value: exif::Value::Ascii(vec![b"2021:07:04 10:20:30".to_vec()]),
+        };
+        let data = exif_with_fields(&[field]);
+
+        let captured = read_capture_date(&data).unwrap();
+        assert_eq!(captured.to_string(), "2021-07-04 10:20:30");
+        assert_eq!(resolve_capture_date(&data, Some(0)).to_string(), "2021-07-04");
+    }
+
+    #[test]
+    fn test_read_gps_location() {
+        let rational = |num, denom| exif::Rational { num, denom };
+        let fields = [
+            exif::Field {
+                tag: exif::Tag::GPSLatitudeRef,
+                ifd_num: exif::In::PRIMARY,
+                value: exif::Value::Ascii(vec![b"S".to_vec()]),
+            },
+            exif::Field {
+                tag: exif::Tag::GPSLatitude,
+                ifd_num: exif::In::PRIMARY,
+                value: exif::Value::Rational(vec![rational(33, 1), rational(51, 1), rational(36, 1)]),
+            },
+            exif::Field {
+                tag: exif::Tag::GPSLongitudeRef,
+                ifd_num: exif::In::PRIMARY,
+                value: exif::Value::Ascii(vec![b"E".to_vec()]),
+            },
+            exif::Field {
+                tag: exif::Tag::GPSLongitude,
+                ifd_num: exif::In::PRIMARY,
+                value: exif::Value::Rational(vec![rational(151, 1), rational(12, 1), rational(3060, 100)]),
+            },
+        ];
+
+        let exif = read_exif(&mut Cursor::new(exif_with_fields(&fields))).unwrap();
+        let location = location_from_exif(&exif).unwrap();
+        assert!((location.latitude + 33.86).abs() < 1e-9);
+        assert!((location.longitude - 151.2085).abs() < 1e-9);
+
+        let exif = read_exif(&mut Cursor::new(exif_with_fields(&fields[..2]))).unwrap();
+        assert!(location_from_exif(&exif).is_none());
+    }
+
+    #[test]
+    fn test_capture_date_falls_back_to_mtime() {
+        // 2020-01-15T12:00:00Z
+        let modified = 1_579_089_600_000;
+        let date = resolve_capture_date(b"no exif here", Some(modified));
+        assert_eq!(date.format("%Y-%m").to_string(), "2020-01");
+    }
+}
@@ -0,0 +1,187 @@
+//! 元数据导入导出模块
+//!
+//! 多个团队通过 NAS 共享同一批文件、但各自维护资料库时，只交换文件的元数据：
+//! - 导出文件为 JSON，只包含标签、评分、备注和标题，不包含文件内容；没有任何元数据的文件不导出
+//! - 每个文件同时记录内容哈希、所在目录路径和原始文件名，导入时可选择匹配方式
+//! - 按内容哈希匹配时，同一内容的多个副本都会更新；尚未计算哈希的文件无法按哈希匹配
+//! - 按路径匹配时比较目录路径和原始文件名，文件名按 NFC 规范化后比较
+//! - 应用方式与附属文件相同：标签追加到已有标签，评分、备注和标题在导出文件中有值时覆盖
+
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::paths::normalize_name;
+use crate::file_manager::sidecar::SidecarMetadata;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::HashMap;
+
+/// 导出文件格式版本
+pub const METADATA_EXPORT_VERSION: u32 = 1;
+
+/// 导出文件中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MetadataEntry {
+    /// 内容的 SHA-256，导出时尚未计算则为空
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    pub directory_path: String,
+    pub original_name: String,
+    #[serde(flatten)]
+    pub metadata: SidecarMetadata,
+}
+
+impl MetadataEntry {
+    /// 文件在库中的显示路径，用于报告未匹配的文件
+    pub fn display_path(&self) -> String {
+        format!("{}/{}", self.directory_path.trim_end_matches('/'), self.original_name)
+    }
+}
+
+/// 元数据导出文件
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MetadataExport {
+    pub version: u32,
+    pub exported_at: DateTime<Local>,
+    pub files: Vec<MetadataEntry>,
+}
+
+impl MetadataExport {
+    pub fn new(files: Vec<MetadataEntry>) -> Self {
+        Self { version: METADATA_EXPORT_VERSION, exported_at: Local::now(), files }
+    }
+
+    /// 解析导出文件，拒绝更新版本写入的文件
+    pub fn parse(content: &str) -> Result<Self> {
+        let export: Self = serde_json::from_str(content)?;
+        if export.version > METADATA_EXPORT_VERSION {
+            return Err(FileManagerError::general_error(format!(
+                "Unsupported metadata export version: {}",
+                export.version
+            )));
+        }
+        Ok(export)
+    }
+}
+
+/// 元数据是否为空，空元数据的文件不导出
+pub fn is_empty(metadata: &SidecarMetadata) -> bool {
+    metadata.tags.is_empty() && metadata.rating.is_none() && metadata.notes.is_none() && metadata.title.is_none()
+}
+
+/// 元数据导出结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MetadataExportReport {
+    pub path: String,
+    /// 写入导出文件的文件数
+    pub exported: usize,
+}
+
+/// 元数据导入结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MetadataImportReport {
+    /// 导出文件中的文件数
+    pub total: usize,
+    /// 在当前库中找到对应文件的条目数
+    pub matched: usize,
+    /// 更新的库中文件数，按哈希匹配到多个副本时每个副本都计入
+    pub updated_files: usize,
+    /// 未匹配的条目，为其在来源库中的路径
+    pub unmatched: Vec<String>,
+}
+
+/// 按选定的匹配方式查找库中文件
+pub struct MetadataMatcher {
+    match_by_hash: bool,
+    index: HashMap<String, Vec<String>>,
+}
+
+impl MetadataMatcher {
+    /// 由库中文件建立索引，`files` 为 (文件 ID, 内容哈希, 目录路径, 原始文件名)
+    pub fn new<'a>(
+        match_by_hash: bool,
+        files: impl IntoIterator<Item = (&'a str, Option<&'a str>, &'a str, &'a str)>,
+    ) -> Self {
+        let mut index: HashMap<String, Vec<String>> = HashMap::new();
+        for (id, content_hash, directory_path, original_name) in files {
+            let key = if match_by_hash {
+                match content_hash {
+                    Some(hash) => hash.to_ascii_lowercase(),
+                    None => continue,
+                }
+            } else {
+                path_key(directory_path, original_name)
+            };
+            index.entry(key).or_default().push(id.to_string());
+        }
+        Self { match_by_hash, index }
+    }
+
+    /// 与条目对应的库中文件 ID
+    pub fn find(&self, entry: &MetadataEntry) -> &[String] {
+        let key = if self.match_by_hash {
+            match &entry.content_hash {
+                Some(hash) => hash.to_ascii_lowercase(),
+                None => return &[],
+            }
+        } else {
+            path_key(&entry.directory_path, &entry.original_name)
+        };
+        self.index.get(&key).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+fn path_key(directory_path: &str, original_name: &str) -> String {
+    format!("{}/{}", normalize_name(directory_path.trim_end_matches('/')), normalize_name(original_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(content_hash: Option<&str>, directory_path: &str, original_name: &str) -> MetadataEntry {
+        MetadataEntry {
+            content_hash: content_hash.map(str::to_string),
+            directory_path: directory_path.to_string(),
+            original_name: original_name.to_string(),
+            metadata: SidecarMetadata { tags: vec!["shared".to_string()], ..Default::default() },
+        }
+    }
+
+    #[test]
+    fn test_match_by_hash_and_path() {
+        let files = [
+            ("f1", Some("ABC"), "/photos", "sea.jpg"),
+            ("f2", Some("abc"), "/copies", "sea copy.jpg"),
+            ("f3", None, "/photos", "cafe\u{301}.jpg"),
+        ];
+
+        let by_hash = MetadataMatcher::new(true, files);
+        assert_eq!(by_hash.find(&entry(Some("abc"), "/elsewhere", "x.jpg")), ["f1", "f2"]);
+        assert!(by_hash.find(&entry(None, "/photos", "sea.jpg")).is_empty());
+
+        let by_path = MetadataMatcher::new(false, files);
+        assert_eq!(by_path.find(&entry(Some("def"), "/photos/", "sea.jpg")), ["f1"]);
+        // 另一台机器上以 NFC 保存的同名文件
+        assert_eq!(by_path.find(&entry(None, "/photos", "caf\u{e9}.jpg")), ["f3"]);
+        assert!(by_path.find(&entry(None, "/copies", "sea.jpg")).is_empty());
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let export = MetadataExport::new(vec![entry(Some("abc"), "/photos", "sea.jpg")]);
+        let json = serde_json::to_string(&export).unwrap();
+        // 元数据字段与附属 JSON 文件相同，直接位于条目中
+        assert!(json.contains("\"tags\":[\"shared\"]"));
+
+        let parsed = MetadataExport::parse(&json).unwrap();
+        assert_eq!(parsed.files, export.files);
+        assert_eq!(parsed.files[0].display_path(), "/photos/sea.jpg");
+
+        let newer = json.replacen("\"version\":1", "\"version\":99", 1);
+        assert!(MetadataExport::parse(&newer).is_err());
+    }
+}
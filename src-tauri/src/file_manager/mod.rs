@@ -4,14 +4,158 @@
 //! - 数据库操作服务
 //! - 文件系统操作服务  
 //! - 核心业务逻辑服务
-//! - Tauri 命令接口
+//! - 多步操作的预写日志
+//! - 导入、导出和维护任务的持久化队列，崩溃后启动时恢复或回滚
+//! - 重命名、移动和删除的乐观并发检查
+//! - 可整体撤销的批量文件操作
+//! - 跨平台路径处理
+//! - 按文件内容签名识别 MIME 类型
+//! - 开发构建中的 SQL 语句日志
+//! - 文件被临时占用和数据库忙时的退避重试
+//! - 被占用存储文件的延迟删除队列
+//! - 可取消的后台目录删除任务
+//! - 破坏性命令的试运行，列出将要进行的修改
+//! - 存储布局和布局迁移
+//! - 不常用文件的归档存储
+//! - 大量小文件的打包存储
+//! - 多分辨率缩略图，可选在 GPU 上缩小大图
+//! - HEIC / AVIF 图像预览：可选以 libheif / dav1d 在进程内解码，否则借助系统解码工具
+//! - 图像差异比较
+//! - 相似图像搜索
+//! - EXIF 元数据读取
+//! - 从 URL 导入文件
+//! - 屏幕截图保存到库中
+//! - 全局快捷键快速截图
+//! - 收件箱整理
+//! - 跨目录收集文件的临时工作集
+//! - 全屏评审模式使用的播放列表
+//! - 按预定义结构创建目录的目录模板
+//! - 文件使用统计和推荐素材
+//! - 按文件类别的大小上限
+//! - 被拒绝上传的隔离区
+//! - 按保留天数和总大小自动清理的回收站
+//! - 搜索历史和输入建议
+//! - 命令面板的快速切换
+//! - 共享资料库的写入锁和只读模式
+//! - 网络共享上的资料库：关闭内存映射、延长重试窗口和断开检测
+//! - 窗口标题和崩溃日志使用的资料库概要
+//! - 超大列表响应的分页保护
+//! - 列表和搜索的游标分页
+//! - 错误信息本地化
+//! - 可切换的记录 ID 生成策略（UUID、ULID、Nano ID）
+//! - 启动阶段耗时统计
+//! - 发往前端的强类型事件总线
+//! - 可选的匿名使用统计
+//! - 后台任务完成的系统通知
+//! - 按文件类型配置的外部打开应用
+//! - 在外部应用中编辑并导入为新版本
+//! - 监视文件夹自动导入
+//! - 资源管理器右键菜单和深度链接导入
+//! - 本地文件夹一次性导入，可先试运行查看导入计划
+//! - 导出文件到外部目录
+//! - 按预设缩放和转换格式的图片导出
+//! - 导出图片时叠加的文字或图片水印
+//! - 交付文件的校验清单（SFV、sha256sum、JSON）
+//! - 画板文件（.cboard）的导出和打开
+//! - 画板素材按网格、颜色、宽高比或标签分组自动排列
+//! - 画板上的框架和分组，包含其中的元素并一起移动
+//! - 资料库完整和增量备份
+//! - 目录数据库的定时快照和回滚
+//! - XMP / JSON 附属元数据文件
+//! - 图像内嵌的 XMP 元数据
+//! - 资料库之间按内容哈希交换标签等元数据
+//! - 库增长和存储占用统计
+//! - 合并操作、任务、同步和外部编辑的最近动态流
+//! - 存储文件完整性抽检
+//! - 批量索引时并行计算内容哈希、感知哈希和读取 EXIF
+//! - 后台导入、同步和打包维护的磁盘读写限速
+//! - 与大型软件同时运行时的低内存模式：限制缓存、停止预取、流式预览、减少并行线程
+//! - 重复文件查找和清理
+//! - 内存数据库、内存存储和测试资料库构建工具（仅测试）
+//! - 上传流程的故障注入（仅测试）
+//! - Tauri 命令接口，构建时由 ts-rs 生成参数和返回值的 TypeScript 定义，检查前端手写的类型是否一致
 //! - 错误处理和配置管理
 
+pub mod activity;
+pub mod analytics;
+pub mod archive;
+pub mod backup;
+pub mod batch;
+pub mod board_bundle;
+pub mod board_frames;
+pub mod board_layout;
+pub mod catalog_snapshots;
+pub mod checksum_manifest;
 pub mod config;
 pub mod database;
+pub mod deferred_delete;
+pub mod directory_delete;
+pub mod directory_import;
+pub mod directory_templates;
+pub mod dry_run;
+pub mod duplicates;
+pub mod embedded_metadata;
 pub mod error;
+pub mod events;
+pub mod export;
+pub mod export_presets;
+pub mod external_edit;
+#[cfg(test)]
+pub mod faults;
 pub mod filesystem;
+#[cfg(feature = "gpu-thumbnails")]
+pub mod gpu_resize;
+pub mod heif;
+pub mod i18n;
+pub mod ids;
+pub mod inbox;
+pub mod indexer;
+pub mod image_compare;
+pub mod integrity;
+pub mod io_throttle;
+pub mod job_queue;
+pub mod journal;
+pub mod library_summary;
+pub mod low_memory;
+#[cfg(test)]
+pub mod memory_storage;
+pub mod metadata;
+pub mod metadata_transfer;
+pub mod network_share;
+pub mod notifications;
+pub mod open_with;
+pub mod pack_store;
+pub mod pagination;
+pub mod paths;
+pub mod playlists;
+pub mod quarantine;
+pub mod query_log;
+pub mod quick_capture;
+pub mod quick_find;
+pub mod read_only;
+pub mod response_guard;
+pub mod retry;
+pub mod revision;
+pub mod screenshot;
 pub mod service;
+pub mod shell_integration;
+pub mod search;
+pub mod sidecar;
+pub mod startup;
+pub mod storage_layout;
+pub mod telemetry;
+#[cfg(test)]
+pub mod testing;
+pub mod similarity;
+pub mod size_limits;
+pub mod sniffer;
+pub mod thumbnail;
+pub mod trash;
+pub mod url_import;
+pub mod usage;
+pub mod watched_folders;
+pub mod watermark;
+pub mod working_sets;
 pub mod commands;
 
 // 重新导出主要类型和函数
@@ -31,4 +175,8 @@ pub async fn initialize() -> Result<FileManagerService> {
     let fs_service = FileSystemService::new(&config.storage_path)?;
     
     Ok(FileManagerService::new(db_service, fs_service))
-}
\ No newline at end of file
+}
+
+/// 初始化测试用的文件管理系统，数据库在内存中，存储文件写入临时目录
+#[cfg(test)]
+pub use testing::initialize_for_tests;
\ No newline at end of file
@@ -0,0 +1,311 @@
+//! 网络共享资料库模块
+//!
+//! 资料库位于 SMB / NFS 共享上时，适合本地磁盘的设置容易导致数据库损坏或操作频繁失败：
+//! - 配置文件 `[library] network_share` 为 `auto`（默认）时按资料库所在的文件系统自动判断，
+//!   `enabled` / `disabled` 强制开启或关闭；Windows 上映射为驱动器号的共享无法自动识别，需要显式开启
+//! - 开启后数据库关闭内存映射，使用回滚日志而非 WAL（WAL 依赖共享内存，网络文件系统上不可靠），
+//!   并以 `synchronous = FULL` 写入
+//! - 文件操作和数据库忙等待的重试窗口延长，见 [`retry`](crate::file_manager::retry)
+//! - 后台定期检查数据库目录和存储目录能否访问，共享断开时进入离线状态：发送 `library-offline` 事件，
+//!   `get_backend_status` 返回离线信息，修改资料库的命令返回 `library_offline` 错误码；
+//!   恢复访问后发送 `library-online` 事件
+//! - 检查在阻塞线程中执行并设置超时，共享无响应时同样视为离线
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::events::{self, LibraryOnline};
+use crate::file_manager::retry;
+use chrono::{DateTime, Local};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// 默认的离线检查间隔（秒）
+pub const DEFAULT_CHECK_INTERVAL_SECS: u64 = 15;
+
+/// 单次访问检查的超时
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 视为网络共享的文件系统类型
+const NETWORK_FILESYSTEMS: &[&str] = &[
+    "cifs", "smb3", "smbfs", "nfs", "nfs4", "afpfs", "webdav", "davfs", "fuse.sshfs", "9p",
+];
+
+static MODE: AtomicU8 = AtomicU8::new(NetworkShareMode::Auto as u8);
+static CHECK_INTERVAL_SECS: AtomicU64 = AtomicU64::new(DEFAULT_CHECK_INTERVAL_SECS);
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// 离线检查的目录，启用网络共享模式时记录
+static CHECKED_PATHS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// 网络共享模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum NetworkShareMode {
+    /// 按资料库所在的文件系统自动判断
+    #[default]
+    Auto,
+    Enabled,
+    Disabled,
+}
+
+impl NetworkShareMode {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Enabled,
+            2 => Self::Disabled,
+            _ => Self::Auto,
+        }
+    }
+}
+
+/// 资料库离线信息，`library-offline` 事件负载
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LibraryOffline {
+    pub since: DateTime<Local>,
+    /// 无法访问的目录
+    pub path: String,
+    pub error: String,
+}
+
+/// 设置网络共享模式和离线检查间隔，需在初始化文件管理服务之前调用
+pub fn configure(mode: NetworkShareMode, check_interval_secs: u64) {
+    MODE.store(mode as u8, Ordering::Relaxed);
+    CHECK_INTERVAL_SECS.store(check_interval_secs.max(1), Ordering::Relaxed);
+}
+
+/// 按设置和资料库位置决定是否启用网络共享模式，需在打开数据库之前调用
+///
+/// `paths` 为数据库所在目录和存储目录，其中任一位于网络共享上即启用
+pub fn activate(paths: &[&Path]) -> bool {
+    let active = match NetworkShareMode::from_u8(MODE.load(Ordering::Relaxed)) {
+        NetworkShareMode::Enabled => true,
+        NetworkShareMode::Disabled => false,
+        NetworkShareMode::Auto => paths.iter().any(|path| is_network_path(path)),
+    };
+
+    ACTIVE.store(active, Ordering::Relaxed);
+    retry::set_extended_window(active);
+    *CHECKED_PATHS.lock().unwrap() = if active { paths.iter().map(|path| path.to_path_buf()).collect() } else { Vec::new() };
+    if active {
+        tracing::info!("资料库位于网络共享上，启用网络共享模式: {:?}", paths);
+    }
+    active
+}
+
+/// 是否已启用网络共享模式
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// 为数据库连接应用网络共享设置，未启用时不做修改；加密数据库需在设置密码之后调用
+pub fn configure_connection(connection: &Connection) -> rusqlite::Result<()> {
+    if !is_active() {
+        return Ok(());
+    }
+
+    connection.pragma_update(None, "mmap_size", 0)?;
+    connection.pragma_update_and_check(None, "journal_mode", "DELETE", |row| row.get::<_, String>(0))?;
+    connection.pragma_update(None, "synchronous", "FULL")?;
+    Ok(())
+}
+
+/// 判断路径是否位于网络共享上，无法判断时返回 `false`
+pub fn is_network_path(path: &Path) -> bool {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    #[cfg(windows)]
+    {
+        let path = path.to_string_lossy();
+        // `\\server\share` 或规范化后的 `\\?\UNC\server\share`
+        path.starts_with(r"\\?\UNC\") || (path.starts_with(r"\\") && !path.starts_with(r"\\?\"))
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string("/proc/self/mounts")
+            .map(|mounts| filesystem_type(parse_proc_mounts(&mounts), &path).is_some_and(|filesystem| is_network_filesystem(&filesystem)))
+            .unwrap_or(false)
+    }
+
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        std::process::Command::new("mount")
+            .output()
+            .map(|output| {
+                let mounts = String::from_utf8_lossy(&output.stdout);
+                filesystem_type(parse_bsd_mounts(&mounts), &path).is_some_and(|filesystem| is_network_filesystem(&filesystem))
+            })
+            .unwrap_or(false)
+    }
+
+    #[cfg(not(any(windows, unix)))]
+    {
+        false
+    }
+}
+
+#[cfg_attr(windows, allow(dead_code))]
+fn is_network_filesystem(filesystem: &str) -> bool {
+    NETWORK_FILESYSTEMS.contains(&filesystem.to_ascii_lowercase().as_str())
+}
+
+/// 包含 `path` 的最深挂载点的文件系统类型
+#[cfg_attr(windows, allow(dead_code))]
+fn filesystem_type(mounts: Vec<(PathBuf, String)>, path: &Path) -> Option<String> {
+    mounts.into_iter()
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.components().count())
+        .map(|(_, filesystem)| filesystem)
+}
+
+/// 解析 `/proc/self/mounts`：`设备 挂载点 类型 选项 0 0`，挂载点中的空格等字符写为八进制转义
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_proc_mounts(mounts: &str) -> Vec<(PathBuf, String)> {
+    mounts.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = unescape_mount_point(fields.nth(1)?);
+            Some((PathBuf::from(mount_point), fields.next()?.to_string()))
+        })
+        .collect()
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn unescape_mount_point(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(index) = rest.find('\\') {
+        result.push_str(&rest[..index]);
+        let escaped = rest.get(index + 1..index + 4).and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match escaped {
+            Some(byte) => {
+                result.push(byte as char);
+                rest = &rest[index + 4..];
+            }
+            None => {
+                result.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// 解析 BSD / macOS 的 `mount` 输出：`//user@server/share on /Volumes/share (smbfs, nodev, ...)`
+#[cfg_attr(any(windows, target_os = "linux"), allow(dead_code))]
+fn parse_bsd_mounts(mounts: &str) -> Vec<(PathBuf, String)> {
+    mounts.lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" on ")?;
+            let (mount_point, options) = rest.rsplit_once(" (")?;
+            let filesystem = options.split([',', ')']).next()?.trim();
+            Some((PathBuf::from(mount_point), filesystem.to_string()))
+        })
+        .collect()
+}
+
+/// 检查目录能否访问，返回第一个无法访问的目录和错误
+async fn check_paths(paths: &[PathBuf]) -> std::result::Result<(), (PathBuf, String)> {
+    for path in paths {
+        let target = path.clone();
+        let check = tokio::task::spawn_blocking(move || std::fs::read_dir(&target).map(|_| ()));
+        match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+            Ok(Ok(Ok(()))) => {}
+            Ok(Ok(Err(e))) => return Err((path.clone(), e.to_string())),
+            Ok(Err(e)) => return Err((path.clone(), e.to_string())),
+            Err(_) => return Err((path.clone(), format!("no response within {} seconds", CHECK_TIMEOUT.as_secs()))),
+        }
+    }
+    Ok(())
+}
+
+/// 启用网络共享模式时启动后台离线检查，状态变化时更新服务状态并通知前端
+pub fn spawn_offline_monitor(app: AppHandle, state: FileManagerState) {
+    let paths = CHECKED_PATHS.lock().unwrap().clone();
+    if !is_active() || paths.is_empty() {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(CHECK_INTERVAL_SECS.load(Ordering::Relaxed)));
+        loop {
+            interval.tick().await;
+
+            match (check_paths(&paths).await, state.offline()) {
+                (Err((path, error)), None) => {
+                    let offline = LibraryOffline {
+                        since: Local::now(),
+                        path: path.to_string_lossy().into_owned(),
+                        error,
+                    };
+                    tracing::error!("资料库所在的网络共享无法访问，进入离线状态: {}, {}", offline.path, offline.error);
+                    state.set_offline(Some(offline.clone()));
+                    events::emit(&app, &offline);
+                }
+                (Ok(()), Some(offline)) => {
+                    tracing::info!("网络共享恢复访问，离线 {} 秒", (Local::now() - offline.since).num_seconds());
+                    state.set_offline(None);
+                    events::emit(&app, &LibraryOnline);
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_mounts() {
+        let mounts = "\
+/dev/sda1 / ext4 rw,relatime 0 0
+//nas/team /mnt/team\\040share cifs rw,vers=3.0 0 0
+nas:/export /mnt/team\\040share/nfs nfs4 rw 0 0
+tmpfs /mnt/team\\040share/cache tmpfs rw 0 0
+";
+        let library = Path::new("/mnt/team share/Collaboard");
+        assert_eq!(filesystem_type(parse_proc_mounts(mounts), library).as_deref(), Some("cifs"));
+        let nested = Path::new("/mnt/team share/nfs/files");
+        assert_eq!(filesystem_type(parse_proc_mounts(mounts), nested).as_deref(), Some("nfs4"));
+        let local = Path::new("/home/user/Collaboard");
+        assert_eq!(filesystem_type(parse_proc_mounts(mounts), local).as_deref(), Some("ext4"));
+        // 仅前缀相同的目录不属于该挂载点
+        let sibling = Path::new("/mnt/team share/cache2");
+        assert_eq!(filesystem_type(parse_proc_mounts(mounts), sibling).as_deref(), Some("cifs"));
+
+        assert!(is_network_filesystem("NFS4"));
+        assert!(!is_network_filesystem("tmpfs"));
+    }
+
+    #[test]
+    fn test_parse_bsd_mounts() {
+        let mounts = "\
+/dev/disk3s1s1 on / (apfs, sealed, local, read-only, journaled)
+//alice@nas._smb._tcp.local/Team on /Volumes/Team Files (smbfs, nodev, nosuid, mounted by alice)
+";
+        let library = Path::new("/Volumes/Team Files/Collaboard");
+        assert_eq!(filesystem_type(parse_bsd_mounts(mounts), library).as_deref(), Some("smbfs"));
+        assert_eq!(filesystem_type(parse_bsd_mounts(mounts), Path::new("/Users/alice")).as_deref(), Some("apfs"));
+    }
+
+    #[tokio::test]
+    async fn test_check_paths_reports_missing_directory() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let missing = temp_dir.path().join("unplugged");
+        assert!(check_paths(&[temp_dir.path().to_path_buf()]).await.is_ok());
+
+        let (path, _) = check_paths(&[temp_dir.path().to_path_buf(), missing.clone()]).await.unwrap_err();
+        assert_eq!(path, missing);
+    }
+}
@@ -0,0 +1,159 @@
+//! 系统通知模块
+//!
+//! 耗时操作在后台完成或失败时发送系统通知：
+//! - URL 导入、监视文件夹导入、导出完成或失败
+//! - 完整性抽检发现哈希不一致
+//! - 可在设置中关闭，默认开启
+
+use crate::file_manager::backup::BackupReport;
+use crate::file_manager::error::FileManagerError;
+use crate::file_manager::export::ExportReport;
+use crate::file_manager::integrity::IntegrityReport;
+use crate::file_manager::service::FileManagerService;
+use crate::file_manager::watched_folders::WatchedImportReport;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+/// 保存通知设置的状态键
+pub const NOTIFICATION_SETTINGS_KEY: &str = "notifications";
+
+/// 通知设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// 一条后台任务通知
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobNotification {
+    pub title: String,
+    pub body: String,
+}
+
+impl JobNotification {
+    /// 导出完成
+    pub fn export_finished(report: &ExportReport) -> Self {
+        let mut body = format!("已导出 {} 个文件", report.exported.len());
+        if !report.skipped.is_empty() {
+            body.push_str(&format!("，跳过 {} 个", report.skipped.len()));
+        }
+        if !report.failed.is_empty() {
+            body.push_str(&format!("，失败 {} 个", report.failed.len()));
+        }
+
+        let title = if report.failed.is_empty() { "导出完成" } else { "导出完成（部分失败）" };
+        Self { title: title.to_string(), body }
+    }
+
+    /// 导出失败
+    pub fn export_failed(error: &FileManagerError) -> Self {
+        Self { title: "导出失败".to_string(), body: error.to_string() }
+    }
+
+    /// 备份完成
+    pub fn backup_finished(report: &BackupReport) -> Self {
+        let mut body = format!("已备份 {} 个文件", report.files_copied);
+        if report.files_reused > 0 {
+            body.push_str(&format!("，{} 个未变化", report.files_reused));
+        }
+        if !report.failed.is_empty() {
+            body.push_str(&format!("，失败 {} 个", report.failed.len()));
+        }
+
+        let title = if report.failed.is_empty() { "备份完成" } else { "备份完成（部分失败）" };
+        Self { title: title.to_string(), body }
+    }
+
+    /// 备份失败
+    pub fn backup_failed(error: &FileManagerError) -> Self {
+        Self { title: "备份失败".to_string(), body: error.to_string() }
+    }
+
+    /// URL 导入完成
+    pub fn url_import_finished(file_name: &str) -> Self {
+        Self { title: "导入完成".to_string(), body: format!("已从链接导入 {}", file_name) }
+    }
+
+    /// URL 导入失败
+    pub fn url_import_failed(url: &str, error: &FileManagerError) -> Self {
+        Self { title: "导入失败".to_string(), body: format!("{}: {}", url, error) }
+    }
+
+    /// 监视文件夹导入完成
+    pub fn watched_import_finished(report: &WatchedImportReport) -> Self {
+        let mut body = format!("已从监视文件夹导入 {} 个文件", report.imported);
+        if report.duplicates > 0 {
+            body.push_str(&format!("，{} 个与已有文件相同未导入", report.duplicates));
+        }
+        if report.failed > 0 {
+            body.push_str(&format!("，{} 个失败，稍后重试", report.failed));
+        }
+        Self { title: "导入完成".to_string(), body }
+    }
+
+    /// 完整性抽检发现不一致
+    pub fn integrity_mismatch(report: &IntegrityReport) -> Self {
+        Self {
+            title: "文件完整性告警".to_string(),
+            body: format!("{} 个存储文件的内容与记录的哈希不一致", report.mismatched.len()),
+        }
+    }
+}
+
+/// 按设置发送系统通知，发送失败只记录日志
+pub async fn notify<R: Runtime>(app: &AppHandle<R>, service: &FileManagerService, notification: JobNotification) {
+    let enabled = match service.get_notification_settings().await {
+        Ok(settings) => settings.enabled,
+        Err(e) => {
+            tracing::warn!("读取通知设置失败: {}", e);
+            return;
+        }
+    };
+    if !enabled {
+        return;
+    }
+
+    if let Err(e) = app.notification()
+        .builder()
+        .title(notification.title)
+        .body(notification.body)
+        .show()
+    {
+        tracing::warn!("系统通知发送失败: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_manager::export::ExportFailure;
+
+    #[test]
+    fn test_export_notification_summary() {
+        let report = ExportReport {
+            exported: vec!["a.jpg".to_string(), "b.jpg".to_string()],
+            skipped: vec!["c".to_string()],
+            failed: vec![],
+            bytes_copied: 10,
+            manifest_path: None,
+        };
+        let notification = JobNotification::export_finished(&report);
+        assert_eq!(notification.title, "导出完成");
+        assert_eq!(notification.body, "已导出 2 个文件，跳过 1 个");
+
+        let report = ExportReport {
+            failed: vec![ExportFailure { file_id: "d".to_string(), error: "denied".to_string() }],
+            ..report
+        };
+        assert_eq!(JobNotification::export_finished(&report).title, "导出完成（部分失败）");
+    }
+}
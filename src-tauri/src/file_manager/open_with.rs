@@ -0,0 +1,142 @@
+//! 外部应用打开模块
+//!
+//! 按 MIME 类型配置用于打开文件的外部应用，如“在 Photoshop / Krita 中编辑”：
+//! - 设置保存在资料库中，在其他机器上打开同一资料库时配置保持一致
+//! - MIME 类型可写成 `image/png` 或通配的 `image/*`，精确匹配的应用排在前面
+//! - 应用的程序可以是可执行文件路径，也可以是系统能解析的应用名（如 `krita`），
+//!   跨机器使用时建议填写应用名
+//! - 打开时未指定应用则使用首选应用，没有配置时交给系统默认程序
+
+use crate::file_manager::error::{FileManagerError, Result};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::BTreeMap;
+
+/// 保存外部应用设置的状态键
+pub const OPEN_WITH_SETTINGS_KEY: &str = "open_with";
+
+/// 外部应用
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExternalApp {
+    /// 应用标识，同一 MIME 类型下唯一
+    pub id: String,
+    /// 显示名称
+    pub name: String,
+    /// 可执行文件路径或应用名
+    pub program: String,
+}
+
+/// 外部应用设置，键为规范化后的 MIME 类型，应用按优先顺序排列
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct OpenWithSettings {
+    #[serde(default)]
+    pub associations: BTreeMap<String, Vec<ExternalApp>>,
+}
+
+impl OpenWithSettings {
+    /// 设置 MIME 类型对应的应用，列表为空时移除该类型的配置
+    pub fn set(&mut self, mime_type: &str, apps: Vec<ExternalApp>) -> Result<()> {
+        let mime_type = normalize_mime_pattern(mime_type)?;
+        let apps = validate_apps(apps)?;
+        if apps.is_empty() {
+            self.associations.remove(&mime_type);
+        } else {
+            self.associations.insert(mime_type, apps);
+        }
+        Ok(())
+    }
+
+    /// 获取可打开指定 MIME 类型的应用，精确匹配的排在通配匹配之前
+    pub fn apps_for(&self, mime_type: &str) -> Vec<ExternalApp> {
+        let mime_type = mime_type.trim().to_lowercase();
+        let wildcard = mime_type.split('/').next().map(|top_level| format!("{}/*", top_level));
+
+        let mut apps: Vec<ExternalApp> = Vec::new();
+        for key in std::iter::once(Some(mime_type)).chain(std::iter::once(wildcard)).flatten() {
+            for app in self.associations.get(&key).into_iter().flatten() {
+                if !apps.iter().any(|existing| existing.id == app.id) {
+                    apps.push(app.clone());
+                }
+            }
+        }
+        apps
+    }
+}
+
+/// 规范化 MIME 类型：去除空白并转为小写，只接受 `type/subtype` 或 `type/*`
+pub fn normalize_mime_pattern(mime_type: &str) -> Result<String> {
+    let normalized = mime_type.trim().to_lowercase();
+    let valid = match normalized.split_once('/') {
+        Some((top_level, subtype)) => {
+            !top_level.is_empty()
+                && top_level != "*"
+                && !subtype.is_empty()
+                && !subtype.contains('/')
+                && !normalized.contains(char::is_whitespace)
+        }
+        None => false,
+    };
+
+    if !valid {
+        return Err(FileManagerError::general_error(format!("Invalid MIME type: {}", mime_type)));
+    }
+    Ok(normalized)
+}
+
+/// 去除应用字段的首尾空白，拒绝缺少标识或程序以及标识重复的应用
+fn validate_apps(apps: Vec<ExternalApp>) -> Result<Vec<ExternalApp>> {
+    let mut validated: Vec<ExternalApp> = Vec::with_capacity(apps.len());
+    for app in apps {
+        let app = ExternalApp {
+            id: app.id.trim().to_string(),
+            name: app.name.trim().to_string(),
+            program: app.program.trim().to_string(),
+        };
+        if app.id.is_empty() || app.program.is_empty() {
+            return Err(FileManagerError::general_error("External app requires an ID and a program"));
+        }
+        if validated.iter().any(|existing| existing.id == app.id) {
+            return Err(FileManagerError::general_error(format!("Duplicate external app ID: {}", app.id)));
+        }
+        validated.push(app);
+    }
+    Ok(validated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(id: &str) -> ExternalApp {
+        ExternalApp { id: id.to_string(), name: id.to_string(), program: id.to_string() }
+    }
+
+    #[test]
+    fn test_apps_for_prefers_exact_match() {
+        let mut settings = OpenWithSettings::default();
+        settings.set(" Image/* ", vec![app("krita"), app("gimp")]).unwrap();
+        settings.set("image/vnd.adobe.photoshop", vec![app("photoshop"), app("krita")]).unwrap();
+
+        let ids = |settings: &OpenWithSettings, mime: &str| {
+            settings.apps_for(mime).into_iter().map(|app| app.id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&settings, "image/vnd.adobe.photoshop"), ["photoshop", "krita", "gimp"]);
+        assert_eq!(ids(&settings, "image/png"), ["krita", "gimp"]);
+        assert!(ids(&settings, "video/mp4").is_empty());
+
+        settings.set("image/*", vec![]).unwrap();
+        assert!(ids(&settings, "image/png").is_empty());
+    }
+
+    #[test]
+    fn test_invalid_settings_are_rejected() {
+        let mut settings = OpenWithSettings::default();
+        assert!(settings.set("image", vec![app("krita")]).is_err());
+        assert!(settings.set("*/*", vec![app("krita")]).is_err());
+        assert!(settings.set("image/png", vec![app("krita"), app("krita")]).is_err());
+        assert!(settings.set("image/png", vec![ExternalApp { program: " ".to_string(), ..app("krita") }]).is_err());
+        assert!(settings.associations.is_empty());
+    }
+}
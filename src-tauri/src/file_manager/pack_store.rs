@@ -0,0 +1,294 @@
+//! 打包存储模块
+//!
+//! 为包含大量小文件（图标、片段）的资料库减少文件系统开销，把小文件的内容合并存放在少量打包文件中：
+//! - 默认关闭；开启后后台任务把不超过大小上限、已完成索引的存储文件追加到打包文件，并删除原文件
+//! - 打包内容按内容哈希寻址，相同内容只存一份；索引（哈希 → 打包文件、偏移、长度）保存在数据库中
+//! - 读取文件内容时直接从打包文件读取；需要文件路径的操作（asset 协议、外部应用、备份、导出等）
+//!   先把内容解出到打包目录下的缓存，缓存按修改时间定期清理
+//! - 文件删除或内容被替换后，打包文件中对应的内容成为无用数据；后台压缩把有效内容不足一半的打包文件
+//!   重写为新的打包文件，没有有效内容的直接删除
+//! - 写入顺序为追加内容、更新数据库、删除原文件，中断时只会留下无用数据，不会丢失文件
+//! - 维护时每个文件和打包文件单独获取服务锁，按后台 I/O 限额等待时不持有服务锁，维护期间其他命令仍可执行
+
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::io_throttle;
+use crate::file_manager::job_queue::QueuedJob;
+use crate::file_manager::paths::long_path;
+use crate::file_manager::commands::FileManagerState;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// 保存打包存储设置的状态键
+pub const PACK_SETTINGS_KEY: &str = "pack_storage";
+
+/// 存储根目录下存放打包文件的目录名
+pub const PACK_DIR: &str = "packs";
+
+/// 打包目录下存放解出内容的缓存目录名
+const PACK_CACHE_DIR: &str = "cache";
+
+/// 单个打包文件的大小上限，超过后写入新的打包文件
+pub const MAX_PACK_SIZE: u64 = 64 * 1024 * 1024;
+
+/// 默认打包的文件大小上限
+pub const DEFAULT_PACK_FILE_SIZE: u64 = 64 * 1024;
+
+/// 可设置的打包文件大小上限
+pub const MAX_PACK_FILE_SIZE: u64 = 1024 * 1024;
+
+/// 每批打包的文件数
+pub const PACK_BATCH_SIZE: usize = 500;
+
+/// 有效内容低于此比例的打包文件被重写
+pub const COMPACTION_RATIO: f64 = 0.5;
+
+/// 后台打包和压缩的间隔
+pub const PACK_MAINTENANCE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// 解出内容的缓存保留时间
+pub const PACK_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// 打包存储设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PackSettings {
+    pub enabled: bool,
+    /// 不超过此大小（字节）的文件会被打包
+    pub max_file_size: u64,
+}
+
+impl Default for PackSettings {
+    fn default() -> Self {
+        Self { enabled: false, max_file_size: DEFAULT_PACK_FILE_SIZE }
+    }
+}
+
+impl PackSettings {
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=MAX_PACK_FILE_SIZE).contains(&self.max_file_size) {
+            return Err(FileManagerError::general_error(format!(
+                "Packed file size limit must be between 1 and {} bytes", MAX_PACK_FILE_SIZE
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 打包文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackInfo {
+    pub id: String,
+    /// 打包文件的当前大小（字节），包含无用数据
+    pub size: u64,
+}
+
+/// 打包内容的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackEntry {
+    pub content_hash: String,
+    pub pack_id: String,
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// 打包存储的使用情况
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PackStats {
+    pub settings: PackSettings,
+    pub packs: usize,
+    pub packed_files: usize,
+    /// 打包文件的总大小
+    pub pack_bytes: u64,
+    /// 其中仍被文件引用的内容大小
+    pub live_bytes: u64,
+}
+
+/// 一次打包和压缩的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PackReport {
+    /// 本次打包的文件数
+    pub packed: usize,
+    /// 重写或删除的打包文件数
+    pub compacted: usize,
+    /// 压缩回收的字节数
+    pub reclaimed_bytes: u64,
+}
+
+/// 打包文件的路径
+pub fn pack_path(storage_root: &Path, pack_id: &str) -> PathBuf {
+    storage_root.join(PACK_DIR).join(format!("{}.pack", pack_id))
+}
+
+/// 打包内容解出到缓存中的路径，保留原文件的扩展名
+pub fn cache_path(storage_root: &Path, content_hash: &str, name: &str) -> PathBuf {
+    let file_name = match Path::new(name).extension() {
+        Some(extension) => format!("{}.{}", content_hash, extension.to_string_lossy()),
+        None => content_hash.to_string(),
+    };
+    storage_root.join(PACK_DIR).join(PACK_CACHE_DIR).join(file_name)
+}
+
+/// 把内容追加到打包文件末尾并同步到磁盘，返回写入位置的偏移
+pub fn append(path: &Path, data: &[u8]) -> Result<u64> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(long_path(parent))?;
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(long_path(path))?;
+    let offset = file.seek(SeekFrom::End(0))?;
+    file.write_all(data)?;
+    file.sync_data()?;
+    Ok(offset)
+}
+
+/// 读取打包内容并校验哈希
+pub fn read_entry(storage_root: &Path, entry: &PackEntry) -> Result<Vec<u8>> {
+    let mut file = std::fs::File::open(long_path(&pack_path(storage_root, &entry.pack_id)))?;
+    file.seek(SeekFrom::Start(entry.offset))?;
+    let mut data = vec![0u8; entry.length as usize];
+    file.read_exact(&mut data)?;
+
+    if format!("{:x}", Sha256::digest(&data)) != entry.content_hash {
+        return Err(FileManagerError::general_error(format!(
+            "Packed content does not match its hash: {}", entry.content_hash
+        )));
+    }
+    Ok(data)
+}
+
+/// 删除超过保留时间的缓存文件，返回删除的文件数
+pub fn clean_cache(storage_root: &Path, ttl: Duration) -> usize {
+    let Ok(entries) = std::fs::read_dir(storage_root.join(PACK_DIR).join(PACK_CACHE_DIR)) else {
+        return 0;
+    };
+
+    let now = SystemTime::now();
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let expired = entry.metadata()
+            .and_then(|metadata| metadata.modified())
+            .map(|modified| now.duration_since(modified).unwrap_or_default() >= ttl)
+            .unwrap_or(false);
+        if expired && std::fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// 打包一批小文件，压缩无用数据过多的打包文件，并清理过期的缓存
+///
+/// 打包存储关闭时只压缩和清理。每个文件和打包文件单独获取服务锁，资料库变为只读时停止；
+/// 应用开始退出时停止并保留在任务队列中，下次启动时回滚
+pub async fn run_maintenance(state: &FileManagerState) -> Result<PackReport> {
+    let job_id = {
+        let service = state.lock_writable().await?;
+        let job_id = service.generate_id();
+        service.enqueue_job(&job_id, &QueuedJob::PackMaintenance).await;
+        job_id
+    };
+
+    let result = maintenance_steps(state, &job_id).await;
+    if let Ok(service) = state.lock().await {
+        service.finish_job(&job_id).await;
+    }
+    result
+}
+
+async fn maintenance_steps(state: &FileManagerState, job_id: &str) -> Result<PackReport> {
+    let mut report = PackReport::default();
+
+    let candidates = state.lock_writable().await?.pack_candidates().await?;
+    for file in candidates {
+        let packed = {
+            let _permit = io_throttle::write_permit().await;
+            state.lock_writable().await?.pack_file(&file).await
+        };
+        match packed {
+            Ok(transferred) => {
+                report.packed += 1;
+                io_throttle::throttle(transferred).await;
+            }
+            Err(e) => tracing::warn!("文件打包失败: {}, {}", file.id, e),
+        }
+    }
+
+    let packs = state.lock_writable().await?.compaction_candidates().await?;
+    for (pack, live_bytes) in packs {
+        let compacted = {
+            let _permit = io_throttle::write_permit().await;
+            state.lock_writable().await?.compact_pack(&pack.id, job_id, report.packed + report.compacted).await
+        };
+        match compacted {
+            Ok(transferred) => {
+                report.compacted += 1;
+                report.reclaimed_bytes += pack.size - live_bytes;
+                io_throttle::throttle(transferred).await;
+            }
+            Err(e) => tracing::warn!("打包文件压缩失败: {}, {}", pack.id, e),
+        }
+    }
+
+    state.lock().await?.clean_pack_cache().await?;
+    Ok(report)
+}
+
+/// 启动后台打包和压缩任务
+pub fn spawn_maintenance_loop(state: FileManagerState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(PACK_MAINTENANCE_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let Ok(service) = state.lock().await else {
+                continue;
+            };
+            // 只读模式下由写入的进程打包
+            if service.is_read_only() {
+                continue;
+            }
+            drop(service);
+            match run_maintenance(&state).await {
+                Ok(report) if report.packed > 0 || report.compacted > 0 => {
+                    tracing::info!("打包存储维护完成: 打包 {} 个文件, 压缩 {} 个打包文件, 回收 {} 字节",
+                        report.packed, report.compacted, report.reclaimed_bytes);
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("打包存储维护失败: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_append_and_read_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = pack_path(temp_dir.path(), "p1");
+
+        let mut entries = Vec::new();
+        for data in [&b"first"[..], b"second"] {
+            let offset = append(&path, data).unwrap();
+            entries.push(PackEntry {
+                content_hash: format!("{:x}", Sha256::digest(data)),
+                pack_id: "p1".to_string(),
+                offset,
+                length: data.len() as u64,
+            });
+        }
+        assert_eq!(entries[1].offset, 5);
+        assert_eq!(read_entry(temp_dir.path(), &entries[1]).unwrap(), b"second");
+
+        let corrupt = PackEntry { length: 4, ..entries[0].clone() };
+        assert!(read_entry(temp_dir.path(), &corrupt).is_err());
+    }
+}
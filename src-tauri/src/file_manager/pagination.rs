@@ -0,0 +1,130 @@
+//! 分页游标模块
+//!
+//! 列表和搜索命令使用统一的游标分页，翻页期间有条目增删时“加载更多”仍然稳定：
+//! - 游标记录上一页最后一项的排序键和 ID，下一页从严格排在其后的条目开始
+//! - 排序键相同的条目按 ID 区分，不会重复或遗漏
+//! - 游标对前端不透明，编码为 URL 安全的 Base64 字符串
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// 默认每页条目数
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// 每页条目数上限
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// 分页游标
+///
+/// 按 (排序键, ID) 排序，字段顺序决定比较顺序
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PageCursor {
+    #[serde(rename = "k")]
+    pub sort_key: String,
+    #[serde(rename = "i")]
+    pub id: String,
+}
+
+impl PageCursor {
+    pub fn new(sort_key: impl Into<String>, id: impl Into<String>) -> Self {
+        Self { sort_key: sort_key.into(), id: id.into() }
+    }
+
+    /// 编码为前端使用的不透明字符串
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// 解析前端传回的游标，格式无效时返回 `None`
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor.trim()).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+/// 一页结果，`next_cursor` 为空时表示已是最后一页
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// 由已排序且多取一项的结果生成一页
+    ///
+    /// 查询时多取一项用于判断是否还有下一页，多出的一项不返回
+    pub fn from_overfetched(mut items: Vec<T>, limit: usize, cursor_of: impl Fn(&T) -> PageCursor) -> Self {
+        let next_cursor = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(|item| cursor_of(item).encode())
+        } else {
+            None
+        };
+        Self { items, next_cursor }
+    }
+}
+
+/// 在内存中对条目分页
+///
+/// 按 (排序键, ID) 排序后取游标之后的 `limit` 项
+pub fn paginate<T>(
+    items: Vec<T>,
+    after: Option<&PageCursor>,
+    limit: usize,
+    cursor_of: impl Fn(&T) -> PageCursor,
+) -> Page<T> {
+    let mut keyed: Vec<(PageCursor, T)> = items
+        .into_iter()
+        .map(|item| (cursor_of(&item), item))
+        .filter(|(cursor, _)| after.is_none_or(|after| cursor > after))
+        .collect();
+    keyed.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let keyed: Vec<(PageCursor, T)> = keyed.into_iter().take(limit + 1).collect();
+    let page = Page::from_overfetched(keyed, limit, |(cursor, _)| cursor.clone());
+    Page {
+        items: page.items.into_iter().map(|(_, item)| item).collect(),
+        next_cursor: page.next_cursor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = PageCursor::new("photo.jpg", "id-1");
+        assert_eq!(PageCursor::decode(&cursor.encode()), Some(cursor));
+        assert_eq!(PageCursor::decode("not a cursor"), None);
+    }
+
+    #[test]
+    fn test_paginate_is_stable_across_changes() {
+        let cursor_of = |item: &(String, String)| PageCursor::new(item.0.clone(), item.1.clone());
+        let mut items: Vec<(String, String)> = ["b", "a", "c", "a", "d"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.to_string(), format!("id-{}", i)))
+            .collect();
+
+        let first = paginate(items.clone(), None, 2, cursor_of);
+        assert_eq!(first.items, [("a".to_string(), "id-1".to_string()), ("a".to_string(), "id-3".to_string())]);
+
+        // 翻页之间删除已返回的条目并插入新条目，下一页仍从游标位置继续
+        items.retain(|item| item.1 != "id-1");
+        items.push(("a".to_string(), "id-9".to_string()));
+        let after = PageCursor::decode(first.next_cursor.as_deref().unwrap()).unwrap();
+        let second = paginate(items.clone(), Some(&after), 2, cursor_of);
+        let names: Vec<_> = second.items.iter().map(|item| item.1.as_str()).collect();
+        assert_eq!(names, ["id-9", "id-0"]);
+
+        let after = PageCursor::decode(second.next_cursor.as_deref().unwrap()).unwrap();
+        let last = paginate(items, Some(&after), 2, cursor_of);
+        assert_eq!(last.items.len(), 2);
+        assert!(last.next_cursor.is_none());
+    }
+}
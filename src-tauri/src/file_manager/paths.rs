@@ -0,0 +1,317 @@
+//! 跨平台路径处理模块
+//!
+//! 处理 Windows 下的路径限制：
+//! - 超过 MAX_PATH（260 字符）的路径使用 `\\?\` 扩展长度前缀
+//! - 避免 `CON`、`NUL`、`COM1` 等保留设备名
+//! - 过滤文件名中的非法字符并限制文件名长度
+//! - 统一名称的 Unicode 规范化形式（macOS 使用 NFD，Windows/Linux 通常为 NFC）
+
+use std::path::{Path, PathBuf};
+use unicode_normalization::UnicodeNormalization;
+
+/// Windows 传统路径长度上限（含结尾的空字符）
+pub const WINDOWS_MAX_PATH: usize = 260;
+
+/// 单个文件名的最大长度（字节），NTFS/ext4 均为 255
+pub const MAX_FILE_NAME_LEN: usize = 255;
+
+/// 扩展名的最大长度，超长的扩展名不保留在存储文件名中
+pub const MAX_EXTENSION_LEN: usize = 16;
+
+/// Windows 保留的设备名（不区分大小写，带扩展名时同样保留）
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 文件名中不允许出现的字符
+const INVALID_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// 检查文件名是否为 Windows 保留设备名
+///
+/// `con`、`NUL.txt`、`com1.tar.gz` 都视为保留名
+pub fn is_reserved_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+/// 将名称规范化为 NFC 形式
+///
+/// 所有写入数据库的文件名和目录名都应经过此函数，保证同一名称只有一种编码
+pub fn normalize_name(name: &str) -> String {
+    name.nfc().collect()
+}
+
+/// 生成用于搜索比较的键
+///
+/// 使用 NFKC 规范化并转换为小写，使 NFD/NFC、全角/半角和大小写差异不影响匹配
+pub fn search_key(text: &str) -> String {
+    text.nfkc().collect::<String>().to_lowercase()
+}
+
+/// 将文件名转换为可在所有平台上安全使用的名称
+///
+/// - 规范化为 NFC 形式
+/// - 非法字符和控制字符替换为 `_`
+/// - 去除结尾的空格和点（Windows 会静默删除它们）
+/// - 保留设备名前加 `_`
+/// - 超长的文件名在保留扩展名的前提下截断
+pub fn sanitize_file_name(name: &str) -> String {
+    let replaced: String = normalize_name(name.trim())
+        .chars()
+        .map(|c| if c.is_control() || INVALID_CHARS.contains(&c) { '_' } else { c })
+        .collect();
+
+    // 截断可能在结尾留下空格和点，截断后再去除
+    let mut sanitized = truncate_file_name(&replaced, MAX_FILE_NAME_LEN);
+    trim_trailing_dots(&mut sanitized);
+
+    if sanitized.is_empty() {
+        return "_".to_string();
+    }
+
+    if is_reserved_name(&sanitized) {
+        sanitized = truncate_file_name(&format!("_{}", sanitized), MAX_FILE_NAME_LEN);
+        trim_trailing_dots(&mut sanitized);
+    }
+
+    sanitized
+}
+
+/// 去除结尾的空格和点
+fn trim_trailing_dots(name: &mut String) {
+    let trimmed_len = name.trim_end_matches(['.', ' ']).len();
+    name.truncate(trimmed_len);
+}
+
+/// 在保留扩展名的前提下将文件名截断到指定字节数
+fn truncate_file_name(name: &str, max_len: usize) -> String {
+    if name.len() <= max_len {
+        return name.to_string();
+    }
+
+    let (stem, extension) = split_extension(name);
+    let mut end = max_len - extension.len();
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    format!("{}{}", &stem[..end], extension)
+}
+
+/// 拆分主文件名和扩展名（含 `.`），超长的扩展名视为主文件名的一部分
+fn split_extension(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(index) if index > 0 && name.len() - index <= MAX_EXTENSION_LEN + 1 => name.split_at(index),
+        _ => (name, ""),
+    }
+}
+
+/// 提取适合用作存储文件名的扩展名
+///
+/// 只保留长度合理且仅由 ASCII 字母数字组成的扩展名，统一为小写
+pub fn storage_extension(original_name: &str) -> Option<String> {
+    Path::new(original_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .filter(|ext| !ext.is_empty() && ext.len() <= MAX_EXTENSION_LEN)
+        .filter(|ext| ext.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+/// 为文件名追加序号后缀
+///
+/// `photo.jpg` -> `photo (1).jpg`，没有扩展名时直接追加；
+/// 追加后超过 [`MAX_FILE_NAME_LEN`] 时截断主文件名
+pub fn with_copy_suffix(original_name: &str, index: usize) -> String {
+    let (stem, extension) = split_extension(original_name);
+    let suffix = format!(" ({})", index);
+
+    let mut end = stem.len().min(MAX_FILE_NAME_LEN.saturating_sub(suffix.len() + extension.len()));
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    format!("{}{}{}", &stem[..end], suffix, extension)
+}
+
+/// 为路径添加 Windows 扩展长度前缀
+///
+/// 仅在 Windows 上对超过 MAX_PATH 的绝对路径生效，其他平台原样返回
+pub fn long_path(path: &Path) -> PathBuf {
+    if cfg!(windows) && path.as_os_str().len() >= WINDOWS_MAX_PATH {
+        if let Some(path_str) = path.to_str() {
+            return PathBuf::from(extended_length_path(path_str));
+        }
+    }
+    path.to_path_buf()
+}
+
+/// 将 Windows 绝对路径转换为 `\\?\` 扩展长度形式
+///
+/// - `C:\dir\file` -> `\\?\C:\dir\file`
+/// - `\\server\share\file` -> `\\?\UNC\server\share\file`
+/// - 已带前缀的路径和相对路径保持不变
+///
+/// 扩展长度路径不会再被规范化，因此这里统一使用 `\` 作为分隔符
+pub fn extended_length_path(path: &str) -> String {
+    if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return path.to_string();
+    }
+
+    let normalized = path.replace('/', "\\");
+    if let Some(unc) = normalized.strip_prefix(r"\\") {
+        return format!(r"\\?\UNC\{}", unc);
+    }
+
+    let bytes = normalized.as_bytes();
+    if bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\' {
+        return format!(r"\\?\{}", normalized);
+    }
+
+    path.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_reserved_names() {
+        assert!(is_reserved_name("CON"));
+        assert!(is_reserved_name("nul.txt"));
+        assert!(is_reserved_name("Com1.tar.gz"));
+        assert!(!is_reserved_name("console.log"));
+        assert!(!is_reserved_name("COM10"));
+
+        assert_eq!(sanitize_file_name("aux.jpg"), "_aux.jpg");
+        assert_eq!(sanitize_file_name("a<b>:c?.txt. "), "a_b__c_.txt");
+        assert_eq!(sanitize_file_name("..."), "_");
+    }
+
+    #[test]
+    fn test_long_file_name_is_truncated() {
+        let long_name = format!("{}.jpeg", "照".repeat(200));
+        let sanitized = sanitize_file_name(&long_name);
+
+        assert!(sanitized.len() <= MAX_FILE_NAME_LEN);
+        assert!(sanitized.ends_with(".jpeg"));
+
+        assert_eq!(storage_extension(&long_name).as_deref(), Some("jpeg"));
+        assert_eq!(storage_extension(&format!("file.{}", "x".repeat(300))), None);
+    }
+
+    #[test]
+    fn test_unicode_normalization() {
+        let nfd = "Cafe\u{301}.txt";
+        let nfc = "Caf\u{e9}.txt";
+
+        assert_eq!(normalize_name(nfd), nfc);
+        assert_eq!(sanitize_file_name(nfd), nfc);
+        assert_eq!(search_key(nfd), search_key("CAF\u{c9}.TXT"));
+        assert_eq!(search_key("\u{ff21}\u{ff22}"), "ab");
+    }
+
+    #[test]
+    fn test_extended_length_path() {
+        let deep = format!(r"C:\Users\me\AppData\Roaming\Collaboard\files\{}\file.png", "d".repeat(260));
+        assert!(deep.len() > WINDOWS_MAX_PATH);
+
+        let extended = extended_length_path(&deep);
+        assert!(extended.starts_with(r"\\?\C:\Users"));
+        assert_eq!(extended.len(), deep.len() + 4);
+
+        assert_eq!(extended_length_path(r"\\server\share\a.txt"), r"\\?\UNC\server\share\a.txt");
+        assert_eq!(extended_length_path("C:/data/a.txt"), r"\\?\C:\data\a.txt");
+        assert_eq!(extended_length_path(&extended), extended);
+        assert_eq!(extended_length_path("relative/a.txt"), "relative/a.txt");
+    }
+
+    /// 混合路径分隔符、保留名、结尾点号和多字节字符的文件名
+    fn file_name_strategy() -> impl Strategy<Value = String> {
+        let part = prop_oneof![
+            Just("..".to_string()),
+            Just(".".to_string()),
+            Just("/".to_string()),
+            Just("\\".to_string()),
+            Just(" ".to_string()),
+            Just("CON".to_string()),
+            Just("lpt1".to_string()),
+            Just("e\u{301}".to_string()),
+            Just("照".to_string()),
+            "[a-z]{1,8}",
+            "\\PC{1,4}",
+            "[\\x00-\\x1f<>:\"|?*]",
+        ];
+        (prop::collection::vec(part, 0..12), prop::option::of("[a-zA-Z0-9]{1,20}"), 0usize..300)
+            .prop_map(|(parts, extension, padding)| {
+                // 填充放在前面，使截断位置落在各种片段上
+                let mut name = "x".repeat(padding);
+                name.push_str(&parts.concat());
+                if let Some(extension) = extension {
+                    name.push('.');
+                    name.push_str(&extension);
+                }
+                name
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_sanitized_name_is_single_safe_component(name in file_name_strategy()) {
+            let sanitized = sanitize_file_name(&name);
+
+            prop_assert!(!sanitized.is_empty());
+            prop_assert!(sanitized.len() <= MAX_FILE_NAME_LEN);
+            prop_assert!(!sanitized.chars().any(|c| c.is_control() || INVALID_CHARS.contains(&c)));
+            prop_assert!(!sanitized.ends_with(['.', ' ']));
+            prop_assert!(!is_reserved_name(&sanitized));
+            prop_assert_eq!(normalize_name(&sanitized), sanitized.clone());
+
+            // 拼接到任意目录下只增加一级，不会跳出目录
+            let base = Path::new("library").join("files");
+            let joined = base.join(&sanitized);
+            prop_assert_eq!(joined.parent(), Some(base.as_path()));
+            prop_assert_eq!(joined.components().count(), 3);
+        }
+
+        #[test]
+        fn prop_sanitize_is_idempotent(name in file_name_strategy()) {
+            let sanitized = sanitize_file_name(&name);
+            prop_assert_eq!(sanitize_file_name(&sanitized), sanitized);
+        }
+
+        #[test]
+        fn prop_copy_suffix_names_are_unique_and_safe(name in file_name_strategy(), a in 1usize..10_000, b in 1usize..10_000) {
+            let sanitized = sanitize_file_name(&name);
+            let first = with_copy_suffix(&sanitized, a);
+
+            prop_assert_ne!(&first, &sanitized);
+            prop_assert!(first.len() <= MAX_FILE_NAME_LEN);
+            prop_assert_eq!(sanitize_file_name(&first), first.clone());
+            if a != b {
+                prop_assert_ne!(first, with_copy_suffix(&sanitized, b));
+            }
+        }
+
+        #[test]
+        fn prop_storage_extension_is_plain_ascii(name in file_name_strategy()) {
+            if let Some(extension) = storage_extension(&name) {
+                prop_assert!(!extension.is_empty() && extension.len() <= MAX_EXTENSION_LEN);
+                prop_assert!(extension.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+            }
+        }
+
+        #[test]
+        fn prop_extended_length_path_is_idempotent(path in r"([A-Za-z]:[\\/]|\\\\|/)?[^\x00]{0,40}") {
+            let extended = extended_length_path(&path);
+            prop_assert_eq!(extended_length_path(&extended), extended.clone());
+            if extended != path {
+                prop_assert!(extended.starts_with(r"\\?\"));
+                prop_assert!(!extended.contains('/'));
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! 播放列表模块
+//!
+//! 播放列表是有顺序的文件列表，用于全屏评审模式逐张放映：
+//! - 保存在数据库中，可以关联到画板，作为画板的讲解顺序
+//! - 同一文件可以出现多次；修改时整体替换文件顺序
+//! - 成员只引用文件，文件被删除后跳过，序号按仍存在的文件计算，撤销删除后重新出现
+//! - 放映时按序号获取单个条目，返回文件信息和供 asset 协议加载的存储路径
+
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::service::FileListItem;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// 单个播放列表的条目上限
+pub const MAX_PLAYLIST_ITEMS: usize = 10_000;
+
+/// 播放列表
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Playlist {
+    pub id: String,
+    pub name: String,
+    /// 关联的画板 ID，表示画板的讲解顺序
+    pub board_id: Option<String>,
+    /// 仍存在的文件数
+    pub item_count: usize,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+/// 创建或修改播放列表的请求，修改时整体替换
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PlaylistRequest {
+    pub name: String,
+    #[serde(default)]
+    pub board_id: Option<String>,
+    /// 按放映顺序排列的文件 ID
+    #[serde(default)]
+    pub file_ids: Vec<String>,
+}
+
+impl PlaylistRequest {
+    /// 检查并规范化：名称去除首尾空白且不能为空，空的画板 ID 视为未关联，条目数不超过上限
+    pub fn normalize(mut self) -> Result<Self> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return Err(FileManagerError::general_error("Playlist name cannot be empty"));
+        }
+        self.board_id = self.board_id.filter(|board_id| !board_id.trim().is_empty());
+        if self.file_ids.len() > MAX_PLAYLIST_ITEMS {
+            return Err(FileManagerError::general_error(format!(
+                "Playlist cannot contain more than {} files", MAX_PLAYLIST_ITEMS
+            )));
+        }
+        Ok(self)
+    }
+}
+
+/// 放映时的单个条目
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PlaylistItem {
+    pub index: usize,
+    /// 播放列表中仍存在的文件数
+    pub total: usize,
+    pub file: FileListItem,
+    /// 存储文件的绝对路径，前端通过 asset 协议加载
+    pub asset_path: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_playlist_request() {
+        let request = PlaylistRequest {
+            name: "  Review ".to_string(),
+            board_id: Some(" ".to_string()),
+            file_ids: vec!["a".to_string(), "a".to_string()],
+        }.normalize().unwrap();
+        assert_eq!(request.name, "Review");
+        assert_eq!(request.board_id, None);
+        assert_eq!(request.file_ids.len(), 2);
+
+        assert!(PlaylistRequest { name: " ".to_string(), board_id: None, file_ids: vec![] }.normalize().is_err());
+        assert!(PlaylistRequest {
+            name: "Too long".to_string(),
+            board_id: None,
+            file_ids: vec!["a".to_string(); MAX_PLAYLIST_ITEMS + 1],
+        }.normalize().is_err());
+    }
+}
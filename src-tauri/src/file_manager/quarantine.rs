@@ -0,0 +1,88 @@
+//! 隔离区模块
+//!
+//! 开启后，因类型不支持或超出大小限制而被拒绝的上传不会直接丢弃，而是放入隔离区等待审核：
+//! - 文件保存在应用数据目录的 `quarantine` 目录中，记录原始文件名、目标目录和拒绝原因
+//! - 批准后跳过类型和大小检查导入到库中，丢弃则删除
+//! - 上传返回 `quarantined` 错误码，前端据此提示用户到隔离区审核
+
+use crate::file_manager::error::FileManagerError;
+use crate::file_manager::size_limits::FileCategory;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// 保存隔离区设置的状态键
+pub const QUARANTINE_SETTINGS_KEY: &str = "quarantine";
+
+/// 隔离区设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct QuarantineSettings {
+    /// 关闭时被拒绝的上传直接返回错误
+    pub enabled: bool,
+}
+
+/// 文件被隔离的原因
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuarantineReason {
+    UnsupportedFileType { file_type: String },
+    FileSizeExceeded {
+        size: u64,
+        max_size: u64,
+        /// 单独设置了上限的文件类别
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        category: Option<FileCategory>,
+    },
+}
+
+impl QuarantineReason {
+    /// 判断上传错误是否可以放入隔离区
+    pub fn from_error(error: &FileManagerError) -> Option<Self> {
+        match error {
+            FileManagerError::UnsupportedFileType { file_type } => Some(Self::UnsupportedFileType {
+                file_type: file_type.clone(),
+            }),
+            FileManagerError::FileSizeExceeded { size, max_size, category } => Some(Self::FileSizeExceeded {
+                size: *size,
+                max_size: *max_size,
+                category: *category,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// 隔离区中的文件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct QuarantinedFile {
+    pub id: String,
+    pub original_name: String,
+    /// 上传时指定的目标目录
+    pub directory_id: Option<String>,
+    pub file_size: i64,
+    pub reason: QuarantineReason,
+    pub created_at: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reason_from_error() {
+        let error = FileManagerError::FileSizeExceeded { size: 10, max_size: 5, category: None };
+        assert_eq!(
+            QuarantineReason::from_error(&error),
+            Some(QuarantineReason::FileSizeExceeded { size: 10, max_size: 5, category: None })
+        );
+        assert_eq!(
+            serde_json::to_string(&QuarantineReason::from_error(&error).unwrap()).unwrap(),
+            r#"{"type":"file_size_exceeded","size":10,"max_size":5}"#
+        );
+
+        let error = FileManagerError::DirectoryNotFound { path: "d1".to_string() };
+        assert_eq!(QuarantineReason::from_error(&error), None);
+    }
+}
@@ -0,0 +1,341 @@
+//! SQL 查询日志模块
+//!
+//! 开启 `development.log_sql_queries` 后，在 DEBUG 级别记录数据库连接执行的每条语句，便于在大型资料库中找出慢查询：
+//! - 记录语句、绑定参数摘要、行数和耗时；查询语句的行数为返回的行数，写入语句为修改的行数
+//! - 通过 SQLite 的跟踪回调实现，事务和预编译语句中的语句同样会被记录，[`DatabaseService`] 的各个方法无需改动
+//! - 只在开发构建中生效；未开启时不注册回调，没有额外开销
+//! - 涉及密码的语句（`PRAGMA key`、`PRAGMA rekey`、带 `KEY` 的 `ATTACH`）不记录参数和语句中的字面量
+//!
+//! [`DatabaseService`]: crate::file_manager::database::DatabaseService
+
+use rusqlite::{ffi, Connection};
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 参数摘要中单个值保留的字符数
+const MAX_PARAM_CHARS: usize = 40;
+
+/// 参数摘要中列出的参数数
+const MAX_LOGGED_PARAMS: usize = 16;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// 尚未执行完的语句已返回的行数，以语句指针为键
+static ROW_COUNTS: Mutex<Vec<(usize, u64)>> = Mutex::new(Vec::new());
+
+/// 设置是否记录 SQL 语句，只影响之后打开的连接
+///
+/// 发布构建中总是关闭
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled && cfg!(debug_assertions), Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// 开启时为连接注册跟踪回调
+pub fn install(connection: &Connection) {
+    if !is_enabled() {
+        return;
+    }
+    let mask = (ffi::SQLITE_TRACE_PROFILE | ffi::SQLITE_TRACE_ROW) as c_uint;
+    // SAFETY: 连接句柄在连接关闭前有效，连接关闭时 SQLite 自动注销回调；回调不持有上下文指针
+    unsafe {
+        ffi::sqlite3_trace_v2(connection.handle(), mask, Some(trace_callback), std::ptr::null_mut());
+    }
+}
+
+unsafe extern "C" fn trace_callback(event: c_uint, _context: *mut c_void, p: *mut c_void, x: *mut c_void) -> c_int {
+    let stmt = p as *mut ffi::sqlite3_stmt;
+    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| match event as c_int {
+        ffi::SQLITE_TRACE_ROW => count_row(stmt as usize),
+        ffi::SQLITE_TRACE_PROFILE => {
+            let nanos = *(x as *const i64);
+            log_statement(stmt, Duration::from_nanos(nanos.max(0) as u64));
+        }
+        _ => {}
+    }));
+    0
+}
+
+fn count_row(stmt: usize) {
+    let mut counts = ROW_COUNTS.lock().unwrap();
+    match counts.iter_mut().find(|(pending, _)| *pending == stmt) {
+        Some((_, count)) => *count += 1,
+        None => counts.push((stmt, 1)),
+    }
+}
+
+fn take_row_count(stmt: usize) -> u64 {
+    let mut counts = ROW_COUNTS.lock().unwrap();
+    match counts.iter().position(|(pending, _)| *pending == stmt) {
+        Some(index) => counts.swap_remove(index).1,
+        None => 0,
+    }
+}
+
+/// 语句执行完后记录一条日志
+///
+/// # Safety
+///
+/// `stmt` 必须是跟踪回调收到的有效语句指针
+unsafe fn log_statement(stmt: *mut ffi::sqlite3_stmt, duration: Duration) {
+    let returned = take_row_count(stmt as usize);
+    let sql = c_string(ffi::sqlite3_sql(stmt));
+    let rows = if ffi::sqlite3_stmt_readonly(stmt) != 0 {
+        returned
+    } else {
+        ffi::sqlite3_changes64(ffi::sqlite3_db_handle(stmt)).max(0) as u64
+    };
+
+    let param_count = ffi::sqlite3_bind_parameter_count(stmt).max(0) as usize;
+    let (sql, params) = if is_sensitive(&sql) {
+        (redact(&sql), format!("{} [redacted]", param_count))
+    } else if param_count == 0 {
+        (sql, String::from("0"))
+    } else {
+        let expanded = ffi::sqlite3_expanded_sql(stmt);
+        let params = summarize_params(&sql, &c_string(expanded), param_count);
+        ffi::sqlite3_free(expanded as *mut c_void);
+        (sql, params)
+    };
+
+    tracing::debug!(
+        sql = %compact_whitespace(&sql),
+        params = %params,
+        rows,
+        duration_ms = duration.as_secs_f64() * 1000.0,
+        "SQL 语句"
+    );
+}
+
+unsafe fn c_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    CStr::from_ptr(ptr).to_string_lossy().into_owned()
+}
+
+/// 语句是否包含密码
+fn is_sensitive(sql: &str) -> bool {
+    let upper = sql.trim_start().to_ascii_uppercase();
+    let words: Vec<&str> = upper.split(|c: char| !c.is_ascii_alphanumeric() && c != '_').collect();
+    (upper.starts_with("PRAGMA") && words.iter().any(|word| *word == "KEY" || *word == "REKEY"))
+        || (upper.starts_with("ATTACH") && words.contains(&"KEY"))
+}
+
+/// 只保留包含密码的语句的开头关键字
+fn redact(sql: &str) -> String {
+    let keyword = sql.split_whitespace().next().unwrap_or_default();
+    format!("{} [redacted]", keyword)
+}
+
+fn compact_whitespace(sql: &str) -> String {
+    sql.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 生成绑定参数摘要，如 `2 ['photo.jpg', 42]`
+///
+/// 对照语句模板和 SQLite 展开参数后的语句逐个取出参数值，过长的值截断；同一编号的参数只列一次，无法对照时只记录参数个数
+fn summarize_params(sql: &str, expanded: &str, param_count: usize) -> String {
+    match extract_params(sql, expanded) {
+        Some(values) => {
+            let mut listed: Vec<String> = values.iter().take(MAX_LOGGED_PARAMS).map(|value| truncate(value)).collect();
+            if values.len() > MAX_LOGGED_PARAMS {
+                listed.push(format!("… {} more", values.len() - MAX_LOGGED_PARAMS));
+            }
+            format!("{} [{}]", param_count, listed.join(", "))
+        }
+        None => param_count.to_string(),
+    }
+}
+
+fn truncate(value: &str) -> String {
+    let length = value.chars().count();
+    if length <= MAX_PARAM_CHARS {
+        return value.to_string();
+    }
+    let kept: String = value.chars().take(MAX_PARAM_CHARS).collect();
+    format!("{}… ({} chars)", kept, length)
+}
+
+/// 从展开后的语句中取出各参数的字面量
+fn extract_params(sql: &str, expanded: &str) -> Option<Vec<String>> {
+    let template = sql.as_bytes();
+    let expanded = expanded.as_bytes();
+    let (mut i, mut j) = (0, 0);
+    let mut names: Vec<&[u8]> = Vec::new();
+    let mut values = Vec::new();
+
+    while i < template.len() {
+        match template[i] {
+            // 语句中的字符串字面量原样出现在展开后的语句中
+            b'\'' => {
+                let end = quoted_end(template, i)?;
+                if expanded.get(j..j + (end - i)) != Some(&template[i..end]) {
+                    return None;
+                }
+                j += end - i;
+                i = end;
+            }
+            b'?' | b':' | b'@' | b'$' => {
+                let name_end = parameter_end(template, i);
+                if name_end == i + 1 && template[i] != b'?' {
+                    // 单独的 `:` 等不是参数
+                    if expanded.get(j) != Some(&template[i]) {
+                        return None;
+                    }
+                    i += 1;
+                    j += 1;
+                    continue;
+                }
+                let value_end = literal_end(expanded, j)?;
+                let name = &template[i..name_end];
+                if name == b"?" || !names.contains(&name) {
+                    names.push(name);
+                    values.push(String::from_utf8_lossy(&expanded[j..value_end]).into_owned());
+                }
+                i = name_end;
+                j = value_end;
+            }
+            byte => {
+                if expanded.get(j) != Some(&byte) {
+                    return None;
+                }
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+
+    (j == expanded.len()).then_some(values)
+}
+
+/// 从 `start` 处的引号开始，返回字符串字面量结束后的位置
+fn quoted_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut index = start + 1;
+    while index < bytes.len() {
+        if bytes[index] == b'\'' {
+            if bytes.get(index + 1) == Some(&b'\'') {
+                index += 2;
+                continue;
+            }
+            return Some(index + 1);
+        }
+        index += 1;
+    }
+    None
+}
+
+/// 参数名结束后的位置：`?` 后接数字，`:`、`@`、`$` 后接标识符
+fn parameter_end(bytes: &[u8], start: usize) -> usize {
+    let mut index = start + 1;
+    while index < bytes.len() {
+        let byte = bytes[index];
+        let part_of_name = if bytes[start] == b'?' { byte.is_ascii_digit() } else { byte.is_ascii_alphanumeric() || byte == b'_' };
+        if !part_of_name {
+            break;
+        }
+        index += 1;
+    }
+    index
+}
+
+/// 展开后的参数值结束后的位置：字符串、`x'…'` 形式的二进制数据、数字或 `NULL`
+fn literal_end(bytes: &[u8], start: usize) -> Option<usize> {
+    match bytes.get(start)? {
+        b'\'' => quoted_end(bytes, start),
+        b'x' | b'X' if bytes.get(start + 1) == Some(&b'\'') => quoted_end(bytes, start + 1),
+        _ => {
+            let mut index = start;
+            while index < bytes.len() {
+                let byte = bytes[index];
+                let exponent_sign = (byte == b'+' || byte == b'-') && index > start
+                    && matches!(bytes[index - 1], b'e' | b'E');
+                let leading_sign = byte == b'-' && index == start;
+                if !(byte.is_ascii_alphanumeric() || byte == b'.' || exponent_sign || leading_sign) {
+                    break;
+                }
+                index += 1;
+            }
+            (index > start).then_some(index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_params() {
+        let sql = "SELECT * FROM files WHERE name = ?1 AND size > ?2 AND note != 'a?b' AND id = ?1";
+        let expanded = "SELECT * FROM files WHERE name = 'it''s.jpg' AND size > -1.5e+3 AND note != 'a?b' AND id = 'it''s.jpg'";
+        assert_eq!(summarize_params(sql, expanded, 2), "2 ['it''s.jpg', -1.5e+3]");
+
+        let sql = "INSERT INTO t VALUES (:data, @missing, NULL)";
+        let expanded = format!("INSERT INTO t VALUES (x'{}', NULL, NULL)", "ab".repeat(40));
+        let summary = summarize_params(sql, &expanded, 2);
+        assert!(summary.starts_with("2 [x'abab") && summary.ends_with("(83 chars), NULL]"), "{}", summary);
+
+        // 无法对照时只记录个数
+        assert_eq!(summarize_params("SELECT ?1", "SELECT", 1), "1");
+    }
+
+    #[test]
+    fn test_logs_statements_with_rows_and_params() {
+        #[derive(Clone, Default)]
+        struct Capture(std::sync::Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Capture {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let capture = Capture::default();
+        let writer = capture.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        set_enabled(true);
+        let connection = Connection::open_in_memory().unwrap();
+        install(&connection);
+        set_enabled(false);
+        tracing::subscriber::with_default(subscriber, || {
+            connection.execute_batch("CREATE TABLE t (name TEXT); INSERT INTO t VALUES ('a'), ('b'), ('c');").unwrap();
+            connection.execute("UPDATE t SET name = ?1 WHERE name != ?2", ["z", "a"]).unwrap();
+            let names: Vec<String> = connection.prepare("SELECT name FROM t").unwrap()
+                .query_map([], |row| row.get(0)).unwrap()
+                .collect::<rusqlite::Result<_>>().unwrap();
+            assert_eq!(names, ["a", "z", "z"]);
+        });
+
+        let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+        let line = |prefix: &str| output.lines().find(|line| line.contains(prefix)).unwrap_or_default().to_string();
+        assert!(line("sql=UPDATE").contains("params=2 ['z', 'a'] rows=2"), "{}", output);
+        assert!(line("sql=SELECT name").contains("rows=3"), "{}", output);
+    }
+
+    #[test]
+    fn test_sensitive_statements_are_redacted() {
+        assert!(is_sensitive("PRAGMA key = 'secret'"));
+        assert!(is_sensitive("pragma rekey='secret'"));
+        assert!(is_sensitive("ATTACH DATABASE ?1 AS encrypted KEY ?2"));
+        assert!(!is_sensitive("SELECT key FROM app_state"));
+        assert!(!is_sensitive("ATTACH DATABASE ?1 AS backup"));
+        assert_eq!(redact("PRAGMA key = 'secret'"), "PRAGMA [redacted]");
+    }
+}
@@ -0,0 +1,121 @@
+//! 快速截图模块
+//!
+//! 注册全局快捷键，窗口不在前台时也能截图：
+//! - 快捷键和截图方式可配置，默认关闭
+//! - 截图保存到收件箱目录，并添加配置的默认标签
+//! - 完成或失败后发送 `quick-capture` 事件
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::events;
+use crate::file_manager::screenshot::{capture_into_library, CaptureMode};
+use crate::file_manager::service::UploadResponse;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// 保存快速截图设置的状态键
+pub const QUICK_CAPTURE_SETTINGS_KEY: &str = "quick_capture";
+
+/// 快速截图设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct QuickCaptureSettings {
+    pub enabled: bool,
+    /// 快捷键，如 `CommandOrControl+Alt+S`
+    pub shortcut: String,
+    #[serde(default)]
+    pub mode: CaptureMode,
+    /// 截图自动添加的标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl Default for QuickCaptureSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            shortcut: "CommandOrControl+Alt+S".to_string(),
+            mode: CaptureMode::FullScreen,
+            tags: Vec::new(),
+        }
+    }
+}
+
+/// 快速截图结果，`quick-capture` 事件负载
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct QuickCaptureOutcome {
+    pub file: Option<UploadResponse>,
+    pub error: Option<String>,
+}
+
+/// 解析快捷键
+pub fn parse_shortcut(shortcut: &str) -> Result<Shortcut> {
+    shortcut.parse::<Shortcut>()
+        .map_err(|e| FileManagerError::general_error(format!("Invalid shortcut '{}': {}", shortcut, e)))
+}
+
+/// 按设置重新注册快速截图快捷键
+///
+/// 本应用只注册这一个全局快捷键，先注销全部再按需注册
+pub fn apply_settings(app: &AppHandle, settings: &QuickCaptureSettings) -> Result<()> {
+    let global_shortcut = app.global_shortcut();
+    global_shortcut.unregister_all()
+        .map_err(|e| FileManagerError::general_error(format!("Failed to unregister shortcuts: {}", e)))?;
+
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let shortcut = parse_shortcut(&settings.shortcut)?;
+    let mode = settings.mode;
+    let tags = settings.tags.clone();
+    global_shortcut.on_shortcut(shortcut, move |app, _shortcut, event| {
+        if event.state() == ShortcutState::Pressed {
+            spawn_quick_capture(app.clone(), mode, tags.clone());
+        }
+    }).map_err(|e| FileManagerError::general_error(format!(
+        "Failed to register shortcut '{}': {}", settings.shortcut, e
+    )))?;
+
+    tracing::info!("已注册快速截图快捷键: {}", settings.shortcut);
+    Ok(())
+}
+
+/// 在后台截图并保存到收件箱目录
+fn spawn_quick_capture(app: AppHandle, mode: CaptureMode, tags: Vec<String>) {
+    tauri::async_runtime::spawn(async move {
+        let Some(state) = app.try_state::<FileManagerState>().map(|state| state.inner().clone()) else {
+            return;
+        };
+
+        let result = async {
+            let inbox_id = state.lock().await?.ensure_inbox_directory().await?;
+            capture_into_library(&state, mode, Some(inbox_id), tags).await
+        }.await;
+
+        let outcome = match result {
+            Ok(file) => QuickCaptureOutcome { file: Some(file), error: None },
+            Err(e) => {
+                tracing::error!("快速截图失败: {}", e);
+                QuickCaptureOutcome { file: None, error: Some(e.to_string()) }
+            }
+        };
+        events::emit(&app, &outcome);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_shortcut() {
+        assert!(parse_shortcut(&QuickCaptureSettings::default().shortcut).is_ok());
+        assert!(parse_shortcut("Ctrl+Shift+F12").is_ok());
+        assert!(parse_shortcut("Ctrl+NotAKey").is_err());
+        assert!(parse_shortcut("").is_err());
+    }
+}
@@ -0,0 +1,192 @@
+//! 快速切换模块
+//!
+//! 为命令面板提供按短前缀模糊查找目录、标签、工作集和文件的数据：
+//! - 输入的字符按顺序出现在名称中即视为匹配，如 `scr` 匹配 `screenshots`、`sc_render`
+//! - 名称以输入开头的排最前，其次是从词首开始的匹配，再次是名称中任意位置的连续匹配，最后是分散的字符匹配
+//! - 比较时使用 [`search_key`]，忽略大小写和全角/半角差异；纯 ASCII 名称跳过 Unicode 规范化以保证响应速度
+//! - 只读取名称等少量列，不加载完整的文件记录
+
+use crate::file_manager::paths::search_key;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::borrow::Cow;
+
+/// 默认返回的结果数
+pub const DEFAULT_QUICK_FIND_LIMIT: usize = 20;
+
+/// 结果数上限
+pub const MAX_QUICK_FIND_LIMIT: usize = 100;
+
+/// 名称以输入开头
+const PREFIX_BONUS: i64 = 3000;
+
+/// 输入从某个词的开头连续匹配
+const WORD_START_BONUS: i64 = 2000;
+
+/// 输入在名称中连续出现
+const SUBSTRING_BONUS: i64 = 1000;
+
+/// 结果类型，得分相同时按此顺序排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum QuickFindKind {
+    Directory,
+    WorkingSet,
+    Tag,
+    File,
+}
+
+/// 快速切换的一条结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct QuickFindResult {
+    pub kind: QuickFindKind,
+    /// 目录、工作集或文件的 ID，标签为标签名
+    pub id: String,
+    /// 显示的名称
+    pub label: String,
+    /// 附加说明：目录和文件为所在路径，标签为文件数，工作集为成员数
+    pub detail: Option<String>,
+    pub score: i64,
+}
+
+/// 待匹配的候选项
+pub struct QuickFindCandidate {
+    pub kind: QuickFindKind,
+    pub id: String,
+    pub label: String,
+    pub detail: Option<String>,
+}
+
+/// 计算输入与名称的匹配得分，不匹配时返回 `None`
+///
+/// 两者都应已转换为 [`search_key`]
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query.chars().collect();
+    let candidate: Vec<char> = candidate.chars().collect();
+    if query.is_empty() || query.len() > candidate.len() {
+        return None;
+    }
+
+    let is_word_start = |index: usize| index == 0 || !candidate[index - 1].is_alphanumeric();
+    let tier = if candidate.starts_with(&query) {
+        Some(PREFIX_BONUS)
+    } else {
+        let mut tier = None;
+        for start in 0..=candidate.len() - query.len() {
+            if candidate[start..start + query.len()] == query[..] {
+                if is_word_start(start) {
+                    tier = Some(WORD_START_BONUS);
+                    break;
+                }
+                tier.get_or_insert(SUBSTRING_BONUS);
+            }
+        }
+        tier
+    };
+
+    // 分散匹配：逐个字符按顺序查找，连续和位于词首的字符加分
+    let mut score = 0;
+    let mut next = 0;
+    let mut last_match: Option<usize> = None;
+    for (index, c) in candidate.iter().enumerate() {
+        if next < query.len() && *c == query[next] {
+            score += 10;
+            if last_match.is_some_and(|last| last + 1 == index) {
+                score += 15;
+            }
+            if is_word_start(index) {
+                score += 20;
+            }
+            last_match = Some(index);
+            next += 1;
+        }
+    }
+    if next < query.len() {
+        return None;
+    }
+
+    // 名称越短越接近输入
+    let extra = (candidate.len() - query.len()).min(100) as i64;
+    Some(tier.unwrap_or(0) + score - extra)
+}
+
+/// 生成名称的比较键，纯 ASCII 名称只转换大小写
+fn match_key(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        if text.bytes().any(|byte| byte.is_ascii_uppercase()) {
+            Cow::Owned(text.to_ascii_lowercase())
+        } else {
+            Cow::Borrowed(text)
+        }
+    } else {
+        Cow::Owned(search_key(text))
+    }
+}
+
+/// 对候选项打分并返回得分最高的 `limit` 条
+///
+/// 得分相同时按类型，再按名称排列
+pub fn rank(query: &str, candidates: impl IntoIterator<Item = QuickFindCandidate>, limit: usize) -> Vec<QuickFindResult> {
+    let query = search_key(query.trim());
+    let mut results: Vec<QuickFindResult> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let score = fuzzy_score(&query, &match_key(&candidate.label))?;
+            Some(QuickFindResult {
+                kind: candidate.kind,
+                id: candidate.id,
+                label: candidate.label,
+                detail: candidate.detail,
+                score,
+            })
+        })
+        .collect();
+
+    let order = |a: &QuickFindResult, b: &QuickFindResult| {
+        b.score.cmp(&a.score).then(a.kind.cmp(&b.kind)).then_with(|| a.label.cmp(&b.label))
+    };
+    if results.len() > limit {
+        results.select_nth_unstable_by(limit, order);
+        results.truncate(limit);
+    }
+    results.sort_by(order);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(kind: QuickFindKind, label: &str) -> QuickFindCandidate {
+        QuickFindCandidate { kind, id: label.to_string(), label: label.to_string(), detail: None }
+    }
+
+    #[test]
+    fn test_fuzzy_score_ordering() {
+        let prefix = fuzzy_score("scr", "screenshots").unwrap();
+        let word_start = fuzzy_score("scr", "old-screenshots").unwrap();
+        let substring = fuzzy_score("scr", "description").unwrap();
+        let scattered = fuzzy_score("scr", "sc_render").unwrap();
+        assert!(prefix > word_start && word_start > substring && substring > scattered);
+
+        assert_eq!(fuzzy_score("scr", "rcs"), None);
+        assert_eq!(fuzzy_score("", "screenshots"), None);
+    }
+
+    #[test]
+    fn test_rank_mixes_kinds_and_limits_results() {
+        let candidates = vec![
+            candidate(QuickFindKind::File, "Sunset.jpg"),
+            candidate(QuickFindKind::Tag, "sunset"),
+            candidate(QuickFindKind::Directory, "Ｓｕｎｓｅｔ"),
+            candidate(QuickFindKind::File, "beach_sun.png"),
+            candidate(QuickFindKind::File, "notes.txt"),
+        ];
+        let results = rank(" SUN ", candidates, 3);
+        let labels: Vec<_> = results.iter().map(|result| result.label.as_str()).collect();
+        // 同名的目录和标签得分相同，按类型排列
+        assert_eq!(labels, ["Ｓｕｎｓｅｔ", "sunset", "Sunset.jpg"]);
+    }
+}
@@ -0,0 +1,279 @@
+//! 只读模式模块
+//!
+//! 资料库位于共享网络驱动器上、被第二个用户同时打开时，两个进程同时写入会损坏目录数据库：
+//! - 可写入的进程持有应用数据目录下的写入锁，并在旁边的文件中记录持有者（用户名、计算机名、进程号）
+//!   和心跳时间，每 30 秒更新一次心跳
+//! - 拿不到写入锁、或锁文件可以锁定但其他进程的心跳仍在更新（部分网络驱动器不支持文件锁）时，
+//!   自动以只读模式打开，错误信息中说明资料库正被谁使用
+//! - 其他进程的心跳超过 2 分钟未更新，或文件锁已释放时，可以接管资料库；
+//!   被接管的进程在下次心跳时发现持有者已变，自动转为只读模式，两个进程不会交替写入
+//! - 也可用 `--read-only` 启动参数或 `set_read_only` 命令手动进入只读模式，手动进入时释放写入锁
+//! - 只读模式下修改资料库的命令返回 `read_only` 错误码，后台导入、抽检、打包等任务暂停；
+//!   数据库连接设置为 `query_only`，遗漏的写入也会被 SQLite 拒绝
+//! - 浏览、搜索、导出、备份等操作不受影响，记录搜索历史和使用统计等附带的写入直接跳过
+//! - 只读状态保存在 `FileManagerState` 中，通过 `get_backend_status` 返回
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::events::{self, LibraryTakenOver};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// 以只读模式启动的参数
+pub const READ_ONLY_ARG: &str = "--read-only";
+
+/// 写入锁文件，位于应用数据目录下
+const WRITER_LOCK_FILE: &str = "writer.lock";
+
+/// 记录写入锁持有者和心跳的文件；Windows 上被锁定的文件其他进程无法读取，因此单独存放
+const WRITER_OWNER_FILE: &str = "writer.owner";
+
+/// 心跳间隔
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 心跳超过该时长未更新时，认为持有者已退出
+const HEARTBEAT_STALE_AFTER: Duration = Duration::from_secs(120);
+
+/// 只读原因
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReadOnlyReason {
+    /// 通过启动参数或设置手动进入
+    Requested,
+    /// 其他进程正在写入，`holder` 为其记录的持有者
+    InUse {
+        holder: Option<String>,
+        /// 持有者最近一次心跳的时间
+        #[serde(default)]
+        heartbeat_at: Option<DateTime<Local>>,
+        /// 持有者可能已退出，可以通过 `take_over_library` 接管
+        #[serde(default)]
+        can_take_over: bool,
+    },
+}
+
+impl std::fmt::Display for ReadOnlyReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Requested => write!(f, "read-only mode is enabled"),
+            Self::InUse { holder: Some(holder), .. } => write!(f, "library is in use by {}", holder),
+            Self::InUse { holder: None, .. } => write!(f, "library is in use by another process"),
+        }
+    }
+}
+
+/// 写入锁持有者的记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct WriterInfo {
+    /// 每次获取写入锁时生成，用于判断是否已被其他进程接管
+    pub session_id: String,
+    pub user: String,
+    pub host: String,
+    pub pid: u32,
+    pub heartbeat_at: DateTime<Local>,
+}
+
+impl WriterInfo {
+    fn current() -> Self {
+        Self {
+            session_id: uuid::Uuid::new_v4().to_string(),
+            user: std::env::var("USERNAME").or_else(|_| std::env::var("USER")).unwrap_or_default(),
+            host: std::env::var("COMPUTERNAME").or_else(|_| std::env::var("HOSTNAME")).unwrap_or_default(),
+            pid: std::process::id(),
+            heartbeat_at: Local::now(),
+        }
+    }
+
+    /// 持有者的描述
+    pub fn holder(&self) -> String {
+        format!("{}@{} (pid {})", self.user, self.host, self.pid)
+    }
+
+    fn is_stale(&self, now: DateTime<Local>) -> bool {
+        (now - self.heartbeat_at).to_std().is_ok_and(|elapsed| elapsed > HEARTBEAT_STALE_AFTER)
+    }
+}
+
+/// 获取写入锁的结果
+#[derive(Debug)]
+pub enum WriterLock {
+    /// 本进程可以写入，退出前需一直持有
+    Acquired(WriterLease),
+    /// 其他进程正在写入
+    InUse {
+        holder: Option<String>,
+        heartbeat_at: Option<DateTime<Local>>,
+        can_take_over: bool,
+    },
+}
+
+impl WriterLock {
+    /// 其他进程正在写入时转换为只读原因
+    pub fn into_reason(self) -> std::result::Result<WriterLease, ReadOnlyReason> {
+        match self {
+            Self::Acquired(lease) => Ok(lease),
+            Self::InUse { holder, heartbeat_at, can_take_over } => {
+                Err(ReadOnlyReason::InUse { holder, heartbeat_at, can_take_over })
+            }
+        }
+    }
+}
+
+/// 持有中的写入锁，释放时删除持有者记录
+#[derive(Debug)]
+pub struct WriterLease {
+    /// 接管仍被锁定的资料库时没有文件锁，只靠心跳防止交替写入
+    _lock: Option<File>,
+    owner_path: PathBuf,
+    info: WriterInfo,
+}
+
+impl WriterLease {
+    /// 更新心跳；已被其他进程接管时返回新的持有者，此后不应再写入
+    pub fn heartbeat(&mut self) -> std::io::Result<Option<WriterInfo>> {
+        match read_owner(&self.owner_path) {
+            Some(owner) if owner.session_id != self.info.session_id => Ok(Some(owner)),
+            _ => {
+                self.info.heartbeat_at = Local::now();
+                write_owner(&self.owner_path, &self.info)?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl Drop for WriterLease {
+    fn drop(&mut self) {
+        if read_owner(&self.owner_path).is_some_and(|owner| owner.session_id == self.info.session_id) {
+            let _ = std::fs::remove_file(&self.owner_path);
+        }
+    }
+}
+
+/// 尝试获取资料库的写入锁，成功后记录持有者
+///
+/// 文件锁被占用或其他进程的心跳仍在更新时返回 [`WriterLock::InUse`]；
+/// `take_over` 为 `true` 时忽略可能已退出的持有者，文件锁被占用且心跳仍在更新时仍无法接管
+pub fn acquire_writer_lock(app_data_dir: &Path, take_over: bool) -> std::io::Result<WriterLock> {
+    std::fs::create_dir_all(app_data_dir)?;
+    let lock = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(app_data_dir.join(WRITER_LOCK_FILE))?;
+    let lock = match lock.try_lock() {
+        Ok(()) => Some(lock),
+        Err(std::fs::TryLockError::WouldBlock) => None,
+        Err(std::fs::TryLockError::Error(e)) => return Err(e),
+    };
+
+    let owner_path = app_data_dir.join(WRITER_OWNER_FILE);
+    let owner = read_owner(&owner_path);
+    let locked = lock.is_none();
+    let alive = owner.as_ref().is_some_and(|owner| !owner.is_stale(Local::now()));
+    if (locked && alive) || ((locked || alive) && !take_over) {
+        return Ok(WriterLock::InUse {
+            holder: owner.as_ref().map(WriterInfo::holder),
+            heartbeat_at: owner.map(|owner| owner.heartbeat_at),
+            can_take_over: !(locked && alive),
+        });
+    }
+
+    let info = WriterInfo::current();
+    write_owner(&owner_path, &info)?;
+    Ok(WriterLock::Acquired(WriterLease { _lock: lock, owner_path, info }))
+}
+
+fn read_owner(path: &Path) -> Option<WriterInfo> {
+    serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+}
+
+/// 先写入临时文件再重命名，其他进程不会读到写了一半的记录
+fn write_owner(path: &Path, info: &WriterInfo) -> std::io::Result<()> {
+    let temp_path = path.with_extension("owner.tmp");
+    std::fs::write(&temp_path, serde_json::to_vec(info)?)?;
+    std::fs::rename(&temp_path, path)
+}
+
+/// 启动后台心跳任务，发现资料库已被其他进程接管时转为只读模式并通知前端
+pub fn spawn_heartbeat_loop(app: AppHandle, state: FileManagerState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            match state.refresh_writer_lock().await {
+                Ok(Some(reason)) => {
+                    tracing::warn!("资料库已被其他进程接管，转为只读模式: {}", reason);
+                    events::emit(&app, &LibraryTakenOver(reason));
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("写入锁心跳更新失败: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_writer_lock_reports_holder() {
+        let temp_dir = TempDir::new().unwrap();
+        let WriterLock::Acquired(mut lease) = acquire_writer_lock(temp_dir.path(), false).unwrap() else {
+            panic!("first writer should get the lock");
+        };
+        assert_eq!(lease.heartbeat().unwrap(), None);
+
+        match acquire_writer_lock(temp_dir.path(), false).unwrap().into_reason() {
+            Err(reason @ ReadOnlyReason::InUse { holder: Some(_), heartbeat_at: Some(_), can_take_over: false }) => {
+                let holder = lease.info.holder();
+                assert!(holder.ends_with(&format!("(pid {})", std::process::id())));
+                assert_eq!(reason.to_string(), format!("library is in use by {}", holder));
+            }
+            other => panic!("unexpected lock result: {:?}", other),
+        }
+
+        drop(lease);
+        assert!(matches!(acquire_writer_lock(temp_dir.path(), false).unwrap(), WriterLock::Acquired(_)));
+    }
+
+    #[test]
+    fn test_take_over_stale_writer() {
+        let temp_dir = TempDir::new().unwrap();
+        let WriterLock::Acquired(mut first) = acquire_writer_lock(temp_dir.path(), false).unwrap() else {
+            panic!("first writer should get the lock");
+        };
+        // 文件锁被占用且心跳仍在更新时无法接管
+        assert!(matches!(
+            acquire_writer_lock(temp_dir.path(), true).unwrap(),
+            WriterLock::InUse { can_take_over: false, .. }
+        ));
+
+        let mut stale = first.info.clone();
+        stale.heartbeat_at = Local::now() - chrono::Duration::minutes(5);
+        write_owner(&first.owner_path, &stale).unwrap();
+        assert!(matches!(
+            acquire_writer_lock(temp_dir.path(), false).unwrap(),
+            WriterLock::InUse { can_take_over: true, .. }
+        ));
+        let WriterLock::Acquired(second) = acquire_writer_lock(temp_dir.path(), true).unwrap() else {
+            panic!("stale writer should be taken over");
+        };
+
+        // 原持有者下次心跳时发现已被接管，释放时不删除新持有者的记录
+        assert_eq!(first.heartbeat().unwrap(), Some(second.info.clone()));
+        drop(first);
+        assert_eq!(read_owner(&second.owner_path), Some(second.info.clone()));
+        drop(second);
+        assert_eq!(read_owner(&temp_dir.path().join(WRITER_OWNER_FILE)), None);
+    }
+}
@@ -0,0 +1,243 @@
+//! 响应大小保护模块
+//!
+//! 防止单个命令一次返回过多数据（如包含数万个文件的目录）导致前端渲染进程卡顿：
+//! - 列表命令的响应超过大小上限时只返回第一页，并在响应中附带续传信息和警告
+//! - 其余条目暂存在进程内，前端通过 `get_listing_page` 按偏移量分页获取
+//! - 大小上限保存在数据库中，启动时加载到进程内
+//! - 暂存的列表过期或数量超出上限时丢弃最旧的，低内存模式下最多暂存 2 个
+
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::i18n::{current_locale, Locale};
+use crate::file_manager::low_memory::{self, LOW_MEMORY_CACHED_LISTINGS};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 保存响应大小上限设置的状态键
+pub const RESPONSE_LIMIT_KEY: &str = "response_limit";
+
+/// 默认的单个响应大小上限（字节）
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 4 * 1024 * 1024;
+
+/// 可设置的最小上限，避免每页只有极少条目
+pub const MIN_MAX_RESPONSE_BYTES: usize = 64 * 1024;
+
+/// 暂存列表的有效期
+const LISTING_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// 最多暂存的列表数
+const MAX_CACHED_LISTINGS: usize = 8;
+
+/// 当前的响应大小上限
+static MAX_RESPONSE_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_RESPONSE_BYTES);
+
+/// 等待分页获取的列表
+static LISTINGS: Mutex<Vec<CachedListing>> = Mutex::new(Vec::new());
+
+/// 响应大小上限设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ResponseLimitSettings {
+    pub max_response_bytes: usize,
+}
+
+impl Default for ResponseLimitSettings {
+    fn default() -> Self {
+        Self { max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES }
+    }
+}
+
+/// 获取当前的响应大小上限
+pub fn max_response_bytes() -> usize {
+    MAX_RESPONSE_BYTES.load(Ordering::Relaxed)
+}
+
+/// 设置响应大小上限
+pub fn set_max_response_bytes(bytes: usize) {
+    MAX_RESPONSE_BYTES.store(bytes.max(MIN_MAX_RESPONSE_BYTES), Ordering::Relaxed);
+}
+
+/// 分页续传信息
+///
+/// 响应被截断时附带在响应中，`next_offset` 为下一页在完整列表中的起始位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ListingContinuation {
+    pub listing_id: String,
+    pub next_offset: usize,
+    /// 完整列表的条目数
+    pub total: usize,
+    /// 按当前语言生成的警告信息
+    pub warning: String,
+}
+
+/// 暂存的列表，只保存第一页之后的条目
+struct CachedListing {
+    id: String,
+    /// `items[0]` 在完整列表中的位置
+    base_offset: usize,
+    items: Vec<serde_json::Value>,
+    created_at: Instant,
+}
+
+impl CachedListing {
+    fn total(&self) -> usize {
+        self.base_offset + self.items.len()
+    }
+}
+
+/// 按当前上限检查列表响应
+///
+/// 未超出上限时原样返回；超出时返回第一页和续传信息，其余条目暂存等待分页获取
+pub fn guard_listing<T: Serialize>(items: Vec<T>) -> (Vec<T>, Option<ListingContinuation>) {
+    guard_listing_with_limit(items, max_response_bytes())
+}
+
+/// 按当前上限获取暂存列表中从 `offset` 开始的一页
+///
+/// 最后一页取出后丢弃暂存的列表
+pub fn listing_page(listing_id: &str, offset: usize) -> Result<(Vec<serde_json::Value>, Option<ListingContinuation>)> {
+    listing_page_with_limit(listing_id, offset, max_response_bytes())
+}
+
+fn guard_listing_with_limit<T: Serialize>(mut items: Vec<T>, limit: usize) -> (Vec<T>, Option<ListingContinuation>) {
+    let sizes: Vec<usize> = items.iter().map(serialized_size).collect();
+    if sizes.iter().sum::<usize>() <= limit {
+        return (items, None);
+    }
+
+    let count = fitting_count(&sizes, limit);
+    let rest: Vec<serde_json::Value> = items
+        .split_off(count)
+        .iter()
+        .map(|item| serde_json::to_value(item).unwrap_or(serde_json::Value::Null))
+        .collect();
+
+    let listing = CachedListing {
+        id: uuid::Uuid::new_v4().to_string(),
+        base_offset: count,
+        items: rest,
+        created_at: Instant::now(),
+    };
+    let continuation = continuation_for(&listing, count);
+    tracing::warn!("列表响应超出大小上限 {} 字节，改为分页返回: 共 {} 项，首页 {} 项",
+        limit, listing.total(), count);
+
+    let mut listings = LISTINGS.lock().unwrap();
+    prune_expired(&mut listings);
+    let max_listings = if low_memory::is_enabled() { LOW_MEMORY_CACHED_LISTINGS } else { MAX_CACHED_LISTINGS };
+    while listings.len() >= max_listings {
+        listings.remove(0);
+    }
+    listings.push(listing);
+
+    (items, Some(continuation))
+}
+
+fn listing_page_with_limit(
+    listing_id: &str,
+    offset: usize,
+    limit: usize,
+) -> Result<(Vec<serde_json::Value>, Option<ListingContinuation>)> {
+    let mut listings = LISTINGS.lock().unwrap();
+    prune_expired(&mut listings);
+
+    let index = listings.iter().position(|listing| listing.id == listing_id)
+        .ok_or_else(|| FileManagerError::general_error(format!("Listing not found or expired: {}", listing_id)))?;
+    let listing = &listings[index];
+    if offset < listing.base_offset || offset >= listing.total() {
+        return Err(FileManagerError::general_error(format!(
+            "Listing offset {} out of range {}..{}", offset, listing.base_offset, listing.total()
+        )));
+    }
+
+    let remaining = &listing.items[offset - listing.base_offset..];
+    let sizes: Vec<usize> = remaining.iter().map(serialized_size).collect();
+    let count = fitting_count(&sizes, limit);
+    let page = remaining[..count].to_vec();
+
+    let next_offset = offset + count;
+    if next_offset >= listing.total() {
+        listings.remove(index);
+        return Ok((page, None));
+    }
+    Ok((page, Some(continuation_for(listing, next_offset))))
+}
+
+/// 条目序列化后的字节数，含数组中的分隔符
+fn serialized_size<T: Serialize>(item: &T) -> usize {
+    serde_json::to_vec(item).map_or(0, |bytes| bytes.len()) + 1
+}
+
+/// 不超出上限的前缀条目数，至少为一项
+fn fitting_count(sizes: &[usize], limit: usize) -> usize {
+    let mut total = 0;
+    let count = sizes.iter()
+        .take_while(|size| {
+            total += **size;
+            total <= limit
+        })
+        .count();
+    count.max(1).min(sizes.len())
+}
+
+fn continuation_for(listing: &CachedListing, next_offset: usize) -> ListingContinuation {
+    let total = listing.total();
+    ListingContinuation {
+        listing_id: listing.id.clone(),
+        next_offset,
+        total,
+        warning: truncated_warning(next_offset, total, current_locale()),
+    }
+}
+
+fn truncated_warning(delivered: usize, total: usize, locale: Locale) -> String {
+    match locale {
+        Locale::En => format!("Response too large, showing {} of {} items; load the rest page by page", delivered, total),
+        Locale::ZhCn => format!("响应过大，已显示 {} 项，共 {} 项，其余部分分页加载", delivered, total),
+    }
+}
+
+fn prune_expired(listings: &mut Vec<CachedListing>) {
+    listings.retain(|listing| listing.created_at.elapsed() < LISTING_TTL);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_listing_is_returned_whole() {
+        let items = vec!["a".to_string(), "b".to_string()];
+        let (page, continuation) = guard_listing_with_limit(items.clone(), 1024);
+        assert_eq!(page, items);
+        assert!(continuation.is_none());
+    }
+
+    #[test]
+    fn test_oversized_listing_is_paginated() {
+        // 每项序列化为 12 字节（含引号和分隔符），上限 40 字节每页 3 项
+        let items: Vec<String> = (0..8).map(|i| format!("item-{:04}", i)).collect();
+        let (first, continuation) = guard_listing_with_limit(items.clone(), 40);
+        assert_eq!(first, items[..3]);
+
+        let mut continuation = continuation.unwrap();
+        assert_eq!((continuation.next_offset, continuation.total), (3, 8));
+
+        let mut collected: Vec<String> = first;
+        loop {
+            let (page, next) = listing_page_with_limit(&continuation.listing_id, continuation.next_offset, 40).unwrap();
+            collected.extend(page.into_iter().map(|value| value.as_str().unwrap().to_string()));
+            match next {
+                Some(next) => continuation = next,
+                None => break,
+            }
+        }
+        assert_eq!(collected, items);
+
+        // 最后一页取出后列表被丢弃
+        assert!(listing_page_with_limit(&continuation.listing_id, 6, 40).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+//! 瞬时错误重试模块
+//!
+//! 杀毒软件扫描、索引服务或同步工具会短暂占用刚写入的文件，此时文件操作失败
+//! （Windows 上的共享冲突、Unix 上的 EBUSY）；其他进程写入数据库时 SQLite 返回 `SQLITE_BUSY`。
+//! 这类错误通常在几百毫秒内消失，不应直接报告给用户：
+//! - [`FileSystemService`] 的写入、移动、复制和删除遇到瞬时错误时按指数退避重试，
+//!   间隔从 50 毫秒开始翻倍，单次不超过 1 秒，最多重试 5 次
+//! - [`DatabaseService`] 的连接注册忙等待回调，遇到 `SQLITE_BUSY` 时按同样的策略等待后重试，
+//!   事务和预编译语句中的语句同样生效，各个方法无需改动
+//! - 文件不存在、权限不足、磁盘已满等非瞬时错误立即返回
+//! - 开启 `error_handling.log_retry_attempts` 时在 WARN 级别记录每次重试
+//! - 资料库位于网络共享上时延长重试窗口：单次等待不超过 5 秒，最多重试 10 次
+//!
+//! [`FileSystemService`]: crate::file_manager::filesystem::FileSystemService
+//! [`DatabaseService`]: crate::file_manager::database::DatabaseService
+
+use rusqlite::Connection;
+use std::future::Future;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// 首次失败后的最多重试次数
+pub const MAX_RETRIES: u32 = 5;
+
+/// 第一次重试前的等待时间
+const INITIAL_DELAY: Duration = Duration::from_millis(50);
+
+/// 单次等待的上限
+const MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// 网络共享模式下的最多重试次数
+pub const NETWORK_MAX_RETRIES: u32 = 10;
+
+/// 网络共享模式下单次等待的上限
+const NETWORK_MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// Windows 上文件被其他进程打开时的错误码
+#[cfg(windows)]
+const ERROR_SHARING_VIOLATION: i32 = 32;
+#[cfg(windows)]
+const ERROR_LOCK_VIOLATION: i32 = 33;
+
+static LOG_ATTEMPTS: AtomicBool = AtomicBool::new(true);
+
+static EXTENDED_WINDOW: AtomicBool = AtomicBool::new(false);
+
+/// 设置是否记录每次重试
+pub fn set_log_attempts(enabled: bool) {
+    LOG_ATTEMPTS.store(enabled, Ordering::Relaxed);
+}
+
+/// 设置是否使用网络共享的重试窗口
+pub fn set_extended_window(enabled: bool) {
+    EXTENDED_WINDOW.store(enabled, Ordering::Relaxed);
+}
+
+/// 当前的最多重试次数和单次等待上限
+fn retry_window() -> (u32, Duration) {
+    if EXTENDED_WINDOW.load(Ordering::Relaxed) {
+        (NETWORK_MAX_RETRIES, NETWORK_MAX_DELAY)
+    } else {
+        (MAX_RETRIES, MAX_DELAY)
+    }
+}
+
+/// 第 `retry` 次重试（从 0 开始）前的等待时间
+pub fn backoff_delay(retry: u32) -> Duration {
+    capped_delay(retry, retry_window().1)
+}
+
+fn capped_delay(retry: u32, max_delay: Duration) -> Duration {
+    INITIAL_DELAY.saturating_mul(1 << retry.min(16)).min(max_delay)
+}
+
+/// 是否为文件被临时占用等可以重试的错误
+pub fn is_transient_io_error(error: &std::io::Error) -> bool {
+    if matches!(
+        error.kind(),
+        ErrorKind::ResourceBusy | ErrorKind::ExecutableFileBusy | ErrorKind::WouldBlock | ErrorKind::Interrupted
+    ) {
+        return true;
+    }
+    #[cfg(windows)]
+    if matches!(error.raw_os_error(), Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)) {
+        return true;
+    }
+    false
+}
+
+/// 执行文件操作，遇到瞬时错误时等待后重试
+///
+/// `operation` 和 `path` 只用于日志
+pub async fn retry_io<T, F, Fut>(operation: &str, path: &Path, mut f: F) -> std::io::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::io::Result<T>>,
+{
+    let (max_retries, max_delay) = retry_window();
+    let mut retry = 0;
+    loop {
+        match f().await {
+            Err(error) if retry < max_retries && is_transient_io_error(&error) => {
+                let delay = capped_delay(retry, max_delay);
+                retry += 1;
+                if LOG_ATTEMPTS.load(Ordering::Relaxed) {
+                    tracing::warn!("{}遇到瞬时错误，{} 毫秒后重试（第 {}/{} 次）: {:?}, {}",
+                        operation, delay.as_millis(), retry, max_retries, path, error);
+                }
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// SQLite 的忙等待回调，`retry` 为此前已调用的次数；返回 `false` 时语句以 `SQLITE_BUSY` 失败
+///
+/// 在持有连接的线程中同步等待
+fn sqlite_busy_handler(retry: i32) -> bool {
+    let retry = retry.max(0) as u32;
+    let (max_retries, max_delay) = retry_window();
+    if retry >= max_retries {
+        return false;
+    }
+
+    let delay = capped_delay(retry, max_delay);
+    if LOG_ATTEMPTS.load(Ordering::Relaxed) {
+        tracing::warn!("数据库被其他连接占用，{} 毫秒后重试（第 {}/{} 次）", delay.as_millis(), retry + 1, max_retries);
+    }
+    std::thread::sleep(delay);
+    true
+}
+
+/// 为连接注册忙等待回调，需在执行任何语句之前调用
+pub fn install(connection: &Connection) -> rusqlite::Result<()> {
+    connection.busy_handler(Some(sqlite_busy_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_retry_io_only_retries_transient_errors() {
+        assert_eq!(backoff_delay(0), Duration::from_millis(50));
+        assert_eq!(backoff_delay(2), Duration::from_millis(200));
+        assert_eq!(capped_delay(10, MAX_DELAY), MAX_DELAY);
+        assert_eq!(capped_delay(6, NETWORK_MAX_DELAY), Duration::from_millis(3200));
+        assert_eq!(capped_delay(10, NETWORK_MAX_DELAY), NETWORK_MAX_DELAY);
+
+        let calls = AtomicU32::new(0);
+        let result = retry_io("测试", Path::new("busy"), || async {
+            match calls.fetch_add(1, Ordering::Relaxed) {
+                0 | 1 => Err(std::io::Error::from(ErrorKind::ResourceBusy)),
+                _ => Ok(7),
+            }
+        }).await;
+        assert_eq!(result.unwrap(), 7);
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+
+        calls.store(0, Ordering::Relaxed);
+        let result: std::io::Result<()> = retry_io("测试", Path::new("missing"), || async {
+            calls.fetch_add(1, Ordering::Relaxed);
+            Err(std::io::Error::from(ErrorKind::NotFound))
+        }).await;
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::NotFound);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_busy_handler_waits_for_writer() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("busy.db");
+        let writer = Connection::open(&path).unwrap();
+        writer.execute_batch("CREATE TABLE items (id INTEGER); BEGIN IMMEDIATE; INSERT INTO items VALUES (1);").unwrap();
+
+        let reader = Connection::open(&path).unwrap();
+        install(&reader).unwrap();
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            writer.execute_batch("COMMIT").unwrap();
+        });
+
+        // 写入锁释放前语句一直等待，而不是立即返回 SQLITE_BUSY
+        reader.execute("INSERT INTO items VALUES (2)", []).unwrap();
+        release.join().unwrap();
+        let count: i64 = reader.query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 2);
+    }
+}
@@ -0,0 +1,46 @@
+//! 乐观并发控制模块
+//!
+//! 两个打开的窗口（或协作端）可能基于同一份旧数据修改同一个文件或目录：
+//! - 文件和目录响应中的 `updated_at` 作为版本标记，每次修改记录时更新
+//! - 重命名、移动和删除命令要求带上调用方最后看到的 `updated_at`
+//! - 与当前记录不一致时返回 [`FileManagerError::Conflict`]，调用方应刷新后重试
+//! - 检查和修改在同一次持有服务锁期间完成，中间不会插入其他修改
+
+use crate::file_manager::error::{FileManagerError, Result};
+use chrono::{DateTime, Local};
+
+/// 检查调用方持有的版本与记录的 `updated_at` 一致
+///
+/// 按时间点比较，时区写法不同但表示同一时刻的版本视为一致
+pub fn check(id: &str, expected: &str, actual: &DateTime<Local>) -> Result<()> {
+    let matches = DateTime::parse_from_rfc3339(expected.trim())
+        .map(|expected| expected == *actual)
+        .unwrap_or(false);
+    if matches {
+        return Ok(());
+    }
+
+    Err(FileManagerError::Conflict {
+        id: id.to_string(),
+        expected: expected.to_string(),
+        actual: actual.to_rfc3339(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_revision() {
+        let updated_at = DateTime::parse_from_rfc3339("2024-05-01T10:00:00.123456+08:00").unwrap().with_timezone(&Local);
+
+        assert!(check("f1", &updated_at.to_rfc3339(), &updated_at).is_ok());
+        assert!(check("f1", "2024-05-01T02:00:00.123456Z", &updated_at).is_ok());
+        assert!(matches!(
+            check("f1", "2024-05-01T10:00:00+08:00", &updated_at),
+            Err(FileManagerError::Conflict { id, .. }) if id == "f1"
+        ));
+        assert!(matches!(check("f1", "yesterday", &updated_at), Err(FileManagerError::Conflict { .. })));
+    }
+}
@@ -0,0 +1,125 @@
+//! 屏幕截图模块
+//!
+//! 截取屏幕或当前活动窗口，保存为库中的 PNG 文件：
+//! - 全屏模式截取主显示器
+//! - 窗口模式截取最上层的其他应用窗口（跳过本应用自身的窗口）
+//! - 文件名按截图时间自动生成
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::service::UploadResponse;
+use chrono::{DateTime, Local};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::io::Cursor;
+
+/// 截图方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureMode {
+    /// 主显示器全屏
+    #[default]
+    FullScreen,
+    /// 当前活动窗口
+    ActiveWindow,
+}
+
+/// 截取屏幕图像
+///
+/// 调用系统截图接口，属于阻塞操作
+pub fn capture(mode: CaptureMode) -> Result<RgbaImage> {
+    match mode {
+        CaptureMode::FullScreen => {
+            let monitors = xcap::Monitor::all().map_err(capture_error)?;
+            let monitor = monitors.iter()
+                .find(|monitor| monitor.is_primary())
+                .or(monitors.first())
+                .ok_or_else(|| FileManagerError::general_error("No monitor found"))?;
+            monitor.capture_image().map_err(capture_error)
+        }
+        CaptureMode::ActiveWindow => {
+            // 窗口按层叠顺序从上到下排列，跳过本应用自身和不可见的窗口
+            let windows = xcap::Window::all().map_err(capture_error)?;
+            let window = windows.iter()
+                .find(|window| {
+                    !is_own_window(window)
+                        && !window.is_minimized()
+                        && !window.title().is_empty()
+                        && window.width() > 0
+                        && window.height() > 0
+                })
+                .ok_or_else(|| FileManagerError::general_error("No active window found"))?;
+            window.capture_image().map_err(capture_error)
+        }
+    }
+}
+
+/// 截图并保存为库中的新文件
+///
+/// 截图和编码期间不持有服务锁
+pub async fn capture_into_library(
+    state: &FileManagerState,
+    mode: CaptureMode,
+    directory_id: Option<String>,
+    tags: Vec<String>,
+) -> Result<UploadResponse> {
+    // 只读模式下不必截图
+    state.lock().await?.ensure_writable()?;
+
+    let taken_at = Local::now();
+    let png_data = tokio::task::spawn_blocking(move || capture(mode).and_then(|image| encode_png(&image)))
+        .await
+        .map_err(|e| FileManagerError::general_error(format!("Screenshot task failed: {}", e)))??;
+
+    let service = state.lock().await?;
+    service.import_screenshot(png_data, screenshot_file_name(taken_at), directory_id, tags).await
+}
+
+/// 将截图编码为 PNG
+pub fn encode_png(image: &RgbaImage) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    image.write_to(&mut Cursor::new(&mut data), image::ImageFormat::Png)
+        .map_err(|e| FileManagerError::general_error(format!("Failed to encode screenshot: {}", e)))?;
+    Ok(data)
+}
+
+/// 按截图时间生成文件名，如 `Screenshot 2024-03-04 153012.png`
+pub fn screenshot_file_name(taken_at: DateTime<Local>) -> String {
+    format!("Screenshot {}.png", taken_at.format("%Y-%m-%d %H%M%S"))
+}
+
+/// 判断窗口是否属于本应用
+#[cfg(target_os = "windows")]
+fn is_own_window(window: &xcap::Window) -> bool {
+    window.process_id() == std::process::id()
+}
+
+/// 判断窗口是否属于本应用（其他平台没有进程 ID，按应用名与可执行文件名比较）
+#[cfg(not(target_os = "windows"))]
+fn is_own_window(window: &xcap::Window) -> bool {
+    std::env::current_exe().ok()
+        .and_then(|exe| exe.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .is_some_and(|name| window.app_name().eq_ignore_ascii_case(&name))
+}
+
+fn capture_error(e: xcap::XCapError) -> FileManagerError {
+    FileManagerError::general_error(format!("Screen capture failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_screenshot_file_name_and_encoding() {
+        let taken_at = Local.with_ymd_and_hms(2024, 3, 4, 15, 30, 12).unwrap();
+        assert_eq!(screenshot_file_name(taken_at), "Screenshot 2024-03-04 153012.png");
+
+        let image = RgbaImage::from_pixel(2, 2, image::Rgba([1, 2, 3, 255]));
+        let png = encode_png(&image).unwrap();
+        assert_eq!(image::load_from_memory(&png).unwrap().to_rgba8(), image);
+    }
+}
@@ -0,0 +1,155 @@
+//! 搜索历史和搜索建议模块
+//!
+//! 为输入即搜索的搜索框提供数据：
+//! - 每次搜索记录到资料库的搜索历史中，相同关键词只保留一条并累计次数
+//! - 输入前缀时依次合并搜索历史、标签名和文件名中的常见词作为建议
+//! - 比较时使用 [`search_key`]，忽略大小写和全角/半角差异
+
+use crate::file_manager::paths::search_key;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// 搜索历史最多保留的条数
+pub const MAX_SEARCH_HISTORY: usize = 100;
+
+/// 默认返回的建议数
+pub const DEFAULT_SUGGESTION_LIMIT: usize = 10;
+
+/// 建议数上限
+pub const MAX_SUGGESTION_LIMIT: usize = 50;
+
+/// 文件名中作为建议的词的最小长度
+const MIN_TOKEN_LEN: usize = 2;
+
+/// 搜索历史记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SearchHistoryEntry {
+    pub query: String,
+    pub use_count: i64,
+    pub last_used_at: String,
+}
+
+/// 建议来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionSource {
+    History,
+    Tag,
+    FileName,
+}
+
+/// 搜索建议
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SearchSuggestion {
+    pub text: String,
+    pub source: SuggestionSource,
+    /// 搜索次数、使用该标签的文件数或包含该词的文件数
+    pub count: i64,
+}
+
+/// 拆分文件名（不含扩展名）中的词
+///
+/// 按非字母数字字符拆分，忽略过短的词和纯数字（如日期、序号）
+pub fn file_name_tokens(name: &str) -> Vec<String> {
+    let stem = Path::new(name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+
+    stem.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| token.chars().count() >= MIN_TOKEN_LEN)
+        .filter(|token| !token.chars().all(|c| c.is_ascii_digit()))
+        .map(search_key)
+        .collect()
+}
+
+/// 合并搜索建议
+///
+/// 历史记录按最近使用排在最前，其后是标签和文件名中的词（按出现次数）；
+/// 相同文本只保留第一条
+pub fn merge_suggestions<'a>(
+    prefix: &str,
+    history: &[SearchHistoryEntry],
+    tag_counts: &[(String, i64)],
+    file_names: impl IntoIterator<Item = &'a str>,
+    limit: usize,
+) -> Vec<SearchSuggestion> {
+    let prefix = search_key(prefix.trim());
+    let mut seen = HashSet::new();
+    let mut suggestions = Vec::new();
+
+    let mut push = |text: &str, source: SuggestionSource, count: i64| {
+        let key = search_key(text);
+        if key.starts_with(&prefix) && seen.insert(key) {
+            suggestions.push(SearchSuggestion { text: text.to_string(), source, count });
+        }
+    };
+
+    for entry in history {
+        push(&entry.query, SuggestionSource::History, entry.use_count);
+    }
+
+    let mut tags: Vec<_> = tag_counts.iter().collect();
+    tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    for (tag, count) in tags {
+        push(tag, SuggestionSource::Tag, *count);
+    }
+
+    // 每个文件中的同一个词只计一次
+    let mut token_counts: HashMap<String, i64> = HashMap::new();
+    for name in file_names {
+        let tokens: HashSet<String> = file_name_tokens(name).into_iter().collect();
+        for token in tokens {
+            if token.starts_with(&prefix) {
+                *token_counts.entry(token).or_default() += 1;
+            }
+        }
+    }
+    let mut tokens: Vec<_> = token_counts.into_iter().collect();
+    tokens.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    for (token, count) in tokens {
+        push(&token, SuggestionSource::FileName, count);
+    }
+
+    suggestions.truncate(limit);
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name_tokens() {
+        assert_eq!(file_name_tokens("Beach_Sunset-2024 01.JPG"), ["beach", "sunset"]);
+        assert_eq!(file_name_tokens("海边 日落.png"), ["海边", "日落"]);
+        assert!(file_name_tokens("a.txt").is_empty());
+    }
+
+    #[test]
+    fn test_merge_suggestions() {
+        let history = vec![SearchHistoryEntry {
+            query: "Sunset".to_string(),
+            use_count: 3,
+            last_used_at: "2024-01-01T00:00:00+00:00".to_string(),
+        }];
+        let tags = vec![("summer".to_string(), 2), ("sunset".to_string(), 5), ("beach".to_string(), 9)];
+        let names = ["sunny_day.jpg", "sunny-beach.jpg", "sunset.jpg"];
+
+        let suggestions = merge_suggestions("SU", &history, &tags, names, 10);
+        let texts: Vec<_> = suggestions.iter().map(|s| (s.text.as_str(), s.source)).collect();
+        assert_eq!(texts, [
+            ("Sunset", SuggestionSource::History),
+            ("summer", SuggestionSource::Tag),
+            ("sunny", SuggestionSource::FileName),
+        ]);
+        assert_eq!(suggestions[2].count, 2);
+
+        assert_eq!(merge_suggestions("su", &history, &tags, names, 1).len(), 1);
+    }
+}
@@ -0,0 +1,394 @@
+//! 资源管理器集成模块
+//!
+//! 在资源管理器的右键菜单中添加“Send to Collaboard”，把选中的文件导入收件箱：
+//! - 安装程序在当前用户的注册表中登记菜单项和 `collaboard://` 深度链接协议，卸载时删除；
+//!   设置中也可随时开关菜单项（仅 Windows）
+//! - 菜单项以 `--send-to <文件>` 参数启动应用，深度链接 `collaboard://import?path=<编码后的路径>` 效果相同
+//! - 资源管理器为选中的每个文件各启动一个进程；只有拿到实例锁的进程成为主实例，
+//!   其余进程把文件写入启动请求目录后立即退出，主实例导入这些文件并激活窗口
+//! - 双击 `.cboard` 画板文件时文件路径作为普通参数传入，主实例打开画板而不是导入文件
+//! - 启动请求以文件形式交接，主实例尚未初始化完成时请求也不会丢失
+
+use crate::file_manager::board_bundle::is_board_file;
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::events::{self, BoardOpened, SendToImport};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// 右键菜单启动应用时的参数
+pub const SEND_TO_ARG: &str = "--send-to";
+
+/// 深度链接协议
+pub const DEEP_LINK_SCHEME: &str = "collaboard";
+
+/// 启动请求目录，位于应用数据目录下
+pub const LAUNCH_REQUESTS_DIR: &str = "launch-requests";
+
+/// 实例锁文件，位于应用数据目录下
+const INSTANCE_LOCK_FILE: &str = "instance.lock";
+
+/// 主实例检查启动请求的间隔
+pub const LAUNCH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 右键菜单的注册表项
+#[cfg(windows)]
+const CONTEXT_MENU_KEY: &str = r"HKCU\Software\Classes\*\shell\Collaboard";
+
+/// 深度链接协议的注册表项
+#[cfg(windows)]
+const URL_SCHEME_KEY: &str = r"HKCU\Software\Classes\collaboard";
+
+/// 资源管理器集成的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ShellIntegrationStatus {
+    /// 当前平台是否支持右键菜单
+    pub supported: bool,
+    pub context_menu_enabled: bool,
+}
+
+/// 其他进程交给主实例的启动请求，都为空时只激活窗口
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LaunchRequest {
+    /// 要导入收件箱的文件
+    pub paths: Vec<PathBuf>,
+    /// 要打开的画板文件
+    #[serde(default)]
+    pub boards: Vec<PathBuf>,
+}
+
+impl LaunchRequest {
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.boards.is_empty()
+    }
+}
+
+/// 通过右键菜单或深度链接导入文件的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SendToReport {
+    /// 导入的文件 ID
+    pub imported: Vec<String>,
+    /// 导入失败的路径
+    pub failed: Vec<String>,
+}
+
+/// 从命令行参数中解析要导入的文件和要打开的画板，参数不包含程序名
+///
+/// `--send-to` 之后的参数都是要导入的文件；深度链接中的每个 `path` 参数是一个要导入的文件；
+/// 其余以 `.cboard` 结尾的参数是要打开的画板
+pub fn parse_launch_args<I>(args: I) -> LaunchRequest
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut request = LaunchRequest::default();
+    let mut send_to = false;
+    for arg in args {
+        if arg == SEND_TO_ARG {
+            send_to = true;
+        } else if let Some(link) = strip_scheme(&arg) {
+            request.paths.extend(parse_deep_link(link));
+        } else if send_to && !arg.starts_with("--") {
+            request.paths.push(PathBuf::from(arg));
+        } else if is_board_file(Path::new(&arg)) {
+            request.boards.push(PathBuf::from(arg));
+        }
+    }
+    request
+}
+
+/// 去掉 `collaboard://` 前缀，协议名不区分大小写
+fn strip_scheme(arg: &str) -> Option<&str> {
+    let prefix_len = DEEP_LINK_SCHEME.len() + 3;
+    let prefix = arg.get(..prefix_len)?;
+    if prefix.eq_ignore_ascii_case(&format!("{}://", DEEP_LINK_SCHEME)) {
+        Some(&arg[prefix_len..])
+    } else {
+        None
+    }
+}
+
+/// 解析 `import?path=...&path=...`，不认识的动作和参数忽略
+fn parse_deep_link(link: &str) -> Vec<PathBuf> {
+    let (action, query) = link.split_once('?').unwrap_or((link, ""));
+    if !action.trim_end_matches('/').eq_ignore_ascii_case("import") {
+        tracing::warn!("未知的深度链接: {}://{}", DEEP_LINK_SCHEME, link);
+        return Vec::new();
+    }
+
+    query.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(key, _)| *key == "path")
+        .filter_map(|(_, value)| percent_decode(value))
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// 解码 URL 查询参数，`+` 视为空格；编码无效时返回 `None`
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// 尝试成为主实例
+///
+/// 成功时返回实例锁，主实例退出前需一直持有；已有主实例运行时返回 `None`
+pub fn acquire_instance_lock(app_data_dir: &Path) -> std::io::Result<Option<File>> {
+    std::fs::create_dir_all(app_data_dir)?;
+    let lock = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(app_data_dir.join(INSTANCE_LOCK_FILE))?;
+    match lock.try_lock() {
+        Ok(()) => Ok(Some(lock)),
+        Err(std::fs::TryLockError::WouldBlock) => Ok(None),
+        Err(std::fs::TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// 写入启动请求，先写临时文件再重命名，主实例不会读到写了一半的请求
+pub fn enqueue_launch_request(app_data_dir: &Path, request: &LaunchRequest) -> std::io::Result<()> {
+    let dir = app_data_dir.join(LAUNCH_REQUESTS_DIR);
+    std::fs::create_dir_all(&dir)?;
+
+    // 文件名以时间开头，按名称排序即为请求顺序
+    let name = format!("{}-{}", chrono::Local::now().format("%Y%m%d%H%M%S%f"), uuid::Uuid::new_v4());
+    let temp_path = dir.join(format!("{}.tmp", name));
+    std::fs::write(&temp_path, serde_json::to_vec(request)?)?;
+    std::fs::rename(&temp_path, dir.join(format!("{}.json", name)))
+}
+
+/// 取出所有启动请求并删除对应文件，按写入顺序返回；无法解析的请求直接删除
+pub fn take_launch_requests(app_data_dir: &Path) -> Vec<LaunchRequest> {
+    let Ok(entries) = std::fs::read_dir(app_data_dir.join(LAUNCH_REQUESTS_DIR)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+
+    files.into_iter()
+        .filter_map(|path| {
+            let request = std::fs::read(&path).ok()
+                .and_then(|data| serde_json::from_slice::<LaunchRequest>(&data).ok());
+            if request.is_none() {
+                tracing::warn!("启动请求无法解析: {:?}", path);
+            }
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::warn!("启动请求删除失败: {:?}, {}", path, e);
+            }
+            request
+        })
+        .collect()
+}
+
+/// 定期处理启动请求：导入文件、打开画板、激活主窗口并通知前端
+pub fn spawn_launch_request_loop(app: AppHandle, state: FileManagerState, app_data_dir: PathBuf) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(LAUNCH_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let requests = take_launch_requests(&app_data_dir);
+            if requests.is_empty() {
+                continue;
+            }
+            activate_main_window(&app);
+
+            let mut paths = Vec::new();
+            let mut boards = Vec::new();
+            for request in requests {
+                paths.extend(request.paths);
+                boards.extend(request.boards);
+            }
+            paths.dedup();
+            boards.dedup();
+            if paths.is_empty() && boards.is_empty() {
+                continue;
+            }
+
+            let Ok(service) = state.lock().await else {
+                continue;
+            };
+            if let Err(e) = service.ensure_writable() {
+                tracing::warn!("资料库为只读模式，忽略右键菜单和画板文件: {}", e);
+                continue;
+            }
+            if !paths.is_empty() {
+                match service.import_local_files(&paths).await {
+                    Ok(report) => {
+                        tracing::info!("右键菜单导入: 成功 {} 个, 失败 {} 个", report.imported.len(), report.failed.len());
+                        events::emit(&app, &SendToImport(report));
+                    }
+                    Err(e) => tracing::warn!("右键菜单导入失败: {}", e),
+                }
+            }
+            for board in boards {
+                match service.open_board(&board).await {
+                    Ok(opened) => events::emit(&app, &BoardOpened(opened)),
+                    Err(e) => tracing::warn!("画板文件打开失败: {:?}, {}", board, e),
+                }
+            }
+        }
+    });
+}
+
+/// 显示并聚焦主窗口
+fn activate_main_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let _ = window.unminimize();
+    let _ = window.show();
+    if let Err(e) = window.set_focus() {
+        tracing::debug!("主窗口聚焦失败: {}", e);
+    }
+}
+
+/// 获取资源管理器集成的状态
+pub fn status() -> ShellIntegrationStatus {
+    ShellIntegrationStatus {
+        supported: cfg!(windows),
+        context_menu_enabled: is_context_menu_registered(),
+    }
+}
+
+#[cfg(windows)]
+fn is_context_menu_registered() -> bool {
+    reg(&["query", CONTEXT_MENU_KEY]).is_ok()
+}
+
+#[cfg(not(windows))]
+fn is_context_menu_registered() -> bool {
+    false
+}
+
+/// 开启或关闭右键菜单；开启时同时登记深度链接协议，关闭时保留协议
+#[cfg(windows)]
+pub fn set_context_menu_enabled(enabled: bool) -> Result<()> {
+    if !enabled {
+        if is_context_menu_registered() {
+            reg(&["delete", CONTEXT_MENU_KEY, "/f"])?;
+        }
+        return Ok(());
+    }
+
+    let exe = std::env::current_exe()?;
+    let exe = exe.to_string_lossy();
+    let icon = format!("\"{}\",0", exe);
+    let send_to = format!("\"{}\" {} \"%1\"", exe, SEND_TO_ARG);
+    let open_link = format!("\"{}\" \"%1\"", exe);
+    let menu_command = format!(r"{}\command", CONTEXT_MENU_KEY);
+    let link_command = format!(r"{}\shell\open\command", URL_SCHEME_KEY);
+
+    reg(&["add", CONTEXT_MENU_KEY, "/ve", "/d", "Send to Collaboard", "/f"])?;
+    reg(&["add", CONTEXT_MENU_KEY, "/v", "Icon", "/d", &icon, "/f"])?;
+    reg(&["add", &menu_command, "/ve", "/d", &send_to, "/f"])?;
+    reg(&["add", URL_SCHEME_KEY, "/ve", "/d", "URL:Collaboard", "/f"])?;
+    reg(&["add", URL_SCHEME_KEY, "/v", "URL Protocol", "/d", "", "/f"])?;
+    reg(&["add", &link_command, "/ve", "/d", &open_link, "/f"])?;
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn set_context_menu_enabled(_enabled: bool) -> Result<()> {
+    Err(FileManagerError::general_error("Explorer context menu is only supported on Windows"))
+}
+
+/// 运行 `reg.exe`，不显示控制台窗口
+#[cfg(windows)]
+fn reg(args: &[&str]) -> Result<()> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let output = std::process::Command::new("reg")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(FileManagerError::general_error(format!(
+            "reg {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_launch_args() {
+        assert!(parse_launch_args(args(&[])).is_empty());
+        assert_eq!(
+            parse_launch_args(args(&["--send-to", r"C:\refs\a.png", r"C:\refs\b c.jpg"])).paths,
+            [PathBuf::from(r"C:\refs\a.png"), PathBuf::from(r"C:\refs\b c.jpg")]
+        );
+        assert_eq!(
+            parse_launch_args(args(&["Collaboard://import?path=C%3A%5Cref%20s%5C%E5%9B%BE.png&x=1&path=%2Ftmp%2Fa+b.jpg"])).paths,
+            [PathBuf::from("C:\\ref s\\图.png"), PathBuf::from("/tmp/a b.jpg")]
+        );
+        assert!(parse_launch_args(args(&["collaboard://open?path=a.png"])).is_empty());
+        assert!(parse_launch_args(args(&["collaboard://import?path=%ZZ"])).is_empty());
+        // 没有 `--send-to` 时普通参数不是要导入的文件，只有画板文件会被打开
+        assert!(parse_launch_args(args(&["a.png"])).is_empty());
+        let request = parse_launch_args(args(&[r"D:\boards\Mood.CBOARD"]));
+        assert_eq!((request.paths.len(), request.boards), (0, vec![PathBuf::from(r"D:\boards\Mood.CBOARD")]));
+    }
+
+    #[test]
+    fn test_launch_requests_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = LaunchRequest { paths: vec![PathBuf::from("a.png")], boards: vec![PathBuf::from("b.cboard")] };
+        enqueue_launch_request(temp_dir.path(), &first).unwrap();
+        enqueue_launch_request(temp_dir.path(), &LaunchRequest::default()).unwrap();
+        std::fs::write(temp_dir.path().join(LAUNCH_REQUESTS_DIR).join("broken.json"), b"{").unwrap();
+
+        let requests = take_launch_requests(temp_dir.path());
+        assert_eq!(requests, [first, LaunchRequest::default()]);
+        assert!(take_launch_requests(temp_dir.path()).is_empty());
+
+        let lock = acquire_instance_lock(temp_dir.path()).unwrap();
+        assert!(lock.is_some());
+        assert!(acquire_instance_lock(temp_dir.path()).unwrap().is_none());
+        drop(lock);
+        assert!(acquire_instance_lock(temp_dir.path()).unwrap().is_some());
+    }
+}
@@ -0,0 +1,305 @@
+//! 附属元数据文件模块
+//!
+//! 在导出的文件旁写入 XMP 或 JSON 附属文件，记录标签、评分和备注，
+//! 经过 Bridge、digiKam 等其他资源管理工具处理后再导入时可以恢复元数据：
+//! - XMP 使用 `dc:subject`（标签）、`xmp:Rating`（评分）、`dc:description`（备注）和 `dc:title`（标题）
+//! - 附属文件命名为 `photo.jpg.xmp` / `photo.jpg.json`，读取时也识别 `photo.xmp`
+//! - 监视文件夹导入时自动读取同名附属文件，附属文件本身不会作为文件导入
+
+use crate::file_manager::error::{FileManagerError, Result};
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::name::{Namespace, ResolveResult};
+use quick_xml::NsReader;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::path::{Path, PathBuf};
+
+/// 评分上限
+pub const MAX_RATING: u8 = 5;
+
+const RDF_NAMESPACE: &[u8] = b"http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const DC_NAMESPACE: &[u8] = b"http://purl.org/dc/elements/1.1/";
+const XMP_NAMESPACE: &[u8] = b"http://ns.adobe.com/xap/1.0/";
+
+/// 附属文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum SidecarFormat {
+    Xmp,
+    Json,
+}
+
+impl SidecarFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Xmp => "xmp",
+            Self::Json => "json",
+        }
+    }
+
+    /// 按扩展名识别附属文件格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        if extension.eq_ignore_ascii_case("xmp") {
+            Some(Self::Xmp)
+        } else if extension.eq_ignore_ascii_case("json") {
+            Some(Self::Json)
+        } else {
+            None
+        }
+    }
+}
+
+/// 附属文件中记录的元数据
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SidecarMetadata {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub rating: Option<u8>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// 文件对应的附属文件路径，如 `photo.jpg` -> `photo.jpg.xmp`
+pub fn sidecar_path(file: &Path, format: SidecarFormat) -> PathBuf {
+    let mut name = file.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(format.extension());
+    file.with_file_name(name)
+}
+
+/// 查找文件旁已有的附属文件
+///
+/// 依次尝试 `photo.jpg.xmp`、`photo.xmp`（Bridge 的命名方式）和 `photo.jpg.json`
+pub fn find_sidecar(file: &Path) -> Option<(PathBuf, SidecarFormat)> {
+    [
+        (sidecar_path(file, SidecarFormat::Xmp), SidecarFormat::Xmp),
+        (file.with_extension(SidecarFormat::Xmp.extension()), SidecarFormat::Xmp),
+        (sidecar_path(file, SidecarFormat::Json), SidecarFormat::Json),
+    ]
+    .into_iter()
+    .find(|(path, _)| path != file && path.is_file())
+}
+
+/// 判断文件是否为其他文件的附属文件
+///
+/// `.xmp` 总是附属文件；`.json` 只有去掉扩展名后对应的文件存在时才是
+pub fn is_sidecar_file(path: &Path) -> bool {
+    match SidecarFormat::from_path(path) {
+        Some(SidecarFormat::Xmp) => true,
+        Some(SidecarFormat::Json) => {
+            let owner = path.with_extension("");
+            owner.extension().is_some() && owner.is_file()
+        }
+        None => false,
+    }
+}
+
+/// 读取附属文件
+pub fn read_sidecar(path: &Path, format: SidecarFormat) -> Result<SidecarMetadata> {
+    let content = std::fs::read_to_string(path)?;
+    match format {
+        SidecarFormat::Xmp => parse_xmp(&content),
+        SidecarFormat::Json => Ok(serde_json::from_str(&content)?),
+    }
+}
+
+/// 写入附属文件，已存在时覆盖
+pub fn write_sidecar(path: &Path, format: SidecarFormat, metadata: &SidecarMetadata) -> Result<()> {
+    let content = match format {
+        SidecarFormat::Xmp => to_xmp(metadata),
+        SidecarFormat::Json => serde_json::to_string_pretty(metadata)?,
+    };
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+/// 生成 XMP 数据包
+pub fn to_xmp(metadata: &SidecarMetadata) -> String {
+    let mut xmp = String::new();
+    xmp.push_str("<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n");
+    xmp.push_str("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n");
+    xmp.push_str(" <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n");
+    xmp.push_str("  <rdf:Description rdf:about=\"\"\n");
+    xmp.push_str("    xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n");
+    xmp.push_str("    xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"");
+    if let Some(rating) = metadata.rating {
+        xmp.push_str(&format!("\n    xmp:Rating=\"{}\"", rating));
+    }
+    xmp.push_str(">\n");
+
+    if !metadata.tags.is_empty() {
+        xmp.push_str("   <dc:subject>\n    <rdf:Bag>\n");
+        for tag in &metadata.tags {
+            xmp.push_str(&format!("     <rdf:li>{}</rdf:li>\n", escape(tag.as_str())));
+        }
+        xmp.push_str("    </rdf:Bag>\n   </dc:subject>\n");
+    }
+
+    if let Some(title) = &metadata.title {
+        xmp.push_str("   <dc:title>\n    <rdf:Alt>\n");
+        xmp.push_str(&format!("     <rdf:li xml:lang=\"x-default\">{}</rdf:li>\n", escape(title.as_str())));
+        xmp.push_str("    </rdf:Alt>\n   </dc:title>\n");
+    }
+
+    if let Some(notes) = &metadata.notes {
+        xmp.push_str("   <dc:description>\n    <rdf:Alt>\n");
+        xmp.push_str(&format!("     <rdf:li xml:lang=\"x-default\">{}</rdf:li>\n", escape(notes.as_str())));
+        xmp.push_str("    </rdf:Alt>\n   </dc:description>\n");
+    }
+
+    xmp.push_str("  </rdf:Description>\n </rdf:RDF>\n</x:xmpmeta>\n");
+    xmp.push_str("<?xpacket end=\"w\"?>\n");
+    xmp
+}
+
+/// 当前读取的 XMP 属性
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum XmpField {
+    Subject,
+    Description,
+    Title,
+    Rating,
+}
+
+/// 解析 XMP 数据包
+///
+/// 评分可以是 `rdf:Description` 的属性或子元素；超出 0-5 的评分（如 -1 表示拒绝）忽略
+pub fn parse_xmp(content: &str) -> Result<SidecarMetadata> {
+    let xml_error = |e: quick_xml::Error| FileManagerError::general_error(format!("Invalid XMP: {}", e));
+
+    let mut reader = NsReader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut metadata = SidecarMetadata::default();
+    let mut field = None;
+    let mut in_item = false;
+
+    loop {
+        let (namespace, event) = reader.read_resolved_event().map_err(xml_error)?;
+        match event {
+            Event::Start(element) | Event::Empty(element) => {
+                match (namespace_of(&namespace), element.local_name().as_ref()) {
+                    (Some(DC_NAMESPACE), b"subject") => field = Some(XmpField::Subject),
+                    (Some(DC_NAMESPACE), b"description") => field = Some(XmpField::Description),
+                    (Some(DC_NAMESPACE), b"title") => field = Some(XmpField::Title),
+                    (Some(XMP_NAMESPACE), b"Rating") => field = Some(XmpField::Rating),
+                    (Some(RDF_NAMESPACE), b"li") => in_item = true,
+                    (Some(RDF_NAMESPACE), b"Description") => {
+                        for attribute in element.attributes().flatten() {
+                            let (namespace, name) = reader.resolve_attribute(attribute.key);
+                            if namespace_of(&namespace) == Some(XMP_NAMESPACE) && name.as_ref() == b"Rating" {
+                                let value = attribute.unescape_value().map_err(xml_error)?;
+                                metadata.rating = parse_rating(&value);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map_err(xml_error)?;
+                match field {
+                    Some(XmpField::Subject) if in_item => metadata.tags.push(text.into_owned()),
+                    Some(XmpField::Description) if in_item && metadata.notes.is_none() => {
+                        metadata.notes = Some(text.into_owned());
+                    }
+                    Some(XmpField::Title) if in_item && metadata.title.is_none() => {
+                        metadata.title = Some(text.into_owned());
+                    }
+                    Some(XmpField::Rating) => metadata.rating = parse_rating(&text),
+                    _ => {}
+                }
+            }
+            Event::End(element) => {
+                match (namespace_of(&namespace), element.local_name().as_ref()) {
+                    (Some(RDF_NAMESPACE), b"li") => in_item = false,
+                    (Some(DC_NAMESPACE), b"subject" | b"description" | b"title") | (Some(XMP_NAMESPACE), b"Rating") => {
+                        field = None;
+                    }
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(metadata)
+}
+
+fn namespace_of<'a>(resolved: &ResolveResult<'a>) -> Option<&'a [u8]> {
+    match resolved {
+        ResolveResult::Bound(Namespace(namespace)) => Some(namespace),
+        _ => None,
+    }
+}
+
+fn parse_rating(value: &str) -> Option<u8> {
+    let rating = value.trim().parse::<f64>().ok()?.round();
+    (0.0..=MAX_RATING as f64).contains(&rating).then_some(rating as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xmp_round_trip() {
+        let metadata = SidecarMetadata {
+            tags: vec!["travel".to_string(), "R&D <draft>".to_string()],
+            rating: Some(4),
+            notes: Some("选用这张".to_string()),
+            title: Some("Sunset & sea".to_string()),
+        };
+
+        assert_eq!(parse_xmp(&to_xmp(&metadata)).unwrap(), metadata);
+        assert_eq!(parse_xmp(&to_xmp(&SidecarMetadata::default())).unwrap(), SidecarMetadata::default());
+    }
+
+    #[test]
+    fn test_parse_xmp_element_rating_and_custom_prefixes() {
+        // 其他工具可能使用不同的命名空间前缀，并把评分写成子元素
+        let xmp = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+          <r:RDF xmlns:r="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+            <r:Description xmlns:d="http://purl.org/dc/elements/1.1/" xmlns:a="http://ns.adobe.com/xap/1.0/">
+              <a:Rating>3</a:Rating>
+              <d:subject><r:Bag><r:li>a</r:li><r:li>b</r:li></r:Bag></d:subject>
+            </r:Description>
+          </r:RDF>
+        </x:xmpmeta>"#;
+
+        let metadata = parse_xmp(xmp).unwrap();
+        assert_eq!(metadata.tags, ["a", "b"]);
+        assert_eq!(metadata.rating, Some(3));
+        assert_eq!(metadata.notes, None);
+
+        assert_eq!(parse_rating("-1"), None);
+    }
+
+    #[test]
+    fn test_sidecar_paths() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let photo = temp_dir.path().join("photo.jpg");
+        std::fs::write(&photo, b"jpg").unwrap();
+
+        assert_eq!(sidecar_path(&photo, SidecarFormat::Xmp), temp_dir.path().join("photo.jpg.xmp"));
+        assert_eq!(find_sidecar(&photo), None);
+
+        let bridge = temp_dir.path().join("photo.xmp");
+        std::fs::write(&bridge, b"").unwrap();
+        assert_eq!(find_sidecar(&photo), Some((bridge.clone(), SidecarFormat::Xmp)));
+        assert!(is_sidecar_file(&bridge));
+
+        let json = sidecar_path(&photo, SidecarFormat::Json);
+        std::fs::write(&json, b"{}").unwrap();
+        assert!(is_sidecar_file(&json));
+        assert!(!is_sidecar_file(&temp_dir.path().join("data.json")));
+    }
+}
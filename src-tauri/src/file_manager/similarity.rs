@@ -0,0 +1,231 @@
+//! 相似图像搜索模块
+//!
+//! 提供基于感知哈希的相似图像检索：
+//! - 差值哈希（dHash），64 位，对缩放和轻微调色不敏感
+//! - 按汉明距离组织的 BK 树，查询只访问距离可能满足条件的分支
+//! - 常驻内存的索引，首次查询时从数据库中已存储的哈希构建
+
+use image::{imageops::FilterType, DynamicImage};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// 默认的最大汉明距离（64 位哈希中不同的位数）
+pub const DEFAULT_MAX_DISTANCE: u32 = 10;
+
+/// 哈希的位数，也是汉明距离的上限
+pub const HASH_BITS: u32 = 64;
+
+/// 计算图像的差值哈希
+///
+/// 缩放为 9x8 灰度图后逐行比较相邻像素的亮度，每个比较结果对应一位
+pub fn perceptual_hash(image: &DynamicImage) -> u64 {
+    let gray = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if gray.get_pixel(x, y).0[0] < gray.get_pixel(x + 1, y).0[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+/// 计算两个哈希的汉明距离
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// BK 树节点，哈希相同的文件共享一个节点
+#[derive(Debug)]
+struct BkNode {
+    hash: u64,
+    file_ids: Vec<String>,
+    /// 子节点按与本节点的距离索引
+    children: HashMap<u32, usize>,
+}
+
+/// 以汉明距离为度量的 BK 树
+///
+/// 节点存放在数组中，子节点通过下标引用
+#[derive(Debug, Default)]
+pub struct BkTree {
+    nodes: Vec<BkNode>,
+}
+
+impl BkTree {
+    /// 插入一个文件的哈希
+    pub fn insert(&mut self, hash: u64, file_id: String) {
+        if self.nodes.is_empty() {
+            self.nodes.push(BkNode { hash, file_ids: vec![file_id], children: HashMap::new() });
+            return;
+        }
+
+        let mut current = 0;
+        loop {
+            let distance = hamming_distance(self.nodes[current].hash, hash);
+            if distance == 0 {
+                self.nodes[current].file_ids.push(file_id);
+                return;
+            }
+
+            match self.nodes[current].children.get(&distance) {
+                Some(&child) => current = child,
+                None => {
+                    let index = self.nodes.len();
+                    self.nodes.push(BkNode { hash, file_ids: vec![file_id], children: HashMap::new() });
+                    self.nodes[current].children.insert(distance, index);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 查找与给定哈希距离不超过 `max_distance` 的所有文件
+    ///
+    /// 返回 `(文件 ID, 索引中的哈希, 距离)`
+    pub fn find(&self, hash: u64, max_distance: u32) -> Vec<(String, u64, u32)> {
+        let mut results = Vec::new();
+        if self.nodes.is_empty() {
+            return results;
+        }
+
+        let mut stack = vec![0];
+        while let Some(current) = stack.pop() {
+            let node = &self.nodes[current];
+            let distance = hamming_distance(node.hash, hash);
+            if distance <= max_distance {
+                results.extend(node.file_ids.iter().map(|id| (id.clone(), node.hash, distance)));
+            }
+
+            // 三角不等式：只有与本节点距离在 [d - max, d + max] 内的子树可能包含结果
+            let low = distance.saturating_sub(max_distance);
+            let high = distance + max_distance;
+            stack.extend(node.children.iter()
+                .filter(|(child_distance, _)| (low..=high).contains(*child_distance))
+                .map(|(_, &child)| child));
+        }
+
+        results
+    }
+}
+
+/// 相似图像索引
+///
+/// 索引只会追加：文件删除或内容替换后旧条目仍留在树中，查询结果需要与数据库中的哈希核对
+#[derive(Debug, Default)]
+pub struct SimilarityIndex {
+    state: Mutex<IndexState>,
+}
+
+#[derive(Debug, Default)]
+struct IndexState {
+    tree: Option<BkTree>,
+    /// 无法解码、不再重复尝试计算哈希的文件
+    failed: HashSet<String>,
+}
+
+impl SimilarityIndex {
+    /// 索引是否已构建
+    pub fn is_built(&self) -> bool {
+        self.state.lock().unwrap().tree.is_some()
+    }
+
+    /// 用已存储的哈希构建索引
+    pub fn build(&self, entries: Vec<(String, u64)>) {
+        let mut tree = BkTree::default();
+        for (file_id, hash) in entries {
+            tree.insert(hash, file_id);
+        }
+        self.state.lock().unwrap().tree = Some(tree);
+    }
+
+    /// 向已构建的索引中添加一个哈希
+    pub fn insert(&self, file_id: String, hash: u64) {
+        if let Some(tree) = self.state.lock().unwrap().tree.as_mut() {
+            tree.insert(hash, file_id);
+        }
+    }
+
+    /// 查询相似的哈希
+    pub fn find(&self, hash: u64, max_distance: u32) -> Vec<(String, u64, u32)> {
+        self.state.lock().unwrap().tree.as_ref()
+            .map(|tree| tree.find(hash, max_distance))
+            .unwrap_or_default()
+    }
+
+    /// 记录无法计算哈希的文件
+    pub fn mark_failed(&self, file_id: String) {
+        self.state.lock().unwrap().failed.insert(file_id);
+    }
+
+    /// 清除文件的失败记录，重新索引后允许再次尝试计算哈希
+    pub fn clear_failed(&self, file_id: &str) {
+        self.state.lock().unwrap().failed.remove(file_id);
+    }
+
+    /// 文件是否曾经计算哈希失败
+    pub fn has_failed(&self, file_id: &str) -> bool {
+        self.state.lock().unwrap().failed.contains(file_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bk_tree_matches_linear_scan() {
+        // 简单的线性同余序列，生成可复现的伪随机哈希
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let hashes: Vec<u64> = (0..500)
+            .map(|_| {
+                seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+                seed
+            })
+            .collect();
+
+        let mut tree = BkTree::default();
+        for (index, hash) in hashes.iter().enumerate() {
+            tree.insert(*hash, index.to_string());
+        }
+        tree.insert(hashes[0], "duplicate".to_string());
+
+        let query = hashes[0] ^ 0b1011;
+        for max_distance in [0, 3, 20, 30] {
+            let mut found: Vec<String> = tree.find(query, max_distance).into_iter().map(|(id, _, _)| id).collect();
+            let mut expected: Vec<String> = hashes.iter().enumerate()
+                .filter(|(_, hash)| hamming_distance(**hash, query) <= max_distance)
+                .map(|(index, _)| index.to_string())
+                .collect();
+            if hamming_distance(hashes[0], query) <= max_distance {
+                expected.push("duplicate".to_string());
+            }
+
+            found.sort();
+            expected.sort();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn test_perceptual_hash_tolerates_resize_and_brightness() {
+        let gradient = |width: u32, height: u32, offset: u8| {
+            DynamicImage::ImageRgb8(image::RgbImage::from_fn(width, height, |x, y| {
+                let value = ((x * 200 / width + y * 40 / height) as u8).saturating_add(offset);
+                image::Rgb([value, value, value])
+            }))
+        };
+
+        let original = perceptual_hash(&gradient(64, 64, 0));
+        let resized = perceptual_hash(&gradient(200, 150, 0));
+        let brighter = perceptual_hash(&gradient(64, 64, 10));
+        let flipped = perceptual_hash(&gradient(64, 64, 0).fliph());
+
+        assert!(hamming_distance(original, resized) <= DEFAULT_MAX_DISTANCE);
+        assert!(hamming_distance(original, brighter) <= DEFAULT_MAX_DISTANCE);
+        assert!(hamming_distance(original, flipped) > DEFAULT_MAX_DISTANCE);
+    }
+}
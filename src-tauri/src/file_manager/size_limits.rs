@@ -0,0 +1,160 @@
+//! 按文件类别的大小限制模块
+//!
+//! 不同类别的文件合理的大小差别很大，一个全局上限要么对图片太宽松，要么挡住视频和压缩包：
+//! - 按扩展名把文件分为图片、视频、音频、文档和压缩包五类
+//! - 每个类别可以单独设置上限（如图片 20 MB、视频 500 MB、压缩包 2 GB），
+//!   未设置的类别和无法归类的文件使用全局的 `max_file_size`
+//! - 上传、文件夹导入、监视文件夹、URL 导入和替换文件内容都按同一规则检查，
+//!   超出时错误中包含文件类别和对应的上限
+//! - 设置保存在数据库中，修改后立即生效
+
+use crate::file_manager::error::{FileManagerError, Result};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// 保存大小限制设置的状态键
+pub const SIZE_LIMITS_KEY: &str = "size_limits";
+
+/// 单个类别上限的最大值（1 TB）
+pub const MAX_CATEGORY_SIZE_LIMIT: u64 = 1024 * 1024 * 1024 * 1024;
+
+/// 归为压缩包的扩展名
+const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "rar", "7z", "tar", "gz", "tgz", "bz2", "xz", "zst", "cab", "iso"];
+
+/// 归为文档的扩展名，`text/*` 类型的文件也归为文档
+const DOCUMENT_EXTENSIONS: &[&str] = &[
+    "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "ods", "odp", "rtf", "epub", "pages", "key", "numbers",
+];
+
+/// 大小限制使用的文件类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum FileCategory {
+    Image,
+    Video,
+    Audio,
+    Document,
+    Archive,
+}
+
+impl FileCategory {
+    /// 按扩展名归类，无法归类时返回 `None`
+    pub fn of(file_path: &Path) -> Option<Self> {
+        let extension = file_path.extension()?.to_str()?.to_lowercase();
+        if ARCHIVE_EXTENSIONS.contains(&extension.as_str()) {
+            return Some(Self::Archive);
+        }
+        if DOCUMENT_EXTENSIONS.contains(&extension.as_str()) {
+            return Some(Self::Document);
+        }
+
+        let mime_type = mime_guess::from_ext(&extension).first()?;
+        match mime_type.type_() {
+            mime_guess::mime::IMAGE => Some(Self::Image),
+            mime_guess::mime::VIDEO => Some(Self::Video),
+            mime_guess::mime::AUDIO => Some(Self::Audio),
+            mime_guess::mime::TEXT => Some(Self::Document),
+            _ => None,
+        }
+    }
+
+    /// 错误信息中使用的名称
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Image => "image",
+            Self::Video => "video",
+            Self::Audio => "audio",
+            Self::Document => "document",
+            Self::Archive => "archive",
+        }
+    }
+}
+
+/// 按类别的大小上限（字节），未列出的类别使用全局上限
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SizeLimits {
+    #[serde(default)]
+    pub limits: BTreeMap<FileCategory, u64>,
+}
+
+impl SizeLimits {
+    /// 检查每个上限都在 1 字节到 1 TB 之间
+    pub fn validate(&self) -> Result<()> {
+        for (category, limit) in &self.limits {
+            if !(1..=MAX_CATEGORY_SIZE_LIMIT).contains(limit) {
+                return Err(FileManagerError::general_error(format!(
+                    "Size limit for {} files must be between 1 byte and {} bytes",
+                    category.name(), MAX_CATEGORY_SIZE_LIMIT
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// 文件适用的上限和类别；类别没有单独设置时为 `default` 和 `None`
+    pub fn limit_for(&self, file_path: &Path, default: u64) -> (u64, Option<FileCategory>) {
+        FileCategory::of(file_path)
+            .and_then(|category| self.limits.get(&category).map(|limit| (*limit, Some(category))))
+            .unwrap_or((default, None))
+    }
+
+    /// 所有类别中最大的上限，不小于 `default`；用于接收数据时还不知道文件类别的情况
+    pub fn largest(&self, default: u64) -> u64 {
+        self.limits.values().copied().fold(default, u64::max)
+    }
+
+    /// 检查文件大小，超出时返回包含类别和上限的 `FileSizeExceeded`
+    pub fn check(&self, file_path: &Path, size: u64, default: u64) -> Result<()> {
+        let (max_size, category) = self.limit_for(file_path, default);
+        if size > max_size {
+            return Err(FileManagerError::FileSizeExceeded { size, max_size, category });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_file_categories() {
+        assert_eq!(FileCategory::of(Path::new("photo.JPG")), Some(FileCategory::Image));
+        assert_eq!(FileCategory::of(Path::new("clip.mp4")), Some(FileCategory::Video));
+        assert_eq!(FileCategory::of(Path::new("voice.mp3")), Some(FileCategory::Audio));
+        assert_eq!(FileCategory::of(Path::new("notes.md")), Some(FileCategory::Document));
+        assert_eq!(FileCategory::of(Path::new("report.docx")), Some(FileCategory::Document));
+        assert_eq!(FileCategory::of(Path::new("assets.tar.gz")), Some(FileCategory::Archive));
+        assert_eq!(FileCategory::of(Path::new("README")), None);
+        assert_eq!(FileCategory::of(Path::new("model.blend")), None);
+    }
+
+    #[test]
+    fn test_category_limits_override_default() {
+        let limits = SizeLimits {
+            limits: BTreeMap::from([(FileCategory::Image, 20 * MB), (FileCategory::Archive, 2048 * MB)]),
+        };
+        assert!(limits.validate().is_ok());
+        assert_eq!(limits.largest(100 * MB), 2048 * MB);
+
+        assert!(limits.check(Path::new("big.zip"), 500 * MB, 100 * MB).is_ok());
+        assert!(limits.check(Path::new("clip.mp4"), 50 * MB, 100 * MB).is_ok());
+        assert!(matches!(
+            limits.check(Path::new("photo.png"), 30 * MB, 100 * MB),
+            Err(FileManagerError::FileSizeExceeded { max_size, category: Some(FileCategory::Image), .. }) if max_size == 20 * MB
+        ));
+        assert!(matches!(
+            limits.check(Path::new("clip.mp4"), 200 * MB, 100 * MB),
+            Err(FileManagerError::FileSizeExceeded { category: None, .. })
+        ));
+
+        let invalid = SizeLimits { limits: BTreeMap::from([(FileCategory::Video, 0)]) };
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! 文件内容类型识别模块
+//!
+//! 文件名没有可识别的扩展名时，根据文件内容的签名判断 MIME 类型：
+//! - 基于 `infer` 的签名库，覆盖常见的图片、文档、压缩包和音视频格式，
+//!   包括 WebP、TIFF 和 Office 文档（docx / xlsx / pptx 等基于 ZIP 的格式）
+//! - `infer` 不区分或不支持的格式在 [`BUILTIN_SIGNATURES`] 中补充，优先于 `infer` 匹配：
+//!   HEIC（`infer` 识别为 `image/heif`）、TGA（签名位于文件末尾）
+//! - 新增格式时在 [`BUILTIN_SIGNATURES`] 中添加一项，或通过 [`Sniffer::with_signature`] 注册
+//! - 流式上传只传入第一个数据块，签名位于文件末尾的格式（如 TGA）此时无法识别
+
+use std::sync::OnceLock;
+
+/// 无法识别时使用的 MIME 类型
+pub const UNKNOWN_MIME_TYPE: &str = "application/octet-stream";
+
+/// 文件内容签名
+#[derive(Debug, Clone, Copy)]
+pub struct Signature {
+    pub mime_type: &'static str,
+    pub extension: &'static str,
+    /// 判断数据是否为该格式
+    pub matcher: fn(&[u8]) -> bool,
+}
+
+/// 补充 `infer` 的内置签名，按顺序匹配
+pub const BUILTIN_SIGNATURES: &[Signature] = &[
+    Signature { mime_type: "image/heic", extension: "heic", matcher: is_heic },
+    Signature { mime_type: "image/x-tga", extension: "tga", matcher: is_tga },
+];
+
+/// HEVC 编码图像的 ftyp 品牌
+const HEIC_BRANDS: &[&[u8]] = &[b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis"];
+
+/// 通用 HEIF 主品牌，兼容品牌中包含 HEIC 品牌时为 HEIC 图像
+const HEIF_BRANDS: &[&[u8]] = &[b"mif1", b"msf1"];
+
+/// TGA 2.0 文件末尾 18 字节的签名
+const TGA_FOOTER: &[u8] = b"TRUEVISION-XFILE.\0";
+
+/// HEIC：ISO BMFF 的 ftyp 盒中主品牌为 HEVC 编码的图像，或主品牌为通用 HEIF 且兼容 HEVC 编码
+fn is_heic(data: &[u8]) -> bool {
+    if data.len() < 16 || &data[4..8] != b"ftyp" {
+        return false;
+    }
+    let major = &data[8..12];
+    if HEIC_BRANDS.contains(&major) {
+        return true;
+    }
+
+    // 兼容品牌列表从第 16 字节开始，到 ftyp 盒结束
+    let box_len = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let compatible = data.get(16..box_len.min(data.len())).unwrap_or_default();
+    HEIF_BRANDS.contains(&major) && compatible.chunks_exact(4).any(|brand| HEIC_BRANDS.contains(&brand))
+}
+
+/// TGA：文件头没有魔数，只能识别带 TGA 2.0 文件尾的文件
+fn is_tga(data: &[u8]) -> bool {
+    data.ends_with(TGA_FOOTER)
+}
+
+/// 签名注册表，先匹配注册的签名，再匹配 `infer` 的签名库
+pub struct Sniffer {
+    infer: infer::Infer,
+}
+
+impl Default for Sniffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sniffer {
+    /// 包含内置签名的注册表
+    pub fn new() -> Self {
+        BUILTIN_SIGNATURES.iter().fold(
+            Self { infer: infer::Infer::new() },
+            |sniffer, signature| sniffer.with_signature(*signature),
+        )
+    }
+
+    /// 注册签名，排在已注册的签名之后、`infer` 的签名之前
+    pub fn with_signature(mut self, signature: Signature) -> Self {
+        self.infer.add(signature.mime_type, signature.extension, signature.matcher);
+        self
+    }
+
+    /// 识别数据的 MIME 类型，无法识别时返回 `None`
+    pub fn sniff(&self, data: &[u8]) -> Option<&'static str> {
+        self.infer.get(data).map(|kind| kind.mime_type())
+    }
+}
+
+/// 使用内置签名的共享注册表
+pub fn sniffer() -> &'static Sniffer {
+    static SNIFFER: OnceLock<Sniffer> = OnceLock::new();
+    SNIFFER.get_or_init(Sniffer::new)
+}
+
+/// 从文件内容检测 MIME 类型，无法识别时为 `application/octet-stream`
+pub fn detect_mime_from_content(data: &[u8]) -> String {
+    sniffer().sniff(data).unwrap_or(UNKNOWN_MIME_TYPE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 只有一个本地文件头的 ZIP 数据
+    fn zip_with_entry(name: &str) -> Vec<u8> {
+        let mut data = vec![0x50, 0x4B, 0x03, 0x04];
+        data.extend_from_slice(&[0; 22]);
+        data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        data.extend_from_slice(&[0; 2]);
+        data.extend_from_slice(name.as_bytes());
+        data
+    }
+
+    #[test]
+    fn test_detect_common_formats() {
+        assert_eq!(detect_mime_from_content(&[0xFF, 0xD8, 0xFF, 0xE0]), "image/jpeg");
+        assert_eq!(detect_mime_from_content(b"RIFF\x10\0\0\0WEBPVP8 "), "image/webp");
+        assert_eq!(detect_mime_from_content(b"II*\0\x08\0\0\0\0\0"), "image/tiff");
+        assert_eq!(detect_mime_from_content(b"\0\0\0\x18ftypheic\0\0\0\0mif1heic"), "image/heic");
+        assert_eq!(detect_mime_from_content(b"\0\0\0\x18ftypmif1\0\0\0\0mif1heic"), "image/heic");
+        assert_eq!(detect_mime_from_content(b"\0\0\0\x18ftypavif\0\0\0\0mif1avif"), "image/avif");
+        assert_eq!(
+            detect_mime_from_content(&zip_with_entry("word/document.xml")),
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+        );
+        assert_eq!(
+            detect_mime_from_content(&zip_with_entry("xl/workbook.xml")),
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+        );
+        assert_eq!(detect_mime_from_content(&zip_with_entry("notes.txt")), "application/zip");
+
+        let mut tga = vec![0; 18];
+        tga.extend_from_slice(&[0; 8]);
+        tga.extend_from_slice(TGA_FOOTER);
+        assert_eq!(detect_mime_from_content(&tga), "image/x-tga");
+
+        assert_eq!(detect_mime_from_content(&[]), UNKNOWN_MIME_TYPE);
+        assert_eq!(detect_mime_from_content(b"plain"), UNKNOWN_MIME_TYPE);
+    }
+
+    #[test]
+    fn test_registered_signature() {
+        fn is_custom(data: &[u8]) -> bool {
+            data.starts_with(b"CUST")
+        }
+
+        let sniffer = Sniffer::new().with_signature(Signature {
+            mime_type: "application/x-custom",
+            extension: "cust",
+            matcher: is_custom,
+        });
+        assert_eq!(sniffer.sniff(b"CUST\x01"), Some("application/x-custom"));
+        assert_eq!(sniffer.sniff(b"GIF89a"), Some("image/gif"));
+        assert_eq!(detect_mime_from_content(b"CUST\x01"), UNKNOWN_MIME_TYPE);
+    }
+}
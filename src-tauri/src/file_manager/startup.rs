@@ -0,0 +1,327 @@
+//! 启动耗时统计模块
+//!
+//! 记录启动各阶段（配置、数据库、迁移、文件系统等）的耗时：
+//! - 每个阶段在独立的 tracing span 中执行，日志中可按阶段查看
+//! - 启动完成后发送 `backend://ready` 事件，负载为各阶段耗时
+//! - 资料库已加密时发送 `backend://locked` 事件，等待输入密码后继续
+//! - 初始化失败时发送 `backend://failed` 事件，负载为错误信息
+//! - 存储在网络驱动器上时可据此定位启动慢的原因
+//! - 表结构迁移、存储路径迁移和恢复扫描登记为启动任务，执行中发送 `backend://startup-task` 进度事件，
+//!   初始化期间也可通过 `get_startup_tasks_status` 查询，大型资料库升级时前端可显示进度而不是像卡住一样
+
+use crate::file_manager::events;
+use crate::file_manager::network_share::LibraryOffline;
+use crate::file_manager::read_only::ReadOnlyReason;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tracing::Instrument;
+
+/// 同一任务两次进度事件的最小间隔，任务开始和结束时总是发送
+const PROGRESS_EVENT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 后端服务状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum BackendStatus {
+    /// 正在后台初始化，命令返回 `initializing` 错误码
+    Initializing,
+    /// 资料库已加密，等待 `unlock_library` 提交密码
+    Locked,
+    /// 可以使用；`read_only` 不为空时修改资料库的命令返回 `read_only` 错误码，
+    /// `offline` 不为空时返回 `library_offline` 错误码
+    Ready {
+        #[serde(default)]
+        read_only: Option<ReadOnlyReason>,
+        #[serde(default)]
+        offline: Option<LibraryOffline>,
+    },
+    Failed { message: String },
+}
+
+/// 单个启动阶段的耗时
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct StartupPhase {
+    pub name: String,
+    pub duration_ms: u64,
+}
+
+/// 启动耗时报告，`backend://ready` 事件负载
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct StartupReport {
+    pub phases: Vec<StartupPhase>,
+    pub total_ms: u64,
+}
+
+/// 启动任务，按执行顺序排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupTaskKind {
+    /// 数据库表结构迁移
+    SchemaMigration,
+    /// 旧版本绝对存储路径迁移为相对路径
+    BlobPathMigration,
+    /// 重放未完成的操作日志
+    JournalRecovery,
+    /// 恢复被中断的上传
+    UploadRecovery,
+}
+
+impl StartupTaskKind {
+    pub const ALL: [Self; 4] = [Self::SchemaMigration, Self::BlobPathMigration, Self::JournalRecovery, Self::UploadRecovery];
+}
+
+/// 启动任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupTaskState {
+    Pending,
+    Running,
+    Completed,
+    /// 无需执行，如上次正常关闭时跳过恢复扫描
+    Skipped,
+    Failed,
+}
+
+/// 启动任务的状态和进度，`backend://startup-task` 事件负载
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct StartupTask {
+    pub kind: StartupTaskKind,
+    pub state: StartupTaskState,
+    /// 已处理的条目数
+    pub processed: usize,
+    /// 总条目数，无法预先统计时为空
+    pub total: Option<usize>,
+    pub started_at: Option<DateTime<Local>>,
+    pub finished_at: Option<DateTime<Local>>,
+    pub error: Option<String>,
+}
+
+impl StartupTask {
+    fn new(kind: StartupTaskKind) -> Self {
+        Self {
+            kind,
+            state: StartupTaskState::Pending,
+            processed: 0,
+            total: None,
+            started_at: None,
+            finished_at: None,
+            error: None,
+        }
+    }
+}
+
+/// 启动任务进度记录，可在多个线程间共享
+#[derive(Clone)]
+pub struct StartupTasks {
+    inner: Arc<StartupTasksInner>,
+}
+
+struct StartupTasksInner {
+    tasks: Mutex<Vec<StartupTask>>,
+    last_event: Mutex<Option<Instant>>,
+    app: OnceLock<AppHandle>,
+}
+
+impl Default for StartupTasks {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(StartupTasksInner {
+                tasks: Mutex::new(StartupTaskKind::ALL.into_iter().map(StartupTask::new).collect()),
+                last_event: Mutex::new(None),
+                app: OnceLock::new(),
+            }),
+        }
+    }
+}
+
+impl StartupTasks {
+    /// 设置发送进度事件的应用句柄，设置之前只更新状态
+    pub fn attach(&self, app: AppHandle) {
+        let _ = self.inner.app.set(app);
+    }
+
+    /// 所有任务的当前状态
+    pub fn snapshot(&self) -> Vec<StartupTask> {
+        self.inner.tasks.lock().unwrap().clone()
+    }
+
+    pub fn start(&self, kind: StartupTaskKind) {
+        self.update(kind, true, |task| {
+            task.state = StartupTaskState::Running;
+            task.started_at = Some(Local::now());
+        });
+    }
+
+    /// 报告进度，事件按间隔节流
+    pub fn progress(&self, kind: StartupTaskKind, processed: usize, total: Option<usize>) {
+        self.update(kind, false, |task| {
+            task.processed = processed;
+            task.total = total;
+        });
+    }
+
+    pub fn complete(&self, kind: StartupTaskKind) {
+        self.finish(kind, StartupTaskState::Completed, None);
+    }
+
+    pub fn skip(&self, kind: StartupTaskKind) {
+        self.finish(kind, StartupTaskState::Skipped, None);
+    }
+
+    pub fn fail(&self, kind: StartupTaskKind, error: impl Display) {
+        self.finish(kind, StartupTaskState::Failed, Some(error.to_string()));
+    }
+
+    /// 按执行结果完成任务，原样返回结果
+    pub fn finish_with<T, E: Display>(&self, kind: StartupTaskKind, result: Result<T, E>) -> Result<T, E> {
+        match &result {
+            Ok(_) => self.complete(kind),
+            Err(e) => self.fail(kind, e),
+        }
+        result
+    }
+
+    fn finish(&self, kind: StartupTaskKind, state: StartupTaskState, error: Option<String>) {
+        self.update(kind, true, |task| {
+            task.state = state;
+            task.finished_at = Some(Local::now());
+            task.error = error;
+        });
+    }
+
+    fn update(&self, kind: StartupTaskKind, force_event: bool, f: impl FnOnce(&mut StartupTask)) {
+        let task = {
+            let mut tasks = self.inner.tasks.lock().unwrap();
+            let Some(task) = tasks.iter_mut().find(|task| task.kind == kind) else {
+                return;
+            };
+            f(task);
+            task.clone()
+        };
+
+        let Some(app) = self.inner.app.get() else {
+            return;
+        };
+        {
+            let mut last_event = self.inner.last_event.lock().unwrap();
+            if !force_event && last_event.is_some_and(|sent| sent.elapsed() < PROGRESS_EVENT_INTERVAL) {
+                return;
+            }
+            *last_event = Some(Instant::now());
+        }
+        events::emit(app, &task);
+    }
+}
+
+/// 启动阶段计时器
+pub struct StartupProfiler {
+    started: Instant,
+    phases: Vec<StartupPhase>,
+}
+
+impl Default for StartupProfiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StartupProfiler {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            phases: Vec::new(),
+        }
+    }
+
+    /// 在 `startup` span 中执行一个阶段并记录耗时
+    pub fn measure<T>(&mut self, phase: &str, f: impl FnOnce() -> T) -> T {
+        let span = tracing::info_span!("startup", phase);
+        let _guard = span.enter();
+
+        let started = Instant::now();
+        let output = f();
+        self.record(phase, started);
+        output
+    }
+
+    /// 在 `startup` span 中执行一个异步阶段并记录耗时
+    pub async fn measure_async<T>(&mut self, phase: &str, future: impl Future<Output = T>) -> T {
+        let started = Instant::now();
+        let output = future.instrument(tracing::info_span!("startup", phase)).await;
+        self.record(phase, started);
+        output
+    }
+
+    /// 结束计时，生成报告
+    pub fn finish(self) -> StartupReport {
+        let total_ms = self.started.elapsed().as_millis() as u64;
+        tracing::info!("启动完成，总耗时 {} ms", total_ms);
+        StartupReport {
+            phases: self.phases,
+            total_ms,
+        }
+    }
+
+    fn record(&mut self, phase: &str, started: Instant) {
+        let duration_ms = started.elapsed().as_millis() as u64;
+        tracing::info!("启动阶段完成: {} ({} ms)", phase, duration_ms);
+        self.phases.push(StartupPhase {
+            name: phase.to_string(),
+            duration_ms,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiler_records_phases_in_order() {
+        let mut profiler = StartupProfiler::new();
+        assert_eq!(profiler.measure("config", || 1), 1);
+        profiler.measure("database", || std::thread::sleep(std::time::Duration::from_millis(5)));
+
+        let report = profiler.finish();
+        let names: Vec<_> = report.phases.iter().map(|phase| phase.name.as_str()).collect();
+        assert_eq!(names, ["config", "database"]);
+        assert!(report.phases[1].duration_ms >= 5);
+        assert!(report.total_ms >= report.phases[1].duration_ms);
+    }
+
+    #[test]
+    fn test_startup_tasks_track_progress() {
+        let tasks = StartupTasks::default();
+        let kinds: Vec<_> = tasks.snapshot().iter().map(|task| task.kind).collect();
+        assert_eq!(kinds, StartupTaskKind::ALL);
+
+        tasks.start(StartupTaskKind::BlobPathMigration);
+        tasks.progress(StartupTaskKind::BlobPathMigration, 120, Some(200_000));
+        let running = tasks.snapshot()[1].clone();
+        assert_eq!(running.state, StartupTaskState::Running);
+        assert_eq!((running.processed, running.total), (120, Some(200_000)));
+        assert!(running.started_at.is_some());
+
+        let result: Result<(), String> = Err("disk full".to_string());
+        assert!(tasks.clone().finish_with(StartupTaskKind::BlobPathMigration, result).is_err());
+        tasks.skip(StartupTaskKind::JournalRecovery);
+
+        let snapshot = tasks.snapshot();
+        assert_eq!(snapshot[1].state, StartupTaskState::Failed);
+        assert_eq!(snapshot[1].error.as_deref(), Some("disk full"));
+        assert_eq!(snapshot[2].state, StartupTaskState::Skipped);
+        assert_eq!(snapshot[3].state, StartupTaskState::Pending);
+    }
+}
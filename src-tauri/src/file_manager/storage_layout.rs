@@ -0,0 +1,230 @@
+//! 存储布局模块
+//!
+//! 决定存储文件在存储根目录下的位置，并在切换布局时迁移已有文件：
+//! - 按日期：`YYYY/MM/DD/文件名`（默认）
+//! - 按哈希分片：`ab/cd/文件名`，分片取存储文件名的 SHA-256 前四位，避免单个目录文件过多
+//! - 切换布局后在后台分批移动存储文件，每批移动完成后在一个事务中更新数据库路径
+//! - 迁移状态保存在数据库中，中断后（包括应用退出）在下次启动时继续
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::events;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::AppHandle;
+
+/// 保存存储布局设置的状态键
+pub const STORAGE_LAYOUT_KEY: &str = "storage_layout";
+
+/// 每批迁移的文件数
+pub const MIGRATION_BATCH_SIZE: usize = 100;
+
+/// 是否有迁移任务在运行，避免重复启动
+static MIGRATION_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 存储布局
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageLayout {
+    /// 按上传日期（或拍摄日期）组织：`YYYY/MM/DD/文件名`
+    #[default]
+    DateBased,
+    /// 按文件名哈希分片：`ab/cd/文件名`
+    HashSharded,
+}
+
+impl StorageLayout {
+    /// 文件在存储根目录下的相对路径（`/` 分隔）
+    pub fn relative_path(&self, name: &str, date: NaiveDate) -> String {
+        match self {
+            Self::DateBased => format!("{}/{}", date.format("%Y/%m/%d"), name),
+            Self::HashSharded => sharded_path(name),
+        }
+    }
+
+    /// 存储路径是否已符合该布局
+    ///
+    /// 按日期布局只检查路径形式，按拍摄日期存放的文件不会被移动
+    pub fn matches(&self, stored_path: &str, name: &str) -> bool {
+        let parts: Vec<&str> = stored_path.split('/').collect();
+        match self {
+            Self::DateBased => {
+                let widths = [4, 2, 2];
+                parts.len() == 4
+                    && parts[3] == name
+                    && parts[..3].iter().zip(widths).all(|(part, width)| {
+                        part.len() == width && part.chars().all(|c| c.is_ascii_digit())
+                    })
+            }
+            Self::HashSharded => stored_path == sharded_path(name),
+        }
+    }
+}
+
+/// 按哈希分片布局时文件的相对路径，与日期无关
+pub fn sharded_path(name: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(name.as_bytes()));
+    format!("{}/{}/{}", &hash[..2], &hash[2..4], name)
+}
+
+/// 存储布局设置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct StorageLayoutSettings {
+    pub layout: StorageLayout,
+    /// 切换布局后尚未迁移完成
+    pub migration_pending: bool,
+}
+
+/// 迁移失败的文件
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LayoutMigrationFailure {
+    pub file_id: String,
+    pub error: String,
+}
+
+/// 一批迁移的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LayoutMigrationBatch {
+    pub moved: usize,
+    pub failed: Vec<LayoutMigrationFailure>,
+    /// 本批之后仍未迁移的文件数（不含失败的文件）
+    pub remaining: usize,
+}
+
+/// 迁移进度事件负载
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LayoutMigrationProgress {
+    pub layout: StorageLayout,
+    pub moved: usize,
+    pub failed: usize,
+    pub remaining: usize,
+    pub finished: bool,
+}
+
+/// 在后台执行未完成的迁移
+///
+/// 每批单独获取服务锁，迁移期间其他命令仍可执行；失败的文件不再重试，
+/// 保持迁移未完成状态，下次启动时再次尝试
+pub fn spawn_migration(app: AppHandle, state: FileManagerState) {
+    if MIGRATION_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let mut progress = LayoutMigrationProgress::default();
+        let mut failed = HashSet::new();
+
+        loop {
+            let batch = {
+                let Ok(service) = state.lock().await else {
+                    break;
+                };
+                // 只读模式下暂停迁移，下次以可写模式启动时继续
+                if service.is_read_only() {
+                    break;
+                }
+                progress.layout = service.storage_layout();
+                service.migrate_storage_layout_batch(&failed).await
+            };
+
+            match batch {
+                Ok(batch) => {
+                    progress.moved += batch.moved;
+                    progress.failed += batch.failed.len();
+                    progress.remaining = batch.remaining;
+                    progress.finished = batch.remaining == 0;
+                    for failure in batch.failed {
+                        tracing::warn!("存储文件迁移失败: {}, {}", failure.file_id, failure.error);
+                        failed.insert(failure.file_id);
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("存储布局迁移中断: {}", e);
+                    break;
+                }
+            }
+
+            events::emit(&app, &progress);
+            if progress.finished {
+                break;
+            }
+        }
+
+        MIGRATION_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// 启动时继续上次未完成的迁移
+pub fn resume_pending_migration(app: AppHandle, state: FileManagerState) {
+    tauri::async_runtime::spawn(async move {
+        let settings = match state.lock().await {
+            Ok(service) => service.get_storage_layout_settings().await,
+            Err(_) => return,
+        };
+        match settings {
+            Ok(settings) if settings.migration_pending => {
+                tracing::info!("继续未完成的存储布局迁移: {:?}", settings.layout);
+                spawn_migration(app, state);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("读取存储布局设置失败: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_manager::paths::sanitize_file_name;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_layout_paths() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+        let name = "0b6e.jpg";
+
+        let dated = StorageLayout::DateBased.relative_path(name, date);
+        assert_eq!(dated, "2024/03/09/0b6e.jpg");
+        assert!(StorageLayout::DateBased.matches(&dated, name));
+        assert!(!StorageLayout::DateBased.matches("uploads/0b6e.jpg", name));
+
+        let sharded = StorageLayout::HashSharded.relative_path(name, date);
+        assert_eq!(sharded.split('/').count(), 3);
+        assert!(sharded.ends_with("/0b6e.jpg"));
+        assert_eq!(sharded, sharded_path(name));
+        assert!(StorageLayout::HashSharded.matches(&sharded, name));
+        assert!(!StorageLayout::HashSharded.matches(&dated, name));
+        assert!(!StorageLayout::DateBased.matches(&sharded, name));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_layout_paths_stay_under_storage_root(raw_name in "\\PC{0,64}", days in 0i64..40_000) {
+            let name = sanitize_file_name(&raw_name);
+            let date = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap() + chrono::Duration::days(days);
+
+            let file_suffix = format!("/{}", name);
+            for layout in [StorageLayout::DateBased, StorageLayout::HashSharded] {
+                let relative = layout.relative_path(&name, date);
+                prop_assert!(relative.ends_with(&file_suffix));
+                prop_assert!(layout.matches(&relative, &name));
+                prop_assert!(relative.split('/').all(|part| !part.is_empty() && part != "." && part != ".."));
+
+                let root = std::path::Path::new("files");
+                prop_assert!(root.join(&relative).starts_with(root));
+            }
+
+            // 两种布局的路径形式互不相同，迁移时可以区分
+            let dated = StorageLayout::DateBased.relative_path(&name, date);
+            prop_assert!(!StorageLayout::HashSharded.matches(&dated, &name));
+        }
+    }
+}
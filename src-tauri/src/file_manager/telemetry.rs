@@ -0,0 +1,306 @@
+//! 匿名使用统计模块
+//!
+//! 严格按用户选择开启，默认关闭：
+//! - 只统计固定功能名的使用次数和错误码出现次数，不包含文件名、路径、标签等任何用户数据
+//! - 计数保存在内存中，每小时批量发送到设置的地址，发送成功后扣除已发送的计数
+//! - `get_telemetry_preview` 返回下次将要发送的完整内容
+//! - 关闭时立即清空尚未发送的计数
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::error::{FileManagerError, Result};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 保存统计设置的状态键
+pub const TELEMETRY_SETTINGS_KEY: &str = "telemetry";
+
+/// 批量发送的间隔
+const SEND_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// 发送请求超时时间
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 统计设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TelemetrySettings {
+    pub enabled: bool,
+    /// 接收统计数据的地址，未设置时不发送
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
+/// 统计使用次数的功能
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    Upload,
+    BatchUpload,
+    Search,
+    UrlImport,
+    Screenshot,
+    Export,
+    BulkTag,
+    Triage,
+    CompareImages,
+    SimilarImages,
+    FindDuplicates,
+    ResolveDuplicates,
+    IntegrityCheck,
+    WatchedFolder,
+    Backup,
+    Restore,
+    LinkFile,
+    BatchOperations,
+}
+
+impl Feature {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Upload => "upload",
+            Self::BatchUpload => "batch_upload",
+            Self::Search => "search",
+            Self::UrlImport => "url_import",
+            Self::Screenshot => "screenshot",
+            Self::Export => "export",
+            Self::BulkTag => "bulk_tag",
+            Self::Triage => "triage",
+            Self::CompareImages => "compare_images",
+            Self::SimilarImages => "similar_images",
+            Self::FindDuplicates => "find_duplicates",
+            Self::ResolveDuplicates => "resolve_duplicates",
+            Self::IntegrityCheck => "integrity_check",
+            Self::WatchedFolder => "watched_folder",
+            Self::Backup => "backup",
+            Self::Restore => "restore",
+            Self::LinkFile => "link_file",
+            Self::BatchOperations => "batch_operations",
+        }
+    }
+}
+
+/// 一批统计数据，即实际发送的内容
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TelemetryBatch {
+    pub app_version: String,
+    pub os: String,
+    /// 功能名 -> 使用次数
+    pub features: BTreeMap<String, u64>,
+    /// 错误码 -> 出现次数
+    pub errors: BTreeMap<String, u64>,
+}
+
+impl TelemetryBatch {
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty() && self.errors.is_empty()
+    }
+}
+
+/// 统计预览
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TelemetryPreview {
+    pub settings: TelemetrySettings,
+    /// 下次将要发送的内容
+    pub batch: TelemetryBatch,
+}
+
+/// 内存中的计数
+#[derive(Debug, Default)]
+pub struct TelemetryCounters {
+    features: BTreeMap<&'static str, u64>,
+    errors: BTreeMap<&'static str, u64>,
+}
+
+impl TelemetryCounters {
+    pub const fn new() -> Self {
+        Self {
+            features: BTreeMap::new(),
+            errors: BTreeMap::new(),
+        }
+    }
+
+    pub fn record_feature(&mut self, feature: Feature) {
+        *self.features.entry(feature.as_str()).or_default() += 1;
+    }
+
+    pub fn record_error(&mut self, code: &'static str) {
+        *self.errors.entry(code).or_default() += 1;
+    }
+
+    /// 生成当前计数的批次
+    pub fn snapshot(&self) -> TelemetryBatch {
+        let to_owned = |counts: &BTreeMap<&'static str, u64>| {
+            counts.iter().map(|(name, count)| (name.to_string(), *count)).collect()
+        };
+
+        TelemetryBatch {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            features: to_owned(&self.features),
+            errors: to_owned(&self.errors),
+        }
+    }
+
+    /// 扣除已发送的计数，发送期间新增的计数保留到下一批
+    pub fn acknowledge(&mut self, batch: &TelemetryBatch) {
+        fn subtract(counts: &mut BTreeMap<&'static str, u64>, sent: &BTreeMap<String, u64>) {
+            counts.retain(|name, count| {
+                *count = count.saturating_sub(sent.get(*name).copied().unwrap_or(0));
+                *count > 0
+            });
+        }
+
+        subtract(&mut self.features, &batch.features);
+        subtract(&mut self.errors, &batch.errors);
+    }
+
+    pub fn clear(&mut self) {
+        self.features.clear();
+        self.errors.clear();
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static COUNTERS: Mutex<TelemetryCounters> = Mutex::new(TelemetryCounters::new());
+
+/// 开启或关闭统计，关闭时清空尚未发送的计数
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    if !enabled {
+        COUNTERS.lock().unwrap().clear();
+    }
+}
+
+/// 记录一次功能使用，未开启时忽略
+pub fn record_feature(feature: Feature) {
+    if ENABLED.load(Ordering::Relaxed) {
+        COUNTERS.lock().unwrap().record_feature(feature);
+    }
+}
+
+/// 记录一次错误，未开启时忽略
+pub fn record_error(code: &'static str) {
+    if ENABLED.load(Ordering::Relaxed) {
+        COUNTERS.lock().unwrap().record_error(code);
+    }
+}
+
+/// 获取下次将要发送的内容
+pub fn snapshot() -> TelemetryBatch {
+    COUNTERS.lock().unwrap().snapshot()
+}
+
+/// 检查统计地址，只允许 https
+pub fn parse_endpoint(endpoint: &str) -> Result<reqwest::Url> {
+    let parsed = reqwest::Url::parse(endpoint.trim())
+        .map_err(|e| FileManagerError::general_error(format!("Invalid telemetry endpoint: {}", e)))?;
+
+    match parsed.scheme() {
+        "https" => Ok(parsed),
+        scheme => Err(FileManagerError::general_error(format!(
+            "Telemetry endpoint must use https, got: {}", scheme
+        ))),
+    }
+}
+
+/// 发送一批统计数据
+pub async fn send(endpoint: reqwest::Url, batch: &TelemetryBatch) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(SEND_TIMEOUT)
+        .user_agent(concat!("Collaboard/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| FileManagerError::general_error(format!("Failed to create HTTP client: {}", e)))?;
+
+    client.post(endpoint)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(batch)?)
+        .send().await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| FileManagerError::general_error(format!("Failed to send telemetry: {}", e)))?;
+
+    Ok(())
+}
+
+/// 启动后台发送任务
+///
+/// 每小时检查一次，开启且设置了地址时发送累积的计数
+pub fn spawn_send_loop(state: FileManagerState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SEND_INTERVAL);
+        loop {
+            interval.tick().await;
+            if !ENABLED.load(Ordering::Relaxed) {
+                continue;
+            }
+
+            let settings = match state.lock().await {
+                Ok(service) => service.get_telemetry_settings().await,
+                Err(_) => continue,
+            };
+            let endpoint = match settings {
+                Ok(TelemetrySettings { enabled: true, endpoint: Some(endpoint) }) => endpoint,
+                Ok(_) => continue,
+                Err(e) => {
+                    tracing::warn!("读取统计设置失败: {}", e);
+                    continue;
+                }
+            };
+
+            let batch = snapshot();
+            if batch.is_empty() {
+                continue;
+            }
+
+            let result = match parse_endpoint(&endpoint) {
+                Ok(url) => send(url, &batch).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => {
+                    COUNTERS.lock().unwrap().acknowledge(&batch);
+                    tracing::debug!("使用统计已发送");
+                }
+                Err(e) => tracing::warn!("使用统计发送失败，下次重试: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_acknowledge_keeps_new_counts() {
+        let mut counters = TelemetryCounters::new();
+        counters.record_feature(Feature::Upload);
+        counters.record_feature(Feature::Upload);
+        counters.record_error("file_not_found");
+
+        let batch = counters.snapshot();
+        assert_eq!(batch.features.get("upload"), Some(&2));
+        assert_eq!(batch.errors.get("file_not_found"), Some(&1));
+
+        // 发送期间又使用了一次
+        counters.record_feature(Feature::Upload);
+        counters.acknowledge(&batch);
+
+        let batch = counters.snapshot();
+        assert_eq!(batch.features.get("upload"), Some(&1));
+        assert!(batch.errors.is_empty());
+    }
+
+    #[test]
+    fn test_parse_endpoint() {
+        assert!(parse_endpoint("https://telemetry.example.com/v1").is_ok());
+        assert!(parse_endpoint("http://telemetry.example.com/v1").is_err());
+        assert!(parse_endpoint("not a url").is_err());
+    }
+}
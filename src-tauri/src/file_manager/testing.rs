@@ -0,0 +1,303 @@
+//! 测试工具模块
+//!
+//! 为服务和命令的测试提供快速、互不干扰的资料库：
+//! - 数据库使用内存中的 SQLite，不写入磁盘
+//! - 存储文件默认写入每个资料库独立的临时目录，资料库释放时自动删除，不会访问 APPDATA；
+//!   [`TestLibraryBuilder::in_memory_storage`] 改用 [`MemoryStorage`]，存储文件不写入磁盘
+//! - 内存存储只覆盖经过文件系统服务的读写（上传、读取、移动、复制、删除和目录操作）；
+//!   索引、缩略图、asset 协议、外部应用、备份和导出直接使用文件路径，测试这些功能时使用默认的磁盘存储
+//! - [`TestLibraryBuilder`] 配置允许的文件类型和大小上限，fixture 函数生成内容固定的测试数据，
+//!   同样的输入每次得到同样的字节
+//! - [`mock_webview`] 和 [`invoke_command`] 在 Tauri 的模拟运行时中按前端 `invoke` 的参数调用命令，
+//!   用于检查命令与前端封装之间的参数名
+
+use crate::file_manager::{
+    commands::FileManagerState,
+    config::{FileManagerConfig, FileTypePolicy},
+    database::DatabaseService,
+    filesystem::FileSystemService,
+    memory_storage::MemoryStorage,
+    service::{BulkTagRequest, CreateDirectoryRequest, FileManagerService, UploadRequest},
+    size_limits::SizeLimits,
+    startup::StartupReport,
+    storage_layout::StorageLayout,
+    thumbnail::PreviewSettings,
+};
+use serde_json::Value;
+use std::ops::Deref;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// 测试资料库默认允许的文件类型
+pub const TEST_FILE_TYPES: &[&str] = &["txt", "jpg", "png"];
+
+/// 测试资料库默认的文件大小上限
+pub const TEST_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// 创建使用默认设置的测试资料库
+pub async fn initialize_for_tests() -> TestLibrary {
+    TestLibrary::builder().build().await
+}
+
+/// 测试资料库，可直接当作 [`FileManagerService`] 使用
+pub struct TestLibrary {
+    service: FileManagerService,
+    temp_dir: TempDir,
+    storage: Option<MemoryStorage>,
+}
+
+impl TestLibrary {
+    pub fn builder() -> TestLibraryBuilder {
+        TestLibraryBuilder::default()
+    }
+
+    /// 资料库的临时根目录
+    pub fn path(&self) -> &Path {
+        self.temp_dir.path()
+    }
+
+    /// 使用内存存储时返回存储内容
+    pub fn memory_storage(&self) -> Option<&MemoryStorage> {
+        self.storage.as_ref()
+    }
+
+    /// 拆分为服务和临时目录，临时目录释放时删除所有文件
+    pub fn into_parts(self) -> (FileManagerService, TempDir) {
+        (self.service, self.temp_dir)
+    }
+
+    /// 上传文件到根目录，返回文件 ID
+    pub async fn add_file(&self, name: &str, data: &[u8]) -> String {
+        self.add_file_in(None, name, data).await
+    }
+
+    /// 上传文件到指定目录，返回文件 ID
+    pub async fn add_file_in(&self, directory_id: Option<&str>, name: &str, data: &[u8]) -> String {
+        let request = UploadRequest {
+            file_data: data.to_vec(),
+            original_name: name.to_string(),
+            directory_id: directory_id.map(str::to_string),
+            ..Default::default()
+        };
+        self.service.upload_file(request).await.unwrap().file_id
+    }
+
+    /// 创建目录，未指定父目录时创建在根目录下，返回目录 ID
+    pub async fn add_directory(&self, parent_id: Option<&str>, name: &str) -> String {
+        let request = CreateDirectoryRequest {
+            name: name.to_string(),
+            parent_id: parent_id.map(str::to_string),
+        };
+        self.service.create_directory(request).await.unwrap().directory_id
+    }
+
+    /// 给文件添加标签
+    pub async fn tag(&self, file_id: &str, tags: &[&str]) {
+        let request = BulkTagRequest {
+            file_ids: vec![file_id.to_string()],
+            add_tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            remove_tags: Vec::new(),
+        };
+        self.service.update_tags_bulk(request).await.unwrap();
+    }
+}
+
+impl Deref for TestLibrary {
+    type Target = FileManagerService;
+
+    fn deref(&self) -> &FileManagerService {
+        &self.service
+    }
+}
+
+/// 测试资料库的构建器
+pub struct TestLibraryBuilder {
+    supported_file_types: Vec<String>,
+    max_file_size: u64,
+    in_memory_storage: bool,
+}
+
+impl Default for TestLibraryBuilder {
+    fn default() -> Self {
+        Self {
+            supported_file_types: TEST_FILE_TYPES.iter().map(|extension| extension.to_string()).collect(),
+            max_file_size: TEST_MAX_FILE_SIZE,
+            in_memory_storage: false,
+        }
+    }
+}
+
+impl TestLibraryBuilder {
+    /// 允许名单模式下允许的文件类型
+    pub fn supported_file_types(mut self, extensions: &[&str]) -> Self {
+        self.supported_file_types = extensions.iter().map(|extension| extension.to_string()).collect();
+        self
+    }
+
+    pub fn max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// 存储文件保存在内存中，不写入临时目录
+    pub fn in_memory_storage(mut self) -> Self {
+        self.in_memory_storage = true;
+        self
+    }
+
+    pub async fn build(self) -> TestLibrary {
+        let temp_dir = TempDir::new().unwrap();
+        let config = FileManagerConfig {
+            app_data_dir: temp_dir.path().to_path_buf(),
+            database_path: temp_dir.path().join("library.db"),
+            storage_path: temp_dir.path().join("files"),
+            max_file_size: self.max_file_size,
+            supported_file_types: self.supported_file_types,
+            file_type_policy: FileTypePolicy::default(),
+            storage_layout: StorageLayout::default(),
+            preview_settings: PreviewSettings::default(),
+            size_limits: SizeLimits::default(),
+        };
+
+        let db_service = DatabaseService::open_in_memory().await.unwrap();
+        let storage = self.in_memory_storage.then(MemoryStorage::new);
+        let fs_service = match &storage {
+            Some(storage) => FileSystemService::in_memory(&config.storage_path, storage.clone()),
+            None => FileSystemService::new(&config.storage_path).unwrap(),
+        };
+        let service = FileManagerService::with_config(config, db_service, fs_service);
+
+        TestLibrary { service, temp_dir, storage }
+    }
+}
+
+/// 生成固定内容的文本
+///
+/// 内容由 `seed` 决定，不同的 `seed` 得到不同的内容哈希
+pub fn text_fixture(seed: u32) -> Vec<u8> {
+    format!("fixture {}\n", seed).into_bytes()
+}
+
+/// 生成固定内容的 PNG 图像：以 `seed` 为起点的渐变，不同的 `seed` 得到不同的感知哈希
+pub fn png_fixture(width: u32, height: u32, seed: u8) -> Vec<u8> {
+    let image = image::RgbImage::from_fn(width, height, |x, y| {
+        let shade = |value: u32, size: u32| (value * 255 / size.max(1)) as u8;
+        image::Rgb([shade(x, width).wrapping_add(seed), shade(y, height), seed])
+    });
+    let mut data = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut data), image::ImageFormat::Png).unwrap();
+    data
+}
+
+/// 前端 `invoke` 发出的请求
+pub fn invoke_request(cmd: &str, args: Value) -> tauri::webview::InvokeRequest {
+    tauri::webview::InvokeRequest {
+        cmd: cmd.to_string(),
+        callback: tauri::ipc::CallbackFn(0),
+        error: tauri::ipc::CallbackFn(1),
+        url: "tauri://localhost".parse().unwrap(),
+        body: tauri::ipc::InvokeBody::Json(args),
+        headers: Default::default(),
+        invoke_key: tauri::test::INVOKE_KEY.to_string(),
+    }
+}
+
+/// 在模拟运行时中按前端 `invoke` 的参数调用命令，返回响应 JSON
+pub fn invoke_command(webview: &tauri::WebviewWindow<tauri::test::MockRuntime>, cmd: &str, args: Value) -> Value {
+    tauri::test::get_ipc_response(webview, invoke_request(cmd, args))
+        .unwrap_or_else(|e| panic!("{} failed: {}", cmd, e))
+        .deserialize()
+        .unwrap()
+}
+
+/// 调用命令并返回命令的错误 JSON，命令成功时测试失败
+pub fn invoke_command_error(webview: &tauri::WebviewWindow<tauri::test::MockRuntime>, cmd: &str, args: Value) -> Value {
+    match tauri::test::get_ipc_response(webview, invoke_request(cmd, args)) {
+        Ok(_) => panic!("{} should fail", cmd),
+        Err(error) => error,
+    }
+}
+
+/// 管理给定服务状态、只注册 `handler` 中命令的模拟应用和主窗口
+pub fn mock_webview<F>(
+    state: &FileManagerState,
+    handler: F,
+) -> (tauri::App<tauri::test::MockRuntime>, tauri::WebviewWindow<tauri::test::MockRuntime>)
+where
+    F: Fn(tauri::ipc::Invoke<tauri::test::MockRuntime>) -> bool + Send + Sync + 'static,
+{
+    let app = tauri::test::mock_builder()
+        .manage(state.clone())
+        .invoke_handler(handler)
+        .build(tauri::test::mock_context(tauri::test::noop_assets()))
+        .unwrap();
+    let webview = tauri::WebviewWindowBuilder::new(&app, "main", Default::default()).build().unwrap();
+    (app, webview)
+}
+
+/// 以测试资料库为服务的就绪状态
+pub fn ready_state(library: TestLibrary) -> (FileManagerState, TempDir) {
+    let (service, temp_dir) = library.into_parts();
+    let state = FileManagerState::new();
+    state.set_ready(service, StartupReport::default());
+    (state, temp_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_library_fixtures() {
+        let library = crate::file_manager::initialize_for_tests().await;
+        let directory_id = library.add_directory(None, "Icons").await;
+        let file_id = library.add_file_in(Some(&directory_id), "icon.png", &png_fixture(8, 8, 1)).await;
+        library.tag(&file_id, &["ui"]).await;
+        library.add_file("notes.txt", &text_fixture(1)).await;
+
+        assert_eq!(png_fixture(8, 8, 1), png_fixture(8, 8, 1));
+        assert_ne!(text_fixture(1), text_fixture(2));
+        assert!(!library.path().join("library.db").exists());
+        assert!(library.read_file_content(&file_id).await.unwrap().starts_with(b"\x89PNG"));
+        assert_eq!(library.quick_find("ui", 10).await.unwrap()[0].id, "ui");
+
+        let temp_path = library.path().to_path_buf();
+        drop(library);
+        assert!(!temp_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_builder_limits() {
+        let library = TestLibrary::builder()
+            .supported_file_types(&["txt"])
+            .max_file_size(4)
+            .build()
+            .await;
+        let upload = |name: &str, data: &[u8]| library.upload_file(UploadRequest {
+            file_data: data.to_vec(),
+            original_name: name.to_string(),
+            ..Default::default()
+        });
+
+        assert!(upload("a.txt", b"tiny").await.is_ok());
+        assert!(upload("b.txt", b"too large").await.is_err());
+        assert!(upload("c.png", b"png").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_storage() {
+        let library = TestLibrary::builder().in_memory_storage().build().await;
+        let directory_id = library.add_directory(None, "Notes").await;
+        let file_id = library.add_file_in(Some(&directory_id), "a.txt", &text_fixture(1)).await;
+        library.add_file("b.txt", &text_fixture(2)).await;
+
+        let storage = library.memory_storage().unwrap();
+        assert_eq!(storage.file_count(), 2);
+        assert!(!library.path().join("files").exists());
+        assert_eq!(library.read_file_content(&file_id).await.unwrap(), text_fixture(1));
+
+        library.delete_file(&file_id).await.unwrap();
+        assert_eq!(storage.file_count(), 1);
+        assert!(TestLibrary::builder().build().await.memory_storage().is_none());
+    }
+}
@@ -0,0 +1,809 @@
+//! 缩略图模块
+//!
+//! 提供多分辨率缩略图的生成和缓存：
+//! - 小/中/大三档尺寸（128/256/512）
+//! - 按需生成，结果缓存在应用数据目录中
+//! - 请求的尺寸尚未生成时返回最接近的已有尺寸
+//! - 按浏览位置在后台预取前后相邻文件的缩略图
+//! - 可设置编码格式（PNG / JPEG / WebP）、JPEG 质量、大尺寸的最长边和色彩空间，
+//!   修改设置后在后台按新设置重新生成已有的缩略图
+//! - 缓存总大小有上限（默认 2 GB），超出后按最近访问时间淘汰最久未用的缩略图；
+//!   访问时间记录在缩略图文件的修改时间上，重启后仍然有效
+//! - 以 `gpu-thumbnails` 特性编译并在配置文件中开启 `[thumbnails] gpu_acceleration` 时，
+//!   大图在 GPU 上缩小，GPU 不可用或出错时回退到 CPU；缓存文件与 CPU 生成的相同，切换后无需重新生成
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::heif;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, ImageDecoder, ImageEncoder};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::{BufRead, Cursor, Seek};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// 是否尝试在 GPU 上缩小图像，由配置文件设置
+static GPU_ACCELERATION: AtomicBool = AtomicBool::new(false);
+
+/// 保存预览设置的状态键
+pub const PREVIEW_SETTINGS_KEY: &str = "preview_settings";
+
+/// 默认 JPEG 质量
+pub const DEFAULT_PREVIEW_QUALITY: u8 = 85;
+
+/// 默认大尺寸缩略图的最长边
+pub const DEFAULT_MAX_PREVIEW_DIMENSION: u32 = 512;
+
+/// 大尺寸缩略图最长边的允许范围
+pub const MIN_PREVIEW_DIMENSION: u32 = 128;
+pub const MAX_PREVIEW_DIMENSION: u32 = 2048;
+
+/// 保存缩略图缓存设置的状态键
+pub const THUMBNAIL_CACHE_SETTINGS_KEY: &str = "thumbnail_cache_settings";
+
+/// 默认缓存上限
+pub const DEFAULT_THUMBNAIL_CACHE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// 缓存上限的最小值
+pub const MIN_THUMBNAIL_CACHE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// 超出上限后淘汰到上限的这一比例，避免每生成一个缩略图就淘汰一次
+const EVICTION_TARGET_PERCENT: u64 = 90;
+
+/// 访问时间的更新间隔，避免每次查看都写入文件系统
+const TOUCH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// 是否有重新生成任务在运行，避免重复启动
+static REGENERATION_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 缩略图尺寸档位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ThumbnailSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl ThumbnailSize {
+    /// 所有尺寸档位，按从小到大排列
+    pub const ALL: [ThumbnailSize; 3] = [Self::Small, Self::Medium, Self::Large];
+
+    /// 缩略图最长边的像素数
+    pub fn pixels(&self) -> u32 {
+        match self {
+            Self::Small => 128,
+            Self::Medium => 256,
+            Self::Large => 512,
+        }
+    }
+
+    /// 缓存文件名中的档位名
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Small => "small",
+            Self::Medium => "medium",
+            Self::Large => "large",
+        }
+    }
+
+    /// 从缓存文件名中的档位名解析
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|size| size.as_str() == name)
+    }
+}
+
+/// 缩略图编码格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewFormat {
+    #[default]
+    Png,
+    /// 有损压缩，按 `quality` 编码，透明区域变为黑色
+    Jpeg,
+    /// 无损压缩（image 库的 WebP 编码器只支持无损）
+    Webp,
+}
+
+impl PreviewFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+        }
+    }
+
+    /// 缩略图数据的 MIME 类型
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Webp => "image/webp",
+        }
+    }
+}
+
+/// 缩略图的色彩空间
+///
+/// 不做像素级的色彩转换，只决定是否携带原图的 ICC 配置文件
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewColorSpace {
+    /// 不嵌入配置文件，按 sRGB 显示
+    #[default]
+    Srgb,
+    /// 嵌入原图的 ICC 配置文件，支持色彩管理的界面按原图色彩空间显示广色域图像
+    Source,
+}
+
+/// 缩略图生成设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PreviewSettings {
+    #[serde(default)]
+    pub format: PreviewFormat,
+    /// JPEG 质量（1-100）
+    #[serde(default = "default_preview_quality")]
+    pub quality: u8,
+    /// 大尺寸缩略图的最长边，小尺寸和中尺寸不超过它
+    #[serde(default = "default_max_preview_dimension")]
+    pub max_dimension: u32,
+    #[serde(default)]
+    pub color_space: PreviewColorSpace,
+}
+
+fn default_preview_quality() -> u8 {
+    DEFAULT_PREVIEW_QUALITY
+}
+
+fn default_max_preview_dimension() -> u32 {
+    DEFAULT_MAX_PREVIEW_DIMENSION
+}
+
+impl Default for PreviewSettings {
+    fn default() -> Self {
+        Self {
+            format: PreviewFormat::default(),
+            quality: DEFAULT_PREVIEW_QUALITY,
+            max_dimension: DEFAULT_MAX_PREVIEW_DIMENSION,
+            color_space: PreviewColorSpace::default(),
+        }
+    }
+}
+
+impl PreviewSettings {
+    /// 检查质量和尺寸是否在允许范围内
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=100).contains(&self.quality) {
+            return Err(FileManagerError::general_error("Preview quality must be between 1 and 100"));
+        }
+        if !(MIN_PREVIEW_DIMENSION..=MAX_PREVIEW_DIMENSION).contains(&self.max_dimension) {
+            return Err(FileManagerError::general_error(format!(
+                "Preview max dimension must be between {} and {}", MIN_PREVIEW_DIMENSION, MAX_PREVIEW_DIMENSION
+            )));
+        }
+        Ok(())
+    }
+
+    /// 档位最长边的像素数
+    pub fn pixels(&self, size: ThumbnailSize) -> u32 {
+        match size {
+            ThumbnailSize::Large => self.max_dimension,
+            _ => size.pixels().min(self.max_dimension),
+        }
+    }
+
+    /// 缓存文件名
+    ///
+    /// 默认设置沿用 `small.png` 这样的文件名，已有缓存无需重新生成；
+    /// 其他设置在文件名中带上设置指纹，修改设置后旧缓存自然失效
+    fn file_name(&self, size: ThumbnailSize) -> String {
+        if *self == Self::default() {
+            return format!("{}.png", size.as_str());
+        }
+        let fingerprint = format!("{:x}", Sha256::digest(serde_json::to_vec(self).unwrap_or_default()));
+        format!("{}-{}.{}", size.as_str(), &fingerprint[..8], self.format.extension())
+    }
+}
+
+/// 缩略图缓存设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ThumbnailCacheSettings {
+    /// 缓存总大小上限（字节）
+    pub max_bytes: u64,
+}
+
+impl Default for ThumbnailCacheSettings {
+    fn default() -> Self {
+        Self { max_bytes: DEFAULT_THUMBNAIL_CACHE_BYTES }
+    }
+}
+
+impl ThumbnailCacheSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.max_bytes < MIN_THUMBNAIL_CACHE_BYTES {
+            return Err(FileManagerError::general_error(format!(
+                "Thumbnail cache limit must be at least {} bytes", MIN_THUMBNAIL_CACHE_BYTES
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 缩略图缓存占用情况
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CacheStats {
+    pub total_bytes: u64,
+    pub file_count: usize,
+    /// 当前的缓存上限
+    pub max_bytes: u64,
+}
+
+/// 默认预取当前位置前后各多少个文件
+pub const DEFAULT_PREFETCH_COUNT: usize = 3;
+
+/// 预取数量上限
+pub const MAX_PREFETCH_COUNT: usize = 20;
+
+/// 前端当前的浏览位置，用于预取相邻文件的缩略图
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PrefetchContext {
+    pub directory_id: String,
+    /// 当前文件在目录中按原始文件名排序后的位置
+    pub index: usize,
+    /// 前后各预取的文件数，默认 [`DEFAULT_PREFETCH_COUNT`]
+    #[serde(default)]
+    pub count: Option<usize>,
+}
+
+/// 计算需要预取的位置
+///
+/// 由近到远交替取后一个和前一个，越界的位置跳过，不包含当前位置
+pub fn prefetch_window(len: usize, index: usize, count: usize) -> Vec<usize> {
+    let mut window = Vec::with_capacity(count * 2);
+    for distance in 1..=count {
+        if let Some(next) = index.checked_add(distance).filter(|next| *next < len) {
+            window.push(next);
+        }
+        if let Some(previous) = index.checked_sub(distance).filter(|previous| *previous < len) {
+            window.push(previous);
+        }
+    }
+    window
+}
+
+/// 解码图像文件
+///
+/// 按文件内容识别格式，不依赖扩展名（存储文件的扩展名来自用户上传时的文件名）；
+/// HEIC / AVIF 优先在进程内解码，没有进程内解码器或解码失败时由外部工具转换后解码
+pub fn decode_image(source: &Path) -> Result<image::DynamicImage> {
+    decode_image_with_profile(source).map(|(image, _)| image)
+}
+
+/// 解码图像文件，同时读取嵌入的 ICC 配置文件
+fn decode_image_with_profile(source: &Path) -> Result<(DynamicImage, Option<Vec<u8>>)> {
+    if let Some(format) = heif::detect_file_format(source)? {
+        match heif::decode_native(source, format) {
+            Some(Ok(decoded)) => return Ok(decoded),
+            Some(Err(e)) => tracing::warn!("进程内解码失败，改用外部工具: {}, {}", source.display(), e),
+            None => {}
+        }
+        let png = heif::convert_to_png(source, format)?;
+        return decode_reader_with_profile(image::ImageReader::new(Cursor::new(png)).with_guessed_format()?);
+    }
+    decode_reader_with_profile(image::ImageReader::open(source)?.with_guessed_format()?)
+}
+
+fn decode_reader_with_profile<R: BufRead + Seek>(reader: image::ImageReader<R>) -> Result<(DynamicImage, Option<Vec<u8>>)> {
+    let decode_error = |e: image::ImageError| FileManagerError::general_error(format!("Failed to decode image: {}", e));
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(decode_error)?;
+    let icc_profile = decoder.icc_profile().unwrap_or(None);
+    let image = DynamicImage::from_decoder(decoder).map_err(decode_error)?;
+    Ok((image, icc_profile))
+}
+
+/// 设置是否在 GPU 上缩小图像；未以 `gpu-thumbnails` 特性编译时始终使用 CPU
+pub fn set_gpu_acceleration(enabled: bool) {
+    if enabled && !cfg!(feature = "gpu-thumbnails") {
+        tracing::warn!("未以 gpu-thumbnails 特性编译，缩略图使用 CPU 生成");
+    }
+    GPU_ACCELERATION.store(enabled, Ordering::Relaxed);
+}
+
+/// 保持宽高比把图像缩放到 `pixels` × `pixels` 以内，开启 GPU 加速时优先在 GPU 上缩小
+fn downscale(image: &DynamicImage, pixels: u32) -> DynamicImage {
+    #[cfg(feature = "gpu-thumbnails")]
+    if GPU_ACCELERATION.load(Ordering::Relaxed) {
+        match crate::file_manager::gpu_resize::downscale(image, pixels) {
+            Some(Ok(thumbnail)) => return thumbnail,
+            Some(Err(e)) => tracing::warn!("GPU 缩放失败，改用 CPU: {}", e),
+            None => {}
+        }
+    }
+    image.thumbnail(pixels, pixels)
+}
+
+/// 按格式编码图像，`quality` 只用于 JPEG；缩略图和导出预设共用
+pub fn encode_image(
+    image: &DynamicImage,
+    format: PreviewFormat,
+    quality: u8,
+    icc_profile: Option<Vec<u8>>,
+    target: &Path,
+) -> Result<()> {
+    let writer = std::io::BufWriter::new(std::fs::File::create(target)?);
+    let result = match format {
+        PreviewFormat::Png => write_preview(image, PngEncoder::new(writer), icc_profile),
+        PreviewFormat::Jpeg => write_preview(
+            &DynamicImage::ImageRgb8(image.to_rgb8()),
+            JpegEncoder::new_with_quality(writer, quality),
+            icc_profile,
+        ),
+        PreviewFormat::Webp => write_preview(
+            &DynamicImage::ImageRgba8(image.to_rgba8()),
+            WebPEncoder::new_lossless(writer),
+            icc_profile,
+        ),
+    };
+    result.map_err(|e| FileManagerError::general_error(format!("Failed to encode image: {}", e)))
+}
+
+fn write_preview(
+    image: &DynamicImage,
+    mut encoder: impl ImageEncoder,
+    icc_profile: Option<Vec<u8>>,
+) -> image::ImageResult<()> {
+    if let Some(icc_profile) = icc_profile {
+        // 嵌入失败时仍输出缩略图，只是按 sRGB 显示
+        if let Err(e) = encoder.set_icc_profile(icc_profile) {
+            tracing::debug!("缩略图无法嵌入 ICC 配置文件: {}", e);
+        }
+    }
+    image.write_with_encoder(encoder)
+}
+
+/// 需要按当前设置重新生成的缩略图
+#[derive(Debug, Clone)]
+pub struct StalePreviews {
+    pub file_id: String,
+    /// 已有旧缩略图的档位
+    pub sizes: Vec<ThumbnailSize>,
+}
+
+/// 缩略图服务
+///
+/// 缓存布局为 `cache_dir/<file_id>/<size>[-<设置指纹>].<格式>`；可以廉价克隆，用于后台生成任务
+#[derive(Debug, Clone)]
+pub struct ThumbnailService {
+    cache_dir: PathBuf,
+    settings: PreviewSettings,
+    max_cache_bytes: u64,
+    /// 正在生成中的缩略图，避免重复生成
+    in_flight: Arc<Mutex<HashSet<(String, ThumbnailSize)>>>,
+    /// 缓存占用的估计值，生成时累加，淘汰时重新统计；尚未统计时为 `None`
+    cache_usage: Arc<Mutex<Option<u64>>>,
+}
+
+impl ThumbnailService {
+    /// 创建新的缩略图服务实例
+    pub fn new(cache_dir: &Path, settings: PreviewSettings) -> Self {
+        Self {
+            cache_dir: cache_dir.to_path_buf(),
+            settings,
+            max_cache_bytes: DEFAULT_THUMBNAIL_CACHE_BYTES,
+            in_flight: Arc::new(Mutex::new(HashSet::new())),
+            cache_usage: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// 当前的生成设置
+    pub fn settings(&self) -> PreviewSettings {
+        self.settings
+    }
+
+    /// 更换生成设置，之后生成和查找的缩略图都使用新设置
+    pub fn apply_settings(&mut self, settings: PreviewSettings) {
+        self.settings = settings;
+    }
+
+    /// 更换缓存上限，下次生成缩略图时按新上限淘汰
+    pub fn apply_cache_settings(&mut self, settings: ThumbnailCacheSettings) {
+        self.max_cache_bytes = settings.max_bytes;
+    }
+
+    /// 获取缩略图缓存路径
+    pub fn thumbnail_path(&self, file_id: &str, size: ThumbnailSize) -> PathBuf {
+        self.cache_dir.join(file_id).join(self.settings.file_name(size))
+    }
+
+    /// 查找最合适的已有缩略图
+    ///
+    /// 优先返回请求的尺寸，其次是比它大的最小尺寸（缩小显示不失真），最后是比它小的最大尺寸
+    pub fn best_available(&self, file_id: &str, size: ThumbnailSize) -> Option<(ThumbnailSize, PathBuf)> {
+        let larger = ThumbnailSize::ALL.into_iter().filter(|candidate| *candidate >= size);
+        let smaller = ThumbnailSize::ALL.into_iter().rev().filter(|candidate| *candidate < size);
+
+        let found = larger.chain(smaller)
+            .map(|candidate| (candidate, self.thumbnail_path(file_id, candidate)))
+            .find(|(_, path)| path.is_file());
+        if let Some((_, path)) = &found {
+            touch(path);
+        }
+        found
+    }
+
+    /// 同步生成缩略图
+    ///
+    /// 按内容识别图像格式，先写入临时文件再重命名，避免读到写了一半的缩略图
+    pub fn generate(&self, source: &Path, file_id: &str, size: ThumbnailSize) -> Result<PathBuf> {
+        let (image, icc_profile) = decode_image_with_profile(source)?;
+        let pixels = self.settings.pixels(size);
+        let thumbnail = downscale(&image, pixels);
+
+        let target = self.thumbnail_path(file_id, size);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let icc_profile = match self.settings.color_space {
+            PreviewColorSpace::Source => icc_profile,
+            PreviewColorSpace::Srgb => None,
+        };
+        let temp_path = target.with_extension("tmp");
+        encode_image(&thumbnail, self.settings.format, self.settings.quality, icc_profile, &temp_path)?;
+        std::fs::rename(&temp_path, &target)?;
+
+        self.record_written(std::fs::metadata(&target).map(|metadata| metadata.len()).unwrap_or(0));
+        Ok(target)
+    }
+
+    /// 在阻塞线程池中生成缩略图
+    pub async fn generate_async(&self, source: PathBuf, file_id: String, size: ThumbnailSize) -> Result<PathBuf> {
+        let service = self.clone();
+        tokio::task::spawn_blocking(move || service.generate(&source, &file_id, size))
+            .await
+            .map_err(|e| FileManagerError::general_error(format!("Thumbnail task failed: {}", e)))?
+    }
+
+    /// 在后台生成缩略图，同一缩略图同时只会有一个生成任务
+    pub fn generate_in_background(&self, source: PathBuf, file_id: String, size: ThumbnailSize) {
+        let key = (file_id.clone(), size);
+        if !self.in_flight.lock().unwrap().insert(key.clone()) {
+            return;
+        }
+
+        let service = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = service.generate_async(source, file_id, size).await {
+                tracing::warn!("后台生成缩略图失败: {} {:?}: {}", key.0, size, e);
+            }
+            service.in_flight.lock().unwrap().remove(&key);
+        });
+    }
+
+    /// 删除文件的所有缩略图
+    ///
+    /// 文件被删除或内容被替换后调用
+    pub async fn remove(&self, file_id: &str) -> Result<()> {
+        match tokio::fs::remove_dir_all(self.cache_dir.join(file_id)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(FileManagerError::FileSystem(e)),
+        }
+    }
+
+    /// 查找按其他设置生成的缩略图
+    pub fn stale_previews(&self) -> Vec<StalePreviews> {
+        let Ok(entries) = std::fs::read_dir(&self.cache_dir) else {
+            return Vec::new();
+        };
+
+        let mut stale = Vec::new();
+        for entry in entries.flatten() {
+            let file_id = entry.file_name().to_string_lossy().to_string();
+            let sizes: HashSet<ThumbnailSize> = self.stale_files(&file_id)
+                .iter()
+                .filter_map(|path| {
+                    let name = path.file_name()?.to_string_lossy().to_string();
+                    ThumbnailSize::from_name(name.split(['-', '.']).next()?)
+                })
+                .collect();
+            if !sizes.is_empty() {
+                let mut sizes: Vec<ThumbnailSize> = sizes.into_iter().collect();
+                sizes.sort();
+                stale.push(StalePreviews { file_id, sizes });
+            }
+        }
+        stale
+    }
+
+    /// 删除文件按其他设置生成的缩略图
+    pub fn remove_stale(&self, file_id: &str) {
+        for path in self.stale_files(file_id) {
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::warn!("旧缩略图删除失败: {:?}, {}", path, e);
+            }
+        }
+    }
+
+    /// 统计缓存占用
+    pub fn cache_stats(&self) -> CacheStats {
+        let entries = self.cache_entries();
+        let total_bytes = entries.iter().map(|(_, size, _)| size).sum();
+        *self.cache_usage.lock().unwrap() = Some(total_bytes);
+        CacheStats { total_bytes, file_count: entries.len(), max_bytes: self.max_cache_bytes }
+    }
+
+    /// 删除所有缩略图，返回释放的字节数；之后查看时重新生成
+    pub fn clear(&self) -> Result<u64> {
+        let freed = self.cache_entries().iter().map(|(_, size, _)| size).sum();
+        let entries = match std::fs::read_dir(&self.cache_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries.flatten() {
+            match std::fs::remove_dir_all(entry.path()) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        *self.cache_usage.lock().unwrap() = Some(0);
+        Ok(freed)
+    }
+
+    /// 缓存超出上限时，按访问时间从旧到新删除缩略图，直到低于上限的 90%；返回释放的字节数
+    pub fn enforce_cache_limit(&self) -> u64 {
+        let mut entries = self.cache_entries();
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        let mut freed = 0;
+        if total > self.max_cache_bytes {
+            let target = self.max_cache_bytes / 100 * EVICTION_TARGET_PERCENT;
+            entries.sort_by_key(|(_, _, accessed)| *accessed);
+            for (path, size, _) in entries {
+                if total <= target {
+                    break;
+                }
+                match std::fs::remove_file(&path) {
+                    Ok(()) => {
+                        total -= size;
+                        freed += size;
+                        if let Some(parent) = path.parent() {
+                            // 目录中还有其他缩略图时删除失败，忽略
+                            let _ = std::fs::remove_dir(parent);
+                        }
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => total -= size,
+                    Err(e) => tracing::warn!("缩略图淘汰失败: {:?}, {}", path, e),
+                }
+            }
+            tracing::info!("缩略图缓存超出上限，已淘汰 {} 字节", freed);
+        }
+        *self.cache_usage.lock().unwrap() = Some(total);
+        freed
+    }
+
+    /// 累加新写入的缩略图大小，估计值超出上限时重新统计并淘汰
+    fn record_written(&self, bytes: u64) {
+        let over_limit = match self.cache_usage.lock().unwrap().as_mut() {
+            Some(usage) => {
+                *usage += bytes;
+                *usage > self.max_cache_bytes
+            }
+            None => true,
+        };
+        if over_limit {
+            self.enforce_cache_limit();
+        }
+    }
+
+    /// 缓存中的所有缩略图及其大小和访问时间，不含生成中的临时文件
+    fn cache_entries(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let Ok(directories) = std::fs::read_dir(&self.cache_dir) else {
+            return Vec::new();
+        };
+        directories
+            .flatten()
+            .filter_map(|directory| std::fs::read_dir(directory.path()).ok())
+            .flat_map(|entries| entries.flatten())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext != "tmp"))
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok().filter(|metadata| metadata.is_file())?;
+                Some((entry.path(), metadata.len(), metadata.modified().ok()?))
+            })
+            .collect()
+    }
+
+    /// 文件缓存目录中不属于当前设置的缩略图，不含生成中的临时文件
+    fn stale_files(&self, file_id: &str) -> Vec<PathBuf> {
+        let Ok(entries) = std::fs::read_dir(self.cache_dir.join(file_id)) else {
+            return Vec::new();
+        };
+        let current: Vec<PathBuf> = ThumbnailSize::ALL.iter().map(|size| self.thumbnail_path(file_id, *size)).collect();
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext != "tmp") && !current.contains(path))
+            .collect()
+    }
+}
+
+/// 把缩略图的修改时间更新为当前时间，作为淘汰时的访问时间
+fn touch(path: &Path) {
+    let now = SystemTime::now();
+    let recent = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| now.duration_since(modified).is_ok_and(|elapsed| elapsed < TOUCH_INTERVAL));
+    if recent {
+        return;
+    }
+    if let Err(e) = std::fs::File::options().write(true).open(path).and_then(|file| file.set_modified(now)) {
+        tracing::debug!("缩略图访问时间更新失败: {:?}, {}", path, e);
+    }
+}
+
+/// 在后台按当前设置重新生成旧缩略图
+///
+/// 每轮在服务锁内列出旧缩略图，释放锁后逐个生成，生成后删除该文件的旧缩略图；
+/// 生成期间设置再次变化时，下一轮处理按中间设置生成的缩略图
+pub fn spawn_preview_regeneration(state: FileManagerState) {
+    if REGENERATION_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let mut regenerated = 0;
+        loop {
+            let jobs = {
+                let Ok(service) = state.lock().await else {
+                    break;
+                };
+                // 只读模式下暂停生成，下次以可写模式启动时继续
+                if service.is_read_only() {
+                    break;
+                }
+                service.stale_preview_jobs().await
+            };
+            let (thumbnails, jobs) = match jobs {
+                Ok((_, jobs)) if jobs.is_empty() => break,
+                Ok(jobs) => jobs,
+                Err(e) => {
+                    tracing::warn!("旧缩略图查找失败: {}", e);
+                    break;
+                }
+            };
+
+            for (stale, source) in jobs {
+                if let Some(source) = source {
+                    for size in &stale.sizes {
+                        match thumbnails.generate_async(source.clone(), stale.file_id.clone(), *size).await {
+                            Ok(_) => regenerated += 1,
+                            Err(e) => tracing::warn!("缩略图重新生成失败: {} {:?}: {}", stale.file_id, size, e),
+                        }
+                    }
+                }
+                thumbnails.remove_stale(&stale.file_id);
+            }
+        }
+
+        tracing::info!("缩略图已按新设置重新生成: {} 个", regenerated);
+        REGENERATION_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_generate_and_pick_best_available() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source.bin");
+        image::RgbImage::new(600, 300).save_with_format(&source, image::ImageFormat::Png).unwrap();
+
+        let service = ThumbnailService::new(&temp_dir.path().join("thumbnails"), PreviewSettings::default());
+        assert!(service.best_available("file-1", ThumbnailSize::Medium).is_none());
+
+        let path = service.generate(&source, "file-1", ThumbnailSize::Small).unwrap();
+        let (width, height) = image::image_dimensions(&path).unwrap();
+        assert_eq!((width, height), (128, 64));
+
+        // 只有小尺寸时，请求大尺寸返回小尺寸
+        let (size, _) = service.best_available("file-1", ThumbnailSize::Large).unwrap();
+        assert_eq!(size, ThumbnailSize::Small);
+
+        // 有更大尺寸时优先使用更大的
+        service.generate(&source, "file-1", ThumbnailSize::Large).unwrap();
+        let (size, _) = service.best_available("file-1", ThumbnailSize::Medium).unwrap();
+        assert_eq!(size, ThumbnailSize::Large);
+    }
+
+    #[test]
+    fn test_settings_change_makes_previews_stale() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source.bin");
+        image::RgbImage::new(1200, 600).save_with_format(&source, image::ImageFormat::Png).unwrap();
+
+        let mut service = ThumbnailService::new(&temp_dir.path().join("thumbnails"), PreviewSettings::default());
+        let legacy = service.generate(&source, "file-1", ThumbnailSize::Large).unwrap();
+        assert!(legacy.ends_with("file-1/large.png"));
+        assert!(service.stale_previews().is_empty());
+
+        service.apply_settings(PreviewSettings {
+            format: PreviewFormat::Jpeg,
+            max_dimension: 1024,
+            ..PreviewSettings::default()
+        });
+        assert!(service.best_available("file-1", ThumbnailSize::Large).is_none());
+        let stale = service.stale_previews();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].sizes, [ThumbnailSize::Large]);
+
+        let path = service.generate(&source, "file-1", ThumbnailSize::Large).unwrap();
+        assert_eq!(image::ImageReader::open(&path).unwrap().with_guessed_format().unwrap().format(), Some(image::ImageFormat::Jpeg));
+        assert_eq!(image::image_dimensions(&path).unwrap(), (1024, 512));
+        service.remove_stale("file-1");
+        assert!(!legacy.exists());
+        assert!(service.stale_previews().is_empty());
+
+        assert!(PreviewSettings { quality: 0, ..PreviewSettings::default() }.validate().is_err());
+        assert!(PreviewSettings { max_dimension: 64, ..PreviewSettings::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_cache_limit_evicts_least_recently_used() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source.bin");
+        image::RgbImage::from_fn(600, 300, |x, y| image::Rgb([x as u8, y as u8, (x * y) as u8]))
+            .save_with_format(&source, image::ImageFormat::Png)
+            .unwrap();
+
+        let mut service = ThumbnailService::new(&temp_dir.path().join("thumbnails"), PreviewSettings::default());
+        for (file_id, hours_ago) in [("file-1", 3), ("file-2", 2), ("file-3", 0)] {
+            let path = service.generate(&source, file_id, ThumbnailSize::Small).unwrap();
+            let accessed = SystemTime::now() - Duration::from_secs(hours_ago * 60 * 60);
+            std::fs::File::options().write(true).open(&path).unwrap().set_modified(accessed).unwrap();
+        }
+        let stats = service.cache_stats();
+        assert_eq!(stats.file_count, 3);
+
+        // 查看后 file-1 成为最近使用，最久未用的 file-2 被淘汰
+        assert!(service.best_available("file-1", ThumbnailSize::Small).is_some());
+        let each = stats.total_bytes / 3;
+        service.apply_cache_settings(ThumbnailCacheSettings { max_bytes: each * 2 * 100 / EVICTION_TARGET_PERCENT + 100 });
+        assert_eq!(service.enforce_cache_limit(), each);
+        assert!(service.best_available("file-2", ThumbnailSize::Small).is_none());
+        assert!(!temp_dir.path().join("thumbnails/file-2").exists());
+        assert_eq!(service.cache_stats().file_count, 2);
+
+        assert_eq!(service.clear().unwrap(), each * 2);
+        assert_eq!(service.cache_stats().total_bytes, 0);
+        assert!(ThumbnailCacheSettings { max_bytes: 1024 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_prefetch_window() {
+        assert_eq!(prefetch_window(10, 5, 2), vec![6, 4, 7, 3]);
+        assert_eq!(prefetch_window(3, 0, 3), vec![1, 2]);
+        assert_eq!(prefetch_window(3, 2, 1), vec![1]);
+        assert!(prefetch_window(0, 0, 3).is_empty());
+    }
+}
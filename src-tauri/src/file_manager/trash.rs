@@ -0,0 +1,153 @@
+//! 回收站模块
+//!
+//! 开启后删除文件时不立即删除存储文件，而是移入回收站，在保留期内可以恢复：
+//! - 存储文件移入应用数据目录的 `trash` 目录，记录原始文件名、所在目录、标签和删除时间
+//! - 删除文件和删除目录都会把其中的文件移入回收站；链接文件只删除记录，原始文件不受影响；
+//!   批量操作和重复文件清理有各自的撤销，不经过回收站
+//! - 恢复时导入到原目录（目录已不存在时导入到根目录），同名文件自动重命名，标签一并恢复
+//! - 后台任务每小时按保留策略清理：超过保留天数的文件永久删除，总大小超过上限时从最早删除的开始清理
+
+use crate::file_manager::commands::FileManagerState;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use ts_rs::TS;
+
+/// 保存回收站设置的状态键
+pub const TRASH_SETTINGS_KEY: &str = "trash";
+
+/// 后台任务按保留策略清理的间隔
+pub const TRASH_RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// 默认保留天数
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// 回收站设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TrashSettings {
+    /// 关闭时删除的文件立即永久删除
+    pub enabled: bool,
+    /// 删除超过该天数的文件自动清理，为空时不按时间清理
+    pub retention_days: Option<u32>,
+    /// 回收站总大小上限（字节），超过时从最早删除的文件开始清理，为空时不限制
+    pub max_size: Option<u64>,
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: Some(DEFAULT_RETENTION_DAYS),
+            max_size: None,
+        }
+    }
+}
+
+/// 回收站中的文件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TrashedFile {
+    pub id: String,
+    /// 删除前的文件 ID，恢复后的文件使用新 ID
+    pub file_id: String,
+    pub original_name: String,
+    /// 删除前所在的目录
+    pub directory_id: String,
+    pub file_size: i64,
+    pub mime_type: String,
+    pub tags: Vec<String>,
+    pub deleted_at: String,
+}
+
+/// 按保留策略清理的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TrashPurgeReport {
+    pub purged: usize,
+    pub freed_bytes: u64,
+}
+
+/// 按保留策略选出需要清理的文件
+///
+/// `files` 按删除时间从新到旧排列；删除时间无法解析的文件只按大小上限清理
+pub fn expired_files<'a>(files: &'a [TrashedFile], settings: &TrashSettings, now: DateTime<Local>) -> Vec<&'a TrashedFile> {
+    let cutoff = settings.retention_days.map(|days| now - chrono::Duration::days(days as i64));
+    let mut kept_size = 0u64;
+
+    files
+        .iter()
+        .filter(|file| {
+            let too_old = cutoff.is_some_and(|cutoff| {
+                DateTime::parse_from_rfc3339(&file.deleted_at).is_ok_and(|deleted_at| deleted_at < cutoff)
+            });
+            if too_old {
+                return true;
+            }
+
+            kept_size += file.file_size.max(0) as u64;
+            settings.max_size.is_some_and(|max_size| kept_size > max_size)
+        })
+        .collect()
+}
+
+/// 启动后台任务，开启回收站后定期按保留策略清理
+pub fn spawn_retention_loop(state: FileManagerState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TRASH_RETENTION_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let Ok(service) = state.lock().await else {
+                continue;
+            };
+            // 只读模式下由写入的进程清理
+            if service.is_read_only() {
+                continue;
+            }
+            match service.apply_trash_retention().await {
+                Ok(report) if report.purged > 0 => {
+                    tracing::info!("回收站清理完成: 删除 {} 个文件, 释放 {} 字节", report.purged, report.freed_bytes);
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("回收站清理失败: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trashed(id: &str, file_size: i64, deleted_at: DateTime<Local>) -> TrashedFile {
+        TrashedFile {
+            id: id.to_string(),
+            file_id: format!("file-{}", id),
+            original_name: format!("{}.txt", id),
+            directory_id: "root".to_string(),
+            file_size,
+            mime_type: "text/plain".to_string(),
+            tags: Vec::new(),
+            deleted_at: deleted_at.to_rfc3339(),
+        }
+    }
+
+    #[test]
+    fn test_expired_by_age_and_size() {
+        let now = Local::now();
+        let files = vec![
+            trashed("new", 40, now - chrono::Duration::days(1)),
+            trashed("middle", 40, now - chrono::Duration::days(5)),
+            trashed("old", 10, now - chrono::Duration::days(40)),
+        ];
+        let ids = |settings: &TrashSettings| -> Vec<String> {
+            expired_files(&files, settings, now).iter().map(|file| file.id.clone()).collect()
+        };
+
+        assert_eq!(ids(&TrashSettings::default()), vec!["old"]);
+        assert_eq!(ids(&TrashSettings { enabled: true, retention_days: None, max_size: Some(50) }), vec!["middle", "old"]);
+        assert_eq!(ids(&TrashSettings { enabled: true, retention_days: Some(30), max_size: Some(80) }), vec!["old"]);
+        assert!(ids(&TrashSettings { enabled: true, retention_days: None, max_size: None }).is_empty());
+    }
+}
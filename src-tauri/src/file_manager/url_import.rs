@@ -0,0 +1,205 @@
+//! URL 导入模块
+//!
+//! 从远程地址下载文件，供"粘贴链接添加文件"使用：
+//! - 只允许 http/https 地址
+//! - 按配置的最大文件大小限制下载量，超出时立即中止
+//! - 从响应头或地址中推断文件名，缺少扩展名时根据内容类型补全（优先使用支持的扩展名）
+
+use crate::file_manager::error::{FileManagerError, Result};
+use std::time::Duration;
+
+/// 下载超时时间
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 无法推断文件名时使用的名称
+const DEFAULT_FILE_NAME: &str = "download";
+
+/// 下载得到的文件
+#[derive(Debug, Clone)]
+pub struct DownloadedFile {
+    pub data: Vec<u8>,
+    pub file_name: String,
+    /// 重定向后的最终地址
+    pub final_url: String,
+}
+
+/// 解析并检查导入地址
+pub fn parse_import_url(url: &str) -> Result<reqwest::Url> {
+    let parsed = reqwest::Url::parse(url.trim())
+        .map_err(|e| FileManagerError::general_error(format!("Invalid URL: {}", e)))?;
+
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed),
+        scheme => Err(FileManagerError::general_error(format!("Unsupported URL scheme: {}", scheme))),
+    }
+}
+
+/// 下载远程文件
+///
+/// 响应声明的长度或实际接收的数据超过 `max_size` 时返回 `FileSizeExceeded`
+pub async fn download(url: reqwest::Url, max_size: u64, supported_types: &[String]) -> Result<DownloadedFile> {
+    let client = reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .user_agent(concat!("Collaboard/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| FileManagerError::general_error(format!("Failed to create HTTP client: {}", e)))?;
+
+    let mut response = client.get(url).send().await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| FileManagerError::general_error(format!("Download failed: {}", e)))?;
+
+    if let Some(length) = response.content_length() {
+        if length > max_size {
+            return Err(FileManagerError::FileSizeExceeded { size: length, max_size, category: None });
+        }
+    }
+
+    let content_type = header_value(&response, reqwest::header::CONTENT_TYPE);
+    if content_type.as_deref().is_some_and(|value| value.starts_with("text/html")) {
+        return Err(FileManagerError::UnsupportedFileType {
+            file_type: "text/html".to_string(),
+        });
+    }
+
+    let file_name = file_name_from_response(&response, content_type.as_deref(), supported_types);
+    let final_url = response.url().to_string();
+
+    let mut data = Vec::new();
+    while let Some(chunk) = response.chunk().await
+        .map_err(|e| FileManagerError::general_error(format!("Download failed: {}", e)))?
+    {
+        let size = (data.len() + chunk.len()) as u64;
+        if size > max_size {
+            return Err(FileManagerError::FileSizeExceeded { size, max_size, category: None });
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    Ok(DownloadedFile { data, file_name, final_url })
+}
+
+/// 读取响应头的文本值
+fn header_value(response: &reqwest::Response, name: reqwest::header::HeaderName) -> Option<String> {
+    response.headers().get(name)?.to_str().ok().map(str::to_string)
+}
+
+/// 推断下载文件的文件名
+///
+/// 依次使用 `Content-Disposition` 中的文件名和地址的最后一段路径；
+/// 没有扩展名时根据 `Content-Type` 补全
+fn file_name_from_response(
+    response: &reqwest::Response,
+    content_type: Option<&str>,
+    supported_types: &[String],
+) -> String {
+    let from_disposition = header_value(response, reqwest::header::CONTENT_DISPOSITION)
+        .and_then(|value| disposition_file_name(&value));
+    let from_url = response.url().path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .map(percent_decode)
+        .filter(|segment| !segment.is_empty());
+
+    let file_name = from_disposition.or(from_url).unwrap_or_else(|| DEFAULT_FILE_NAME.to_string());
+    if std::path::Path::new(&file_name).extension().is_some() {
+        return file_name;
+    }
+
+    let extension = content_type
+        .and_then(|value| value.split(';').next())
+        .and_then(|mime| mime_guess::get_mime_extensions_str(mime.trim()))
+        .and_then(|extensions| {
+            extensions.iter()
+                .find(|extension| supported_types.iter().any(|supported| supported == *extension))
+                .or(extensions.first())
+        });
+    match extension {
+        Some(extension) => format!("{}.{}", file_name, extension),
+        None => file_name,
+    }
+}
+
+/// 从 `Content-Disposition` 头中提取文件名
+fn disposition_file_name(value: &str) -> Option<String> {
+    value.split(';')
+        .filter_map(|part| part.trim().split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("filename"))
+        .map(|(_, name)| name.trim().trim_matches('"').to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// 解码地址路径中的百分号编码，无效的编码原样保留
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = bytes.get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// 启动只响应一次请求的本地 HTTP 服务，返回其地址
+    pub(crate) async fn serve_once(headers: &str, body: Vec<u8>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let response_head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n",
+            body.len(), headers
+        );
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 4096];
+            let _ = socket.read(&mut request).await;
+            socket.write_all(response_head.as_bytes()).await.unwrap();
+            socket.write_all(&body).await.unwrap();
+        });
+
+        format!("http://{}", address)
+    }
+
+    #[test]
+    fn test_parse_import_url() {
+        assert!(parse_import_url("https://example.com/a.png").is_ok());
+        assert!(parse_import_url("file:///etc/passwd").is_err());
+        assert!(parse_import_url("not a url").is_err());
+
+        assert_eq!(disposition_file_name(r#"attachment; filename="photo 1.jpg""#).as_deref(), Some("photo 1.jpg"));
+        assert_eq!(percent_decode("%E7%85%A7%E7%89%87.png"), "照片.png");
+    }
+
+    #[tokio::test]
+    async fn test_download_infers_name_and_enforces_limit() {
+        let supported = vec!["jpg".to_string()];
+
+        let base = serve_once("Content-Type: image/jpeg\r\n", vec![7; 32]).await;
+        let url = parse_import_url(&format!("{}/images/cat", base)).unwrap();
+        let downloaded = download(url, 1024, &supported).await.unwrap();
+        assert_eq!(downloaded.data.len(), 32);
+        assert_eq!(downloaded.file_name, "cat.jpg");
+
+        let base = serve_once("Content-Type: image/png\r\n", vec![7; 32]).await;
+        let url = parse_import_url(&format!("{}/big.png", base)).unwrap();
+        assert!(matches!(
+            download(url, 16, &supported).await,
+            Err(FileManagerError::FileSizeExceeded { size: 32, max_size: 16, category: None })
+        ));
+    }
+}
@@ -0,0 +1,204 @@
+//! 使用统计和推荐模块
+//!
+//! 记录文件和标签的使用次数，为“推荐素材”面板提供与当前画板或目录相关的文件：
+//! - 使用分为打开（在查看器或外部应用中打开）和放置（拖到画板上），放置时记录画板 ID
+//! - 每次使用同时累加文件所带标签的使用次数；文件删除后标签的统计仍保留，已删除文件的统计不再参与推荐
+//! - 推荐得分由四部分组成：在当前画板上的放置次数、是否位于当前目录、与常用标签的重合度、全局使用频率
+//! - 有画板时常用标签取自画板上已放置的文件，否则取全局的标签使用次数
+//! - 全局使用频率按距上次使用的时间衰减，很久没用的文件逐渐排到后面
+//! - 前端可传入已在画板上的文件 ID，避免推荐已经放置的素材
+
+use crate::file_manager::service::FileListItem;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::HashMap;
+
+/// 默认返回的推荐数
+pub const DEFAULT_SUGGESTED_FILES: usize = 20;
+
+/// 推荐数上限
+pub const MAX_SUGGESTED_FILES: usize = 100;
+
+/// 参与打分的候选文件数上限
+pub const MAX_SUGGESTION_CANDIDATES: usize = 500;
+
+/// 用于查找候选文件的常用标签数
+pub const MAX_SUGGESTION_TAGS: usize = 10;
+
+/// 全局使用频率的半衰期（天）
+const USAGE_HALF_LIFE_DAYS: f64 = 30.0;
+
+const BOARD_WEIGHT: f64 = 4.0;
+const DIRECTORY_WEIGHT: f64 = 2.0;
+const TAG_WEIGHT: f64 = 3.0;
+const FREQUENCY_WEIGHT: f64 = 1.0;
+
+/// 使用类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageKind {
+    /// 在查看器或外部应用中打开
+    Open,
+    /// 放置到画板上
+    Placement,
+}
+
+impl UsageKind {
+    /// 获取数据库中存储的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Placement => "placement",
+        }
+    }
+}
+
+/// 推荐的上下文
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SuggestionContext {
+    /// 当前画板 ID
+    #[serde(default)]
+    pub board_id: Option<String>,
+    /// 当前目录 ID
+    #[serde(default)]
+    pub directory_id: Option<String>,
+    /// 不推荐的文件，如已在画板上的文件
+    #[serde(default)]
+    pub exclude_file_ids: Vec<String>,
+    /// 返回的推荐数，默认 [`DEFAULT_SUGGESTED_FILES`]
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// 推荐理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionReason {
+    /// 曾放置到当前画板上
+    UsedOnBoard,
+    /// 位于当前目录
+    InDirectory,
+    /// 带有常用标签
+    SharedTags,
+    /// 经常使用
+    FrequentlyUsed,
+}
+
+/// 一条推荐
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SuggestedFile {
+    pub file: FileListItem,
+    pub score: f64,
+    pub reasons: Vec<SuggestionReason>,
+}
+
+/// 文件的使用统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileUsageStats {
+    pub file_id: String,
+    /// 所有类型的使用次数
+    pub total_uses: u64,
+    /// 在指定画板上的放置次数
+    pub board_uses: u64,
+    pub last_used_at: Option<DateTime<Local>>,
+}
+
+/// 待打分的候选文件
+#[derive(Debug, Clone, Default)]
+pub struct SuggestionCandidate {
+    pub file_id: String,
+    pub total_uses: u64,
+    pub board_uses: u64,
+    pub last_used_at: Option<DateTime<Local>>,
+    pub in_directory: bool,
+    pub tags: Vec<String>,
+}
+
+/// 把标签的使用次数换算为 0 到 1 之间的权重，使用最多的标签为 1
+pub fn tag_weights(counts: &HashMap<String, u64>) -> HashMap<String, f64> {
+    let max = counts.values().copied().max().unwrap_or(0);
+    if max == 0 {
+        return HashMap::new();
+    }
+    counts.iter()
+        .filter(|(_, count)| **count > 0)
+        .map(|(tag, count)| (tag.clone(), *count as f64 / max as f64))
+        .collect()
+}
+
+/// 按权重取常用标签，权重相同时按标签名排列
+pub fn top_tags(weights: &HashMap<String, f64>, limit: usize) -> Vec<String> {
+    let mut tags: Vec<_> = weights.iter().collect();
+    tags.sort_by(|a, b| b.1.total_cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    tags.into_iter().take(limit).map(|(tag, _)| tag.clone()).collect()
+}
+
+/// 计算候选文件的得分和推荐理由
+pub fn score_candidate(
+    candidate: &SuggestionCandidate,
+    tag_weights: &HashMap<String, f64>,
+    now: DateTime<Local>,
+) -> (f64, Vec<SuggestionReason>) {
+    let mut score = 0.0;
+    let mut reasons = Vec::new();
+
+    if candidate.board_uses > 0 {
+        score += BOARD_WEIGHT * (1.0 + candidate.board_uses as f64).ln();
+        reasons.push(SuggestionReason::UsedOnBoard);
+    }
+    if candidate.in_directory {
+        score += DIRECTORY_WEIGHT;
+        reasons.push(SuggestionReason::InDirectory);
+    }
+
+    // 多个常用标签的权重相加，最多按 1 计
+    let tag_score: f64 = candidate.tags.iter()
+        .filter_map(|tag| tag_weights.get(tag))
+        .sum();
+    if tag_score > 0.0 {
+        score += TAG_WEIGHT * tag_score.min(1.0);
+        reasons.push(SuggestionReason::SharedTags);
+    }
+
+    if candidate.total_uses > 0 {
+        let age_days = candidate.last_used_at
+            .map(|last_used_at| (now - last_used_at).num_seconds().max(0) as f64 / 86_400.0)
+            .unwrap_or(0.0);
+        let decay = 0.5_f64.powf(age_days / USAGE_HALF_LIFE_DAYS);
+        score += FREQUENCY_WEIGHT * (1.0 + candidate.total_uses as f64).ln() * decay;
+        reasons.push(SuggestionReason::FrequentlyUsed);
+    }
+
+    (score, reasons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_score_candidate() {
+        let now = Local::now();
+        let weights = tag_weights(&HashMap::from([("logo".to_string(), 4), ("blue".to_string(), 2)]));
+        assert_eq!(weights["logo"], 1.0);
+        assert_eq!(top_tags(&weights, 1), ["logo"]);
+
+        let recent = SuggestionCandidate { total_uses: 5, last_used_at: Some(now), ..Default::default() };
+        let stale = SuggestionCandidate { last_used_at: Some(now - Duration::days(90)), ..recent.clone() };
+        assert!(score_candidate(&recent, &weights, now).0 > score_candidate(&stale, &weights, now).0);
+
+        let on_board = SuggestionCandidate { board_uses: 1, tags: vec!["blue".to_string()], ..Default::default() };
+        let (score, reasons) = score_candidate(&on_board, &weights, now);
+        assert!(score > score_candidate(&recent, &weights, now).0);
+        assert_eq!(reasons, [SuggestionReason::UsedOnBoard, SuggestionReason::SharedTags]);
+
+        let unrelated = SuggestionCandidate { tags: vec!["other".to_string()], ..Default::default() };
+        assert_eq!(score_candidate(&unrelated, &weights, now), (0.0, Vec::new()));
+    }
+}
@@ -0,0 +1,353 @@
+//! 监视文件夹模块
+//!
+//! 用户登记的外部文件夹（如系统截图文件夹）会被定期扫描，新文件自动导入：
+//! - 每个文件夹可指定目标目录和默认标签
+//! - 复制模式保留源文件，按路径、大小和修改时间记录已导入的文件
+//! - 移动模式导入成功后删除源文件
+//! - 云同步模式用于 OneDrive、Dropbox 等同步文件夹：与复制模式一样保留源文件，
+//!   尚未下载到本地的占位文件先在后台触发下载，下载完成后的扫描再导入；
+//!   导入前计算内容哈希，与资料库中已有文件相同的不再导入，只记录为已导入
+//! - 扫描时每个文件单独获取服务锁，计算哈希和按后台 I/O 限额等待时不持有服务锁，导入期间其他命令仍可执行
+
+use crate::file_manager::commands::FileManagerState;
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::events::{self, WatchedImport};
+use crate::file_manager::integrity::hash_file;
+use crate::file_manager::io_throttle;
+use crate::file_manager::notifications::{self, JobNotification};
+use crate::file_manager::sidecar::is_sidecar_file;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+/// 扫描间隔
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 文件在这段时间内没有被修改才会导入，避免导入仍在写入的文件
+pub const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// 下载或写入过程中的临时文件扩展名
+const TEMPORARY_EXTENSIONS: &[&str] = &["tmp", "part", "crdownload", "download", "partial"];
+
+/// 正在后台下载的占位文件
+static HYDRATING: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// 导入方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// 复制到存储区，保留源文件
+    #[default]
+    Copy,
+    /// 导入成功后删除源文件
+    Move,
+    /// 云同步文件夹：保留源文件，占位文件先触发下载，内容重复的文件不导入
+    CloudSync,
+}
+
+impl ImportMode {
+    /// 获取数据库中存储的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Copy => "copy",
+            Self::Move => "move",
+            Self::CloudSync => "cloud_sync",
+        }
+    }
+
+    /// 从数据库值解析，未知值按复制处理
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "move" => Self::Move,
+            "cloud_sync" => Self::CloudSync,
+            _ => Self::Copy,
+        }
+    }
+}
+
+/// 监视文件夹
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct WatchedFolder {
+    pub id: String,
+    /// 被监视的外部文件夹路径
+    pub path: String,
+    /// 导入的目标目录，`None` 表示收件箱
+    pub target_directory_id: Option<String>,
+    /// 导入的文件自动添加的标签
+    pub default_tags: Vec<String>,
+    pub import_mode: ImportMode,
+    pub created_at: String,
+}
+
+/// 添加监视文件夹请求
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AddWatchedFolderRequest {
+    pub path: String,
+    pub target_directory_id: Option<String>,
+    #[serde(default)]
+    pub default_tags: Vec<String>,
+    #[serde(default)]
+    pub import_mode: ImportMode,
+}
+
+/// 一次扫描的导入结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct WatchedImportReport {
+    /// 新导入的文件数
+    pub imported: usize,
+    /// 导入失败的文件数（下次扫描会重试）
+    pub failed: usize,
+    /// 内容与已有文件相同而未导入的文件数（云同步模式）
+    #[serde(default)]
+    pub duplicates: usize,
+    /// 尚未下载到本地、等待下载完成的占位文件数（云同步模式）
+    #[serde(default)]
+    pub hydrating: usize,
+}
+
+/// 待导入的文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateFile {
+    pub path: PathBuf,
+    pub size: u64,
+    /// 修改时间（毫秒时间戳）
+    pub modified: i64,
+    /// 是否为尚未下载到本地的云端占位文件
+    pub placeholder: bool,
+}
+
+/// 列出文件夹中可以导入的文件
+///
+/// 只扫描第一层；跳过隐藏文件、临时文件、附属元数据文件和最近仍在修改的文件
+pub fn candidate_files(folder: &Path, now: SystemTime) -> std::io::Result<Vec<CandidateFile>> {
+    let mut candidates = Vec::new();
+
+    for entry in std::fs::read_dir(folder)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() {
+            continue;
+        }
+
+        let path = entry.path();
+        if is_ignored_file(&path) {
+            continue;
+        }
+
+        let modified = metadata.modified()?;
+        if now.duration_since(modified).unwrap_or_default() < SETTLE_TIME {
+            continue;
+        }
+
+        candidates.push(CandidateFile {
+            path,
+            size: metadata.len(),
+            modified: modified.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64,
+            placeholder: is_cloud_placeholder(&metadata),
+        });
+    }
+
+    candidates.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(candidates)
+}
+
+/// 是否为导入时忽略的文件：隐藏文件、下载中的临时文件和附属元数据文件
+pub fn is_ignored_file(path: &Path) -> bool {
+    let hidden = path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+    let temporary = path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| TEMPORARY_EXTENSIONS.iter().any(|temp| temp.eq_ignore_ascii_case(ext)));
+    hidden || temporary || is_sidecar_file(path)
+}
+
+/// 文件是否为尚未下载到本地的云端占位文件
+///
+/// Windows 上 OneDrive、Dropbox 等通过 Cloud Files API 创建的占位文件带有按需下载属性；
+/// macOS 上通过 File Provider 同步的文件未下载时带有 `SF_DATALESS` 标记；其他平台总是返回 `false`
+#[cfg(windows)]
+pub fn is_cloud_placeholder(metadata: &Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x0000_1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x0004_0000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+    metadata.file_attributes()
+        & (FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_OPEN | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+        != 0
+}
+
+#[cfg(target_os = "macos")]
+pub fn is_cloud_placeholder(metadata: &Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+
+    const SF_DATALESS: u32 = 0x4000_0000;
+    metadata.st_flags() & SF_DATALESS != 0
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn is_cloud_placeholder(_metadata: &Metadata) -> bool {
+    false
+}
+
+/// 在后台线程中读取占位文件，触发同步客户端把内容下载到本地
+///
+/// 下载可能需要很长时间，不阻塞扫描；同一文件正在下载时不重复触发，返回是否新触发了下载
+pub fn request_hydration(path: &Path) -> bool {
+    {
+        let mut hydrating = HYDRATING.lock().unwrap();
+        if hydrating.iter().any(|pending| pending == path) {
+            return false;
+        }
+        hydrating.push(path.to_path_buf());
+    }
+
+    let path = path.to_path_buf();
+    std::thread::spawn(move || {
+        let result = std::fs::File::open(&path)
+            .and_then(|mut file| std::io::copy(&mut file, &mut std::io::sink()));
+        match result {
+            Ok(size) => tracing::info!("占位文件已下载: {:?} ({} 字节)", path, size),
+            Err(e) => tracing::warn!("占位文件下载失败: {:?}, {}", path, e),
+        }
+        HYDRATING.lock().unwrap().retain(|pending| *pending != path);
+    });
+    true
+}
+
+/// 扫描所有监视文件夹并导入新文件
+///
+/// 不支持的类型和超过大小限制的文件直接跳过；导入失败的文件留待下次扫描重试；
+/// 云同步模式下占位文件先触发下载，内容与已有文件相同的只记录为已导入。
+/// 每个文件单独获取服务锁，资料库变为只读或应用开始退出时停止扫描
+pub async fn import_all(state: &FileManagerState) -> Result<WatchedImportReport> {
+    let mut report = WatchedImportReport::default();
+    let now = SystemTime::now();
+    let folders = state.lock_writable().await?.get_watched_folders().await?;
+
+    for folder in folders {
+        let candidates = match candidate_files(Path::new(&folder.path), now) {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                tracing::warn!("监视文件夹无法读取: {}, {}", folder.path, e);
+                continue;
+            }
+        };
+
+        for candidate in candidates {
+            if !state.lock_writable().await?.is_watched_file_pending(&folder, &candidate).await? {
+                continue;
+            }
+
+            if folder.import_mode == ImportMode::CloudSync {
+                if candidate.placeholder {
+                    if request_hydration(&candidate.path) {
+                        tracing::info!("占位文件开始下载: {:?}", candidate.path);
+                    }
+                    report.hydrating += 1;
+                    continue;
+                }
+
+                let path = candidate.path.clone();
+                let hashed = tokio::task::spawn_blocking(move || hash_file(&path))
+                    .await
+                    .map_err(|e| FileManagerError::general_error(format!("Hash task failed: {}", e)))?;
+                let content_hash = match hashed {
+                    Ok(content_hash) => content_hash,
+                    Err(e) => {
+                        tracing::warn!("监视文件夹文件哈希计算失败: {:?}, {}", candidate.path, e);
+                        report.failed += 1;
+                        continue;
+                    }
+                };
+                io_throttle::throttle(candidate.size).await;
+
+                let duplicate = state.lock_writable().await?.record_watched_duplicate(&folder, &candidate, &content_hash).await?;
+                if let Some(file_id) = duplicate {
+                    tracing::debug!("监视文件夹文件与已有文件 {} 相同，跳过: {:?}", file_id, candidate.path);
+                    report.duplicates += 1;
+                    continue;
+                }
+            }
+
+            let imported = {
+                let _permit = io_throttle::write_permit().await;
+                state.lock_writable().await?.import_watched_file(&folder, &candidate).await
+            };
+            match imported {
+                Ok(()) => report.imported += 1,
+                Err(e) => {
+                    tracing::warn!("监视文件夹导入失败: {:?}, {}", candidate.path, e);
+                    report.failed += 1;
+                }
+            }
+            io_throttle::throttle(candidate.size).await;
+        }
+    }
+
+    Ok(report)
+}
+
+/// 启动后台扫描任务，按固定间隔导入所有监视文件夹中的新文件
+pub fn spawn_watch_loop(app: AppHandle, state: FileManagerState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let Ok(service) = state.lock().await else {
+                continue;
+            };
+            // 只读模式下由写入的进程导入，网络共享断开时等待恢复
+            if service.is_read_only() || state.offline().is_some() {
+                continue;
+            }
+            drop(service);
+            match import_all(&state).await {
+                Ok(report) if report.imported > 0 || report.failed > 0 => {
+                    tracing::info!("监视文件夹导入: 成功 {} 个, 失败 {} 个", report.imported, report.failed);
+                    if report.imported > 0 {
+                        if let Ok(service) = state.lock().await {
+                            notifications::notify(&app, &service, JobNotification::watched_import_finished(&report)).await;
+                        }
+                    }
+                    events::emit(&app, &WatchedImport(report));
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("监视文件夹扫描失败: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_candidate_files_skips_hidden_temporary_and_fresh_files() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["b.png", "a.jpg", ".DS_Store", "video.mp4.part"] {
+            std::fs::write(temp_dir.path().join(name), b"data").unwrap();
+        }
+        std::fs::create_dir(temp_dir.path().join("nested")).unwrap();
+
+        // 刚写入的文件还在稳定期内
+        assert!(candidate_files(temp_dir.path(), SystemTime::now()).unwrap().is_empty());
+
+        let later = SystemTime::now() + SETTLE_TIME * 2;
+        let names: Vec<String> = candidate_files(temp_dir.path(), later).unwrap()
+            .into_iter()
+            .map(|candidate| candidate.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["a.jpg", "b.png"]);
+    }
+}
@@ -0,0 +1,399 @@
+//! 导出水印模块
+//!
+//! 分享未发布作品的预览时，可以在导出的图片上叠加水印：
+//! - 水印为文字或图片；文字使用内置的 5×7 点阵字体，只支持 ASCII 字符，`©` 显示为 `(C)`，其他字符显示为 `?`
+//! - 可设置位置（四角、居中或平铺）、不透明度，以及水印宽度占图片宽度的比例
+//! - `export_files` 和 `export_with_preset` 都可以附带水印；加水印时解码图片、叠加后重新编码，
+//!   无法解码的文件记为失败，不会导出没有水印的原文件
+
+use crate::file_manager::error::{FileManagerError, Result};
+use crate::file_manager::thumbnail::{encode_image, PreviewFormat};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::path::Path;
+
+/// 水印文字的长度上限
+pub const MAX_WATERMARK_TEXT_LEN: usize = 100;
+
+/// 加水印后重新编码 JPEG 的质量
+const WATERMARK_JPEG_QUALITY: u8 = 90;
+
+/// 水印距图片边缘的距离占图片短边的比例
+const WATERMARK_MARGIN_RATIO: u32 = 30;
+
+/// 点阵字体的字形大小，每个字符另加 1 像素的间距和阴影
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+
+/// 水印内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WatermarkContent {
+    Text { text: String },
+    /// 图片文件的绝对路径，透明区域保持透明
+    Image { path: String },
+}
+
+/// 水印位置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    Center,
+    /// 铺满整张图片
+    Tile,
+}
+
+/// 导出时叠加的水印
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Watermark {
+    pub content: WatermarkContent,
+    #[serde(default)]
+    pub position: WatermarkPosition,
+    /// 不透明度（0-1）
+    #[serde(default = "default_watermark_opacity")]
+    pub opacity: f32,
+    /// 水印宽度占图片宽度的比例（0-1）
+    #[serde(default = "default_watermark_scale")]
+    pub scale: f32,
+}
+
+fn default_watermark_opacity() -> f32 {
+    0.5
+}
+
+fn default_watermark_scale() -> f32 {
+    0.25
+}
+
+impl Watermark {
+    /// 检查文字、图片路径、不透明度和比例
+    pub fn validate(&self) -> Result<()> {
+        match &self.content {
+            WatermarkContent::Text { text } => {
+                if text.trim().is_empty() {
+                    return Err(FileManagerError::general_error("Watermark text cannot be empty"));
+                }
+                if text.chars().count() > MAX_WATERMARK_TEXT_LEN {
+                    return Err(FileManagerError::general_error(format!(
+                        "Watermark text cannot be longer than {} characters", MAX_WATERMARK_TEXT_LEN
+                    )));
+                }
+            }
+            WatermarkContent::Image { path } => {
+                if !Path::new(path).is_absolute() {
+                    return Err(FileManagerError::general_error("Watermark image must be an absolute path"));
+                }
+            }
+        }
+        if !(self.opacity > 0.0 && self.opacity <= 1.0) {
+            return Err(FileManagerError::general_error("Watermark opacity must be between 0 and 1"));
+        }
+        if !(self.scale > 0.0 && self.scale <= 1.0) {
+            return Err(FileManagerError::general_error("Watermark scale must be between 0 and 1"));
+        }
+        Ok(())
+    }
+}
+
+/// 已加载的水印，一次导出中的所有图片共用
+#[derive(Debug, Clone)]
+pub struct PreparedWatermark {
+    position: WatermarkPosition,
+    opacity: f32,
+    scale: f32,
+    overlay: RgbaImage,
+    /// 文字按最近邻缩放保持清晰，图片按 Lanczos 缩放
+    filter: FilterType,
+}
+
+impl PreparedWatermark {
+    /// 校验水印并渲染文字或读取水印图片
+    pub fn prepare(watermark: &Watermark) -> Result<Self> {
+        watermark.validate()?;
+        let (overlay, filter) = match &watermark.content {
+            WatermarkContent::Text { text } => (render_text(text.trim()), FilterType::Nearest),
+            WatermarkContent::Image { path } => {
+                let image = image::open(path)
+                    .map_err(|e| FileManagerError::general_error(format!("Failed to load watermark image: {}", e)))?;
+                (image.to_rgba8(), FilterType::Lanczos3)
+            }
+        };
+        Ok(Self {
+            position: watermark.position,
+            opacity: watermark.opacity,
+            scale: watermark.scale,
+            overlay,
+            filter,
+        })
+    }
+
+    /// 在图片上叠加水印
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        let mut base = image.to_rgba8();
+        let (width, height) = base.dimensions();
+        let overlay = self.scaled_overlay(width, height);
+        let (overlay_width, overlay_height) = (i64::from(overlay.width()), i64::from(overlay.height()));
+        let margin = i64::from(width.min(height) / WATERMARK_MARGIN_RATIO);
+        let (right, bottom) = (i64::from(width) - overlay_width - margin, i64::from(height) - overlay_height - margin);
+
+        match self.position {
+            WatermarkPosition::TopLeft => image::imageops::overlay(&mut base, &overlay, margin, margin),
+            WatermarkPosition::TopRight => image::imageops::overlay(&mut base, &overlay, right, margin),
+            WatermarkPosition::BottomLeft => image::imageops::overlay(&mut base, &overlay, margin, bottom),
+            WatermarkPosition::BottomRight => image::imageops::overlay(&mut base, &overlay, right, bottom),
+            WatermarkPosition::Center => image::imageops::overlay(
+                &mut base,
+                &overlay,
+                (i64::from(width) - overlay_width) / 2,
+                (i64::from(height) - overlay_height) / 2,
+            ),
+            WatermarkPosition::Tile => {
+                // 行间错开半个水印宽度，避免排成整齐的网格
+                let (step_x, step_y) = (overlay_width * 3 / 2, overlay_height * 3);
+                for (row, y) in (0..i64::from(height)).step_by(step_y.max(1) as usize).enumerate() {
+                    let offset = if row % 2 == 1 { step_x / 2 } else { 0 };
+                    for x in (-offset..i64::from(width)).step_by(step_x.max(1) as usize) {
+                        image::imageops::overlay(&mut base, &overlay, x, y);
+                    }
+                }
+            }
+        }
+        DynamicImage::ImageRgba8(base)
+    }
+
+    /// 按图片大小缩放水印并乘上不透明度；水印不超出图片
+    fn scaled_overlay(&self, width: u32, height: u32) -> RgbaImage {
+        let (source_width, source_height) = self.overlay.dimensions();
+        let target_width = ((width as f32 * self.scale).round() as u32).clamp(1, width);
+        let mut target_height = ((target_width as f32 * source_height as f32 / source_width as f32).round() as u32).max(1);
+        let mut target_width = target_width;
+        if target_height > height {
+            target_width = ((target_width as f32 * height as f32 / target_height as f32).round() as u32).max(1);
+            target_height = height;
+        }
+
+        let mut overlay = image::imageops::resize(&self.overlay, target_width, target_height, self.filter);
+        for pixel in overlay.pixels_mut() {
+            pixel[3] = (f32::from(pixel[3]) * self.opacity).round() as u8;
+        }
+        overlay
+    }
+}
+
+/// 给图片文件加水印后写入 `target`，按目标扩展名（没有时按源文件内容）确定格式，返回写入的字节数
+///
+/// 解码和编码较慢，需在阻塞线程中调用
+pub fn watermark_file(source: &Path, target: &Path, watermark: &PreparedWatermark) -> Result<u64> {
+    let decode_error = |e: image::ImageError| FileManagerError::general_error(format!("Failed to decode image: {}", e));
+    let reader = image::ImageReader::open(source)?.with_guessed_format()?;
+    let format = ImageFormat::from_path(target).ok().or(reader.format())
+        .ok_or_else(|| FileManagerError::general_error("Cannot watermark a file that is not an image"))?;
+    let image = watermark.apply(&reader.decode().map_err(decode_error)?);
+
+    match format {
+        ImageFormat::Png => encode_image(&image, PreviewFormat::Png, WATERMARK_JPEG_QUALITY, None, target)?,
+        ImageFormat::Jpeg => encode_image(&image, PreviewFormat::Jpeg, WATERMARK_JPEG_QUALITY, None, target)?,
+        ImageFormat::WebP => encode_image(&image, PreviewFormat::Webp, WATERMARK_JPEG_QUALITY, None, target)?,
+        other => image.save_with_format(target, other)
+            .map_err(|e| FileManagerError::general_error(format!("Failed to encode image: {}", e)))?,
+    }
+    Ok(std::fs::metadata(target)?.len())
+}
+
+/// 用点阵字体渲染白色文字，右下方带半透明的黑色阴影，便于在浅色背景上辨认
+fn render_text(text: &str) -> RgbaImage {
+    let text = text.replace('\u{a9}', "(C)");
+    let glyphs: Vec<&[u8; GLYPH_HEIGHT as usize]> = text.chars()
+        .map(|c| {
+            let index = if c.is_ascii() && !c.is_ascii_control() { c as usize - 0x20 } else { '?' as usize - 0x20 };
+            &FONT[index]
+        })
+        .collect();
+
+    let cell_width = GLYPH_WIDTH + 1;
+    let mut image = RgbaImage::new(cell_width * glyphs.len() as u32 + 1, GLYPH_HEIGHT + 1);
+    for (layer, color) in [(1, Rgba([0, 0, 0, 160])), (0, Rgba([255, 255, 255, 255]))] {
+        for (index, rows) in glyphs.iter().enumerate() {
+            for (y, row) in rows.iter().enumerate() {
+                for x in 0..GLYPH_WIDTH {
+                    if row & (1 << (GLYPH_WIDTH - 1 - x)) != 0 {
+                        image.put_pixel(index as u32 * cell_width + x + layer, y as u32 + layer, color);
+                    }
+                }
+            }
+        }
+    }
+    image
+}
+
+/// ASCII 0x20-0x7E 的 5×7 点阵字形，每行的低 5 位从左到右
+const FONT: [[u8; GLYPH_HEIGHT as usize]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04], // '!'
+    [0x0a, 0x0a, 0x0a, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a], // '#'
+    [0x04, 0x0f, 0x14, 0x0e, 0x05, 0x1e, 0x04], // '$'
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03], // '%'
+    [0x0c, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0d], // '&'
+    [0x04, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00], // "'"
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02], // '('
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08], // ')'
+    [0x00, 0x04, 0x15, 0x0e, 0x15, 0x04, 0x00], // '*'
+    [0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08], // ','
+    [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c], // '.'
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00], // '/'
+    [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e], // '0'
+    [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e], // '1'
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f], // '2'
+    [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e], // '3'
+    [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02], // '4'
+    [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e], // '5'
+    [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e], // '6'
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08], // '7'
+    [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e], // '8'
+    [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c], // '9'
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00], // ':'
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x04, 0x08], // ';'
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02], // '<'
+    [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00], // '='
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08], // '>'
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // '?'
+    [0x0e, 0x11, 0x01, 0x0d, 0x15, 0x15, 0x0e], // '@'
+    [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11], // 'A'
+    [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e], // 'B'
+    [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e], // 'C'
+    [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c], // 'D'
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f], // 'E'
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10], // 'F'
+    [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f], // 'G'
+    [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11], // 'H'
+    [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e], // 'I'
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c], // 'J'
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11], // 'K'
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f], // 'L'
+    [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11], // 'M'
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11], // 'N'
+    [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e], // 'O'
+    [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10], // 'P'
+    [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d], // 'Q'
+    [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11], // 'R'
+    [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e], // 'S'
+    [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // 'T'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e], // 'U'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04], // 'V'
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a], // 'W'
+    [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11], // 'X'
+    [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04], // 'Y'
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f], // 'Z'
+    [0x0e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0e], // '['
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00], // '\\'
+    [0x0e, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0e], // ']'
+    [0x04, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f], // '_'
+    [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f], // 'a'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e], // 'b'
+    [0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e], // 'c'
+    [0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f], // 'd'
+    [0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e], // 'e'
+    [0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08], // 'f'
+    [0x00, 0x0f, 0x11, 0x11, 0x0f, 0x01, 0x0e], // 'g'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11], // 'h'
+    [0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e], // 'i'
+    [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0c], // 'j'
+    [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12], // 'k'
+    [0x0c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e], // 'l'
+    [0x00, 0x00, 0x1a, 0x15, 0x15, 0x11, 0x11], // 'm'
+    [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11], // 'n'
+    [0x00, 0x00, 0x0e, 0x11, 0x11, 0x11, 0x0e], // 'o'
+    [0x00, 0x00, 0x1e, 0x11, 0x1e, 0x10, 0x10], // 'p'
+    [0x00, 0x00, 0x0d, 0x13, 0x0f, 0x01, 0x01], // 'q'
+    [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10], // 'r'
+    [0x00, 0x00, 0x0e, 0x10, 0x0e, 0x01, 0x1e], // 's'
+    [0x08, 0x08, 0x1c, 0x08, 0x08, 0x09, 0x06], // 't'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0d], // 'u'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04], // 'v'
+    [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0a], // 'w'
+    [0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11], // 'x'
+    [0x00, 0x00, 0x11, 0x11, 0x0f, 0x01, 0x0e], // 'y'
+    [0x00, 0x00, 0x1f, 0x02, 0x04, 0x08, 0x1f], // 'z'
+    [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02], // '{'
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // '|'
+    [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08], // '}'
+    [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00], // '~'
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn text_watermark(position: WatermarkPosition) -> Watermark {
+        Watermark {
+            content: WatermarkContent::Text { text: "DRAFT".to_string() },
+            position,
+            opacity: 1.0,
+            scale: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_text_watermark_position() {
+        let image = DynamicImage::ImageRgb8(image::RgbImage::new(400, 200));
+        let prepared = PreparedWatermark::prepare(&text_watermark(WatermarkPosition::BottomRight)).unwrap();
+        let marked = prepared.apply(&image).to_rgba8();
+
+        // 白色文字只出现在右下区域
+        let white: Vec<_> = marked.enumerate_pixels().filter(|(_, _, pixel)| pixel[0] == 255).map(|(x, y, _)| (x, y)).collect();
+        assert!(!white.is_empty());
+        assert!(white.iter().all(|(x, y)| *x >= 150 && *y >= 100));
+
+        let tiled = PreparedWatermark::prepare(&text_watermark(WatermarkPosition::Tile)).unwrap().apply(&image).to_rgba8();
+        assert!(tiled.enumerate_pixels().any(|(x, y, pixel)| pixel[0] == 255 && x < 100 && y < 50));
+
+        assert!(Watermark { opacity: 0.0, ..text_watermark(WatermarkPosition::Center) }.validate().is_err());
+        assert!(Watermark { content: WatermarkContent::Text { text: " ".to_string() }, ..text_watermark(WatermarkPosition::Center) }
+            .validate().is_err());
+    }
+
+    #[test]
+    fn test_watermark_file_with_image_overlay() {
+        let temp_dir = TempDir::new().unwrap();
+        let logo = temp_dir.path().join("logo.png");
+        RgbaImage::from_pixel(10, 10, Rgba([255, 0, 0, 255])).save(&logo).unwrap();
+        let source = temp_dir.path().join("art.bin");
+        image::RgbImage::new(100, 100).save_with_format(&source, ImageFormat::Png).unwrap();
+
+        let prepared = PreparedWatermark::prepare(&Watermark {
+            content: WatermarkContent::Image { path: logo.to_string_lossy().into_owned() },
+            position: WatermarkPosition::Center,
+            opacity: 0.5,
+            scale: 0.2,
+        }).unwrap();
+        let target = temp_dir.path().join("art.png");
+        let size = watermark_file(&source, &target, &prepared).unwrap();
+        assert_eq!(std::fs::metadata(&target).unwrap().len(), size);
+
+        let marked = image::open(&target).unwrap().to_rgba8();
+        assert_eq!(marked.get_pixel(0, 0), &Rgba([0, 0, 0, 255]));
+        let center = marked.get_pixel(50, 50);
+        assert!((120..=135).contains(&center[0]) && center[1] == 0);
+
+        // 不是图片的文件不能加水印
+        let text = temp_dir.path().join("notes.txt");
+        std::fs::write(&text, b"notes").unwrap();
+        assert!(watermark_file(&text, &temp_dir.path().join("notes-out.txt"), &prepared).is_err());
+    }
+}
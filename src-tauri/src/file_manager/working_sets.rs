@@ -0,0 +1,44 @@
+//! 工作集模块
+//!
+//! 工作集是临时的文件选择，用于跨目录的多步操作，如从不同文件夹收集文件后一起导出：
+//! - 保存在数据库中，应用重启后仍然可用
+//! - 每个工作集有有效期，每次添加或移除成员后重新计时；过期的工作集在下次访问工作集时清除
+//! - 成员只引用文件，文件被删除后不再列出，撤销删除后重新出现
+
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// 默认有效期（分钟）
+pub const DEFAULT_WORKING_SET_TTL_MINUTES: u32 = 24 * 60;
+
+/// 有效期上限（分钟）
+pub const MAX_WORKING_SET_TTL_MINUTES: u32 = 7 * 24 * 60;
+
+/// 单个工作集的成员上限
+pub const MAX_WORKING_SET_MEMBERS: usize = 10_000;
+
+/// 工作集
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct WorkingSet {
+    pub id: String,
+    pub name: Option<String>,
+    /// 仍存在的成员文件数
+    pub file_count: usize,
+    /// 有效期（分钟），每次修改后从修改时间重新计算
+    pub ttl_minutes: u32,
+    pub expires_at: DateTime<Local>,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+/// 从现在起经过 `ttl_minutes` 后的过期时间
+pub fn expiry_from(now: DateTime<Local>, ttl_minutes: u32) -> DateTime<Local> {
+    now + Duration::minutes(ttl_minutes as i64)
+}
+
+/// 有效期是否在允许范围内
+pub fn is_valid_ttl(ttl_minutes: u32) -> bool {
+    (1..=MAX_WORKING_SET_TTL_MINUTES).contains(&ttl_minutes)
+}
@@ -2,11 +2,9 @@
 use std::collections::HashMap;
 use std::ffi::CString;
 use std::os::raw::{c_char, c_int};
-use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use log::{info, warn, error, debug};
 use tracing::{info as tracing_info, warn as tracing_warn, error as tracing_error, debug as tracing_debug};
-use tokio::sync::Mutex;
 use chrono::Datelike;
 use tauri::Manager;
 
@@ -18,11 +16,34 @@ mod config_loader;
 // 文件管理模块
 mod file_manager;
 use file_manager::{
+    analytics,
+    catalog_snapshots,
     commands::*,
     config::FileManagerConfig,
     database::DatabaseService,
+    deferred_delete,
+    error::FileManagerError,
+    events::{self, BackendFailed, BackendLocked, BackendReady},
+    external_edit,
     filesystem::FileSystemService,
+    i18n,
+    integrity,
+    job_queue,
+    library_summary,
+    low_memory,
+    network_share,
+    pack_store,
+    quick_capture,
+    read_only,
+    response_guard,
     service::FileManagerService,
+    shell_integration,
+    startup::{StartupProfiler, StartupTaskKind},
+    storage_layout,
+    telemetry,
+    thumbnail,
+    trash,
+    watched_folders,
 };
 
 // FFI 绑定：C++ TGA 图像加载库
@@ -50,6 +71,17 @@ extern "C" {
      * @param img 图像结构体指针
      */
     fn tga_free(img: *mut TgaImage);
+    
+    /**
+     * 将 RGBA 像素保存为未压缩的 32 位 TGA 文件
+     * @param path 输出文件路径
+     * @param width 图像宽度
+     * @param height 图像高度
+     * @param data RGBA 像素数据
+     * @param len 数据字节数，必须为 width * height * 4
+     * @return 0 成功，非 0 失败
+     */
+    fn tga_save_rgba(path: *const c_char, width: i32, height: i32, data: *const u8, len: usize) -> c_int;
 }
 
 // Tauri 返回的图像数据结构
@@ -274,6 +306,63 @@ fn load_tga_image(path: String) -> Result<ImageData, String> {
     })
 }
 
+/**
+ * 保存 RGBA 图像为 TGA 文件
+ * @param path 输出文件路径
+ * @param image RGBA 图像
+ */
+fn save_tga_image(path: &str, image: &image::RgbaImage) -> Result<(), String> {
+    let c_path = CString::new(path)
+        .map_err(|_| format!("Invalid path: {}", path))?;
+    
+    let (width, height) = image.dimensions();
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(format!("Image too large for TGA: {}x{}", width, height));
+    }
+    
+    let pixels = image.as_raw();
+    let result_code = unsafe {
+        tga_save_rgba(c_path.as_ptr(), width as i32, height as i32, pixels.as_ptr(), pixels.len())
+    };
+    
+    match result_code {
+        0 => Ok(()),
+        -1 => Err("Invalid parameters".to_string()),
+        -3 => Err(format!("Failed to create file: {}", path)),
+        -4 => Err(format!("Failed to write file: {}", path)),
+        _ => Err(format!("Unknown error code: {}", result_code)),
+    }
+}
+
+/**
+ * 将库中的图像导出为 TGA 文件
+ * @param file_id 文件 ID
+ * @param path 导出文件路径
+ */
+#[tauri::command]
+async fn export_as_tga(
+    file_id: String,
+    path: String,
+    service: tauri::State<'_, FileManagerState>,
+) -> Result<(), String> {
+    info!("开始导出TGA图片: {} -> {}", file_id, path);
+    
+    let image = service.lock().await
+        .map_err(|e| e.to_string())?
+        .decode_file_image(&file_id).await
+        .map_err(|e| {
+            error!("图像解码失败: {} - {}", file_id, e);
+            e.to_string()
+        })?;
+    
+    tauri::async_runtime::spawn_blocking(move || save_tga_image(&path, &image))
+        .await
+        .map_err(|e| format!("TGA export task failed: {}", e))??;
+    
+    info!("TGA图片导出完成: {}", file_id);
+    Ok(())
+}
+
 /**
  * 获取支持的图像格式列表
  * @return 支持的图像格式数组
@@ -292,6 +381,195 @@ fn get_supported_image_formats() -> Vec<String> {
     ]
 }
 
+/**
+ * 在后台初始化文件管理服务
+ * 各阶段耗时记录在 profiler 中，失败时返回错误信息
+ */
+async fn initialize_file_manager(
+    app: &tauri::AppHandle,
+    state: &FileManagerState,
+    profiler: &mut StartupProfiler,
+) -> Result<FileManagerService, String> {
+    let _app_data_dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    
+    let config = profiler.measure_async("config", FileManagerConfig::new()).await
+        .map_err(|e| format!("Failed to create file manager config: {}", e))?;
+    
+    // 其他进程正在写入共享资料库时以只读模式打开
+    let read_only = state.acquire_writer_lock(&config.app_data_dir);
+    
+    // 按配置和资料库位置启用网络共享模式，需在打开数据库之前决定
+    file_manager::network_share::activate(&[&config.app_data_dir, &config.storage_path]);
+    
+    // 创建数据库服务（包含表结构迁移），资料库已加密时等待用户输入密码
+    let tasks = state.startup_tasks();
+    tasks.start(StartupTaskKind::SchemaMigration);
+    let db_service = match profiler.measure_async("database", DatabaseService::new(&config.database_path)).await {
+        Err(FileManagerError::LibraryLocked) => unlock_database(app, state, &config.database_path).await,
+        result => result.map_err(|e| format!("Failed to initialize database: {}", e)),
+    };
+    let db_service = tasks.finish_with(StartupTaskKind::SchemaMigration, db_service)?;
+    
+    // 创建文件系统服务
+    let fs_service = profiler.measure("filesystem", || {
+        // 允许前端通过 asset 协议加载存储目录中的大文件
+        app.asset_protocol_scope()
+            .allow_directory(&config.storage_path, true)
+            .map_err(|e| format!("Failed to allow storage directory in asset scope: {}", e))?;
+        
+        FileSystemService::new(&config.storage_path)
+            .map_err(|e| format!("Failed to initialize filesystem: {}", e))
+    })?;
+    
+    // 创建文件管理服务
+    let mut file_manager = FileManagerService::with_config(config, db_service, fs_service);
+    if let Some(reason) = read_only {
+        tracing_warn!("资料库以只读模式打开: {}", reason);
+        file_manager.apply_read_only(Some(reason)).await
+            .map_err(|e| format!("Failed to enable read-only mode: {}", e))?;
+    }
+    
+    // 旧版本数据库保存的是绝对存储路径，迁移为相对路径
+    tasks.start(StartupTaskKind::BlobPathMigration);
+    let migrated = profiler.measure_async("migrations", file_manager.migrate_blob_paths(|processed, total| {
+        tasks.progress(StartupTaskKind::BlobPathMigration, processed, Some(total));
+    })).await;
+    let migrated = tasks.finish_with(StartupTaskKind::BlobPathMigration, migrated)
+        .map_err(|e| format!("Failed to migrate blob paths: {}", e))?;
+    if migrated > 0 {
+        tracing_info!("已将 {} 个文件的存储路径迁移为相对路径", migrated);
+    }
+    
+    // 上次未正常关闭时，恢复被中断的上传；只读模式下由写入的进程负责
+    profiler.measure_async("recovery", async {
+        let skip_recovery = || {
+            tasks.skip(StartupTaskKind::JournalRecovery);
+            tasks.skip(StartupTaskKind::UploadRecovery);
+        };
+        if file_manager.is_read_only() {
+            skip_recovery();
+            return Ok(());
+        }
+        let was_clean_shutdown = file_manager.begin_session().await
+            .map_err(|e| format!("Failed to read shutdown state: {}", e))?;
+        
+        if was_clean_shutdown {
+            tracing_info!("上次正常关闭，跳过恢复扫描");
+            skip_recovery();
+            return Ok(());
+        }
+        
+        tasks.start(StartupTaskKind::JournalRecovery);
+        let journal_recovery = file_manager.recover_journal(|processed, total| {
+            tasks.progress(StartupTaskKind::JournalRecovery, processed, Some(total));
+        }).await;
+        let journal_recovery = tasks.finish_with(StartupTaskKind::JournalRecovery, journal_recovery)
+            .map_err(|e| format!("Failed to replay operation journal: {}", e))?;
+        if journal_recovery.completed > 0 || journal_recovery.failed > 0 {
+            tracing_info!("操作日志重放完成: 完成 {} 个, 失败 {} 个", journal_recovery.completed, journal_recovery.failed);
+        }
+        
+        tasks.start(StartupTaskKind::UploadRecovery);
+        let recovery = file_manager.recover_interrupted_uploads(|processed, total| {
+            tasks.progress(StartupTaskKind::UploadRecovery, processed, Some(total));
+        }).await;
+        let recovery = tasks.finish_with(StartupTaskKind::UploadRecovery, recovery)
+            .map_err(|e| format!("Failed to recover interrupted uploads: {}", e))?;
+        if recovery.promoted > 0 || recovery.discarded > 0 {
+            tracing_info!("中断上传恢复完成: 提升 {} 个, 丢弃 {} 个", recovery.promoted, recovery.discarded);
+        }
+        Ok::<(), String>(())
+    }).await?;
+    
+    // 上次运行时未结束的任务标记为被中断，服务就绪后由后台恢复；只读模式下由写入的进程负责
+    if !file_manager.is_read_only() {
+        let interrupted = file_manager.mark_interrupted_jobs().await
+            .map_err(|e| format!("Failed to read job queue: {}", e))?;
+        if interrupted > 0 {
+            tracing_info!("发现 {} 个被中断的任务", interrupted);
+        }
+    }
+    
+    profiler.measure_async("settings", async {
+        // 加载错误信息语言设置
+        let locale = file_manager.get_locale().await.unwrap_or_default();
+        i18n::set_current_locale(locale);
+        
+        // 超出上限的列表响应改为分页返回
+        let response_limit = file_manager.get_response_limit_settings().await.unwrap_or_default();
+        response_guard::set_max_response_bytes(response_limit.max_response_bytes);
+        
+        // 文件类型策略保存在数据库中，覆盖默认的允许名单模式
+        let file_type_policy = file_manager.get_file_type_policy().await.unwrap_or_default();
+        file_manager.apply_file_type_policy(file_type_policy);
+        
+        // 按文件类别的大小上限，未设置的类别使用全局上限
+        let size_limits = file_manager.get_size_limits().await.unwrap_or_default();
+        file_manager.apply_size_limits(size_limits);
+        
+        // 新记录按保存的策略生成 ID
+        let id_strategy = file_manager.get_id_strategy().await.unwrap_or_default();
+        file_manager.apply_id_strategy(id_strategy);
+        
+        // 新上传文件按保存的存储布局存放
+        let storage_layout = file_manager.get_storage_layout_settings().await.unwrap_or_default();
+        file_manager.apply_storage_layout(storage_layout.layout);
+        
+        // 低内存模式限制缓存和并行线程数，需在应用缩略图缓存设置之前开启
+        let low_memory_settings = file_manager.get_low_memory_settings().await.unwrap_or_default();
+        low_memory::set_enabled(low_memory_settings.enabled);
+        
+        // 缩略图按保存的预览设置生成
+        let preview_settings = file_manager.get_preview_settings().await.unwrap_or_default();
+        file_manager.apply_preview_settings(preview_settings);
+        let thumbnail_cache_settings = file_manager.get_thumbnail_cache_settings().await.unwrap_or_default();
+        file_manager.apply_thumbnail_cache_settings(thumbnail_cache_settings);
+        
+        // 用户开启后才统计功能使用次数
+        let telemetry_settings = file_manager.get_telemetry_settings().await.unwrap_or_default();
+        telemetry::set_enabled(telemetry_settings.enabled);
+        
+        // 注册快速截图快捷键，注册失败不影响启动
+        let quick_capture_settings = file_manager.get_quick_capture_settings().await.unwrap_or_default();
+        if let Err(e) = quick_capture::apply_settings(app, &quick_capture_settings) {
+            tracing_warn!("快速截图快捷键注册失败: {}", e);
+        }
+    }).await;
+    
+    Ok(file_manager)
+}
+
+/**
+ * 等待用户提交密码并打开加密的资料库
+ * 密码错误时回复错误并继续等待
+ */
+async fn unlock_database(
+    app: &tauri::AppHandle,
+    state: &FileManagerState,
+    database_path: &std::path::Path,
+) -> Result<DatabaseService, String> {
+    tracing_info!("资料库已加密，等待输入密码");
+    let mut requests = state.set_locked();
+    events::emit(app, &BackendLocked);
+    
+    while let Some(request) = requests.recv().await {
+        match DatabaseService::open(database_path, Some(&request.password)).await {
+            Ok(db_service) => {
+                state.set_unlocked();
+                let _ = request.reply.send(Ok(()));
+                return Ok(db_service);
+            }
+            Err(e) => {
+                tracing_warn!("资料库解锁失败: {}", e);
+                let _ = request.reply.send(Err(e));
+            }
+        }
+    }
+    
+    Err("Library unlock was abandoned".to_string())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // 加载配置文件
@@ -306,44 +584,160 @@ pub fn run() {
         std::process::exit(1);
     }
     
+    // 开发构建中按配置记录 SQL 语句和耗时，需在打开数据库之前设置
+    file_manager::query_log::set_enabled(app_config.logging.development.log_sql_queries);
+    
+    // 文件被临时占用或数据库忙时按退避策略重试，按配置记录每次重试
+    file_manager::retry::set_log_attempts(app_config.logging.error_handling.log_retry_attempts);
+    
+    // 资料库位于 SMB / NFS 共享上时调整数据库设置和重试窗口，并检测共享断开
+    file_manager::network_share::configure(
+        app_config.library.network_share,
+        app_config.library.offline_check_interval_seconds,
+    );
+    
+    // 按配置在 GPU 上缩小大图，GPU 不可用时回退到 CPU
+    file_manager::thumbnail::set_gpu_acceleration(app_config.thumbnails.gpu_acceleration);
+    
+    // 批量索引使用的线程数，需在首次索引之前设置
+    file_manager::indexer::set_max_threads(app_config.indexing.max_threads);
+    
+    // 后台导入、同步和打包维护的磁盘读写限速，避免占满磁盘带宽
+    file_manager::io_throttle::configure(
+        app_config.storage_io.max_mb_per_sec,
+        app_config.storage_io.max_concurrent_writes,
+    );
+    
     // 初始化高级日志系统
     let log_config = app_config.logging.to_advanced_log_config()
         .expect("Failed to convert logging config");
     
-    let _log_manager = advanced_logging::AdvancedLogManager::new(log_config)
+    let log_manager = advanced_logging::AdvancedLogManager::new(log_config)
         .init()
         .expect("Failed to initialize logging system");
     
     tracing::info!("Collaboard Tauri应用程序启动");
     
-    tauri::Builder::default()
+    // 程序崩溃时在日志中记录资料库规模和状态
+    library_summary::install_panic_hook();
+    
+    // 右键菜单、深度链接和双击画板文件每次都会启动新进程；已有实例运行时把文件交给它处理后退出，
+    // 主实例也把自己的启动参数写入启动请求，待文件管理服务初始化完成后处理
+    let launch_request = shell_integration::parse_launch_args(std::env::args().skip(1));
+    let app_data_dir = FileManagerConfig::get_app_data_dir().ok();
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    let opened_files_dir = app_data_dir.clone();
+    let _instance_lock = match &app_data_dir {
+        Some(app_data_dir) => match shell_integration::acquire_instance_lock(app_data_dir) {
+            Ok(Some(lock)) => Some(lock),
+            Ok(None) => {
+                if let Err(e) = shell_integration::enqueue_launch_request(app_data_dir, &launch_request) {
+                    tracing_error!("启动请求写入失败: {}", e);
+                }
+                tracing_info!("已有实例正在运行，交给该实例处理后退出");
+                std::process::exit(0);
+            }
+            Err(e) => {
+                tracing_warn!("实例锁获取失败，按独立实例运行: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+    if let Some(app_data_dir) = &app_data_dir {
+        if !launch_request.is_empty() {
+            if let Err(e) = shell_integration::enqueue_launch_request(app_data_dir, &launch_request) {
+                tracing_error!("启动请求写入失败: {}", e);
+            }
+        }
+    }
+    
+    let app = tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
-        .setup(|app| {
-            // 初始化文件管理服务
-            let app_data_dir = app.path().app_data_dir()
-                .map_err(|e| format!("Failed to get app data dir: {}", e))?;
-            
-            let config = tauri::async_runtime::block_on(async {
-                FileManagerConfig::new().await
-            }).map_err(|e| format!("Failed to create file manager config: {}", e))?;
-            
-            // 创建数据库服务
-            let db_service = tauri::async_runtime::block_on(async {
-                DatabaseService::new(&config.database_path).await
-            }).map_err(|e| format!("Failed to initialize database: {}", e))?;
-            
-            // 创建文件系统服务
-            let fs_service = FileSystemService::new(&config.storage_path)
-                .map_err(|e| format!("Failed to initialize filesystem: {}", e))?;
-            
-            // 创建文件管理服务
-            let file_manager = FileManagerService::with_config(config, db_service, fs_service);
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
+        .setup(move |app| {
+            // 先注册空的服务状态，初始化在后台完成，避免在网络驱动器上阻塞窗口创建
+            let file_manager_state = FileManagerState::new();
+            if std::env::args().any(|arg| arg == read_only::READ_ONLY_ARG) {
+                file_manager_state.request_read_only();
+            }
+            app.manage(file_manager_state.clone());
             
-            // 将服务添加到应用状态
-            app.manage(Arc::new(Mutex::new(file_manager)));
+            let app_handle = app.handle().clone();
+            // 迁移和恢复任务的进度以事件通知前端
+            file_manager_state.startup_tasks().attach(app_handle.clone());
+            tauri::async_runtime::spawn(async move {
+                let mut profiler = StartupProfiler::new();
+                match initialize_file_manager(&app_handle, &file_manager_state, &mut profiler).await {
+                    Ok(file_manager) => {
+                        let startup_report = profiler.finish();
+                        file_manager_state.set_ready(file_manager, startup_report.clone());
+                        
+                        // 定期扫描监视文件夹，自动导入新文件
+                        watched_folders::spawn_watch_loop(app_handle.clone(), file_manager_state.clone());
+                        
+                        // 开启完整性抽检后，每天重新校验一批存储文件
+                        integrity::spawn_sampling_loop(app_handle.clone(), file_manager_state.clone());
+                        
+                        // 每天记录一次库的统计快照，供增长趋势图使用
+                        analytics::spawn_snapshot_loop(file_manager_state.clone());
+                        
+                        // 检查外部编辑的副本，保存后通知前端确认导入
+                        external_edit::spawn_edit_watch_loop(app_handle.clone());
+                        
+                        // 上次运行时被中断的导入、导出、目录删除和打包维护，在后台恢复或回滚
+                        job_queue::spawn_recovery(app_handle.clone(), file_manager_state.clone());
+                        
+                        // 上次切换存储布局后未迁移完成时，继续在后台迁移
+                        storage_layout::resume_pending_migration(app_handle.clone(), file_manager_state.clone());
+                        
+                        // 上次修改预览设置后未重新生成完的缩略图，继续在后台生成
+                        thumbnail::spawn_preview_regeneration(file_manager_state.clone());
+                        
+                        // 按设置的间隔保存目录数据库快照，批量操作出错时可以回滚
+                        catalog_snapshots::spawn_snapshot_loop(file_manager_state.clone());
+                        
+                        // 开启打包存储后，定期把小文件合并到打包文件，并压缩无用数据过多的打包文件
+                        pack_store::spawn_maintenance_loop(file_manager_state.clone());
+                        
+                        // 处理右键菜单和深度链接传来的文件，以及其他实例的激活请求
+                        if let Some(app_data_dir) = app_data_dir {
+                            shell_integration::spawn_launch_request_loop(app_handle.clone(), file_manager_state.clone(), app_data_dir);
+                        }
+                        
+                        // 开启回收站后，每小时按保留天数和大小上限清理回收站
+                        trash::spawn_retention_loop(file_manager_state.clone());
+                        
+                        // 重试被其他进程占用、暂时无法删除的存储文件
+                        deferred_delete::spawn_deferred_delete_loop(app_handle.clone(), file_manager_state.clone());
+                        
+                        // 持有写入锁时定期更新心跳，被其他进程接管后转为只读模式
+                        read_only::spawn_heartbeat_loop(app_handle.clone(), file_manager_state.clone());
+                        
+                        // 网络共享模式下定期检查资料库能否访问，共享断开时进入离线状态
+                        network_share::spawn_offline_monitor(app_handle.clone(), file_manager_state.clone());
+                        
+                        // 资料库概要变化时通知前端并更新窗口标题
+                        library_summary::spawn_refresh_loop(app_handle.clone(), file_manager_state.clone());
+                        
+                        // 开启使用统计后，每小时发送一次累积的计数
+                        telemetry::spawn_send_loop(file_manager_state);
+                        
+                        tracing_info!("文件管理系统初始化完成");
+                        
+                        // 通知前端启动完成，窗口错过事件时可通过 get_startup_report 查询
+                        events::emit(&app_handle, &BackendReady(startup_report));
+                    }
+                    Err(message) => {
+                        tracing_error!("文件管理系统初始化失败: {}", message);
+                        file_manager_state.set_failed(message.clone());
+                        events::emit(&app_handle, &BackendFailed(message));
+                    }
+                }
+            });
             
-            tracing_info!("文件管理系统初始化完成");
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -354,12 +748,16 @@ pub fn run() {
             process_user_data,
             async_operation,
             load_tga_image,
+            export_as_tga,
             get_supported_image_formats,
             // 文件管理命令
             upload_file,
             create_directory,
             delete_file,
             delete_directory,
+            cancel_directory_delete,
+            get_directory_item_counts,
+            move_directory,
             get_directory_tree,
             get_directory_files,
             get_file_info,
@@ -367,8 +765,258 @@ pub fn run() {
             search_files,
             get_storage_stats,
             validate_file_type,
-            read_file_content
+            read_file_content,
+            get_file_asset_path,
+            scan_missing_files,
+            search_by_location,
+            get_broken_files,
+            relink_file,
+            purge_broken_files,
+            update_tags_bulk,
+            get_file_tags,
+            get_thumbnail,
+            compare_images,
+            find_similar_images,
+            import_from_url,
+            add_watched_folder,
+            remove_watched_folder,
+            get_watched_folders,
+            scan_watched_folders,
+            export_files,
+            get_activity_timeline,
+            get_storage_breakdown,
+            get_integrity_settings,
+            set_integrity_settings,
+            run_integrity_check,
+            find_duplicates,
+            resolve_duplicates,
+            undo_resolve_duplicates,
+            capture_screenshot,
+            get_quick_capture_settings,
+            set_quick_capture_settings,
+            get_notification_settings,
+            set_notification_settings,
+            triage_file,
+            get_inbox_count,
+            get_locale,
+            set_locale,
+            get_startup_report,
+            get_recovered_jobs,
+            get_startup_tasks_status,
+            get_library_summary,
+            set_library_name,
+            get_backend_status,
+            get_telemetry_settings,
+            set_telemetry_settings,
+            get_telemetry_preview,
+            is_library_encrypted,
+            set_library_password,
+            change_library_password,
+            unlock_library,
+            set_file_annotations,
+            import_sidecar,
+            export_metadata,
+            import_metadata,
+            get_file_type_policy,
+            set_file_type_policy,
+            get_size_limits,
+            set_size_limits,
+            get_id_strategy,
+            set_id_strategy,
+            get_quarantine_settings,
+            set_quarantine_settings,
+            get_quarantined_files,
+            approve_quarantined_file,
+            discard_quarantined_file,
+            get_trash_settings,
+            set_trash_settings,
+            get_trashed_files,
+            restore_trashed_file,
+            purge_trashed_file,
+            apply_trash_retention,
+            get_directory_defaults,
+            set_directory_defaults,
+            get_search_suggestions,
+            get_search_history,
+            clear_search_history,
+            backup_library,
+            restore_library,
+            get_storage_layout,
+            set_storage_layout,
+            link_file,
+            check_linked_files,
+            convert_to_managed,
+            get_listing_page,
+            get_response_limit,
+            set_response_limit,
+            get_event_catalog,
+            get_recent_events,
+            get_directory_files_page,
+            search_files_page,
+            get_activity_feed,
+            get_unprocessed_files,
+            process_pending_files,
+            reindex_files,
+            get_stats_history,
+            batch_operations,
+            undo_batch_operations,
+            get_open_with,
+            set_open_with,
+            get_apps_for_file,
+            open_file_with,
+            edit_externally,
+            get_external_edits,
+            apply_external_edit,
+            end_external_edit,
+            get_preview_settings,
+            set_preview_settings,
+            write_image_metadata,
+            create_working_set,
+            list_working_sets,
+            add_to_working_set,
+            remove_from_working_set,
+            get_working_set_files,
+            delete_working_set,
+            quick_find,
+            get_archive_settings,
+            set_archive_settings,
+            archive_files,
+            restore_archived_files,
+            get_pack_settings,
+            set_pack_settings,
+            get_pack_stats,
+            run_pack_maintenance,
+            create_directory_template,
+            list_directory_templates,
+            update_directory_template,
+            delete_directory_template,
+            create_directory_from_template,
+            record_file_usage,
+            get_suggestions,
+            get_shell_integration,
+            set_context_menu_enabled,
+            export_board,
+            open_board_file,
+            set_read_only,
+            take_over_library,
+            get_cache_stats,
+            clear_thumbnail_cache,
+            get_thumbnail_cache_settings,
+            set_thumbnail_cache_settings,
+            get_low_memory_settings,
+            set_low_memory_settings,
+            get_export_presets,
+            set_export_presets,
+            export_with_preset,
+            generate_manifest,
+            import_directory,
+            collect_board_assets,
+            auto_layout_board,
+            create_board_frame,
+            update_board_frame,
+            list_board_frames,
+            move_board_frame,
+            delete_board_frame,
+            replace_board_frames,
+            create_playlist,
+            update_playlist,
+            list_playlists,
+            get_playlist_files,
+            get_playlist_item,
+            delete_playlist,
+            list_catalog_snapshots,
+            create_catalog_snapshot,
+            get_catalog_snapshot_settings,
+            set_catalog_snapshot_settings,
+            rollback_catalog,
+            list_deferred_deletes,
+            retry_deferred_deletes
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application");
+    
+    // 事件循环结束时 Tauri 会直接退出进程，日志管理器需在 Exit 事件中显式释放以刷新日志
+    let mut log_manager = Some(log_manager);
+    
+    app.run(move |app_handle, event| match event {
+        // macOS 上双击画板文件不会启动新进程，而是发送打开文件事件
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        tauri::RunEvent::Opened { urls } => {
+            let boards: Vec<_> = urls.iter()
+                .filter_map(|url| url.to_file_path().ok())
+                .filter(|path| file_manager::board_bundle::is_board_file(path))
+                .collect();
+            if let (Some(app_data_dir), false) = (&opened_files_dir, boards.is_empty()) {
+                let request = shell_integration::LaunchRequest { paths: Vec::new(), boards };
+                if let Err(e) = shell_integration::enqueue_launch_request(app_data_dir, &request) {
+                    tracing_error!("启动请求写入失败: {}", e);
+                }
+            }
+        }
+        // 关闭最后一个窗口时就等待任务结束并标记正常关闭，不依赖之后的 Exit 事件一定送达
+        tauri::RunEvent::WindowEvent { event: tauri::WindowEvent::CloseRequested { .. }, .. } => {
+            if app_handle.webview_windows().len() <= 1 {
+                shutdown(app_handle);
+            }
+        }
+        tauri::RunEvent::Exit => {
+            shutdown(app_handle);
+            
+            if let Some(log_manager) = log_manager.take() {
+                log_manager.log_shutdown_info();
+            }
+        }
+        _ => {}
+    });
+}
+
+/**
+ * 关闭最后一个窗口或应用退出时关闭文件管理服务，重复调用只会再次标记正常关闭
+ */
+fn shutdown(app_handle: &tauri::AppHandle) {
+    let Some(file_manager) = app_handle.try_state::<FileManagerState>() else {
+        return;
+    };
+    
+    let file_manager = file_manager.inner().clone();
+    let result = tauri::async_runtime::block_on(async move {
+        file_manager.shutdown(SHUTDOWN_DRAIN_TIMEOUT).await
+    });
+    
+    if let Err(e) = result {
+        tracing::error!("文件管理服务关闭失败: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use file_manager::testing::{invoke_command, mock_webview, png_fixture, ready_state, TestLibrary};
+    use image::GenericImageView;
+
+    #[test]
+    fn test_save_tga_image_roundtrip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("out.tga");
+        let image = image::RgbaImage::from_fn(3, 2, |x, y| image::Rgba([x as u8 * 80, y as u8 * 120, 7, 200]));
+        
+        save_tga_image(path.to_str().unwrap(), &image).unwrap();
+        
+        let decoded = image::open(&path).unwrap().to_rgba8();
+        assert_eq!(decoded, image);
+    }
+
+    /// 与 FileManagerService.exportAsTga 发送的参数相同
+    #[test]
+    fn test_export_as_tga_args() {
+        let library = tauri::async_runtime::block_on(TestLibrary::builder().build());
+        let file_id = tauri::async_runtime::block_on(library.add_file("a.png", &png_fixture(3, 2, 1)));
+        let path = library.path().join("out.tga");
+        let (state, _temp_dir) = ready_state(library);
+        let (_app, webview) = mock_webview(&state, tauri::generate_handler![export_as_tga]);
+
+        invoke_command(&webview, "export_as_tga", serde_json::json!({ "fileId": file_id, "path": path }));
+
+        assert_eq!(image::open(&path).unwrap().dimensions(), (3, 2));
+    }
 }
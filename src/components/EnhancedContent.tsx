@@ -47,7 +47,7 @@ function getFileTypeInfo(file: FileListItem): {
   const mimeType = file.mime_type.toLowerCase();
 
   // 图片文件
-  if (mimeType.startsWith('image/') || ['jpg', 'jpeg', 'png', 'gif', 'bmp', 'svg', 'webp'].includes(extension)) {
+  if (mimeType.startsWith('image/') || ['jpg', 'jpeg', 'png', 'gif', 'bmp', 'svg', 'webp', 'heic', 'heif', 'avif'].includes(extension)) {
     return {
       icon: <PhotoIcon className="w-5 h-5" />,
       color: 'text-green-500',
@@ -292,9 +292,10 @@ export function useFileManager(): UseFileManagerReturn {
     try {
       updateState({ loading: true, error: undefined });
       
-      // 并发删除
+      // 并发删除，带上列表中的版本，文件已被其他窗口修改时拒绝删除
+      const revisions = new Map(state.files.map(file => [file.id, file.updated_at]));
       await Promise.all(
-        fileIds.map(id => FileManagerService.deleteFile(id))
+        fileIds.map(id => FileManagerService.deleteFile(id, revisions.get(id) ?? ''))
       );
       
       // 刷新当前目录
@@ -317,7 +318,7 @@ export function useFileManager(): UseFileManagerReturn {
     } catch (error) {
       setError(error as Error);
     }
-  }, [refreshCurrentDirectory, setError]);
+  }, [refreshCurrentDirectory, state.files, setError]);
 
   /**
    * 删除目录
@@ -326,9 +327,18 @@ export function useFileManager(): UseFileManagerReturn {
     try {
       updateState({ loading: true, error: undefined });
       
-      // 并发删除
+      // 带上目录树中的版本，目录已被其他窗口修改时拒绝删除
+      const revisions = new Map<string, string>();
+      const collect = (nodes: DirectoryTreeNode[]) => nodes.forEach(node => {
+        revisions.set(node.id, node.updated_at);
+        collect(node.children);
+      });
+      collect(state.directoryTree);
+      const directoryRevision = (id: string) => revisions.get(id) ?? '';
+
+      // 并发删除（调用方已确认删除，非空目录同样删除）
       await Promise.all(
-        directoryIds.map(id => FileManagerService.deleteDirectory(id))
+        directoryIds.map(id => FileManagerService.deleteDirectoryAndWait(id, directoryRevision(id), true))
       );
       
       // 重新加载目录树
@@ -356,7 +366,7 @@ export function useFileManager(): UseFileManagerReturn {
     } catch (error) {
       setError(error as Error);
     }
-  }, [loadDirectoryTree, state.currentDirectory, updateState, setError]);
+  }, [loadDirectoryTree, state.currentDirectory, state.directoryTree, updateState, setError]);
 
   /**
    * 创建目录
@@ -479,10 +489,11 @@ export function useFileManager(): UseFileManagerReturn {
    */
   const searchFiles = useCallback(async (
     query: string, 
-    directoryId?: string
+    directoryId?: string,
+    recursive = false
   ): Promise<FileListItem[]> => {
     try {
-      return await FileManagerService.searchFiles(query, directoryId);
+      return await FileManagerService.searchFiles(query, directoryId, recursive);
     } catch (error) {
       setError(error as Error);
       return [];
@@ -629,7 +640,7 @@ export function useFileManagerConfig(initialConfig?: Partial<{
   const [config, setConfig] = useState({
     maxFileSize: 100 * 1024 * 1024, // 100MB
     allowedFileTypes: [
-      'jpg', 'jpeg', 'png', 'gif', 'bmp', 'webp', 'svg',
+      'jpg', 'jpeg', 'png', 'gif', 'bmp', 'webp', 'heic', 'heif', 'avif', 'svg',
       'pdf', 'txt', 'md', 'doc', 'docx',
       'zip', 'rar', '7z'
     ],
@@ -4,23 +4,166 @@
  * 封装与 Tauri 后端的通信，提供类型安全的 API 调用
  */
 
-import { invoke } from '@tauri-apps/api/core';
+import { convertFileSrc, invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import type {
   CommandResponse,
+  CommandError,
+  ReadOnlyReason,
+  AppEventMap,
+  AppEventName,
+  EventDescriptor,
+  EventRecord,
+  ActivityEntry,
+  ActivityFilter,
+  RecoveredJob,
+  ListingPage,
+  Page,
+  ResponseLimitSettings,
   UploadFileRequest,
   UploadFileResponse,
+  UploadItemResult,
   CreateDirectoryRequest,
   CreateDirectoryResponse,
   DeleteFileCommand,
   DeleteDirectoryCommand,
+  MoveDirectoryCommand,
+  MoveDirectoryResponse,
+  DirectoryItemCounts,
+  DirectoryDeleteJob,
+  MetadataExportReport,
+  MetadataImportReport,
+  DirectoryDeleteProgress,
   GetDirectoryFilesCommand,
   GetFileInfoCommand,
   ReadFileContentCommand,
   DirectoryTreeNode,
   FileListItem,
+  GeoBoundingBox,
+  SimilarImage,
+  ImageComparison,
+  WatchedFolder,
+  AddWatchedFolderRequest,
+  WatchedImportReport,
+  ImportDirectoryRequest,
+  DirectoryImportReport,
+  ShellIntegrationStatus,
+  ExportBoardRequest,
+  BoardExportReport,
+  OpenedBoard,
+  CollectBoardAssetsRequest,
+  CollectBoardAssetsReport,
+  AutoLayoutRequest,
+  AutoLayoutResult,
+  BoardFrame,
+  BoardFrameRequest,
+  BoardFrameMove,
+  Playlist,
+  PlaylistRequest,
+  PlaylistItem,
+  ExportRequest,
+  ExportReport,
+  ExportPreset,
+  Watermark,
+  ManifestFormat,
+  ChecksumManifest,
+  BackupScope,
+  StorageLayout,
+  StorageLayoutSettings,
+  PreviewSettings,
+  ThumbnailSize,
+  PrefetchContext,
+  ThumbnailResponse,
+  ThumbnailCacheSettings,
+  CacheStats,
+  LowMemorySettings,
+  BackupReport,
+  RestoreMode,
+  RestoreReport,
+  CatalogSnapshotSettings,
+  CatalogSnapshot,
+  CatalogRollbackReport,
+  MissingFileScanReport,
+  RelinkFileCommand,
+  ProcessingReport,
+  ReindexScope,
+  TimelineRange,
+  TimelineBucket,
+  ActivityPoint,
+  StatsSnapshot,
+  StorageBreakdown,
+  CaptureMode,
+  NotificationSettings,
+  QuickCaptureSettings,
+  Locale,
+  StartupReport,
+  StartupTask,
+  LibrarySummary,
+  BackendStatus,
+  TelemetrySettings,
+  TelemetryPreview,
+  FileTypePolicy,
+  SizeLimits,
+  IdStrategy,
+  DirectoryDefaults,
+  BulkTagRequest,
+  BulkTagResponse,
+  SearchHistoryEntry,
+  SearchSuggestion,
+  QuickFindResult,
+  QuarantineSettings,
+  QuarantinedFile,
+  TrashSettings,
+  TrashedFile,
+  TrashPurgeReport,
+  TriageAction,
+  IntegritySettings,
+  IntegrityReport,
+  DeferredDelete,
+  DeferredDeleteReport,
+  DuplicateGroup,
+  DuplicatePlan,
+  ResolveDuplicatesReport,
+  BatchOperation,
+  BatchReport,
+  ImageMetadataFields,
+  ImageMetadataResult,
+  ArchiveSettings,
+  ArchiveReport,
+  PackSettings,
+  PackStats,
+  PackReport,
+  ExternalApp,
+  OpenWithSettings,
+  ExternalEditSession,
+  WorkingSet,
+  TemplateFolder,
+  DirectoryTemplate,
+  TemplateApplication,
+  UsageKind,
+  SuggestionContext,
+  SuggestedFile,
   StorageStats,
 } from '../types/fileManager';
 
+/** 通过 IPC 直接读取的文件大小上限，与后端 MAX_INLINE_READ_SIZE 一致 */
+export const MAX_INLINE_READ_SIZE = 32 * 1024 * 1024;
+
+/** 是否处于低内存模式，读取或保存低内存模式设置时更新；开启时预览一律通过 asset 协议加载 */
+let lowMemoryMode = false;
+
+/** 后端启动完成事件名，负载为 StartupReport */
+export const BACKEND_READY_EVENT = 'backend://ready';
+
+/** 资料库已加密、等待 unlockLibrary 提交密码的事件名 */
+export const BACKEND_LOCKED_EVENT = 'backend://locked';
+
+/** 后端初始化失败事件名，负载为错误信息 */
+export const BACKEND_FAILED_EVENT = 'backend://failed';
+
+/** 启动迁移和恢复任务的进度事件名，负载为 StartupTask */
+export const BACKEND_STARTUP_TASK_EVENT = 'backend://startup-task';
+
 /**
  * 文件管理服务类
  */
@@ -65,6 +208,8 @@ export class FileManagerService {
 
   /**
    * 批量上传文件
+   *
+   * 单个文件失败不会使整个调用失败，按请求顺序返回每个文件的结果
    */
   static async uploadMultipleFiles(
     files: Array<{
@@ -72,14 +217,14 @@ export class FileManagerService {
       originalName: string;
       directoryId?: string;
     }>
-  ): Promise<UploadFileResponse[]> {
+  ): Promise<UploadItemResult[]> {
     const requests = files.map(({ fileData, originalName, directoryId }) => ({
       file_data: Array.from(fileData),
       original_name: originalName,
       directory_id: directoryId,
     }));
 
-    const response = await invoke<CommandResponse<UploadFileResponse[]>>(
+    const response = await invoke<CommandResponse<UploadItemResult[]>>(
       'upload_multiple_files',
       { files: requests }
     );
@@ -123,11 +268,12 @@ export class FileManagerService {
   }
 
   /**
-   * 删除文件
+   * 删除文件，expectedUpdatedAt 为最后看到的文件 updated_at
    */
-  static async deleteFile(fileId: string): Promise<void> {
+  static async deleteFile(fileId: string, expectedUpdatedAt: string): Promise<void> {
     const command: DeleteFileCommand = {
       file_id: fileId,
+      expected_updated_at: expectedUpdatedAt,
     };
 
     const response = await invoke<CommandResponse<void>>('delete_file', { command });
@@ -138,18 +284,117 @@ export class FileManagerService {
   }
 
   /**
-   * 删除目录
+   * 删除目录，后台任务开始后立即返回任务句柄，进度通过 directory-delete-progress 事件发送
+   *
+   * dryRun 为 true 时不删除，返回的句柄中列出要删除的文件和目录
    */
-  static async deleteDirectory(directoryId: string): Promise<void> {
+  static async deleteDirectory(
+    directoryId: string,
+    expectedUpdatedAt: string,
+    force = false,
+    dryRun = false
+  ): Promise<DirectoryDeleteJob> {
     const command: DeleteDirectoryCommand = {
       directory_id: directoryId,
+      expected_updated_at: expectedUpdatedAt,
+      force,
+      dry_run: dryRun,
     };
 
-    const response = await invoke<CommandResponse<void>>('delete_directory', { command });
+    const response = await invoke<CommandResponse<DirectoryDeleteJob>>('delete_directory', { command });
 
-    if (!response.success) {
+    if (!response.success || !response.data) {
       throw new Error(response.error || 'Directory deletion failed');
     }
+
+    return response.data;
+  }
+
+  /**
+   * 删除目录并等待后台任务结束，任务被取消或失败时抛出错误
+   */
+  static async deleteDirectoryAndWait(
+    directoryId: string,
+    expectedUpdatedAt: string,
+    force = false,
+    onProgress?: (progress: DirectoryDeleteProgress) => void
+  ): Promise<void> {
+    // 先订阅再开始任务，避免错过很快结束的任务的事件
+    const ended = new Map<string, DirectoryDeleteProgress>();
+    let jobId: string | undefined;
+    let settle: ((progress: DirectoryDeleteProgress) => void) | undefined;
+    const unlisten = await FileManagerService.subscribe('directory-delete-progress', (progress) => {
+      if (progress.status === 'running') {
+        if (progress.job_id === jobId) onProgress?.(progress);
+        return;
+      }
+      ended.set(progress.job_id, progress);
+      if (progress.job_id === jobId) settle?.(progress);
+    });
+
+    try {
+      const job = await FileManagerService.deleteDirectory(directoryId, expectedUpdatedAt, force);
+      jobId = job.job_id;
+      const progress = ended.get(job.job_id) ?? await new Promise<DirectoryDeleteProgress>((resolve) => {
+        settle = resolve;
+      });
+      onProgress?.(progress);
+
+      if (progress.status !== 'finished') {
+        throw new Error(progress.error || `Directory deletion ${progress.status}`);
+      }
+    } finally {
+      unlisten();
+    }
+  }
+
+  /**
+   * 取消目录删除，返回任务是否仍在运行
+   */
+  static async cancelDirectoryDelete(jobId: string): Promise<boolean> {
+    const response = await invoke<CommandResponse<boolean>>('cancel_directory_delete', { jobId });
+
+    if (!response.success || response.data === undefined || response.data === null) {
+      throw new Error(response.error || 'Failed to cancel directory deletion');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取目录子树中的文件数和子目录数，用于删除前确认
+   */
+  static async getDirectoryItemCounts(directoryId: string): Promise<DirectoryItemCounts> {
+    const response = await invoke<CommandResponse<DirectoryItemCounts>>('get_directory_item_counts', { directoryId });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to count directory items');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 移动目录到新的父目录下，newParentId 为 null 时移动到根目录
+   */
+  static async moveDirectory(
+    directoryId: string,
+    newParentId: string | null,
+    expectedUpdatedAt: string
+  ): Promise<MoveDirectoryResponse> {
+    const command: MoveDirectoryCommand = {
+      directory_id: directoryId,
+      new_parent_id: newParentId,
+      expected_updated_at: expectedUpdatedAt,
+    };
+
+    const response = await invoke<CommandResponse<MoveDirectoryResponse>>('move_directory', { command });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Directory move failed');
+    }
+
+    return response.data;
   }
 
   /**
@@ -170,9 +415,10 @@ export class FileManagerService {
   /**
    * 获取目录中的文件列表
    */
-  static async getDirectoryFiles(directoryId: string): Promise<FileListItem[]> {
+  static async getDirectoryFiles(directoryId: string, archived?: boolean): Promise<FileListItem[]> {
     const command: GetDirectoryFilesCommand = {
       directory_id: directoryId,
+      archived: archived ?? null,
     };
 
     const response = await invoke<CommandResponse<FileListItem[]>>(
@@ -184,6 +430,46 @@ export class FileManagerService {
       throw new Error(response.error || 'Failed to load directory files');
     }
 
+    if (response.continuation) {
+      console.warn('[FileManagerService]', response.continuation.warning);
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取被截断的列表响应的下一页
+   */
+  static async getListingPage<T>(listingId: string, offset: number): Promise<ListingPage<T>> {
+    const response = await invoke<CommandResponse<T[]>>(
+      'get_listing_page',
+      { listingId, offset }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to load listing page');
+    }
+
+    return { items: response.data, continuation: response.continuation };
+  }
+
+  /**
+   * 按原始文件名分页获取目录中的文件，cursor 为上一页返回的 next_cursor
+   */
+  static async getDirectoryFilesPage(
+    directoryId: string,
+    cursor?: string | null,
+    limit?: number
+  ): Promise<Page<FileListItem>> {
+    const response = await invoke<CommandResponse<Page<FileListItem>>>(
+      'get_directory_files_page',
+      { directoryId, cursor, limit }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to load directory files');
+    }
+
     return response.data;
   }
 
@@ -208,17 +494,19 @@ export class FileManagerService {
   }
 
   /**
-   * 搜索文件
+   * 搜索文件，指定 directoryId 时只搜索该目录，recursive 为 true 时包含所有子目录
    */
   static async searchFiles(
     query: string,
-    directoryId?: string
+    directoryId?: string,
+    recursive = false
   ): Promise<FileListItem[]> {
     const response = await invoke<CommandResponse<FileListItem[]>>(
       'search_files',
       {
         query,
-        directory_id: directoryId,
+        scopeDirectoryId: directoryId,
+        recursive,
       }
     );
 
@@ -230,62 +518,2505 @@ export class FileManagerService {
   }
 
   /**
-   * 获取存储统计信息
+   * 分页搜索文件，cursor 为上一页返回的 next_cursor
    */
-  static async getStorageStats(): Promise<StorageStats> {
-    const response = await invoke<CommandResponse<StorageStats>>(
-      'get_storage_stats'
+  static async searchFilesPage(
+    query: string,
+    directoryId?: string,
+    cursor?: string | null,
+    limit?: number,
+    recursive = false
+  ): Promise<Page<FileListItem>> {
+    const response = await invoke<CommandResponse<Page<FileListItem>>>(
+      'search_files_page',
+      { query, scopeDirectoryId: directoryId, recursive, cursor, limit }
     );
 
     if (!response.success || !response.data) {
-      throw new Error(response.error || 'Failed to get storage stats');
+      throw new Error(response.error || 'Search failed');
     }
 
     return response.data;
   }
 
   /**
-   * 验证文件类型
+   * 分页获取最近动态，按时间从新到旧排列，cursor 为上一页返回的 next_cursor
    */
-  static async validateFileType(filename: string): Promise<boolean> {
-    const response = await invoke<CommandResponse<boolean>>(
-      'validate_file_type',
-      { filename }
+  static async getActivityFeed(
+    cursor?: string | null,
+    filter?: ActivityFilter,
+    limit?: number
+  ): Promise<Page<ActivityEntry>> {
+    const response = await invoke<CommandResponse<Page<ActivityEntry>>>(
+      'get_activity_feed',
+      { cursor, filter, limit }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get activity feed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 从 URL 导入文件
+   */
+  static async importFromUrl(
+    url: string,
+    directoryId?: string,
+    tags?: string[]
+  ): Promise<UploadFileResponse> {
+    const response = await invoke<CommandResponse<UploadFileResponse>>(
+      'import_from_url',
+      {
+        url,
+        directoryId,
+        tags,
+      }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Import from URL failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取库增长时间线
+   */
+  static async getActivityTimeline(
+    range: TimelineRange,
+    bucket: TimelineBucket = 'day'
+  ): Promise<ActivityPoint[]> {
+    const response = await invoke<CommandResponse<ActivityPoint[]>>(
+      'get_activity_timeline',
+      { range, bucket }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get activity timeline');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取每日统计快照历史，用于绘制库增长趋势
+   */
+  static async getStatsHistory(range: TimelineRange): Promise<StatsSnapshot[]> {
+    const response = await invoke<CommandResponse<StatsSnapshot[]>>('get_stats_history', { range });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get stats history');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取存储占用分布
+   */
+  static async getStorageBreakdown(): Promise<StorageBreakdown> {
+    const response = await invoke<CommandResponse<StorageBreakdown>>(
+      'get_storage_breakdown'
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get storage breakdown');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 截图并保存到库中
+   */
+  static async captureScreenshot(
+    mode: CaptureMode,
+    directoryId?: string,
+    tags?: string[]
+  ): Promise<UploadFileResponse> {
+    const response = await invoke<CommandResponse<UploadFileResponse>>(
+      'capture_screenshot',
+      { mode, directoryId, tags }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Screenshot capture failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取通知设置
+   */
+  static async getNotificationSettings(): Promise<NotificationSettings> {
+    const response = await invoke<CommandResponse<NotificationSettings>>(
+      'get_notification_settings'
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get notification settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 保存通知设置
+   */
+  static async setNotificationSettings(settings: NotificationSettings): Promise<void> {
+    const response = await invoke<CommandResponse<void>>(
+      'set_notification_settings',
+      { settings }
     );
 
     if (!response.success) {
-      throw new Error(response.error || 'File type validation failed');
+      throw new Error(response.error || 'Failed to save notification settings');
     }
+  }
 
-    return response.data || false;
+  /**
+   * 获取快速截图设置
+   */
+  static async getQuickCaptureSettings(): Promise<QuickCaptureSettings> {
+    const response = await invoke<CommandResponse<QuickCaptureSettings>>(
+      'get_quick_capture_settings'
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get quick capture settings');
+    }
+
+    return response.data;
   }
 
   /**
-   * 读取文件内容
+   * 保存快速截图设置并重新注册快捷键
    */
-  static async readFileContent(fileId: string): Promise<Uint8Array> {
-    const command: ReadFileContentCommand = {
-      file_id: fileId,
-    };
-    
-    const response = await invoke<CommandResponse<number[]>>('read_file_content', { command });
-    
+  static async setQuickCaptureSettings(settings: QuickCaptureSettings): Promise<void> {
+    const response = await invoke<CommandResponse<void>>(
+      'set_quick_capture_settings',
+      { settings }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to save quick capture settings');
+    }
+  }
+
+  /**
+   * 获取后端服务状态，初始化期间也可调用
+   */
+  static async getBackendStatus(): Promise<BackendStatus> {
+    const response = await invoke<CommandResponse<BackendStatus>>('get_backend_status');
+
     if (!response.success || !response.data) {
-      throw new Error(response.error || 'Failed to read file content');
+      throw new Error(response.error || 'Failed to get backend status');
     }
 
-    return new Uint8Array(response.data);
+    return response.data;
   }
 
   /**
-   * 生成文件预览 URL（如果支持）
+   * 进入或退出只读模式，返回切换后的只读原因；其他用户正在写入时无法退出
    */
-  static async generatePreviewUrl(file: FileListItem): Promise<string | null> {
-    if (!FileUtils.isImageFile(file.name)) {
-      return null;
+  static async setReadOnly(enabled: boolean): Promise<ReadOnlyReason | null> {
+    const response = await invoke<CommandResponse<ReadOnlyReason | null>>('set_read_only', { enabled });
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to switch read-only mode');
     }
-    
-    try {
+
+    return response.data ?? null;
+  }
+
+  /**
+   * 接管心跳已停止的资料库，原持有者转为只读模式
+   */
+  static async takeOverLibrary(): Promise<ReadOnlyReason | null> {
+    const response = await invoke<CommandResponse<ReadOnlyReason | null>>('take_over_library');
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to take over library');
+    }
+
+    return response.data ?? null;
+  }
+
+  /**
+   * 启动时提交资料库密码，密码正确后继续初始化
+   */
+  static async unlockLibrary(password: string): Promise<void> {
+    const response = await invoke<CommandResponse<void>>('unlock_library', { password });
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to unlock library');
+    }
+  }
+
+  /**
+   * 查询资料库是否已设置密码
+   */
+  static async isLibraryEncrypted(): Promise<boolean> {
+    const response = await invoke<CommandResponse<boolean>>('is_library_encrypted');
+
+    if (!response.success || response.data === undefined || response.data === null) {
+      throw new Error(response.error || 'Failed to get library encryption state');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 设置资料库密码，加密目录数据库；密码不会保存，每次启动都需要输入
+   */
+  static async setLibraryPassword(password: string): Promise<void> {
+    const response = await invoke<CommandResponse<void>>('set_library_password', { password });
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to set library password');
+    }
+  }
+
+  /**
+   * 修改资料库密码
+   */
+  static async changeLibraryPassword(oldPassword: string, newPassword: string): Promise<void> {
+    const response = await invoke<CommandResponse<void>>(
+      'change_library_password',
+      { oldPassword, newPassword }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to change library password');
+    }
+  }
+
+  /**
+   * 获取启动耗时报告，用于错过 backend://ready 事件时补查
+   */
+  static async getStartupReport(): Promise<StartupReport> {
+    const response = await invoke<CommandResponse<StartupReport>>('get_startup_report');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get startup report');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取本次启动时恢复或回滚的、上次运行被中断的任务
+   */
+  static async getRecoveredJobs(): Promise<RecoveredJob[]> {
+    const response = await invoke<CommandResponse<RecoveredJob[]>>('get_recovered_jobs');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get recovered jobs');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取启动迁移和恢复任务的进度，初始化期间也可调用
+   */
+  static async getStartupTasksStatus(): Promise<StartupTask[]> {
+    const response = await invoke<CommandResponse<StartupTask[]>>('get_startup_tasks_status');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get startup tasks status');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取目录默认标签
+   */
+  static async getDirectoryDefaults(directoryId: string): Promise<DirectoryDefaults> {
+    const response = await invoke<CommandResponse<DirectoryDefaults>>(
+      'get_directory_defaults',
+      { directoryId }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get directory defaults');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 设置目录默认标签，标签为空且不继承时清除设置
+   */
+  static async setDirectoryDefaults(
+    directoryId: string,
+    tags: string[],
+    inherit: boolean
+  ): Promise<DirectoryDefaults> {
+    const response = await invoke<CommandResponse<DirectoryDefaults>>(
+      'set_directory_defaults',
+      { directoryId, tags, inherit }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to set directory defaults');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取搜索框的输入建议（搜索历史、标签名、文件名中的词）
+   */
+  static async getSearchSuggestions(prefix: string, limit?: number): Promise<SearchSuggestion[]> {
+    const response = await invoke<CommandResponse<SearchSuggestion[]>>(
+      'get_search_suggestions',
+      { prefix, limit }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get search suggestions');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 命令面板快速切换：按前缀模糊查找目录、标签、工作集和文件
+   */
+  static async quickFind(prefix: string, limit?: number): Promise<QuickFindResult[]> {
+    const response = await invoke<CommandResponse<QuickFindResult[]>>(
+      'quick_find',
+      { prefix, limit }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to run quick find');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取搜索历史，最近使用的在前
+   */
+  static async getSearchHistory(): Promise<SearchHistoryEntry[]> {
+    const response = await invoke<CommandResponse<SearchHistoryEntry[]>>('get_search_history');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get search history');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 清空搜索历史
+   */
+  static async clearSearchHistory(): Promise<void> {
+    const response = await invoke<CommandResponse<void>>('clear_search_history');
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to clear search history');
+    }
+  }
+
+  /**
+   * 获取隔离区设置
+   */
+  static async getQuarantineSettings(): Promise<QuarantineSettings> {
+    const response = await invoke<CommandResponse<QuarantineSettings>>('get_quarantine_settings');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get quarantine settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 保存隔离区设置
+   */
+  static async setQuarantineSettings(settings: QuarantineSettings): Promise<void> {
+    const response = await invoke<CommandResponse<void>>('set_quarantine_settings', { settings });
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to save quarantine settings');
+    }
+  }
+
+  /**
+   * 获取隔离区中的文件，最新的在前
+   */
+  static async getQuarantinedFiles(): Promise<QuarantinedFile[]> {
+    const response = await invoke<CommandResponse<QuarantinedFile[]>>('get_quarantined_files');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get quarantined files');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 批准隔离区中的文件并导入到库中，未指定目录时使用上传时的目标目录
+   */
+  static async approveQuarantinedFile(id: string, directoryId?: string): Promise<UploadFileResponse> {
+    const response = await invoke<CommandResponse<UploadFileResponse>>(
+      'approve_quarantined_file',
+      { id, directoryId: directoryId ?? null }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to approve quarantined file');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 丢弃隔离区中的文件
+   */
+  static async discardQuarantinedFile(id: string): Promise<void> {
+    const response = await invoke<CommandResponse<void>>('discard_quarantined_file', { id });
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to discard quarantined file');
+    }
+  }
+
+  /**
+   * 获取回收站设置
+   */
+  static async getTrashSettings(): Promise<TrashSettings> {
+    const response = await invoke<CommandResponse<TrashSettings>>('get_trash_settings');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get trash settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 保存回收站设置，关闭回收站不会清空其中已有的文件
+   */
+  static async setTrashSettings(settings: TrashSettings): Promise<void> {
+    const response = await invoke<CommandResponse<void>>('set_trash_settings', { settings });
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to save trash settings');
+    }
+  }
+
+  /**
+   * 获取回收站中的文件，最新删除的在前
+   */
+  static async getTrashedFiles(): Promise<TrashedFile[]> {
+    const response = await invoke<CommandResponse<TrashedFile[]>>('get_trashed_files');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get trashed files');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 从回收站恢复文件，未指定目录时恢复到删除前所在的目录
+   */
+  static async restoreTrashedFile(id: string, directoryId?: string): Promise<UploadFileResponse> {
+    const response = await invoke<CommandResponse<UploadFileResponse>>(
+      'restore_trashed_file',
+      { id, directoryId: directoryId ?? null }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to restore trashed file');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 永久删除回收站中的文件
+   */
+  static async purgeTrashedFile(id: string): Promise<void> {
+    const response = await invoke<CommandResponse<void>>('purge_trashed_file', { id });
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to purge trashed file');
+    }
+  }
+
+  /**
+   * 立即按保留策略清理回收站
+   */
+  static async applyTrashRetention(): Promise<TrashPurgeReport> {
+    const response = await invoke<CommandResponse<TrashPurgeReport>>('apply_trash_retention');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to apply trash retention');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取文件类型策略
+   */
+  static async getFileTypePolicy(): Promise<FileTypePolicy> {
+    const response = await invoke<CommandResponse<FileTypePolicy>>('get_file_type_policy');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get file type policy');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 保存文件类型策略，立即生效，返回规范化后的策略
+   */
+  static async setFileTypePolicy(policy: FileTypePolicy): Promise<FileTypePolicy> {
+    const response = await invoke<CommandResponse<FileTypePolicy>>('set_file_type_policy', { policy });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to set file type policy');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取新记录的 ID 生成策略
+   */
+  static async getIdStrategy(): Promise<IdStrategy> {
+    const response = await invoke<CommandResponse<IdStrategy>>('get_id_strategy');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get ID strategy');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 保存 ID 生成策略，只影响之后创建的记录
+   */
+  static async setIdStrategy(strategy: IdStrategy): Promise<IdStrategy> {
+    const response = await invoke<CommandResponse<IdStrategy>>('set_id_strategy', { strategy });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to set ID strategy');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取按文件类别的大小上限
+   */
+  static async getSizeLimits(): Promise<SizeLimits> {
+    const response = await invoke<CommandResponse<SizeLimits>>('get_size_limits');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get size limits');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 保存按文件类别的大小上限，立即生效
+   */
+  static async setSizeLimits(limits: SizeLimits): Promise<SizeLimits> {
+    const response = await invoke<CommandResponse<SizeLimits>>('set_size_limits', { limits });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to set size limits');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取匿名使用统计设置
+   */
+  static async getTelemetrySettings(): Promise<TelemetrySettings> {
+    const response = await invoke<CommandResponse<TelemetrySettings>>('get_telemetry_settings');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get telemetry settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 订阅后端事件，负载类型由事件名决定
+   */
+  static subscribe<K extends AppEventName>(
+    name: K,
+    handler: (payload: AppEventMap[K]) => void
+  ): Promise<UnlistenFn> {
+    return listen<AppEventMap[K]>(name, (event) => handler(event.payload));
+  }
+
+  /**
+   * 获取后端会发送的所有事件
+   */
+  static async getEventCatalog(): Promise<EventDescriptor[]> {
+    const response = await invoke<CommandResponse<EventDescriptor[]>>('get_event_catalog');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get event catalog');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取序号大于 afterSeq 的最近事件，用于补收订阅前错过的事件
+   */
+  static async getRecentEvents(afterSeq?: number): Promise<EventRecord[]> {
+    const response = await invoke<CommandResponse<EventRecord[]>>(
+      'get_recent_events',
+      { afterSeq }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get recent events');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取列表响应大小上限
+   */
+  static async getResponseLimit(): Promise<ResponseLimitSettings> {
+    const response = await invoke<CommandResponse<ResponseLimitSettings>>('get_response_limit');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get response limit');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 保存列表响应大小上限，立即生效
+   */
+  static async setResponseLimit(settings: ResponseLimitSettings): Promise<void> {
+    const response = await invoke<CommandResponse<void>>('set_response_limit', { settings });
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to save response limit');
+    }
+  }
+
+  /**
+   * 保存匿名使用统计设置，关闭时清空尚未发送的计数
+   */
+  static async setTelemetrySettings(settings: TelemetrySettings): Promise<void> {
+    const response = await invoke<CommandResponse<void>>('set_telemetry_settings', { settings });
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to save telemetry settings');
+    }
+  }
+
+  /**
+   * 预览下次将要发送的使用统计
+   */
+  static async getTelemetryPreview(): Promise<TelemetryPreview> {
+    const response = await invoke<CommandResponse<TelemetryPreview>>('get_telemetry_preview');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get telemetry preview');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取资料库概要，变化时后端另外发送 library-summary 事件
+   */
+  static async getLibrarySummary(): Promise<LibrarySummary> {
+    const response = await invoke<CommandResponse<LibrarySummary>>('get_library_summary');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get library summary');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 设置资料库名称，传入空字符串恢复默认名称；返回生效的名称
+   */
+  static async setLibraryName(name: string): Promise<string> {
+    const response = await invoke<CommandResponse<string>>('set_library_name', { name });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to set library name');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取后端错误信息语言
+   */
+  static async getLocale(): Promise<Locale> {
+    const response = await invoke<CommandResponse<Locale>>('get_locale');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get locale');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 设置后端错误信息语言，立即生效
+   */
+  static async setLocale(locale: Locale): Promise<void> {
+    const response = await invoke<CommandResponse<void>>('set_locale', { locale });
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to set locale');
+    }
+  }
+
+  /**
+   * 为多个文件批量添加和移除标签
+   */
+  static async updateTagsBulk(
+    fileIds: string[],
+    addTags: string[] = [],
+    removeTags: string[] = []
+  ): Promise<BulkTagResponse> {
+    const request: BulkTagRequest = {
+      file_ids: fileIds,
+      add_tags: addTags,
+      remove_tags: removeTags,
+    };
+
+    const response = await invoke<CommandResponse<BulkTagResponse>>('update_tags_bulk', { request });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to update tags');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取文件的标签
+   */
+  static async getFileTags(fileId: string): Promise<string[]> {
+    const response = await invoke<CommandResponse<string[]>>('get_file_tags', { fileId });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to load file tags');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 整理收件箱中的文件，删除时返回 null
+   */
+  static async triageFile(fileId: string, action: TriageAction): Promise<FileListItem | null> {
+    const response = await invoke<CommandResponse<FileListItem | null>>(
+      'triage_file',
+      { fileId, action }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to triage file');
+    }
+
+    return response.data ?? null;
+  }
+
+  /**
+   * 设置文件评分（0-5）和备注，传 null 清除
+   */
+  static async setFileAnnotations(
+    fileId: string,
+    rating: number | null,
+    notes: string | null
+  ): Promise<FileListItem> {
+    const response = await invoke<CommandResponse<FileListItem>>(
+      'set_file_annotations',
+      { fileId, rating, notes }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to set file annotations');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 写入图像的标题、描述和关键词，JPEG / PNG 文件同时以 XMP 内嵌
+   */
+  static async writeImageMetadata(fileId: string, fields: ImageMetadataFields): Promise<ImageMetadataResult> {
+    const response = await invoke<CommandResponse<ImageMetadataResult>>(
+      'write_image_metadata',
+      { fileId, fields }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to write image metadata');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 创建工作集，ttlMinutes 省略时默认一天
+   */
+  static async createWorkingSet(name?: string | null, ttlMinutes?: number | null): Promise<WorkingSet> {
+    const response = await invoke<CommandResponse<WorkingSet>>(
+      'create_working_set',
+      { name: name ?? null, ttlMinutes: ttlMinutes ?? null }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to create working set');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取所有未过期的工作集
+   */
+  static async listWorkingSets(): Promise<WorkingSet[]> {
+    const response = await invoke<CommandResponse<WorkingSet[]>>('list_working_sets');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to list working sets');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 向工作集添加文件
+   */
+  static async addToWorkingSet(workingSetId: string, fileIds: string[]): Promise<WorkingSet> {
+    const response = await invoke<CommandResponse<WorkingSet>>(
+      'add_to_working_set',
+      { workingSetId, fileIds }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to add files to working set');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 从工作集移除文件
+   */
+  static async removeFromWorkingSet(workingSetId: string, fileIds: string[]): Promise<WorkingSet> {
+    const response = await invoke<CommandResponse<WorkingSet>>(
+      'remove_from_working_set',
+      { workingSetId, fileIds }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to remove files from working set');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取工作集中的文件，按加入顺序排列
+   */
+  static async getWorkingSetFiles(workingSetId: string): Promise<FileListItem[]> {
+    const response = await invoke<CommandResponse<FileListItem[]>>(
+      'get_working_set_files',
+      { workingSetId }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get working set files');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 删除工作集，成员文件不受影响
+   */
+  static async deleteWorkingSet(workingSetId: string): Promise<void> {
+    const response = await invoke<CommandResponse<void>>(
+      'delete_working_set',
+      { workingSetId }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to delete working set');
+    }
+  }
+
+  /**
+   * 记录一次文件使用，放置到画板时传入画板 ID
+   */
+  static async recordFileUsage(fileId: string, kind: UsageKind, boardId?: string | null): Promise<void> {
+    const response = await invoke<CommandResponse<void>>(
+      'record_file_usage',
+      { fileId, kind, boardId: boardId ?? null }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to record file usage');
+    }
+  }
+
+  /**
+   * 获取与当前画板或目录相关的推荐素材
+   */
+  static async getSuggestions(context: SuggestionContext): Promise<SuggestedFile[]> {
+    const response = await invoke<CommandResponse<SuggestedFile[]>>('get_suggestions', { context });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get suggestions');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 创建目录模板
+   */
+  static async createDirectoryTemplate(name: string, folders: TemplateFolder[]): Promise<DirectoryTemplate> {
+    const response = await invoke<CommandResponse<DirectoryTemplate>>(
+      'create_directory_template',
+      { name, folders }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to create directory template');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取所有目录模板，按名称排列
+   */
+  static async listDirectoryTemplates(): Promise<DirectoryTemplate[]> {
+    const response = await invoke<CommandResponse<DirectoryTemplate[]>>('list_directory_templates');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to list directory templates');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 修改目录模板，已按模板创建的目录不受影响
+   */
+  static async updateDirectoryTemplate(
+    templateId: string,
+    name: string,
+    folders: TemplateFolder[]
+  ): Promise<DirectoryTemplate> {
+    const response = await invoke<CommandResponse<DirectoryTemplate>>(
+      'update_directory_template',
+      { templateId, name, folders }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to update directory template');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 删除目录模板
+   */
+  static async deleteDirectoryTemplate(templateId: string): Promise<void> {
+    const response = await invoke<CommandResponse<void>>(
+      'delete_directory_template',
+      { templateId }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to delete directory template');
+    }
+  }
+
+  /**
+   * 按模板在父目录下创建目录及其子目录，parentId 为空时创建在顶层
+   */
+  static async createDirectoryFromTemplate(
+    templateId: string,
+    parentId: string | null,
+    name: string
+  ): Promise<TemplateApplication> {
+    const response = await invoke<CommandResponse<TemplateApplication>>(
+      'create_directory_from_template',
+      { templateId, parentId, name }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to create directory from template');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 读取 XMP / JSON 附属文件，将标签、评分和备注应用到文件
+   */
+  static async importSidecar(fileId: string, path: string): Promise<FileListItem> {
+    const response = await invoke<CommandResponse<FileListItem>>(
+      'import_sidecar',
+      { fileId, path }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to import sidecar');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 把标签、评分、备注和标题导出到 JSON 文件，指定 directoryId 时只导出该目录（recursive 时含子目录）
+   */
+  static async exportMetadata(
+    destination: string,
+    directoryId?: string,
+    recursive = false
+  ): Promise<MetadataExportReport> {
+    const response = await invoke<CommandResponse<MetadataExportReport>>(
+      'export_metadata',
+      { destination, scopeDirectoryId: directoryId, recursive }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to export metadata');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 从其他资料库导出的 JSON 文件导入元数据，matchByHash 为 false 时按目录路径和文件名匹配
+   */
+  static async importMetadata(path: string, matchByHash = true): Promise<MetadataImportReport> {
+    const response = await invoke<CommandResponse<MetadataImportReport>>(
+      'import_metadata',
+      { path, matchByHash }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to import metadata');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取收件箱待整理文件数，用于显示角标
+   */
+  static async getInboxCount(): Promise<number> {
+    const response = await invoke<CommandResponse<number>>('get_inbox_count');
+
+    if (!response.success || response.data === undefined || response.data === null) {
+      throw new Error(response.error || 'Failed to get inbox count');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取完整性抽检设置
+   */
+  static async getIntegritySettings(): Promise<IntegritySettings> {
+    const response = await invoke<CommandResponse<IntegritySettings>>(
+      'get_integrity_settings'
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get integrity settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 保存完整性抽检设置
+   */
+  static async setIntegritySettings(settings: IntegritySettings): Promise<void> {
+    const response = await invoke<CommandResponse<void>>(
+      'set_integrity_settings',
+      { settings }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to save integrity settings');
+    }
+  }
+
+  /**
+   * 立即抽检一批存储文件
+   */
+  static async runIntegrityCheck(sampleSize?: number): Promise<IntegrityReport> {
+    const response = await invoke<CommandResponse<IntegrityReport>>(
+      'run_integrity_check',
+      { sampleSize }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Integrity check failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取延迟删除队列
+   */
+  static async listDeferredDeletes(): Promise<DeferredDelete[]> {
+    const response = await invoke<CommandResponse<DeferredDelete[]>>('list_deferred_deletes');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to list deferred deletes');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 立即重试延迟删除
+   */
+  static async retryDeferredDeletes(): Promise<DeferredDeleteReport> {
+    const response = await invoke<CommandResponse<DeferredDeleteReport>>('retry_deferred_deletes');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to retry deferred deletes');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 按内容查找重复文件
+   */
+  static async findDuplicates(): Promise<DuplicateGroup[]> {
+    const response = await invoke<CommandResponse<DuplicateGroup[]>>(
+      'find_duplicates'
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to find duplicates');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 执行重复文件清理计划，返回的 undo_id 可用于撤销
+   *
+   * dryRun 为 true 时只检查计划并列出将要进行的修改
+   */
+  static async resolveDuplicates(plan: DuplicatePlan, dryRun = false): Promise<ResolveDuplicatesReport> {
+    const response = await invoke<CommandResponse<ResolveDuplicatesReport>>(
+      'resolve_duplicates',
+      { plan, dryRun }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to resolve duplicates');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 撤销重复文件清理
+   */
+  static async undoResolveDuplicates(undoId: string): Promise<void> {
+    const response = await invoke<CommandResponse<void>>(
+      'undo_resolve_duplicates',
+      { undoId }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to undo duplicate cleanup');
+    }
+  }
+
+  /**
+   * 在一个事务中执行一组移动、重命名、标签修改和删除，任一失败时整批不生效
+   *
+   * revisions 为文件 ID 到最后看到的 updated_at，移动、重命名和删除的文件必须提供；
+   * dryRun 为 true 时不修改资料库，返回的结果中列出各项修改
+   */
+  static async batchOperations(
+    operations: BatchOperation[],
+    revisions: Record<string, string>,
+    dryRun = false
+  ): Promise<BatchReport> {
+    const response = await invoke<CommandResponse<BatchReport>>(
+      'batch_operations',
+      { operations, revisions, dryRun }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Batch operation failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 撤销批量操作
+   */
+  static async undoBatchOperations(undoId: string): Promise<void> {
+    const response = await invoke<CommandResponse<void>>(
+      'undo_batch_operations',
+      { undoId }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to undo batch operation');
+    }
+  }
+
+  /**
+   * 获取归档设置
+   */
+  static async getArchiveSettings(): Promise<ArchiveSettings> {
+    const response = await invoke<CommandResponse<ArchiveSettings>>('get_archive_settings');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get archive settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 设置归档位置，传入空值时清除
+   */
+  static async setArchiveSettings(settings: ArchiveSettings): Promise<ArchiveSettings> {
+    const response = await invoke<CommandResponse<ArchiveSettings>>(
+      'set_archive_settings',
+      { settings }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to set archive settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 将文件的存储文件移到归档位置，文件记录和缩略图保留
+   */
+  static async archiveFiles(fileIds: string[]): Promise<ArchiveReport> {
+    const response = await invoke<CommandResponse<ArchiveReport>>(
+      'archive_files',
+      { fileIds }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to archive files');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 将已归档文件移回主存储
+   */
+  static async restoreArchivedFiles(fileIds: string[]): Promise<ArchiveReport> {
+    const response = await invoke<CommandResponse<ArchiveReport>>(
+      'restore_archived_files',
+      { fileIds }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to restore archived files');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取打包存储设置
+   */
+  static async getPackSettings(): Promise<PackSettings> {
+    const response = await invoke<CommandResponse<PackSettings>>('get_pack_settings');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get pack settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 设置打包存储，关闭后已打包的文件保持打包
+   */
+  static async setPackSettings(settings: PackSettings): Promise<PackSettings> {
+    const response = await invoke<CommandResponse<PackSettings>>(
+      'set_pack_settings',
+      { settings }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to set pack settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取打包存储使用情况
+   */
+  static async getPackStats(): Promise<PackStats> {
+    const response = await invoke<CommandResponse<PackStats>>('get_pack_stats');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get pack stats');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 立即打包小文件并压缩打包文件
+   */
+  static async runPackMaintenance(): Promise<PackReport> {
+    const response = await invoke<CommandResponse<PackReport>>('run_pack_maintenance');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to run pack maintenance');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取外部应用设置
+   */
+  static async getOpenWith(): Promise<OpenWithSettings> {
+    const response = await invoke<CommandResponse<OpenWithSettings>>('get_open_with');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get open-with settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 设置 MIME 类型对应的外部应用，传入空列表时移除该类型的配置
+   */
+  static async setOpenWith(mimeType: string, apps: ExternalApp[]): Promise<OpenWithSettings> {
+    const response = await invoke<CommandResponse<OpenWithSettings>>(
+      'set_open_with',
+      { mimeType, apps }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to set open-with settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取可打开文件的外部应用，首选应用在前
+   */
+  static async getAppsForFile(fileId: string): Promise<ExternalApp[]> {
+    const response = await invoke<CommandResponse<ExternalApp[]>>('get_apps_for_file', { fileId });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get apps for file');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 用外部应用打开文件，未指定应用时使用首选应用或系统默认程序
+   */
+  static async openFileWith(fileId: string, appId?: string): Promise<void> {
+    const response = await invoke<CommandResponse<void>>(
+      'open_file_with',
+      { fileId, appId: appId ?? null }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to open file');
+    }
+  }
+
+  /**
+   * 在外部应用中编辑文件，副本被保存时收到 external-edit-changed 事件
+   */
+  static async editExternally(fileId: string, appId?: string): Promise<ExternalEditSession> {
+    const response = await invoke<CommandResponse<ExternalEditSession>>(
+      'edit_externally',
+      { fileId, appId: appId ?? null }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to edit file externally');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取进行中的外部编辑会话
+   */
+  static async getExternalEdits(): Promise<ExternalEditSession[]> {
+    const response = await invoke<CommandResponse<ExternalEditSession[]>>('get_external_edits');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get external edits');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 将外部编辑的副本导入为原文件的新版本
+   */
+  static async applyExternalEdit(sessionId: string): Promise<UploadFileResponse> {
+    const response = await invoke<CommandResponse<UploadFileResponse>>(
+      'apply_external_edit',
+      { sessionId }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to apply external edit');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 结束外部编辑并删除副本，未导入的修改被丢弃
+   */
+  static async endExternalEdit(sessionId: string): Promise<void> {
+    const response = await invoke<CommandResponse<void>>('end_external_edit', { sessionId });
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to end external edit');
+    }
+  }
+
+  /**
+   * 将库中的图像导出为 TGA 文件
+   */
+  static async exportAsTga(fileId: string, path: string): Promise<void> {
+    await invoke<void>('export_as_tga', { fileId, path });
+  }
+
+  /**
+   * 导出文件到外部目录
+   *
+   * 进度通过 export-progress 事件推送
+   */
+  static async exportFiles(request: ExportRequest): Promise<ExportReport> {
+    const response = await invoke<CommandResponse<ExportReport>>(
+      'export_files',
+      { request }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Export failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取导出预设
+   */
+  static async getExportPresets(): Promise<ExportPreset[]> {
+    const response = await invoke<CommandResponse<ExportPreset[]>>('get_export_presets');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get export presets');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 保存导出预设，整体替换已有的预设
+   */
+  static async setExportPresets(presets: ExportPreset[]): Promise<ExportPreset[]> {
+    const response = await invoke<CommandResponse<ExportPreset[]>>('set_export_presets', { presets });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to save export presets');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 按预设缩放、转换格式后导出图片，可选叠加水印
+   *
+   * 进度通过 export-progress 事件推送
+   */
+  static async exportWithPreset(
+    fileIds: string[],
+    presetId: string,
+    destination: string,
+    watermark?: Watermark
+  ): Promise<ExportReport> {
+    const response = await invoke<CommandResponse<ExportReport>>(
+      'export_with_preset',
+      { fileIds, presetId, destination, watermark: watermark ?? null }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Export failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 为选中的文件生成校验清单，文件名与平铺导出时一致
+   */
+  static async generateManifest(fileIds: string[], format: ManifestFormat): Promise<ChecksumManifest> {
+    const response = await invoke<CommandResponse<ChecksumManifest>>(
+      'generate_manifest',
+      { fileIds, format }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to generate manifest');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取存储布局设置
+   */
+  static async getStorageLayout(): Promise<StorageLayoutSettings> {
+    const response = await invoke<CommandResponse<StorageLayoutSettings>>('get_storage_layout');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get storage layout');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 切换存储布局，已有文件在后台迁移
+   */
+  static async setStorageLayout(layout: StorageLayout): Promise<StorageLayoutSettings> {
+    const response = await invoke<CommandResponse<StorageLayoutSettings>>(
+      'set_storage_layout',
+      { layout }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to set storage layout');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取缩略图生成设置
+   */
+  static async getPreviewSettings(): Promise<PreviewSettings> {
+    const response = await invoke<CommandResponse<PreviewSettings>>('get_preview_settings');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get preview settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 保存缩略图生成设置，已有缩略图在后台按新设置重新生成
+   */
+  static async setPreviewSettings(settings: PreviewSettings): Promise<PreviewSettings> {
+    const response = await invoke<CommandResponse<PreviewSettings>>('set_preview_settings', { settings });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to set preview settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取缩略图缓存占用
+   */
+  static async getCacheStats(): Promise<CacheStats> {
+    const response = await invoke<CommandResponse<CacheStats>>('get_cache_stats');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get cache stats');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取缩略图，请求的尺寸尚未生成时先返回已有的较小尺寸
+   *
+   * 传入 context 时在后台预取浏览位置前后相邻文件的缩略图
+   */
+  static async getThumbnail(
+    fileId: string,
+    size: ThumbnailSize,
+    context?: PrefetchContext | null
+  ): Promise<ThumbnailResponse> {
+    const response = await invoke<CommandResponse<ThumbnailResponse>>(
+      'get_thumbnail',
+      { fileId, size, context: context ?? null }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to load thumbnail');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 清空缩略图缓存，返回释放的字节数
+   */
+  static async clearThumbnailCache(): Promise<number> {
+    const response = await invoke<CommandResponse<number>>('clear_thumbnail_cache');
+
+    if (!response.success || response.data === undefined || response.data === null) {
+      throw new Error(response.error || 'Failed to clear thumbnail cache');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取缩略图缓存设置
+   */
+  static async getThumbnailCacheSettings(): Promise<ThumbnailCacheSettings> {
+    const response = await invoke<CommandResponse<ThumbnailCacheSettings>>('get_thumbnail_cache_settings');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get thumbnail cache settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 保存缩略图缓存设置，返回淘汰后的缓存占用
+   */
+  static async setThumbnailCacheSettings(settings: ThumbnailCacheSettings): Promise<CacheStats> {
+    const response = await invoke<CommandResponse<CacheStats>>('set_thumbnail_cache_settings', { settings });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to set thumbnail cache settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取低内存模式设置
+   */
+  static async getLowMemorySettings(): Promise<LowMemorySettings> {
+    const response = await invoke<CommandResponse<LowMemorySettings>>('get_low_memory_settings');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get low memory settings');
+    }
+
+    lowMemoryMode = response.data.enabled;
+    return response.data;
+  }
+
+  /**
+   * 保存低内存模式设置，立即生效，返回淘汰后的缩略图缓存占用
+   */
+  static async setLowMemorySettings(settings: LowMemorySettings): Promise<CacheStats> {
+    const response = await invoke<CommandResponse<CacheStats>>('set_low_memory_settings', { settings });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to set low memory settings');
+    }
+
+    lowMemoryMode = settings.enabled;
+    return response.data;
+  }
+
+  /**
+   * 备份资料库到指定目录
+   */
+  static async backupLibrary(scope: BackupScope, destination: string): Promise<BackupReport> {
+    const response = await invoke<CommandResponse<BackupReport>>(
+      'backup_library',
+      { scope, destination }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Backup failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 从备份恢复资料库，校验未通过时返回 applied 为 false 的报告
+   */
+  static async restoreLibrary(backupPath: string, mode: RestoreMode): Promise<RestoreReport> {
+    const response = await invoke<CommandResponse<RestoreReport>>(
+      'restore_library',
+      { backupPath, mode }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Restore failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取目录快照列表，最新的在前
+   */
+  static async listCatalogSnapshots(): Promise<CatalogSnapshot[]> {
+    const response = await invoke<CommandResponse<CatalogSnapshot[]>>('list_catalog_snapshots');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to list catalog snapshots');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 立即保存目录快照
+   */
+  static async createCatalogSnapshot(): Promise<CatalogSnapshot> {
+    const response = await invoke<CommandResponse<CatalogSnapshot>>('create_catalog_snapshot');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to create catalog snapshot');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取目录快照设置
+   */
+  static async getCatalogSnapshotSettings(): Promise<CatalogSnapshotSettings> {
+    const response = await invoke<CommandResponse<CatalogSnapshotSettings>>('get_catalog_snapshot_settings');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get catalog snapshot settings');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 保存目录快照设置
+   */
+  static async setCatalogSnapshotSettings(settings: CatalogSnapshotSettings): Promise<void> {
+    const response = await invoke<CommandResponse<void>>(
+      'set_catalog_snapshot_settings',
+      { settings }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to save catalog snapshot settings');
+    }
+  }
+
+  /**
+   * 回滚目录到快照
+   *
+   * 先以 confirm = false 调用获取预览，用户确认后再以 confirm = true 执行
+   */
+  static async rollbackCatalog(snapshotId: string, confirm: boolean): Promise<CatalogRollbackReport> {
+    const response = await invoke<CommandResponse<CatalogRollbackReport>>(
+      'rollback_catalog',
+      { snapshotId, confirm }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to roll back catalog');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 链接库外的文件，只记录原始文件位置，不复制到存储区
+   */
+  static async linkFile(path: string, directoryId?: string): Promise<UploadFileResponse> {
+    const response = await invoke<CommandResponse<UploadFileResponse>>(
+      'link_file',
+      { path, directoryId }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Link file failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 扫描存储文件已丢失的文件，并将其标记为损坏
+   */
+  static async scanMissingFiles(): Promise<MissingFileScanReport> {
+    const response = await invoke<CommandResponse<MissingFileScanReport>>('scan_missing_files');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to scan missing files');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取标记为损坏的文件
+   */
+  static async getBrokenFiles(): Promise<FileListItem[]> {
+    const response = await invoke<CommandResponse<FileListItem[]>>('get_broken_files');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to load broken files');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 使用找到的本地文件重新关联损坏的文件
+   */
+  static async relinkFile(fileId: string, newPath: string): Promise<FileListItem> {
+    const command: RelinkFileCommand = {
+      file_id: fileId,
+      new_path: newPath,
+    };
+
+    const response = await invoke<CommandResponse<FileListItem>>('relink_file', { command });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to relink file');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 删除所有标记为损坏的文件记录，返回清除的数量
+   */
+  static async purgeBrokenFiles(): Promise<number> {
+    const response = await invoke<CommandResponse<number>>('purge_broken_files');
+
+    if (!response.success || response.data === undefined || response.data === null) {
+      throw new Error(response.error || 'Failed to purge broken files');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 检查链接文件的原始文件是否仍然存在
+   */
+  static async checkLinkedFiles(): Promise<MissingFileScanReport> {
+    const response = await invoke<CommandResponse<MissingFileScanReport>>('check_linked_files');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to check linked files');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取尚未处理或处理失败的文件
+   */
+  static async getUnprocessedFiles(): Promise<FileListItem[]> {
+    const response = await invoke<CommandResponse<FileListItem[]>>('get_unprocessed_files');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to load unprocessed files');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 重新处理尚未处理或处理失败的文件
+   */
+  static async processPendingFiles(): Promise<ProcessingReport> {
+    const response = await invoke<CommandResponse<ProcessingReport>>('process_pending_files');
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to process files');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 清除缩略图和派生数据后重新索引文件
+   */
+  static async reindexFiles(scope: ReindexScope): Promise<ProcessingReport> {
+    const response = await invoke<CommandResponse<ProcessingReport>>('reindex_files', { scope });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Reindex failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 将链接文件复制到存储区，转换为托管文件
+   */
+  static async convertToManaged(fileId: string): Promise<FileListItem> {
+    const response = await invoke<CommandResponse<FileListItem>>(
+      'convert_to_managed',
+      { fileId }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Convert to managed failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 添加监视文件夹
+   */
+  static async addWatchedFolder(request: AddWatchedFolderRequest): Promise<WatchedFolder> {
+    const response = await invoke<CommandResponse<WatchedFolder>>(
+      'add_watched_folder',
+      { request }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to add watched folder');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 移除监视文件夹
+   */
+  static async removeWatchedFolder(folderId: string): Promise<void> {
+    const response = await invoke<CommandResponse<void>>(
+      'remove_watched_folder',
+      { folderId }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to remove watched folder');
+    }
+  }
+
+  /**
+   * 获取监视文件夹列表
+   */
+  static async getWatchedFolders(): Promise<WatchedFolder[]> {
+    const response = await invoke<CommandResponse<WatchedFolder[]>>(
+      'get_watched_folders'
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get watched folders');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 立即扫描监视文件夹
+   */
+  static async scanWatchedFolders(): Promise<WatchedImportReport> {
+    const response = await invoke<CommandResponse<WatchedImportReport>>(
+      'scan_watched_folders'
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to scan watched folders');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 导入本地文件夹，dry_run 为 true 时只返回将要导入的文件、已存在的文件和预计占用的空间
+   */
+  static async importDirectory(request: ImportDirectoryRequest): Promise<DirectoryImportReport> {
+    const response = await invoke<CommandResponse<DirectoryImportReport>>(
+      'import_directory',
+      { request }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to import directory');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取资源管理器右键菜单的状态
+   */
+  static async getShellIntegration(): Promise<ShellIntegrationStatus> {
+    const response = await invoke<CommandResponse<ShellIntegrationStatus>>(
+      'get_shell_integration'
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get shell integration');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 开启或关闭资源管理器的“Send to Collaboard”右键菜单，仅支持 Windows
+   */
+  static async setContextMenuEnabled(enabled: boolean): Promise<ShellIntegrationStatus> {
+    const response = await invoke<CommandResponse<ShellIntegrationStatus>>(
+      'set_context_menu_enabled',
+      { enabled }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to update context menu');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 导出画板为 .cboard 文件，画板上使用的文件一并嵌入
+   */
+  static async exportBoard(request: ExportBoardRequest): Promise<BoardExportReport> {
+    const response = await invoke<CommandResponse<BoardExportReport>>(
+      'export_board',
+      { request }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to export board');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 打开 .cboard 文件，素材按内容哈希对应到资料库中的文件
+   */
+  static async openBoardFile(path: string): Promise<OpenedBoard> {
+    const response = await invoke<CommandResponse<OpenedBoard>>(
+      'open_board_file',
+      { path }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to open board file');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 把画板上的素材复制或移动到一个目录，便于整体交接
+   */
+  static async collectBoardAssets(request: CollectBoardAssetsRequest): Promise<CollectBoardAssetsReport> {
+    const response = await invoke<CommandResponse<CollectBoardAssetsReport>>(
+      'collect_board_assets',
+      { request }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to collect board assets');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 自动排列画板素材，返回新的位置和尺寸
+   */
+  static async autoLayoutBoard(request: AutoLayoutRequest): Promise<AutoLayoutResult> {
+    const response = await invoke<CommandResponse<AutoLayoutResult>>(
+      'auto_layout_board',
+      { request }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to auto layout board');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 在画板上创建框架或分组
+   */
+  static async createBoardFrame(request: BoardFrameRequest): Promise<BoardFrame> {
+    const response = await invoke<CommandResponse<BoardFrame>>(
+      'create_board_frame',
+      { request }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to create board frame');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 修改画板框架，整体替换名称、区域、上级框架和包含的元素
+   */
+  static async updateBoardFrame(frameId: string, request: BoardFrameRequest): Promise<BoardFrame> {
+    const response = await invoke<CommandResponse<BoardFrame>>(
+      'update_board_frame',
+      { frameId, request }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to update board frame');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取画板的所有框架
+   */
+  static async listBoardFrames(boardId: string): Promise<BoardFrame[]> {
+    const response = await invoke<CommandResponse<BoardFrame[]>>(
+      'list_board_frames',
+      { boardId }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to list board frames');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 移动框架及其子框架，返回需要按同样位移移动的画板元素
+   */
+  static async moveBoardFrame(frameId: string, dx: number, dy: number): Promise<BoardFrameMove> {
+    const response = await invoke<CommandResponse<BoardFrameMove>>(
+      'move_board_frame',
+      { frameId, dx, dy }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to move board frame');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 删除框架及其子框架，其中的元素保留在画板上；返回删除的框架 ID
+   */
+  static async deleteBoardFrame(frameId: string): Promise<string[]> {
+    const response = await invoke<CommandResponse<string[]>>(
+      'delete_board_frame',
+      { frameId }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to delete board frame');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 整体替换画板的框架，如保存从画板文件打开的框架；框架使用新 ID
+   */
+  static async replaceBoardFrames(boardId: string, frames: BoardFrame[]): Promise<BoardFrame[]> {
+    const response = await invoke<CommandResponse<BoardFrame[]>>(
+      'replace_board_frames',
+      { boardId, frames }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to replace board frames');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 创建播放列表
+   */
+  static async createPlaylist(request: PlaylistRequest): Promise<Playlist> {
+    const response = await invoke<CommandResponse<Playlist>>(
+      'create_playlist',
+      { request }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to create playlist');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 修改播放列表，整体替换名称、关联画板和文件顺序
+   */
+  static async updatePlaylist(playlistId: string, request: PlaylistRequest): Promise<Playlist> {
+    const response = await invoke<CommandResponse<Playlist>>(
+      'update_playlist',
+      { playlistId, request }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to update playlist');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取播放列表，指定画板时只返回该画板的讲解顺序
+   */
+  static async listPlaylists(boardId?: string): Promise<Playlist[]> {
+    const response = await invoke<CommandResponse<Playlist[]>>(
+      'list_playlists',
+      { boardId: boardId ?? null }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to list playlists');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取播放列表中的文件
+   */
+  static async getPlaylistFiles(playlistId: string): Promise<FileListItem[]> {
+    const response = await invoke<CommandResponse<FileListItem[]>>(
+      'get_playlist_files',
+      { playlistId }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get playlist files');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取放映条目，asset_path 已转换为可直接加载的 URL
+   */
+  static async getPlaylistItem(playlistId: string, index: number): Promise<PlaylistItem> {
+    const response = await invoke<CommandResponse<PlaylistItem>>(
+      'get_playlist_item',
+      { playlistId, index }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get playlist item');
+    }
+
+    return { ...response.data, asset_path: convertFileSrc(response.data.asset_path) };
+  }
+
+  /**
+   * 删除播放列表，其中的文件不受影响
+   */
+  static async deletePlaylist(playlistId: string): Promise<void> {
+    const response = await invoke<CommandResponse<void>>(
+      'delete_playlist',
+      { playlistId }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'Failed to delete playlist');
+    }
+  }
+
+  /**
+   * 按拍摄位置搜索文件
+   */
+  static async searchByLocation(bbox: GeoBoundingBox): Promise<FileListItem[]> {
+    const response = await invoke<CommandResponse<FileListItem[]>>(
+      'search_by_location',
+      { bbox }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Location search failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 查找相似图像
+   */
+  static async findSimilarImages(
+    fileId: string,
+    maxDistance?: number
+  ): Promise<SimilarImage[]> {
+    const response = await invoke<CommandResponse<SimilarImage[]>>(
+      'find_similar_images',
+      {
+        fileId,
+        maxDistance,
+      }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Similar image search failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 比较两张图像，返回差异热力图、像素差异数和 SSIM
+   */
+  static async compareImages(fileIdA: string, fileIdB: string): Promise<ImageComparison> {
+    const response = await invoke<CommandResponse<ImageComparison>>('compare_images', { fileIdA, fileIdB });
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Image comparison failed');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 获取存储统计信息
+   */
+  static async getStorageStats(): Promise<StorageStats> {
+    const response = await invoke<CommandResponse<StorageStats>>(
+      'get_storage_stats'
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get storage stats');
+    }
+
+    return response.data;
+  }
+
+  /**
+   * 验证文件类型
+   */
+  static async validateFileType(filename: string): Promise<boolean> {
+    const response = await invoke<CommandResponse<boolean>>(
+      'validate_file_type',
+      { filename }
+    );
+
+    if (!response.success) {
+      throw new Error(response.error || 'File type validation failed');
+    }
+
+    return response.data || false;
+  }
+
+  /**
+   * 读取文件内容
+   *
+   * 后端以原始字节返回；超过 MAX_INLINE_READ_SIZE 的文件抛出错误码为 file_size_exceeded 的错误，
+   * 请改用 getFileAssetUrl
+   */
+  static async readFileContent(fileId: string): Promise<Uint8Array> {
+    const command: ReadFileContentCommand = {
+      file_id: fileId,
+    };
+    
+    try {
+      const data = await invoke<ArrayBuffer>('read_file_content', { command });
+      return new Uint8Array(data);
+    } catch (error) {
+      const { code, message } = error as CommandError;
+      throw new FileManagerError(message, code, error);
+    }
+  }
+
+  /**
+   * 获取文件的 asset 协议地址，用于直接加载大文件
+   */
+  static async getFileAssetUrl(fileId: string): Promise<string> {
+    const response = await invoke<CommandResponse<string>>(
+      'get_file_asset_path',
+      { fileId }
+    );
+
+    if (!response.success || !response.data) {
+      throw new Error(response.error || 'Failed to get file path');
+    }
+
+    return convertFileSrc(response.data);
+  }
+
+  /**
+   * 生成文件预览 URL（如果支持）
+   */
+  static async generatePreviewUrl(file: FileListItem): Promise<string | null> {
+    if (!FileUtils.isImageFile(file.name)) {
+      return null;
+    }
+    
+    try {
+      // 大文件和低内存模式下直接通过 asset 协议流式加载
+      if (lowMemoryMode || file.file_size > MAX_INLINE_READ_SIZE) {
+        return await FileManagerService.getFileAssetUrl(file.id);
+      }
+
       // 读取文件内容
       const fileContent = await FileManagerService.readFileContent(file.id);
       
@@ -345,7 +3076,7 @@ export class FileUtils {
     const extension = filename.split('.').pop()?.toLowerCase() || '';
     
     // 图片类型
-    if (['jpg', 'jpeg', 'png', 'gif', 'bmp', 'webp', 'svg'].includes(extension)) {
+    if (['jpg', 'jpeg', 'png', 'gif', 'bmp', 'webp', 'heic', 'heif', 'avif', 'svg'].includes(extension)) {
       return {
         type: 'image',
         icon: '🖼️',
@@ -393,7 +3124,7 @@ export class FileUtils {
    */
   static isImageFile(filename: string): boolean {
     const extension = filename.split('.').pop()?.toLowerCase() || '';
-    return ['jpg', 'jpeg', 'png', 'gif', 'bmp', 'webp', 'svg'].includes(extension);
+    return ['jpg', 'jpeg', 'png', 'gif', 'bmp', 'webp', 'heic', 'heif', 'avif', 'svg'].includes(extension);
   }
 
   /**
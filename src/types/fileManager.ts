@@ -2,6 +2,9 @@
  * 文件管理系统类型定义
  * 
  * 定义前端与后端通信的数据结构
+ *
+ * 命令参数和返回值的类型与后端 Rust 类型对应：构建时 `yarn generate:types` 把后端类型生成到 `./generated`（不提交），
+ * `./generatedTypes.check.ts` 检查本文件与生成的类型一致，不一致时构建失败
  */
 
 // ============= 基础类型 =============
@@ -11,10 +14,230 @@
  */
 export interface CommandResponse<T> {
   success: boolean;
-  data?: T;
-  error?: string;
+  data?: T | null;
+  /** 按当前语言生成的错误信息 */
+  error?: string | null;
+  /** 稳定的错误码，如 file_not_found、empty_file_id */
+  error_code?: string | null;
+  /** 列表响应超出大小上限时只返回第一页，其余部分通过 get_listing_page 获取 */
+  continuation?: ListingContinuation | null;
+}
+
+/** 直接返回原始字节的命令（如 read_file_content）出错时的错误 */
+export interface CommandError {
+  /** 稳定的错误码，如 file_size_exceeded、empty_file_id */
+  code: string;
+  /** 按当前语言生成的错误信息 */
+  message: string;
+}
+
+/** 被截断的列表响应的续传信息 */
+export interface ListingContinuation {
+  listing_id: string;
+  /** 下一页在完整列表中的起始位置 */
+  next_offset: number;
+  total: number;
+  /** 按当前语言生成的警告信息 */
+  warning: string;
+}
+
+/** 分页获取的一页列表，continuation 为空时表示已是最后一页 */
+export interface ListingPage<T> {
+  items: T[];
+  continuation?: ListingContinuation | null;
+}
+
+/** 游标分页的一页结果，next_cursor 为空时表示已是最后一页 */
+export interface Page<T> {
+  items: T[];
+  next_cursor?: string | null;
+}
+
+/** 单个列表响应的大小上限（字节），超出后分页返回 */
+export interface ResponseLimitSettings {
+  max_response_bytes: number;
+}
+
+/** 单个启动阶段耗时 */
+export interface StartupPhase {
+  name: string;
+  duration_ms: number;
+}
+
+/** backend://ready 事件负载：各启动阶段耗时 */
+export interface StartupReport {
+  phases: StartupPhase[];
+  total_ms: number;
+}
+
+/** 启动时的迁移和恢复任务 */
+export type StartupTaskKind = 'schema_migration' | 'blob_path_migration' | 'journal_recovery' | 'upload_recovery';
+
+export type StartupTaskState = 'pending' | 'running' | 'completed' | 'skipped' | 'failed';
+
+/** backend://startup-task 事件负载：启动任务的状态和进度，total 为空时无法预先统计总数 */
+export interface StartupTask {
+  kind: StartupTaskKind;
+  state: StartupTaskState;
+  processed: number;
+  total: number | null;
+  started_at: string | null;
+  finished_at: string | null;
+  error: string | null;
+}
+
+/** 任务队列中的任务，记录重新执行所需的参数；恢复后导出的 file_ids 只包含尚未导出的文件 */
+export type QueuedJob =
+  | { type: 'directory_import'; request: ImportDirectoryRequest }
+  | { type: 'export'; request: ExportRequest }
+  | { type: 'directory_delete'; directory_id: string; path: string; started_at: string | null }
+  | { type: 'pack_maintenance' };
+
+/** 任务检查点：已处理的条目数，以及中断时可能不完整的文件 */
+export interface JobCheckpoint {
+  processed: number;
+  writing: string | null;
+}
+
+/** 被中断任务的处理方式：resumed 继续执行，rolled_back 撤销不完整的部分，discarded 已无需继续 */
+export type RecoveryAction = 'resumed' | 'rolled_back' | 'discarded' | 'failed';
+
+/** 启动时恢复或回滚的、上次运行被中断的任务 */
+export interface RecoveredJob {
+  job_id: string;
+  job: QueuedJob;
+  /** 中断前的最后一个检查点 */
+  checkpoint: JobCheckpoint;
+  interrupted_at: string;
+  action: RecoveryAction;
+  error?: string | null;
+}
+
+/** 只读模式的原因：requested 为手动进入，in_use 为其他用户正在写入；can_take_over 时可调用 takeOverLibrary 接管 */
+export type ReadOnlyReason =
+  | { kind: 'requested' }
+  | { kind: 'in_use'; holder: string | null; heartbeat_at: string | null; can_take_over: boolean };
+
+/** 资料库的同步状态：writable 可写，read_only 其他进程正在写入或手动只读，offline 网络共享断开 */
+export type SyncStatus = 'writable' | 'read_only' | 'offline';
+
+/** library-summary 事件负载：窗口标题、托盘提示使用的资料库概要 */
+export interface LibrarySummary {
+  workspace_name: string;
+  storage_path: string;
+  /** 文件总数，不含目录 */
+  total_items: number;
+  /** 等待索引的文件、延迟删除的文件和运行中的目录删除任务 */
+  pending_jobs: number;
+  sync_status: SyncStatus;
+}
+
+/** 资料库所在的网络共享无法访问时的离线信息 */
+export interface LibraryOffline {
+  since: string;
+  /** 无法访问的目录 */
+  path: string;
+  error: string;
+}
+
+/** 后端服务状态，initializing 期间其他命令返回 initializing 错误码；只读模式下修改资料库的命令返回 read_only 错误码，离线时返回 library_offline 错误码 */
+export type BackendStatus =
+  | { state: 'initializing' }
+  | { state: 'locked' }
+  | { state: 'ready'; read_only?: ReadOnlyReason | null; offline?: LibraryOffline | null }
+  | { state: 'failed'; message: string };
+
+/** 匿名使用统计设置，默认关闭 */
+/** 文件类型限制方式：allowlist 只允许支持列表，blocklist 禁止黑名单，allow_all_except_executables 禁止可执行文件 */
+export type FileTypePolicyMode = 'allowlist' | 'blocklist' | 'allow_all_except_executables';
+
+export interface FileTypePolicy {
+  mode: FileTypePolicyMode;
+  /** 黑名单模式下禁止的扩展名，不含点 */
+  blocked_types?: string[];
+}
+
+/** 新记录的 ID 格式：uuid 为默认，ulid 按时间排序，nano_id 较短，适合分享链接 */
+export type IdStrategy = 'uuid' | 'ulid' | 'nano_id';
+
+/** 大小上限使用的文件类别 */
+export type FileCategory = 'image' | 'video' | 'audio' | 'document' | 'archive';
+
+/** 按文件类别的大小上限（字节），未列出的类别使用全局上限 */
+export interface SizeLimits {
+  limits: Partial<Record<FileCategory, number>>;
+}
+
+/** 隔离区设置：开启后被拒绝的上传放入隔离区，上传返回 quarantined 错误码 */
+export interface QuarantineSettings {
+  enabled: boolean;
+}
+
+/** 文件被隔离的原因 */
+export type QuarantineReason =
+  | { type: 'unsupported_file_type'; file_type: string }
+  | { type: 'file_size_exceeded'; size: number; max_size: number; category?: FileCategory | null };
+
+export interface QuarantinedFile {
+  id: string;
+  original_name: string;
+  /** 上传时指定的目标目录 */
+  directory_id?: string | null;
+  file_size: number;
+  reason: QuarantineReason;
+  created_at: string;
 }
 
+/** 回收站设置：开启后删除的文件移入回收站，后台按保留天数和大小上限清理 */
+export interface TrashSettings {
+  enabled: boolean;
+  /** 删除超过该天数的文件自动清理，为空时不按时间清理 */
+  retention_days: number | null;
+  /** 回收站总大小上限（字节），超过时从最早删除的文件开始清理，为空时不限制 */
+  max_size: number | null;
+}
+
+export interface TrashedFile {
+  id: string;
+  /** 删除前的文件 ID，恢复后的文件使用新 ID */
+  file_id: string;
+  original_name: string;
+  /** 删除前所在的目录 */
+  directory_id: string;
+  file_size: number;
+  mime_type: string;
+  tags: string[];
+  deleted_at: string;
+}
+
+export interface TrashPurgeReport {
+  purged: number;
+  freed_bytes: number;
+}
+
+export interface TelemetrySettings {
+  enabled: boolean;
+  /** 接收统计数据的 https 地址，未设置时不发送 */
+  endpoint?: string | null;
+}
+
+/** 一批统计数据：只包含功能名和错误码的计数 */
+export interface TelemetryBatch {
+  app_version: string;
+  os: string;
+  features: Partial<Record<string, number>>;
+  errors: Partial<Record<string, number>>;
+}
+
+export interface TelemetryPreview {
+  settings: TelemetrySettings;
+  /** 下次将要发送的内容 */
+  batch: TelemetryBatch;
+}
+
+/** 后端错误信息语言 */
+export type Locale = 'en' | 'zh-CN';
+
 /**
  * 文件上传请求
  */
@@ -35,15 +258,25 @@ export interface UploadFileResponse {
   file_size: number;
   mime_type: string;
   directory_id: string;
+  version: number;
+  /** 因冲突策略为 skip 而未上传，返回的是已有文件 */
+  skipped: boolean;
   created_at: string;
 }
 
+/**
+ * 批量上传中单个文件的结果，index 为文件在请求中的位置
+ */
+export type UploadItemResult =
+  | { status: 'uploaded'; index: number; original_name: string; response: UploadFileResponse }
+  | { status: 'failed'; index: number; original_name: string; error: string; error_code: string };
+
 /**
  * 创建目录请求
  */
 export interface CreateDirectoryRequest {
   name: string;
-  parent_id?: string;
+  parent_id?: string | null;
   [key: string]: unknown;
 }
 
@@ -53,7 +286,7 @@ export interface CreateDirectoryRequest {
 export interface CreateDirectoryResponse {
   directory_id: string;
   name: string;
-  parent_id?: string;
+  parent_id?: string | null;
   path: string;
   created_at: string;
 }
@@ -63,6 +296,8 @@ export interface CreateDirectoryResponse {
  */
 export interface DeleteFileCommand {
   file_id: string;
+  /** 最后看到的文件 updated_at，文件已被其他窗口修改时返回 conflict 错误 */
+  expected_updated_at: string;
   [key: string]: unknown;
 }
 
@@ -71,14 +306,93 @@ export interface DeleteFileCommand {
  */
 export interface DeleteDirectoryCommand {
   directory_id: string;
+  /** 目录非空时是否仍然删除 */
+  force?: boolean;
+  /** 最后看到的目录 updated_at，目录已被其他窗口修改时返回 conflict 错误 */
+  expected_updated_at: string;
+  /** 只列出要删除的文件和目录，不删除 */
+  dry_run?: boolean;
   [key: string]: unknown;
 }
 
+/**
+ * 移动目录请求
+ */
+export interface MoveDirectoryCommand {
+  directory_id: string;
+  /** 新的父目录，为空时移动到根目录 */
+  new_parent_id?: string | null;
+  /** 最后看到的目录 updated_at，目录已被其他窗口修改时返回 conflict 错误 */
+  expected_updated_at: string;
+  [key: string]: unknown;
+}
+
+/**
+ * 移动目录响应
+ */
+export interface MoveDirectoryResponse {
+  directory_id: string;
+  parent_id?: string | null;
+  old_path: string;
+  new_path: string;
+  /** 移动后的版本标记 */
+  updated_at: string;
+}
+
+/**
+ * 目录内容统计，用于删除前确认
+ */
+export interface DirectoryItemCounts {
+  directory_id: string;
+  path: string;
+  /** 子树中的文件总数 */
+  file_count: number;
+  /** 子树中的子目录总数（不含目录自身） */
+  directory_count: number;
+}
+
+/** 试运行报告中的一项修改，路径为资料库中的显示路径 */
+export type PlannedChange =
+  | { type: 'delete_file'; file_id: string; path: string }
+  | { type: 'delete_directory'; directory_id: string; path: string }
+  /** 目标目录中有同名文件时 new_path 中的文件名已添加序号 */
+  | { type: 'move_file'; file_id: string; path: string; new_path: string }
+  | { type: 'rename_file'; file_id: string; path: string; new_path: string }
+  /** 只列出实际增减的标签 */
+  | { type: 'tag_file'; file_id: string; path: string; added: string[]; removed: string[] }
+  | { type: 'link_file'; file_id: string; path: string; target_file_id: string };
+
+/** 后台目录删除任务句柄 */
+export interface DirectoryDeleteJob {
+  /** 试运行时不创建任务，为空 */
+  job_id: string;
+  directory_id: string;
+  path: string;
+  /** 要删除的项数：子树中的文件、子目录和目录自身 */
+  total: number;
+  dry_run: boolean;
+  /** 试运行时列出要删除的文件和目录 */
+  changes: PlannedChange[];
+}
+
+export type DirectoryDeleteStatus = 'running' | 'finished' | 'cancelled' | 'failed';
+
+/** directory-delete-progress 事件负载 */
+export interface DirectoryDeleteProgress {
+  job_id: string;
+  directory_id: string;
+  removed: number;
+  total: number;
+  status: DirectoryDeleteStatus;
+  error?: string | null;
+}
+
 /**
  * 获取目录文件请求
  */
 export interface GetDirectoryFilesCommand {
   directory_id: string;
+  archived?: boolean | null; // 按是否归档筛选，省略时返回全部
   [key: string]: unknown;
 }
 
@@ -106,16 +420,73 @@ export interface ReadFileContentCommand {
 export interface DirectoryTreeNode {
   id: string;
   name: string;
-  parent_id?: string;
+  parent_id?: string | null;
   path: string;
   children: DirectoryTreeNode[];
   file_count: number;
+  /** 目录及其所有子目录中文件的总字节数 */
+  total_size: number;
   created_at: string;
+  /** 版本标记，移动和删除目录时需要带上 */
+  updated_at: string;
 }
 
 /**
  * 文件列表项
  */
+/** 文件状态：broken 表示存储文件已丢失 */
+export type FileStatus = 'ok' | 'broken';
+
+/** 索引处理状态：pending 表示内容哈希等派生数据尚未生成 */
+export type ProcessingStatus = 'pending' | 'indexed' | 'failed';
+
+/** 目录默认标签：上传到该目录的文件自动添加，inherit 为 true 时同时应用到所有子目录 */
+export interface DirectoryDefaults {
+  directory_id: string;
+  tags: string[];
+  inherit: boolean;
+}
+
+/** 批量标签更新请求，在一个事务中为多个文件添加和移除标签 */
+export interface BulkTagRequest {
+  file_ids: string[];
+  add_tags?: string[];
+  remove_tags?: string[];
+}
+
+/** 批量标签更新结果 */
+export interface BulkTagResponse {
+  /** 涉及的文件数 */
+  file_count: number;
+  /** 实际新增或移除的标签关联数 */
+  changed: number;
+}
+
+export interface SearchHistoryEntry {
+  query: string;
+  use_count: number;
+  last_used_at: string;
+}
+
+export type SuggestionSource = 'history' | 'tag' | 'file_name';
+
+export interface SearchSuggestion {
+  text: string;
+  source: SuggestionSource;
+  count: number;
+}
+
+export type QuickFindKind = 'directory' | 'working_set' | 'tag' | 'file';
+
+/** 快速切换结果；标签的 id 为标签名，detail 为所在路径（目录、文件）或文件数（标签、工作集） */
+export interface QuickFindResult {
+  kind: QuickFindKind;
+  id: string;
+  label: string;
+  detail?: string | null;
+  score: number;
+}
+
 export interface FileListItem {
   id: string;
   name: string;
@@ -124,11 +495,841 @@ export interface FileListItem {
   file_size: number;
   size: number; // 添加size属性用于排序和过滤
   mime_type: string;
+  status?: FileStatus;
+  latitude?: number | null; // EXIF 拍摄位置
+  longitude?: number | null;
+  source_url?: string | null; // 从 URL 导入的来源地址
+  rating?: number | null; // 评分 0-5
+  notes?: string | null;
+  title?: string | null; // 标题，写回图像元数据时使用
+  linked?: boolean; // 链接文件：引用库外的原始文件，不占用存储区
+  archived?: boolean; // 存储文件已移到归档位置，访问内容时自动移回
+  processing_status?: ProcessingStatus;
+  processing_error?: string | null; // 处理失败的原因
   created_at: string;
   updated_at: string;
   modified_at: string; // 添加modified_at属性用于排序
 }
 
+/** 写入图像元数据的字段，省略表示保持不变，空字符串表示清除；keywords 替换全部标签 */
+export interface ImageMetadataFields {
+  title?: string | null;
+  description?: string | null;
+  keywords?: string[] | null;
+}
+
+/** embedded 为 false 时格式不支持内嵌或为链接文件，只更新了数据库 */
+export interface ImageMetadataResult {
+  file: FileListItem;
+  embedded: boolean;
+}
+
+/** 跨目录收集文件的临时工作集，expires_at 在每次修改成员后按 ttl_minutes 重新计算 */
+export interface WorkingSet {
+  id: string;
+  name?: string | null;
+  file_count: number;
+  ttl_minutes: number;
+  expires_at: string;
+  created_at: string;
+  updated_at: string;
+}
+
+/** 目录模板中的文件夹，同一层级不能重名 */
+export interface TemplateFolder {
+  name: string;
+  children?: TemplateFolder[];
+}
+
+/** 预定义文件夹结构的目录模板 */
+export interface DirectoryTemplate {
+  id: string;
+  name: string;
+  folders: TemplateFolder[];
+  created_at: string;
+  updated_at: string;
+}
+
+/** 按模板创建目录的结果，folders_created 为按模板创建的子目录数 */
+export interface TemplateApplication {
+  directory_id: string;
+  path: string;
+  folders_created: number;
+}
+
+/** 文件使用类型：open 为打开，placement 为放置到画板上 */
+export type UsageKind = 'open' | 'placement';
+
+/** 推荐素材的上下文，exclude_file_ids 传入已在画板上的文件 */
+export interface SuggestionContext {
+  board_id?: string | null;
+  directory_id?: string | null;
+  exclude_file_ids?: string[];
+  limit?: number | null;
+}
+
+export type SuggestionReason = 'used_on_board' | 'in_directory' | 'shared_tags' | 'frequently_used';
+
+/** 一条推荐素材，score 越高越相关 */
+export interface SuggestedFile {
+  file: FileListItem;
+  score: number;
+  reasons: SuggestionReason[];
+}
+
+/** 时间线汇总粒度，week 从周一开始 */
+export type TimelineBucket = 'day' | 'week';
+
+/** 时间线日期范围（YYYY-MM-DD，含首尾） */
+export interface TimelineRange {
+  start: string;
+  end: string;
+}
+
+export interface ActivityPoint {
+  start: string;
+  files_added: number;
+  bytes_added: number;
+}
+
+export interface UsageGroup {
+  key: string;
+  file_count: number;
+  total_size: number;
+}
+
+/** 某一天的库统计快照 */
+export interface StatsSnapshot {
+  date: string;
+  total_files: number;
+  total_bytes: number;
+  by_mime_type: UsageGroup[];
+}
+
+/** 存储占用分布，by_age 的键为 last_7_days / last_30_days / last_year / older */
+export interface StorageBreakdown {
+  by_mime_type: UsageGroup[];
+  by_top_level_directory: UsageGroup[];
+  by_age: UsageGroup[];
+}
+
+/** 截图方式：full_screen 主显示器全屏，active_window 当前活动窗口 */
+export type CaptureMode = 'full_screen' | 'active_window';
+
+/** 后台任务完成通知设置，默认开启 */
+export interface NotificationSettings {
+  enabled: boolean;
+}
+
+/** 全局快捷键快速截图设置，截图保存到 Inbox 目录 */
+export interface QuickCaptureSettings {
+  enabled: boolean;
+  shortcut: string;
+  mode?: CaptureMode;
+  tags?: string[];
+}
+
+/** 整理收件箱文件的操作 */
+export type TriageAction =
+  | { type: 'move'; directory_id: string }
+  | { type: 'tag'; add_tags?: string[]; remove_tags?: string[] }
+  | { type: 'delete' };
+
+/** quick-capture 事件负载 */
+export interface QuickCaptureOutcome {
+  file?: UploadFileResponse | null;
+  error?: string | null;
+}
+
+/** 完整性抽检设置，默认关闭 */
+export interface IntegritySettings {
+  enabled: boolean;
+  sample_size: number;
+}
+
+/** 抽检结果，integrity-alert 事件负载 */
+export interface IntegrityReport {
+  checked: number;
+  recorded: number;
+  missing: number;
+  mismatched: { file_id: string; file_path: string; expected: string; actual: string }[];
+}
+
+/** 被其他进程占用、等待后台任务删除的存储文件 */
+export interface DeferredDelete {
+  file_path: string;
+  /** 原文件 ID，文件记录已删除 */
+  file_id: string;
+  attempts: number;
+  last_error: string;
+  queued_at: string;
+  next_attempt_at: string;
+}
+
+/** 处理延迟删除队列的结果，deferred-delete 事件负载 */
+export interface DeferredDeleteReport {
+  deleted: DeferredDelete[];
+  /** 存储路径重新被使用、不再删除的条目 */
+  kept: DeferredDelete[];
+  /** 多次失败后放弃的条目，存储文件保留在磁盘上 */
+  abandoned: DeferredDelete[];
+  pending: number;
+}
+
+/** 内容相同的一组文件，按上传时间排列 */
+export interface DuplicateGroup {
+  content_hash: string;
+  files: FileListItem[];
+}
+
+/** 重复副本处理方式：delete 删除并合并标签，hardlink 替换为硬链接 */
+export type DuplicateAction = 'delete' | 'hardlink';
+
+export interface DuplicatePlan {
+  action?: DuplicateAction;
+  groups: { keep_file_id: string; duplicate_file_ids: string[] }[];
+}
+
+export interface ResolveDuplicatesReport {
+  /** 试运行时为空 */
+  undo_id: string;
+  removed: number;
+  linked: number;
+  bytes_reclaimed: number;
+  dry_run: boolean;
+  /** 试运行时列出删除或链接的副本，以及并入保留文件的标签 */
+  changes: PlannedChange[];
+}
+
+/** 批量操作中的一项，按顺序在同一事务中执行 */
+export type BatchOperation =
+  | { type: 'move'; file_id: string; directory_id: string }
+  | { type: 'rename'; file_id: string; new_name: string }
+  | { type: 'tag'; file_id: string; add_tags?: string[]; remove_tags?: string[] }
+  | { type: 'delete'; file_id: string };
+
+export interface BatchReport {
+  /** 试运行时为空 */
+  undo_id: string;
+  moved: number;
+  renamed: number;
+  tagged: number;
+  deleted: number;
+  dry_run: boolean;
+  /** 试运行时列出的各项修改 */
+  changes: PlannedChange[];
+}
+
+/** 归档设置，location 为存储根目录之外的绝对路径 */
+export interface ArchiveSettings {
+  location?: string | null;
+}
+
+export interface ArchiveReport {
+  moved: number;
+  skipped: string[];
+  failed: string[];
+}
+
+/** 打包存储设置，max_file_size 为打包的文件大小上限（字节） */
+export interface PackSettings {
+  enabled: boolean;
+  max_file_size: number;
+}
+
+export interface PackStats {
+  settings: PackSettings;
+  packs: number;
+  packed_files: number;
+  pack_bytes: number;
+  live_bytes: number; // 仍被文件引用的内容大小，其余为待压缩的无用数据
+}
+
+export interface PackReport {
+  packed: number;
+  compacted: number;
+  reclaimed_bytes: number;
+}
+
+/** 外部应用，program 可以是可执行文件路径或系统能解析的应用名 */
+export interface ExternalApp {
+  id: string;
+  name: string;
+  program: string;
+}
+
+/** 外部应用设置，键为 MIME 类型（如 image/png 或 image/*），应用按优先顺序排列 */
+export interface OpenWithSettings {
+  associations: Partial<Record<string, ExternalApp[]>>;
+}
+
+/** 外部编辑会话，has_changes 表示副本在上次导入后被保存过 */
+export interface ExternalEditSession {
+  id: string;
+  file_id: string;
+  temp_path: string;
+  base_version: number;
+  has_changes: boolean;
+  started_at: string;
+}
+
+/** 导出的目录结构：flatten 平铺，keep_structure 保留库中的目录结构 */
+export type ExportLayout = 'flatten' | 'keep_structure';
+
+/** 附属元数据文件格式，记录标签、评分和备注 */
+export type SidecarFormat = 'xmp' | 'json';
+
+/** 元数据导出结果 */
+export interface MetadataExportReport {
+  path: string;
+  /** 写入导出文件的文件数，没有元数据的文件不导出 */
+  exported: number;
+}
+
+/** 元数据导入结果 */
+export interface MetadataImportReport {
+  total: number;
+  matched: number;
+  /** 更新的文件数，按哈希匹配到多个副本时每个都计入 */
+  updated_files: number;
+  /** 未匹配条目在来源库中的路径 */
+  unmatched: string[];
+}
+
+/** 导出目标文件已存在时的处理方式 */
+export type ExportCollisionPolicy = 'rename' | 'overwrite' | 'skip';
+
+export interface ExportRequest {
+  file_ids: string[];
+  destination_dir: string;
+  use_original_names?: boolean;
+  layout?: ExportLayout;
+  collision_policy?: ExportCollisionPolicy;
+  sidecar?: SidecarFormat | null;
+  /** 叠加在导出图片上的水印，设置后无法解码的文件导出失败 */
+  watermark?: Watermark | null;
+  /** 导出完成后写入目标目录的校验清单格式 */
+  manifest?: ManifestFormat | null;
+}
+
+/** export-progress 事件负载 */
+export interface ExportProgress {
+  completed: number;
+  total: number;
+  bytes_copied: number;
+  file_id: string;
+}
+
+export interface ExportReport {
+  exported: string[];
+  skipped: string[];
+  failed: { file_id: string; error: string }[];
+  bytes_copied: number;
+  /** 写入的校验清单路径 */
+  manifest_path?: string | null;
+}
+
+/** 校验清单格式：sfv 使用 CRC32，sha256sum 和 json 使用 SHA-256 */
+export type ManifestFormat = 'sfv' | 'sha256sum' | 'json';
+
+export interface ManifestEntry {
+  /** 相对于交付目录的路径 */
+  name: string;
+  size: number;
+  checksum: string;
+}
+
+export interface ChecksumManifest {
+  format: ManifestFormat;
+  /** 建议的清单文件名 */
+  file_name: string;
+  content: string;
+  entries: ManifestEntry[];
+}
+
+/** 水印内容：文字只支持 ASCII，图片为绝对路径 */
+export type WatermarkContent =
+  | { kind: 'text'; text: string }
+  | { kind: 'image'; path: string };
+
+export type WatermarkPosition = 'top_left' | 'top_right' | 'bottom_left' | 'bottom_right' | 'center' | 'tile';
+
+/** 导出时叠加的水印 */
+export interface Watermark {
+  content: WatermarkContent;
+  position?: WatermarkPosition;
+  /** 不透明度（0-1），默认 0.5 */
+  opacity?: number;
+  /** 水印宽度占图片宽度的比例（0-1），默认 0.25 */
+  scale?: number;
+}
+
+/** 图片导出预设，保存时 id 为空的预设分配新 ID */
+export interface ExportPreset {
+  id: string;
+  name: string;
+  /** 导出图片的最长边（16-16384 像素） */
+  max_edge: number;
+  format: PreviewFormat;
+  /** JPEG 质量（1-100） */
+  quality: number;
+  /** 追加在文件名后的后缀，如 _web */
+  suffix: string;
+  /** 比最长边小的图片是否放大 */
+  upscale: boolean;
+}
+
+/** 存储布局：date_based 按日期目录，hash_sharded 按文件名哈希分片 */
+export type StorageLayout = 'date_based' | 'hash_sharded';
+
+export interface StorageLayoutSettings {
+  layout: StorageLayout;
+  /** 切换布局后已有文件尚未迁移完成 */
+  migration_pending: boolean;
+}
+
+/** 缩略图编码格式，webp 为无损压缩 */
+export type PreviewFormat = 'png' | 'jpeg' | 'webp';
+
+/** 缩略图色彩空间：srgb 不嵌入配置文件，source 嵌入原图的 ICC 配置文件 */
+export type PreviewColorSpace = 'srgb' | 'source';
+
+/** 缩略图生成设置 */
+export interface PreviewSettings {
+  format: PreviewFormat;
+  /** JPEG 质量（1-100） */
+  quality: number;
+  /** 大尺寸缩略图的最长边（128-2048） */
+  max_dimension: number;
+  color_space: PreviewColorSpace;
+}
+
+/** 缩略图尺寸档位，最长边分别为 128、256、512 像素 */
+export type ThumbnailSize = 'small' | 'medium' | 'large';
+
+/** 前端当前的浏览位置，用于预取相邻文件的缩略图 */
+export interface PrefetchContext {
+  directory_id: string;
+  /** 当前文件在目录中按原始文件名排序后的位置 */
+  index: number;
+  /** 前后各预取的文件数 */
+  count?: number | null;
+}
+
+/** 缩略图响应 */
+export interface ThumbnailResponse {
+  file_id: string;
+  /** 请求的尺寸 */
+  requested_size: ThumbnailSize;
+  /** 实际返回的尺寸 */
+  size: ThumbnailSize;
+  width: number;
+  height: number;
+  /** 缩略图数据的 MIME 类型，由预览设置的编码格式决定 */
+  mime_type: string;
+  /** 编码后的缩略图数据 */
+  data: number[];
+  /** 请求的尺寸是否仍在后台生成中（生成后可再次请求获取） */
+  pending: boolean;
+}
+
+/** 缩略图缓存设置，超出上限后淘汰最久未用的缩略图 */
+export interface ThumbnailCacheSettings {
+  /** 缓存总大小上限（字节），不小于 64 MB */
+  max_bytes: number;
+}
+
+/** 低内存模式设置，开启时限制缓存、停止预取、预览改为流式加载并减少并行线程 */
+export interface LowMemorySettings {
+  enabled: boolean;
+}
+
+/** 缩略图缓存占用 */
+export interface CacheStats {
+  total_bytes: number;
+  file_count: number;
+  max_bytes: number;
+}
+
+/** storage-migration-progress 事件负载 */
+export interface LayoutMigrationProgress {
+  layout: StorageLayout;
+  moved: number;
+  failed: number;
+  remaining: number;
+  finished: boolean;
+}
+
+/** 备份范围：full 复制所有文件，incremental 只复制上次备份后变化的文件 */
+export type BackupScope = 'full' | 'incremental';
+
+export interface BackupReport {
+  backup_dir: string;
+  /** 实际执行的范围，没有基准备份时增量备份按完整备份执行 */
+  scope: BackupScope;
+  files_copied: number;
+  files_reused: number;
+  bytes_copied: number;
+  failed: { file_id: string; error: string }[];
+}
+
+/** 恢复方式：full 替换当前库，merge 只添加当前库中没有的目录和文件 */
+export type RestoreMode = 'full' | 'merge';
+
+export interface RestoreReport {
+  mode: RestoreMode;
+  verified: number;
+  missing: string[];
+  corrupt: string[];
+  database_ok: boolean;
+  /** 校验发现问题时为 false，当前库未做修改 */
+  applied: boolean;
+  files_restored: number;
+  files_skipped: number;
+}
+
+/** 目录快照设置 */
+export interface CatalogSnapshotSettings {
+  enabled: boolean;
+  /** 两次定时快照之间的间隔（小时，1-168） */
+  interval_hours: number;
+  /** 保留的快照数（1-100） */
+  retain: number;
+}
+
+/** 目录数据库快照 */
+export interface CatalogSnapshot {
+  id: string;
+  created_at: string;
+  size: number;
+}
+
+/** 回滚目录的结果；未确认时 applied 为 false，只包含预览信息 */
+export interface CatalogRollbackReport {
+  snapshot: CatalogSnapshot;
+  applied: boolean;
+  current_files: number;
+  snapshot_files: number;
+  /** 回滚前为当前状态保存的快照，可用于撤回回滚 */
+  safety_snapshot?: CatalogSnapshot | null;
+  /** 快照之后已删除、缺少存储文件的文件 ID，这些文件已标记为损坏 */
+  missing_files: string[];
+}
+
+/** 缺失文件扫描或链接文件检查结果 */
+export interface MissingFileScanReport {
+  scanned: number;
+  /** 新标记为损坏的文件数 */
+  broken: number;
+  /** 文件重新出现、恢复为正常的文件数 */
+  restored: number;
+}
+
+/** 重新关联损坏文件的请求 */
+export interface RelinkFileCommand {
+  file_id: string;
+  /** 找到的文件所在的本地路径 */
+  new_path: string;
+  [key: string]: unknown;
+}
+
+/** 重新处理文件的结果 */
+export interface ProcessingReport {
+  indexed: number;
+  /** 仍然处理失败的文件 ID */
+  failed: string[];
+  /** 存储文件已丢失、跳过的文件数 */
+  skipped: number;
+}
+
+/** 重新索引范围：directory 包含所有子目录 */
+export type ReindexScope =
+  | { type: 'files'; file_ids: string[] }
+  | { type: 'directory'; directory_id: string }
+  | { type: 'all' };
+
+/**
+ * 监视文件夹导入方式：copy 保留源文件，move 导入后删除源文件，
+ * cloud_sync 用于 OneDrive、Dropbox 等同步文件夹：保留源文件，占位文件先下载，内容重复的文件不导入
+ */
+export type WatchedImportMode = 'copy' | 'move' | 'cloud_sync';
+
+export interface WatchedFolder {
+  id: string;
+  path: string;
+  /** 导入的目标目录，为空时导入到 Inbox 目录 */
+  target_directory_id?: string | null;
+  default_tags: string[];
+  import_mode: WatchedImportMode;
+  created_at: string;
+}
+
+export interface AddWatchedFolderRequest {
+  path: string;
+  target_directory_id?: string | null;
+  default_tags?: string[];
+  import_mode?: WatchedImportMode;
+}
+
+export interface WatchedImportReport {
+  imported: number;
+  failed: number;
+  /** 内容与已有文件相同而未导入的文件数 */
+  duplicates: number;
+  /** 等待下载完成的云端占位文件数 */
+  hydrating: number;
+}
+
+/** 资源管理器集成状态，supported 仅在 Windows 上为 true */
+export interface ShellIntegrationStatus {
+  supported: boolean;
+  context_menu_enabled: boolean;
+}
+
+/** 从右键菜单或深度链接发送的文件导入结果，failed 为导入失败的路径 */
+export interface SendToReport {
+  imported: string[];
+  failed: string[];
+}
+
+/** 导入本地文件夹的请求，dry_run 为 true 时只返回导入计划 */
+export interface ImportDirectoryRequest {
+  path: string;
+  /** 目标目录，未指定时导入收件箱 */
+  directory_id?: string | null;
+  recursive?: boolean;
+  dry_run?: boolean;
+}
+
+/** 内容已存在的文件：file_id 为资料库中相同的文件，duplicate_of 为文件夹中会被导入的相同文件 */
+export interface ExistingImport {
+  path: string;
+  size: number;
+  file_id?: string | null;
+  duplicate_of?: string | null;
+}
+
+export interface DirectoryImportReport {
+  dry_run: boolean;
+  to_import: { path: string; size: number }[];
+  existing: ExistingImport[];
+  rejected: { path: string; reason: string }[];
+  /** 预计新占用的空间（字节） */
+  bytes_to_import: number;
+  bytes_existing: number;
+  /** 导入的文件 ID，试运行时为空 */
+  imported: string[];
+  failed: { path: string; reason: string }[];
+}
+
+/** .cboard 画板文件中的素材，画板内容以 content_hash 引用素材 */
+export interface BoardAsset {
+  content_hash: string;
+  name: string;
+  size: number;
+}
+
+export interface ExportBoardRequest {
+  name: string;
+  /** 前端定义的画板内容，后端原样保存 */
+  board: unknown;
+  /** 画板 ID，指定时一并导出该画板的框架和分组 */
+  board_id?: string | null;
+  file_ids: string[];
+  /** 绝对路径，没有扩展名时补上 .cboard */
+  destination: string;
+}
+
+export interface BoardExportReport {
+  path: string;
+  assets: BoardAsset[];
+  size: number;
+}
+
+/** 素材对应的资料库文件，reused 表示资料库中已有相同内容的文件 */
+export interface ResolvedBoardAsset {
+  content_hash: string;
+  file_id: string;
+  reused: boolean;
+}
+
+/** 打开的画板，failed_assets 为无法导入的素材的内容哈希 */
+export interface OpenedBoard {
+  path: string;
+  name: string;
+  board: unknown;
+  /** 画板的框架和分组，ID 为导出时的 ID，以新画板 ID 调用 replaceBoardFrames 保存 */
+  frames: BoardFrame[];
+  assets: ResolvedBoardAsset[];
+  failed_assets: string[];
+}
+
+/** 收集画板素材的方式：copy 创建副本，move 移动原文件（可撤销） */
+export type CollectMode = 'copy' | 'move';
+
+export interface CollectBoardAssetsRequest {
+  board_id: string;
+  target_directory_id: string;
+  /** 画板当前的文件，未指定时使用放置到该画板上记录过的文件 */
+  file_ids?: string[] | null;
+  mode?: CollectMode;
+}
+
+export interface CollectBoardAssetsReport {
+  /** 收集后目标目录中的文件 ID，复制时为副本的 ID */
+  collected: string[];
+  already_collected: string[];
+  failed: string[];
+  /** 移动时的撤销条目 ID，可传给 undoBatchOperations */
+  undo_id?: string | null;
+}
+
+/** 画板自动排列方式 */
+export type LayoutStrategy = 'grid' | 'color' | 'aspect_ratio' | 'tag_clusters';
+
+export interface LayoutItem {
+  /** 前端的画板元素 ID，同一文件可放置多次 */
+  item_id: string;
+  file_id: string;
+}
+
+export interface AutoLayoutRequest {
+  board_id: string;
+  strategy: LayoutStrategy;
+  /** 画板当前的元素，未指定时使用放置到该画板上记录过的文件，元素 ID 即文件 ID */
+  items?: LayoutItem[] | null;
+  /** 单元格边长，默认 256 */
+  cell_size?: number | null;
+  /** 素材间距，默认 16 */
+  spacing?: number | null;
+}
+
+/** 元素的新位置和尺寸，坐标为左上角，相对于排列区域的左上角 */
+export interface ItemTransform {
+  item_id: string;
+  file_id: string;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  /** 按标签分组时所在的标签 */
+  group?: string | null;
+}
+
+export interface AutoLayoutResult {
+  board_id: string;
+  strategy: LayoutStrategy;
+  transforms: ItemTransform[];
+  width: number;
+  height: number;
+  /** 没有图像元数据、按正方形排列的文件 ID */
+  missing_metadata: string[];
+}
+
+/** 框架类型：frame 显示名称和边框，group 只把元素组合在一起 */
+export type FrameKind = 'frame' | 'group';
+
+/** 画板上的框架，包含其中的元素并一起移动 */
+export interface BoardFrame {
+  id: string;
+  board_id: string;
+  /** 所在的上级框架，顶层框架为空 */
+  parent_id?: string | null;
+  kind: FrameKind;
+  name: string;
+  /** 左上角坐标 */
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  /** 直接包含的画板元素 ID，一个元素只属于一个框架 */
+  item_ids: string[];
+  created_at: string;
+  updated_at: string;
+}
+
+/** 创建或修改框架的请求，修改时整体替换 */
+export interface BoardFrameRequest {
+  board_id: string;
+  parent_id?: string | null;
+  /** 默认 frame */
+  kind?: FrameKind;
+  name: string;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  item_ids?: string[];
+}
+
+/** 移动框架的结果 */
+export interface BoardFrameMove {
+  /** 移动后的框架及其所有子框架 */
+  frames: BoardFrame[];
+  /** 需要按同样位移移动的画板元素 ID，包括子框架中的元素 */
+  item_ids: string[];
+  dx: number;
+  dy: number;
+}
+
+/** 播放列表，关联画板时表示画板的讲解顺序 */
+export interface Playlist {
+  id: string;
+  name: string;
+  board_id?: string | null;
+  /** 仍存在的文件数 */
+  item_count: number;
+  created_at: string;
+  updated_at: string;
+}
+
+/** 创建或修改播放列表的请求，修改时整体替换 */
+export interface PlaylistRequest {
+  name: string;
+  board_id?: string | null;
+  /** 按放映顺序排列的文件 ID */
+  file_ids: string[];
+}
+
+/** 放映时的单个条目 */
+export interface PlaylistItem {
+  index: number;
+  total: number;
+  file: FileListItem;
+  /** 服务层已通过 convertFileSrc 转换为可直接加载的 URL */
+  asset_path: string;
+}
+
+/** 相似图像，distance 为感知哈希的汉明距离 */
+export interface SimilarImage {
+  file: FileListItem;
+  distance: number;
+}
+
+/** 图像比较结果，第二张图像尺寸不同时缩放到第一张图像的尺寸后再比较 */
+export interface ImageComparison {
+  width: number;
+  height: number;
+  resized: boolean;
+  /** 差异超过阈值的像素数 */
+  different_pixels: number;
+  total_pixels: number;
+  /** 不同像素占比（0.0 ~ 1.0） */
+  diff_ratio: number;
+  /** 基于亮度的结构相似度（1.0 表示完全相同） */
+  ssim: number;
+  /** PNG 编码的差异热力图，差异越大颜色越亮（黑 -> 红 -> 黄） */
+  heatmap: number[];
+}
+
+/** 经纬度范围，west 大于 east 时表示跨越 180° 经线 */
+export interface GeoBoundingBox {
+  south: number;
+  west: number;
+  north: number;
+  east: number;
+}
+
 /**
  * 存储统计信息
  */
@@ -137,7 +1338,7 @@ export interface StorageStats {
   total_directories: number;
   total_size: number;
   largest_file_size: number;
-  most_recent_upload?: string;
+  most_recent_upload?: string | null;
 }
 
 // ============= 前端扩展类型 =============
@@ -426,8 +1627,87 @@ export interface UseFileManagerReturn {
   removeUploadItem: (uploadId: string) => void;
   
   // 搜索
-  searchFiles: (query: string, directoryId?: string) => Promise<FileListItem[]>;
+  /** recursive 为 true 时同时搜索 directoryId 的所有子目录 */
+  searchFiles: (query: string, directoryId?: string, recursive?: boolean) => Promise<FileListItem[]>;
   
   // 统计
   getStorageStats: () => Promise<StorageStats>;
-}
\ No newline at end of file
+}
+// ============= 事件 =============
+
+/** 事件名到负载类型的映射，与后端事件目录一致 */
+export interface AppEventMap {
+  'backend://ready': StartupReport;
+  'backend://locked': null;
+  'backend://failed': string;
+  'backend://startup-task': StartupTask;
+  'upload-completed': { files: UploadFileResponse[] };
+  'export-progress': ExportProgress;
+  'directory-delete-progress': DirectoryDeleteProgress;
+  'storage-migration-progress': LayoutMigrationProgress;
+  'watched-import': WatchedImportReport;
+  'send-to-import': SendToReport;
+  'board-opened': OpenedBoard;
+  'quick-capture': QuickCaptureOutcome;
+  'external-edit-changed': ExternalEditSession;
+  'integrity-alert': IntegrityReport;
+  'deferred-delete': DeferredDeleteReport;
+  'library-taken-over': ReadOnlyReason;
+  'library-offline': LibraryOffline;
+  'library-online': null;
+  'library-summary': LibrarySummary;
+}
+
+export type AppEventName = keyof AppEventMap;
+
+/** 事件目录中的一项 */
+export interface EventDescriptor {
+  name: AppEventName;
+  /** 事件来源：startup、uploads、jobs、watchers、shell、monitoring */
+  source: string;
+  description: string;
+}
+
+/** 事件日志中的记录，seq 按发送顺序递增 */
+export interface EventRecord<K extends AppEventName = AppEventName> {
+  seq: number;
+  name: K;
+  payload: AppEventMap[K];
+  emitted_at: string;
+}
+
+export type ActivityKind = 'operation' | 'job' | 'sync' | 'edit';
+
+/** 最近动态的筛选条件 */
+export interface ActivityFilter {
+  /** 只返回这些类别，为空时返回全部 */
+  kinds?: ActivityKind[];
+  /** 只返回此时间及之后的动态 */
+  since?: string | null;
+}
+
+/** 动态内容，任务类动态的负载与对应事件相同 */
+export type ActivityDetail =
+  /** 可撤销的操作，undo_id 为对应的撤销条目 */
+  | { type: 'operation'; undo_id: string; operation_type: string }
+  | ({ type: 'directory_delete' } & DirectoryDeleteProgress)
+  | ({ type: 'export' } & ExportProgress)
+  | ({ type: 'storage_migration' } & LayoutMigrationProgress)
+  | ({ type: 'deferred_delete' } & DeferredDeleteReport)
+  | {
+      type: 'watched_import';
+      folder_id: string;
+      folder_path: string;
+      source_path: string;
+      file_id: string;
+      file_name: string;
+    }
+  | ({ type: 'external_edit' } & ExternalEditSession);
+
+/** 最近动态中的一项，按 occurred_at 从新到旧排列 */
+export interface ActivityEntry {
+  /** 带来源前缀的 ID，在动态流中唯一 */
+  id: string;
+  occurred_at: string;
+  detail: ActivityDetail;
+}
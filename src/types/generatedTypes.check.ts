@@ -0,0 +1,390 @@
+/**
+ * 手写类型与后端生成类型的一致性检查
+ *
+ * `yarn build` 先执行 `yarn generate:types`，由后端 Rust 类型生成 TypeScript 定义到 `./generated`，
+ * 再由 `tsc` 检查本文件：生成的类型必须能赋给 `./fileManager` 中对应的手写类型，且两者的字段完全相同，
+ * 不一致时构建失败。新增命令参数或返回值类型时在下面添加对应的一项
+ */
+
+import type { ActivityDetail } from './generated/ActivityDetail';
+import type { ActivityEntry } from './generated/ActivityEntry';
+import type { ActivityFilter } from './generated/ActivityFilter';
+import type { ActivityKind } from './generated/ActivityKind';
+import type { ActivityPoint } from './generated/ActivityPoint';
+import type { AddWatchedFolderRequest } from './generated/AddWatchedFolderRequest';
+import type { ArchiveReport } from './generated/ArchiveReport';
+import type { ArchiveSettings } from './generated/ArchiveSettings';
+import type { AutoLayoutRequest } from './generated/AutoLayoutRequest';
+import type { AutoLayoutResult } from './generated/AutoLayoutResult';
+import type { BackendStatus } from './generated/BackendStatus';
+import type { BackupReport } from './generated/BackupReport';
+import type { BackupScope } from './generated/BackupScope';
+import type { BatchOperation } from './generated/BatchOperation';
+import type { BatchReport } from './generated/BatchReport';
+import type { BoardAsset } from './generated/BoardAsset';
+import type { BoardExportReport } from './generated/BoardExportReport';
+import type { BoardFrame } from './generated/BoardFrame';
+import type { BoardFrameMove } from './generated/BoardFrameMove';
+import type { BoardFrameRequest } from './generated/BoardFrameRequest';
+import type { BulkTagRequest } from './generated/BulkTagRequest';
+import type { BulkTagResponse } from './generated/BulkTagResponse';
+import type { CacheStats } from './generated/CacheStats';
+import type { CaptureMode } from './generated/CaptureMode';
+import type { CatalogRollbackReport } from './generated/CatalogRollbackReport';
+import type { CatalogSnapshot } from './generated/CatalogSnapshot';
+import type { CatalogSnapshotSettings } from './generated/CatalogSnapshotSettings';
+import type { ChecksumManifest } from './generated/ChecksumManifest';
+import type { CollectBoardAssetsReport } from './generated/CollectBoardAssetsReport';
+import type { CollectBoardAssetsRequest } from './generated/CollectBoardAssetsRequest';
+import type { CollectMode } from './generated/CollectMode';
+import type { CommandError } from './generated/CommandError';
+import type { CommandResponse } from './generated/CommandResponse';
+import type { CreateDirectoryRequest } from './generated/CreateDirectoryRequest';
+import type { CreateDirectoryResponse } from './generated/CreateDirectoryResponse';
+import type { DeferredDelete } from './generated/DeferredDelete';
+import type { DeferredDeleteReport } from './generated/DeferredDeleteReport';
+import type { DeleteDirectoryCommand } from './generated/DeleteDirectoryCommand';
+import type { DeleteFileCommand } from './generated/DeleteFileCommand';
+import type { DirectoryDefaults } from './generated/DirectoryDefaults';
+import type { DirectoryDeleteJob } from './generated/DirectoryDeleteJob';
+import type { DirectoryDeleteProgress } from './generated/DirectoryDeleteProgress';
+import type { DirectoryDeleteStatus } from './generated/DirectoryDeleteStatus';
+import type { DirectoryImportReport } from './generated/DirectoryImportReport';
+import type { DirectoryItemCounts } from './generated/DirectoryItemCounts';
+import type { DirectoryTemplate } from './generated/DirectoryTemplate';
+import type { DirectoryTreeNode } from './generated/DirectoryTreeNode';
+import type { DuplicateAction } from './generated/DuplicateAction';
+import type { DuplicateGroup } from './generated/DuplicateGroup';
+import type { DuplicatePlan } from './generated/DuplicatePlan';
+import type { EventDescriptor } from './generated/EventDescriptor';
+import type { EventRecord } from './generated/EventRecord';
+import type { ExistingImport } from './generated/ExistingImport';
+import type { ExportBoardRequest } from './generated/ExportBoardRequest';
+import type { ExportCollisionPolicy } from './generated/ExportCollisionPolicy';
+import type { ExportLayout } from './generated/ExportLayout';
+import type { ExportPreset } from './generated/ExportPreset';
+import type { ExportProgress } from './generated/ExportProgress';
+import type { ExportReport } from './generated/ExportReport';
+import type { ExportRequest } from './generated/ExportRequest';
+import type { ExternalApp } from './generated/ExternalApp';
+import type { ExternalEditSession } from './generated/ExternalEditSession';
+import type { FileCategory } from './generated/FileCategory';
+import type { FileListItem } from './generated/FileListItem';
+import type { FileStatus } from './generated/FileStatus';
+import type { FileTypePolicy } from './generated/FileTypePolicy';
+import type { FileTypePolicyMode } from './generated/FileTypePolicyMode';
+import type { FrameKind } from './generated/FrameKind';
+import type { GeoBoundingBox } from './generated/GeoBoundingBox';
+import type { GetDirectoryFilesCommand } from './generated/GetDirectoryFilesCommand';
+import type { GetFileInfoCommand } from './generated/GetFileInfoCommand';
+import type { IdStrategy } from './generated/IdStrategy';
+import type { ImageComparison } from './generated/ImageComparison';
+import type { ImageMetadataFields } from './generated/ImageMetadataFields';
+import type { ImageMetadataResult } from './generated/ImageMetadataResult';
+import type { ImportDirectoryRequest } from './generated/ImportDirectoryRequest';
+import type { ImportMode } from './generated/ImportMode';
+import type { IntegrityReport } from './generated/IntegrityReport';
+import type { IntegritySettings } from './generated/IntegritySettings';
+import type { ItemTransform } from './generated/ItemTransform';
+import type { JobCheckpoint } from './generated/JobCheckpoint';
+import type { LayoutItem } from './generated/LayoutItem';
+import type { LayoutMigrationProgress } from './generated/LayoutMigrationProgress';
+import type { LayoutStrategy } from './generated/LayoutStrategy';
+import type { LibraryOffline } from './generated/LibraryOffline';
+import type { LibrarySummary } from './generated/LibrarySummary';
+import type { ListingContinuation } from './generated/ListingContinuation';
+import type { Locale } from './generated/Locale';
+import type { LowMemorySettings } from './generated/LowMemorySettings';
+import type { ManifestEntry } from './generated/ManifestEntry';
+import type { ManifestFormat } from './generated/ManifestFormat';
+import type { MetadataExportReport } from './generated/MetadataExportReport';
+import type { MetadataImportReport } from './generated/MetadataImportReport';
+import type { MissingFileScanReport } from './generated/MissingFileScanReport';
+import type { MoveDirectoryCommand } from './generated/MoveDirectoryCommand';
+import type { MoveDirectoryResponse } from './generated/MoveDirectoryResponse';
+import type { NotificationSettings } from './generated/NotificationSettings';
+import type { OpenWithSettings } from './generated/OpenWithSettings';
+import type { OpenedBoard } from './generated/OpenedBoard';
+import type { PackReport } from './generated/PackReport';
+import type { PackSettings } from './generated/PackSettings';
+import type { PackStats } from './generated/PackStats';
+import type { Page } from './generated/Page';
+import type { PlannedChange } from './generated/PlannedChange';
+import type { Playlist } from './generated/Playlist';
+import type { PlaylistItem } from './generated/PlaylistItem';
+import type { PlaylistRequest } from './generated/PlaylistRequest';
+import type { PrefetchContext } from './generated/PrefetchContext';
+import type { PreviewColorSpace } from './generated/PreviewColorSpace';
+import type { PreviewFormat } from './generated/PreviewFormat';
+import type { PreviewSettings } from './generated/PreviewSettings';
+import type { ProcessingReport } from './generated/ProcessingReport';
+import type { ProcessingStatus } from './generated/ProcessingStatus';
+import type { QuarantineReason } from './generated/QuarantineReason';
+import type { QuarantineSettings } from './generated/QuarantineSettings';
+import type { QuarantinedFile } from './generated/QuarantinedFile';
+import type { QueuedJob } from './generated/QueuedJob';
+import type { QuickCaptureOutcome } from './generated/QuickCaptureOutcome';
+import type { QuickCaptureSettings } from './generated/QuickCaptureSettings';
+import type { QuickFindKind } from './generated/QuickFindKind';
+import type { QuickFindResult } from './generated/QuickFindResult';
+import type { ReadFileContentCommand } from './generated/ReadFileContentCommand';
+import type { ReadOnlyReason } from './generated/ReadOnlyReason';
+import type { RecoveredJob } from './generated/RecoveredJob';
+import type { RecoveryAction } from './generated/RecoveryAction';
+import type { ReindexScope } from './generated/ReindexScope';
+import type { RelinkFileCommand } from './generated/RelinkFileCommand';
+import type { ResolveDuplicatesReport } from './generated/ResolveDuplicatesReport';
+import type { ResolvedBoardAsset } from './generated/ResolvedBoardAsset';
+import type { ResponseLimitSettings } from './generated/ResponseLimitSettings';
+import type { RestoreMode } from './generated/RestoreMode';
+import type { RestoreReport } from './generated/RestoreReport';
+import type { SearchHistoryEntry } from './generated/SearchHistoryEntry';
+import type { SearchSuggestion } from './generated/SearchSuggestion';
+import type { SendToReport } from './generated/SendToReport';
+import type { ShellIntegrationStatus } from './generated/ShellIntegrationStatus';
+import type { SidecarFormat } from './generated/SidecarFormat';
+import type { SimilarImage } from './generated/SimilarImage';
+import type { SizeLimits } from './generated/SizeLimits';
+import type { StartupPhase } from './generated/StartupPhase';
+import type { StartupReport } from './generated/StartupReport';
+import type { StartupTask } from './generated/StartupTask';
+import type { StartupTaskKind } from './generated/StartupTaskKind';
+import type { StartupTaskState } from './generated/StartupTaskState';
+import type { StatsSnapshot } from './generated/StatsSnapshot';
+import type { StorageBreakdown } from './generated/StorageBreakdown';
+import type { StorageLayout } from './generated/StorageLayout';
+import type { StorageLayoutSettings } from './generated/StorageLayoutSettings';
+import type { StorageStats } from './generated/StorageStats';
+import type { SuggestedFile } from './generated/SuggestedFile';
+import type { SuggestionContext } from './generated/SuggestionContext';
+import type { SuggestionReason } from './generated/SuggestionReason';
+import type { SuggestionSource } from './generated/SuggestionSource';
+import type { SyncStatus } from './generated/SyncStatus';
+import type { TelemetryBatch } from './generated/TelemetryBatch';
+import type { TelemetryPreview } from './generated/TelemetryPreview';
+import type { TelemetrySettings } from './generated/TelemetrySettings';
+import type { TemplateApplication } from './generated/TemplateApplication';
+import type { TemplateFolder } from './generated/TemplateFolder';
+import type { ThumbnailCacheSettings } from './generated/ThumbnailCacheSettings';
+import type { ThumbnailResponse } from './generated/ThumbnailResponse';
+import type { ThumbnailSize } from './generated/ThumbnailSize';
+import type { TimelineBucket } from './generated/TimelineBucket';
+import type { TimelineRange } from './generated/TimelineRange';
+import type { TrashPurgeReport } from './generated/TrashPurgeReport';
+import type { TrashSettings } from './generated/TrashSettings';
+import type { TrashedFile } from './generated/TrashedFile';
+import type { TriageAction } from './generated/TriageAction';
+import type { UploadItemResult } from './generated/UploadItemResult';
+import type { UploadResponse } from './generated/UploadResponse';
+import type { UsageGroup } from './generated/UsageGroup';
+import type { UsageKind } from './generated/UsageKind';
+import type { WatchedFolder } from './generated/WatchedFolder';
+import type { WatchedImportReport } from './generated/WatchedImportReport';
+import type { Watermark } from './generated/Watermark';
+import type { WatermarkContent } from './generated/WatermarkContent';
+import type { WatermarkPosition } from './generated/WatermarkPosition';
+import type { WorkingSet } from './generated/WorkingSet';
+import type * as Hand from './fileManager';
+
+/** 去掉索引签名后的字段名，手写的命令参数类型带有 `[key: string]: unknown` 以便传给 invoke */
+type KnownKeys<T> = keyof { [K in keyof T as string extends K ? never : number extends K ? never : K]: T[K] };
+
+/** 两个类型的字段名完全相同 */
+type SameKeys<A, B> = [Exclude<KnownKeys<A>, KnownKeys<B>> | Exclude<KnownKeys<B>, KnownKeys<A>>] extends [never]
+  ? true
+  : false;
+
+/** 生成的类型可以赋给手写的类型（手写的可以更宽松，如可选字段），且字段名完全相同 */
+type Matches<HandWritten, Generated> = [Generated] extends [HandWritten] ? SameKeys<HandWritten, Generated> : false;
+
+/** 字符串枚举的取值完全相同 */
+type SameValues<HandWritten, Generated> = [Generated] extends [HandWritten]
+  ? [HandWritten] extends [Generated]
+    ? true
+    : false
+  : false;
+
+type Assert<T extends true> = T;
+
+export type GeneratedTypeChecks = [
+  Assert<Matches<Hand.ActivityDetail, ActivityDetail>>,
+  Assert<Matches<Hand.ActivityEntry, ActivityEntry>>,
+  Assert<Matches<Hand.ActivityFilter, ActivityFilter>>,
+  Assert<SameValues<Hand.ActivityKind, ActivityKind>>,
+  Assert<Matches<Hand.ActivityPoint, ActivityPoint>>,
+  Assert<Matches<Hand.AddWatchedFolderRequest, AddWatchedFolderRequest>>,
+  Assert<Matches<Hand.ArchiveReport, ArchiveReport>>,
+  Assert<Matches<Hand.ArchiveSettings, ArchiveSettings>>,
+  Assert<Matches<Hand.AutoLayoutRequest, AutoLayoutRequest>>,
+  Assert<Matches<Hand.AutoLayoutResult, AutoLayoutResult>>,
+  Assert<Matches<Hand.BackendStatus, BackendStatus>>,
+  Assert<Matches<Hand.BackupReport, BackupReport>>,
+  Assert<SameValues<Hand.BackupScope, BackupScope>>,
+  Assert<Matches<Hand.BatchOperation, BatchOperation>>,
+  Assert<Matches<Hand.BatchReport, BatchReport>>,
+  Assert<Matches<Hand.BoardAsset, BoardAsset>>,
+  Assert<Matches<Hand.BoardExportReport, BoardExportReport>>,
+  Assert<Matches<Hand.BoardFrame, BoardFrame>>,
+  Assert<Matches<Hand.BoardFrameMove, BoardFrameMove>>,
+  Assert<Matches<Hand.BoardFrameRequest, BoardFrameRequest>>,
+  Assert<Matches<Hand.BulkTagRequest, BulkTagRequest>>,
+  Assert<Matches<Hand.BulkTagResponse, BulkTagResponse>>,
+  Assert<Matches<Hand.CacheStats, CacheStats>>,
+  Assert<SameValues<Hand.CaptureMode, CaptureMode>>,
+  Assert<Matches<Hand.CatalogRollbackReport, CatalogRollbackReport>>,
+  Assert<Matches<Hand.CatalogSnapshot, CatalogSnapshot>>,
+  Assert<Matches<Hand.CatalogSnapshotSettings, CatalogSnapshotSettings>>,
+  Assert<Matches<Hand.ChecksumManifest, ChecksumManifest>>,
+  Assert<Matches<Hand.CollectBoardAssetsReport, CollectBoardAssetsReport>>,
+  Assert<Matches<Hand.CollectBoardAssetsRequest, CollectBoardAssetsRequest>>,
+  Assert<SameValues<Hand.CollectMode, CollectMode>>,
+  Assert<Matches<Hand.CommandError, CommandError>>,
+  Assert<Matches<Hand.CommandResponse<unknown>, CommandResponse<unknown>>>,
+  Assert<Matches<Hand.CreateDirectoryRequest, CreateDirectoryRequest>>,
+  Assert<Matches<Hand.CreateDirectoryResponse, CreateDirectoryResponse>>,
+  Assert<Matches<Hand.DeferredDelete, DeferredDelete>>,
+  Assert<Matches<Hand.DeferredDeleteReport, DeferredDeleteReport>>,
+  Assert<Matches<Hand.DeleteDirectoryCommand, DeleteDirectoryCommand>>,
+  Assert<Matches<Hand.DeleteFileCommand, DeleteFileCommand>>,
+  Assert<Matches<Hand.DirectoryDefaults, DirectoryDefaults>>,
+  Assert<Matches<Hand.DirectoryDeleteJob, DirectoryDeleteJob>>,
+  Assert<Matches<Hand.DirectoryDeleteProgress, DirectoryDeleteProgress>>,
+  Assert<SameValues<Hand.DirectoryDeleteStatus, DirectoryDeleteStatus>>,
+  Assert<Matches<Hand.DirectoryImportReport, DirectoryImportReport>>,
+  Assert<Matches<Hand.DirectoryItemCounts, DirectoryItemCounts>>,
+  Assert<Matches<Hand.DirectoryTemplate, DirectoryTemplate>>,
+  Assert<Matches<Hand.DirectoryTreeNode, DirectoryTreeNode>>,
+  Assert<SameValues<Hand.DuplicateAction, DuplicateAction>>,
+  Assert<Matches<Hand.DuplicateGroup, DuplicateGroup>>,
+  Assert<Matches<Hand.DuplicatePlan, DuplicatePlan>>,
+  Assert<Matches<Omit<Hand.EventDescriptor, 'name'>, Omit<EventDescriptor, 'name'>>>,
+  // 事件名和载荷由前端按 AppEventMap 收窄
+  Assert<Matches<Omit<Hand.EventRecord, 'name' | 'payload'>, Omit<EventRecord, 'name' | 'payload'>>>,
+  Assert<Matches<Hand.ExistingImport, ExistingImport>>,
+  Assert<Matches<Hand.ExportBoardRequest, ExportBoardRequest>>,
+  Assert<SameValues<Hand.ExportCollisionPolicy, ExportCollisionPolicy>>,
+  Assert<SameValues<Hand.ExportLayout, ExportLayout>>,
+  Assert<Matches<Hand.ExportPreset, ExportPreset>>,
+  Assert<Matches<Hand.ExportProgress, ExportProgress>>,
+  Assert<Matches<Hand.ExportReport, ExportReport>>,
+  Assert<Matches<Hand.ExportRequest, ExportRequest>>,
+  Assert<Matches<Hand.ExternalApp, ExternalApp>>,
+  Assert<Matches<Hand.ExternalEditSession, ExternalEditSession>>,
+  Assert<SameValues<Hand.FileCategory, FileCategory>>,
+  // path、size 和 modified_at 由前端补充，用于排序和过滤
+  Assert<Matches<Omit<Hand.FileListItem, 'path' | 'size' | 'modified_at'>, FileListItem>>,
+  Assert<SameValues<Hand.FileStatus, FileStatus>>,
+  Assert<Matches<Hand.FileTypePolicy, FileTypePolicy>>,
+  Assert<SameValues<Hand.FileTypePolicyMode, FileTypePolicyMode>>,
+  Assert<SameValues<Hand.FrameKind, FrameKind>>,
+  Assert<Matches<Hand.GeoBoundingBox, GeoBoundingBox>>,
+  Assert<Matches<Hand.GetDirectoryFilesCommand, GetDirectoryFilesCommand>>,
+  Assert<Matches<Hand.GetFileInfoCommand, GetFileInfoCommand>>,
+  Assert<SameValues<Hand.IdStrategy, IdStrategy>>,
+  Assert<Matches<Hand.ImageComparison, ImageComparison>>,
+  Assert<Matches<Hand.ImageMetadataFields, ImageMetadataFields>>,
+  Assert<Matches<Hand.ImageMetadataResult, ImageMetadataResult>>,
+  Assert<Matches<Hand.ImportDirectoryRequest, ImportDirectoryRequest>>,
+  Assert<Matches<Hand.IntegrityReport, IntegrityReport>>,
+  Assert<Matches<Hand.IntegritySettings, IntegritySettings>>,
+  Assert<Matches<Hand.ItemTransform, ItemTransform>>,
+  Assert<Matches<Hand.JobCheckpoint, JobCheckpoint>>,
+  Assert<Matches<Hand.LayoutItem, LayoutItem>>,
+  Assert<Matches<Hand.LayoutMigrationProgress, LayoutMigrationProgress>>,
+  Assert<SameValues<Hand.LayoutStrategy, LayoutStrategy>>,
+  Assert<Matches<Hand.LibraryOffline, LibraryOffline>>,
+  Assert<Matches<Hand.LibrarySummary, LibrarySummary>>,
+  Assert<Matches<Hand.ListingContinuation, ListingContinuation>>,
+  Assert<SameValues<Hand.Locale, Locale>>,
+  Assert<Matches<Hand.LowMemorySettings, LowMemorySettings>>,
+  Assert<Matches<Hand.ManifestEntry, ManifestEntry>>,
+  Assert<SameValues<Hand.ManifestFormat, ManifestFormat>>,
+  Assert<Matches<Hand.MetadataExportReport, MetadataExportReport>>,
+  Assert<Matches<Hand.MetadataImportReport, MetadataImportReport>>,
+  Assert<Matches<Hand.MissingFileScanReport, MissingFileScanReport>>,
+  Assert<Matches<Hand.MoveDirectoryCommand, MoveDirectoryCommand>>,
+  Assert<Matches<Hand.MoveDirectoryResponse, MoveDirectoryResponse>>,
+  Assert<Matches<Hand.NotificationSettings, NotificationSettings>>,
+  Assert<Matches<Hand.OpenWithSettings, OpenWithSettings>>,
+  Assert<Matches<Hand.OpenedBoard, OpenedBoard>>,
+  Assert<Matches<Hand.PackReport, PackReport>>,
+  Assert<Matches<Hand.PackSettings, PackSettings>>,
+  Assert<Matches<Hand.PackStats, PackStats>>,
+  Assert<Matches<Hand.Page<unknown>, Page<unknown>>>,
+  Assert<Matches<Hand.PlannedChange, PlannedChange>>,
+  Assert<Matches<Hand.Playlist, Playlist>>,
+  Assert<Matches<Hand.PlaylistItem, PlaylistItem>>,
+  Assert<Matches<Hand.PlaylistRequest, PlaylistRequest>>,
+  Assert<Matches<Hand.PrefetchContext, PrefetchContext>>,
+  Assert<SameValues<Hand.PreviewColorSpace, PreviewColorSpace>>,
+  Assert<SameValues<Hand.PreviewFormat, PreviewFormat>>,
+  Assert<Matches<Hand.PreviewSettings, PreviewSettings>>,
+  Assert<Matches<Hand.ProcessingReport, ProcessingReport>>,
+  Assert<SameValues<Hand.ProcessingStatus, ProcessingStatus>>,
+  Assert<Matches<Hand.QuarantineReason, QuarantineReason>>,
+  Assert<Matches<Hand.QuarantineSettings, QuarantineSettings>>,
+  Assert<Matches<Hand.QuarantinedFile, QuarantinedFile>>,
+  Assert<Matches<Hand.QueuedJob, QueuedJob>>,
+  Assert<Matches<Hand.QuickCaptureOutcome, QuickCaptureOutcome>>,
+  Assert<Matches<Hand.QuickCaptureSettings, QuickCaptureSettings>>,
+  Assert<SameValues<Hand.QuickFindKind, QuickFindKind>>,
+  Assert<Matches<Hand.QuickFindResult, QuickFindResult>>,
+  Assert<Matches<Hand.ReadFileContentCommand, ReadFileContentCommand>>,
+  Assert<Matches<Hand.ReadOnlyReason, ReadOnlyReason>>,
+  Assert<Matches<Hand.RecoveredJob, RecoveredJob>>,
+  Assert<SameValues<Hand.RecoveryAction, RecoveryAction>>,
+  Assert<Matches<Hand.ReindexScope, ReindexScope>>,
+  Assert<Matches<Hand.RelinkFileCommand, RelinkFileCommand>>,
+  Assert<Matches<Hand.ResolveDuplicatesReport, ResolveDuplicatesReport>>,
+  Assert<Matches<Hand.ResolvedBoardAsset, ResolvedBoardAsset>>,
+  Assert<Matches<Hand.ResponseLimitSettings, ResponseLimitSettings>>,
+  Assert<SameValues<Hand.RestoreMode, RestoreMode>>,
+  Assert<Matches<Hand.RestoreReport, RestoreReport>>,
+  Assert<Matches<Hand.SearchHistoryEntry, SearchHistoryEntry>>,
+  Assert<Matches<Hand.SearchSuggestion, SearchSuggestion>>,
+  Assert<Matches<Hand.SendToReport, SendToReport>>,
+  Assert<Matches<Hand.ShellIntegrationStatus, ShellIntegrationStatus>>,
+  Assert<SameValues<Hand.SidecarFormat, SidecarFormat>>,
+  Assert<Matches<Hand.SimilarImage, SimilarImage>>,
+  Assert<Matches<Hand.SizeLimits, SizeLimits>>,
+  Assert<Matches<Hand.StartupPhase, StartupPhase>>,
+  Assert<Matches<Hand.StartupReport, StartupReport>>,
+  Assert<Matches<Hand.StartupTask, StartupTask>>,
+  Assert<SameValues<Hand.StartupTaskKind, StartupTaskKind>>,
+  Assert<SameValues<Hand.StartupTaskState, StartupTaskState>>,
+  Assert<Matches<Hand.StatsSnapshot, StatsSnapshot>>,
+  Assert<Matches<Hand.StorageBreakdown, StorageBreakdown>>,
+  Assert<SameValues<Hand.StorageLayout, StorageLayout>>,
+  Assert<Matches<Hand.StorageLayoutSettings, StorageLayoutSettings>>,
+  Assert<Matches<Hand.StorageStats, StorageStats>>,
+  Assert<Matches<Hand.SuggestedFile, SuggestedFile>>,
+  Assert<Matches<Hand.SuggestionContext, SuggestionContext>>,
+  Assert<SameValues<Hand.SuggestionReason, SuggestionReason>>,
+  Assert<SameValues<Hand.SuggestionSource, SuggestionSource>>,
+  Assert<SameValues<Hand.SyncStatus, SyncStatus>>,
+  Assert<Matches<Hand.TelemetryBatch, TelemetryBatch>>,
+  Assert<Matches<Hand.TelemetryPreview, TelemetryPreview>>,
+  Assert<Matches<Hand.TelemetrySettings, TelemetrySettings>>,
+  Assert<Matches<Hand.TemplateApplication, TemplateApplication>>,
+  Assert<Matches<Hand.TemplateFolder, TemplateFolder>>,
+  Assert<Matches<Hand.ThumbnailCacheSettings, ThumbnailCacheSettings>>,
+  Assert<Matches<Hand.ThumbnailResponse, ThumbnailResponse>>,
+  Assert<SameValues<Hand.ThumbnailSize, ThumbnailSize>>,
+  Assert<SameValues<Hand.TimelineBucket, TimelineBucket>>,
+  Assert<Matches<Hand.TimelineRange, TimelineRange>>,
+  Assert<Matches<Hand.TrashPurgeReport, TrashPurgeReport>>,
+  Assert<Matches<Hand.TrashSettings, TrashSettings>>,
+  Assert<Matches<Hand.TrashedFile, TrashedFile>>,
+  Assert<Matches<Hand.TriageAction, TriageAction>>,
+  Assert<Matches<Hand.UploadFileResponse, UploadResponse>>,
+  Assert<Matches<Hand.UploadItemResult, UploadItemResult>>,
+  Assert<Matches<Hand.UsageGroup, UsageGroup>>,
+  Assert<SameValues<Hand.UsageKind, UsageKind>>,
+  Assert<Matches<Hand.WatchedFolder, WatchedFolder>>,
+  Assert<SameValues<Hand.WatchedImportMode, ImportMode>>,
+  Assert<Matches<Hand.WatchedImportReport, WatchedImportReport>>,
+  Assert<Matches<Hand.Watermark, Watermark>>,
+  Assert<Matches<Hand.WatermarkContent, WatermarkContent>>,
+  Assert<SameValues<Hand.WatermarkPosition, WatermarkPosition>>,
+  Assert<Matches<Hand.WorkingSet, WorkingSet>>,
+];
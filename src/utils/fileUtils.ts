@@ -36,7 +36,7 @@ export function getFileTypeInfo(filename: string): {
   const extension = filename.split('.').pop()?.toLowerCase() || '';
   
   // 图片文件
-  if (['jpg', 'jpeg', 'png', 'gif', 'bmp', 'webp', 'svg', 'ico'].includes(extension)) {
+  if (['jpg', 'jpeg', 'png', 'gif', 'bmp', 'webp', 'heic', 'heif', 'avif', 'svg', 'ico'].includes(extension)) {
     return {
       type: extension.toUpperCase(),
       category: 'image',